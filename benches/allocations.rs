@@ -0,0 +1,100 @@
+// Transforming, subdividing and reading back the points of a 200 contour outline, the allocating way and the in
+// place way. Before the timings it prints how many allocations each way makes, counted by a global allocator that
+// wraps the system one.
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use qstroke::qmath::{Affine, Bezier, Piecewise, Vector};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8
+    {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        return System.alloc(layout);
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout)
+    {
+        System.dealloc(ptr, layout);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8
+    {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        return System.realloc(ptr, layout, new_size);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+// How many allocations f makes.
+fn allocations<F: FnOnce()>(f: F) -> usize
+{
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    return ALLOCATIONS.load(Ordering::Relaxed) - before;
+}
+
+// 200 circles of 4 curves each on a grid, about a font's worth of contours for a handful of glyphs.
+fn outline() -> Piecewise<Piecewise<Bezier>>
+{
+    let mut contours = Vec::new();
+    for i in 0..200 {
+        let center = Vector { x: (i % 20) as f64 * 100., y: (i / 20) as f64 * 100. };
+        contours.push(Piecewise::circle(center, 40.));
+    }
+
+    return Piecewise::new(contours);
+}
+
+fn report_allocations(outline: &Piecewise<Piecewise<Bezier>>, slant: &Affine)
+{
+    let mut copy = outline.clone();
+    println!("transform: {} allocations, transform_mut: {}",
+        allocations(|| { outline.transform(slant); }),
+        allocations(|| copy.transform_mut(slant)));
+
+    let mut buffer = Vec::new();
+    println!("subdivide: {} allocations, subdivide_into with one buffer: {}",
+        allocations(|| { outline.subdivide(0.5); }),
+        allocations(|| for contour in outline.segs() { buffer.clear(); contour.subdivide_into(0.5, &mut buffer); }));
+
+    let mut points = Vec::new();
+    println!("to_control_points_vec: {} allocations, extend_control_points with one buffer: {}",
+        allocations(|| for contour in outline.segs() { for bez in contour.segs() { bez.to_control_points_vec(); } }),
+        allocations(|| for contour in outline.segs() { points.clear(); for bez in contour.segs() { bez.extend_control_points(&mut points); } }));
+}
+
+fn bench_allocations(c: &mut Criterion)
+{
+    let outline = outline();
+    let slant = Affine::skew(0.2, 0.);
+    report_allocations(&outline, &slant);
+
+    c.bench_function("transform", |b| b.iter(|| outline.transform(&slant)));
+    c.bench_function("transform_mut", |b| b.iter_batched_ref(|| outline.clone(), |copy| copy.transform_mut(&slant), BatchSize::SmallInput));
+
+    c.bench_function("subdivide", |b| b.iter(|| outline.subdivide(0.5)));
+    let mut buffer = Vec::new();
+    c.bench_function("subdivide_into", |b| b.iter(|| for contour in outline.segs() { buffer.clear(); contour.subdivide_into(0.5, &mut buffer); }));
+
+    c.bench_function("to_control_points_vec", |b| b.iter(|| {
+        let mut count = 0;
+        for contour in outline.segs() { for bez in contour.segs() { count = count + bez.to_control_points_vec().len(); } }
+        count
+    }));
+    let mut points = Vec::new();
+    c.bench_function("extend_control_points", |b| b.iter(|| {
+        let mut count = 0;
+        for contour in outline.segs() { points.clear(); for bez in contour.segs() { bez.extend_control_points(&mut points); } count = count + points.len(); }
+        count
+    }));
+}
+
+criterion_group!(benches, bench_allocations);
+criterion_main!(benches);
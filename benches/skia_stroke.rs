@@ -0,0 +1,26 @@
+// Our stroker against skia's on every fixture skeleton the two are compared on, see compare_with_skia_stroker in
+// src/testing.rs. Before the timings it prints how far each of our strokes came out from skia's.
+use criterion::{criterion_group, criterion_main, Criterion};
+use qstroke::stroke::constant_width_stroke;
+use qstroke::testing::*;
+use std::path::Path;
+
+fn benchmark(c: &mut Criterion)
+{
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+
+    for comparison in compare_with_skia_stroker(&fixtures, SKIA_STROKE_WIDTH).unwrap() {
+        println!("{}: {} from skia's stroke", comparison.skeleton, comparison.deviation);
+    }
+
+    let settings = skia_comparison_settings(SKIA_STROKE_WIDTH);
+    for (name, skeleton) in skia_comparison_skeletons(&fixtures).unwrap() {
+        let mut group = c.benchmark_group(name.as_str());
+        group.bench_function("ours", |b| b.iter(|| constant_width_stroke(&skeleton, &settings)));
+        group.bench_function("skia", |b| b.iter(|| skia_stroke(&skeleton, SKIA_STROKE_WIDTH).unwrap()));
+        group.finish();
+    }
+}
+
+criterion_group!(benches, benchmark);
+criterion_main!(benches);
@@ -0,0 +1,44 @@
+fn main()
+{
+    #[cfg(feature = "ffi")]
+    generate_header();
+}
+
+// What a C caller has to know before calling anything, cbindgen only carries over the doc comments on the functions.
+#[cfg(feature = "ffi")]
+const HEADER_PREAMBLE: &str = "/*
+ * The C API of MFEKstroke, see src/ffi.rs.
+ *
+ * Memory ownership: the library never allocates memory for the caller and never frees anything the caller passes
+ * in. Output is written into a buffer the caller owns. Pass the buffer's capacity in *out_len, on return *out_len
+ * holds the number of bytes the output needs including the trailing NUL. If the buffer is NULL or too small nothing
+ * is written and MFEKSTROKE_BUFFER_TOO_SMALL is returned, so call once to get the size, allocate, and call again.
+ * The string mfekstroke_version returns is static and must not be freed.
+ */";
+
+// Generates mfekstroke.h from the functions in src/ffi.rs into OUT_DIR. The copy in include/ is only rewritten when
+// asked for, so building never touches the source tree:
+//
+//     MFEKSTROKE_WRITE_HEADER=1 cargo build --features ffi
+#[cfg(feature = "ffi")]
+fn generate_header()
+{
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+
+    let bindings = cbindgen::Builder::new()
+        .with_crate(&crate_dir)
+        .with_language(cbindgen::Language::C)
+        .with_header(HEADER_PREAMBLE)
+        .with_include_guard("MFEKSTROKE_H")
+        .generate()
+        .expect("Unable to generate C bindings!");
+
+    bindings.write_to_file(format!("{}/mfekstroke.h", out_dir));
+
+    println!("cargo:rerun-if-env-changed=MFEKSTROKE_WRITE_HEADER");
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    if std::env::var_os("MFEKSTROKE_WRITE_HEADER").is_some() {
+        bindings.write_to_file(format!("{}/include/mfekstroke.h", crate_dir));
+    }
+}
@@ -0,0 +1,53 @@
+/*
+ * The C API of MFEKstroke, see src/ffi.rs.
+ *
+ * Memory ownership: the library never allocates memory for the caller and never frees anything the caller passes
+ * in. Output is written into a buffer the caller owns. Pass the buffer's capacity in *out_len, on return *out_len
+ * holds the number of bytes the output needs including the trailing NUL. If the buffer is NULL or too small nothing
+ * is written and MFEKSTROKE_BUFFER_TOO_SMALL is returned, so call once to get the size, allocate, and call again.
+ * The string mfekstroke_version returns is static and must not be freed.
+ */
+
+#ifndef MFEKSTROKE_H
+#define MFEKSTROKE_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define MFEKSTROKE_OK 0
+
+#define MFEKSTROKE_INVALID_ARGUMENT 1
+
+#define MFEKSTROKE_INVALID_SETTINGS 2
+
+#define MFEKSTROKE_STROKE_FAILED 3
+
+#define MFEKSTROKE_BUFFER_TOO_SMALL 4
+
+/**
+ * Strokes the glif in input_xml (input_len bytes of UTF-8, no NUL needed) using the NUL terminated settings_json.
+ * The resulting glif is written to out_buf as a NUL terminated string. Pass out_buf's capacity in *out_len, on return
+ * it holds the bytes the glif needs including the NUL, and if out_buf is NULL or smaller than that nothing is
+ * written and MFEKSTROKE_BUFFER_TOO_SMALL is returned. The caller owns out_buf, the library never keeps or frees it.
+ * Returns MFEKSTROKE_OK on success, on failure mfekstroke_last_error_message explains what happened.
+ */
+int32_t mfekstroke_stroke_glif_utf8(const char *input_xml,
+                                    uintptr_t input_len,
+                                    const char *settings_json,
+                                    char *out_buf,
+                                    uintptr_t *out_len);
+
+/**
+ * The library's version as a static NUL terminated string. Don't free it.
+ */
+const char *mfekstroke_version(void);
+
+/**
+ * Copies the last error that happened on this thread into buf using the same size negotiation as
+ * mfekstroke_stroke_glif_utf8. If there was no error an empty string is written.
+ */
+int32_t mfekstroke_last_error_message(char *buf, uintptr_t *len);
+
+#endif /* MFEKSTROKE_H */
@@ -0,0 +1,51 @@
+// Carries the skeleton's anchors over to the stroked glyph. Stroking changes the glyph's outline so an anchor that
+// sat on the skeleton can end up inside the stroke, projecting moves each one out to the nearest point of the
+// stroked outline instead.
+use crate::qmath::*;
+use glifparser::Anchor;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AnchorMode {
+    // anchors stay exactly where they were on the skeleton
+    Fixed,
+    // anchors move to the closest point on the stroked outline
+    Project,
+}
+
+// The anchors for the output along with every one that moved as (name, from, to).
+pub fn place_anchors(anchors: &[Anchor], outline: &Piecewise<Piecewise<Bezier>>, mode: AnchorMode) -> (Vec<Anchor>, Vec<(String, Vector, Vector)>)
+{
+    let mut placed = Vec::new();
+    let mut moved = Vec::new();
+
+    for anchor in anchors {
+        let from = Vector { x: anchor.x as f64, y: anchor.y as f64 };
+
+        let to = match mode {
+            AnchorMode::Fixed => from,
+            AnchorMode::Project => {
+                let mut best: Option<(Vector, f64)> = None;
+                for contour in outline.segs() {
+                    if let Some((idx, t, distance)) = contour.nearest(from) {
+                        if best.map_or(true, |b| distance < b.1) {
+                            best = Some((contour[idx].evaluate(t), distance));
+                        }
+                    }
+                }
+
+                best.map_or(from, |b| b.0)
+            }
+        };
+
+        let mut output = anchor.clone();
+        output.x = to.x as f32;
+        output.y = to.y as f32;
+        placed.push(output);
+
+        if !to.is_near(from, 1e-6) {
+            moved.push((anchor.class.clone(), from, to));
+        }
+    }
+
+    return (placed, moved);
+}
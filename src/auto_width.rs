@@ -0,0 +1,202 @@
+// Picks the pattern's vertical scale so the stroked stems measure a given width. The relationship between the
+// scale and what you measure isn't exact once the pattern is bent along curves, so we stroke, measure, and adjust
+// with the secant method until we're within tolerance.
+use crate::qmath::*;
+use crate::pattern_along_path::*;
+
+#[derive(Clone)]
+pub struct TargetMeasurement {
+    pub target: f64,
+    // Points on or near the skeleton where the stem gets measured, horizontally through the point. When empty we
+    // measure halfway up every straight vertical run in the skeleton.
+    pub probes: Vec<Vector>,
+    pub tolerance: f64,
+}
+
+const MAX_ITERATIONS: usize = 8;
+
+// how far from vertical a line can lean and still count as a vertical run, as dx/dy
+const VERTICAL_SLOPE: f64 = 0.05;
+
+// The middle of every near-vertical line in the skeleton.
+pub fn vertical_probes(skeleton: &Piecewise<Piecewise<Bezier>>) -> Vec<Vector>
+{
+    let mut probes = Vec::new();
+
+    for contour in skeleton.segs() {
+        for bez in contour.segs() {
+            let p = bez.to_control_points();
+            let is_line = p[0] == p[1] && p[2] == p[3];
+            let dx = (p[3].x - p[0].x).abs();
+            let dy = (p[3].y - p[0].y).abs();
+
+            if is_line && dy > 0. && dx / dy <= VERTICAL_SLOPE {
+                probes.push(p[0].lerp(p[3], 0.5));
+            }
+        }
+    }
+
+    return probes;
+}
+
+// The width of the filled run a horizontal line through probe crosses, nonzero fill. If the probe isn't inside
+// the outline we measure the closest run instead. None when the line misses the outline entirely.
+pub fn measure_stem(outline: &Piecewise<Piecewise<Bezier>>, probe: Vector) -> Option<f64>
+{
+    // every place the horizontal line crosses the outline along with which way the outline was going
+    let mut crossings: Vec<(f64, i32)> = Vec::new();
+    for contour in outline.segs() {
+        for bez in contour.segs() {
+            let p = bez.to_control_points();
+            let a = -p[0].y + 3. * p[1].y - 3. * p[2].y + p[3].y;
+            let b = 3. * p[0].y - 6. * p[1].y + 3. * p[2].y;
+            let c = -3. * p[0].y + 3. * p[1].y;
+            let d = p[0].y - probe.y;
+
+            for t in roots::solve_cubic(a, b, c, d, 0., 1.) {
+                if t >= 1. { continue; }
+                let dy = bez.derivative(t).y;
+                if dy == 0. { continue; }
+                crossings.push((bez.evaluate(t).x, if dy > 0. { 1 } else { -1 }));
+            }
+        }
+    }
+
+    crossings.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    // walk left to right collecting the runs where the winding isn't zero
+    let mut runs: Vec<(f64, f64)> = Vec::new();
+    let mut winding = 0;
+    let mut run_start = 0.;
+    for (x, direction) in crossings {
+        let was_filled = winding != 0;
+        winding = winding + direction;
+
+        if !was_filled && winding != 0 { run_start = x; }
+        if was_filled && winding == 0 { runs.push((run_start, x)); }
+    }
+
+    let distance = |run: &(f64, f64)| {
+        if probe.x < run.0 { run.0 - probe.x } else if probe.x > run.1 { probe.x - run.1 } else { 0. }
+    };
+
+    return runs.iter()
+        .min_by(|a, b| distance(a).partial_cmp(&distance(b)).unwrap())
+        .map(|run| run.1 - run.0);
+}
+
+fn mean_stem(outline: &Piecewise<Piecewise<Bezier>>, probes: &[Vector]) -> Option<f64>
+{
+    let measurements: Vec<f64> = probes.iter().filter_map(|p| measure_stem(outline, *p)).collect();
+    if measurements.is_empty() { return None; }
+
+    return Some(measurements.iter().sum::<f64>() / measurements.len() as f64);
+}
+
+// Strokes with whatever vertical pattern scale makes the stems measure target.target. The returned result's
+// auto_width holds the scale we settled on.
+pub fn stroke_to_target(piece_path: &Piecewise<Piecewise<Bezier>>, piece_pattern: &Piecewise<Piecewise<Bezier>>, settings: &PatternSettings, target: &TargetMeasurement) -> Result<StrokeResult, String>
+{
+    let probes = if target.probes.is_empty() { vertical_probes(piece_path) } else { target.probes.clone() };
+    if probes.is_empty() {
+        return Err(String::from("Auto width found no vertical stems to measure, give it some probe points."));
+    }
+
+    let mut working = settings.clone();
+    working.auto_width = None;
+
+    let mut measure = |scale: f64| {
+        working.pattern_scale.y = scale;
+        let result = stroke_outline(piece_path, piece_pattern, &working);
+        let measured = mean_stem(&result.outline, &probes);
+        (result, measured)
+    };
+
+    let mut scale = settings.pattern_scale.y;
+    let (mut result, measured) = measure(scale);
+    let mut error = match measured {
+        Some(m) => m - target.target,
+        None => return Err(String::from("Auto width couldn't measure the stroked output at any of the probes.")),
+    };
+
+    // Our first guess assumes the width is proportional to the scale, which it is for straight stems, after that
+    // the secant method takes over for everything else.
+    let mut previous: Option<(f64, f64)> = None;
+    for _ in 0..MAX_ITERATIONS {
+        if error.abs() <= target.tolerance { break; }
+
+        let next = match previous {
+            Some((prev_scale, prev_error)) if error != prev_error => scale - error * (scale - prev_scale) / (error - prev_error),
+            _ => scale * target.target / (error + target.target),
+        };
+
+        if !next.is_finite() { break; }
+
+        previous = Some((scale, error));
+        scale = next;
+
+        let (next_result, measured) = measure(scale);
+        result = next_result;
+        error = match measured {
+            Some(m) => m - target.target,
+            None => return Err(String::from("Auto width lost track of the stems while iterating.")),
+        };
+    }
+
+    if error.abs() > target.tolerance {
+        return Err(format!("Auto width didn't converge, the stems still measure {} off the target.", error));
+    }
+
+    result.auto_width = Some(scale);
+    return Ok(result);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn skeleton(to: Vector) -> Piecewise<Piecewise<Bezier>>
+    {
+        let from = Vector { x: 0., y: 0. };
+        return Piecewise::new(vec![Piecewise::new(vec![Bezier::from_control_points(from, from, to, to)])]);
+    }
+
+    // a 20 unit tall square stretched along the whole path, so the stroke is 20 times the vertical scale wide
+    fn settings() -> PatternSettings
+    {
+        return PatternSettings { stretch: true, ..PatternSettings::default() };
+    }
+
+    fn pattern() -> Piecewise<Piecewise<Bezier>>
+    {
+        return Piecewise::rect(Rect { left: 0., bottom: -10., right: 20., top: 10. });
+    }
+
+    #[test]
+    fn vertical_stem()
+    {
+        let path = skeleton(Vector { x: 0., y: 400. });
+        assert_eq!(vertical_probes(&path), vec![Vector { x: 0., y: 200. }]);
+
+        let target = TargetMeasurement { target: 84., probes: Vec::new(), tolerance: 0.01 };
+        let result = stroke_to_target(&path, &pattern(), &settings(), &target).unwrap();
+        assert!((result.auto_width.unwrap() - 4.2).abs() < 1e-3, "{:?}", result.auto_width);
+        assert!((measure_stem(&result.outline, Vector { x: 0., y: 200. }).unwrap() - 84.).abs() <= 0.01);
+    }
+
+    // Measured horizontally a stem leaning 1 in 4 is sqrt(17)/4 times wider than it is across.
+    #[test]
+    fn slanted_stem()
+    {
+        let path = skeleton(Vector { x: 100., y: 400. });
+        assert!(vertical_probes(&path).is_empty());
+        let target = TargetMeasurement { target: 84., probes: Vec::new(), tolerance: 0.01 };
+        assert!(stroke_to_target(&path, &pattern(), &settings(), &target).is_err());
+
+        let target = TargetMeasurement { probes: vec![Vector { x: 50., y: 200. }], ..target };
+        let result = stroke_to_target(&path, &pattern(), &settings(), &target).unwrap();
+        let want = 84. / (20. * f64::sqrt(17.) / 4.);
+        assert!((result.auto_width.unwrap() - want).abs() < 1e-3, "{:?} rather than {}", result.auto_width, want);
+        assert!((measure_stem(&result.outline, Vector { x: 50., y: 200. }).unwrap() - 84.).abs() <= 0.01);
+    }
+}
@@ -0,0 +1,233 @@
+// Running a stroke over every glyph in a layer of a UFO. The layer's glyphs come from its contents.plist and the
+// layer itself from the font's layercontents.plist, so names that don't match their file names are fine. Results go
+// back over the glyphs they came from or into another layer, which gets made if the font doesn't have it yet. One
+// glyph failing doesn't stop the rest, it's just reported.
+use crate::qmath::*;
+use crate::components;
+use crate::glifwriter;
+use crate::plistio::{self, child_elements, read_plist, write_plist};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use xmlwriter::*;
+
+pub const DEFAULT_LAYER: &str = "public.default";
+
+// What happened to one glyph.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GlyphOutcome {
+    Processed,
+    // why we left it alone, like it being made of nothing but components
+    Skipped(String),
+    Failed(String),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BatchReport {
+    // every glyph in the layer by name, in the order contents.plist has them
+    pub glyphs: Vec<(String, GlyphOutcome)>,
+}
+
+impl BatchReport {
+    pub fn processed(&self) -> usize
+    {
+        return self.glyphs.iter().filter(|(_, o)| *o == GlyphOutcome::Processed).count();
+    }
+
+    pub fn skipped(&self) -> usize
+    {
+        return self.glyphs.iter().filter(|(_, o)| matches!(o, GlyphOutcome::Skipped(_))).count();
+    }
+
+    pub fn failed(&self) -> usize
+    {
+        return self.glyphs.iter().filter(|(_, o)| matches!(o, GlyphOutcome::Failed(_))).count();
+    }
+
+    // A line for every glyph that wasn't processed saying why, then the totals.
+    pub fn summary(&self) -> Vec<String>
+    {
+        let mut lines = Vec::new();
+        for (name, outcome) in &self.glyphs {
+            match outcome {
+                GlyphOutcome::Processed => {}
+                GlyphOutcome::Skipped(reason) => lines.push(format!("Skipped {}: {}", name, reason)),
+                GlyphOutcome::Failed(reason) => lines.push(format!("Failed {}: {}", name, reason)),
+            }
+        }
+        lines.push(format!("{} processed, {} skipped, {} failed.", self.processed(), self.skipped(), self.failed()));
+
+        return lines;
+    }
+}
+
+fn write_string(writer: &mut XmlWriter, element: &str, text: &str)
+{
+    writer.start_element(element);
+    writer.write_text(text);
+    writer.end_element();
+}
+
+// The layers in the font as (name, directory). A font without a layercontents.plist only has the default layer.
+fn read_layers(ufo: &Path) -> Result<Vec<(String, String)>, String>
+{
+    let path = ufo.join("layercontents.plist");
+    if !path.exists() { return Ok(vec![(String::from(DEFAULT_LAYER), String::from("glyphs"))]); }
+
+    let plist = read_plist(&path)?;
+    let array = plist.get_child("array").ok_or(format!("{} has no array.", path.display()))?;
+
+    let mut layers = Vec::new();
+    for entry in child_elements(array) {
+        let pair = child_elements(entry);
+        if pair.len() != 2 { return Err(format!("{} has a layer that isn't a name and a directory.", path.display())); }
+        layers.push((plistio::text(pair[0]), plistio::text(pair[1])));
+    }
+
+    return Ok(layers);
+}
+
+fn write_layers(ufo: &Path, layers: &[(String, String)]) -> Result<(), String>
+{
+    return write_plist(&ufo.join("layercontents.plist"), |writer| {
+        writer.start_element("array");
+        for (name, directory) in layers {
+            writer.start_element("array");
+            write_string(writer, "string", name);
+            write_string(writer, "string", directory);
+            writer.end_element();
+        }
+        writer.end_element();
+    });
+}
+
+// The glyphs in a layer's directory as (name, file name), from its contents.plist.
+pub fn read_layer_contents(directory: &Path) -> Result<Vec<(String, String)>, String>
+{
+    let path = directory.join("contents.plist");
+    let plist = read_plist(&path)?;
+    let dict = plist.get_child("dict").ok_or(format!("{} has no dict.", path.display()))?;
+    let entries = plistio::dict_entries(dict).map_err(|e| format!("{} {}", path.display(), e))?;
+
+    return Ok(entries.into_iter().map(|(name, file)| (name, plistio::text(file))).collect());
+}
+
+fn write_layer_contents(directory: &Path, glyphs: &[(String, String)]) -> Result<(), String>
+{
+    return write_plist(&directory.join("contents.plist"), |writer| {
+        writer.start_element("dict");
+        for (name, file) in glyphs {
+            write_string(writer, "key", name);
+            write_string(writer, "string", file);
+        }
+        writer.end_element();
+    });
+}
+
+// The directory a layer's glyphs are in.
+pub fn layer_directory(ufo: &Path, layer: &str) -> Result<PathBuf, String>
+{
+    return read_layers(ufo)?.into_iter()
+        .find(|(name, _)| name == layer)
+        .map(|(_, directory)| ufo.join(directory))
+        .ok_or(format!("{} has no layer {}.", ufo.display(), layer));
+}
+
+// The directory for the output layer, added to layercontents.plist as glyphs.<layer> if the font doesn't have it.
+fn output_directory(ufo: &Path, layer: &str) -> Result<PathBuf, String>
+{
+    let mut layers = read_layers(ufo)?;
+    if let Some((_, directory)) = layers.iter().find(|(name, _)| name == layer) {
+        return Ok(ufo.join(directory));
+    }
+
+    let directory = format!("glyphs.{}", layer);
+    fs::create_dir_all(ufo.join(&directory)).map_err(|e| format!("Unable to make {}! {}", directory, e))?;
+    write_layer_contents(&ufo.join(&directory), &[])?;
+    layers.push((String::from(layer), directory.clone()));
+    write_layers(ufo, &layers)?;
+
+    return Ok(ufo.join(directory));
+}
+
+// Reads one glyph, runs stroke over it and writes it out to output. Gives back what happened.
+fn process_glyph<F>(input: &Path, output: &Path, file: &str, stroke: &F) -> GlyphOutcome
+    where F: Fn(&Piecewise<Piecewise<Bezier>>, &[bool], &[(String, String)]) -> Result<Piecewise<Piecewise<Bezier>>, String>
+{
+    let xml = match fs::read_to_string(input.join(file)) {
+        Ok(xml) => xml,
+        Err(e) => return GlyphOutcome::Failed(format!("Failed to read {}! {}", file, e)),
+    };
+    let (glyph, lib) = match components::read_glyph(&xml).and_then(|g| Ok((g, components::read_lib(&xml)?))) {
+        Ok(read) => read,
+        Err(e) => return GlyphOutcome::Failed(e),
+    };
+
+    if glyph.glif.outline.as_ref().map_or(true, |o| o.is_empty()) {
+        let reason = if glyph.components.is_empty() { "it has no outline" } else { "it's only components" };
+        return GlyphOutcome::Skipped(String::from(reason));
+    }
+
+    // contours starting with a move were drawn open, even where their ends meet
+    let closed = match &glyph.glif.outline {
+        Some(outline) => Piecewise::from_outline_with_closed(outline).1,
+        None => Vec::new(),
+    };
+
+    // process_glif can't fail part way so we hang on to the stroke's error and check it after
+    let mut failure = None;
+    let processed = components::process_glif(&glyph, |outline| match stroke(outline, &closed, &lib) {
+        Ok(stroked) => stroked,
+        Err(e) => {
+            failure = Some(e);
+            outline.clone()
+        }
+    });
+
+    let processed = match (processed, failure) {
+        (_, Some(e)) => return GlyphOutcome::Failed(e),
+        (Err(e), None) => return GlyphOutcome::Failed(e.to_string()),
+        (Ok(processed), None) => processed,
+    };
+
+    let lib: Vec<(&str, &str)> = lib.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+    let contents = glifwriter::write_ufo_glif_with_components(processed.glif, &processed.components, &lib);
+    return match fs::write(output.join(file), contents) {
+        Ok(()) => GlyphOutcome::Processed,
+        Err(e) => GlyphOutcome::Failed(format!("Unable to write {}! {}", file, e)),
+    };
+}
+
+// Runs stroke over the outline of every glyph in layer, along with whether each contour was drawn closed and the
+// string entries in the glyph's lib which glifparser doesn't read for us. The results replace the glyphs where they are, or go into output_layer. Glyphs
+// with no outline, including composites, are skipped and left out of the output layer. Only errors that stop the
+// whole batch, like the layer not existing, come back as Err. Everything about a single glyph goes in the report.
+pub fn stroke_layer<F>(ufo: &Path, layer: &str, output_layer: Option<&str>, stroke: F) -> Result<BatchReport, String>
+    where F: Fn(&Piecewise<Piecewise<Bezier>>, &[bool], &[(String, String)]) -> Result<Piecewise<Piecewise<Bezier>>, String> + Sync
+{
+    let input = layer_directory(ufo, layer)?;
+    let glyphs = read_layer_contents(&input)?;
+    let output = match output_layer {
+        Some(name) if name != layer => output_directory(ufo, name)?,
+        _ => input.clone(),
+    };
+
+    // every glyph writes its own file, collecting in order keeps the report the same either way
+    #[cfg(feature = "rayon")]
+    let outcomes: Vec<GlyphOutcome> = glyphs.par_iter().map(|(_, file)| process_glyph(&input, &output, file, &stroke)).collect();
+
+    #[cfg(not(feature = "rayon"))]
+    let outcomes: Vec<GlyphOutcome> = glyphs.iter().map(|(_, file)| process_glyph(&input, &output, file, &stroke)).collect();
+
+    if output != input {
+        let mut contents = read_layer_contents(&output)?;
+        for ((name, file), outcome) in glyphs.iter().zip(&outcomes) {
+            if *outcome != GlyphOutcome::Processed || contents.iter().any(|(n, _)| n == name) { continue; }
+            contents.push((name.clone(), file.clone()));
+        }
+        write_layer_contents(&output, &contents)?;
+    }
+
+    return Ok(BatchReport { glyphs: glyphs.into_iter().map(|(name, _)| name).zip(outcomes).collect() });
+}
@@ -0,0 +1,162 @@
+// Lets repeated runs over the same glyphs skip the stroking. Every input that can change the output gets hashed
+// into a key: the crate version, the value of every option that affects the stroke, the contents of every file
+// those options point at and whatever the stroke was made from, which for a glyph in a UFO is its own glif and the
+// glifs of every glyph it uses as a component. If the cache directory already has an output under that key we use
+// it instead of doing the work again.
+use clap::ArgMatches;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Options that only change where things go or what gets reported, every other option given is part of the key.
+// path is hashed by what's in it, see StrokeCache::key.
+const UNKEYED_ARGS: [&str; 6] = ["path", "output", "report", "cache_dir", "measure_angle", "verify_offset"];
+
+// Options that are file names, we hash what's in them rather than where they are.
+const FILE_ARGS: [&str; 5] = ["pattern", "cap", "start_cap", "end_cap", "resume_from"];
+
+// FNV-1a, std's hasher is allowed to change between releases and these keys live on disk
+#[derive(Clone)]
+struct Fnv(u64);
+
+impl Fnv {
+    fn write(&mut self, bytes: &[u8])
+    {
+        for b in bytes {
+            self.0 = (self.0 ^ *b as u64).wrapping_mul(0x100000001b3);
+        }
+
+        // keeps ("ab", "c") and ("a", "bc") apart
+        self.0 = (self.0 ^ 0xff).wrapping_mul(0x100000001b3);
+    }
+}
+
+pub struct StrokeCache {
+    dir: PathBuf,
+    // everything but the input itself
+    settings: Fnv,
+    hits: usize,
+    misses: usize,
+}
+
+impl StrokeCache {
+    // files are anything else the stroke reads, like the fontinfo.plist --snap-to-metrics takes its metrics from.
+    pub fn new(dir: &str, matches: &ArgMatches, output: &str, files: &[&Path]) -> Result<Self, String>
+    {
+        let mut hash = Fnv(0xcbf29ce484222325);
+        hash.write(env!("CARGO_PKG_VERSION").as_bytes());
+
+        // svg and glif output of the same stroke are different files
+        hash.write(output.rsplit('.').next().unwrap_or("").as_bytes());
+
+        // clap keeps what was given in a map, sorted they come out the same every run
+        let mut names: Vec<&str> = matches.args.keys().cloned().filter(|name| !UNKEYED_ARGS.contains(name)).collect();
+        names.sort();
+
+        for name in names {
+            hash.write(name.as_bytes());
+            let value = matches.value_of(name).unwrap_or("");
+
+            // built in caps are just names, custom ones are glifs, artifacts to resume from are JSON
+            let is_file = FILE_ARGS.contains(&name) && (value.ends_with(".glif") || value.ends_with(".svg") || value.ends_with(".json"));
+            if is_file {
+                let contents = fs::read(value).map_err(|e| format!("Failed to read {} for the cache key! {}", value, e))?;
+                hash.write(&contents);
+            } else {
+                hash.write(value.as_bytes());
+            }
+        }
+
+        for file in files {
+            let contents = fs::read(file).map_err(|e| format!("Failed to read {} for the cache key! {}", file.display(), e))?;
+            hash.write(&contents);
+        }
+
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create cache directory! {}", e))?;
+
+        return Ok(StrokeCache { dir: PathBuf::from(dir), settings: hash, hits: 0, misses: 0 });
+    }
+
+    // The key for stroking inputs with our settings, inputs being the contents of every file the stroke is made from.
+    pub fn key(&self, inputs: &[&[u8]]) -> String
+    {
+        let mut hash = self.settings.clone();
+        for input in inputs {
+            hash.write(input);
+        }
+
+        return format!("{:016x}", hash.0);
+    }
+
+    // What was stored under key, if anything. Counts as a hit or a miss.
+    pub fn fetch(&mut self, key: &str) -> Option<String>
+    {
+        match fs::read_to_string(self.dir.join(key)) {
+            Ok(contents) => {
+                self.hits += 1;
+                Some(contents)
+            }
+            Err(_) => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn store(&self, key: &str, contents: &str) -> Result<(), String>
+    {
+        return fs::write(self.dir.join(key), contents).map_err(|e| format!("Failed to write to the cache! {}", e));
+    }
+
+    pub fn hits(&self) -> usize
+    {
+        return self.hits;
+    }
+
+    pub fn misses(&self) -> usize
+    {
+        return self.misses;
+    }
+
+    // For the end of a batch.
+    pub fn summary(&self) -> String
+    {
+        return format!("{} from the cache, {} stroked.", self.hits, self.misses);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(args: &[&str]) -> ArgMatches<'static>
+    {
+        return crate::app().get_matches_from(["QPaP"].iter().chain(args.iter()));
+    }
+
+    #[test]
+    fn hits_and_misses()
+    {
+        let dir = std::env::temp_dir().join(format!("qstroke-cache-unit-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let pattern = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures").join("pattern.glif");
+        let args = ["--path", "a.glif", "--pattern", pattern.to_str().unwrap(), "--out", "out.glif", "--mode", "repeated"];
+
+        let mut cache = StrokeCache::new(dir.to_str().unwrap(), &matches(&args), "out.glif", &[]).unwrap();
+        let key = cache.key(&[b"skeleton"]);
+        assert_eq!(cache.fetch(&key), None);
+        cache.store(&key, "stroked").unwrap();
+        assert_eq!(cache.fetch(&key), Some(String::from("stroked")));
+        assert_eq!(cache.fetch(&cache.key(&[b"another skeleton"])), None);
+        assert_eq!((cache.hits(), cache.misses()), (1, 2));
+
+        // where the input and output are and what gets reported don't matter, the settings and the input do
+        let moved = ["--path", "b.glif", "--pattern", pattern.to_str().unwrap(), "--out", "elsewhere.glif", "--mode", "repeated", "--report", "r.csv"];
+        assert_eq!(StrokeCache::new(dir.to_str().unwrap(), &matches(&moved), "elsewhere.glif", &[]).unwrap().key(&[b"skeleton"]), key);
+        let changed = ["--path", "a.glif", "--pattern", pattern.to_str().unwrap(), "--out", "out.glif", "--mode", "single"];
+        assert_ne!(StrokeCache::new(dir.to_str().unwrap(), &matches(&changed), "out.glif", &[]).unwrap().key(&[b"skeleton"]), key);
+        let fontinfo = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures").join("ufo").join("components.ufo").join("fontinfo.plist");
+        assert_ne!(StrokeCache::new(dir.to_str().unwrap(), &matches(&args), "out.glif", &[&fontinfo]).unwrap().key(&[b"skeleton"]), key);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
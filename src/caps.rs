@@ -0,0 +1,49 @@
+// The shapes that close off the ends of open strokes, on their own so anything drawing a stroke can use them and not
+// just stroke.rs. A cap runs from the right side of the stroke round to the left, looking along the direction it
+// points in, so the same call works at both ends of a path: at the end the tangent is the way the path arrives, at
+// the start it's the way the path leaves turned around.
+use crate::qmath::*;
+use crate::stroke::StrokeCap;
+use std::f64::consts::PI;
+
+fn line(from: Vector, to: Vector) -> Bezier
+{
+    return Bezier::from_control_points(from, from, to, to);
+}
+
+// A circular arc around center starting at center + from and turning sweep radians, counter-clockwise when
+// positive. See Piecewise::arc for how it's split up.
+pub(crate) fn arc(center: Vector, from: Vector, sweep: f64) -> Vec<Bezier>
+{
+    return Piecewise::arc(center, from.magnitude(), from.y.atan2(from.x), sweep).into_segs();
+}
+
+// The cap for a stroke width wide ending at end_point and pointing along tangent. Custom caps are drawn for a stroke
+// one unit wide that ends at the origin heading along +x, going from the right side at (0, -0.5) round to the left
+// at (0, 0.5), and get scaled up to width and turned to face along tangent. Nothing if the stroke has no width or
+// the tangent is zero.
+pub fn generate(kind: &StrokeCap, end_point: Vector, tangent: Vector, width: f64) -> Piecewise<Bezier>
+{
+    let half = width / 2.;
+    let forward = match tangent.try_normalize() {
+        Some(forward) if half > 0. => forward,
+        _ => return Piecewise::new(Vec::new()),
+    };
+    let left = forward.perpendicular();
+    let (from, to) = (end_point + -left * half, end_point + left * half);
+
+    let curves = match kind {
+        StrokeCap::Butt => vec![line(from, to)],
+        StrokeCap::Round => arc(end_point, from + -end_point, PI),
+        StrokeCap::Square => {
+            let out = forward * half;
+            vec![line(from, from + out), line(from + out, to + out), line(to + out, to)]
+        }
+        StrokeCap::Custom(contour) => {
+            let place = |v: &Vector| end_point + forward * (v.x * width) + left * (v.y * width);
+            contour.segs().map(|bez| bez.apply_transform(&place)).collect()
+        }
+    };
+
+    return Piecewise::new(curves);
+}
@@ -0,0 +1,320 @@
+// Stroking a whole set of glyphs where some of them are built out of components. Glif doesn't know about
+// components so we read them out of the XML ourselves.
+//
+// With components preserved a composite stays a composite and its bases get stroked like any other glyph, so
+// 'adieresis' ends up pointing at the stroked 'a' and 'dieresis'. That only works if the component's transform
+// doesn't change the stroke's width unevenly, anything besides rotation, uniform scale, reflection and
+// translation would squash the stroke on the way in so those components get decomposed into the composite's
+// skeleton and stroked along with it instead.
+use crate::qmath::*;
+use crate::pattern_along_path::{PatternSettings, pattern_along_piecewise};
+use crate::plistio;
+use glifparser::Glif;
+use std::collections::{BTreeMap, HashSet};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Component {
+    pub base: String,
+    pub transform: Affine,
+}
+
+// A glyph as we read it, its glif plus whatever components it has.
+pub struct GlyphSource {
+    pub glif: Glif<Option<PointData>>,
+    pub components: Vec<Component>,
+}
+
+pub struct StrokedGlyph {
+    pub glif: Glif<Option<PointData>>,
+    // the components that were kept, these get written back out as they are
+    pub components: Vec<Component>,
+    pub warnings: Vec<String>,
+}
+
+fn attribute(element: &xmltree::Element, name: &str, default: f64) -> Result<f64, String>
+{
+    return match element.attributes.get(name) {
+        Some(v) => v.trim().parse::<f64>().map_err(|_| format!("Invalid component {} {}.", name, v)),
+        None => Ok(default)
+    };
+}
+
+// The components in a glif, in the order they appear in.
+pub fn read_components(xml: &str) -> Result<Vec<Component>, String>
+{
+    let glyph = xmltree::Element::parse(xml.as_bytes()).map_err(|e| format!("Failed to parse glif! {}", e))?;
+    let outline = match glyph.get_child("outline") {
+        Some(o) => o,
+        None => return Ok(Vec::new())
+    };
+
+    let mut components = Vec::new();
+    for element in outline.children.iter().filter_map(|c| c.as_element()) {
+        if element.name != "component" { continue; }
+
+        let base = element.attributes.get("base").ok_or(String::from("Component without a base."))?;
+        let matrix = [
+            attribute(element, "xScale", 1.)?,
+            attribute(element, "xyScale", 0.)?,
+            attribute(element, "yxScale", 0.)?,
+            attribute(element, "yScale", 1.)?,
+            attribute(element, "xOffset", 0.)?,
+            attribute(element, "yOffset", 0.)?,
+        ];
+        components.push(Component { base: base.clone(), transform: Affine { matrix: matrix } });
+    }
+
+    return Ok(components);
+}
+
+// The string entries in a glif's lib, which glifparser doesn't read for us.
+pub fn read_lib(xml: &str) -> Result<Vec<(String, String)>, String>
+{
+    let glyph = xmltree::Element::parse(xml.as_bytes()).map_err(|e| format!("Failed to parse glif! {}", e))?;
+    let dict = match glyph.get_child("lib").and_then(|lib| lib.get_child("dict")) {
+        Some(dict) => dict,
+        None => return Ok(Vec::new())
+    };
+
+    let entries = plistio::dict_entries(dict).map_err(|e| format!("The glif's lib {}", e))?;
+    return Ok(entries.into_iter().filter(|(_, value)| value.name == "string").map(|(key, value)| (key, plistio::text(value))).collect());
+}
+
+// The points of a glif's contours straight from the XML, each with the segment type a PointPen would be given for
+// it. Nothing gets folded into handles, so TrueType runs of off-curve points come through as they were written.
+pub fn read_contour_points(xml: &str) -> Result<Vec<Vec<(Vector, Option<SegmentType>)>>, String>
+{
+    let glyph = xmltree::Element::parse(xml.as_bytes()).map_err(|e| format!("Failed to parse glif! {}", e))?;
+    let outline = match glyph.get_child("outline") {
+        Some(o) => o,
+        None => return Ok(Vec::new())
+    };
+
+    let mut contours = Vec::new();
+    for contour in outline.children.iter().filter_map(|c| c.as_element()) {
+        if contour.name != "contour" { continue; }
+
+        let mut points = Vec::new();
+        for point in contour.children.iter().filter_map(|c| c.as_element()) {
+            if point.name != "point" { continue; }
+
+            let coordinate = |name: &str| match point.attributes.get(name) {
+                Some(v) => v.trim().parse::<f64>().map_err(|_| format!("Invalid point {} {}.", name, v)),
+                None => Err(format!("Point without {}.", name)),
+            };
+            let segment_type = match point.attributes.get("type").map(|t| t.as_str()) {
+                None | Some("offcurve") => None,
+                Some("move") => Some(SegmentType::Move),
+                Some("line") => Some(SegmentType::Line),
+                Some("curve") => Some(SegmentType::Curve),
+                Some("qcurve") => Some(SegmentType::QCurve),
+                Some(t) => return Err(format!("Unknown point type {}.", t)),
+            };
+            points.push((Vector { x: coordinate("x")?, y: coordinate("y")? }, segment_type));
+        }
+        contours.push(points);
+    }
+
+    return Ok(contours);
+}
+
+pub fn read_glyph(xml: &str) -> Result<GlyphSource, String>
+{
+    return Ok(GlyphSource { glif: glifparser::read_ufo_glif(xml), components: read_components(xml)? });
+}
+
+// Why process_glif couldn't do anything with a glyph.
+#[derive(Debug)]
+pub enum ProcessError {
+    // the glyph's name, it's made of nothing but components so there's no outline to hand over
+    ComponentsOnly(String),
+}
+
+impl std::fmt::Display for ProcessError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        match self {
+            ProcessError::ComponentsOnly(name) => write!(f, "{} only has components, there's no outline to stroke.", name),
+        }
+    }
+}
+
+// Hands the glyph's outline to f and gives back the glyph with what f made of it in place of the outline.
+// Everything else is copied across as it was: name, width, unicode, anchors, point order, format and components.
+// Glif drops guidelines and lib data when it reads a glyph so those can't come along. A glyph with no outline and
+// no components gets f called on an empty outline, one with components and no outline is an error.
+pub fn process_glif<F>(glyph: &GlyphSource, f: F) -> Result<GlyphSource, ProcessError>
+    where F: FnOnce(&Piecewise<Piecewise<Bezier>>) -> Piecewise<Piecewise<Bezier>>
+{
+    let outline = match &glyph.glif.outline {
+        Some(outline) => Piecewise::from_outline(outline),
+        None if !glyph.components.is_empty() => return Err(ProcessError::ComponentsOnly(glyph.glif.name.clone())),
+        None => Piecewise::new(Vec::new())
+    };
+
+    let processed = f(&outline);
+    let glif = Glif {
+        outline: if processed.is_empty() { None } else { Some(processed.to_outline()) },
+        order: glyph.glif.order,
+        anchors: glyph.glif.anchors.clone(),
+        width: glyph.glif.width,
+        unicode: glyph.glif.unicode,
+        name: glyph.glif.name.clone(),
+        format: glyph.glif.format,
+    };
+
+    return Ok(GlyphSource { glif: glif, components: glyph.components.clone() });
+}
+
+// Rotation and uniform scale, possibly mirrored, plus any translation. These scale a stroke's width the same
+// way in every direction.
+pub fn is_similarity(transform: &Affine) -> bool
+{
+    let [a, b, c, d, _, _] = transform.matrix;
+    let scale = f64::max(a.abs() + b.abs(), c.abs() + d.abs());
+    if scale < 1e-9 { return false; }
+
+    let tolerance = scale * 1e-6;
+    let rotation = (a - d).abs() < tolerance && (b + c).abs() < tolerance;
+    let reflection = (a + d).abs() < tolerance && (b - c).abs() < tolerance;
+    return rotation || reflection;
+}
+
+// The glyph names, every one after the glyphs its components point at. Components of glyphs we don't have are
+// left out, a glyph that ends up being its own component somewhere down the line is an error.
+pub fn component_order(glyphs: &BTreeMap<String, GlyphSource>) -> Result<Vec<String>, String>
+{
+    fn visit(name: &str, glyphs: &BTreeMap<String, GlyphSource>, done: &mut HashSet<String>, visiting: &mut Vec<String>, order: &mut Vec<String>) -> Result<(), String>
+    {
+        if done.contains(name) { return Ok(()); }
+        if visiting.iter().any(|v| v == name) {
+            visiting.push(name.to_string());
+            return Err(format!("Glyphs {} use each other as components.", visiting.join(" -> ")));
+        }
+
+        let glyph = match glyphs.get(name) {
+            Some(g) => g,
+            None => return Ok(())
+        };
+
+        visiting.push(name.to_string());
+        for component in &glyph.components {
+            visit(&component.base, glyphs, done, visiting, order)?;
+        }
+        visiting.pop();
+
+        done.insert(name.to_string());
+        order.push(name.to_string());
+        return Ok(());
+    }
+
+    let mut done = HashSet::new();
+    let mut order = Vec::new();
+    for name in glyphs.keys() {
+        visit(name, glyphs, &mut done, &mut Vec::new(), &mut order)?;
+    }
+
+    return Ok(order);
+}
+
+// The glyph's skeleton with all of its components flattened into it, moved by transform.
+fn decompose(name: &str, glyphs: &BTreeMap<String, GlyphSource>, transform: &Affine, warnings: &mut Vec<String>) -> Vec<Piecewise<Bezier>>
+{
+    let glyph = match glyphs.get(name) {
+        Some(g) => g,
+        None => {
+            warnings.push(format!("Component base {} doesn't exist, leaving it out.", name));
+            return Vec::new();
+        }
+    };
+
+    let mut contours = Vec::new();
+    if let Some(outline) = &glyph.glif.outline {
+        for contour in Piecewise::from_outline(outline) {
+            contours.push(contour.transform(transform));
+        }
+    }
+
+    for component in &glyph.components {
+        // transform after the component's own one
+        let combined = *transform * component.transform;
+        contours.extend(decompose(&component.base, glyphs, &combined, warnings));
+    }
+
+    return contours;
+}
+
+// The glyph and every glyph it's built from, however far down, each once. What the glyph strokes to can't change
+// unless one of these does. Components of glyphs we don't have are left out.
+pub fn dependencies(name: &str, glyphs: &BTreeMap<String, GlyphSource>) -> Vec<String>
+{
+    let mut found = vec![name.to_string()];
+    let mut i = 0;
+    while i < found.len() {
+        if let Some(glyph) = glyphs.get(&found[i]) {
+            for component in &glyph.components {
+                if glyphs.contains_key(&component.base) && !found.contains(&component.base) {
+                    found.push(component.base.clone());
+                }
+            }
+        }
+        i += 1;
+    }
+
+    return found;
+}
+
+// Strokes one of the glyphs. With preserve_components off every composite is decomposed first so the glyph comes out
+// as plain contours, with it on a composite keeps the components it can, see the top of this file.
+pub fn stroke_glyph(name: &str, glyphs: &BTreeMap<String, GlyphSource>, pattern: &Glif<Option<PointData>>, settings: &PatternSettings, preserve_components: bool) -> StrokedGlyph
+{
+    let glyph = &glyphs[name];
+    let mut warnings = Vec::new();
+
+    let mut skeleton = match &glyph.glif.outline {
+        Some(outline) => Piecewise::from_outline(outline).into_segs(),
+        None => Vec::new()
+    };
+
+    let mut kept = Vec::new();
+    for component in &glyph.components {
+        if preserve_components && is_similarity(&component.transform) && glyphs.contains_key(&component.base) {
+            kept.push(component.clone());
+            continue;
+        }
+
+        if preserve_components && glyphs.contains_key(&component.base) {
+            warnings.push(format!("{}: component {} has a non-uniform or skewed transform, decomposing it.", name, component.base));
+        }
+        skeleton.extend(decompose(&component.base, glyphs, &component.transform, &mut warnings));
+    }
+
+    let mut glif = if skeleton.is_empty() {
+        Glif { outline: None, order: glyph.glif.order, anchors: None, width: 0, unicode: glyph.glif.unicode, name: String::new(), format: 2 }
+    } else {
+        let (stroked, name_warnings, _) = pattern_along_piecewise(&Piecewise::new(skeleton), &[], pattern, settings);
+        warnings.extend(name_warnings);
+        stroked
+    };
+
+    // the stroke only replaces the outline, everything else about the glyph stays
+    glif.name = glyph.glif.name.clone();
+    glif.width = glyph.glif.width;
+    glif.unicode = glyph.glif.unicode;
+    glif.anchors = glyph.glif.anchors.clone();
+
+    return StrokedGlyph { glif: glif, components: kept, warnings: warnings };
+}
+
+// stroke_glyph over every glyph, bases before the composites using them.
+pub fn stroke_glyphs(glyphs: &BTreeMap<String, GlyphSource>, pattern: &Glif<Option<PointData>>, settings: &PatternSettings, preserve_components: bool) -> Result<BTreeMap<String, StrokedGlyph>, String>
+{
+    let mut output = BTreeMap::new();
+
+    for name in component_order(glyphs)? {
+        let stroked = stroke_glyph(&name, glyphs, pattern, settings, preserve_components);
+        output.insert(name, stroked);
+    }
+
+    return Ok(output);
+}
@@ -0,0 +1,142 @@
+// A small C API so the stroker can be called from Python or C tooling without going through the CLI.
+//
+// Memory ownership: the library never allocates memory for the caller. Output is written into a buffer the caller
+// owns. Pass the buffer's capacity in out_len, on return out_len holds the number of bytes the output needs including
+// the trailing NUL. If the buffer is NULL or too small nothing is written and MFEKSTROKE_BUFFER_TOO_SMALL is
+// returned, so call once to get the size, allocate, and call again. Every call catches panics at the boundary.
+use crate::glifwriter;
+use crate::geometry_profile::GEOMETRY_PROFILE_LIB_KEY;
+use crate::pattern_along_path::pattern_along_glif;
+use crate::qmath::{Piecewise, PointData};
+use crate::settings_json::parse_settings;
+use glifparser::Glif;
+use std::cell::RefCell;
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+pub const MFEKSTROKE_OK: i32 = 0;
+pub const MFEKSTROKE_INVALID_ARGUMENT: i32 = 1;
+pub const MFEKSTROKE_INVALID_SETTINGS: i32 = 2;
+pub const MFEKSTROKE_STROKE_FAILED: i32 = 3;
+pub const MFEKSTROKE_BUFFER_TOO_SMALL: i32 = 4;
+
+static VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), "\0");
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<String>> = RefCell::new(None);
+}
+
+fn set_last_error(error: String)
+{
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(error));
+}
+
+struct FfiError(i32, String);
+
+fn stroke_glif(input: &str, settings_json: &str) -> Result<String, FfiError>
+{
+    let parsed = parse_settings(settings_json).map_err(|e| FfiError(MFEKSTROKE_INVALID_SETTINGS, e))?;
+    let (settings, pattern_xml, round) = (parsed.settings, parsed.pattern, parsed.round);
+
+    let path: Glif<Option<PointData>> = glifparser::read_ufo_glif(input);
+    let pattern: Glif<Option<PointData>> = glifparser::read_ufo_glif(&pattern_xml);
+
+    if path.outline.is_none() { return Err(FfiError(MFEKSTROKE_STROKE_FAILED, String::from("The input glif has no outline."))); }
+    if pattern.outline.is_none() { return Err(FfiError(MFEKSTROKE_STROKE_FAILED, String::from("The pattern glif has no outline."))); }
+
+    let mut output = pattern_along_glif(&path, &pattern, &settings);
+
+    if let Some(decimals) = round {
+        let (quantized, _report) = Piecewise::from_outline(output.outline.as_ref().unwrap()).quantize(decimals);
+        output.outline = Some(quantized.to_outline_preserving(output.outline.as_ref().unwrap()));
+    }
+
+    let profile = settings.geometry_profile.resolve().name();
+    return Ok(glifwriter::write_ufo_glif_with_lib(output, &[(GEOMETRY_PROFILE_LIB_KEY, profile)]));
+}
+
+unsafe fn read_utf8<'a>(data: *const c_char, len: usize) -> Result<&'a str, FfiError>
+{
+    if data.is_null() { return Err(FfiError(MFEKSTROKE_INVALID_ARGUMENT, String::from("Input pointer is NULL."))); }
+
+    let bytes = slice::from_raw_parts(data as *const u8, len);
+    return std::str::from_utf8(bytes).map_err(|_| FfiError(MFEKSTROKE_INVALID_ARGUMENT, String::from("Input isn't valid UTF-8.")));
+}
+
+unsafe fn read_c_str<'a>(data: *const c_char) -> Result<&'a str, FfiError>
+{
+    if data.is_null() { return Err(FfiError(MFEKSTROKE_INVALID_ARGUMENT, String::from("Settings pointer is NULL."))); }
+
+    return std::ffi::CStr::from_ptr(data).to_str().map_err(|_| FfiError(MFEKSTROKE_INVALID_ARGUMENT, String::from("Settings aren't valid UTF-8.")));
+}
+
+/// Strokes the glif in input_xml (input_len bytes of UTF-8, no NUL needed) using the NUL terminated settings_json.
+/// The resulting glif is written to out_buf as a NUL terminated string. Pass out_buf's capacity in *out_len, on return
+/// it holds the bytes the glif needs including the NUL, and if out_buf is NULL or smaller than that nothing is
+/// written and MFEKSTROKE_BUFFER_TOO_SMALL is returned. The caller owns out_buf, the library never keeps or frees it.
+/// Returns MFEKSTROKE_OK on success, on failure mfekstroke_last_error_message explains what happened.
+#[no_mangle]
+pub unsafe extern "C" fn mfekstroke_stroke_glif_utf8(input_xml: *const c_char, input_len: usize, settings_json: *const c_char, out_buf: *mut c_char, out_len: *mut usize) -> i32
+{
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        if out_len.is_null() { return Err(FfiError(MFEKSTROKE_INVALID_ARGUMENT, String::from("out_len is NULL."))); }
+
+        let input = read_utf8(input_xml, input_len)?;
+        let settings = read_c_str(settings_json)?;
+        let output = stroke_glif(input, settings)?;
+
+        let required = output.len() + 1;
+        let capacity = *out_len;
+        *out_len = required;
+
+        if out_buf.is_null() || capacity < required {
+            return Err(FfiError(MFEKSTROKE_BUFFER_TOO_SMALL, format!("The output needs a buffer of {} bytes.", required)));
+        }
+
+        ptr::copy_nonoverlapping(output.as_ptr(), out_buf as *mut u8, output.len());
+        *out_buf.add(output.len()) = 0;
+
+        return Ok(());
+    }));
+
+    match result {
+        Ok(Ok(())) => MFEKSTROKE_OK,
+        Ok(Err(FfiError(status, message))) => {
+            set_last_error(message);
+            status
+        }
+        Err(_) => {
+            set_last_error(String::from("The stroker panicked, the input glif is probably malformed."));
+            MFEKSTROKE_STROKE_FAILED
+        }
+    }
+}
+
+/// The library's version as a static NUL terminated string. Don't free it.
+#[no_mangle]
+pub extern "C" fn mfekstroke_version() -> *const c_char
+{
+    return VERSION.as_ptr() as *const c_char;
+}
+
+/// Copies the last error that happened on this thread into buf using the same size negotiation as
+/// mfekstroke_stroke_glif_utf8. If there was no error an empty string is written.
+#[no_mangle]
+pub unsafe extern "C" fn mfekstroke_last_error_message(buf: *mut c_char, len: *mut usize) -> i32
+{
+    if len.is_null() { return MFEKSTROKE_INVALID_ARGUMENT; }
+
+    let message = LAST_ERROR.with(|e| e.borrow().clone()).unwrap_or_default();
+    let required = message.len() + 1;
+    let capacity = *len;
+    *len = required;
+
+    if buf.is_null() || capacity < required { return MFEKSTROKE_BUFFER_TOO_SMALL; }
+
+    ptr::copy_nonoverlapping(message.as_ptr(), buf as *mut u8, message.len());
+    *buf.add(message.len()) = 0;
+
+    return MFEKSTROKE_OK;
+}
@@ -0,0 +1,73 @@
+// Font builds need to come out the same years later, so every constant and algorithm choice that shapes the
+// output belongs to a versioned profile. When we change a default it goes into a new profile and Latest moves
+// to it; the old profiles keep their values and their code paths so archived builds still reproduce.
+//
+// v1 - the stroker as it is today
+
+// the lib key the profile a glif was stroked with gets recorded under
+pub const GEOMETRY_PROFILE_LIB_KEY: &str = "com.qstroke.geometryProfile";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GeometryProfile {
+    V1,
+    // whichever profile is newest, resolve it before recording it anywhere
+    Latest,
+}
+
+impl Default for GeometryProfile {
+    fn default() -> Self
+    {
+        return GeometryProfile::Latest;
+    }
+}
+
+impl GeometryProfile {
+    pub fn resolve(self) -> GeometryProfile
+    {
+        return match self {
+            GeometryProfile::Latest => GeometryProfile::V1,
+            p => p
+        };
+    }
+
+    pub fn from_name(name: &str) -> Option<GeometryProfile>
+    {
+        return match name {
+            "v1" => Some(GeometryProfile::V1),
+            "latest" => Some(GeometryProfile::Latest),
+            _ => None
+        };
+    }
+
+    pub fn name(self) -> &'static str
+    {
+        return match self {
+            GeometryProfile::V1 => "v1",
+            GeometryProfile::Latest => "latest",
+        };
+    }
+
+    // how many steps the arc length table of each path contour takes
+    pub fn arclen_cuts(self) -> usize
+    {
+        return match self.resolve() {
+            GeometryProfile::V1 | GeometryProfile::Latest => 10000
+        };
+    }
+
+    // how many normals get sampled along each path contour
+    pub fn normal_samples(self) -> usize
+    {
+        return match self.resolve() {
+            GeometryProfile::V1 | GeometryProfile::Latest => 100000
+        };
+    }
+
+    // how far off tangent, in degrees, the curves either side of a seam can be and still get merged
+    pub fn seam_angle_tolerance(self) -> f64
+    {
+        return match self.resolve() {
+            GeometryProfile::V1 | GeometryProfile::Latest => 1.
+        };
+    }
+}
@@ -1,6 +1,7 @@
 use glifparser::*;
 extern crate xmlwriter;
 use xmlwriter::*;
+use crate::components::Component;
 
 fn point_type_to_string(ptype: PointType) -> Option<String>
 {
@@ -15,13 +16,20 @@ fn point_type_to_string(ptype: PointType) -> Option<String>
     }
 }
 
+// Display never gives us trailing zeros but it will write out negative zero, which has no business being in a glif.
+fn format_coordinate(v: f32) -> String
+{
+    if v == 0. { return String::from("0"); }
+    return v.to_string();
+}
+
 fn write_ufo_point_from_handle(mut writer: XmlWriter, handle: Handle) -> XmlWriter
 {
     match handle {
         Handle::At(x, y) => {
             writer.start_element("point");
-                writer.write_attribute("x", &x);
-                writer.write_attribute("y", &y);
+                writer.write_attribute("x", &format_coordinate(x));
+                writer.write_attribute("y", &format_coordinate(y));
             writer.end_element();
         },
         _ => {}
@@ -30,7 +38,51 @@ fn write_ufo_point_from_handle(mut writer: XmlWriter, handle: Handle) -> XmlWrit
     return writer;
 }
 
+// The b handle of point, unless it's a qcurve whose control point already went out as the a handle of the point
+// before it. A qcurve segment has the one control point, which can be kept on either end, and writing it from both
+// would make it a cubic with two.
+fn incoming_handle<T>(previous: &Point<T>, point: &Point<T>) -> Handle
+{
+    let previous_has_a = if let Handle::At(..) = previous.a { true } else { false };
+    let previous_wrote_a = previous_has_a && [PointType::Move, PointType::Line, PointType::Curve, PointType::QCurve].contains(&previous.ptype);
+
+    if point.ptype == PointType::QCurve && previous_wrote_a {
+        return Handle::Colocated;
+    }
+    return point.b;
+}
+
+// Only the parts of the transform that aren't the identity get written out.
+fn write_component(mut writer: XmlWriter, component: &Component) -> XmlWriter
+{
+    let names = ["xScale", "xyScale", "yxScale", "yScale", "xOffset", "yOffset"];
+    let identity = [1., 0., 0., 1., 0., 0.];
+
+    writer.start_element("component");
+    writer.write_attribute("base", &component.base);
+    for i in 0..6 {
+        if component.transform.matrix[i] != identity[i] {
+            writer.write_attribute(names[i], &format_coordinate(component.transform.matrix[i] as f32));
+        }
+    }
+    writer.end_element();
+
+    return writer;
+}
+
 pub fn write_ufo_glif<T>(glif: Glif<T>) -> String
+{
+    return write_ufo_glif_with_lib(glif, &[]);
+}
+
+// Same as above with some string entries in the glif's lib. Glif doesn't carry a lib so we take them separately.
+pub fn write_ufo_glif_with_lib<T>(glif: Glif<T>, lib: &[(&str, &str)]) -> String
+{
+    return write_ufo_glif_with_components(glif, &[], lib);
+}
+
+// Same again with components after the contours, see components.rs.
+pub fn write_ufo_glif_with_components<T>(glif: Glif<T>, components: &[Component], lib: &[(&str, &str)]) -> String
 {
     let mut writer = XmlWriter::new(Options::default());
 
@@ -57,8 +109,8 @@ pub fn write_ufo_glif<T>(glif: Glif<T>) -> String
         Some(anchor_vec) => {
             for anchor in anchor_vec {
                 writer.start_element("anchor");
-                writer.write_attribute("x", &anchor.x);
-                writer.write_attribute("y", &anchor.y);
+                writer.write_attribute("x", &format_coordinate(anchor.x));
+                writer.write_attribute("y", &format_coordinate(anchor.y));
                 writer.write_attribute("name", &anchor.class);
                 // Anchor does not currently contain a color, or identifier attribute
                 writer.end_element();
@@ -67,7 +119,7 @@ pub fn write_ufo_glif<T>(glif: Glif<T>) -> String
         None => {}
     }
 
-    match glif.outline
+    match glif.outline.or_else(|| if components.is_empty() { None } else { Some(Vec::new()) })
     {
         Some(outline) => {
             writer.start_element("outline");
@@ -84,14 +136,14 @@ pub fn write_ufo_glif<T>(glif: Glif<T>) -> String
                 for point in &contour {
                     if let Some(lp) = last_point {
                         // if there was a point prior to this one we emit our b handle
-                        writer = write_ufo_point_from_handle(writer, point.b);
+                        writer = write_ufo_point_from_handle(writer, incoming_handle(lp, point));
                     }
 
 
                 
                     writer.start_element("point");
-                        writer.write_attribute("x", &point.x);
-                        writer.write_attribute("y", &point.y);
+                        writer.write_attribute("x", &format_coordinate(point.x));
+                        writer.write_attribute("y", &format_coordinate(point.y));
                 
                         match point_type_to_string(point.ptype) {
                             Some(ptype_string) => writer.write_attribute("type", &ptype_string),
@@ -107,13 +159,9 @@ pub fn write_ufo_glif<T>(glif: Glif<T>) -> String
                     writer.end_element();
                 
                     match point.ptype {
-                        PointType::Line | PointType::Curve => {
+                        PointType::Move | PointType::Line | PointType::Curve | PointType::QCurve => {
                             writer = write_ufo_point_from_handle(writer, point.a);
                         },
-
-                        PointType::QCurve => {
-                            //QCurve currently unhandled. This needs to be implemented.
-                        },
                         _ => { } // I don't think this should be reachable in a well formed Glif object?
                     }    
                     
@@ -122,15 +170,96 @@ pub fn write_ufo_glif<T>(glif: Glif<T>) -> String
 
                 // if a move wasn't our first point then we gotta close the shape by emitting the first point's b handle
                 if !open_contour {
-                    writer = write_ufo_point_from_handle(writer, contour.first().unwrap().b);
+                    writer = write_ufo_point_from_handle(writer, incoming_handle(contour.last().unwrap(), contour.first().unwrap()));
                 }
 
                 writer.end_element();
             }
+
+            for component in components {
+                writer = write_component(writer, component);
+            }
             writer.end_element();
         },
         None => {}
     }
 
+    if !lib.is_empty() {
+        writer.start_element("lib");
+        writer.start_element("dict");
+        for (key, value) in lib {
+            writer.start_element("key");
+            writer.write_text(key);
+            writer.end_element();
+            writer.start_element("string");
+            writer.write_text(value);
+            writer.end_element();
+        }
+        writer.end_element();
+        writer.end_element();
+    }
+
     writer.end_document()
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::qmath::{Affine, Bezier, Piecewise, PointData, Vector};
+    use crate::testing::read_glif_piecewise;
+    use std::path::Path;
+
+    // Every x and y attribute in the xml.
+    fn coordinates(xml: &str) -> Vec<String>
+    {
+        let mut values = Vec::new();
+        for attribute in [" x=\"", " y=\""].iter() {
+            for (start, _) in xml.match_indices(attribute) {
+                let rest = &xml[start + attribute.len()..];
+                values.push(rest[..rest.find('"').unwrap()].to_string());
+            }
+        }
+
+        return values;
+    }
+
+    fn glif(outline: &Piecewise<Piecewise<Bezier>>) -> Glif<Option<PointData>>
+    {
+        return Glif {
+            outline: Some(outline.to_outline()),
+            order: OutlineType::Cubic,
+            anchors: None,
+            width: 500,
+            unicode: Codepoint::Undefined,
+            name: String::from("test"),
+            format: 2,
+        };
+    }
+
+    // Circles with radii and centers that don't come out even and a fixture skeleton scaled and moved by awkward
+    // amounts, some of it a hair either side of zero, quantized and written out. Nothing in the xml can have more
+    // decimals than we rounded to, or be negative zero.
+    #[test]
+    fn quantized_output_has_at_most_the_requested_decimals()
+    {
+        let skeleton = read_glif_piecewise(&Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/skeletons/s_curve.glif")).unwrap();
+        let mut contours = skeleton.transform(&(Affine::translate(103.49999999999997, -0.000001) * Affine::scale(1. / 3., std::f64::consts::FRAC_1_SQRT_2))).into_segs();
+        contours.push(Piecewise::circle(Vector { x: 1. / 7., y: -1e-7 }, 100. / 3.));
+        contours.push(Piecewise::circle(Vector { x: -250.123456789, y: 999.987654321 }, std::f64::consts::PI));
+        let outline = Piecewise::new(contours);
+
+        for decimals in 0..=4 {
+            let (quantized, _) = outline.quantize(decimals);
+            let xml = write_ufo_glif(glif(&quantized));
+
+            let values = coordinates(&xml);
+            assert!(values.len() > 20, "{}", xml);
+            for value in values {
+                let places = value.find('.').map_or(0, |dot| value.len() - dot - 1);
+                assert!(places <= decimals as usize, "{} has more than {} decimals in {}", value, decimals, xml);
+                assert!(value != "-0", "negative zero in {}", xml);
+                value.parse::<f64>().unwrap();
+            }
+        }
+    }
+}
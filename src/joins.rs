@@ -0,0 +1,290 @@
+// Patterns bend around the corners of the path rather than getting a join built for them, so what comes out at a
+// sharp corner depends on the pattern. This measures what actually came out so it can be checked, and can clip
+// anything that sticks out too far. generate builds joins for strokers that do want them, see stroke.rs.
+use crate::qmath::*;
+use crate::caps::arc;
+use crate::stroke::StrokeJoin;
+use std::collections::HashMap;
+
+// A corner in the path and how far the output sticks out past it.
+#[derive(Debug, Clone, Copy)]
+pub struct JoinRecord {
+    pub contour: usize,
+    // the segment leaving the corner
+    pub segment: usize,
+    // how much the path turns here, in degrees
+    pub angle: f64,
+    // how far from the corner the output reaches on the outside of the turn, and that over half the stroke's width,
+    // which is the miter ratio for a stroke with mitered joins. None when nothing in the output was nearest the corner.
+    pub extent: Option<f64>,
+    pub ratio: Option<f64>,
+}
+
+// corners that turn less than this are smooth enough to not count
+const MIN_CORNER_ANGLE: f64 = 1.;
+
+// samples per output contour when looking for what sits around each corner
+const JOIN_SAMPLES: usize = 400;
+
+// Upper bounds of the histogram buckets for join ratios, the last bucket takes everything above.
+pub const JOIN_BUCKETS: [f64; 4] = [1.5, 2., 3., 4.];
+
+// the direction a segment leaves its start and arrives at its end, from short chords since our lines have their
+// handles on their ends and no derivative there
+fn leaving(bez: &Bezier) -> Vector
+{
+    return (bez.evaluate(1e-4) + -bez.evaluate(0.)).normalize();
+}
+
+fn arriving(bez: &Bezier) -> Vector
+{
+    return (bez.evaluate(1.) + -bez.evaluate(1. - 1e-4)).normalize();
+}
+
+// The nearest point on the whole path as (contour, segment, t, distance).
+fn nearest_on_path(path: &Piecewise<Piecewise<Bezier>>, point: Vector) -> Option<(usize, usize, f64, f64)>
+{
+    let mut best: Option<(usize, usize, f64, f64)> = None;
+
+    for (contour_idx, contour) in path.segs().enumerate() {
+        if let Some((segment, t, distance)) = contour.nearest(point) {
+            if best.map_or(true, |b| distance < b.3) {
+                best = Some((contour_idx, segment, t, distance));
+            }
+        }
+    }
+
+    return best;
+}
+
+// The stroke's half width as the pattern would be laid out across the path.
+pub fn half_width(pattern: &Piecewise<Piecewise<Bezier>>, scale_y: f64) -> f64
+{
+    if pattern.is_empty() { return 0.; }
+
+    let b = pattern.bounds();
+    return (b.top - b.bottom).abs() * scale_y.abs() / 2.;
+}
+
+pub fn measure_joins(path: &Piecewise<Piecewise<Bezier>>, outline: &Piecewise<Piecewise<Bezier>>, half_width: f64) -> Vec<JoinRecord>
+{
+    let mut joins = Vec::new();
+    // (contour, segment leaving the corner) to where it is in joins
+    let mut corners: HashMap<(usize, usize), usize> = HashMap::new();
+
+    for (contour_idx, contour) in path.segs().enumerate() {
+        let count = contour.len();
+        if count == 0 { continue; }
+        let first = if contour.is_closed() { 0 } else { 1 };
+
+        for segment in first..count {
+            let incoming = arriving(&contour[(segment + count - 1) % count]);
+            let outgoing = leaving(&contour[segment]);
+
+            let cross = incoming.x * outgoing.y - incoming.y * outgoing.x;
+            let dot = incoming.x * outgoing.x + incoming.y * outgoing.y;
+            let angle = cross.atan2(dot).abs().to_degrees();
+            if !(angle >= MIN_CORNER_ANGLE) { continue; }
+
+            corners.insert((contour_idx, segment), joins.len());
+            joins.push(JoinRecord { contour: contour_idx, segment: segment, angle: angle, extent: None, ratio: None });
+        }
+    }
+
+    if joins.is_empty() { return joins; }
+
+    // Anything in the output whose nearest point on the path is a corner is sitting around the outside of it.
+    for contour in outline.segs() {
+        if contour.is_empty() { continue; }
+
+        for n in 0..JOIN_SAMPLES {
+            let point = contour.evaluate(n as f64 / JOIN_SAMPLES as f64);
+            let (path_contour, segment, t, distance) = match nearest_on_path(path, point) {
+                Some(nearest) => nearest,
+                None => continue
+            };
+
+            let segment_count = path[path_contour].len();
+            let corner = if t <= 1e-6 { segment } else if t >= 1. - 1e-6 { (segment + 1) % segment_count } else { continue };
+
+            if let Some(idx) = corners.get(&(path_contour, corner)) {
+                let join = &mut joins[*idx];
+                if join.extent.map_or(true, |e| distance > e) { join.extent = Some(distance); }
+            }
+        }
+    }
+
+    for join in &mut joins {
+        if half_width > 0. { join.ratio = join.extent.map(|e| e / half_width); }
+    }
+
+    return joins;
+}
+
+// How many joins fall into each of JOIN_BUCKETS, plus one more for everything over the last.
+pub fn join_histogram(joins: &[JoinRecord]) -> Vec<usize>
+{
+    let mut histogram = vec![0; JOIN_BUCKETS.len() + 1];
+
+    for ratio in joins.iter().filter_map(|j| j.ratio) {
+        let bucket = JOIN_BUCKETS.iter().position(|b| ratio < *b).unwrap_or(JOIN_BUCKETS.len());
+        histogram[bucket] = histogram[bucket] + 1;
+    }
+
+    return histogram;
+}
+
+// Where along bez the distance from the path crosses max_distance, bez has to start inside and end outside.
+fn crossing(bez: &Bezier, distance: &impl Fn(Vector) -> f64, max_distance: f64) -> f64
+{
+    let (mut inside, mut outside) = (0., 1.);
+    for _ in 0..40 {
+        let mid = (inside + outside) / 2.;
+        if distance(bez.evaluate(mid)) > max_distance { outside = mid; } else { inside = mid; }
+    }
+
+    return inside;
+}
+
+// Cuts off any on-curve point of a closed output contour that's farther than max_distance from the path, leaving
+// a short bevel where the two curves leaving it cross max_distance. Points we can't bevel, because a neighbouring
+// curve is out there the whole way, are left alone. Returns the clipped outline and how many points were clipped.
+pub fn clip_spikes(outline: &Piecewise<Piecewise<Bezier>>, path: &Piecewise<Piecewise<Bezier>>, max_distance: f64) -> (Piecewise<Piecewise<Bezier>>, usize)
+{
+    let distance = |p: Vector| nearest_on_path(path, p).map_or(0., |n| n.3);
+    let mut clipped = 0;
+    let mut contours = Vec::new();
+
+    for contour in outline.segs() {
+        let count = contour.len();
+        if count < 2 || !contour.is_closed() {
+            contours.push(contour.clone());
+            continue;
+        }
+
+        // anchor k is where curve k starts, and where curve k - 1 ends
+        let far: Vec<bool> = contour.segs().map(|c| distance(c.evaluate(0.)) > max_distance).collect();
+
+        let mut start = vec![0.; count];
+        let mut end = vec![1.; count];
+        let mut bevel = vec![false; count];
+
+        for k in 0..count {
+            let prev = (k + count - 1) % count;
+            let next = (k + 1) % count;
+            if !far[k] || far[prev] || far[next] { continue; }
+
+            let incoming = &contour[prev];
+            let outgoing = &contour[k];
+
+            // the incoming curve already runs from inside to outside, the outgoing one gets walked backwards
+            let t_in = crossing(incoming, &distance, max_distance);
+            let t_out = 1. - crossing(&outgoing.reverse(), &distance, max_distance);
+
+            if t_in <= start[prev] || t_out >= end[k] { continue; }
+
+            end[prev] = t_in;
+            start[k] = t_out;
+            bevel[k] = true;
+            clipped = clipped + 1;
+        }
+
+        let pieces: Vec<Bezier> = (0..count).map(|i| contour[i].restrict(start[i], end[i])).collect();
+
+        let mut curves = Vec::new();
+        for i in 0..count {
+            curves.push(pieces[i].clone());
+
+            let next = (i + 1) % count;
+            if bevel[next] {
+                let from = pieces[i].to_control_points()[3];
+                let to = pieces[next].to_control_points()[0];
+                curves.push(Bezier::from_control_points(from, from, to, to));
+            }
+        }
+
+        // the bevel into the first curve goes on the end so the contour keeps starting where it did
+        contours.push(Piecewise::new(curves));
+    }
+
+    return (Piecewise::new(contours), clipped);
+}
+
+// The join on the outside of a corner in a stroke width wide, from where the incoming side ends to where the
+// outgoing one starts. The outside is the right going round a counter-clockwise turn and the left going round a
+// clockwise one, a path that doubles straight back has no outside and gets its join on the left. Miters reaching
+// further than miter_limit times half the width from the corner fall back on a bevel. Nothing if the path carries
+// straight on or either tangent is zero.
+pub fn generate(kind: StrokeJoin, corner: Vector, in_tangent: Vector, out_tangent: Vector, width: f64, miter_limit: f64) -> Piecewise<Bezier>
+{
+    let half = width / 2.;
+    let (incoming, outgoing) = match (in_tangent.try_normalize(), out_tangent.try_normalize()) {
+        (Some(incoming), Some(outgoing)) if half > 0. => (incoming, outgoing),
+        _ => return Piecewise::new(Vec::new()),
+    };
+
+    let turn = incoming.cross(outgoing);
+    if turn == 0. && incoming.dot(outgoing) > 0. { return Piecewise::new(Vec::new()); }
+
+    let side = if turn > 0. { -1. } else { 1. };
+    let na = incoming.perpendicular() * side;
+    let nb = outgoing.perpendicular() * side;
+    let (end, start) = (corner + na * half, corner + nb * half);
+    let line = |from: Vector, to: Vector| Bezier::from_control_points(from, from, to, to);
+
+    let curves = match kind {
+        StrokeJoin::Bevel => vec![line(end, start)],
+        StrokeJoin::Round => arc(corner, na * half, na.angle_to(nb)),
+        StrokeJoin::Miter => {
+            // the miter sits along the bisector of the two normals, half / cos(turn / 2) out from the corner
+            let m = na + nb;
+            let length = m.magnitude();
+            if length <= 1e-9 || 2. / length > miter_limit {
+                vec![line(end, start)]
+            } else {
+                let tip = corner + m * (2. * half / (length * length));
+                vec![line(end, tip), line(tip, start)]
+            }
+        }
+    };
+
+    return Piecewise::new(curves);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stroke::{constant_width_stroke, StrokeSettings};
+
+    // A path that turns through 170 degrees, leaving 10 degrees between its sides. A miter there reaches
+    // 1 / sin(5 degrees), about 11.5 times half the width, out from the corner. Any limit under that gets a bevel,
+    // which only reaches half the width out.
+    #[test]
+    fn acute_corner_at_several_miter_limits()
+    {
+        let v = |x, y| Vector { x: x, y: y };
+        let line = |from: Vector, to: Vector| Bezier::from_control_points(from, from, to, to);
+        let tip = v(200., 0.);
+        let path = Piecewise::new(vec![Piecewise::new(vec![line(v(0., 0.), tip), line(tip, tip + v(-200., 200. * 10f64.to_radians().tan()))])]);
+        let miter = 1. / 5f64.to_radians().sin();
+
+        for limit in &[4., 11., 12., 30.] {
+            let settings = StrokeSettings { width: 40., join: StrokeJoin::Miter, miter_limit: *limit, ..StrokeSettings::default() };
+            let stroke = constant_width_stroke(&path, &settings);
+
+            let joins = measure_joins(&path, &stroke, 20.);
+            assert_eq!(joins.len(), 1);
+            assert!((joins[0].angle - 170.).abs() < 1e-6, "{:?}", joins[0]);
+            let ratio = joins[0].ratio.unwrap();
+            let want = if *limit < miter { 1. } else { miter };
+            assert!((ratio - want).abs() < 0.3, "limit {}: ratio {} rather than {}", limit, ratio, want);
+            assert_eq!(join_histogram(&joins)[if *limit < miter { 0 } else { JOIN_BUCKETS.len() }], 1);
+
+            // clipped at three times half the width only the miter's tip goes, a bevel never got that far
+            let (clipped, count) = clip_spikes(&stroke, &path, 60.);
+            assert_eq!(count, if *limit < miter { 0 } else { 1 }, "limit {}", limit);
+            let clipped_ratio = measure_joins(&path, &clipped, 20.)[0].ratio.unwrap();
+            assert!(clipped_ratio <= 3. + 1e-6, "limit {}: ratio {} after clipping", limit, clipped_ratio);
+        }
+    }
+}
@@ -0,0 +1,32 @@
+pub mod qmath;
+pub mod pattern_along_path;
+pub mod glifwriter;
+pub mod stroke_report;
+pub mod outline_stats;
+pub mod svgio;
+pub mod plistio;
+pub mod nib;
+pub mod point_names;
+pub mod auto_width;
+pub mod joins;
+pub mod anchors;
+pub mod geometry_profile;
+pub mod components;
+pub mod pipeline;
+pub mod stroke;
+pub mod skeleton;
+pub mod caps;
+pub mod batch;
+pub mod ops;
+
+#[cfg(any(feature = "ffi", feature = "wasm"))]
+pub mod settings_json;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -1,37 +1,100 @@
-mod qmath;
-mod pattern_along_path;
-mod glifwriter;
+mod reportwriter;
+mod cache;
 
-use clap::{Arg, App};
+use clap::{Arg, App, AppSettings, ArgMatches, SubCommand};
+use qstroke::{qmath, pattern_along_path, glifwriter, stroke_report, outline_stats, svgio, plistio, point_names};
+use qstroke::auto_width::TargetMeasurement;
+use qstroke::anchors::{self, AnchorMode};
+use qstroke::components;
+use qstroke::pipeline::{Pipeline, Stage, StageArtifact};
+use qstroke::nib::{NibShape, nib_stroke};
+use qstroke::stroke::{self, StrokeCap, StrokeJoin, StrokeSettings};
+use qstroke::batch;
+use qstroke::ops;
+use qmath::Evaluate;
+use qstroke::geometry_profile::{GeometryProfile, GEOMETRY_PROFILE_LIB_KEY};
 use pattern_along_path::*;
-use qmath::{Vector};
+use qmath::Piecewise;
+use stroke_report::StrokeReport;
+use svgio::SvgSpace;
+use cache::StrokeCache;
 use std::fs;
+use std::process;
+use std::time::Instant;
 
 fn main() {
-    let matches = App::new("QPaP")
+    let matches = app().get_matches();
+
+    if let Some(sub_matches) = matches.subcommand_matches("NIB") {
+        nib_main(sub_matches);
+        return;
+    }
+
+    if let Some(sub_matches) = matches.subcommand_matches("CWS") {
+        stroke_main(sub_matches, false);
+        return;
+    }
+
+    if let Some(sub_matches) = matches.subcommand_matches("VWS") {
+        stroke_main(sub_matches, true);
+        return;
+    }
+
+    if let Some(sub_matches) = matches.subcommand_matches("INTERPOLATE") {
+        interpolate_main(sub_matches);
+        return;
+    }
+
+    if let Some(sub_matches) = matches.subcommand_matches("OPS") {
+        ops_main(sub_matches);
+        return;
+    }
+
+    pattern_main(&matches);
+}
+
+// The command line, on its own so the tests can hand it arguments.
+fn app() -> App<'static, 'static>
+{
+    return App::new("QPaP")
         .version("0.0.0")
         .author("Matthew Blanchard <matthewrblanchard@gmail.com")
         .about("A utility for applying pattern-along-path to ufo files.")
+        .setting(AppSettings::SubcommandsNegateReqs)
         .arg(Arg::with_name("path")
             .long("path")
+            .alias("input")
             .takes_value(true)
-            .help("The path to the input path file.")
+            .help("The path to the input path file. This can be a .glif or an .svg file, or a .ufo to stroke every glyph in it.")
             .required(true))
         .arg(Arg::with_name("pattern")
             .long("pattern")
             .takes_value(true)
             .help("The path to the input pattern file.")
-            .required(true))
+            .required_unless_one(&["check", "stats"]))
         .arg(Arg::with_name("output")
             .long("out")
+            .alias("output")
             .takes_value(true)
-            .help("The path where the output will be saved.")
-            .required(true))
+            .help("The path where the output will be saved. This can be a .glif or an .svg file.")
+            .required_unless_one(&["check", "stats"]))
         .arg(Arg::with_name("mode")
             .short("m")
             .long("mode")
             .takes_value(true)
-            .help("<[single|repeated] (single)> set our repeat mode."))
+            .help("<[single|repeated|fixed] (single)> set our repeat mode, fixed puts down --count copies."))
+        .arg(Arg::with_name("count")
+            .long("count")
+            .takes_value(true)
+            .help("<usize (1)> how many copies to spread along the path in fixed mode."))
+        .arg(Arg::with_name("placement")
+            .long("placement")
+            .takes_value(true)
+            .help("<[warp|rigid] (warp)> bend the pattern along the path, or only move and turn each copy onto it."))
+        .arg(Arg::with_name("ends")
+            .long("ends")
+            .takes_value(true)
+            .help("<[drop|overflow|clip] (drop)> what to do with a copy that doesn't fit whole at the end of the path."))
         .arg(Arg::with_name("scale_x")
             .long("sx")
             .takes_value(true)
@@ -61,6 +124,18 @@ fn main() {
             .long("stretch")
             .takes_value(true)
             .help("<boolean (false)> whether to stretch the input pattern or not."))
+        .arg(Arg::with_name("stretch_factor")
+            .long("stretch-factor")
+            .takes_value(true)
+            .help("<f64 (1)> how much to stretch each copy along the path about its center once they're laid out."))
+        .arg(Arg::with_name("flip_alternate")
+            .long("flip-alternate")
+            .takes_value(true)
+            .help("<boolean (false)> mirror every other copy across the path."))
+        .arg(Arg::with_name("rotation")
+            .long("rotation")
+            .takes_value(true)
+            .help("<[follow|upright] (follow)> turn copies with the path, or keep them upright."))
         .arg(Arg::with_name("simplify")
             .long("simplify")
             .takes_value(true)
@@ -69,40 +144,576 @@ fn main() {
             .long("center_pattern")
             .takes_value(true)
             .help("<boolean (true)> if you want to align a pattern manually you can change this to false."))
-        .get_matches();
+        .arg(Arg::with_name("cap")
+            .long("cap")
+            .takes_value(true)
+            .help("<butt|round|square|pointed|file.glif (butt)> cap for both ends of open paths, a glif is a custom cap."))
+        .arg(Arg::with_name("start_cap")
+            .long("startcap")
+            .takes_value(true)
+            .help("<butt|round|square|pointed|file.glif (butt)> cap for the start of open paths, overrides --cap."))
+        .arg(Arg::with_name("end_cap")
+            .long("endcap")
+            .takes_value(true)
+            .help("<butt|round|square|pointed|file.glif (butt)> cap for the end of open paths, overrides --cap."))
+        .arg(Arg::with_name("snap_to_metrics")
+            .long("snap-to-metrics")
+            .takes_value(true)
+            .help("<f64> move horizontal edges within this many units of the font's vertical metrics onto them, reads the fontinfo.plist of the path glif's UFO."))
+        .arg(Arg::with_name("max_spike")
+            .long("max-spike")
+            .takes_value(true)
+            .help("<f64> bevel off any point of the output that ends up farther than this from the path."))
+        .arg(Arg::with_name("merge_seams")
+            .long("merge-seams")
+            .takes_value(true)
+            .help("<f64> when simplifying, merge the curves where caps meet the stroke into one if it stays within this many units of them."))
+        .arg(Arg::with_name("geometry_profile")
+            .long("geometry-profile")
+            .takes_value(true)
+            .help("<[v1|latest] (latest)> pin the stroker's algorithms and constants to a version so the output can be reproduced later."))
+        .arg(Arg::with_name("preserve_components")
+            .long("preserve-components")
+            .takes_value(true)
+            .help("<boolean (false)> with a .ufo path, stroke only the base glyphs and keep composites as components. Components that aren't just moved, rotated or uniformly scaled get decomposed anyway."))
+        .arg(Arg::with_name("layer")
+            .long("layer")
+            .takes_value(true)
+            .help("<name (public.default)> with a .ufo path, the layer whose glyphs get stroked."))
+        .arg(Arg::with_name("project_anchors")
+            .long("project-anchors")
+            .takes_value(true)
+            .help("<boolean (false)> move the path glif's anchors onto the nearest point of the stroked outline instead of keeping them where they are."))
+        .arg(Arg::with_name("expand")
+            .long("expand")
+            .takes_value(true)
+            .help("<[both|outward|inward] (both)> keep only the part of the stroke outside or inside of closed paths, with the path as its other edge."))
+        .arg(Arg::with_name("auto_width")
+            .long("auto-width")
+            .takes_value(true)
+            .help("<f64> pick the pattern's vertical scale so vertical stems in the output measure this wide."))
+        .arg(Arg::with_name("fill_rule")
+            .long("fill-rule")
+            .takes_value(true)
+            .help("<[nonzero|evenodd] (nonzero)> which fill rule to use when simplifying and measuring the result."))
+        .arg(Arg::with_name("round")
+            .long("round")
+            .takes_value(true)
+            .help("<[usize|off] (2)> how many decimal places to round output coordinates to."))
+        .arg(Arg::with_name("stop_after")
+            .long("stop-after")
+            .takes_value(true)
+            .help("<[prepare|corner-split|offset|repair|assemble|boolean|postprocess]> stop stroking after this stage and save what's been done to --out as a JSON artifact."))
+        .arg(Arg::with_name("resume_from")
+            .long("resume-from")
+            .takes_value(true)
+            .help("<path> pick stroking back up from an artifact saved by --stop-after. Pass the same settings as the run that saved it."))
+        .arg(Arg::with_name("report")
+            .long("report")
+            .takes_value(true)
+            .help("<path> write statistics about the run to a .csv or .json file."))
+        .arg(Arg::with_name("cache_dir")
+            .long("cache-dir")
+            .takes_value(true)
+            .help("<path> reuse output from earlier runs with the same inputs and settings, and save this one's there."))
+        .arg(Arg::with_name("measure_angle")
+            .long("measure-angle")
+            .takes_value(true)
+            .help("<f64> report how far the output extends along this angle, in degrees from the x axis."))
+        .arg(Arg::with_name("verify_offset")
+            .long("verify-offset")
+            .takes_value(true)
+            .help("<f64> report how far the output strays from sitting this far from the path."))
+        .arg(Arg::with_name("upm")
+            .long("upm")
+            .takes_value(true)
+            .help("<f64> scale svg input so the height of its viewBox maps to this many font units."))
+        .arg(Arg::with_name("svg_scale")
+            .long("svg-scale")
+            .takes_value(true)
+            .help("<f64 (1)> how many font units one svg unit is, overrides --upm."))
+        .arg(Arg::with_name("check")
+            .long("check")
+            .help("check the path for problems like NaNs, zero length segments and bad winding instead of stroking it, prints what it finds and exits non-zero if it finds anything."))
+        .arg(Arg::with_name("check_intersections")
+            .long("check-intersections")
+            .help("with --check, also look for contours that cross themselves. This is slow."))
+        .arg(Arg::with_name("expect_closed")
+            .long("expect-closed")
+            .takes_value(true)
+            .help("<boolean (false)> with --check, report open contours."))
+        .arg(Arg::with_name("stats")
+            .long("stats")
+            .help("print the path's contours' segment and point counts, winding, area, length, bounds, corners and curvature as JSON instead of stroking it."))
+        .subcommand(SubCommand::with_name("NIB")
+            .about("Sweeps a convex nib read from a glif along every contour of a path.")
+            .arg(Arg::with_name("nib")
+                .long("nib")
+                .takes_value(true)
+                .help("The path to the nib glif, it should have a single closed convex contour centered on the origin.")
+                .required(true))
+            .arg(Arg::with_name("path")
+                .long("path")
+                .alias("input")
+                .takes_value(true)
+                .help("The path to the input path glif.")
+                .required(true))
+            .arg(Arg::with_name("output")
+                .long("out")
+                .alias("output")
+                .takes_value(true)
+                .help("The path where the output will be saved.")
+                .required(true))
+            .arg(Arg::with_name("angle")
+                .long("angle")
+                .takes_value(true)
+                .help("<f64 (0)> rotate the nib counter-clockwise by this many degrees."))
+            .arg(Arg::with_name("scale")
+                .long("scale")
+                .takes_value(true)
+                .help("<f64 (1)> scale the nib by this much."))
+            .arg(Arg::with_name("simplify")
+                .long("simplify")
+                .takes_value(true)
+                .help("<boolean (false)> remove the overlaps the sweep leaves on the inside of tight bends."))
+            .arg(Arg::with_name("round")
+                .long("round")
+                .takes_value(true)
+                .help("<[usize|off] (2)> how many decimal places to round output coordinates to.")))
+        .subcommand(SubCommand::with_name("CWS")
+            .about("Strokes every contour of a path glif with a pen of constant width.")
+            .arg(Arg::with_name("path")
+                .short("i")
+                .long("path")
+                .alias("input")
+                .takes_value(true)
+                .help("The path to the input path glif."))
+            .arg(Arg::with_name("output")
+                .short("o")
+                .long("out")
+                .alias("output")
+                .takes_value(true)
+                .help("The path where the output will be saved."))
+            .arg(Arg::with_name("ufo")
+                .long("ufo")
+                .takes_value(true)
+                .help("Stroke every glyph in a layer of this UFO instead of a single glif. Glyphs with no outline are skipped."))
+            .arg(Arg::with_name("layer")
+                .long("layer")
+                .takes_value(true)
+                .help("<string (public.default)> the layer of the UFO to stroke."))
+            .arg(Arg::with_name("output_layer")
+                .long("output-layer")
+                .takes_value(true)
+                .help("The layer the stroked glyphs are written to, it's made if the UFO doesn't have it. Without it they replace the originals."))
+            .arg(Arg::with_name("width")
+                .long("width")
+                .takes_value(true)
+                .help("<f64> how wide the stroke is.")
+                .required(true))
+            .arg(Arg::with_name("cap")
+                .long("cap")
+                .takes_value(true)
+                .help("<[butt|round|square|*.glif] (butt)> the cap on the ends of open contours. A glif is a custom cap, an open contour drawn for a stroke one unit wide ending at the origin heading along +x."))
+            .arg(Arg::with_name("join")
+                .long("join")
+                .takes_value(true)
+                .help("<[miter|round|bevel] (miter)> the join at corners in the path."))
+            .arg(Arg::with_name("miter_limit")
+                .long("miter-limit")
+                .takes_value(true)
+                .help("<f64 (4)> how far a miter can reach from the corner as a multiple of half the width before it's beveled."))
+            .arg(Arg::with_name("tolerance")
+                .long("tolerance")
+                .takes_value(true)
+                .help("<f64 (0.01)> how far the sides can stray from the true offset."))
+            .arg(Arg::with_name("remove_overlap")
+                .long("remove-overlap")
+                .takes_value(true)
+                .help("<boolean (false)> remove overlaps in the output and fix its contours' directions after."))
+            .arg(Arg::with_name("add_extrema")
+                .long("add-extrema")
+                .takes_value(true)
+                .help("<boolean (false)> add points at the x and y extrema of the output's curves."))
+            .arg(Arg::with_name("round")
+                .long("round")
+                .takes_value(true)
+                .help("<[usize|off] (2)> how many decimal places to round output coordinates to.")))
+        .subcommand(SubCommand::with_name("VWS")
+            .about("Strokes every contour of a path glif with widths given at each of its points.")
+            .arg(Arg::with_name("path")
+                .short("i")
+                .long("path")
+                .alias("input")
+                .takes_value(true)
+                .help("The path to the input path glif."))
+            .arg(Arg::with_name("output")
+                .short("o")
+                .long("out")
+                .alias("output")
+                .takes_value(true)
+                .help("The path where the output will be saved."))
+            .arg(Arg::with_name("ufo")
+                .long("ufo")
+                .takes_value(true)
+                .help("Stroke every glyph in a layer of this UFO instead of a single glif. Glyphs with no outline are skipped."))
+            .arg(Arg::with_name("layer")
+                .long("layer")
+                .takes_value(true)
+                .help("<string (public.default)> the layer of the UFO to stroke."))
+            .arg(Arg::with_name("output_layer")
+                .long("output-layer")
+                .takes_value(true)
+                .help("The layer the stroked glyphs are written to, it's made if the UFO doesn't have it. Without it they replace the originals."))
+            .arg(Arg::with_name("widths")
+                .long("widths")
+                .takes_value(true)
+                .help("A JSON file with an array for each contour and a width or [left, right] pair for each of its on-curve points. Without it, and always with --ufo, the widths are read from the glif's lib under com.qstroke.pointWidths."))
+            .arg(Arg::with_name("cap")
+                .long("cap")
+                .takes_value(true)
+                .help("<[butt|round|square|*.glif] (butt)> the cap on the ends of open contours. A glif is a custom cap, an open contour drawn for a stroke one unit wide ending at the origin heading along +x."))
+            .arg(Arg::with_name("join")
+                .long("join")
+                .takes_value(true)
+                .help("<[miter|round|bevel] (miter)> the join at corners in the path."))
+            .arg(Arg::with_name("miter_limit")
+                .long("miter-limit")
+                .takes_value(true)
+                .help("<f64 (4)> how far a miter can reach from the corner as a multiple of half the width before it's beveled."))
+            .arg(Arg::with_name("tolerance")
+                .long("tolerance")
+                .takes_value(true)
+                .help("<f64 (0.01)> how far the sides can stray from the true offset."))
+            .arg(Arg::with_name("remove_overlap")
+                .long("remove-overlap")
+                .takes_value(true)
+                .help("<boolean (false)> remove overlaps in the output and fix its contours' directions after."))
+            .arg(Arg::with_name("add_extrema")
+                .long("add-extrema")
+                .takes_value(true)
+                .help("<boolean (false)> add points at the x and y extrema of the output's curves."))
+            .arg(Arg::with_name("round")
+                .long("round")
+                .takes_value(true)
+                .help("<[usize|off] (2)> how many decimal places to round output coordinates to.")))
+        .subcommand(SubCommand::with_name("INTERPOLATE")
+            .about("Interpolates between two compatible stroked glifs.")
+            .arg(Arg::with_name("first")
+                .long("first")
+                .takes_value(true)
+                .help("The path to the glif at t = 0.")
+                .required(true))
+            .arg(Arg::with_name("second")
+                .long("second")
+                .takes_value(true)
+                .help("The path to the glif at t = 1.")
+                .required(true))
+            .arg(Arg::with_name("t")
+                .short("t")
+                .takes_value(true)
+                .help("<f64> where between the two glifs the output should land.")
+                .required(true))
+            .arg(Arg::with_name("output")
+                .long("out")
+                .takes_value(true)
+                .help("The path where the output will be saved.")
+                .required(true))
+            .arg(Arg::with_name("round")
+                .long("round")
+                .takes_value(true)
+                .help("<[usize|off] (2)> how many decimal places to round output coordinates to.")))
+        .subcommand(SubCommand::with_name("OPS")
+            .about("Runs a glif through a pipeline of operations described in a JSON file, see src/ops.rs.")
+            .arg(Arg::with_name("path")
+                .long("path")
+                .takes_value(true)
+                .help("The path to the input glif.")
+                .required(true))
+            .arg(Arg::with_name("pipeline")
+                .long("pipeline")
+                .takes_value(true)
+                .help("The path to the pipeline's JSON, a list of ops like [{\"op\": \"stroke\", \"width\": 20}, {\"op\": \"round\"}].")
+                .required(true))
+            .arg(Arg::with_name("output")
+                .long("out")
+                .takes_value(true)
+                .help("The path where the output will be saved.")
+                .required(true))
+            .arg(Arg::with_name("timings")
+                .long("timings")
+                .takes_value(true)
+                .help("<boolean (false)> print how long each op took.")));
+}
 
+// Pattern along path, what we do when there's no subcommand. Takes a glif or an svg, or a UFO to do every glyph in.
+fn pattern_main(matches: &ArgMatches)
+{
     let path_string = matches.value_of("path").unwrap(); // required options shouldn't panic?
+    if matches.is_present("check") {
+        check_main(matches, path_string);
+        return;
+    }
+
+    if matches.is_present("stats") {
+        stats_main(path_string);
+        return;
+    }
+
     let pattern_string = matches.value_of("pattern").unwrap();
     let output_string = matches.value_of("output").unwrap();
 
-    let path: glifparser::Glif<Option<qmath::PointData>> = glifparser::read_ufo_glif(&fs::read_to_string(path_string)
-    .expect("Failed to read path file!"));
- 
-    let pattern: glifparser::Glif<Option<qmath::PointData>> = glifparser::read_ufo_glif(&fs::read_to_string(pattern_string)
-        .expect("Failed to read pattern file!"));
+    if is_ufo(path_string) {
+        ufo_main(matches, path_string, pattern_string, output_string);
+        return;
+    }
+    let report_string = matches.value_of("report");
+
+    let mut report = StrokeReport::new(path_string);
+
+    if let Some(angle_string) = matches.value_of("measure_angle") {
+        match angle_string.parse::<f64>() {
+            Ok(a) => report.measure_angle = Some(a),
+            Err(_e) => eprintln!("Invalid measure angle. Ignoring it.")
+        }
+    }
+
+    if let Some(offset_string) = matches.value_of("verify_offset") {
+        match offset_string.parse::<f64>() {
+            Ok(d) => report.verify_offset = Some(d),
+            Err(_e) => eprintln!("Invalid verify offset argument. Ignoring it.")
+        }
+    }
+
+    let mut upm = None;
+    if let Some(upm_string) = matches.value_of("upm") {
+        match upm_string.parse::<f64>() {
+            Ok(n) => upm = Some(n),
+            Err(_e) => eprintln!("Invalid upm argument. Ignoring it.")
+        }
+    }
+
+    let mut svg_scale = None;
+    if let Some(scale_string) = matches.value_of("svg_scale") {
+        match scale_string.parse::<f64>() {
+            Ok(n) => svg_scale = Some(n),
+            Err(_e) => eprintln!("Invalid svg scale argument. Falling back to default. (1)")
+        }
+    }
+
+    // svg input gets moved into font space right away, we keep track of how so we can move svg output back
+    let mut svg_space = SvgSpace { view_box: None, scale: svg_scale.unwrap_or(1.) };
+
+    // svg paths don't have point names
+    let mut names = Vec::new();
+    let mut anchors = Vec::new();
+
+    let path: Piecewise<Piecewise<qmath::Bezier>> = if is_svg(path_string) {
+        let document = match fs::read_to_string(path_string) {
+            Ok(s) => svgio::read_svg(&s),
+            Err(e) => fail(report, report_string, format!("Failed to read path file! {}", e))
+        };
+
+        let document = match document {
+            Ok(d) => d,
+            Err(e) => fail(report, report_string, e)
+        };
+
+        if let (None, Some(upm), Some(vb)) = (svg_scale, upm, document.view_box) {
+            if vb[3] > 0. { svg_space.scale = upm / vb[3]; }
+        }
+
+        svg_space.view_box = document.view_box;
+        report.record_input_piecewise(&document.outline);
+        svg_space.to_font_space(&document.outline)
+    } else {
+        let path_glif: glifparser::Glif<Option<qmath::PointData>> = match fs::read_to_string(path_string) {
+            Ok(s) => glifparser::read_ufo_glif(&s),
+            Err(e) => fail(report, report_string, format!("Failed to read path file! {}", e))
+        };
+
+        if !path_glif.name.is_empty() { report.name = path_glif.name.clone(); }
+
+        match &path_glif.outline {
+            Some(outline) => {
+                report.record_input(outline);
+                names = point_names::named_locations(outline);
+                anchors = path_glif.anchors.clone().unwrap_or_default();
+                Piecewise::from_outline(outline)
+            }
+            None => fail(report, report_string, String::from("The path glif has no outline!"))
+        }
+    };
+
+    let pattern: glifparser::Glif<Option<qmath::PointData>> = match fs::read_to_string(pattern_string) {
+        Ok(s) => glifparser::read_ufo_glif(&s),
+        Err(e) => fail(report, report_string, format!("Failed to read pattern file! {}", e))
+    };
+
+    if pattern.outline.is_none() {
+        fail(report, report_string, String::from("The pattern glif has no outline!"));
+    }
+
+
+    let settings = read_settings(matches, path_string);
+
+    let round = parse_round(matches);
+
+    let mut cache = open_cache(matches, path_string, output_string);
+    let cache_key = cache.as_ref().map(|c| c.key(&[&fs::read(path_string).unwrap_or_default()]));
+
+    if let Some(cached) = cache.as_mut().and_then(|c| c.fetch(cache_key.as_ref().unwrap())) {
+        if let Err(e) = fs::write(output_string, &cached) {
+            fail(report, report_string, format!("Unable to write file! {}", e));
+        }
+        report.cached = true;
+
+        if let Some(report_file) = report_string {
+            if let Err(e) = reportwriter::write_report(report_file, &[report]) {
+                eprintln!("{}", e);
+            }
+        }
+
+        return;
+    }
+
+    let mut pipeline = Pipeline::new(&settings);
+    if let Some(stage_string) = matches.value_of("stop_after") {
+        match Stage::from_name(stage_string) {
+            Some(stage) => pipeline.stop_after = stage,
+            None => eprintln!("Invalid stop after argument. Falling back to default. (postprocess)")
+        }
+    }
+
+    let start_time = Instant::now();
+    let piece_pattern = Piecewise::from_outline(pattern.outline.as_ref().unwrap());
+    let artifact = match matches.value_of("resume_from") {
+        Some(artifact_string) => match StageArtifact::read(artifact_string) {
+            Ok(artifact) => pipeline.resume(artifact),
+            Err(e) => fail(report, report_string, e)
+        },
+        None => pipeline.run(&path, &piece_pattern)
+    };
+
+    // a run that stops early writes out the artifact instead of the stroke
+    if artifact.stage != Stage::Postprocess {
+        if let Err(e) = artifact.write(output_string) {
+            fail(report, report_string, e);
+        }
+        return;
+    }
+
+    let result = match artifact.into_result() {
+        Ok(r) => r,
+        Err(e) => fail(report, report_string, e)
+    };
+    let (mut output, name_warnings, joins) = glif_from_result(result, &path, &names, &pattern);
+    report.record_joins(&joins);
+
+    for warning in name_warnings {
+        eprintln!("{}", warning);
+        report.warnings.push(warning);
+    }
+
+    // rounding has to come after simplify otherwise we could end up reintroducing the overlaps it removed
+    if let Some(decimals) = round {
+        let (quantized, quantize_report) = Piecewise::from_outline(output.outline.as_ref().unwrap()).quantize(decimals);
+
+        for warning in quantize_report.warnings {
+            eprintln!("{}", warning);
+            report.warnings.push(warning);
+        }
+
+        // the round trip through Piecewise drops point names so we put them back where they were
+        let placements = point_names::collect_point_names(output.outline.as_ref().unwrap());
+        let mut quantized_outline = quantized.to_outline();
+        point_names::apply_point_names(&mut quantized_outline, &placements);
+        output.outline = Some(quantized_outline);
+    }
+
+    if !anchors.is_empty() {
+        let mode = match matches.value_of("project_anchors") {
+            Some("true") => AnchorMode::Project,
+            Some("false") | None => AnchorMode::Fixed,
+            Some(_) => {
+                eprintln!("Invalid project anchors argument. Falling back to default. (false)");
+                AnchorMode::Fixed
+            }
+        };
+
+        let stroked = Piecewise::from_outline(output.outline.as_ref().unwrap());
+        let (placed, moved) = anchors::place_anchors(&anchors, &stroked, mode);
+        output.anchors = Some(placed);
+        report.moved_anchors = moved.into_iter().map(|(name, _, _)| name).collect();
+    }
 
+    report.duration = start_time.elapsed();
+    report.record_output(output.outline.as_ref().unwrap(), settings.fill_rule);
+    report.record_offset_error(&path, output.outline.as_ref().unwrap());
 
-    let mut settings = PatternSettings{
-        copies: PatternCopies::Single,
-        subdivide: PatternSubdivide::Off,
-        is_vertical: false,
-        normal_offset: 0.,
-        tangent_offset: 0.,
-        center_pattern: true,
-        pattern_scale: Vector{x:1., y: 1.},
-        spacing: 0.,
-        stretch: false,
-        simplify: false
+    let output_contents = if is_svg(output_string) {
+        let svg_outline = svg_space.to_svg_space(&Piecewise::from_outline(output.outline.as_ref().unwrap()));
+        svgio::write_svg(&svg_outline, svg_space.view_box, round.map(|d| d as usize))
+    } else {
+        let profile = settings.geometry_profile.resolve().name();
+        glifwriter::write_ufo_glif_with_lib(output, &[(GEOMETRY_PROFILE_LIB_KEY, profile)])
     };
 
+    if let Err(e) = fs::write(output_string, &output_contents) {
+        fail(report, report_string, format!("Unable to write file! {}", e));
+    }
+
+    if let (Some(c), Some(key)) = (&cache, &cache_key) {
+        if let Err(e) = c.store(key, &output_contents) {
+            eprintln!("{}", e);
+        }
+    }
+
+    if let Some(report_file) = report_string {
+        if let Err(e) = reportwriter::write_report(report_file, &[report]) {
+            eprintln!("{}", e);
+        }
+    }
+}
+
+// Everything in PatternSettings that comes from the command line. path_string is only used to find the font's
+// metrics for --snap-to-metrics.
+fn read_settings(matches: &ArgMatches, path_string: &str) -> PatternSettings
+{
+    let mut settings = PatternSettings::default();
+
     if let Some(copies) = matches.value_of("mode") { 
         match copies {
             "single" => settings.copies = PatternCopies::Single,
             "repeated" => settings.copies = PatternCopies::Repeated,
+            "fixed" => {
+                let count = matches.value_of("count").unwrap_or("1").parse::<usize>();
+                match count {
+                    Ok(n) => settings.copies = PatternCopies::Fixed(n),
+                    Err(_e) => eprintln!("Invalid count argument. Falling back to default mode. (Single)")
+                }
+            }
             _ => eprintln!("Invalid mode argument. Falling back to default. (Single)")
         }
     }
 
+    if let Some(placement) = matches.value_of("placement") {
+        match placement {
+            "warp" => settings.placement = PatternPlacement::Warp,
+            "rigid" => settings.placement = PatternPlacement::Rigid,
+            _ => eprintln!("Invalid placement argument. Falling back to default. (warp)")
+        }
+    }
+
+    if let Some(ends) = matches.value_of("ends") {
+        match ends {
+            "drop" => settings.ends = PatternEnds::Drop,
+            "overflow" => settings.ends = PatternEnds::Overflow,
+            "clip" => settings.ends = PatternEnds::Clip,
+            _ => eprintln!("Invalid ends argument. Falling back to default. (drop)")
+        }
+    }
+
     if let Some(sx_string) = matches.value_of("scale_x") {
         let sx = sx_string.parse::<f64>();
 
@@ -181,8 +792,1096 @@ fn main() {
         }
     }
 
+    if let Some(factor_string) = matches.value_of("stretch_factor") {
+        match factor_string.parse::<f64>() {
+            Ok(n) => settings.stretch_factor = n,
+            Err(_e) => eprintln!("Invalid stretch factor. Falling back to default. (1)")
+        }
+    }
+
+    if let Some(flip_string) = matches.value_of("flip_alternate") {
+        match flip_string {
+            "true" => settings.flip_alternate = true,
+            "false" => settings.flip_alternate = false,
+            _ => eprintln!("Invalid flip alternate argument. Falling back to default. (false)")
+        }
+    }
+
+    if let Some(rotation) = matches.value_of("rotation") {
+        match rotation {
+            "follow" => settings.rotation = PatternRotation::Follow,
+            "upright" => settings.rotation = PatternRotation::Upright,
+            _ => eprintln!("Invalid rotation argument. Falling back to default. (follow)")
+        }
+    }
+
+    if let Some(fill_rule_string) = matches.value_of("fill_rule") {
+        match fill_rule_string {
+            "nonzero" => settings.fill_rule = qmath::FillRule::NonZero,
+            "evenodd" => settings.fill_rule = qmath::FillRule::EvenOdd,
+            _ => eprintln!("Invalid fill rule argument. Falling back to default. (nonzero)")
+        }
+    }
+
+    if let Some(cap) = matches.value_of("cap").and_then(|c| parse_cap(c)) {
+        settings.set_caps(cap);
+    }
+
+    if let Some(cap) = matches.value_of("start_cap").and_then(|c| parse_cap(c)) {
+        settings.start_cap = cap;
+    }
+
+    if let Some(cap) = matches.value_of("end_cap").and_then(|c| parse_cap(c)) {
+        settings.end_cap = cap;
+    }
+
+    if let Some(expand) = matches.value_of("expand") {
+        match expand {
+            "both" => settings.expand = ExpandMode::Both,
+            "outward" => settings.expand = ExpandMode::OutwardOnly,
+            "inward" => settings.expand = ExpandMode::InwardOnly,
+            _ => eprintln!("Invalid expand argument. Falling back to default. (both)")
+        }
+    }
+
+    if let Some(spike_string) = matches.value_of("max_spike") {
+        match spike_string.parse::<f64>() {
+            Ok(n) => settings.max_spike_length = Some(n),
+            Err(_e) => eprintln!("Invalid max spike argument. Ignoring it.")
+        }
+    }
+
+    if let Some(profile) = matches.value_of("geometry_profile") {
+        match GeometryProfile::from_name(profile) {
+            Some(p) => settings.geometry_profile = p,
+            None => eprintln!("Invalid geometry profile argument. Falling back to default. (latest)")
+        }
+    }
+
+    if let Some(seam_string) = matches.value_of("merge_seams") {
+        match seam_string.parse::<f64>() {
+            Ok(n) => settings.merge_seams = Some(n),
+            Err(_e) => eprintln!("Invalid merge seams argument. Ignoring it.")
+        }
+    }
+
+    if let Some(width_string) = matches.value_of("auto_width") {
+        match width_string.parse::<f64>() {
+            Ok(n) => settings.auto_width = Some(TargetMeasurement { target: n, probes: Vec::new(), tolerance: 0.01 }),
+            Err(_e) => eprintln!("Invalid auto width argument. Ignoring it.")
+        }
+    }
+
+    if let Some(tolerance_string) = matches.value_of("snap_to_metrics") {
+        match tolerance_string.parse::<f64>() {
+            Ok(n) => match read_metric_zones(path_string, n) {
+                Ok(zones) => settings.snap_zones = zones,
+                Err(e) => eprintln!("{} Not snapping.", e)
+            },
+            Err(_e) => eprintln!("Invalid snap to metrics argument. Not snapping.")
+        }
+    }
+
+    return settings;
+}
+
+fn parse_round(matches: &ArgMatches) -> Option<u32>
+{
+    let mut round = Some(2);
+    if let Some(round_string) = matches.value_of("round") {
+        match round_string {
+            "off" => round = None,
+            _ => match round_string.parse::<u32>() {
+                Ok(n) => round = Some(n),
+                Err(_e) => eprintln!("Invalid round argument. Falling back to default. (2)")
+            }
+        }
+    }
+
+    return round;
+}
+
+fn parse_cap(cap_string: &str) -> Option<CapStyle>
+{
+    match cap_string {
+        "butt" => Some(CapStyle::Butt),
+        "round" => Some(CapStyle::Round),
+        "square" => Some(CapStyle::Square),
+        "pointed" => Some(CapStyle::Pointed),
+        _ if cap_string.ends_with(".glif") => Some(CapStyle::Custom(read_glif_outline(cap_string).1)),
+        _ => {
+            eprintln!("Invalid cap argument. Falling back to default. (butt)");
+            None
+        }
+    }
+}
+
+// The cache for --cache-dir, if it was given and we can use it. Snapping to metrics reads the font's fontinfo.plist
+// so the metrics go into the key too.
+fn open_cache(matches: &ArgMatches, path_string: &str, output_string: &str) -> Option<StrokeCache>
+{
+    let dir = matches.value_of("cache_dir")?;
+    let fontinfo = fontinfo_path(path_string).filter(|f| matches.is_present("snap_to_metrics") && f.is_file());
+    let files: Vec<&std::path::Path> = fontinfo.iter().map(|f| f.as_path()).collect();
+
+    return match StrokeCache::new(dir, matches, output_string, &files) {
+        Ok(c) => Some(c),
+        Err(e) => { eprintln!("{} Running without the cache.", e); None }
+    };
+}
+
+// The glif lives in font.ufo/glyphs/ so the font's fontinfo.plist is two directories up, unless we were handed the
+// UFO itself.
+fn fontinfo_path(glif_path: &str) -> Option<std::path::PathBuf>
+{
+    let path = std::path::Path::new(glif_path);
+    let ufo = if is_ufo(glif_path) { Some(path) } else { path.parent().and_then(|p| p.parent()) };
+    return ufo.map(|ufo| ufo.join("fontinfo.plist"));
+}
+
+// The baseline is always a zone, the rest are whichever of descender, xHeight, capHeight and ascender the font sets.
+fn read_metric_zones(glif_path: &str, tolerance: f64) -> Result<Vec<(f64, f64)>, String>
+{
+    let fontinfo = fontinfo_path(glif_path).ok_or(String::from("Couldn't find the UFO the path glif belongs to."))?;
+
+    let plist = plistio::read_plist(&fontinfo)?;
+    let dict = plist.get_child("dict").ok_or(String::from("fontinfo.plist has no dict."))?;
+    let entries = plistio::dict_entries(dict).map_err(|e| format!("{} {}", fontinfo.display(), e))?;
+
+    let mut zones = vec![(0., tolerance)];
+    for (key, value) in entries {
+        if !["descender", "xHeight", "capHeight", "ascender"].contains(&key.as_str()) { continue; }
+
+        if let Ok(position) = plistio::text(value).trim().parse::<f64>() {
+            zones.push((position, tolerance));
+        }
+    }
+
+    return Ok(zones);
+}
+
+fn read_glif_outline(filename: &str) -> (glifparser::Glif<Option<qmath::PointData>>, Piecewise<Piecewise<qmath::Bezier>>)
+{
+    let glif: glifparser::Glif<Option<qmath::PointData>> = glifparser::read_ufo_glif(&fs::read_to_string(filename)
+        .expect("Failed to read glif file!"));
+
+    let outline = match &glif.outline {
+        Some(outline) => Piecewise::from_outline(outline),
+        None => {
+            eprintln!("{} has no outline!", filename);
+            process::exit(1);
+        }
+    };
+
+    return (glif, outline);
+}
+
+// Runs validate over the path instead of stroking it.
+// The outline of a single .glif or .svg for the modes that look at one rather than stroke it, flag naming which.
+fn read_single_outline(path_string: &str, flag: &str) -> Piecewise<Piecewise<qmath::Bezier>>
+{
+    return if is_svg(path_string) {
+        let document = fs::read_to_string(path_string)
+            .map_err(|e| format!("Failed to read path file! {}", e))
+            .and_then(|s| svgio::read_svg(&s));
+
+        match document {
+            Ok(d) => d.outline,
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+    } else if is_ufo(path_string) {
+        eprintln!("{} works on a single .glif or .svg.", flag);
+        process::exit(1);
+    } else {
+        read_glif_outline(path_string).1
+    };
+}
+
+fn check_main(matches: &ArgMatches, path_string: &str)
+{
+    let outline = read_single_outline(path_string, "--check");
+
+    let mut options = qmath::ValidateOptions::default();
+    options.self_intersections = matches.is_present("check_intersections");
+    options.expect_closed = false;
+    if let Some(closed_string) = matches.value_of("expect_closed") {
+        match closed_string {
+            "true" => options.expect_closed = true,
+            "false" => options.expect_closed = false,
+            _ => eprintln!("Invalid expect closed argument. Falling back to default. (false)")
+        }
+    }
+
+    let diagnostics = qmath::validate_with(&outline, &options);
+    for diagnostic in &diagnostics {
+        println!("{}", diagnostic);
+    }
+
+    if !diagnostics.is_empty() {
+        eprintln!("Found {} problems in {}.", diagnostics.len(), path_string);
+        process::exit(1);
+    }
+}
+
+fn stats_main(path_string: &str)
+{
+    let outline = read_single_outline(path_string, "--stats");
+    println!("{}", outline_stats::outline_stats(&outline).to_json());
+}
+
+fn nib_main(matches: &ArgMatches)
+{
+    let nib_string = matches.value_of("nib").unwrap();
+    let path_string = matches.value_of("path").unwrap();
+    let output_string = matches.value_of("output").unwrap();
+
+    let mut angle = 0.;
+    if let Some(angle_string) = matches.value_of("angle") {
+        match angle_string.parse::<f64>() {
+            Ok(a) => angle = a,
+            Err(_e) => eprintln!("Invalid angle argument. Falling back to default. (0)")
+        }
+    }
+
+    let mut scale = 1.;
+    if let Some(scale_string) = matches.value_of("scale") {
+        match scale_string.parse::<f64>() {
+            Ok(s) => scale = s,
+            Err(_e) => eprintln!("Invalid scale argument. Falling back to default. (1)")
+        }
+    }
+
+    let simplify = match matches.value_of("simplify") {
+        Some("true") => true,
+        Some("false") | None => false,
+        Some(_) => {
+            eprintln!("Invalid simplify argument. Falling back to default. (false)");
+            false
+        }
+    };
+
+    let (_, nib_outline) = read_glif_outline(nib_string);
+    if nib_outline.len() != 1 {
+        eprintln!("The nib glif needs exactly one contour, it has {}.", nib_outline.len());
+        process::exit(1);
+    }
+
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let nib_contour = nib_outline[0].apply_transform(|v: &qmath::Vector| {
+        qmath::Vector { x: (v.x * cos - v.y * sin) * scale, y: (v.x * sin + v.y * cos) * scale }
+    });
+    let nib = NibShape::Custom(nib_contour);
+
+    let (path_glif, path) = read_glif_outline(path_string);
+    let mut outline = match nib_stroke(&path, &nib) {
+        Ok(o) => o,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+
+    if simplify {
+        outline = pattern_along_path::simplify(outline, qmath::FillRule::NonZero);
+    }
+
+    if let Some(decimals) = parse_round(matches) {
+        let (quantized, quantize_report) = outline.quantize(decimals);
+
+        for warning in quantize_report.warnings {
+            eprintln!("{}", warning);
+        }
+
+        outline = quantized;
+    }
+
+    let output = glifparser::Glif {
+        outline: Some(outline.to_outline()),
+        ..path_glif
+    };
+
+    let glifstring = glifwriter::write_ufo_glif(output);
+    fs::write(output_string, glifstring).expect("Unable to write file");
+}
+
+// The widths for VWS, from the sidecar file if we were given one and the glif's lib if not.
+fn read_point_widths(matches: &ArgMatches, path_string: &str) -> Result<Vec<Vec<(f64, f64)>>, String>
+{
+    if let Some(widths_string) = matches.value_of("widths") {
+        let json = fs::read_to_string(widths_string).map_err(|e| format!("Failed to read {}! {}", widths_string, e))?;
+        return stroke::point_widths_from_json(&json);
+    }
+
+    let xml = fs::read_to_string(path_string).map_err(|e| format!("Failed to read {}! {}", path_string, e))?;
+    let lib = components::read_lib(&xml)?;
+    return match lib.iter().find(|(key, _)| key == stroke::POINT_WIDTHS_LIB_KEY) {
+        Some((_, json)) => stroke::point_widths_from_json(json),
+        None => Err(format!("{} has no {} in its lib, pass the widths with --widths.", path_string, stroke::POINT_WIDTHS_LIB_KEY))
+    };
+}
+
+fn read_stroke_settings(matches: &ArgMatches) -> StrokeSettings
+{
+    let mut settings = StrokeSettings::default();
+
+    if let Some(width_string) = matches.value_of("width") {
+        match width_string.parse::<f64>() {
+            Ok(w) if w > 0. => settings.width = w,
+            _ => {
+                eprintln!("Invalid width argument, it has to be a number more than zero.");
+                process::exit(1);
+            }
+        }
+    }
+
+    match matches.value_of("cap") {
+        Some("butt") | None => {}
+        Some("round") => settings.cap = StrokeCap::Round,
+        Some("square") => settings.cap = StrokeCap::Square,
+        Some(cap_string) if cap_string.ends_with(".glif") => {
+            let (_, cap) = read_glif_outline(cap_string);
+            if cap.len() != 1 || cap[0].is_closed() {
+                eprintln!("The cap glif needs exactly one open contour.");
+                process::exit(1);
+            }
+            settings.cap = StrokeCap::Custom(cap[0].clone());
+        }
+        Some(_) => eprintln!("Invalid cap argument. Falling back to default. (butt)")
+    }
+
+    match matches.value_of("join") {
+        Some("miter") | None => {}
+        Some("round") => settings.join = StrokeJoin::Round,
+        Some("bevel") => settings.join = StrokeJoin::Bevel,
+        Some(_) => eprintln!("Invalid join argument. Falling back to default. (miter)")
+    }
+
+    if let Some(limit_string) = matches.value_of("miter_limit") {
+        match limit_string.parse::<f64>() {
+            Ok(l) => settings.miter_limit = l,
+            Err(_e) => eprintln!("Invalid miter limit argument. Falling back to default. (4)")
+        }
+    }
+
+    if let Some(tolerance_string) = matches.value_of("tolerance") {
+        match tolerance_string.parse::<f64>() {
+            Ok(t) if t > 0. => settings.tolerance = t,
+            _ => eprintln!("Invalid tolerance argument. Falling back to default. (0.01)")
+        }
+    }
+
+    return settings;
+}
+
+// extrema nearer than this to a point that's already there don't get one of their own, in font units
+const EXTREMA_TOLERANCE: f64 = 1.;
+
+// Everything CWS and VWS do to an outline once they've read it: stroke it, tidy it up if asked and round it. widths
+// are only used by VWS. closed is whether each contour was drawn closed, see constant_width_stroke_with_closed.
+fn stroke_outline(path: &Piecewise<Piecewise<qmath::Bezier>>, closed: &[bool], widths: Option<&[Vec<(f64, f64)>]>, settings: &StrokeSettings, remove_overlap: bool, add_extrema: bool, round: Option<u32>) -> Result<Piecewise<Piecewise<qmath::Bezier>>, String>
+{
+    if path.segs().all(|contour| contour.is_empty()) {
+        return Err(String::from("There are no contours to stroke."));
+    }
+
+    let mut outline = match widths {
+        Some(widths) => stroke::point_width_stroke_with_closed(path, closed, widths, settings)?,
+        None => stroke::constant_width_stroke_with_closed(path, closed, settings),
+    };
+
+    if outline.is_empty() {
+        return Err(String::from("Nothing came out of the stroke, none of the contours have any length."));
+    }
+
+    if remove_overlap {
+        outline = pattern_along_path::simplify(outline, qmath::FillRule::NonZero).fix_winding();
+    }
+
+    if add_extrema {
+        outline = outline.insert_extrema_points(EXTREMA_TOLERANCE);
+    }
+
+    if let Some(decimals) = round {
+        let (quantized, quantize_report) = outline.quantize(decimals);
+
+        for warning in quantize_report.warnings {
+            eprintln!("{}", warning);
+        }
+
+        outline = quantized;
+    }
+
+    return Ok(outline);
+}
+
+// CWS and VWS, which only differ in where the widths come from. With --ufo every glyph in a layer gets stroked,
+// see batch.rs, otherwise it's the one glif.
+fn stroke_main(matches: &ArgMatches, variable: bool)
+{
+    let settings = read_stroke_settings(matches);
+    let round = parse_round(matches);
+
+    let remove_overlap = match matches.value_of("remove_overlap") {
+        Some("true") => true,
+        Some("false") | None => false,
+        Some(_) => {
+            eprintln!("Invalid remove overlap argument. Falling back to default. (false)");
+            false
+        }
+    };
+
+    let add_extrema = match matches.value_of("add_extrema") {
+        Some("true") => true,
+        Some("false") | None => false,
+        Some(_) => {
+            eprintln!("Invalid add extrema argument. Falling back to default. (false)");
+            false
+        }
+    };
+
+    if let Some(ufo_string) = matches.value_of("ufo") {
+        let layer = matches.value_of("layer").unwrap_or(batch::DEFAULT_LAYER);
+        let report = batch::stroke_layer(std::path::Path::new(ufo_string), layer, matches.value_of("output_layer"), |path, closed, lib| {
+            let widths = if variable {
+                match lib.iter().find(|(key, _)| key == stroke::POINT_WIDTHS_LIB_KEY) {
+                    Some((_, json)) => Some(stroke::point_widths_from_json(json)?),
+                    None => return Err(format!("There's no {} in the glyph's lib.", stroke::POINT_WIDTHS_LIB_KEY)),
+                }
+            } else {
+                None
+            };
+            stroke_outline(path, closed, widths.as_deref(), &settings, remove_overlap, add_extrema, round)
+        });
+
+        match report {
+            Ok(report) => {
+                for line in report.summary() {
+                    eprintln!("{}", line);
+                }
+                if report.failed() > 0 { process::exit(1); }
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let (path_string, output_string) = match (matches.value_of("path"), matches.value_of("output")) {
+        (Some(path), Some(output)) => (path, output),
+        _ => {
+            eprintln!("Either --ufo or both --path and --out are needed.");
+            process::exit(1);
+        }
+    };
+
+    let (path_glif, path) = read_glif_outline(path_string);
+    // contours starting with a move were drawn open and get caps, even where their ends meet
+    let (_, closed) = Piecewise::from_outline_with_closed(path_glif.outline.as_ref().unwrap());
+    let widths = if variable {
+        match read_point_widths(matches, path_string) {
+            Ok(widths) => Some(widths),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+
+    let outline = match stroke_outline(&path, &closed, widths.as_deref(), &settings, remove_overlap, add_extrema, round) {
+        Ok(o) => o,
+        Err(e) => {
+            eprintln!("{}: {}", path_string, e);
+            process::exit(1);
+        }
+    };
+
+    let output = glifparser::Glif {
+        outline: Some(outline.to_outline()),
+        ..path_glif
+    };
+
+    let glifstring = glifwriter::write_ufo_glif(output);
+    fs::write(output_string, glifstring).expect("Unable to write file");
+}
+
+fn interpolate_main(matches: &ArgMatches)
+{
+    let first_string = matches.value_of("first").unwrap();
+    let second_string = matches.value_of("second").unwrap();
+    let output_string = matches.value_of("output").unwrap();
+
+    let t = match matches.value_of("t").unwrap().parse::<f64>() {
+        Ok(n) => n,
+        Err(_e) => {
+            eprintln!("Invalid t argument. It needs to be a number.");
+            process::exit(1);
+        }
+    };
+
+    let (first_glif, first) = read_glif_outline(first_string);
+    let (second_glif, second) = read_glif_outline(second_string);
+
+    let mut interpolated = match qmath::interpolate_strokes(&first, &second, t) {
+        Ok(outline) => outline,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+
+    if let Some(decimals) = parse_round(matches) {
+        let (quantized, quantize_report) = interpolated.quantize(decimals);
+
+        for warning in quantize_report.warnings {
+            eprintln!("{}", warning);
+        }
+
+        interpolated = quantized;
+    }
+
+    // anchors that exist in both glifs get interpolated too, anything else is taken from the first glif
+    let anchors = first_glif.anchors.clone().map(|anchors| {
+        anchors.into_iter().map(|mut anchor| {
+            let other = second_glif.anchors.as_ref().and_then(|a| a.iter().find(|o| o.class == anchor.class));
+            if let Some(other) = other {
+                anchor.x = anchor.x + (other.x - anchor.x) * t as f32;
+                anchor.y = anchor.y + (other.y - anchor.y) * t as f32;
+            }
+            anchor
+        }).collect()
+    });
+
+    let width = first_glif.width as f64 + (second_glif.width as f64 - first_glif.width as f64) * t;
+
+    let output = glifparser::Glif {
+        outline: Some(interpolated.to_outline()),
+        anchors: anchors,
+        width: f64::max(width.round(), 0.) as u64,
+        ..first_glif
+    };
+
+    let glifstring = glifwriter::write_ufo_glif(output);
+    fs::write(output_string, glifstring).expect("Unable to write file");
+}
+
+fn ops_main(matches: &ArgMatches)
+{
+    let path_string = matches.value_of("path").unwrap();
+    let pipeline_string = matches.value_of("pipeline").unwrap();
+    let output_string = matches.value_of("output").unwrap();
+
+    let timings = match matches.value_of("timings") {
+        Some("true") => true,
+        Some("false") | None => false,
+        Some(_) => {
+            eprintln!("Invalid timings argument. Falling back to default. (false)");
+            false
+        }
+    };
+
+    let pipeline = match fs::read_to_string(pipeline_string).map_err(|e| e.to_string()).and_then(|json| ops::Pipeline::from_json(&json)) {
+        Ok(pipeline) => pipeline,
+        Err(e) => {
+            eprintln!("{}: {}", pipeline_string, e);
+            process::exit(1);
+        }
+    };
+
+    let (path_glif, path) = read_glif_outline(path_string);
+    let outline = match pipeline.run(&path) {
+        Ok((outline, op_timings)) => {
+            if timings {
+                for (name, duration) in op_timings {
+                    eprintln!("{}: {:?}", name, duration);
+                }
+            }
+            outline
+        }
+        Err(e) => {
+            eprintln!("{}: {}", path_string, e);
+            process::exit(1);
+        }
+    };
+
+    let output = glifparser::Glif {
+        outline: Some(outline.to_outline()),
+        ..path_glif
+    };
 
-    let output = pattern_along_glif(&path, &pattern, &settings);
     let glifstring = glifwriter::write_ufo_glif(output);
     fs::write(output_string, glifstring).expect("Unable to write file");
 }
+
+fn is_svg(filename: &str) -> bool
+{
+    return filename.to_lowercase().ends_with(".svg");
+}
+
+fn is_ufo(filename: &str) -> bool
+{
+    return filename.trim_end_matches('/').to_lowercase().ends_with(".ufo");
+}
+
+// Copies everything in from into to, directories and all.
+fn copy_dir(from: &std::path::Path, to: &std::path::Path) -> std::io::Result<()>
+{
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+
+    return Ok(());
+}
+
+// Strokes every glyph in a layer of the UFO at path_string into a copy of it at output_string. Glyph files keep
+// their names, everything that isn't one of the layer's glyphs gets copied over untouched.
+fn ufo_main(matches: &ArgMatches, path_string: &str, pattern_string: &str, output_string: &str)
+{
+    let input = std::path::Path::new(path_string);
+    let output = std::path::Path::new(output_string);
+
+    let pattern: glifparser::Glif<Option<qmath::PointData>> = match fs::read_to_string(pattern_string) {
+        Ok(s) => glifparser::read_ufo_glif(&s),
+        Err(e) => { eprintln!("Failed to read pattern file! {}", e); process::exit(1); }
+    };
+
+    if pattern.outline.is_none() {
+        eprintln!("The pattern glif has no outline!");
+        process::exit(1);
+    }
+
+    let settings = read_settings(matches, path_string);
+    let round = parse_round(matches);
+
+    let preserve = match matches.value_of("preserve_components") {
+        Some("true") => true,
+        Some("false") | None => false,
+        Some(_) => {
+            eprintln!("Invalid preserve components argument. Falling back to default. (false)");
+            false
+        }
+    };
+
+    // the layer's glyphs and their files come from its contents.plist, see batch.rs
+    let layer = matches.value_of("layer").unwrap_or(batch::DEFAULT_LAYER);
+    let (directory, contents) = match batch::layer_directory(input, layer).and_then(|d| Ok((batch::read_layer_contents(&d)?, d))) {
+        Ok((contents, directory)) => (directory, contents),
+        Err(e) => { eprintln!("{}", e); process::exit(1); }
+    };
+
+    // glyph name to the file it came from and what was in it
+    let mut files = std::collections::BTreeMap::new();
+    let mut sources = std::collections::BTreeMap::new();
+    let mut glyphs = std::collections::BTreeMap::new();
+
+    for (name, file) in contents {
+        let xml = match fs::read_to_string(directory.join(&file)) {
+            Ok(xml) => xml,
+            Err(e) => { eprintln!("Skipping {}. {}", name, e); continue; }
+        };
+        let glyph = match components::read_glyph(&xml) {
+            Ok(g) => g,
+            Err(e) => { eprintln!("Skipping {}. {}", name, e); continue; }
+        };
+
+        files.insert(name.clone(), file);
+        sources.insert(name.clone(), xml);
+        glyphs.insert(name, glyph);
+    }
+
+    let order = match components::component_order(&glyphs) {
+        Ok(o) => o,
+        Err(e) => { eprintln!("{}", e); process::exit(1); }
+    };
+
+    if input != output {
+        if let Err(e) = copy_dir(input, output) {
+            eprintln!("Unable to copy the UFO! {}", e);
+            process::exit(1);
+        }
+    }
+
+    // the same layer in the copy
+    let output_directory = output.join(directory.strip_prefix(input).unwrap());
+    let profile = settings.geometry_profile.resolve().name();
+    let mut cache = open_cache(matches, path_string, output_string);
+    let mut reports = std::collections::BTreeMap::new();
+
+    for name in order {
+        // components aren't counted as input, only the glyph's own contours
+        let mut report = StrokeReport::new(&name);
+        if let Some(outline) = &glyphs[&name].glif.outline {
+            report.record_input(outline);
+        }
+
+        // a glyph's stroke changes with any of the glyphs it's built from, so they're all in its key
+        let key = cache.as_ref().map(|c| {
+            let dependencies = components::dependencies(&name, &glyphs);
+            let inputs: Vec<&[u8]> = dependencies.iter().map(|d| sources[d].as_bytes()).collect();
+            c.key(&inputs)
+        });
+        let cached = match (&mut cache, &key) {
+            (Some(c), Some(k)) => c.fetch(k),
+            _ => None
+        };
+
+        let contents = match cached {
+            Some(contents) => {
+                report.cached = true;
+                contents
+            }
+            None => {
+                let mut glyph = components::stroke_glyph(&name, &glyphs, &pattern, &settings, preserve);
+                for warning in &glyph.warnings {
+                    eprintln!("{}", warning);
+                }
+                report.warnings = glyph.warnings.clone();
+
+                if let (Some(decimals), Some(outline)) = (round, &glyph.glif.outline) {
+                    glyph.glif.outline = Some(Piecewise::from_outline(outline).quantize(decimals).0.to_outline_preserving(outline));
+                }
+
+                if let Some(outline) = &glyph.glif.outline {
+                    report.record_output(outline, settings.fill_rule);
+                }
+
+                let contents = glifwriter::write_ufo_glif_with_components(glyph.glif, &glyph.components, &[(GEOMETRY_PROFILE_LIB_KEY, profile)]);
+                if let (Some(c), Some(k)) = (&cache, &key) {
+                    if let Err(e) = c.store(k, &contents) {
+                        eprintln!("{}", e);
+                    }
+                }
+                contents
+            }
+        };
+
+        if let Err(e) = fs::write(output_directory.join(&files[&name]), contents) {
+            eprintln!("Unable to write {}! {}", name, e);
+            report.error = Some(format!("Unable to write file! {}", e));
+        }
+        reports.insert(name, report);
+    }
+
+    if let Some(c) = &cache {
+        eprintln!("{}", c.summary());
+    }
+
+    // one row per glyph, in name order
+    if let Some(report_file) = matches.value_of("report") {
+        let reports: Vec<StrokeReport> = reports.into_iter().map(|(_, report)| report).collect();
+        if let Err(e) = reportwriter::write_report(report_file, &reports) {
+            eprintln!("{}", e);
+        }
+    }
+}
+
+// We still want a report when something goes wrong so the error ends up in it before we bail.
+fn fail(mut report: StrokeReport, report_string: Option<&str>, error: String) -> !
+{
+    eprintln!("{}", error);
+    report.error = Some(error);
+
+    if let Some(report_file) = report_string {
+        if let Err(e) = reportwriter::write_report(report_file, &[report]) {
+            eprintln!("{}", e);
+        }
+    }
+
+    process::exit(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    // fontinfo.plist's metrics in the order it has them, after the baseline.
+    #[test]
+    fn metric_zones_from_fontinfo()
+    {
+        let glif = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/ufo/components.ufo/glyphs/a.glif");
+        assert_eq!(read_metric_zones(glif.to_str().unwrap(), 2.).unwrap(), vec![(0., 2.), (500., 2.), (750., 2.), (-250., 2.)]);
+    }
+
+    // The cached column of a one row report.
+    fn was_cached(report: &Path) -> bool
+    {
+        let rows = reportwriter::tests::parse_csv(&fs::read_to_string(report).unwrap());
+        let column = rows[0].iter().position(|c| c == "cached").unwrap();
+        return rows[1][column] == "true";
+    }
+
+    // Running the same glif twice with a cache directory strokes it once, the second run copies what the first
+    // stored and comes out the same.
+    #[test]
+    fn second_run_comes_from_the_cache()
+    {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        let scratch = std::env::temp_dir().join(format!("qstroke-cache-test-{}", process::id()));
+        let _ = fs::remove_dir_all(&scratch);
+        fs::create_dir_all(&scratch).unwrap();
+
+        let path = fixtures.join("skeletons").join("s_curve.glif");
+        let pattern = fixtures.join("pattern.glif");
+        let output = scratch.join("s_curve.glif");
+        let report = scratch.join("report.csv");
+        let cache_dir = scratch.join("cache");
+        let args = ["QPaP", "--path", path.to_str().unwrap(), "--pattern", pattern.to_str().unwrap(),
+            "--out", output.to_str().unwrap(), "--cache-dir", cache_dir.to_str().unwrap(), "--report", report.to_str().unwrap()];
+
+        let run = || {
+            pattern_main(&app().get_matches_from(args.iter()));
+            let contents = fs::read_to_string(&output).unwrap();
+            fs::remove_file(&output).unwrap();
+            return (contents, was_cached(&report));
+        };
+
+        let (first, first_cached) = run();
+        let (second, second_cached) = run();
+        assert!(!first_cached && second_cached);
+        assert_eq!(first, second);
+
+        fs::remove_dir_all(&scratch).unwrap();
+    }
+
+    // A UFO run twice with a cache directory strokes nothing the second time. Changing dieresis then restrokes it
+    // and adieresis, which is built from it, and nothing else.
+    #[test]
+    fn ufo_cache_follows_components()
+    {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        let scratch = std::env::temp_dir().join(format!("qstroke-ufo-cache-test-{}", process::id()));
+        let _ = fs::remove_dir_all(&scratch);
+        let ufo = scratch.join("input.ufo");
+        copy_dir(&fixtures.join("ufo").join("components.ufo"), &ufo).unwrap();
+
+        let pattern = fixtures.join("pattern.glif");
+        let output = scratch.join("output.ufo");
+        let report = scratch.join("report.csv");
+        let cache_dir = scratch.join("cache");
+        let args = ["QPaP", "--path", ufo.to_str().unwrap(), "--pattern", pattern.to_str().unwrap(), "--out", output.to_str().unwrap(),
+            "--cache-dir", cache_dir.to_str().unwrap(), "--report", report.to_str().unwrap()];
+
+        let run = || {
+            pattern_main(&app().get_matches_from(args.iter()));
+            let rows = reportwriter::tests::parse_csv(&fs::read_to_string(&report).unwrap());
+            let column = rows[0].iter().position(|c| c == "cached").unwrap();
+            let cached: Vec<(String, bool)> = rows[1..].iter().map(|row| (row[0].clone(), row[column] == "true")).collect();
+            let adieresis = fs::read_to_string(output.join("glyphs").join("adieresis.glif")).unwrap();
+            return (cached, adieresis);
+        };
+
+        let (first, adieresis) = run();
+        assert!(first.iter().all(|(_, cached)| !cached), "{:?}", first);
+        let (second, cached_adieresis) = run();
+        assert!(second.iter().all(|(_, cached)| *cached), "{:?}", second);
+        assert_eq!(adieresis, cached_adieresis);
+
+        let dieresis = ufo.join("glyphs").join("dieresis.glif");
+        fs::write(&dieresis, fs::read_to_string(&dieresis).unwrap().replace("y=\"660\"", "y=\"700\"")).unwrap();
+        let (third, _) = run();
+        let restroked: Vec<&str> = third.iter().filter(|(_, cached)| !cached).map(|(name, _)| name.as_str()).collect();
+        assert_eq!(restroked, ["adieresis", "dieresis"]);
+
+        fs::remove_dir_all(&scratch).unwrap();
+    }
+
+    // The components fixture stroked with and without --preserve-components. Kept, adieresis still points at the
+    // stroked a and dieresis, while a.oblique's skewed a gets decomposed and stroked in place. Without it every glyph
+    // comes out as plain contours.
+    #[test]
+    fn ufo_preserves_components()
+    {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        let scratch = std::env::temp_dir().join(format!("qstroke-components-test-{}", process::id()));
+        let _ = fs::remove_dir_all(&scratch);
+
+        let ufo = fixtures.join("ufo").join("components.ufo");
+        let pattern = fixtures.join("pattern.glif");
+        let stroke = |preserve: &str| {
+            let output = scratch.join(format!("preserve_{}.ufo", preserve));
+            let args = ["QPaP", "--path", ufo.to_str().unwrap(), "--pattern", pattern.to_str().unwrap(),
+                "--out", output.to_str().unwrap(), "--preserve-components", preserve, "--layer", "public.default"];
+            pattern_main(&app().get_matches_from(args.iter()));
+
+            return move |name: &str| {
+                let glyph = components::read_glyph(&fs::read_to_string(output.join("glyphs").join(format!("{}.glif", name))).unwrap()).unwrap();
+                let bases: Vec<String> = glyph.components.iter().map(|c| c.base.clone()).collect();
+                return (glyph.glif.outline.map_or(0, |o| o.len()), bases);
+            };
+        };
+
+        let kept = stroke("true");
+        assert_eq!(kept("adieresis"), (0, vec![String::from("a"), String::from("dieresis")]));
+        assert_eq!(kept("a.oblique").1, Vec::<String>::new());
+        assert_eq!(kept("a.oblique").0, kept("a").0);
+        assert!(kept("a").0 > 0);
+
+        let decomposed = stroke("false");
+        for name in &["a", "a.oblique", "adieresis", "dieresis"] {
+            assert_eq!(decomposed(name).1, Vec::<String>::new(), "{}", name);
+        }
+        assert_eq!(decomposed("adieresis").0, decomposed("a").0 + decomposed("dieresis").0);
+        assert_eq!(decomposed("a"), kept("a"));
+
+        fs::remove_dir_all(&scratch).unwrap();
+    }
+
+    // Stroking a whole UFO with --report writes a row for every glyph. Input is only the glyph's own contours.
+    #[test]
+    fn ufo_report_has_a_row_per_glyph()
+    {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        let scratch = std::env::temp_dir().join(format!("qstroke-report-test-{}", process::id()));
+        let _ = fs::remove_dir_all(&scratch);
+        fs::create_dir_all(&scratch).unwrap();
+
+        let ufo = fixtures.join("ufo").join("components.ufo");
+        let pattern = fixtures.join("pattern.glif");
+        let output = scratch.join("components.ufo");
+        let report = scratch.join("report.csv");
+        let args = ["QPaP", "--path", ufo.to_str().unwrap(), "--pattern", pattern.to_str().unwrap(),
+            "--out", output.to_str().unwrap(), "--report", report.to_str().unwrap()];
+        pattern_main(&app().get_matches_from(args.iter()));
+
+        let rows = reportwriter::tests::parse_csv(&fs::read_to_string(&report).unwrap());
+        let column = |name: &str| rows[0].iter().position(|c| c == name).unwrap();
+        assert!(rows.iter().all(|row| row.len() == rows[0].len()));
+
+        let names: Vec<&str> = rows[1..].iter().map(|row| row[column("name")].as_str()).collect();
+        assert_eq!(names, ["a", "a.oblique", "adieresis", "dieresis"]);
+
+        // dieresis is two open lines, a.oblique nothing but a component
+        assert_eq!(rows[4][column("input_contours")], "2");
+        assert_eq!(rows[4][column("input_points")], "4");
+        assert_eq!(rows[2][column("input_contours")], "0");
+
+        // the output columns describe what got written
+        let files = ["a.glif", "a.oblique.glif", "adieresis.glif", "dieresis.glif"];
+        for (row, file) in rows[1..].iter().zip(files.iter()) {
+            let written = fs::read_to_string(output.join("glyphs").join(file)).unwrap();
+            assert_eq!(row[column("output_contours")], written.matches("<contour").count().to_string(), "{:?}", row);
+            assert_eq!(row[column("error")], "");
+        }
+
+        fs::remove_dir_all(&scratch).unwrap();
+    }
+
+    // open_loop is a triangle drawn from a move, its last point landing back on its first. Stroked from a UFO by
+    // either pen it stays open: one contour round the outside with caps at the ends, rather than the two rings a
+    // closed triangle strokes into.
+    #[test]
+    fn ufo_strokes_open_contours_open()
+    {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        let scratch = std::env::temp_dir().join(format!("qstroke-open-test-{}", process::id()));
+        let _ = fs::remove_dir_all(&scratch);
+
+        // VWS takes its widths from the glyph's lib, the fixture has them at 20 all the way round
+        for (pen, args) in &[("CWS", vec!["--width", "20"]), ("VWS", vec![])] {
+            let ufo = scratch.join(format!("{}.ufo", pen));
+            copy_dir(&fixtures.join("ufo").join("open.ufo"), &ufo).unwrap();
+            let matches = app().get_matches_from(["QPaP", pen, "--ufo", ufo.to_str().unwrap()].iter().chain(args.iter()));
+            stroke_main(matches.subcommand_matches(pen).unwrap(), *pen == "VWS");
+
+            let written = fs::read_to_string(ufo.join("glyphs").join("open_loop.glif")).unwrap();
+            assert_eq!(written.matches("<contour").count(), 1, "{}", pen);
+        }
+
+        fs::remove_dir_all(&scratch).unwrap();
+    }
+
+    // A rectangular nib swept along a curve with no tight bends covers the nib plus, along every stretch of the
+    // path, the nib's width across it: 40 for every unit the path rises and 10 for every unit it runs. Turning the
+    // nib a quarter swaps those.
+    #[test]
+    fn rectangle_nib_along_a_curve()
+    {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        let scratch = std::env::temp_dir().join(format!("qstroke-nib-test-{}", process::id()));
+        let _ = fs::remove_dir_all(&scratch);
+        fs::create_dir_all(&scratch).unwrap();
+
+        let path = fixtures.join("skeletons").join("s_curve.glif");
+        let nib = fixtures.join("nib.glif");
+        let output = scratch.join("s_curve.glif");
+        let (_, skeleton) = read_glif_outline(path.to_str().unwrap());
+        let (mut rise, mut run) = (0., 0.);
+        for bez in skeleton[0].segs() {
+            for i in 0..1000 {
+                let (a, b) = (bez.evaluate(i as f64 / 1000.), bez.evaluate((i + 1) as f64 / 1000.));
+                rise += (b.y - a.y).abs();
+                run += (b.x - a.x).abs();
+            }
+        }
+
+        for (angle, want) in &[("0", 400. + 40. * rise + 10. * run), ("90", 400. + 10. * rise + 40. * run)] {
+            let args = ["QPaP", "NIB", "--nib", nib.to_str().unwrap(), "--path", path.to_str().unwrap(),
+                "--out", output.to_str().unwrap(), "--angle", angle, "--round", "off"];
+            nib_main(app().get_matches_from(args.iter()).subcommand_matches("NIB").unwrap());
+
+            let (_, stroke) = read_glif_outline(output.to_str().unwrap());
+            assert_eq!(stroke.len(), 1);
+            let area = stroke.area();
+            assert!((area - want).abs() <= 1e-3 * want, "at {} degrees {} rather than {}", angle, area, want);
+        }
+
+        fs::remove_dir_all(&scratch).unwrap();
+    }
+
+    // An SVG of two transformed paths stroked back out to SVG matches stroking it to a glif and carrying that
+    // back over into SVG space. --upm 200 on a 100 high viewBox makes every SVG unit two font units.
+    #[test]
+    fn svg_round_trip()
+    {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        let scratch = std::env::temp_dir().join(format!("qstroke-svg-test-{}", process::id()));
+        let _ = fs::remove_dir_all(&scratch);
+        fs::create_dir_all(&scratch).unwrap();
+
+        let input = scratch.join("two_paths.svg");
+        fs::write(&input, r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 200 100">
+  <path transform="translate(50 30)" d="M0 0L100 0"/>
+  <g transform="translate(150 0)">
+    <path transform="scale(0.5)" d="M0 0L0 100"/>
+  </g>
+</svg>"#).unwrap();
+
+        let pattern = fixtures.join("pattern.glif");
+        let stroke_to = |output: &Path| {
+            let args = ["QPaP", "--path", input.to_str().unwrap(), "--pattern", pattern.to_str().unwrap(),
+                "--out", output.to_str().unwrap(), "--upm", "200", "--mode", "repeated"];
+            pattern_main(&app().get_matches_from(args.iter()));
+            return fs::read_to_string(output).unwrap();
+        };
+
+        let svg = svgio::read_svg(&stroke_to(&scratch.join("out.svg"))).unwrap();
+        assert_eq!(svg.view_box, Some([0., 0., 200., 100.]));
+        assert!(!svg.outline.is_empty());
+
+        let glif: glifparser::Glif<Option<qmath::PointData>> = glifparser::read_ufo_glif(&stroke_to(&scratch.join("out.glif")));
+        let space = SvgSpace { view_box: svg.view_box, scale: 2. };
+        let font = Piecewise::from_outline(glif.outline.as_ref().unwrap());
+        assert!(space.to_svg_space(&font).approx_eq(&svg.outline, 1e-6));
+
+        // the stroke sits on the paths, in font space the horizontal one runs along y = 140
+        let bounds = font.bounds();
+        assert!(bounds.left < 100. + 1e-6 && bounds.right > 300. - 1e-6, "{:?}", bounds);
+        assert!(bounds.bottom < 140. && bounds.top > 140., "{:?}", bounds);
+
+        fs::remove_dir_all(&scratch).unwrap();
+    }
+}
@@ -0,0 +1,502 @@
+// Nib shapes for calligraphic stroking. A nib is a convex shape dragged along the path, the stroke is the area it
+// sweeps. All the shapes here are centered on the origin, angles are in radians counter-clockwise from the x axis.
+use crate::qmath::*;
+
+pub enum NibShape {
+    Circle(f64),
+    Ellipse(f64, f64, f64), // x radius, y radius, angle
+    Rectangle(f64, f64, f64), // width, height, angle
+    Custom(Piecewise<Bezier>),
+}
+
+#[derive(Debug)]
+pub enum NibError {
+    Empty,
+    NotClosed,
+    // the points where the outline turns the wrong way, empty when it turns the right way but goes round more than once
+    NotConvex(Vec<Vector>),
+}
+
+impl std::fmt::Display for NibError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        match self {
+            NibError::Empty => write!(f, "The nib has no curves."),
+            NibError::NotClosed => write!(f, "The nib's contour isn't closed."),
+            NibError::NotConvex(points) if points.is_empty() => write!(f, "The nib isn't convex, its outline goes around more than once."),
+            NibError::NotConvex(points) => {
+                let listed: Vec<String> = points.iter().map(|p| format!("({}, {})", (p.x * 100.).round() / 100., (p.y * 100.).round() / 100.)).collect();
+                write!(f, "The nib isn't convex, its outline bends the wrong way at {}.", listed.join(", "))
+            }
+        }
+    }
+}
+
+fn line(from: Vector, to: Vector) -> Bezier
+{
+    return Bezier::from_control_points(from, from, to, to);
+}
+
+// An ellipse centered on the origin, counter-clockwise starting on the end of its x radius.
+fn ellipse_contour(rx: f64, ry: f64, angle: f64) -> Piecewise<Bezier>
+{
+    return Piecewise::ellipse(Vector { x: 0., y: 0. }, rx, ry, angle);
+}
+
+impl NibShape {
+    // The nib's outline as a counter-clockwise contour. Custom nibs are checked for convexity first.
+    pub fn to_contour(&self) -> Result<Piecewise<Bezier>, NibError>
+    {
+        match self {
+            NibShape::Circle(r) => Ok(ellipse_contour(*r, *r, 0.)),
+            NibShape::Ellipse(rx, ry, angle) => Ok(ellipse_contour(*rx, *ry, *angle)),
+            NibShape::Rectangle(w, h, angle) => {
+                let corners: Vec<Vector> = [(1., 1.), (-1., 1.), (-1., -1.), (1., -1.)].iter()
+                    .map(|(sx, sy)| Vector { x: sx * w / 2., y: sy * h / 2. }.rotate(*angle))
+                    .collect();
+
+                // start on the right edge's midpoint like the ellipse does on the x axis
+                let right = Vector { x: w / 2., y: 0. }.rotate(*angle);
+                Ok(Piecewise::new(vec![
+                    line(right, corners[0]),
+                    line(corners[0], corners[1]),
+                    line(corners[1], corners[2]),
+                    line(corners[2], corners[3]),
+                    line(corners[3], right),
+                ]))
+            }
+            NibShape::Custom(contour) => {
+                self.validate()?;
+                Ok(Piecewise::new(contour.segs().cloned().collect()))
+            }
+        }
+    }
+
+    // The built in shapes are always fine, custom ones need to be closed and convex. We check convexity on a
+    // sampled polygon: every turn has to go the same way and the turns have to add up to a single revolution,
+    // otherwise the contour loops over itself.
+    pub fn validate(&self) -> Result<(), NibError>
+    {
+        let contour = match self {
+            NibShape::Custom(contour) => contour,
+            _ => return Ok(()),
+        };
+
+        if contour.is_empty() { return Err(NibError::Empty); }
+        if !contour.is_closed() { return Err(NibError::NotClosed); }
+
+        let samples = 16;
+        let mut points: Vec<Vector> = Vec::new();
+        for bez in contour.segs() {
+            for i in 0..samples {
+                let p = bez.evaluate(i as f64 / samples as f64);
+                if points.last().map_or(true, |last| !last.is_near(p, 1e-9)) {
+                    points.push(p);
+                }
+            }
+        }
+
+        if points.len() < 3 { return Err(NibError::NotConvex(points)); }
+
+        let scale = contour.bounds();
+        let eps = 1e-9 * f64::max(scale.right - scale.left, scale.top - scale.bottom).powi(2);
+
+        // the overall direction is whichever way most of the outline turns, the points turning the other way
+        // are the ones to blame
+        let mut total = 0.;
+        for i in 0..points.len() {
+            let a = points[i];
+            let b = points[(i + 1) % points.len()];
+            let c = points[(i + 2) % points.len()];
+            total = total + (b + -a).cross(c + -b);
+        }
+        let sign = total.signum();
+
+        let mut offending: Vec<Vector> = Vec::new();
+        let mut turning = 0.;
+        for i in 0..points.len() {
+            let a = points[i];
+            let b = points[(i + 1) % points.len()];
+            let c = points[(i + 2) % points.len()];
+
+            let e1 = b + -a;
+            let e2 = c + -b;
+            let turn = e1.cross(e2);
+
+            if turn.abs() > eps && turn.signum() != sign { offending.push(b); }
+
+            turning = turning + f64::atan2(turn, e1.dot(e2));
+        }
+
+        if !offending.is_empty() { return Err(NibError::NotConvex(offending)); }
+        if (turning.abs() - 2. * std::f64::consts::PI).abs() > 1e-3 {
+            return Err(NibError::NotConvex(Vec::new()));
+        }
+
+        return Ok(());
+    }
+
+    // The point on the nib that reaches furthest in direction. Sweeping the nib along a path, this is the point
+    // that traces the envelope on the side the direction faces, so it's all a stroker needs from the nib.
+    //
+    // The ellipse gets a closed form: it's the unit circle under the linear map M, and the furthest point of M(circle)
+    // along d is M * (M^T d) / |M^T d|. This is exact, where searching the vertices of a polygonized ellipse snaps
+    // the envelope to whichever vertex wins and gives the stroke facets.
+    pub fn support_point(&self, direction: Vector) -> Vector
+    {
+        // no direction has no furthest point, and the ones below would all divide by zero finding it
+        if direction.magnitude() == 0. { return Vector { x: 0., y: 0. }; }
+
+        match self {
+            NibShape::Circle(r) => direction.normalize() * *r,
+            NibShape::Ellipse(rx, ry, angle) => {
+                let local = direction.rotate(-angle);
+                let mtd = Vector { x: rx * local.x, y: ry * local.y };
+                let len = mtd.magnitude();
+                if len == 0. { return Vector { x: 0., y: 0. }; }
+
+                Vector { x: rx * mtd.x / len, y: ry * mtd.y / len }.rotate(*angle)
+            }
+            NibShape::Rectangle(w, h, angle) => {
+                let local = direction.rotate(-angle);
+                let corner = Vector { x: w / 2. * local.x.signum(), y: h / 2. * local.y.signum() };
+                corner.rotate(*angle)
+            }
+            NibShape::Custom(contour) => support_on(contour, direction).1,
+        }
+    }
+}
+
+// The global t of the point on contour furthest in direction, and the point. The extremes along the direction are
+// at the ends of the curves or where the derivative is perpendicular to it.
+fn support_on(contour: &Piecewise<Bezier>, direction: Vector) -> (f64, Vector)
+{
+    let mut best = (0., contour[0].evaluate(0.));
+    for (i, bez) in contour.segs().enumerate() {
+        let p = bez.to_control_points();
+        let d0 = (p[1] + -p[0]).dot(direction);
+        let d1 = (p[2] + -p[1]).dot(direction);
+        let d2 = (p[3] + -p[2]).dot(direction);
+
+        let mut candidates = roots::solve_quadratic(d0 - 2. * d1 + d2, 2. * (d1 - d0), d0, 0., 1.);
+        candidates.push(1.);
+
+        for t in candidates {
+            let q = bez.evaluate(t);
+            if q.dot(direction) > best.1.dot(direction) { best = (contour.global_t(i, t), q); }
+        }
+    }
+
+    return best;
+}
+
+// how closely the stroke's curves follow the true envelope, in font units, the same as StrokeSettings' default
+const NIB_TOLERANCE: f64 = 0.01;
+// past this many halvings the piece is tiny and we take whatever fit we've got
+const MAX_SPLIT_DEPTH: usize = 10;
+// how many points inside each piece we check the fit against
+const ERROR_SAMPLES: usize = 16;
+// how far into a piece, in t, we look for the nib's side at its ends, and for which way the curve is going
+const NUDGE: f64 = 1e-6;
+const STEP: f64 = 1e-4;
+// the ellipse's support point is exact and its contour isn't, ends this close together relative to the size of the
+// nib are the same point and get snapped rather than bridged
+const SNAP: f64 = 1e-3;
+
+// The unit direction of travel at t. At a cusp or a handle sitting on its point the derivative vanishes so we look
+// at which way the curve is heading a little to either side instead.
+fn direction_at(bez: &Bezier, t: f64) -> Vector
+{
+    let d = -bez.derivative(t);
+    if d.magnitude() > 1e-9 { return d.normalize(); }
+
+    return (bez.evaluate(f64::min(t + STEP, 1.)) + -bez.evaluate(f64::max(t - STEP, 0.))).normalize_or_zero();
+}
+
+// The point the nib traces on side of the curve at t, side being 1 for the left and -1 for the right.
+fn envelope_at(nib: &NibShape, bez: &Bezier, t: f64, side: f64) -> Vector
+{
+    let normal = direction_at(bez, t).rotate(side * std::f64::consts::FRAC_PI_2);
+    return bez.evaluate(t) + nib.support_point(normal);
+}
+
+// A cubic with the envelope's ends that leaves and arrives in the same directions as the curve, which the envelope
+// always does since the nib's support point only ever slides at right angles to the normal. The handles get
+// stretched or shrunk by how much faster or slower the envelope is going than the curve right there, and stop at
+// zero where it turns back on itself on the inside of a bend. The nib's side at the ends is looked for a hair
+// inside the piece, a polygonal nib's ends are where it jumps from one corner to the next.
+fn fit_piece(nib: &NibShape, bez: &Bezier, side: f64) -> Bezier
+{
+    let p = bez.to_control_points();
+    let start = p[0] + nib.support_point(direction_at(bez, NUDGE).rotate(side * std::f64::consts::FRAC_PI_2));
+    let end = p[3] + nib.support_point(direction_at(bez, 1. - NUDGE).rotate(side * std::f64::consts::FRAC_PI_2));
+
+    let factor = |t: f64, toward: f64, from: Vector| {
+        let tangent = direction_at(bez, t);
+        let curve = (bez.evaluate(toward) + -bez.evaluate(t)).dot(tangent);
+        if curve.abs() <= 1e-12 { return 1.; }
+        return f64::max((envelope_at(nib, bez, toward, side) + -from).dot(tangent) / curve, 0.);
+    };
+
+    let start_handle = start + direction_at(bez, 0.) * (p[1].distance(p[0]) * factor(0., STEP, start));
+    let end_handle = end + direction_at(bez, 1.) * -(p[3].distance(p[2]) * factor(1., 1. - STEP, end));
+
+    return Bezier::from_control_points(start, start_handle, end_handle, end);
+}
+
+// The furthest any sampled point of the true envelope is from the fitted curve.
+fn fit_error(nib: &NibShape, bez: &Bezier, fitted: &Bezier, side: f64) -> f64
+{
+    let mut error: f64 = 0.;
+    for i in 1..ERROR_SAMPLES {
+        let (_, _, d) = fitted.project(envelope_at(nib, bez, i as f64 / ERROR_SAMPLES as f64, side));
+        if d.is_finite() { error = error.max(d); }
+    }
+
+    return error;
+}
+
+fn append_envelope(nib: &NibShape, bez: &Bezier, side: f64, depth: usize, output: &mut Vec<Bezier>)
+{
+    let fitted = fit_piece(nib, bez, side);
+    if depth >= MAX_SPLIT_DEPTH || fit_error(nib, bez, &fitted, side) <= NIB_TOLERANCE {
+        output.push(fitted);
+        return;
+    }
+
+    let (first, second) = bez.subdivide(0.5);
+    append_envelope(nib, &first, side, depth + 1, output);
+    append_envelope(nib, &second, side, depth + 1, output);
+}
+
+// Adds next on to the end of output. Ends within snap of each other are the same point, anything further apart is
+// a flat side of the nib the support point jumped across, and gets it as a straight line. Curves that are all one
+// point add nothing.
+fn connect(output: &mut Vec<Bezier>, next: Vec<Bezier>, snap: f64)
+{
+    for bez in next {
+        let p = bez.to_control_points();
+        if p[0] == p[1] && p[1] == p[2] && p[2] == p[3] { continue; }
+
+        match output.last().map(|last| last.to_control_points()[3]) {
+            Some(end) if end.distance(p[0]) <= snap => {
+                output.push(Bezier::from_control_points(end, p[1] + (end + -p[0]), p[2], p[3]));
+            }
+            Some(end) => {
+                output.push(line(end, p[0]));
+                output.push(bez);
+            }
+            None => output.push(bez),
+        }
+    }
+}
+
+// The straight sides of a counter-clockwise nib outline as the directions they face out in. Dragging the nib
+// along, its support point jumps from one end of a side to the other as the path's normal turns past one of these.
+fn flats(outline: &Piecewise<Bezier>) -> Vec<Vector>
+{
+    let mut output = Vec::new();
+    for bez in outline.segs() {
+        let p = bez.to_control_points();
+        let chord = p[3] + -p[0];
+        let length = chord.magnitude();
+        if length == 0. { continue; }
+        if chord.cross(p[1] + -p[0]).abs() > 1e-9 * length * length || chord.cross(p[2] + -p[0]).abs() > 1e-9 * length * length { continue; }
+
+        output.push(Vector { x: chord.y, y: -chord.x } * (1. / length));
+    }
+
+    return output;
+}
+
+// The nib's outline from its support point for direction from to the one for from turned by angle, going forward
+// round the counter-clockwise outline when angle is counter-clockwise and backwards when it isn't, placed at point.
+// The directions are pulled a hair in from the ends so when one of them faces a flat we start and finish on the
+// side of it the arc covers, and next to nothing is no arc at all.
+fn nib_arc(outline: &Piecewise<Bezier>, point: Vector, from: Vector, angle: f64) -> Vec<Bezier>
+{
+    if angle.abs() <= 2. * NUDGE { return Vec::new(); }
+
+    let nudge = NUDGE * angle.signum();
+    let (t0, _) = support_on(outline, from.rotate(nudge));
+    let (t1, _) = support_on(outline, from.rotate(angle - nudge));
+    if t0 == t1 { return Vec::new(); }
+
+    let arc = if angle > 0. { outline.cut(t0, t1) } else { outline.cut(t1, t0).reverse() };
+    return arc.segs().map(|bez| bez.apply_transform(|v: &Vector| *v + point)).collect();
+}
+
+// One side of the sweep along a contour. Each curve is cut where it turns past an axis, an inflection or a flat of
+// the nib, so the nib's support point slides smoothly along each piece without jumping, and the piece is fit with
+// cubics. Where the contour has a corner the nib turns between the two sides of it and we stitch that part of its
+// outline in, on the inside of the corner that backs up over itself.
+fn envelope(nib: &NibShape, outline: &Piecewise<Bezier>, contour: &Piecewise<Bezier>, side: f64, closed: bool, snap: f64) -> Vec<Bezier>
+{
+    let flats = flats(outline);
+    let normal = |bez: &Bezier, t: f64| direction_at(bez, t).rotate(side * std::f64::consts::FRAC_PI_2);
+
+    let mut output = Vec::new();
+    let mut previous: Option<Vector> = if closed { contour.last().map(|bez| normal(bez, 1.)) } else { None };
+    for bez in contour.segs() {
+        if let Some(from) = previous {
+            let start = bez.to_control_points()[0];
+            connect(&mut output, nib_arc(outline, start, from, from.angle_to(normal(bez, 0.))), snap);
+        }
+
+        // the normal faces a flat where the derivative is at right angles to it
+        let [a, b, c, _, e, f, g, _] = bez.coefficients();
+        let mut cuts = bez.extrema();
+        cuts.extend(bez.inflections());
+        for d in &flats {
+            cuts.extend(roots::solve_quadratic(3. * (a * d.x + e * d.y), 2. * (b * d.x + f * d.y), c * d.x + g * d.y, 0., 1.));
+        }
+
+        for piece in bez.split_at_multiple(&cuts) {
+            let mut fitted = Vec::new();
+            append_envelope(nib, &piece, side, 0, &mut fitted);
+            connect(&mut output, fitted, snap);
+        }
+        previous = Some(normal(bez, 1.));
+    }
+
+    return output;
+}
+
+// Joins the end of the curves up with their start.
+fn close(curves: &mut Vec<Bezier>, snap: f64)
+{
+    if curves.is_empty() { return; }
+
+    let start = curves[0].to_control_points()[0];
+    let last = curves.len() - 1;
+    let p = curves[last].to_control_points();
+    if p[3].distance(start) <= snap {
+        curves[last] = Bezier::from_control_points(p[0], p[1], p[2] + (start + -p[3]), start);
+    } else {
+        curves.push(line(p[3], start));
+    }
+}
+
+// Sweeps the nib along every contour of the path. Open contours come out as one contour with the nib's own shape
+// for caps, closed ones as an outer and inner pair going opposite ways. The sides are the path's curves pushed out
+// by the nib's support point and fit to within NIB_TOLERANCE, with the nib's outline stitched in at corners and
+// for the caps. Like pattern along path's output it overlaps itself on the inside of tight bends and corners, so
+// it wants simplifying.
+pub fn nib_stroke(path: &Piecewise<Piecewise<Bezier>>, nib: &NibShape) -> Result<Piecewise<Piecewise<Bezier>>, NibError>
+{
+    let outline = nib.to_contour()?;
+    let outline = if Orientation::YUp.is_counter_clockwise(&outline) { outline } else { outline.reverse() };
+    let size = outline.bounds();
+    let snap = f64::max(SNAP * f64::max(size.right - size.left, size.top - size.bottom), NIB_TOLERANCE);
+
+    let mut output = Vec::new();
+    for contour in path.segs() {
+        if contour.is_empty() { continue; }
+
+        let closed = contour.is_closed();
+        let mut right = envelope(nib, &outline, contour, -1., closed, snap);
+        let left: Vec<Bezier> = envelope(nib, &outline, contour, 1., closed, snap).iter().rev().map(|bez| bez.reverse()).collect();
+
+        if closed {
+            let mut left = left;
+            close(&mut right, snap);
+            close(&mut left, snap);
+            output.push(Piecewise::new(right));
+            output.push(Piecewise::new(left));
+            continue;
+        }
+
+        // the caps are the half of the nib facing away from the path, from the right side round to the left at
+        // the end and back again at the start
+        let first = &contour[0];
+        let last = &contour[contour.len() - 1];
+        let end = last.to_control_points()[3];
+        let start = first.to_control_points()[0];
+
+        let mut points = right;
+        connect(&mut points, nib_arc(&outline, end, direction_at(last, 1.).rotate(-std::f64::consts::FRAC_PI_2), std::f64::consts::PI), snap);
+        connect(&mut points, left, snap);
+        connect(&mut points, nib_arc(&outline, start, direction_at(first, 0.).rotate(std::f64::consts::FRAC_PI_2), std::f64::consts::PI), snap);
+        close(&mut points, snap);
+        output.push(Piecewise::new(points));
+    }
+
+    return Ok(Piecewise::new(output));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    fn path(points: [(f64, f64); 4]) -> Piecewise<Piecewise<Bezier>>
+    {
+        let p: Vec<Vector> = points.iter().map(|(x, y)| Vector { x: *x, y: *y }).collect();
+        return Piecewise::new(vec![Piecewise::new(vec![Bezier::from_control_points(p[0], p[1], p[2], p[3])])]);
+    }
+
+    fn assert_close(got: f64, want: f64, tolerance: f64, what: &str)
+    {
+        assert!((got - want).abs() <= tolerance, "{}: {} rather than {}", what, got, want);
+    }
+
+    #[test]
+    fn support_point_of_no_direction()
+    {
+        let nibs = [NibShape::Circle(10.), NibShape::Ellipse(20., 5., 0.3), NibShape::Rectangle(20., 5., 0.3), NibShape::Custom(ellipse_contour(20., 5., 0.3))];
+        for nib in &nibs {
+            let p = nib.support_point(Vector { x: 0., y: 0. });
+            assert!(p.x.is_finite() && p.y.is_finite());
+        }
+    }
+
+    // A circle nib is a round capped stroke of its diameter, so the skeleton's length times that plus the disc the
+    // two caps make between them, and it gets there in curves rather than a polyline.
+    #[test]
+    fn circle_nib_is_a_round_capped_stroke()
+    {
+        let skeleton = path([(0., 0.), (200., 0.), (100., 300.), (300., 300.)]);
+        let r = 10.;
+        let stroke = nib_stroke(&skeleton, &NibShape::Circle(r)).unwrap();
+
+        assert_eq!(stroke.len(), 1);
+        assert!(stroke[0].len() < 64, "{} curves", stroke[0].len());
+        let length = skeleton[0].arclen(1e-6);
+        assert_close(stroke.area(), 2. * r * length + PI * r * r, 1e-3 * stroke.area(), "area");
+
+        let want = skeleton.bounds().outset(r);
+        let got = stroke.bounds();
+        for (g, w) in [(got.left, want.left), (got.bottom, want.bottom), (got.right, want.right), (got.top, want.top)].iter() {
+            assert_close(*g, *w, 0.05, "bounds");
+        }
+    }
+
+    // A rectangle dragged along a straight line sweeps the rectangle at both ends and the parallelograms its sides
+    // make in between, the flats get stitched in where the support point jumps corners.
+    #[test]
+    fn rectangle_nib_along_a_line()
+    {
+        let skeleton = path([(0., 0.), (100., 200. / 3.), (200., 400. / 3.), (300., 200.)]);
+        let stroke = nib_stroke(&skeleton, &NibShape::Rectangle(40., 10., 0.)).unwrap();
+
+        assert_eq!(stroke.len(), 1);
+        assert_close(stroke.area(), 40. * 10. + 300. * 10. + 200. * 40., 1e-6, "area");
+        let bounds = stroke.bounds();
+        assert_close(bounds.left, -20., 1e-9, "left");
+        assert_close(bounds.bottom, -5., 1e-9, "bottom");
+        assert_close(bounds.right, 320., 1e-9, "right");
+        assert_close(bounds.top, 205., 1e-9, "top");
+    }
+
+    // A circle nib round a circle gives the ring between the circles r either side of it.
+    #[test]
+    fn circle_nib_around_a_closed_contour()
+    {
+        let skeleton = Piecewise::new(vec![Piecewise::circle(Vector { x: 0., y: 0. }, 100.)]);
+        let stroke = nib_stroke(&skeleton, &NibShape::Circle(10.)).unwrap();
+
+        assert_eq!(stroke.len(), 2);
+        let ring = stroke.segs().map(|contour| contour.signed_area()).sum::<f64>();
+        assert_close(ring.abs(), PI * (110. * 110. - 90. * 90.), 1e-3 * ring.abs(), "area");
+    }
+}
@@ -0,0 +1,498 @@
+// Whole-outline operations as values, so a run of them like clean, stroke, remove overlap, fix winding, add extrema
+// and round can be put together once and handed around. Every op is its settings struct implementing OutlineOp, and
+// a Pipeline runs a list of them in order, timing each one and stopping at the first that fails with which op it
+// was and, where the op works contour by contour, which contour.
+//
+// A pipeline can be read from JSON, a list of objects each naming its op in "op" with the op's settings next to it:
+//
+//     [{ "op": "clean" }, { "op": "stroke", "width": 20, "cap": "round" }, { "op": "remove-overlap" },
+//      { "op": "fix-winding", "fill_rule": "nonzero" }, { "op": "add-extrema", "tolerance": 1 },
+//      { "op": "round", "decimals": 2 }]
+//
+// Settings left out take their defaults. With the serde feature OpSettings derives the same shape, so it can come
+// from anything serde reads.
+use crate::qmath::*;
+use crate::stroke::{self, StrokeCap, StrokeJoin, StrokeSettings};
+use serde_json::Value;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::time::{Duration, Instant};
+
+// Why an op failed. contour is the one of the op's input it failed on, None when the op works on the outline as a
+// whole.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OpError {
+    pub contour: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for OpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self.contour {
+            Some(contour) => write!(f, "contour {}: {}", contour, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+pub trait OutlineOp {
+    // what it's called in a pipeline's JSON and its timings
+    fn name(&self) -> &'static str;
+    fn apply(&self, outline: &Piecewise<Piecewise<Bezier>>) -> Result<Piecewise<Piecewise<Bezier>>, OpError>;
+}
+
+fn is_finite(contour: &Piecewise<Bezier>) -> bool
+{
+    return contour.segs().all(|bez| bez.coefficients().iter().all(|c| c.is_finite()));
+}
+
+// Runs op on each contour by itself and puts whatever they make together in order. A contour with a NaN or infinity
+// in it, or that the op panics on, fails the op with its index.
+fn each_contour<F>(outline: &Piecewise<Piecewise<Bezier>>, op: F) -> Result<Piecewise<Piecewise<Bezier>>, OpError>
+    where F: Fn(&Piecewise<Bezier>) -> Vec<Piecewise<Bezier>>
+{
+    let mut output = Vec::new();
+    for (i, contour) in outline.segs().enumerate() {
+        if !is_finite(contour) {
+            return Err(OpError { contour: Some(i), message: String::from("There's a NaN or infinity in it.") });
+        }
+
+        match panic::catch_unwind(AssertUnwindSafe(|| op(contour))) {
+            Ok(contours) => output.extend(contours),
+            Err(_) => return Err(OpError { contour: Some(i), message: String::from("It panicked.") }),
+        }
+    }
+
+    return Ok(Piecewise::new(output));
+}
+
+// Drops degenerate segments, welds joints and merges lines, see Piecewise::clean. Contours that clean down to
+// nothing are dropped.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct Clean {
+    pub epsilon: f64,
+}
+
+impl Default for Clean {
+    fn default() -> Self
+    {
+        return Clean { epsilon: 1e-9 };
+    }
+}
+
+impl OutlineOp for Clean {
+    fn name(&self) -> &'static str
+    {
+        return "clean";
+    }
+
+    fn apply(&self, outline: &Piecewise<Piecewise<Bezier>>) -> Result<Piecewise<Piecewise<Bezier>>, OpError>
+    {
+        return each_contour(outline, |contour| {
+            let (cleaned, _) = contour.clean(self.epsilon);
+            if cleaned.is_empty() { vec![] } else { vec![cleaned] }
+        });
+    }
+}
+
+// Constant width stroking, the settings are the op. Each contour is stroked on its own, closed where its ends meet.
+impl OutlineOp for StrokeSettings {
+    fn name(&self) -> &'static str
+    {
+        return "stroke";
+    }
+
+    fn apply(&self, outline: &Piecewise<Piecewise<Bezier>>) -> Result<Piecewise<Piecewise<Bezier>>, OpError>
+    {
+        return each_contour(outline, |contour| {
+            stroke::constant_width_stroke(&Piecewise::new(vec![contour.clone()]), self).into_segs()
+        });
+    }
+}
+
+// Resolves overlaps through skia, see Piecewise::remove_overlap_with_fill_rule. This one fails outright rather than
+// passing the outline through the way pattern_along_path's simplify does.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct RemoveOverlap {
+    pub fill_rule: FillRule,
+}
+
+impl OutlineOp for RemoveOverlap {
+    fn name(&self) -> &'static str
+    {
+        return "remove-overlap";
+    }
+
+    #[cfg(feature = "skia")]
+    fn apply(&self, outline: &Piecewise<Piecewise<Bezier>>) -> Result<Piecewise<Piecewise<Bezier>>, OpError>
+    {
+        return outline.remove_overlap_with_fill_rule(self.fill_rule).map_err(|e| OpError { contour: None, message: e.to_string() });
+    }
+
+    #[cfg(not(feature = "skia"))]
+    fn apply(&self, _outline: &Piecewise<Piecewise<Bezier>>) -> Result<Piecewise<Piecewise<Bezier>>, OpError>
+    {
+        return Err(OpError { contour: None, message: String::from("Removing overlap needs the skia feature.") });
+    }
+}
+
+// Outer contours counter-clockwise and holes clockwise, see Piecewise::fix_winding_with_fill_rule.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct FixWinding {
+    pub fill_rule: FillRule,
+}
+
+impl OutlineOp for FixWinding {
+    fn name(&self) -> &'static str
+    {
+        return "fix-winding";
+    }
+
+    fn apply(&self, outline: &Piecewise<Piecewise<Bezier>>) -> Result<Piecewise<Piecewise<Bezier>>, OpError>
+    {
+        if let Some(i) = outline.segs().position(|contour| !is_finite(contour)) {
+            return Err(OpError { contour: Some(i), message: String::from("There's a NaN or infinity in it.") });
+        }
+
+        return Ok(outline.fix_winding_with_fill_rule(self.fill_rule));
+    }
+}
+
+// Points at the x and y extrema, see Piecewise::insert_extrema_points.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct AddExtrema {
+    pub tolerance: f64,
+}
+
+impl Default for AddExtrema {
+    fn default() -> Self
+    {
+        return AddExtrema { tolerance: 1. };
+    }
+}
+
+impl OutlineOp for AddExtrema {
+    fn name(&self) -> &'static str
+    {
+        return "add-extrema";
+    }
+
+    fn apply(&self, outline: &Piecewise<Piecewise<Bezier>>) -> Result<Piecewise<Piecewise<Bezier>>, OpError>
+    {
+        return each_contour(outline, |contour| vec![contour.insert_extrema_points(self.tolerance)]);
+    }
+}
+
+// Rounds every coordinate to decimals places, see Piecewise::quantize. Anything rounding had to change is logged.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct Round {
+    pub decimals: u32,
+}
+
+impl Default for Round {
+    fn default() -> Self
+    {
+        return Round { decimals: 2 };
+    }
+}
+
+impl OutlineOp for Round {
+    fn name(&self) -> &'static str
+    {
+        return "round";
+    }
+
+    fn apply(&self, outline: &Piecewise<Piecewise<Bezier>>) -> Result<Piecewise<Piecewise<Bezier>>, OpError>
+    {
+        if let Some(i) = outline.segs().position(|contour| !is_finite(contour)) {
+            return Err(OpError { contour: Some(i), message: String::from("There's a NaN or infinity in it.") });
+        }
+
+        let (quantized, report) = outline.quantize(self.decimals);
+        for warning in report.warnings {
+            log::warn!("{}", warning);
+        }
+
+        return Ok(quantized);
+    }
+}
+
+// Any of the ops above, for describing a pipeline as data.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(tag = "op", rename_all = "kebab-case"))]
+pub enum OpSettings {
+    Clean(Clean),
+    Stroke(StrokeSettings),
+    RemoveOverlap(RemoveOverlap),
+    FixWinding(FixWinding),
+    AddExtrema(AddExtrema),
+    Round(Round),
+}
+
+impl OpSettings {
+    fn op(&self) -> &dyn OutlineOp
+    {
+        return match self {
+            OpSettings::Clean(op) => op,
+            OpSettings::Stroke(op) => op,
+            OpSettings::RemoveOverlap(op) => op,
+            OpSettings::FixWinding(op) => op,
+            OpSettings::AddExtrema(op) => op,
+            OpSettings::Round(op) => op,
+        };
+    }
+}
+
+impl OutlineOp for OpSettings {
+    fn name(&self) -> &'static str
+    {
+        return self.op().name();
+    }
+
+    fn apply(&self, outline: &Piecewise<Piecewise<Bezier>>) -> Result<Piecewise<Piecewise<Bezier>>, OpError>
+    {
+        return self.op().apply(outline);
+    }
+}
+
+fn number_setting(op: &Value, name: &str, default: f64) -> Result<f64, String>
+{
+    match op.get(name) {
+        None => Ok(default),
+        Some(v) => v.as_f64().ok_or(format!("Setting {} must be a number.", name))
+    }
+}
+
+fn string_setting<'v>(op: &'v Value, name: &str) -> Result<Option<&'v str>, String>
+{
+    match op.get(name) {
+        None => Ok(None),
+        Some(v) => v.as_str().map(Some).ok_or(format!("Setting {} must be a string.", name))
+    }
+}
+
+fn fill_rule_setting(op: &Value) -> Result<FillRule, String>
+{
+    return match string_setting(op, "fill_rule")? {
+        None | Some("nonzero") => Ok(FillRule::NonZero),
+        Some("evenodd") => Ok(FillRule::EvenOdd),
+        Some(f) => Err(format!("Invalid fill rule {}.", f)),
+    };
+}
+
+// Custom caps need a shape, which only the serde feature's Deserialize reads, so here it's just the built in ones.
+fn stroke_from_json(op: &Value) -> Result<StrokeSettings, String>
+{
+    let defaults = StrokeSettings::default();
+    let cap = match string_setting(op, "cap")? {
+        None | Some("butt") => StrokeCap::Butt,
+        Some("round") => StrokeCap::Round,
+        Some("square") => StrokeCap::Square,
+        Some(c) => return Err(format!("Invalid cap {}.", c))
+    };
+    let join = match string_setting(op, "join")? {
+        None | Some("miter") => StrokeJoin::Miter,
+        Some("round") => StrokeJoin::Round,
+        Some("bevel") => StrokeJoin::Bevel,
+        Some(j) => return Err(format!("Invalid join {}.", j))
+    };
+
+    return Ok(StrokeSettings {
+        width: number_setting(op, "width", defaults.width)?,
+        cap: cap,
+        join: join,
+        miter_limit: number_setting(op, "miter_limit", defaults.miter_limit)?,
+        tolerance: number_setting(op, "tolerance", defaults.tolerance)?,
+    });
+}
+
+impl OpSettings {
+    // One op out of a pipeline's JSON, see the top of this file.
+    pub fn from_json(op: &Value) -> Result<Self, String>
+    {
+        let name = match op.get("op").and_then(|o| o.as_str()) {
+            Some(name) => name,
+            None => return Err(String::from("Every op needs its name in \"op\".")),
+        };
+
+        return Ok(match name {
+            "clean" => OpSettings::Clean(Clean { epsilon: number_setting(op, "epsilon", Clean::default().epsilon)? }),
+            "stroke" => OpSettings::Stroke(stroke_from_json(op)?),
+            "remove-overlap" => OpSettings::RemoveOverlap(RemoveOverlap { fill_rule: fill_rule_setting(op)? }),
+            "fix-winding" => OpSettings::FixWinding(FixWinding { fill_rule: fill_rule_setting(op)? }),
+            "add-extrema" => OpSettings::AddExtrema(AddExtrema { tolerance: number_setting(op, "tolerance", AddExtrema::default().tolerance)? }),
+            "round" => {
+                let decimals = number_setting(op, "decimals", Round::default().decimals as f64)?;
+                if decimals < 0. || decimals.fract() != 0. { return Err(String::from("Setting decimals must be a whole number.")); }
+                OpSettings::Round(Round { decimals: decimals as u32 })
+            }
+            _ => return Err(format!("Unknown op {}.", name)),
+        });
+    }
+}
+
+// How long each op took, in the order they ran.
+pub type OpTimings = Vec<(&'static str, Duration)>;
+
+// Where a pipeline stopped. index is the op's place in the pipeline, timings has every op that finished before it.
+#[derive(Clone, Debug)]
+pub struct PipelineError {
+    pub op: &'static str,
+    pub index: usize,
+    pub error: OpError,
+    pub timings: OpTimings,
+}
+
+impl fmt::Display for PipelineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        write!(f, "{} (op {}) failed on {}", self.op, self.index, self.error)
+    }
+}
+
+#[derive(Default)]
+pub struct Pipeline {
+    ops: Vec<Box<dyn OutlineOp>>,
+}
+
+impl Pipeline {
+    pub fn new() -> Self
+    {
+        return Pipeline { ops: Vec::new() };
+    }
+
+    // Adds op to the end, for building a pipeline up in one expression.
+    pub fn then<O: OutlineOp + 'static>(mut self, op: O) -> Self
+    {
+        self.ops.push(Box::new(op));
+        return self;
+    }
+
+    pub fn push(&mut self, op: Box<dyn OutlineOp>)
+    {
+        self.ops.push(op);
+    }
+
+    pub fn len(&self) -> usize
+    {
+        return self.ops.len();
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        return self.ops.is_empty();
+    }
+
+    pub fn names(&self) -> Vec<&'static str>
+    {
+        return self.ops.iter().map(|op| op.name()).collect();
+    }
+
+    // Every op in turn on what the one before it made.
+    pub fn run(&self, outline: &Piecewise<Piecewise<Bezier>>) -> Result<(Piecewise<Piecewise<Bezier>>, OpTimings), PipelineError>
+    {
+        let mut timings = Vec::new();
+        let mut current = outline.clone();
+
+        for (i, op) in self.ops.iter().enumerate() {
+            let started = Instant::now();
+            match op.apply(&current) {
+                Ok(next) => current = next,
+                Err(e) => return Err(PipelineError { op: op.name(), index: i, error: e, timings: timings }),
+            }
+            timings.push((op.name(), started.elapsed()));
+        }
+
+        return Ok((current, timings));
+    }
+
+    pub fn from_ops(ops: Vec<OpSettings>) -> Self
+    {
+        let mut pipeline = Pipeline::new();
+        for op in ops {
+            pipeline.push(Box::new(op));
+        }
+
+        return pipeline;
+    }
+
+    // A pipeline out of JSON, see the top of this file.
+    pub fn from_json(json: &str) -> Result<Self, String>
+    {
+        let value: Value = serde_json::from_str(json).map_err(|e| format!("Invalid pipeline JSON: {}", e))?;
+        let ops = value.as_array().ok_or(String::from("A pipeline is a list of ops."))?;
+
+        let ops = ops.iter().enumerate()
+            .map(|(i, op)| OpSettings::from_json(op).map_err(|e| format!("Op {}: {}", i, e)))
+            .collect::<Result<Vec<_>, String>>()?;
+
+        return Ok(Pipeline::from_ops(ops));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(from: Vector, to: Vector) -> Piecewise<Bezier>
+    {
+        return Piecewise::new(vec![Bezier::from_control_points(from, from, to, to)]);
+    }
+
+    // The pipeline from the top of this file, without remove overlap so it doesn't need skia.
+    const PIPELINE: &str = r#"[{ "op": "clean" }, { "op": "stroke", "width": 20, "cap": "round" },
+        { "op": "fix-winding", "fill_rule": "nonzero" }, { "op": "add-extrema", "tolerance": 1 }, { "op": "round", "decimals": 2 }]"#;
+
+    #[test]
+    fn runs_in_order()
+    {
+        let pipeline = Pipeline::from_json(PIPELINE).unwrap();
+        assert_eq!(pipeline.names(), vec!["clean", "stroke", "fix-winding", "add-extrema", "round"]);
+
+        let skeleton = Piecewise::new(vec![line(Vector { x: 0.123, y: 0. }, Vector { x: 100., y: 0. })]);
+        let (output, timings) = pipeline.run(&skeleton).unwrap();
+        assert_eq!(timings.iter().map(|(name, _)| *name).collect::<Vec<_>>(), pipeline.names());
+
+        // a round capped line 20 wide, counter-clockwise and on hundredths
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].winding_direction(), WindingDirection::CounterClockwise);
+        let b = output.bounds();
+        assert!((b.left - (0.123 - 10.)).abs() < 0.01 && (b.right - 110.).abs() < 0.01 && (b.top - 10.).abs() < 0.01, "{:?}", b);
+        for bez in output[0].segs() {
+            for p in bez.to_control_points().iter() {
+                assert!(((p.x * 100.).round() - p.x * 100.).abs() < 1e-6 && ((p.y * 100.).round() - p.y * 100.).abs() < 1e-6, "{:?}", p);
+            }
+        }
+    }
+
+    // A NaN in the second contour stops the first op that looks at it, with the op and the contour.
+    #[test]
+    fn reports_where_it_failed()
+    {
+        let nan = Vector { x: f64::NAN, y: 0. };
+        let outline = Piecewise::new(vec![line(Vector { x: 0., y: 0. }, Vector { x: 100., y: 0. }), line(Vector { x: 0., y: 0. }, nan)]);
+
+        let error = match Pipeline::new().then(FixWinding::default()).then(Clean::default()).run(&outline) {
+            Ok(_) => panic!("stroked a NaN"),
+            Err(e) => e,
+        };
+        assert_eq!((error.op, error.index, error.error.contour), ("fix-winding", 0, Some(1)));
+        assert!(error.timings.is_empty());
+    }
+
+    #[test]
+    fn bad_json()
+    {
+        assert!(Pipeline::from_json(r#"{ "op": "clean" }"#).is_err());
+        assert_eq!(Pipeline::from_json(r#"[{ "op": "clean" }, { "op": "sharpen" }]"#).err().unwrap(), "Op 1: Unknown op sharpen.");
+        assert_eq!(Pipeline::from_json(r#"[{ "width": 20 }]"#).err().unwrap(), "Op 0: Every op needs its name in \"op\".");
+        assert_eq!(Pipeline::from_json(r#"[{ "op": "round", "decimals": 1.5 }]"#).err().unwrap(), "Op 0: Setting decimals must be a whole number.");
+        assert_eq!(Pipeline::from_json(r#"[{ "op": "stroke", "cap": "pointy" }]"#).err().unwrap(), "Op 0: Invalid cap pointy.");
+    }
+}
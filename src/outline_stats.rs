@@ -0,0 +1,164 @@
+// Numbers about an outline for checking it from a script or a build: how big it is, which way its contours go, how
+// sharp it gets. Nothing here changes the outline. --stats prints these as JSON.
+use crate::qmath::*;
+use crate::stroke_report::count_points;
+use serde_json::Value;
+
+// how closely lengths are measured, in font units
+const LENGTH_TOLERANCE: f64 = 0.01;
+// how many places along each curve curvature is sampled at, including both ends
+const CURVATURE_SAMPLES: usize = 16;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ContourStats {
+    pub segments: usize,
+    // counted the way they'd be written to a glif, handles included
+    pub points: usize,
+    pub closed: bool,
+    pub winding: WindingDirection,
+    // positive for counter-clockwise, open contours are closed with a straight line to measure it
+    pub signed_area: f64,
+    // how far it is along the contour, for an open one that's without the way back to its start
+    pub length: f64,
+    pub bounds: Option<Rect>,
+    pub corners: usize,
+    // signed, positive where the contour turns counter-clockwise, None where it never has a direction to turn from
+    pub min_curvature: Option<f64>,
+    pub max_curvature: Option<f64>,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OutlineStats {
+    pub contours: Vec<ContourStats>,
+    pub segments: usize,
+    pub points: usize,
+    pub open_contours: usize,
+    // the contours' signed areas added up, which for an outline without overlaps is the filled area
+    pub signed_area: f64,
+    pub length: f64,
+    pub bounds: Option<Rect>,
+    pub corners: usize,
+    pub min_curvature: Option<f64>,
+    pub max_curvature: Option<f64>,
+}
+
+fn union(a: Option<Rect>, b: Option<Rect>) -> Option<Rect>
+{
+    return match (a, b) {
+        (Some(a), Some(b)) => Some(a.encapsulate_rect(b)),
+        (a, None) => a,
+        (None, b) => b,
+    };
+}
+
+fn min_option(a: Option<f64>, b: Option<f64>) -> Option<f64>
+{
+    return match (a, b) { (Some(a), Some(b)) => Some(a.min(b)), (a, None) => a, (None, b) => b };
+}
+
+fn max_option(a: Option<f64>, b: Option<f64>) -> Option<f64>
+{
+    return match (a, b) { (Some(a), Some(b)) => Some(a.max(b)), (a, None) => a, (None, b) => b };
+}
+
+pub fn contour_stats(contour: &Piecewise<Bezier>) -> ContourStats
+{
+    if contour.is_empty() {
+        return ContourStats { segments: 0, points: 0, closed: false, winding: WindingDirection::Degenerate, signed_area: 0.,
+            length: 0., bounds: None, corners: 0, min_curvature: None, max_curvature: None };
+    }
+
+    let mut min_curvature = None;
+    let mut max_curvature = None;
+    for bez in contour.segs() {
+        for k in 0..CURVATURE_SAMPLES {
+            let curvature = bez.curvature(k as f64 / (CURVATURE_SAMPLES - 1) as f64);
+            min_curvature = min_option(min_curvature, curvature);
+            max_curvature = max_option(max_curvature, curvature);
+        }
+    }
+
+    return ContourStats {
+        segments: contour.len(),
+        points: count_points(&vec![contour.to_contour()]),
+        closed: contour.is_closed(),
+        winding: contour.winding_direction(),
+        signed_area: contour.signed_area(),
+        length: contour.arclen(LENGTH_TOLERANCE),
+        bounds: Some(contour.bounds()),
+        corners: contour.find_corners(CORNER_ANGLE).len(),
+        min_curvature: min_curvature,
+        max_curvature: max_curvature,
+    };
+}
+
+pub fn outline_stats(outline: &Piecewise<Piecewise<Bezier>>) -> OutlineStats
+{
+    let contours: Vec<ContourStats> = outline.segs().map(contour_stats).collect();
+
+    return OutlineStats {
+        segments: contours.iter().map(|c| c.segments).sum(),
+        points: contours.iter().map(|c| c.points).sum(),
+        open_contours: contours.iter().filter(|c| c.segments > 0 && !c.closed).count(),
+        signed_area: contours.iter().map(|c| c.signed_area).sum(),
+        length: contours.iter().map(|c| c.length).sum(),
+        bounds: contours.iter().fold(None, |b, c| union(b, c.bounds)),
+        corners: contours.iter().map(|c| c.corners).sum(),
+        min_curvature: contours.iter().fold(None, |m, c| min_option(m, c.min_curvature)),
+        max_curvature: contours.iter().fold(None, |m, c| max_option(m, c.max_curvature)),
+        contours: contours,
+    };
+}
+
+fn bounds_to_json(bounds: Option<Rect>) -> Value
+{
+    return match bounds {
+        Some(b) => serde_json::json!({ "left": b.left, "bottom": b.bottom, "right": b.right, "top": b.top }),
+        None => Value::Null,
+    };
+}
+
+fn winding_name(winding: WindingDirection) -> &'static str
+{
+    return match winding {
+        WindingDirection::Clockwise => "clockwise",
+        WindingDirection::CounterClockwise => "counter-clockwise",
+        WindingDirection::Degenerate => "degenerate",
+    };
+}
+
+impl OutlineStats {
+    // The same shape the serde feature's derive writes, without needing the feature.
+    pub fn to_json(&self) -> String
+    {
+        let contours: Vec<Value> = self.contours.iter().map(|c| serde_json::json!({
+            "segments": c.segments,
+            "points": c.points,
+            "closed": c.closed,
+            "winding": winding_name(c.winding),
+            "signed_area": c.signed_area,
+            "length": c.length,
+            "bounds": bounds_to_json(c.bounds),
+            "corners": c.corners,
+            "min_curvature": c.min_curvature,
+            "max_curvature": c.max_curvature,
+        })).collect();
+
+        let value = serde_json::json!({
+            "contours": contours,
+            "segments": self.segments,
+            "points": self.points,
+            "open_contours": self.open_contours,
+            "signed_area": self.signed_area,
+            "length": self.length,
+            "bounds": bounds_to_json(self.bounds),
+            "corners": self.corners,
+            "min_curvature": self.min_curvature,
+            "max_curvature": self.max_curvature,
+        });
+
+        return serde_json::to_string_pretty(&value).unwrap();
+    }
+}
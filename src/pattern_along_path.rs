@@ -1,6 +1,12 @@
 use crate::qmath::*;
-use glifparser::{Glif, Outline};
-
+use crate::point_names::{self, NamedLocation};
+use crate::auto_width::TargetMeasurement;
+use crate::joins::JoinRecord;
+use crate::geometry_profile::GeometryProfile;
+use crate::pipeline::Pipeline;
+use glifparser::Glif;
+
+#[derive(Clone)]
 pub struct PatternSettings {
     pub copies: PatternCopies,
     pub subdivide: PatternSubdivide,
@@ -11,18 +17,141 @@ pub struct PatternSettings {
     pub normal_offset: f64,
     pub tangent_offset: f64,
     pub pattern_scale: Vector,
-    pub center_pattern: bool
+    pub center_pattern: bool,
+    pub fill_rule: FillRule,
+    // when set pattern_scale.y gets picked for us so the stems measure a target width, see auto_width.rs
+    pub auto_width: Option<TargetMeasurement>,
+    // what gets put on the ends of open paths, closed paths don't have ends so they're left alone
+    pub start_cap: CapStyle,
+    pub end_cap: CapStyle,
+    // (position, tolerance) pairs, horizontal edges of the output that land within tolerance of a position get
+    // moved onto it exactly
+    pub snap_zones: Vec<(f64, f64)>,
+    // on-curve points of the output farther than this from the path get beveled off, see joins.rs
+    pub max_spike_length: Option<f64>,
+    // which sides of a closed path the pattern goes on, open paths always get both
+    pub expand: ExpandMode,
+    // caps get joined to the rest of the stroke by simplify, where they meet smoothly and the two curves on either
+    // side fit a single one within this error they're merged
+    pub merge_seams: Option<f64>,
+    // pins the stroker's constants so the output doesn't change under archived builds, see geometry_profile.rs
+    pub geometry_profile: GeometryProfile,
+    // whether copies bend to follow the path or are only moved and turned onto it
+    pub placement: PatternPlacement,
+    // what happens to a copy that doesn't fit whole at the end of the path, stretch is the way to scale copies to fit
+    pub ends: PatternEnds,
+    // every copy is stretched along the path about its own center by this much once they're all laid out, they keep
+    // their places so over 1 runs them into each other and under 1 opens up gaps between them
+    pub stretch_factor: f64,
+    // every other copy gets mirrored across the path, normal offset and all, for railway tracks and stitched borders
+    pub flip_alternate: bool,
+    // whether copies turn with the path or stay upright
+    pub rotation: PatternRotation
+}
+
+impl Default for PatternSettings {
+    fn default() -> Self
+    {
+        return PatternSettings {
+            copies: PatternCopies::Single,
+            subdivide: PatternSubdivide::Off,
+            is_vertical: false,
+            normal_offset: 0.,
+            tangent_offset: 0.,
+            center_pattern: true,
+            pattern_scale: Vector{x:1., y: 1.},
+            spacing: 0.,
+            stretch: false,
+            simplify: false,
+            fill_rule: FillRule::NonZero,
+            auto_width: None,
+            start_cap: CapStyle::Butt,
+            end_cap: CapStyle::Butt,
+            snap_zones: Vec::new(),
+            max_spike_length: None,
+            expand: ExpandMode::Both,
+            merge_seams: None,
+            geometry_profile: GeometryProfile::Latest,
+            placement: PatternPlacement::Warp,
+            ends: PatternEnds::Drop,
+            stretch_factor: 1.,
+            flip_alternate: false,
+            rotation: PatternRotation::Follow
+        };
+    }
+}
+
+impl PatternSettings {
+    pub fn set_caps(&mut self, cap: CapStyle)
+    {
+        self.start_cap = cap.clone();
+        self.end_cap = cap;
+    }
+}
+
+// butt - nothing, the stroke ends where the path does
+// round/square/pointed - a half circle, a square or a point as long as half the stroke is wide
+// custom - your own shape in cap space, x points away from the stroke and y to its left with the stroke's edges
+// at -1 and 1, the same shape gets used at either end facing outwards
+#[derive(Clone)]
+pub enum CapStyle {
+    Butt,
+    Round,
+    Square,
+    Pointed,
+    Custom(Piecewise<Piecewise<Bezier>>)
+}
+
+// both - the pattern sits wherever center_pattern and the offsets put it
+// outward/inward - only the part of the stroke outside or inside of closed paths is kept, squashed so the path
+// itself becomes one edge of the stroke and the other edge stays where it was, for stencil style designs
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExpandMode {
+    Both,
+    OutwardOnly,
+    InwardOnly
+}
+
+// warp - the pattern bends with the path, its x runs along it by arc length and its y out along the normal
+// rigid - each copy keeps its shape and is just moved and turned to sit on the path at its center, for dots and
+// beads that shouldn't get squashed going round a bend
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PatternPlacement {
+    Warp,
+    Rigid
+}
+
+// follow - copies turn so the pattern's x axis runs along the path's tangent
+// upright - copies never turn, the pattern's y stays pointing up the glyph wherever they are. Warped copies still
+// run along the path by arc length but stand straight up off it instead of out along the normal
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PatternRotation {
+    Follow,
+    Upright
 }
 
+// drop - copies that don't fit whole are left off, so repeated copies can stop short of the end
+// overflow - the copy that doesn't fit goes on anyway, running on past the end of an open path along its last
+// tangent or on round the start of a closed one
+// clip - that copy is cut off where the path ends, this needs skia
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PatternEnds {
+    Drop,
+    Overflow,
+    Clip
+}
+
+#[derive(Clone)]
 pub enum PatternCopies {
     Single,
     Repeated,
-    Fixed(usize) // TODO: Implement
+    Fixed(usize) // this many copies spread evenly along the path
 }
 
 // pff - no splitting
 // simple - split each curve at it's midpoint
 // angle - split the input pattern each x degrees in change in direction on the path
+#[derive(Clone)]
 pub enum PatternSubdivide {
     Off,
     Simple(usize), // The value here is how many times we'll subdivide simply
@@ -39,7 +168,7 @@ pub enum PatternHandleDiscontinuity {
 // and such during the main algorithm. We prepare our input in 'curve space'. In this space 0 on the y-axis will fall onto a point on the path. A value greater or less than 0 represents offset
 // vertically from the path. The x axis represents it's travel along the arclength of the path. Once this is done the main function can naively loop over all the Piecewises in the output
 // vec without caring about any options except normal/tangent offset.
-fn prepare_pattern<T: Evaluate>(path: &Piecewise<T>, pattern: &Piecewise<Piecewise<Bezier>>, arclenparam: &ArcLengthParameterization, settings: &PatternSettings) -> Vec<Piecewise<Piecewise<Bezier>>>
+fn prepare_pattern(measure: &PathMeasure, pattern: &Piecewise<Piecewise<Bezier>>, settings: &PatternSettings) -> Vec<Piecewise<Piecewise<Bezier>>>
 {
     let mut output: Vec<Piecewise<Piecewise<Bezier>>> = Vec::new();
 
@@ -73,8 +202,8 @@ fn prepare_pattern<T: Evaluate>(path: &Piecewise<T>, pattern: &Piecewise<Piecewi
     }
 
     // so first up let's take our path and calculate how many patterns can fit along it
-    // the last element of arclenparam gives us the total arc len over the entire path
-    let total_arclen = arclenparam.get_total_arclen();
+    let total_arclen = measure.length();
+    let closed = measure.is_closed();
 
 
     // we add the width of the pattern and the spacing setting which gives us the overall width of each input pattern including the space
@@ -98,13 +227,21 @@ fn prepare_pattern<T: Evaluate>(path: &Piecewise<T>, pattern: &Piecewise<Piecewi
                 }
 
                 output.push(single);
+            } else if !settings.stretch {
+                output.extend(partial_copy(&working_pattern, 0., total_arclen, settings));
             }
         },
 
         PatternCopies::Repeated => {
             // we divide the total arc-length by our pattern's width and then floor it to the nearest integer
             // and this gives us the total amount of whole copies that could fit along this path
-            let copies = (total_arclen/total_width) as usize;
+            let mut copies = (total_arclen/total_width) as usize;
+
+            // flipping every other copy round a closed path only meets up with the first copy if there's an even
+            // number of them, so we leave one off and let stretch or the spacing take up its room
+            if settings.flip_alternate && closed && copies > 1 && copies % 2 == 1 {
+                copies = copies - 1;
+            }
             let left_over = total_arclen/total_width - copies as f64;
 
 
@@ -120,16 +257,91 @@ fn prepare_pattern<T: Evaluate>(path: &Piecewise<T>, pattern: &Piecewise<Piecewi
             for n in 0..copies {
                 output.push(working_pattern.translate(n as f64 * total_width + n as f64 * stretch_len * pattern_width, 0.));
             }
+
+            // stretching uses up whatever's left over, otherwise there's room for part of one more copy
+            if !settings.stretch && left_over > 0. {
+                output.extend(partial_copy(&working_pattern, copies as f64 * total_width, total_arclen, settings));
+            }
         }
 
         PatternCopies::Fixed(n) => {
-            // TODO: Implement
+            // each copy gets an even share of the path, stretching fills that share less the spacing
+            if n > 0 {
+                let share = total_arclen / n as f64;
+                if settings.stretch && pattern_width > 0. {
+                    working_pattern = working_pattern.scale(f64::max(share - settings.spacing, 0.) / pattern_width, 1.);
+                }
+
+                for i in 0..n {
+                    output.push(working_pattern.translate(i as f64 * share, 0.));
+                }
+
+                if settings.flip_alternate && closed && n > 1 && n % 2 == 1 {
+                    log::warn!("{} copies can't alternate all the way round a closed path, the last one and the first face the same way.", n);
+                }
+            }
+        }
+    }
+
+    if settings.stretch_factor != 1. {
+        for copy in output.iter_mut() {
+            if copy.is_empty() { continue; }
+
+            let b = copy.bounds();
+            let center = (b.left + b.right) / 2.;
+            *copy = copy.translate(-center, 0.).scale(settings.stretch_factor, 1.).translate(center, 0.);
         }
     }
 
     return output;
 }
 
+// The copy that doesn't fit whole at the end of the path, moved along to x. None when settings.ends leaves it off.
+fn partial_copy(pattern: &Piecewise<Piecewise<Bezier>>, x: f64, total_arclen: f64, settings: &PatternSettings) -> Option<Piecewise<Piecewise<Bezier>>>
+{
+    let placed = pattern.translate(x, 0.);
+
+    match settings.ends {
+        PatternEnds::Drop => None,
+        PatternEnds::Overflow => Some(placed),
+        PatternEnds::Clip => clip_at(&placed, total_arclen)
+    }
+}
+
+// Cuts off everything in curve space past x = length, which is where the path ends.
+#[cfg(feature = "skia")]
+fn clip_at(pattern: &Piecewise<Piecewise<Bezier>>, length: f64) -> Option<Piecewise<Piecewise<Bezier>>>
+{
+    if pattern.is_empty() { return None; }
+
+    let b = pattern.bounds();
+    let (left, bottom, top) = (f64::min(b.left, 0.) - 1., b.bottom - 1., b.top + 1.);
+    let line = |a: Vector, b: Vector| Bezier::from_control_points(a, a, b, b);
+    let v = |x: f64, y: f64| Vector { x: x, y: y };
+    let keep = Piecewise::new(vec![Piecewise::new(vec![
+        line(v(left, bottom), v(length, bottom)),
+        line(v(length, bottom), v(length, top)),
+        line(v(length, top), v(left, top)),
+        line(v(left, top), v(left, bottom)),
+    ])]);
+
+    match pattern.intersect(&keep) {
+        Ok(clipped) if !clipped.is_empty() => Some(clipped),
+        Ok(_) => None,
+        Err(e) => {
+            log::warn!("{} Leaving the last copy off.", e);
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "skia"))]
+fn clip_at(_pattern: &Piecewise<Piecewise<Bezier>>, _length: f64) -> Option<Piecewise<Piecewise<Bezier>>>
+{
+    log::warn!("Clipping the last copy needs the skia feature, leaving it off.");
+    return None;
+}
+
 // https://www.khanacademy.org/math/multivariable-calculus/integrating-multivariable-functions/line-integrals-in-vector-fields-articles/
 // This Khan Academy module has a few classes that really help with understanding the math here. Check out the classes on arc-length and
 // the class on getting normals from curve surfaces for more background.
@@ -142,92 +354,314 @@ fn prepare_pattern<T: Evaluate>(path: &Piecewise<T>, pattern: &Piecewise<Piecewi
 // The inkscape implemnetation seems to be very similar to the algorithm described above. The aim is that this implementation gives
 // comparable outputs to inkscape's.
 #[allow(non_snake_case)]
-fn pattern_along_path<T: Evaluate>(path: &Piecewise<T>, pattern: &Piecewise<Piecewise<Bezier>>, settings: &PatternSettings) -> Piecewise<Piecewise<Bezier>>
+// Along with the output we hand back the points where caps meet the rest of the stroke.
+pub(crate) fn pattern_along_path(path: &Piecewise<Bezier>, pattern: &Piecewise<Piecewise<Bezier>>, settings: &PatternSettings) -> (Piecewise<Piecewise<Bezier>>, Vec<Vector>)
 {
-    // we're gonna parameterize the input path such that 0-1 = 0 -> totalArcLength
-    // this is important because samples will be spaced equidistant along the input path
-    let arclenparam = ArcLengthParameterization::from(path);
-    let total_arclen = arclenparam.get_total_arclen();
-
-    let mut output_piecewise: Piecewise<Piecewise<Bezier>> = Piecewise { curves: Vec::new() };
+    // a lone point in the glif has nothing to lay the pattern along
+    if path.is_empty() { return (Piecewise::new(Vec::new()), Vec::new()); }
 
-    let prepared_pattern = prepare_pattern(path, pattern, &arclenparam, settings);
-    let normals = NormalLUT::from_piecewise(path, 1e+5 as usize, Some(&arclenparam));
-
-    let transform = |point: &Vector| {
-        let u = point.x/total_arclen;
-    
-        // Paramaterize u such that 0-1 maps to the curve by arclength
-        let t = arclenparam.parameterize(u);
-        let path_point = path.evaluate(t);
+    // we're gonna measure the input path by arclength
+    // this is important because samples will be spaced equidistant along the input path
+    let profile = settings.geometry_profile;
+    let measure = PathMeasure::with_cuts(path, profile.arclen_cuts());
+
+    let mut output_piecewise: Piecewise<Piecewise<Bezier>> = Piecewise::new(Vec::new());
+    let mut seams = Vec::new();
+
+    let prepared_pattern = prepare_pattern(&measure, pattern, settings);
+    let normals = NormalLUT::from_piecewise(path, profile.normal_samples(), measure.parameterization());
+
+    // The point on the path x along it by arclength, the tangent there which is essentially a velocity facing in
+    // the direction of travel along the path, and the normal. With overflowing ends we carry on past the end of an
+    // open path in a straight line and go round again on a closed one, otherwise x is clamped to the path.
+    let length = measure.length();
+    let frame = |x: f64| {
+        let (s, past) = match settings.ends {
+            PatternEnds::Overflow if measure.is_closed() && length > 0. => (x.rem_euclid(length), 0.),
+            PatternEnds::Overflow => {
+                let clamped = f64::min(f64::max(x, 0.), length);
+                (clamped, x - clamped)
+            }
+            _ => (x, 0.)
+        };
 
-        // the derivative here is essentially a velocity or tangent line on the point we're evaulating
-        // it faces in the direction of travel along the path
-        let d = path.derivative(t);
+        let t = measure.t_at(s);
+        let (path_point, tangent) = measure.pos_tan_at(s);
 
         // we rotate the vector by 90 degrees so that it's perpendicular to the direction of travel along the curve
         // normalize the vector and now we've got a unit vector perpendicular to the curve's surface in 'curve space'
         // check out NormalLUT for more info on this step
         let N = normals.evaluate(t);
 
-        // now we multiply this by the y value of the pattern this gives us a point
-        // that is as far away from the curve as the input is tall in the direction of the
-        // surface normal of the curve.
-        let mut P = N * point.y;
+        return (path_point + tangent * past, tangent, N);
+    };
 
+    // Offsets a point in curve space, already sitting on the path at path_point, by the offset settings.
+    let offset = |P: Vector, tangent: Vector, N: Vector| {
         // Offset the point by the tangent offset setting.
-        P = P + d.normalize() * settings.tangent_offset;
+        // Positive offsets have always moved the pattern back towards the start of the path, this used to use
+        // derivative which points backwards, so we keep doing that.
+        let mut P = P + -tangent * settings.tangent_offset;
 
         // We offset the point by the normal offset setting.
         P = P + N * settings.normal_offset;
+        return P;
+    };
+
+    // upright copies go straight up off the path whichever way it's heading
+    let up = Vector { x: 0., y: 1. };
+    let upright = settings.rotation == PatternRotation::Upright;
+
+    let transform = |point: &Vector| {
+        let (path_point, tangent, N) = frame(point.x);
+        let N = if upright { up } else { N };
+
+        // now we multiply this by the y value of the pattern this gives us a point
+        // that is as far away from the curve as the input is tall in the direction of the
+        // surface normal of the curve.
+        let P = N * point.y;
 
         // Now we add the evaluation of the bezier's point to the offset point 
         // this essentially translates P from 'curve space' where 0,0 is the point on the curve
         // at t being evaluated to 'world space' where 0,0 is relative to the glyph origin
-        return  P + path_point;
+        return offset(P, tangent, N) + path_point;
+    };
+
+    // Rigid copies take the frame at their center and keep it the whole way across.
+    let rigid_transform = |copy: &Piecewise<Piecewise<Bezier>>| {
+        let b = copy.bounds();
+        let center = (b.left + b.right) / 2.;
+        let (path_point, tangent, lut_normal) = frame(center);
+
+        // the LUT's normals are blended between samples, a copy that's meant to keep its shape needs one that's
+        // square to the tangent, on the same side
+        let mut N = Orientation::YUp.left_normal(tangent);
+        if N.x * lut_normal.x + N.y * lut_normal.y < 0. { N = -N; }
+
+        // upright copies keep the pattern's own axes, only the tangent offset still goes along the path
+        let (x_axis, N) = if upright { (Vector { x: 1., y: 0. }, up) } else { (tangent, N) };
+
+        return move |point: &Vector| offset(x_axis * (point.x - center) + N * point.y, tangent, N) + path_point;
     };
 
-    for p in prepared_pattern {
-        let transformed_pattern = p.apply_transform(&transform);
+    // the stroke's edges in curve space, we need these for the caps and one sided expansion
+    let mut stroke_edges: Option<(f64, f64)> = None;
+    for p in &prepared_pattern {
+        if !p.is_empty() {
+            let b = p.bounds();
+            stroke_edges = Some(match stroke_edges {
+                Some((lo, hi)) => (f64::min(lo, b.bottom), f64::max(hi, b.top)),
+                None => (b.bottom, b.top)
+            });
+        }
+    }
+
+    // To expand to one side we squash the pattern towards the edge on that side until its other edge sits on the
+    // path. A pattern with nothing on that side has nothing to keep, so it gets slid across the path whole instead.
+    let mut expand_transform = None;
+    if let (true, Some((lo, hi))) = (measure.is_closed() && settings.expand != ExpandMode::Both, stroke_edges) {
+        // Counter-clockwise contours have their outside on the right. Our normals ride along whichever side they
+        // started on so we check which one that is halfway round.
+        let halfway = measure.length() / 2.;
+        let (_, tangent) = measure.pos_tan_at(halfway);
+        let right = Orientation::YUp.right_normal(tangent);
+        let N = normals.evaluate(measure.t_at(halfway));
+        let normal_is_right = N.x * right.x + N.y * right.y > 0.;
+        let normal_is_outward = normal_is_right == (path.signed_area() > 0.);
+
+        let outward = settings.expand == ExpandMode::OutwardOnly;
+        let (keep, other) = if outward == normal_is_outward { (hi, lo) } else { (lo, hi) };
+        let (scale, shift) = match keep / (keep - other) {
+            scale if scale > 0. => (scale, keep * (1. - scale)),
+            _ => (1., -other),
+        };
+        expand_transform = Some((scale, shift));
+        stroke_edges = Some((lo * scale + shift, hi * scale + shift));
+    }
+
+    // A flipped copy is mirrored across the path after the normal offset, so it sits as far out on the other side.
+    // The offset gets added on after this so we take it off twice here. Mirroring turns the contours inside out,
+    // reversing them puts their winding back.
+    let flip = |p: Piecewise<Piecewise<Bezier>>| p.scale(1., -1.).translate(0., -2. * settings.normal_offset).reverse();
+    let copies = prepared_pattern.len();
+
+    for (i, p) in prepared_pattern.into_iter().enumerate() {
+        let p = match expand_transform {
+            Some((scale, shift)) => p.scale(1., scale).translate(0., shift),
+            None => p,
+        };
+        if p.is_empty() { continue; }
+        let p = if settings.flip_alternate && i % 2 == 1 { flip(p) } else { p };
+
+        let transformed_pattern = match settings.placement {
+            PatternPlacement::Warp => p.apply_transform(&transform),
+            PatternPlacement::Rigid => p.apply_transform(rigid_transform(&p)),
+        };
+
+        for contour in transformed_pattern {
+            output_piecewise.push(contour);
+        }
+    }
+
+    if let (false, Some((lo, hi))) = (measure.is_closed(), stroke_edges) {
+        let half_width = (hi - lo) / 2.;
+        let center = (hi + lo) / 2. + settings.normal_offset;
+
+        // the first copy is never flipped, the last one is when there's an even number of them
+        let end_flipped = settings.flip_alternate && copies % 2 == 0;
+
+        let length = measure.length();
+        for (cap, s, outward, flipped) in [(&settings.start_cap, 0., -1., false), (&settings.end_cap, length, 1., end_flipped)].iter() {
+            let shape = match cap_shape(cap) {
+                Some(shape) => shape,
+                None => continue
+            };
+            let center = if *flipped { -center } else { center };
+
+            // we keep the normal on the same side as the LUT's, sampled just inside the end
+            let (end_point, tangent) = measure.pos_tan_at(*s);
+            let lut_normal = normals.evaluate(measure.t_at(*s - *outward * length * 1e-6));
+            let mut N = measure.normal_at(*s);
+            if N.x * lut_normal.x + N.y * lut_normal.y < 0. { N = -N; }
+            if upright { N = up; }
+
+            let path_point = end_point + -tangent * settings.tangent_offset;
+            let out = tangent * *outward;
+            let left = N * *outward;
+
+            let cap = shape.apply_transform(|v: &Vector| {
+                path_point + out * (v.x * half_width) + left * (v.y * half_width) + N * center
+            });
+
+            for contour in cap {
+                output_piecewise.push(contour);
+            }
 
-        for contour in transformed_pattern.curves {
-            output_piecewise.curves.push(contour);
+            // the cap's corners are on the stroke's edges
+            let base = path_point + N * center;
+            seams.push(base + left * half_width);
+            seams.push(base + -(left * half_width));
         }
     }
 
-    return output_piecewise;
+    return (output_piecewise, seams);
+}
+
+// The cap in cap space, counter-clockwise there so it winds the same way as a pattern does once it's placed
+// along the path. None for butt caps.
+fn cap_shape(cap: &CapStyle) -> Option<Piecewise<Piecewise<Bezier>>>
+{
+    let line = |a: Vector, b: Vector| Bezier::from_control_points(a, a, b, b);
+    let v = |x: f64, y: f64| Vector { x: x, y: y };
+
+    // handle length for a quarter circle out of cubics
+    let k = 0.5522847498;
+
+    let curves = match cap {
+        CapStyle::Butt => return None,
+        CapStyle::Custom(shape) => return Some(shape.clone()),
+        CapStyle::Round => vec![
+            Bezier::from_control_points(v(0., -1.), v(k, -1.), v(1., -k), v(1., 0.)),
+            Bezier::from_control_points(v(1., 0.), v(1., k), v(k, 1.), v(0., 1.)),
+            line(v(0., 1.), v(0., -1.)),
+        ],
+        CapStyle::Square => vec![
+            line(v(0., -1.), v(1., -1.)),
+            line(v(1., -1.), v(1., 1.)),
+            line(v(1., 1.), v(0., 1.)),
+            line(v(0., 1.), v(0., -1.)),
+        ],
+        CapStyle::Pointed => vec![
+            line(v(0., -1.), v(1., 0.)),
+            line(v(1., 0.), v(0., 1.)),
+            line(v(0., 1.), v(0., -1.)),
+        ],
+    };
+
+    return Some(Piecewise::new(vec![Piecewise::new(curves)]));
+}
+
+// The stroked outline along with, for every output contour, the indices of the path contours that produced it.
+// Simplify only ever runs on the output of a single path contour so right now every entry has exactly one index,
+// it's a Vec so that can change if we start removing overlap between contours.
+pub struct StrokeResult {
+    pub outline: Piecewise<Piecewise<Bezier>>,
+    pub provenance: Vec<Vec<usize>>,
+    // the vertical pattern scale auto width settled on, if it was used
+    pub auto_width: Option<f64>,
+    // every corner in the path and what the output did there
+    pub joins: Vec<JoinRecord>,
+}
+
+// Applies the pattern along every contour in path and collects the results into a single outline. This runs every
+// stage of the pipeline in one go, see pipeline.rs for running them separately.
+pub fn stroke_outline(piece_path: &Piecewise<Piecewise<Bezier>>, piece_pattern: &Piecewise<Piecewise<Bezier>>, settings: &PatternSettings) -> StrokeResult
+{
+    return Pipeline::new(settings).run(piece_path, piece_pattern).into_result().unwrap();
+}
+
+pub fn pattern_along_outline(piece_path: &Piecewise<Piecewise<Bezier>>, piece_pattern: &Piecewise<Piecewise<Bezier>>, settings: &PatternSettings) -> Piecewise<Piecewise<Bezier>>
+{
+    return stroke_outline(piece_path, piece_pattern, settings).outline;
+}
+
+// We're gonna simplify our output, particularly useful when using a pattern like a square in single mode to
+// generate a stroke, but skia is a bit finicky about how it returns the winding order so with some complicated
+// shapes it will return flawed results. The output is always nonzero, whatever rule the input was filled with.
+#[cfg(feature = "skia")]
+pub fn simplify(pattern: Piecewise<Piecewise<Bezier>>, fill_rule: FillRule) -> Piecewise<Piecewise<Bezier>>
+{
+    match pattern.remove_overlap_with_fill_rule(fill_rule) {
+        Ok(simplified) => simplified,
+        Err(e) => {
+            log::warn!("{} Leaving the output as is.", e);
+            pattern
+        }
+    }
+}
+
+#[cfg(not(feature = "skia"))]
+pub fn simplify(pattern: Piecewise<Piecewise<Bezier>>, _fill_rule: FillRule) -> Piecewise<Piecewise<Bezier>>
+{
+    log::warn!("Simplify needs the skia feature, leaving the output as is.");
+    return pattern;
 }
 
 // this function is likely not sticking around
 pub fn pattern_along_glif<U>(path: &Glif<U>, pattern: &Glif<Option<PointData>>, settings: &PatternSettings) -> Glif<Option<PointData>>
 {
-    // convert our path and pattern to piecewise collections of beziers
+    // convert our path to a piecewise collection of beziers
     let piece_path = Piecewise::from_outline(path.outline.as_ref().unwrap());
-    let piece_pattern = Piecewise::from_outline(pattern.outline.as_ref().unwrap());
+    let names = point_names::named_locations(path.outline.as_ref().unwrap());
 
-    let mut output_outline: Outline<Option<PointData>> = Vec::new();
+    let mut glif = pattern_along_piecewise(&piece_path, &names, pattern, settings).0;
+    glif.anchors = path.anchors.clone();
 
+    return glif;
+}
 
-    for contour in piece_path.curves {
-        let mut temp_pattern = pattern_along_path(&contour, &piece_pattern, settings);
-
-        if settings.simplify {
-            // we're gonna simplify our output, particularly useful when using a pattern like a 
-            // square in single mode to generate a stroke, but skia is a bit finicky about how it returns the winding
-            // order so with some complicated shapes it will return flawed results
-            let skpattern = temp_pattern.to_skpath();
-            temp_pattern = Piecewise::from_skpath(&skpattern.simplify().unwrap().as_winding().unwrap());
-        }
+// Same as above but for paths that didn't come from a glif, like ones we read out of an SVG. Named skeleton points
+// get carried over to the output, we hand back warnings for any that couldn't be along with what happened at each
+// corner of the path.
+pub fn pattern_along_piecewise(piece_path: &Piecewise<Piecewise<Bezier>>, names: &[NamedLocation], pattern: &Glif<Option<PointData>>, settings: &PatternSettings) -> (Glif<Option<PointData>>, Vec<String>, Vec<JoinRecord>)
+{
+    let piece_pattern = Piecewise::from_outline(pattern.outline.as_ref().unwrap());
+    return glif_from_result(stroke_outline(piece_path, &piece_pattern, settings), piece_path, names, pattern);
+}
 
-        let temp_outline = temp_pattern.to_outline();
+// Turns a finished stroke into a glif the same way as above, for strokes that were run through the pipeline a
+// stage at a time.
+pub fn glif_from_result(mut result: StrokeResult, piece_path: &Piecewise<Piecewise<Bezier>>, names: &[NamedLocation], pattern: &Glif<Option<PointData>>) -> (Glif<Option<PointData>>, Vec<String>, Vec<JoinRecord>)
+{
+    let (placements, mut warnings) = point_names::place_point_names(&mut result, piece_path, names, 1.);
 
-        for contour in temp_outline
-        {
-            output_outline.push(contour);
-        }
+    if let Some(scale) = result.auto_width {
+        warnings.push(format!("Auto width settled on a vertical pattern scale of {}.", scale));
     }
+    let mut output_outline = result.outline.to_outline();
+    point_names::apply_point_names(&mut output_outline, &placements);
 
-    return Glif {
+    let glif = Glif {
         outline: Some(output_outline), 
         order: pattern.order, // default when only corners
         anchors: None,
@@ -236,4 +670,95 @@ pub fn pattern_along_glif<U>(path: &Glif<U>, pattern: &Glif<Option<PointData>>,
         name: String::new(),
         format: 2,
     };
-}
\ No newline at end of file
+
+    return (glif, warnings, result.joins);
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn horizontal_line(from: Vector, length: f64) -> Piecewise<Bezier>
+    {
+        let to = Vector { x: from.x + length, y: from.y };
+        return Piecewise::new(vec![Bezier::from_control_points(from, from, to, to)]);
+    }
+
+    // Two strokes well apart, every output contour has to come from exactly the one it's sitting on.
+    #[test]
+    fn separate_strokes_map_one_to_one()
+    {
+        let path = Piecewise::new(vec![horizontal_line(Vector { x: 0., y: 0. }, 200.), horizontal_line(Vector { x: 0., y: 500. }, 300.)]);
+        let pattern = Piecewise::rect(Rect { left: 0., bottom: -10., right: 20., top: 10. });
+        let settings = PatternSettings { copies: PatternCopies::Repeated, spacing: 10., ..PatternSettings::default() };
+
+        let result = stroke_outline(&path, &pattern, &settings);
+        assert_eq!(result.provenance.len(), result.outline.len());
+        assert!(result.outline.len() > 2);
+
+        for (contour, sources) in result.outline.segs().zip(result.provenance.iter()) {
+            assert_eq!(sources.len(), 1);
+            let y = path[sources[0]][0].evaluate(0.).y;
+            let bounds = contour.bounds();
+            assert!(bounds.bottom >= y - 10.5 && bounds.top <= y + 10.5, "{:?} isn't on the stroke at {}", bounds, y);
+        }
+
+        // and more copies along the longer one
+        let count = |i: usize| result.provenance.iter().filter(|sources| sources[0] == i).count();
+        assert!(count(1) > count(0) && count(0) > 0);
+    }
+
+    // The radii the ends of the output's curves sit at, rounded to the nearest unit.
+    fn radii(outline: &Piecewise<Piecewise<Bezier>>, center: Vector) -> Vec<i64>
+    {
+        let mut radii: Vec<i64> = outline.segs().flat_map(|contour| contour.segs()).map(|bez| bez.evaluate(0.).distance(center).round() as i64).collect();
+        radii.sort();
+        radii.dedup();
+        return radii;
+    }
+
+    // A 20 wide stroke round a circle of radius 100 either straddles it or sits entirely on one side of it, which way
+    // round the circle goes doesn't matter.
+    #[test]
+    fn one_sided_expansion()
+    {
+        let center = Vector { x: 300., y: 300. };
+        let pattern = Piecewise::rect(Rect { left: 0., bottom: -10., right: 20., top: 10. });
+
+        for circle in [Piecewise::circle(center, 100.), Piecewise::circle(center, 100.).reverse()].iter() {
+            let path = Piecewise::new(vec![circle.clone()]);
+            for (expand, want) in [(ExpandMode::Both, vec![90, 110]), (ExpandMode::OutwardOnly, vec![100, 110]), (ExpandMode::InwardOnly, vec![90, 100])].iter() {
+                let settings = PatternSettings { stretch: true, expand: *expand, ..PatternSettings::default() };
+                let outline = stroke_outline(&path, &pattern, &settings).outline;
+                assert_eq!(&radii(&outline, center), want, "{:?}", expand);
+            }
+        }
+    }
+
+    // A round start and a pointed end on a line 20 wide, each end checked on its own. Caps are contours of their own
+    // until simplify joins them up, so we tell them apart by which end they're past.
+    #[test]
+    fn different_caps_at_each_end()
+    {
+        let path = Piecewise::new(vec![horizontal_line(Vector { x: 0., y: 0. }, 200.)]);
+        let pattern = Piecewise::rect(Rect { left: 0., bottom: -10., right: 20., top: 10. });
+        let settings = PatternSettings { stretch: true, start_cap: CapStyle::Round, end_cap: CapStyle::Pointed, ..PatternSettings::default() };
+        let outline = stroke_outline(&path, &pattern, &settings).outline;
+        assert_eq!(outline.len(), 3);
+
+        let start = outline.segs().find(|c| c.bounds().right <= 1e-9).unwrap();
+        let b = start.bounds();
+        assert!((b.left + 10.).abs() < 1e-9 && (b.bottom + 10.).abs() < 1e-9 && (b.top - 10.).abs() < 1e-9, "{:?}", b);
+        let half_disc = std::f64::consts::PI * 100. / 2.;
+        assert!((start.signed_area().abs() - half_disc).abs() < 1e-3 * half_disc, "{}", start.signed_area());
+
+        let end = outline.segs().find(|c| c.bounds().left >= 200. - 1e-9).unwrap();
+        assert!(end.segs().any(|bez| bez.evaluate(0.).is_near(Vector { x: 210., y: 0. }, 1e-9)));
+        assert!((end.signed_area().abs() - 100.).abs() < 1e-9, "{}", end.signed_area());
+
+        // set_caps puts the same square on both
+        let mut settings = PatternSettings { stretch: true, ..PatternSettings::default() };
+        settings.set_caps(CapStyle::Square);
+        let b = stroke_outline(&path, &pattern, &settings).outline.bounds();
+        assert!((b.left + 10.).abs() < 1e-9 && (b.right - 210.).abs() < 1e-9, "{:?}", b);
+    }
+}
@@ -0,0 +1,370 @@
+// Stroking split into named stages so it can be stopped part way, saved to disk, and picked back up later, possibly
+// by another tool. Every stage takes the artifact the one before it left and hands back the next, the artifact
+// holds everything the remaining stages need besides the settings, which the caller passes again when resuming.
+//
+// prepare - works out the pattern's vertical scale when auto width is on
+// corner split - nothing yet, the pattern stroker doesn't split at corners
+// offset - lays the pattern along each contour of the path, caps included
+// repair - nothing yet, pattern output doesn't need mending before it's assembled
+// assemble - puts the contours' strokes together into one outline, remembering which contour made what
+// boolean - simplifies each contour's stroke and merges the seams where its caps meet it
+// postprocess - snaps to metrics and clips spikes
+//
+// Corner split and repair are where the offsetting stroker's steps go, they pass the artifact through untouched
+// here so artifacts line up between the two.
+use crate::qmath::*;
+use crate::pattern_along_path::{self, PatternSettings, StrokeResult, simplify};
+use crate::auto_width;
+use crate::joins;
+use serde_json::Value;
+use std::fs;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    Prepare,
+    CornerSplit,
+    Offset,
+    Repair,
+    Assemble,
+    Boolean,
+    Postprocess
+}
+
+impl Stage {
+    pub const ALL: [Stage; 7] = [Stage::Prepare, Stage::CornerSplit, Stage::Offset, Stage::Repair, Stage::Assemble, Stage::Boolean, Stage::Postprocess];
+
+    pub fn name(&self) -> &'static str
+    {
+        return match self {
+            Stage::Prepare => "prepare",
+            Stage::CornerSplit => "corner-split",
+            Stage::Offset => "offset",
+            Stage::Repair => "repair",
+            Stage::Assemble => "assemble",
+            Stage::Boolean => "boolean",
+            Stage::Postprocess => "postprocess"
+        };
+    }
+
+    pub fn from_name(name: &str) -> Option<Stage>
+    {
+        return Stage::ALL.iter().find(|s| s.name() == name).copied();
+    }
+}
+
+// What's been done so far. Before assemble the output lives in strokes, one entry per path contour, after it
+// it's in outline with provenance saying which path contour each output contour came from.
+#[derive(Clone)]
+pub struct StageArtifact {
+    // the last stage that ran
+    pub stage: Stage,
+    pub path: Piecewise<Piecewise<Bezier>>,
+    pub pattern: Piecewise<Piecewise<Bezier>>,
+    // the scale the pattern gets placed at, prepare fills in the y auto width picks
+    pub pattern_scale: Vector,
+    pub auto_width: Option<f64>,
+    pub strokes: Vec<Piecewise<Piecewise<Bezier>>>,
+    // where caps meet the stroke, for each path contour
+    pub seams: Vec<Vec<Vector>>,
+    pub outline: Piecewise<Piecewise<Bezier>>,
+    pub provenance: Vec<Vec<usize>>,
+}
+
+pub struct Pipeline<'a> {
+    pub settings: &'a PatternSettings,
+    // the last stage run before handing the artifact back
+    pub stop_after: Stage,
+}
+
+// Cuts the assembled outline back up into what each path contour made.
+fn by_source(outline: &Piecewise<Piecewise<Bezier>>, provenance: &[Vec<usize>], sources: usize) -> Vec<Piecewise<Piecewise<Bezier>>>
+{
+    let mut groups: Vec<Piecewise<Piecewise<Bezier>>> = (0..sources).map(|_| Piecewise::new(Vec::new())).collect();
+    for (contour, from) in outline.segs().zip(provenance) {
+        groups[from[0]].push(contour.clone());
+    }
+
+    return groups;
+}
+
+fn assemble(groups: Vec<Piecewise<Piecewise<Bezier>>>) -> (Piecewise<Piecewise<Bezier>>, Vec<Vec<usize>>)
+{
+    let mut outline = Piecewise::new(Vec::new());
+    let mut provenance = Vec::new();
+    for (source, group) in groups.into_iter().enumerate() {
+        for contour in group {
+            outline.push(contour);
+            provenance.push(vec![source]);
+        }
+    }
+
+    return (outline, provenance);
+}
+
+impl<'a> Pipeline<'a> {
+    pub fn new(settings: &'a PatternSettings) -> Self
+    {
+        return Pipeline { settings: settings, stop_after: Stage::Postprocess };
+    }
+
+    // Strokes the path from the start, as far as stop_after.
+    pub fn run(&self, piece_path: &Piecewise<Piecewise<Bezier>>, piece_pattern: &Piecewise<Piecewise<Bezier>>) -> StageArtifact
+    {
+        return self.resume(self.prepare(piece_path, piece_pattern));
+    }
+
+    // Runs the stages after the one the artifact got to, as far as stop_after. An artifact already past it comes
+    // back as it is.
+    pub fn resume(&self, mut artifact: StageArtifact) -> StageArtifact
+    {
+        // everything after prepare uses the scale it settled on
+        let mut settings = self.settings.clone();
+        settings.pattern_scale = artifact.pattern_scale;
+        settings.auto_width = None;
+
+        let from = artifact.stage;
+        for stage in Stage::ALL.iter().filter(|s| **s > from && **s <= self.stop_after) {
+            match stage {
+                Stage::Prepare | Stage::CornerSplit | Stage::Repair => {},
+                Stage::Offset => self.offset(&mut artifact, &settings),
+                Stage::Assemble => {
+                    let (outline, provenance) = assemble(std::mem::replace(&mut artifact.strokes, Vec::new()));
+                    artifact.outline = outline;
+                    artifact.provenance = provenance;
+                },
+                Stage::Boolean => self.boolean(&mut artifact, &settings),
+                Stage::Postprocess => self.postprocess(&mut artifact, &settings)
+            }
+            artifact.stage = *stage;
+        }
+
+        return artifact;
+    }
+
+    pub fn prepare(&self, piece_path: &Piecewise<Piecewise<Bezier>>, piece_pattern: &Piecewise<Piecewise<Bezier>>) -> StageArtifact
+    {
+        let mut artifact = StageArtifact {
+            stage: Stage::Prepare,
+            path: piece_path.clone(),
+            pattern: piece_pattern.clone(),
+            pattern_scale: self.settings.pattern_scale,
+            auto_width: None,
+            strokes: Vec::new(),
+            seams: Vec::new(),
+            outline: Piecewise::new(Vec::new()),
+            provenance: Vec::new(),
+        };
+
+        // Auto width strokes the whole thing to measure it, we only keep the scale it lands on. That way the
+        // stages after this one don't care whether it was used.
+        if let Some(target) = &self.settings.auto_width {
+            match auto_width::stroke_to_target(piece_path, piece_pattern, self.settings, target) {
+                Ok(result) => {
+                    artifact.pattern_scale.y = result.auto_width.unwrap();
+                    artifact.auto_width = result.auto_width;
+                },
+                Err(e) => { log::warn!("{} Using the pattern's scale as is.", e); }
+            }
+        }
+
+        return artifact;
+    }
+
+    fn offset(&self, artifact: &mut StageArtifact, settings: &PatternSettings)
+    {
+        #[cfg(feature = "rayon")]
+        let results: Vec<(Piecewise<Piecewise<Bezier>>, Vec<Vector>)> = artifact.path.as_slice().par_iter()
+            .map(|contour| pattern_along_path::pattern_along_path(contour, &artifact.pattern, settings))
+            .collect();
+
+        #[cfg(not(feature = "rayon"))]
+        let results: Vec<(Piecewise<Piecewise<Bezier>>, Vec<Vector>)> = artifact.path.segs()
+            .map(|contour| pattern_along_path::pattern_along_path(contour, &artifact.pattern, settings))
+            .collect();
+
+        let (strokes, seams) = results.into_iter().unzip();
+        artifact.strokes = strokes;
+        artifact.seams = seams;
+    }
+
+    // Simplify only ever sees one path contour's stroke at a time.
+    fn boolean(&self, artifact: &mut StageArtifact, settings: &PatternSettings)
+    {
+        if !settings.simplify { return; }
+
+        let groups = by_source(&artifact.outline, &artifact.provenance, artifact.path.len());
+        let seams = &artifact.seams;
+        let boolean_group = |(group, seams): (Piecewise<Piecewise<Bezier>>, &Vec<Vector>)| {
+            let simplified = simplify(group, settings.fill_rule);
+            match settings.merge_seams {
+                Some(max_error) => simplified.merge_smooth_seams(seams, settings.geometry_profile.seam_angle_tolerance(), max_error).0,
+                None => simplified
+            }
+        };
+
+        #[cfg(feature = "rayon")]
+        let groups: Vec<Piecewise<Piecewise<Bezier>>> = groups.into_par_iter().zip(seams.par_iter()).map(boolean_group).collect();
+
+        #[cfg(not(feature = "rayon"))]
+        let groups: Vec<Piecewise<Piecewise<Bezier>>> = groups.into_iter().zip(seams.iter()).map(boolean_group).collect();
+
+        let (outline, provenance) = assemble(groups);
+        artifact.outline = outline;
+        artifact.provenance = provenance;
+    }
+
+    fn postprocess(&self, artifact: &mut StageArtifact, settings: &PatternSettings)
+    {
+        let groups = by_source(&artifact.outline, &artifact.provenance, artifact.path.len());
+        let groups = groups.into_iter().map(|group| {
+            // snapping keeps every contour so provenance still lines up
+            let group = if settings.snap_zones.is_empty() { group } else { group.snap_to_zones(&settings.snap_zones).0 };
+            match settings.max_spike_length {
+                Some(max) => joins::clip_spikes(&group, &artifact.path, max).0,
+                None => group
+            }
+        }).collect();
+
+        let (outline, provenance) = assemble(groups);
+        artifact.outline = outline;
+        artifact.provenance = provenance;
+    }
+}
+
+// Curves are written as their coefficients rather than their points so they read back bit for bit, a resumed run
+// has to come out the same as one that went straight through. That also needs serde_json's float_roundtrip.
+fn outline_to_json(outline: &Piecewise<Piecewise<Bezier>>) -> Value
+{
+    return Value::from(outline.segs().map(|contour| {
+        Value::from(contour.segs().map(|bez| Value::from(bez.coefficients().to_vec())).collect::<Vec<Value>>())
+    }).collect::<Vec<Value>>());
+}
+
+fn numbers(value: &Value, what: &str) -> Result<Vec<f64>, String>
+{
+    let array = value.as_array().ok_or(format!("Artifact {} isn't a list.", what))?;
+    return array.iter().map(|n| n.as_f64().ok_or(format!("Artifact {} has something besides numbers in it.", what))).collect();
+}
+
+fn list<'v>(value: Option<&'v Value>, what: &str) -> Result<&'v Vec<Value>, String>
+{
+    return value.and_then(|v| v.as_array()).ok_or(format!("Artifact is missing its {}.", what));
+}
+
+fn outline_from_json(value: Option<&Value>, what: &str) -> Result<Piecewise<Piecewise<Bezier>>, String>
+{
+    let mut contours = Vec::new();
+    for contour in list(value, what)? {
+        let mut curves = Vec::new();
+        for bez in contour.as_array().ok_or(format!("Artifact {} has a contour that isn't a list.", what))? {
+            let c = numbers(bez, what)?;
+            if c.len() != 8 { return Err(format!("Artifact {} has a curve without 8 coefficients.", what)); }
+            curves.push(Bezier::from_coefficients([c[0], c[1], c[2], c[3], c[4], c[5], c[6], c[7]]));
+        }
+        contours.push(Piecewise::new(curves));
+    }
+
+    return Ok(Piecewise::new(contours));
+}
+
+impl StageArtifact {
+    pub fn to_json(&self) -> String
+    {
+        let value = serde_json::json!({
+            "stage": self.stage.name(),
+            "path": outline_to_json(&self.path),
+            "pattern": outline_to_json(&self.pattern),
+            "pattern_scale": vec![self.pattern_scale.x, self.pattern_scale.y],
+            "auto_width": self.auto_width,
+            "strokes": self.strokes.iter().map(outline_to_json).collect::<Vec<Value>>(),
+            "seams": self.seams.iter().map(|s| s.iter().map(|v| vec![v.x, v.y]).collect::<Vec<Vec<f64>>>()).collect::<Vec<Vec<Vec<f64>>>>(),
+            "outline": outline_to_json(&self.outline),
+            "provenance": self.provenance.clone(),
+        });
+
+        return value.to_string();
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String>
+    {
+        let value: Value = serde_json::from_str(json).map_err(|e| format!("Invalid artifact JSON: {}", e))?;
+
+        let stage_name = value.get("stage").and_then(|s| s.as_str()).ok_or(String::from("Artifact is missing its stage."))?;
+        let stage = Stage::from_name(stage_name).ok_or(format!("Unknown stage {} in artifact.", stage_name))?;
+
+        let scale = numbers(value.get("pattern_scale").unwrap_or(&Value::Null), "pattern scale")?;
+        if scale.len() != 2 { return Err(String::from("Artifact pattern scale needs an x and a y.")); }
+
+        let auto_width = match value.get("auto_width") {
+            None | Some(Value::Null) => None,
+            Some(v) => Some(v.as_f64().ok_or(String::from("Artifact auto width isn't a number."))?)
+        };
+
+        let strokes = list(value.get("strokes"), "strokes")?.iter()
+            .map(|s| outline_from_json(Some(s), "strokes"))
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let mut seams = Vec::new();
+        for contour_seams in list(value.get("seams"), "seams")? {
+            let mut points = Vec::new();
+            for point in contour_seams.as_array().ok_or(String::from("Artifact seams aren't lists of points."))? {
+                let p = numbers(point, "seams")?;
+                if p.len() != 2 { return Err(String::from("Artifact seams need an x and a y.")); }
+                points.push(Vector { x: p[0], y: p[1] });
+            }
+            seams.push(points);
+        }
+
+        let path = outline_from_json(value.get("path"), "path")?;
+        let outline = outline_from_json(value.get("outline"), "outline")?;
+
+        let mut provenance = Vec::new();
+        for from in list(value.get("provenance"), "provenance")? {
+            let sources = numbers(from, "provenance")?;
+            if sources.iter().any(|s| *s < 0. || s.fract() != 0. || *s as usize >= path.len()) {
+                return Err(String::from("Artifact provenance points at a path contour that isn't there."));
+            }
+            provenance.push(sources.into_iter().map(|s| s as usize).collect());
+        }
+
+        if provenance.len() != outline.len() {
+            return Err(String::from("Artifact provenance doesn't match its outline."));
+        }
+
+        return Ok(StageArtifact {
+            stage: stage,
+            path: path,
+            pattern: outline_from_json(value.get("pattern"), "pattern")?,
+            pattern_scale: Vector { x: scale[0], y: scale[1] },
+            auto_width: auto_width,
+            strokes: strokes,
+            seams: seams,
+            outline: outline,
+            provenance: provenance,
+        });
+    }
+
+    pub fn write(&self, filename: &str) -> Result<(), String>
+    {
+        return fs::write(filename, self.to_json()).map_err(|e| format!("Unable to write artifact {}! {}", filename, e));
+    }
+
+    pub fn read(filename: &str) -> Result<Self, String>
+    {
+        let json = fs::read_to_string(filename).map_err(|e| format!("Failed to read artifact {}! {}", filename, e))?;
+        return Self::from_json(&json);
+    }
+
+    // The finished stroke, once postprocess has run.
+    pub fn into_result(self) -> Result<StrokeResult, String>
+    {
+        if self.stage != Stage::Postprocess {
+            return Err(format!("The artifact only got as far as {}, it needs to get through postprocess first.", self.stage.name()));
+        }
+
+        let joins = joins::measure_joins(&self.path, &self.outline, joins::half_width(&self.pattern, self.pattern_scale.y));
+        return Ok(StrokeResult { outline: self.outline, provenance: self.provenance, auto_width: self.auto_width, joins: joins });
+    }
+}
@@ -0,0 +1,81 @@
+// The XML property lists a UFO keeps everything but its glyphs in: layercontents.plist, every layer's
+// contents.plist, fontinfo.plist and the lib inside a glif. We only need as much of the format as those use, dicts,
+// arrays and the strings and numbers in them, so this reads them as plain elements rather than pulling in a whole
+// plist parser, and everything that reads a dict goes through dict_entries.
+use std::fs;
+use std::path::Path;
+use xmltree::Element;
+use xmlwriter::*;
+
+pub fn read_plist(path: &Path) -> Result<Element, String>
+{
+    let contents = fs::read_to_string(path).map_err(|e| format!("Failed to read {}! {}", path.display(), e))?;
+    return Element::parse(contents.as_bytes()).map_err(|e| format!("Failed to parse {}! {}", path.display(), e));
+}
+
+// Writes a plist whose top level element write_body puts in.
+pub fn write_plist(path: &Path, write_body: impl Fn(&mut XmlWriter)) -> Result<(), String>
+{
+    let mut writer = XmlWriter::new(Options::default());
+    writer.write_declaration();
+    writer.start_element("plist");
+    writer.write_attribute("version", "1.0");
+    write_body(&mut writer);
+    writer.end_element();
+
+    return fs::write(path, writer.end_document()).map_err(|e| format!("Unable to write {}! {}", path.display(), e));
+}
+
+// The elements directly inside element, skipping text and comments.
+pub fn child_elements(element: &Element) -> Vec<&Element>
+{
+    return element.children.iter().filter_map(|c| c.as_element()).collect();
+}
+
+// A dict's entries in order as (key, value). Dicts are a flat run of <key> elements each followed by its value,
+// which can be anything, a dict of its own included. Errors are worded to follow the name of whatever the dict
+// came from.
+pub fn dict_entries(dict: &Element) -> Result<Vec<(String, &Element)>, String>
+{
+    let elements = child_elements(dict);
+    let mut entries = Vec::new();
+    for pair in elements.chunks(2) {
+        if pair.len() != 2 || pair[0].name != "key" {
+            return Err(String::from("isn't a dict of keys each followed by a value."));
+        }
+        entries.push((text(pair[0]), pair[1]));
+    }
+
+    return Ok(entries);
+}
+
+// What's in a <string>, <integer> or <real>, or any other element that's only text.
+pub fn text(element: &Element) -> String
+{
+    return element.get_text().unwrap_or_default().to_string();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dicts_read_in_order()
+    {
+        let plist = Element::parse(r#"<plist version="1.0"><dict>
+            <key>a</key><string>one</string>
+            <!-- comments don't count -->
+            <key>b</key><integer>2</integer>
+            <key>c</key><dict><key>d</key><real>0.5</real></dict>
+        </dict></plist>"#.as_bytes()).unwrap();
+        let entries = dict_entries(plist.get_child("dict").unwrap()).unwrap();
+
+        let keys: Vec<&str> = entries.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(keys, vec!["a", "b", "c"]);
+        assert_eq!((text(entries[0].1), text(entries[1].1)), (String::from("one"), String::from("2")));
+        assert_eq!(dict_entries(entries[2].1).unwrap()[0].0, "d");
+
+        let broken = Element::parse("<dict><key>a</key><string>one</string><string>two</string></dict>".as_bytes()).unwrap();
+        assert!(dict_entries(&broken).is_err());
+    }
+}
@@ -0,0 +1,188 @@
+// Carries the names of skeleton points over to the stroked output. Before stroking we note where each named point
+// sits on the skeleton, afterwards we find the closest spot on the output contours that came from the same skeleton
+// contour and name the on-curve point there, adding one if there isn't one close enough.
+use crate::qmath::*;
+use crate::pattern_along_path::StrokeResult;
+use glifparser::{Outline, PointType};
+
+pub struct NamedLocation {
+    pub name: String,
+    pub contour: usize,
+    pub segment: usize,
+    pub t: f64,
+}
+
+// Point i of a contour starts segment i of its Piecewise, except the last point of an open contour which only ends
+// the segment before it.
+pub fn named_locations<U>(outline: &Outline<U>) -> Vec<NamedLocation>
+{
+    let mut output = Vec::new();
+
+    for (contour_idx, contour) in outline.iter().enumerate() {
+        let open = contour.first().map_or(false, |p| p.ptype == PointType::Move);
+
+        for (point_idx, point) in contour.iter().enumerate() {
+            let name = match &point.name {
+                Some(name) => name.clone(),
+                None => continue,
+            };
+
+            let (segment, t) = if open && point_idx + 1 == contour.len() && point_idx > 0 {
+                (point_idx - 1, 1.)
+            } else {
+                (point_idx, 0.)
+            };
+
+            output.push(NamedLocation { name: name, contour: contour_idx, segment: segment, t: t });
+        }
+    }
+
+    return output;
+}
+
+// Finds a home for each name on the stroked outline, splitting curves where needed so every name lands on an
+// on-curve point. Returns where each name ended up along with warnings for the ones we couldn't place.
+pub fn place_point_names(result: &mut StrokeResult, skeleton: &Piecewise<Piecewise<Bezier>>, names: &[NamedLocation], tolerance: f64) -> (Vec<(String, Vector)>, Vec<String>)
+{
+    let mut placements = Vec::new();
+    let mut warnings = Vec::new();
+
+    for location in names {
+        let target = match skeleton.get(location.contour).and_then(|c| c.get(location.segment)) {
+            Some(bez) => bez.evaluate(location.t),
+            None => {
+                warnings.push(format!("Couldn't place point name {}, its contour has no segments.", location.name));
+                continue;
+            }
+        };
+
+        // the closest spot on any output contour that came from the same skeleton contour
+        let mut best: Option<(usize, usize, f64, f64)> = None;
+        for (contour_idx, contour) in result.outline.segs().enumerate() {
+            if !result.provenance[contour_idx].contains(&location.contour) { continue; }
+
+            if let Some((curve_idx, t, distance)) = contour.nearest(target) {
+                if best.map_or(true, |b| distance < b.3) {
+                    best = Some((contour_idx, curve_idx, t, distance));
+                }
+            }
+        }
+
+        let (contour_idx, curve_idx, t, _) = match best {
+            Some(b) => b,
+            None => {
+                warnings.push(format!("Couldn't place point name {}, nothing in the output came from its contour.", location.name));
+                continue;
+            }
+        };
+
+        let contour = &mut result.outline[contour_idx];
+        let bez = &contour[curve_idx];
+        let start = bez.evaluate(0.);
+        let end = bez.evaluate(1.);
+        let spot = bez.evaluate(t);
+
+        let position = if spot.distance(start) <= tolerance {
+            start
+        } else if spot.distance(end) <= tolerance {
+            end
+        } else {
+            let (first, second) = bez.subdivide(t);
+            contour.replace_segment(curve_idx, vec![first, second]).expect("Splitting a curve can't open a gap.");
+            spot
+        };
+
+        placements.push((location.name.clone(), position));
+    }
+
+    return (placements, warnings);
+}
+
+// Names the on-curve point closest to each placement. Run this on the final outline, after any rounding.
+pub fn apply_point_names<T>(outline: &mut Outline<T>, placements: &[(String, Vector)])
+{
+    for (name, position) in placements {
+        let mut best: Option<(usize, usize, f64)> = None;
+
+        for (contour_idx, contour) in outline.iter().enumerate() {
+            for (point_idx, point) in contour.iter().enumerate() {
+                if point.ptype == PointType::OffCurve { continue; }
+
+                let distance = Vector::from_point(point).distance(*position);
+                if best.map_or(true, |b| distance < b.2) {
+                    best = Some((contour_idx, point_idx, distance));
+                }
+            }
+        }
+
+        if let Some((contour_idx, point_idx, _)) = best {
+            outline[contour_idx][point_idx].name = Some(name.clone());
+        }
+    }
+}
+
+// The names already on an outline and where they are, for carrying them through something that rebuilds it.
+pub fn collect_point_names<T>(outline: &Outline<T>) -> Vec<(String, Vector)>
+{
+    let mut output = Vec::new();
+    for contour in outline {
+        for point in contour {
+            if let Some(name) = &point.name {
+                output.push((name.clone(), Vector::from_point(point)));
+            }
+        }
+    }
+
+    return output;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern_along_path::{stroke_outline, PatternSettings};
+    use glifparser::Glif;
+
+    const SKELETON: &str = r#"<?xml version='1.0' encoding='UTF-8'?>
+<glyph name="named" format="2">
+  <advance width="500"/>
+  <outline>
+    <contour>
+      <point x="100" y="0" type="move" name="entry"/>
+      <point x="100" y="300" type="line"/>
+      <point x="400" y="300" type="line" name="exit"/>
+    </contour>
+    <contour>
+      <point x="250" y="500" type="move" name="lonely"/>
+    </contour>
+  </outline>
+</glyph>"#;
+
+    // The entry and exit of a stroke 20 wide land in the middle of its butt ends, where there's no point until we
+    // put one there. A name on a contour that strokes to nothing can't go anywhere.
+    #[test]
+    fn names_follow_the_stroke()
+    {
+        let glif: Glif<Option<PointData>> = glifparser::read_ufo_glif(SKELETON);
+        let outline = glif.outline.unwrap();
+        let names = named_locations(&outline);
+        let found: Vec<(&str, usize, usize, f64)> = names.iter().map(|n| (n.name.as_str(), n.contour, n.segment, n.t)).collect();
+        assert_eq!(found, vec![("entry", 0, 0, 0.), ("exit", 0, 1, 1.), ("lonely", 1, 0, 0.)]);
+
+        let skeleton = Piecewise::from_outline(&outline);
+        let pattern = Piecewise::rect(Rect { left: 0., bottom: -10., right: 20., top: 10. });
+        let mut result = stroke_outline(&skeleton, &pattern, &PatternSettings { stretch: true, ..PatternSettings::default() });
+
+        let (placements, warnings) = place_point_names(&mut result, &skeleton, &names, 1.);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("lonely"), "{}", warnings[0]);
+
+        let mut stroked = result.outline.to_outline();
+        apply_point_names(&mut stroked, &placements);
+        let named = collect_point_names(&stroked);
+        assert_eq!(named.len(), 2);
+        for (name, want) in [("entry", Vector { x: 100., y: 0. }), ("exit", Vector { x: 400., y: 300. })].iter() {
+            let (_, at) = named.iter().find(|(n, _)| n == name).unwrap();
+            assert!(at.is_near(*want, 1e-6), "{} at {:?}", name, at);
+        }
+    }
+}
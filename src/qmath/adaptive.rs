@@ -0,0 +1,164 @@
+use super::*;
+
+// how finely we integrate along each curve
+const INTEGRATION_STEPS: usize = 1024;
+// turns at joints smaller than this, in radians, are just rounding and don't get a sample of their own
+const CORNER_ANGLE: f64 = 1e-6;
+
+// First and second derivatives from the coefficients, Bezier's derivative is a backwards finite difference.
+fn derivatives(bez: &Bezier, t: f64) -> (Vector, Vector)
+{
+    let d1 = Vector { x: 3. * bez.A * t * t + 2. * bez.B * t + bez.C, y: 3. * bez.E * t * t + 2. * bez.F * t + bez.G };
+    let d2 = Vector { x: 6. * bez.A * t + 2. * bez.B, y: 6. * bez.E * t + 2. * bez.F };
+    return (d1, d2);
+}
+
+// how fast the curve moves and how fast its tangent turns, both per unit t. Where the curve stops, like at the
+// ends of our lines, it isn't turning.
+fn rates(bez: &Bezier, t: f64) -> (f64, f64)
+{
+    let (d1, d2) = derivatives(bez, t);
+    let speed_squared = d1.x * d1.x + d1.y * d1.y;
+    if speed_squared < 1e-18 { return (0., 0.); }
+
+    return (speed_squared.sqrt(), (d1.x * d2.y - d1.y * d2.x).abs() / speed_squared);
+}
+
+impl Piecewise<Bezier>
+{
+    // Arc length positions along the contour, from 0 to its total length, spaced so that between any two in a row
+    // the tangent turns by at most max_angle_step_deg and the path covers at most max_length_step. We integrate
+    // the length and the absolute turning (curvature over arc length) along each curve and drop a sample wherever
+    // either integral is about to go over its step, so straights get a sample every max_length_step and tight
+    // bends get as many as they need.
+    //
+    // Joints where the path turns sharply get a sample of their own. A corner turns all at once so it can't be
+    // split up, the turn across one isn't counted towards either side.
+    pub fn adaptive_samples(&self, max_angle_step_deg: f64, max_length_step: f64) -> Vec<f64>
+    {
+        let max_angle = max_angle_step_deg.to_radians();
+        let mut samples = vec![0.];
+
+        let mut s = 0.;
+        // how far and how much we've turned since the last sample
+        let mut since_length = 0.;
+        let mut since_angle = 0.;
+        let mut last_tangent: Option<Vector> = None;
+
+        for bez in &self.curves {
+            let cp = bez.to_control_points();
+
+            // the direction the curve leaves its start in, lines have their handles on their ends
+            let start_tangent = [cp[1], cp[2], cp[3]].iter()
+                .map(|p| *p + -cp[0])
+                .find(|d| d.magnitude() > 1e-9)
+                .map(|d| d.normalize());
+
+            if let (Some(last), Some(start)) = (last_tangent, start_tangent) {
+                let turn = (last.x * start.y - last.y * start.x).atan2(last.x * start.x + last.y * start.y).abs();
+                if turn > CORNER_ANGLE && s > *samples.last().unwrap() {
+                    samples.push(s);
+                    since_length = 0.;
+                    since_angle = 0.;
+                }
+            }
+
+            let dt = 1. / INTEGRATION_STEPS as f64;
+            let mut previous = rates(bez, 0.);
+            for i in 0..INTEGRATION_STEPS {
+                let next = rates(bez, (i + 1) as f64 * dt);
+                let step_length = (previous.0 + next.0) / 2. * dt;
+                let step_angle = (previous.1 + next.1) / 2. * dt;
+                previous = next;
+
+                // both grow steadily across the step so we can interpolate where each runs out
+                let mut used = 0.;
+                loop {
+                    let remaining_length = step_length * (1. - used);
+                    let remaining_angle = step_angle * (1. - used);
+
+                    let by_length = if remaining_length > 0. && max_length_step > 0. { (max_length_step - since_length) / step_length } else { f64::INFINITY };
+                    let by_angle = if remaining_angle > 0. && max_angle > 0. { (max_angle - since_angle) / step_angle } else { f64::INFINITY };
+                    let room = f64::min(by_length, by_angle);
+
+                    if used + room >= 1. {
+                        since_length += remaining_length;
+                        since_angle += remaining_angle;
+                        break;
+                    }
+
+                    used += f64::max(room, 0.);
+                    let sample = s + step_length * used;
+                    if sample > *samples.last().unwrap() { samples.push(sample); }
+                    since_length = 0.;
+                    since_angle = 0.;
+                }
+
+                s += step_length;
+            }
+
+            let end_tangent = [cp[2], cp[1], cp[0]].iter()
+                .map(|p| cp[3] + -*p)
+                .find(|d| d.magnitude() > 1e-9)
+                .map(|d| d.normalize());
+            if end_tangent.is_some() { last_tangent = end_tangent; }
+        }
+
+        if s > *samples.last().unwrap() { samples.push(s); }
+        return samples;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(x: f64, y: f64) -> Vector
+    {
+        return Vector { x: x, y: y };
+    }
+
+    // A long straight running smoothly into a cubic that loops over itself.
+    fn straight_then_loop() -> Piecewise<Bezier>
+    {
+        let along = v(1.5, 1.).normalize() * 1000.;
+        return Piecewise::new(vec![
+            Bezier::from_control_points(-along, -along, v(0., 0.), v(0., 0.)),
+            Bezier::from_control_points(v(0., 0.), v(150., 100.), v(-50., 100.), v(100., 0.)),
+        ]);
+    }
+
+    #[test]
+    fn turn_and_length_steps_hold()
+    {
+        let path = straight_then_loop();
+        let measure = PathMeasure::new(&path);
+        let samples = path.adaptive_samples(10., 50.);
+
+        assert_eq!(samples[0], 0.);
+        assert!((samples.last().unwrap() - measure.length()).abs() < 1e-3);
+
+        let angle = |a: Vector, b: Vector| (a.x * b.y - a.y * b.x).atan2(a.x * b.x + a.y * b.y).abs();
+        for pair in samples.windows(2) {
+            assert!(pair[1] > pair[0]);
+            assert!(pair[1] - pair[0] <= 50. + 1e-6, "{:?}", pair);
+
+            // add up the turning between the two in small steps rather than comparing the ends, the loop can turn
+            // back on itself
+            let mut turned = 0.;
+            let mut last = measure.pos_tan_at(pair[0]).1;
+            for k in 1..=200 {
+                let tangent = measure.pos_tan_at(pair[0] + (pair[1] - pair[0]) * k as f64 / 200.).1;
+                turned += angle(last, tangent);
+                last = tangent;
+            }
+            assert!(turned.to_degrees() <= 10.01, "{:?} turns {}", pair, turned.to_degrees());
+        }
+
+        // the straight only needs its length steps, the loop is much more crowded
+        let on_straight = samples.iter().filter(|s| **s <= 1000.).count();
+        assert_eq!(on_straight, 21);
+        let in_loop = samples.len() - on_straight;
+        assert!(in_loop as f64 / (measure.length() - 1000.) > 4. * on_straight as f64 / 1000., "{}", in_loop);
+    }
+}
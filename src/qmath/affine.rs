@@ -0,0 +1,225 @@
+use super::*;
+
+// A 2x3 affine transform in SVG's order: [a b c d e f] maps (x, y) to (ax + cy + e, bx + dy + f).
+//
+// Multiplying composes them like matrices do, a * b is b first and then a. Angles are in radians and rotations
+// are counter-clockwise in y-up space.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Affine {
+    pub matrix: [f64; 6],
+}
+
+impl Affine {
+    pub fn identity() -> Self
+    {
+        return Affine { matrix: [1., 0., 0., 1., 0., 0.] };
+    }
+
+    pub fn translate(x: f64, y: f64) -> Self
+    {
+        return Affine { matrix: [1., 0., 0., 1., x, y] };
+    }
+
+    pub fn scale(x: f64, y: f64) -> Self
+    {
+        return Affine { matrix: [x, 0., 0., y, 0., 0.] };
+    }
+
+    pub fn rotate_about(angle: f64, center: Vector) -> Self
+    {
+        let (sin, cos) = angle.sin_cos();
+        let rotate = Affine { matrix: [cos, sin, -sin, cos, 0., 0.] };
+        return Affine::translate(center.x, center.y) * rotate * Affine::translate(-center.x, -center.y);
+    }
+
+    // x_angle leans vertical lines over, like an oblique, and y_angle does the same to horizontal ones.
+    pub fn skew(x_angle: f64, y_angle: f64) -> Self
+    {
+        return Affine { matrix: [1., y_angle.tan(), x_angle.tan(), 1., 0., 0.] };
+    }
+
+    // Reflects across the line through p going along d. The identity if d is zero, there's no line to reflect in.
+    pub fn mirror_about_line(p: Vector, d: Vector) -> Self
+    {
+        let d = match d.try_normalize() {
+            Some(d) => d,
+            None => return Affine::identity(),
+        };
+
+        let (cos, sin) = (d.x * d.x - d.y * d.y, 2. * d.x * d.y);
+        let reflect = Affine { matrix: [cos, sin, sin, -cos, 0., 0.] };
+        return Affine::translate(p.x, p.y) * reflect * Affine::translate(-p.x, -p.y);
+    }
+
+    pub fn apply(&self, v: Vector) -> Vector
+    {
+        let m = self.matrix;
+        return Vector { x: m[0] * v.x + m[2] * v.y + m[4], y: m[1] * v.x + m[3] * v.y + m[5] };
+    }
+
+    // None when the transform squashes everything onto a line or a point and can't be undone.
+    pub fn inverse(&self) -> Option<Self>
+    {
+        let [a, b, c, d, e, f] = self.matrix;
+        let det = a * d - b * c;
+        if det.abs() <= 1e-12 || !det.is_finite() { return None; }
+
+        return Some(Affine { matrix: [
+            d / det, -b / det,
+            -c / det, a / det,
+            (c * f - d * e) / det, (b * e - a * f) / det,
+        ] });
+    }
+}
+
+impl std::ops::Mul<Affine> for Affine {
+    type Output = Affine;
+
+    fn mul(self, other: Affine) -> Affine
+    {
+        let m = self.matrix;
+        let n = other.matrix;
+        return Affine { matrix: [
+            m[0] * n[0] + m[2] * n[1], m[1] * n[0] + m[3] * n[1],
+            m[0] * n[2] + m[2] * n[3], m[1] * n[2] + m[3] * n[3],
+            m[0] * n[4] + m[2] * n[5] + m[4], m[1] * n[4] + m[3] * n[5] + m[5],
+        ] };
+    }
+}
+
+impl<T: Evaluate> Piecewise<T> {
+    // Cubics transform exactly through their control points, so this is the same shape apply_transform would give.
+    pub fn transform(&self, transform: &Affine) -> Self
+    {
+        return self.apply_transform(|v: &Vector| transform.apply(*v));
+    }
+}
+
+// In place versions of the above for when the original isn't needed afterwards. transform builds a whole new
+// Piecewise, which adds up over a font's worth of contours, these only write over the curves that are there.
+
+impl Bezier {
+    // An affine transform is linear in the coordinates, so it goes straight through the coefficients without a
+    // trip to the control points and back. The translation only lands on the constant terms.
+    pub fn transform_mut(&mut self, transform: &Affine)
+    {
+        let [a, b, c, d, e, f] = transform.matrix;
+        let (x, y) = ([self.A, self.B, self.C, self.D], [self.E, self.F, self.G, self.H]);
+
+        self.A = a * x[0] + c * y[0];
+        self.B = a * x[1] + c * y[1];
+        self.C = a * x[2] + c * y[2];
+        self.D = a * x[3] + c * y[3] + e;
+        self.E = b * x[0] + d * y[0];
+        self.F = b * x[1] + d * y[1];
+        self.G = b * x[2] + d * y[2];
+        self.H = b * x[3] + d * y[3] + f;
+    }
+}
+
+impl Piecewise<Bezier>
+{
+    pub fn transform_mut(&mut self, transform: &Affine)
+    {
+        for bez in self.curves.iter_mut() {
+            bez.transform_mut(transform);
+        }
+    }
+
+    // apply_transform without building a new Piecewise.
+    pub fn apply_transform_mut<F>(&mut self, transform: F) where F: Fn(&Vector) -> Vector
+    {
+        for bez in self.curves.iter_mut() {
+            *bez = bez.apply_transform(&transform);
+        }
+    }
+}
+
+impl Piecewise<Piecewise<Bezier>>
+{
+    pub fn transform_mut(&mut self, transform: &Affine)
+    {
+        for contour in self.curves.iter_mut() {
+            contour.transform_mut(transform);
+        }
+    }
+
+    pub fn apply_transform_mut<F>(&mut self, transform: F) where F: Fn(&Vector) -> Vector
+    {
+        for contour in self.curves.iter_mut() {
+            contour.apply_transform_mut(&transform);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(x: f64, y: f64) -> Vector
+    {
+        return Vector { x: x, y: y };
+    }
+
+    fn s_curve() -> Piecewise<Bezier>
+    {
+        return Piecewise::new(vec![
+            Bezier::from_control_points(v(100., 0.), v(500., 0.), v(500., 350.), v(300., 350.)),
+            Bezier::from_control_points(v(300., 350.), v(100., 350.), v(100., 700.), v(500., 700.)),
+        ]);
+    }
+
+    fn transforms() -> Vec<Affine>
+    {
+        return vec![
+            Affine::translate(-30., 12.5),
+            Affine::scale(2., -0.5),
+            Affine::rotate_about(0.7, v(250., 350.)),
+            Affine::skew(0.2, -0.1),
+            Affine::mirror_about_line(v(10., 20.), v(1., 3.)),
+            Affine::rotate_about(2.1, v(-40., 5.)) * Affine::skew(0.3, 0.) * Affine::scale(1.5, 3.) * Affine::translate(7., -900.),
+        ];
+    }
+
+    #[test]
+    fn inverse_undoes_the_transform()
+    {
+        let curve = s_curve();
+        for transform in transforms() {
+            let inverse = transform.inverse().unwrap();
+            let there_and_back = curve.transform(&transform).transform(&inverse);
+
+            for (a, b) in curve.segs().zip(there_and_back.segs()) {
+                for (p, q) in a.to_control_points().iter().zip(b.to_control_points().iter()) {
+                    assert!(p.is_near(*q, 1e-9), "{:?} came back as {:?} through {:?}", p, q, transform);
+                }
+            }
+
+            for m in (transform * inverse).matrix.iter().zip(Affine::identity().matrix.iter()) {
+                assert!((m.0 - m.1).abs() < 1e-9, "{:?}", transform);
+            }
+        }
+
+        assert!(Affine::scale(0., 1.).inverse().is_none());
+    }
+
+    // a * b is b first, and the in place version agrees with going through the control points.
+    #[test]
+    fn composition_order_and_in_place()
+    {
+        let p = v(3., 4.);
+        assert_eq!((Affine::translate(10., 0.) * Affine::scale(2., 2.)).apply(p), v(16., 8.));
+        assert_eq!((Affine::scale(2., 2.) * Affine::translate(10., 0.)).apply(p), v(26., 8.));
+        assert!(Affine::rotate_about(std::f64::consts::FRAC_PI_2, v(1., 1.)).apply(v(2., 1.)).is_near(v(1., 2.), 1e-12));
+
+        for transform in transforms() {
+            let mut in_place = s_curve();
+            in_place.transform_mut(&transform);
+            let copied = s_curve().transform(&transform);
+            for i in 0..=20 {
+                let t = i as f64 / 20.;
+                assert!(in_place.evaluate(t).is_near(copied.evaluate(t), 1e-9));
+            }
+        }
+    }
+}
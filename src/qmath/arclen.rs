@@ -0,0 +1,86 @@
+use super::*;
+
+// Arc length by Gauss-Legendre quadrature on the curve's speed. A single five point rule is exact for smooth
+// stretches, so we compare it against the two halves and keep halving wherever they disagree by more than the
+// tolerance allows. That puts the effort where the speed changes fast, around cusps and tight loops.
+
+// five point Gauss-Legendre on [-1, 1]
+const GAUSS_NODES: [f64; 5] = [0., -0.5384693101056831, 0.5384693101056831, -0.9061798459386640, 0.9061798459386640];
+const GAUSS_WEIGHTS: [f64; 5] = [0.5688888888888889, 0.4786286704993665, 0.4786286704993665, 0.2369268850561891, 0.2369268850561891];
+
+// past this many halvings we take what we've got, the piece is a millionth of the curve
+const MAX_ARCLEN_DEPTH: usize = 20;
+
+fn speed(bez: &Bezier, t: f64) -> f64
+{
+    let x = 3. * bez.A * t * t + 2. * bez.B * t + bez.C;
+    let y = 3. * bez.E * t * t + 2. * bez.F * t + bez.G;
+    return (x * x + y * y).sqrt();
+}
+
+fn gauss(bez: &Bezier, a: f64, b: f64) -> f64
+{
+    let half = (b - a) / 2.;
+    let mid = (a + b) / 2.;
+    return half * GAUSS_NODES.iter().zip(GAUSS_WEIGHTS.iter()).map(|(x, w)| w * speed(bez, mid + half * x)).sum::<f64>();
+}
+
+fn adaptive(bez: &Bezier, a: f64, b: f64, whole: f64, tolerance: f64, depth: usize) -> f64
+{
+    let mid = (a + b) / 2.;
+    let left = gauss(bez, a, mid);
+    let right = gauss(bez, mid, b);
+
+    if depth >= MAX_ARCLEN_DEPTH || (left + right - whole).abs() <= tolerance {
+        return left + right;
+    }
+
+    return adaptive(bez, a, mid, left, tolerance / 2., depth + 1) + adaptive(bez, mid, b, right, tolerance / 2., depth + 1);
+}
+
+impl Bezier {
+    // The length of the curve to within about tolerance.
+    pub fn arclen(&self, tolerance: f64) -> f64
+    {
+        return self.arclen_between(0., 1., tolerance);
+    }
+
+    // The length of the curve between t0 and t1, which can come in either order.
+    pub fn arclen_between(&self, t0: f64, t1: f64, tolerance: f64) -> f64
+    {
+        let (a, b) = if t0 <= t1 { (t0, t1) } else { (t1, t0) };
+        if a == b { return 0.; }
+
+        return adaptive(self, a, b, gauss(self, a, b), tolerance, 0);
+    }
+}
+
+impl Piecewise<Bezier>
+{
+    // The contour's length, each curve gets its share of the tolerance.
+    pub fn arclen(&self, tolerance: f64) -> f64
+    {
+        return self.arclen_between(0., 1., tolerance);
+    }
+
+    // The length along the contour between global t0 and t1, in either order. This doesn't go round the start of a
+    // closed contour, cut the contour first for that.
+    pub fn arclen_between(&self, t0: f64, t1: f64, tolerance: f64) -> f64
+    {
+        let n = self.curves.len();
+        if n == 0 { return 0.; }
+
+        let (a, b) = if t0 <= t1 { (t0, t1) } else { (t1, t0) };
+        let (a, b) = (a * n as f64, b * n as f64);
+        let share = tolerance / n as f64;
+
+        let mut total = 0.;
+        for (i, bez) in self.curves.iter().enumerate() {
+            let start = f64::max(a - i as f64, 0.);
+            let end = f64::min(b - i as f64, 1.);
+            if end > start { total += bez.arclen_between(start, end, share); }
+        }
+
+        return total;
+    }
+}
@@ -0,0 +1,353 @@
+use super::*;
+
+// Which way a contour goes around in y-up space. Contours with next to no area, a line doubling back over itself
+// or a figure eight whose halves cancel, don't go around either way.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "kebab-case"))]
+pub enum WindingDirection {
+    Clockwise,
+    CounterClockwise,
+    Degenerate,
+}
+
+// contours with less area than this count as degenerate
+const DEGENERATE_AREA: f64 = 1e-9;
+
+// Integrates p(t) * q(t) over 0-1 where both polynomials are given as coefficients in ascending order.
+fn integrate_product(p: &[f64], q: &[f64]) -> f64
+{
+    let mut sum = 0.;
+    for (i, a) in p.iter().enumerate() {
+        for (j, b) in q.iter().enumerate() {
+            sum = sum + a * b / (i + j + 1) as f64;
+        }
+    }
+
+    return sum;
+}
+
+// The coefficients of p(t) * q(t), both in ascending order.
+fn multiply(p: &[f64], q: &[f64]) -> Vec<f64>
+{
+    let mut output = vec![0.; p.len() + q.len() - 1];
+    for (i, a) in p.iter().enumerate() {
+        for (j, b) in q.iter().enumerate() {
+            output[i + j] = output[i + j] + a * b;
+        }
+    }
+
+    return output;
+}
+
+// below this much area a contour has no centroid worth talking about
+const CENTROID_AREA: f64 = 1e-12;
+// how closely perimeter measures the curves, see arclen.rs
+const PERIMETER_TOLERANCE: f64 = 1e-6;
+
+impl Bezier {
+    // This curve's contribution to the signed area of the contour it's part of. It's Green's theorem
+    // (1/2 ∫ x dy - y dx) evaluated exactly over our polynomial coefficients so there's no sampling involved.
+    pub fn signed_area(&self) -> f64
+    {
+        let x = [self.D, self.C, self.B, self.A];
+        let y = [self.H, self.G, self.F, self.E];
+        let dx = [self.C, 2. * self.B, 3. * self.A];
+        let dy = [self.G, 2. * self.F, 3. * self.E];
+
+        return 0.5 * (integrate_product(&x, &dy) - integrate_product(&y, &dx));
+    }
+
+    // This curve's contribution to the first moments of area of its contour, the integrals of x and y over the
+    // area inside. Green's theorem again, as 1/2 ∫ x² dy and -1/2 ∫ y² dx.
+    fn area_moments(&self) -> Vector
+    {
+        let x = [self.D, self.C, self.B, self.A];
+        let y = [self.H, self.G, self.F, self.E];
+        let dx = [self.C, 2. * self.B, 3. * self.A];
+        let dy = [self.G, 2. * self.F, 3. * self.E];
+
+        return Vector { x: 0.5 * integrate_product(&multiply(&x, &x), &dy), y: -0.5 * integrate_product(&multiply(&y, &y), &dx) };
+    }
+}
+
+impl Piecewise<Bezier>
+{
+    // Positive for counter-clockwise contours and negative for clockwise ones in a y-up coordinate system.
+    // Open contours are treated as if they were closed by a straight line.
+    pub fn signed_area(&self) -> f64
+    {
+        let mut area = 0.;
+        for bez in &self.curves {
+            area = area + bez.signed_area();
+        }
+
+        if let (Some(first), Some(last)) = (self.curves.first(), self.curves.last()) {
+            let start = first.to_control_points()[0];
+            let end = last.to_control_points()[3];
+            area = area + 0.5 * (end.x * start.y - start.x * end.y);
+        }
+
+        return area;
+    }
+
+    // The area inside the contour whichever way it goes, open contours closed by a straight line.
+    pub fn area(&self) -> f64
+    {
+        return self.signed_area().abs();
+    }
+
+    // The first moments of area with the same sign as signed_area.
+    fn area_moments(&self) -> Vector
+    {
+        let mut moments = Vector { x: 0., y: 0. };
+        for bez in &self.curves {
+            moments = moments + bez.area_moments();
+        }
+
+        if let Some(closing) = self.closing_line() {
+            moments = moments + closing.area_moments();
+        }
+
+        return moments;
+    }
+
+    // The straight line from the end of an open contour back to its start, None if it's closed or empty.
+    fn closing_line(&self) -> Option<Bezier>
+    {
+        let start = self.curves.first()?.to_control_points()[0];
+        let end = self.curves.last()?.to_control_points()[3];
+        if end == start { return None; }
+
+        return Some(Bezier::from_control_points(end, end, start, start));
+    }
+
+    // The center of the area inside the contour. None for a contour with no area, a figure eight whose halves
+    // cancel out counts as having none.
+    pub fn centroid(&self) -> Option<Vector>
+    {
+        let area = self.signed_area();
+        if area.abs() <= CENTROID_AREA { return None; }
+
+        return Some(self.area_moments() * (1. / area));
+    }
+
+    // How far it is around the contour, open contours include the straight line back to their start.
+    pub fn perimeter(&self) -> f64
+    {
+        let closing = self.closing_line().map_or(0., |bez| bez.arclen(PERIMETER_TOLERANCE));
+        return self.arclen(PERIMETER_TOLERANCE) + closing;
+    }
+
+    pub fn winding_direction(&self) -> WindingDirection
+    {
+        let area = self.signed_area();
+        if area.abs() <= DEGENERATE_AREA { return WindingDirection::Degenerate; }
+        return if area > 0. { WindingDirection::CounterClockwise } else { WindingDirection::Clockwise };
+    }
+}
+
+impl Piecewise<Piecewise<Bezier>>
+{
+    // Reverses contours so the outers go counter-clockwise and the holes clockwise, the way glifs are meant to be
+    // drawn. A contour is a hole when it's inside an odd number of the others, which we find from a point on it.
+    // Degenerate contours are left as they are. This assumes contours don't cross each other, overlapping ones need
+    // simplifying first.
+    pub fn fix_winding(&self) -> Piecewise<Piecewise<Bezier>>
+    {
+        return self.fix_winding_with_fill_rule(FillRule::NonZero);
+    }
+
+    // fix_winding with what's inside a contour going by fill_rule. That only matters for a contour that overlaps
+    // itself: where it goes round a point twice the point is inside it under nonzero, but not under even-odd.
+    pub fn fix_winding_with_fill_rule(&self, fill_rule: FillRule) -> Piecewise<Piecewise<Bezier>>
+    {
+        let output = self.curves.iter().zip(self.winding_fixes(fill_rule))
+            .map(|(contour, reverse)| if reverse { contour.reverse() } else { contour.clone() })
+            .collect();
+
+        return Piecewise { curves: output };
+    }
+
+    // How many of the others contour i is inside of, its depth in the hierarchy of contours. Even depths are outer
+    // contours and odd ones holes.
+    pub fn depth(&self, i: usize, fill_rule: FillRule) -> usize
+    {
+        let contour = &self.curves[i];
+        if contour.curves.is_empty() { return 0; }
+
+        let sample = contour.curves[0].evaluate(0.);
+        return self.curves.iter().enumerate()
+            .filter(|(j, other)| *j != i && !other.curves.is_empty() && fill_rule.is_filled(other.winding_number(sample)))
+            .count();
+    }
+
+    // Which contours fix_winding turns around.
+    pub(super) fn winding_fixes(&self, fill_rule: FillRule) -> Vec<bool>
+    {
+        let mut output = Vec::new();
+        for (i, contour) in self.curves.iter().enumerate() {
+            if contour.curves.is_empty() {
+                output.push(false);
+                continue;
+            }
+
+            let depth = self.depth(i, fill_rule);
+            let wanted = if depth % 2 == 0 { WindingDirection::CounterClockwise } else { WindingDirection::Clockwise };
+            output.push(match contour.winding_direction() {
+                WindingDirection::Degenerate => false,
+                direction => direction != wanted,
+            });
+        }
+
+        return output;
+    }
+
+    // The area covered by the outline under the given fill rule. We go contour by contour: the other contours
+    // give us the winding just outside this one and its own direction tells us the winding just inside, so the
+    // contour's area counts if it flips the point from unfilled to filled and subtracts if it does the opposite.
+    // This is exact as long as contours don't cross each other, overlapping ones need simplifying first.
+    pub fn filled_area(&self, fill_rule: FillRule) -> f64
+    {
+        let mut area = 0.;
+        for i in 0..self.curves.len() {
+            area = area + self.curves[i].area() * self.contribution(i, fill_rule);
+        }
+
+        return area;
+    }
+
+    // 1 if contour i adds to the filled area, -1 if it cuts a hole and 0 if it makes no difference.
+    fn contribution(&self, i: usize, fill_rule: FillRule) -> f64
+    {
+        let contour = &self.curves[i];
+        if contour.curves.is_empty() { return 0.; }
+
+        let contour_area = contour.signed_area();
+        let sample = contour.curves[0].evaluate(0.);
+
+        let mut outside = 0;
+        for (j, other) in self.curves.iter().enumerate() {
+            if i != j { outside = outside + other.winding_number(sample); }
+        }
+
+        let inside = outside + if contour_area > 0. { 1 } else { -1 };
+
+        match (fill_rule.is_filled(outside), fill_rule.is_filled(inside)) {
+            (false, true) => 1.,
+            (true, false) => -1.,
+            _ => 0.,
+        }
+    }
+
+    // filled_area under nonzero, the black area a glyph would have.
+    pub fn area(&self) -> f64
+    {
+        return self.filled_area(FillRule::NonZero);
+    }
+
+    // The center of the filled area under nonzero, with holes taken out. None if nothing's filled. The same
+    // caveat as filled_area, overlapping contours need simplifying first.
+    pub fn centroid(&self) -> Option<Vector>
+    {
+        let mut area = 0.;
+        let mut moments = Vector { x: 0., y: 0. };
+        for (i, contour) in self.curves.iter().enumerate() {
+            let contribution = self.contribution(i, FillRule::NonZero);
+            if contribution == 0. { continue; }
+
+            // the contour's moments come signed by its direction, we want them signed by what it does to the fill
+            let sign = if contour.signed_area() > 0. { 1. } else { -1. };
+            area = area + contour.area() * contribution;
+            moments = moments + contour.area_moments() * (sign * contribution);
+        }
+
+        if area.abs() <= CENTROID_AREA { return None; }
+        return Some(moments * (1. / area));
+    }
+
+    // The length of every contour put together, holes included.
+    pub fn perimeter(&self) -> f64
+    {
+        return self.curves.iter().filter(|c| !c.curves.is_empty()).map(|c| c.perimeter()).sum();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    fn v(x: f64, y: f64) -> Vector
+    {
+        return Vector { x: x, y: y };
+    }
+
+    // One contour going counter-clockwise round a circle of radius 100 and then again round one of 50 inside it,
+    // joined by a line there and back. Outside it the winding is 0, between the circles 1 and inside both 2.
+    fn twice_round() -> Piecewise<Bezier>
+    {
+        let line = |a: Vector, b: Vector| Bezier::from_control_points(a, a, b, b);
+        let mut curves = Piecewise::arc(v(0., 0.), 100., 0., 2. * PI).curves;
+        curves.push(line(v(100., 0.), v(50., 0.)));
+        curves.extend(Piecewise::arc(v(0., 0.), 50., 0., 2. * PI).curves);
+        curves.push(line(v(50., 0.), v(100., 0.)));
+        return Piecewise::new(curves);
+    }
+
+    #[test]
+    fn fill_rules_differ_where_it_goes_round_twice()
+    {
+        let outline = Piecewise::new(vec![twice_round()]);
+        assert_eq!([outline.winding_number(v(200., 0.)), outline.winding_number(v(0., 75.)), outline.winding_number(v(0., 0.))], [0, 1, 2]);
+
+        assert!(outline.contains(v(0., 75.), FillRule::NonZero) && outline.contains(v(0., 75.), FillRule::EvenOdd));
+        assert!(outline.contains(v(0., 0.), FillRule::NonZero));
+        assert!(!outline.contains(v(0., 0.), FillRule::EvenOdd));
+    }
+
+    // A circle in the middle of it is inside it under nonzero, so it's a hole and goes clockwise. Under even-odd the
+    // middle isn't filled, so it's an outer contour of its own and goes counter-clockwise.
+    #[test]
+    fn hierarchy_follows_the_fill_rule()
+    {
+        let outline = Piecewise::new(vec![twice_round(), Piecewise::circle(v(0., 0.), 20.)]);
+
+        assert_eq!(outline.depth(1, FillRule::NonZero), 1);
+        assert_eq!(outline.depth(1, FillRule::EvenOdd), 0);
+        assert_eq!(outline.depth(0, FillRule::NonZero), 0);
+
+        let nonzero = outline.fix_winding_with_fill_rule(FillRule::NonZero);
+        let evenodd = outline.fix_winding_with_fill_rule(FillRule::EvenOdd);
+        assert_eq!(nonzero.curves[1].winding_direction(), WindingDirection::Clockwise);
+        assert_eq!(evenodd.curves[1].winding_direction(), WindingDirection::CounterClockwise);
+        assert_eq!(nonzero.curves[0].winding_direction(), WindingDirection::CounterClockwise);
+        assert!(outline.fix_winding().approx_eq(&nonzero, 0.));
+    }
+
+    // A square with a hole in it and an island in the hole, all drawn the wrong way round, and a line doubling back
+    // on itself that doesn't go round at all.
+    #[test]
+    fn nested_contours_alternate()
+    {
+        let square = |r: f64| Piecewise::rect(Rect { left: -r, bottom: -r, right: r, top: r }).curves.remove(0);
+        assert_eq!(square(10.).signed_area(), 400.);
+        assert_eq!(square(10.).winding_direction(), WindingDirection::CounterClockwise);
+        assert_eq!(square(10.).reverse().winding_direction(), WindingDirection::Clockwise);
+
+        let line = |a: Vector, b: Vector| Bezier::from_control_points(a, a, b, b);
+        let there_and_back = Piecewise::new(vec![line(v(0., 0.), v(50., 0.)), line(v(50., 0.), v(0., 0.))]);
+        assert_eq!(there_and_back.winding_direction(), WindingDirection::Degenerate);
+
+        let outline = Piecewise::new(vec![square(100.).reverse(), square(60.), square(20.).reverse(), there_and_back.clone()]);
+        let fixed = outline.fix_winding();
+        let directions: Vec<WindingDirection> = fixed.curves.iter().map(|c| c.winding_direction()).collect();
+        assert_eq!(directions, vec![WindingDirection::CounterClockwise, WindingDirection::Clockwise, WindingDirection::CounterClockwise, WindingDirection::Degenerate]);
+        assert!(fixed.curves[3].approx_eq(&there_and_back, 0.));
+
+        // reversing keeps the shape, and running it again changes nothing
+        for (got, was) in fixed.curves.iter().zip(&outline.curves) {
+            assert_eq!(got.signed_area().abs(), was.signed_area().abs());
+        }
+        assert!(fixed.fix_winding().approx_eq(&fixed, 0.));
+    }
+}
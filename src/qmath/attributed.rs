@@ -0,0 +1,459 @@
+use super::*;
+
+// A contour with something attached to each of its segments: widths for a variable width stroke, a dash phase,
+// whether the editor has it selected. The attributes live in their own Vec next to the curves and everything that
+// changes how many segments there are or what order they're in goes through here so the two never drift apart.
+//
+// How an attribute gets cut in two or turned around depends on what it is, a selection flag just gets copied while
+// widths have to be interpolated, so that's up to the attribute through SegmentAttribute.
+
+pub trait SegmentAttribute: Clone {
+    // The attributes for the two pieces a segment is cut into at local t. By default both get a copy.
+    fn split(&self, _t: f64) -> (Self, Self)
+    {
+        return (self.clone(), self.clone());
+    }
+
+    // The attribute for the segment going the other way. By default it's the same.
+    fn reversed(&self) -> Self
+    {
+        return self.clone();
+    }
+}
+
+impl SegmentAttribute for bool {}
+impl SegmentAttribute for usize {}
+
+// None stays None, so an attribute that's only known for some segments carries through the same way.
+impl<A: SegmentAttribute> SegmentAttribute for Option<A> {
+    fn split(&self, t: f64) -> (Self, Self)
+    {
+        return match self {
+            Some(a) => { let (x, y) = a.split(t); (Some(x), Some(y)) }
+            None => (None, None),
+        };
+    }
+
+    fn reversed(&self) -> Self
+    {
+        return self.as_ref().map(|a| a.reversed());
+    }
+}
+
+// How far out the left and right sides of a variable width stroke are at each end of a segment, left as seen
+// walking along it. In between they change linearly with the segment's t, the same as point_width_stroke's.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SegmentWidths {
+    pub start: (f64, f64),
+    pub end: (f64, f64),
+}
+
+impl SegmentWidths {
+    // The (left, right) widths at local t.
+    pub fn at(&self, t: f64) -> (f64, f64)
+    {
+        return (self.start.0 + (self.end.0 - self.start.0) * t, self.start.1 + (self.end.1 - self.start.1) * t);
+    }
+}
+
+impl SegmentAttribute for SegmentWidths {
+    fn split(&self, t: f64) -> (Self, Self)
+    {
+        let middle = self.at(t);
+        return (SegmentWidths { start: self.start, end: middle }, SegmentWidths { start: middle, end: self.end });
+    }
+
+    // walking the other way the ends swap and so do the sides
+    fn reversed(&self) -> Self
+    {
+        return SegmentWidths { start: (self.end.1, self.end.0), end: (self.start.1, self.start.0) };
+    }
+}
+
+// The attributes for the pieces Bezier::split_at_multiple makes out of ts, which are filtered the same way.
+fn split_attribute<A: SegmentAttribute>(attribute: &A, ts: &[f64]) -> Vec<A>
+{
+    let mut cuts: Vec<f64> = ts.iter().copied().filter(|t| t.is_finite() && *t > 1e-9 && *t < 1. - 1e-9).collect();
+    cuts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    cuts.dedup_by(|b, a| *b - *a < 1e-9);
+
+    let mut output = Vec::with_capacity(cuts.len() + 1);
+    let mut rest = attribute.clone();
+    let mut rest_start = 0.;
+    for t in cuts {
+        let (piece, after) = rest.split((t - rest_start) / (1. - rest_start));
+        output.push(piece);
+        rest = after;
+        rest_start = t;
+    }
+    output.push(rest);
+
+    return output;
+}
+
+// The attribute for Bezier::restrict's piece between a and b.
+fn restrict_attribute<A: SegmentAttribute>(attribute: &A, a: f64, b: f64) -> A
+{
+    let tail = if a > 0. { attribute.split(a).1 } else { attribute.clone() };
+    if b >= 1. { return tail; }
+
+    return tail.split((b - a) / (1. - a)).0;
+}
+
+#[derive(Clone)]
+pub struct AttributedPiecewise<T: Evaluate, A> {
+    piecewise: Piecewise<T>,
+    // always one for each of piecewise's curves
+    attributes: Vec<A>,
+}
+
+impl<T: Evaluate, A> AttributedPiecewise<T, A> {
+    // None unless there's exactly one attribute for each curve.
+    pub fn new(piecewise: Piecewise<T>, attributes: Vec<A>) -> Option<Self>
+    {
+        if piecewise.curves.len() != attributes.len() { return None; }
+        return Some(AttributedPiecewise { piecewise: piecewise, attributes: attributes });
+    }
+
+    // Every curve with the same attribute.
+    pub fn uniform(piecewise: Piecewise<T>, attribute: A) -> Self where A: Clone
+    {
+        let attributes = vec![attribute; piecewise.curves.len()];
+        return AttributedPiecewise { piecewise: piecewise, attributes: attributes };
+    }
+
+    pub fn piecewise(&self) -> &Piecewise<T>
+    {
+        return &self.piecewise;
+    }
+
+    pub fn attributes(&self) -> &[A]
+    {
+        return &self.attributes;
+    }
+
+    // Changing an attribute can't break anything, changing a curve goes through the methods that keep them paired.
+    pub fn attributes_mut(&mut self) -> &mut [A]
+    {
+        return &mut self.attributes;
+    }
+
+    pub fn into_parts(self) -> (Piecewise<T>, Vec<A>)
+    {
+        return (self.piecewise, self.attributes);
+    }
+
+    pub fn len(&self) -> usize
+    {
+        return self.attributes.len();
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        return self.attributes.is_empty();
+    }
+
+    pub fn get(&self, i: usize) -> Option<(&T, &A)>
+    {
+        return Some((self.piecewise.curves.get(i)?, self.attributes.get(i)?));
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&T, &A)>
+    {
+        return self.piecewise.curves.iter().zip(self.attributes.iter());
+    }
+
+    pub fn push(&mut self, curve: T, attribute: A)
+    {
+        self.piecewise.curves.push(curve);
+        self.attributes.push(attribute);
+    }
+
+    // Panics if i is past the end, like Vec::insert.
+    pub fn insert(&mut self, i: usize, curve: T, attribute: A)
+    {
+        self.piecewise.curves.insert(i, curve);
+        self.attributes.insert(i, attribute);
+    }
+
+    // Panics if i is out of bounds, like Vec::remove.
+    pub fn remove(&mut self, i: usize) -> (T, A)
+    {
+        return (self.piecewise.curves.remove(i), self.attributes.remove(i));
+    }
+}
+
+impl<A: SegmentAttribute> AttributedPiecewise<Bezier, A> {
+    pub(super) fn from_pairs(curves: Vec<Bezier>, attributes: Vec<A>) -> Self
+    {
+        return AttributedPiecewise { piecewise: Piecewise { curves: curves }, attributes: attributes };
+    }
+
+    // Piecewise::subdivide, every segment's attribute split at t.
+    pub fn subdivide(&self, t: f64) -> Self
+    {
+        let mut curves = Vec::new();
+        let mut attributes = Vec::new();
+        for (bez, attribute) in self.iter() {
+            let (a, b) = bez.subdivide(t);
+            let (aa, ab) = attribute.split(t);
+            curves.push(a);
+            curves.push(b);
+            attributes.push(aa);
+            attributes.push(ab);
+        }
+
+        return Self::from_pairs(curves, attributes);
+    }
+
+    // Piecewise::reverse, the attributes turned around with their segments.
+    pub fn reverse(&self) -> Self
+    {
+        return Self::from_pairs(self.piecewise.reverse().curves, self.attributes.iter().rev().map(|a| a.reversed()).collect());
+    }
+
+    // Every curve moved by transform, each keeping its attribute. See Evaluate::apply_transform.
+    pub fn apply_transform<F>(&self, transform: F) -> Self where F: Fn(&Vector) -> Vector
+    {
+        return Self::from_pairs(self.piecewise.apply_transform(&transform).curves, self.attributes.clone());
+    }
+
+    // Piecewise::set_start_segment, the attributes rotated along with their segments.
+    pub fn set_start_segment(&mut self, i: usize) -> Result<(), EditError>
+    {
+        self.piecewise.set_start_segment(i)?;
+        let count = self.attributes.len();
+        self.attributes.rotate_left(i % count);
+        return Ok(());
+    }
+
+    // Piecewise::to_monotone, the attributes split wherever their segment was.
+    pub fn to_monotone(&self) -> Self
+    {
+        let mut curves = Vec::new();
+        let mut attributes = Vec::new();
+        for (bez, attribute) in self.iter() {
+            curves.extend(bez.split_at_extrema());
+            attributes.extend(split_attribute(attribute, &bez.extrema_cuts()));
+        }
+
+        return Self::from_pairs(curves, attributes);
+    }
+
+    // Piecewise::split_at_multiple with the attributes split along with their segments. Segments left whole keep
+    // theirs as they are.
+    pub fn split_at_multiple(&self, ts: &[f64]) -> Vec<Self>
+    {
+        let n = self.piecewise.curves.len();
+        if n == 0 { return vec![self.clone()]; }
+
+        let mut cuts: Vec<f64> = ts.iter().copied().filter(|t| t.is_finite() && *t > 1e-9 && *t < 1. - 1e-9).collect();
+        cuts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        cuts.dedup_by(|b, a| *b - *a < 1e-9);
+
+        let mut locals: Vec<Vec<f64>> = vec![Vec::new(); n];
+        let mut joints = vec![false; n];
+        for t in cuts {
+            let scaled = t * n as f64;
+            let nearest = scaled.round();
+            if (scaled - nearest).abs() < 1e-9 {
+                joints[nearest as usize] = true;
+            } else {
+                locals[scaled.floor() as usize].push(scaled - scaled.floor());
+            }
+        }
+
+        let mut output = Vec::new();
+        let (mut curves, mut attributes) = (Vec::new(), Vec::new());
+        for (i, (bez, attribute)) in self.iter().enumerate() {
+            if joints[i] {
+                output.push(Self::from_pairs(std::mem::replace(&mut curves, Vec::new()), std::mem::replace(&mut attributes, Vec::new())));
+            }
+
+            let pieces = bez.split_at_multiple(&locals[i]);
+            let split = split_attribute(attribute, &locals[i]);
+            for (k, (piece, piece_attribute)) in pieces.into_iter().zip(split).enumerate() {
+                if k > 0 {
+                    output.push(Self::from_pairs(std::mem::replace(&mut curves, Vec::new()), std::mem::replace(&mut attributes, Vec::new())));
+                }
+                curves.push(piece);
+                attributes.push(piece_attribute);
+            }
+        }
+        output.push(Self::from_pairs(curves, attributes));
+
+        return output;
+    }
+
+    // Piecewise::split_at with the attributes along for the ride.
+    pub fn split_at(&self, t: f64) -> (Self, Self)
+    {
+        let mut pieces = self.split_at_multiple(&[t]);
+        if pieces.len() == 2 {
+            let after = pieces.pop().unwrap();
+            return (pieces.pop().unwrap(), after);
+        }
+
+        let empty = Self::from_pairs(Vec::new(), Vec::new());
+        if t <= 0.5 { return (empty, self.clone()); }
+        return (self.clone(), empty);
+    }
+
+    // Piecewise::cut, the segments cut at either end getting the matching part of their attribute.
+    pub fn cut(&self, t0: f64, t1: f64) -> Self
+    {
+        let n = self.piecewise.curves.len();
+        if n == 0 { return self.clone(); }
+
+        let t0 = f64::min(f64::max(t0, 0.), 1.);
+        let t1 = f64::min(f64::max(t1, 0.), 1.);
+        let point = |t: f64| {
+            let (i, local) = self.piecewise.segment_at(t);
+            let p = self.piecewise.curves[i].evaluate(local);
+            return Self::from_pairs(vec![Bezier::from_control_points(p, p, p, p)], vec![restrict_attribute(&self.attributes[i], local, local)]);
+        };
+        if t0 == t1 { return point(t0); }
+
+        let (t0, t1) = if t0 > t1 && !self.piecewise.is_closed() { (t1, t0) } else { (t0, t1) };
+        let (start, start_t) = self.piecewise.segment_at(t0);
+        let (end, end_t) = self.piecewise.segment_at(t1);
+
+        let mut pairs = Vec::new();
+        let piece = |i: usize, a: f64, b: f64| (self.piecewise.curves[i].restrict(a, b), restrict_attribute(&self.attributes[i], a, b));
+        if t0 < t1 && start == end {
+            pairs.push(piece(start, start_t, end_t));
+        } else {
+            pairs.push(piece(start, start_t, 1.));
+            let mut i = (start + 1) % n;
+            while i != end {
+                pairs.push((self.piecewise.curves[i].clone(), self.attributes[i].clone()));
+                i = (i + 1) % n;
+            }
+            pairs.push(piece(end, 0., end_t));
+        }
+
+        // the same zero length pieces Piecewise::cut drops
+        pairs.retain(|(bez, _)| {
+            let p = bez.to_control_points();
+            !(p[0] == p[1] && p[1] == p[2] && p[2] == p[3])
+        });
+        if pairs.is_empty() { return point(t0); }
+
+        let (curves, attributes) = pairs.into_iter().unzip();
+        return Self::from_pairs(curves, attributes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(x: f64, y: f64) -> Vector
+    {
+        return Vector { x: x, y: y };
+    }
+
+    // An S, a line and an arch, open, with widths that grow steadily along it, each segment taking them from
+    // 10 i to 10 (i + 1) on the left and a unit more on the right.
+    fn attributed() -> AttributedPiecewise<Bezier, SegmentWidths>
+    {
+        let curves = vec![
+            Bezier::from_control_points(v(0., 0.), v(100., 0.), v(0., 100.), v(100., 100.)),
+            Bezier::from_control_points(v(100., 100.), v(100., 100.), v(200., 100.), v(200., 100.)),
+            Bezier::from_control_points(v(200., 100.), v(230., 200.), v(270., 200.), v(300., 100.)),
+        ];
+        let widths = (0..3).map(|i| {
+            let (start, end) = (10. * i as f64, 10. * (i + 1) as f64);
+            SegmentWidths { start: (start, start + 1.), end: (end, end + 1.) }
+        }).collect();
+        return AttributedPiecewise::new(Piecewise::new(curves), widths).unwrap();
+    }
+
+    fn assert_near(got: (f64, f64), want: (f64, f64))
+    {
+        assert!((got.0 - want.0).abs() < 1e-9 && (got.1 - want.1).abs() < 1e-9, "{:?} rather than {:?}", got, want);
+    }
+
+    // Every piece's widths are what the original had at the same place along it, sides swapped if it was reversed.
+    fn assert_widths_follow(pieces: &AttributedPiecewise<Bezier, SegmentWidths>, reversed: bool)
+    {
+        let original = attributed();
+        assert_eq!(pieces.piecewise().len(), pieces.attributes().len());
+
+        for (bez, widths) in pieces.iter() {
+            for u in &[0., 0.5, 1.] {
+                let (segment, t, distance) = original.piecewise().nearest(bez.evaluate(*u)).unwrap();
+                assert!(distance < 1e-6);
+                let want = original.attributes()[segment].at(t);
+                let want = if reversed { (want.1, want.0) } else { want };
+                let got = widths.at(*u);
+                assert!((got.0 - want.0).abs() < 1e-4 && (got.1 - want.1).abs() < 1e-4, "{:?} rather than {:?} at {}", got, want, u);
+            }
+        }
+    }
+
+    #[test]
+    fn subdivide_and_reverse()
+    {
+        let subdivided = attributed().subdivide(0.3);
+        assert_eq!(subdivided.len(), 6);
+        assert_widths_follow(&subdivided, false);
+
+        let reversed = attributed().reverse();
+        assert_eq!(reversed.attributes()[0], SegmentWidths { start: (31., 30.), end: (21., 20.) });
+        assert_widths_follow(&reversed, true);
+        assert_eq!(reversed.reverse().attributes(), attributed().attributes());
+    }
+
+    #[test]
+    fn to_monotone()
+    {
+        let monotone = attributed().to_monotone();
+        assert_eq!(monotone.len(), monotone.piecewise().to_monotone().len());
+        assert!(monotone.len() > 3);
+        assert_widths_follow(&monotone, false);
+    }
+
+    // Cuts inside a segment split its widths, cuts on a joint leave both sides' whole.
+    #[test]
+    fn split_at_and_split_at_multiple()
+    {
+        let (before, after) = attributed().split_at(0.5);
+        assert_eq!((before.len(), after.len()), (2, 2));
+        assert_near(before.attributes()[1].end, (15., 16.));
+        assert_widths_follow(&before, false);
+        assert_widths_follow(&after, false);
+
+        let pieces = attributed().split_at_multiple(&[1. / 3., 0.1, 0.9]);
+        assert_eq!(pieces.iter().map(|p| p.len()).collect::<Vec<_>>(), vec![1, 1, 2, 1]);
+        assert_near(pieces[1].attributes()[0].end, (10., 11.));
+        for piece in &pieces {
+            assert_widths_follow(piece, false);
+        }
+    }
+
+    #[test]
+    fn cut_and_set_start_segment()
+    {
+        let cut = attributed().cut(0.2, 0.8);
+        assert_eq!(cut.len(), 3);
+        assert_near(cut.attributes()[0].start, (6., 7.));
+        assert_near(cut.attributes()[2].end, (24., 25.));
+        assert_widths_follow(&cut, false);
+
+        // a single point still carries the widths at it
+        let point = attributed().cut(0.5, 0.5);
+        assert_eq!(point.len(), 1);
+        assert_near(point.attributes()[0].start, (15., 16.));
+
+        // closed up the contour can start anywhere, each segment keeping its own widths
+        let mut closed = attributed();
+        closed.push(Bezier::from_control_points(v(300., 100.), v(300., 100.), v(0., 0.), v(0., 0.)), SegmentWidths { start: (30., 31.), end: (0., 1.) });
+        let before = closed.clone();
+        closed.set_start_segment(2).unwrap();
+        for i in 0..4 {
+            assert_eq!(closed.attributes()[i], before.attributes()[(i + 2) % 4]);
+            assert!(closed.piecewise()[i].to_control_points()[0].is_near(before.piecewise()[(i + 2) % 4].to_control_points()[0], 1e-9));
+        }
+    }
+}
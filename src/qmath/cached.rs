@@ -0,0 +1,226 @@
+use super::*;
+
+// A contour along with the things we keep asking of it, for an editor that evaluates the same contour thousands of
+// times a frame. The bounds of every segment, how far along the contour each segment starts and the contour
+// flattened to a polyline are all worked out up front. Closest point and intersection queries use the segment
+// bounds to skip segments that can't matter. Nothing notices the curves changing under it, call invalidate after
+// editing them through piecewise_mut.
+
+// fewest halvings per segment when flattening, a symmetric S has its midpoint right on its chord
+const MIN_FLATTEN_DEPTH: usize = 2;
+const MAX_FLATTEN_DEPTH: usize = 16;
+
+pub struct CachedPiecewise<T: Evaluate> {
+    piecewise: Piecewise<T>,
+    tolerance: f64,
+    segment_bounds: Vec<Rect>,
+    bounds: Rect,
+    // how far along the contour each segment starts, with the whole length on the end
+    arclens: Vec<f64>,
+    // points along the contour with their global t, no more than tolerance from it
+    polyline: Vec<(f64, Vector)>,
+}
+
+// Halves [t0, t1] until the curve's midpoint is within tolerance of the chord, pushing the end of every piece.
+fn flatten_segment<T: Evaluate>(curve: &T, t0: f64, t1: f64, p0: Vector, p1: Vector, tolerance: f64, depth: usize, output: &mut Vec<(f64, Vector)>)
+{
+    let tm = (t0 + t1) / 2.;
+    let pm = curve.evaluate(tm);
+
+    let chord = p1 + -p0;
+    let off = match chord.try_normalize() {
+        Some(direction) => (pm + -p0).cross(direction).abs(),
+        None => pm.distance(p0),
+    };
+
+    if depth >= MAX_FLATTEN_DEPTH || (depth >= MIN_FLATTEN_DEPTH && off <= tolerance) {
+        output.push((t1, p1));
+        return;
+    }
+
+    flatten_segment(curve, t0, tm, p0, pm, tolerance, depth + 1, output);
+    flatten_segment(curve, tm, t1, pm, p1, tolerance, depth + 1, output);
+}
+
+// How far point is from the nearest point of rect, zero inside it.
+fn distance_to_rect(rect: &Rect, point: Vector) -> f64
+{
+    let dx = f64::max(f64::max(rect.left - point.x, point.x - rect.right), 0.);
+    let dy = f64::max(f64::max(rect.bottom - point.y, point.y - rect.top), 0.);
+    return (dx * dx + dy * dy).sqrt();
+}
+
+impl<T: Evaluate> CachedPiecewise<T> {
+    // Caches everything about piecewise, flattening it to within tolerance.
+    pub fn new(piecewise: Piecewise<T>, tolerance: f64) -> Self
+    {
+        let mut cached = CachedPiecewise {
+            piecewise: piecewise,
+            tolerance: tolerance,
+            segment_bounds: Vec::new(),
+            bounds: Rect::empty(),
+            arclens: Vec::new(),
+            polyline: Vec::new(),
+        };
+        cached.invalidate();
+
+        return cached;
+    }
+
+    // Works everything out again from the curves as they are now.
+    pub fn invalidate(&mut self)
+    {
+        self.segment_bounds = self.piecewise.curves.iter().map(|curve| curve.bounds()).collect();
+        self.bounds = self.segment_bounds.iter().fold(Rect::empty(), |b, r| if b.is_empty() { *r } else { b.encapsulate_rect(*r) });
+
+        self.polyline = Vec::new();
+        self.arclens = vec![0.];
+        for (i, curve) in self.piecewise.curves.iter().enumerate() {
+            let (p0, p1) = (curve.evaluate(0.), curve.evaluate(1.));
+            if i == 0 { self.polyline.push((0., p0)); }
+
+            let start = self.polyline.len() - 1;
+            let mut points = Vec::new();
+            flatten_segment(curve, 0., 1., p0, p1, self.tolerance, 0, &mut points);
+            let piecewise = &self.piecewise;
+            self.polyline.extend(points.into_iter().map(|(t, p)| (piecewise.global_t(i, t), p)));
+
+            let length: f64 = self.polyline[start..].windows(2).map(|w| w[0].1.distance(w[1].1)).sum();
+            self.arclens.push(self.arclens[i] + length);
+        }
+    }
+
+    pub fn piecewise(&self) -> &Piecewise<T>
+    {
+        return &self.piecewise;
+    }
+
+    // The curves to edit, invalidate has to be called after or everything cached is stale.
+    pub fn piecewise_mut(&mut self) -> &mut Piecewise<T>
+    {
+        return &mut self.piecewise;
+    }
+
+    pub fn into_piecewise(self) -> Piecewise<T>
+    {
+        return self.piecewise;
+    }
+
+    pub fn tolerance(&self) -> f64
+    {
+        return self.tolerance;
+    }
+
+    pub fn segment_bounds(&self) -> &[Rect]
+    {
+        return &self.segment_bounds;
+    }
+
+    // How far along the contour each segment starts with the whole length on the end, so segment i runs from
+    // arclens()[i] to arclens()[i + 1]. These are lengths of the polyline so they come up short of the curves by
+    // about as much as the polyline strays from them.
+    pub fn arclens(&self) -> &[f64]
+    {
+        return &self.arclens;
+    }
+
+    pub fn length(&self) -> f64
+    {
+        return *self.arclens.last().unwrap();
+    }
+
+    pub fn polyline(&self) -> &[(f64, Vector)]
+    {
+        return &self.polyline;
+    }
+}
+
+impl<T: Evaluate> Evaluate for CachedPiecewise<T> {
+    fn evaluate(&self, t: f64) -> Vector
+    {
+        return self.piecewise.evaluate(t);
+    }
+
+    fn derivative(&self, t: f64) -> Vector
+    {
+        return self.piecewise.derivative(t);
+    }
+
+    fn derivative2(&self, t: f64) -> Vector
+    {
+        return self.piecewise.derivative2(t);
+    }
+
+    fn curvature(&self, t: f64) -> Option<f64>
+    {
+        return self.piecewise.curvature(t);
+    }
+
+    fn tangent_at(&self, t: f64) -> Vector
+    {
+        return self.piecewise.tangent_at(t);
+    }
+
+    fn bounds(&self) -> Rect
+    {
+        if self.segment_bounds.is_empty() { panic!("An empty piecewise knows no bounds!") }
+        return self.bounds;
+    }
+
+    fn directional_bounds(&self, direction: Vector) -> (f64, f64)
+    {
+        return self.piecewise.directional_bounds(direction);
+    }
+
+    // The transformed curves get cached all over again.
+    fn apply_transform<F>(&self, transform: F) -> Self where F: Fn(&Vector) -> Vector
+    {
+        return CachedPiecewise::new(self.piecewise.apply_transform(transform), self.tolerance);
+    }
+}
+
+impl CachedPiecewise<Bezier> {
+    // Piecewise's project, going through the segments nearest first and stopping at the first whose bounds are
+    // farther away than the closest point found so far.
+    pub fn project(&self, point: Vector) -> Option<(f64, Vector, f64)>
+    {
+        let mut order: Vec<(usize, f64)> = self.segment_bounds.iter().map(|r| distance_to_rect(r, point)).enumerate().collect();
+        order.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        let mut best: Option<(usize, f64, f64)> = None;
+        for (i, bound) in order {
+            if best.map_or(false, |b| bound >= b.2) { break; }
+
+            let (t, distance) = self.piecewise.curves[i].nearest(point);
+            if best.map_or(true, |b| distance < b.2) { best = Some((i, t, distance)); }
+        }
+
+        let (segment, t, distance) = best?;
+        return Some((self.piecewise.global_t(segment, t), self.piecewise.curves[segment].evaluate(t), distance));
+    }
+
+    // Piecewise's intersections_with, only running the intersector on pairs of segments whose bounds overlap.
+    pub fn intersections_with(&self, other: &CachedPiecewise<Bezier>, tolerance: f64) -> Vec<(f64, f64)>
+    {
+        let n = self.piecewise.curves.len() as f64;
+        let m = other.piecewise.curves.len() as f64;
+
+        let mut hits: Vec<(f64, f64)> = Vec::new();
+        for (i, a) in self.piecewise.curves.iter().enumerate() {
+            let ra = self.segment_bounds[i].outset(tolerance);
+            if !ra.overlaps(&other.bounds) { continue; }
+
+            for (j, b) in other.piecewise.curves.iter().enumerate() {
+                if !ra.overlaps(&other.segment_bounds[j]) { continue; }
+
+                for (ta, tb) in a.intersects(b, tolerance) {
+                    hits.push(((i as f64 + ta) / n, (j as f64 + tb) / m));
+                }
+            }
+        }
+
+        hits.sort_by(|x, y| x.partial_cmp(y).unwrap());
+        hits.dedup_by(|x, y| (x.0 - y.0).abs() < 1e-9 && (x.1 - y.1).abs() < 1e-9);
+        return hits;
+    }
+}
@@ -0,0 +1,132 @@
+use super::*;
+use std::f64::consts::PI;
+
+// Circles, arcs and ellipses out of cubics. An arc is cut into pieces of at most a quarter turn, each with its
+// handles 4/3 tan(sweep / 4) of the radius out along the tangent at its ends, which for a quarter turn is the usual
+// 0.5523. That puts the ends and middle of every piece right on the circle and the rest just outside it. The worst
+// a quarter turn strays is 0.027% of the radius, about 0.27 units on a circle 1000 across, and it drops off with the
+// sixth power of the piece's sweep so an eighth turn is already under 0.0005%.
+
+impl Piecewise<Bezier>
+{
+    // A circle made of four quarter arcs, counter-clockwise starting from the right of center.
+    pub fn circle(center: Vector, radius: f64) -> Self
+    {
+        return Self::arc(center, radius, 0., 2. * PI);
+    }
+
+    // The arc around center starting start_angle radians counter-clockwise from the x axis and turning sweep
+    // radians, counter-clockwise when positive. Sweeps of more than a quarter turn are split evenly into pieces of no
+    // more than a quarter turn each. A sweep of zero still gives a single zero length curve on the start point.
+    pub fn arc(center: Vector, radius: f64, start_angle: f64, sweep: f64) -> Self
+    {
+        let pieces = (sweep.abs() / (PI / 2.)).ceil().max(1.) as usize;
+        let step = sweep / pieces as f64;
+        let k = 4. / 3. * (step / 4.).tan();
+
+        let mut curves = Vec::new();
+        for i in 0..pieces {
+            let (a0, a1) = (start_angle + step * i as f64, start_angle + step * (i + 1) as f64);
+            let (s0, c0) = a0.sin_cos();
+            let (s1, c1) = a1.sin_cos();
+
+            let p0 = center + Vector { x: c0, y: s0 } * radius;
+            let p3 = center + Vector { x: c1, y: s1 } * radius;
+            let p1 = p0 + Vector { x: -s0, y: c0 } * (radius * k);
+            let p2 = p3 + Vector { x: s1, y: -c1 } * (radius * k);
+            curves.push(Bezier::from_control_points(p0, p1, p2, p3));
+        }
+
+        // the last piece ends exactly where the first started when we've gone all the way round
+        if (sweep.abs() - 2. * PI).abs() < 1e-12 {
+            let first = curves[0].to_control_points()[0];
+            let last = curves.len() - 1;
+            let p = curves[last].to_control_points();
+            curves[last] = Bezier::from_control_points(p[0], p[1], p[2] + (first + -p[3]), first);
+        }
+
+        return Piecewise { curves: curves };
+    }
+
+    // A circle stretched to radii rx along and ry across its rotation, which is in radians counter-clockwise from the
+    // x axis. Starts at the end of the rx axis and goes counter-clockwise. Stretching the circle stretches how far
+    // it strays too, by the larger radius.
+    pub fn ellipse(center: Vector, rx: f64, ry: f64, rotation: f64) -> Self
+    {
+        let unit = Self::circle(Vector { x: 0., y: 0. }, 1.);
+        return unit.apply_transform(|v: &Vector| center + Vector { x: v.x * rx, y: v.y * ry }.rotate(rotation));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // How far contour strays from the circle around center, as the most it's inside and the most it's outside, each
+    // as a fraction of radius.
+    fn radial_error(contour: &Piecewise<Bezier>, center: Vector, radius: f64) -> (f64, f64)
+    {
+        let (mut inside, mut outside) = (0f64, 0f64);
+        for bez in contour.segs() {
+            for i in 0..=1000 {
+                let error = (bez.evaluate(i as f64 / 1000.).distance(center) - radius) / radius;
+                inside = inside.min(error);
+                outside = outside.max(error);
+            }
+        }
+        return (inside, outside);
+    }
+
+    #[test]
+    fn circle_strays_out_by_the_quarter_turn_error()
+    {
+        let center = Vector { x: 300., y: 200. };
+        let circle = Piecewise::circle(center, 500.);
+        assert_eq!(circle.len(), 4);
+        assert!(circle.is_closed());
+        assert!(circle.signed_area() > 0.);
+
+        // never inside, and at worst 0.027% outside
+        let (inside, outside) = radial_error(&circle, center, 500.);
+        assert!(inside > -1e-12, "{}", inside);
+        assert!(outside > 2.7e-4 && outside < 2.73e-4, "{}", outside);
+    }
+
+    #[test]
+    fn arc_pieces_and_their_error()
+    {
+        let center = Vector { x: 0., y: 0. };
+
+        // an eighth of a turn is a single piece, and its error drops with the sixth power of its sweep
+        let eighth = Piecewise::arc(center, 100., 0.3, PI / 4.);
+        assert_eq!(eighth.len(), 1);
+        let (inside, outside) = radial_error(&eighth, center, 100.);
+        assert!(inside > -1e-12 && outside < 5e-6, "{} {}", inside, outside);
+
+        // three quarters backwards is three pieces, going clockwise from the start angle to where it should end
+        let clockwise = Piecewise::arc(center, 100., PI / 2., -1.5 * PI);
+        assert_eq!(clockwise.len(), 3);
+        assert!(clockwise[0].to_control_points()[0].is_near(Vector { x: 0., y: 100. }, 1e-9));
+        assert!(clockwise.last().unwrap().to_control_points()[3].is_near(Vector { x: -100., y: 0. }, 1e-9));
+        assert!(radial_error(&clockwise, center, 100.).1 < 2.73e-4);
+    }
+
+    // Squashed back into a unit circle, the ellipse strays from it no more than the circle it was stretched from.
+    #[test]
+    fn ellipse_stays_on_its_axes()
+    {
+        let center = Vector { x: 50., y: -20. };
+        let rotation = 0.4;
+        let ellipse = Piecewise::ellipse(center, 200., 80., rotation);
+        assert_eq!(ellipse.len(), 4);
+        assert!(ellipse[0].to_control_points()[0].is_near(center + Vector { x: 200., y: 0. }.rotate(rotation), 1e-9));
+
+        for bez in ellipse.segs() {
+            for i in 0..=100 {
+                let p = (bez.evaluate(i as f64 / 100.) + -center).rotate(-rotation);
+                let along = ((p.x / 200.).powi(2) + (p.y / 80.).powi(2)).sqrt();
+                assert!(along >= 1. - 1e-12 && along < 1. + 2.73e-4, "{:?} is {} of the way out", p, along);
+            }
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use super::*;
+use super::edit::{move_start, move_end};
+
+// Tidying up contours read from glifs we didn't write. Doubled points make segments with no length, which have no
+// direction to normalize or offset along, and editors leave joints a hair apart and lines split in two for no
+// reason. clean gets rid of all of that so nothing downstream has to care.
+
+// What clean changed.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CleanReport {
+    // segments dropped because all their control points were in one place
+    pub degenerate: usize,
+    // lines dropped because they carried straight on from the line before them
+    pub merged: usize,
+    // joints whose two sides were pulled together
+    pub welded: usize,
+}
+
+impl CleanReport {
+    pub fn merge(&mut self, other: &CleanReport)
+    {
+        self.degenerate = self.degenerate + other.degenerate;
+        self.merged = self.merged + other.merged;
+        self.welded = self.welded + other.welded;
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        return self.degenerate == 0 && self.merged == 0 && self.welded == 0;
+    }
+}
+
+fn is_line(bez: &Bezier) -> bool
+{
+    let p = bez.to_control_points();
+    return p[1] == p[0] && p[2] == p[3];
+}
+
+// Two lines meeting at a joint that's within epsilon of the straight line between their far ends, going the same way.
+fn carries_on(a: &Bezier, b: &Bezier, epsilon: f64) -> bool
+{
+    if !is_line(a) || !is_line(b) { return false; }
+
+    let (start, joint, end) = (a.to_control_points()[0], a.to_control_points()[3], b.to_control_points()[3]);
+    let chord = end + -start;
+    let length = chord.length();
+    if length <= epsilon { return false; }
+
+    let off = (joint + -start).cross(chord).abs() / length;
+    return off <= epsilon && (joint + -start).dot(end + -joint) > 0.;
+}
+
+impl Bezier {
+    // Every control point within epsilon of the start, the curve doesn't go anywhere.
+    pub fn is_degenerate(&self, epsilon: f64) -> bool
+    {
+        let p = self.to_control_points();
+        return p[1..].iter().all(|q| q.distance(p[0]) <= epsilon);
+    }
+}
+
+impl Piecewise<Bezier>
+{
+    // Drops degenerate segments, pulls joints no more than epsilon apart together and merges lines that carry
+    // straight on into one. A closed contour's start is left where it is, so a line running through it isn't
+    // merged. A contour with nothing but degenerate segments comes back empty.
+    pub fn clean(&self, epsilon: f64) -> (Self, CleanReport)
+    {
+        let mut report = CleanReport::default();
+
+        let mut curves: Vec<Bezier> = Vec::new();
+        for bez in &self.curves {
+            if bez.is_degenerate(epsilon) {
+                report.degenerate = report.degenerate + 1;
+                continue;
+            }
+            curves.push(bez.clone());
+        }
+        if curves.is_empty() { return (Piecewise { curves: curves }, report); }
+
+        // a dropped segment leaves a gap no wider than epsilon behind, so this closes those up as well
+        for i in 1..curves.len() {
+            let (end, start) = (curves[i - 1].to_control_points()[3], curves[i].to_control_points()[0]);
+            if end != start && end.distance(start) <= epsilon {
+                curves[i] = move_start(&curves[i], end);
+                report.welded = report.welded + 1;
+            }
+        }
+
+        let last = curves.len() - 1;
+        let (end, start) = (curves[last].to_control_points()[3], curves[0].to_control_points()[0]);
+        if end != start && end.distance(start) <= epsilon {
+            curves[last] = move_end(&curves[last], start);
+            report.welded = report.welded + 1;
+        }
+
+        let mut output: Vec<Bezier> = Vec::new();
+        for bez in curves {
+            match output.last() {
+                Some(previous) if carries_on(previous, &bez, epsilon) => {
+                    let (from, to) = (previous.to_control_points()[0], bez.to_control_points()[3]);
+                    *output.last_mut().unwrap() = Bezier::from_control_points(from, from, to, to);
+                    report.merged = report.merged + 1;
+                }
+                _ => output.push(bez),
+            }
+        }
+
+        return (Piecewise { curves: output }, report);
+    }
+
+    // from_contour followed by clean.
+    pub fn from_contour_cleaned<U>(contour: &Contour<U>, epsilon: f64) -> (Self, CleanReport)
+    {
+        return Piecewise::from_contour(contour).clean(epsilon);
+    }
+}
+
+impl Piecewise<Piecewise<Bezier>>
+{
+    // Every contour cleaned, any that clean down to nothing are dropped.
+    pub fn clean(&self, epsilon: f64) -> (Self, CleanReport)
+    {
+        let mut report = CleanReport::default();
+        let mut contours = Vec::new();
+
+        for contour in &self.curves {
+            let (cleaned, contour_report) = contour.clean(epsilon);
+            report.merge(&contour_report);
+            if !cleaned.curves.is_empty() { contours.push(cleaned); }
+        }
+
+        return (Piecewise { curves: contours }, report);
+    }
+
+    // from_outline followed by clean.
+    pub fn from_outline_cleaned<U>(outline: &Outline<U>, epsilon: f64) -> (Self, CleanReport)
+    {
+        return Piecewise::from_outline(outline).clean(epsilon);
+    }
+}
@@ -0,0 +1,153 @@
+use super::*;
+
+// how far outside the rect a point can be and still count as on it
+const CLIP_EPSILON: f64 = 1e-9;
+
+impl Rect {
+    // Edges count as inside.
+    pub fn contains(&self, p: Vector) -> bool
+    {
+        return p.x >= self.left - CLIP_EPSILON && p.x <= self.right + CLIP_EPSILON &&
+            p.y >= self.bottom - CLIP_EPSILON && p.y <= self.top + CLIP_EPSILON;
+    }
+}
+
+impl Bezier {
+    // The t values, sorted, where the curve crosses or touches any of the rect's edges.
+    pub fn intersect_rect(&self, rect: &Rect) -> Vec<f64>
+    {
+        return self.rect_hits(rect, 0, 0).ts_for(0, 0);
+    }
+
+    // The same as an IntersectionSet, with the curve sitting at contour and segment.
+    fn rect_hits(&self, rect: &Rect, contour: usize, segment: usize) -> IntersectionSet
+    {
+        let mut set = IntersectionSet::new(INTERSECTION_EPSILON);
+        let mut hit = |t: f64| set.push(CurveLocation { contour: contour, segment: segment, t: t }, None, self.evaluate(t));
+
+        for x in [rect.left, rect.right].iter() {
+            for t in roots::solve_cubic(self.A, self.B, self.C, self.D - x, 0., 1.) {
+                let y = self.evaluate(t).y;
+                if y >= rect.bottom - CLIP_EPSILON && y <= rect.top + CLIP_EPSILON { hit(t); }
+            }
+        }
+
+        for y in [rect.bottom, rect.top].iter() {
+            for t in roots::solve_cubic(self.E, self.F, self.G, self.H - y, 0., 1.) {
+                let x = self.evaluate(t).x;
+                if x >= rect.left - CLIP_EPSILON && x <= rect.right + CLIP_EPSILON { hit(t); }
+            }
+        }
+
+        // a corner shows up once for each of its edges, the set merges those
+        return set;
+    }
+}
+
+impl Piecewise<Bezier>
+{
+    // The pieces of the contour that lie inside the rect, as open paths in the order they're travelled. Curves that
+    // don't cross an edge are kept or dropped whole.
+    pub fn clip_to_rect(&self, rect: &Rect) -> Vec<Piecewise<Bezier>>
+    {
+        let mut runs: Vec<Vec<Bezier>> = Vec::new();
+        let mut current: Vec<Bezier> = Vec::new();
+        // whether the first run starts at the start of the contour and the last ends at its end
+        let mut starts_inside = false;
+        let mut ends_inside = false;
+
+        let mut hits = IntersectionSet::new(INTERSECTION_EPSILON);
+        for (idx, bez) in self.curves.iter().enumerate() {
+            hits.extend(bez.rect_hits(rect, 0, idx));
+        }
+
+        for (idx, bez) in self.curves.iter().enumerate() {
+            let cuts: Vec<f64> = hits.ts_for(0, idx).into_iter()
+                .filter(|t| *t > 1e-9 && *t < 1. - 1e-9)
+                .collect();
+
+            let mut bounds = vec![0.];
+            bounds.extend(cuts.iter());
+            bounds.push(1.);
+
+            for (piece_idx, pair) in bounds.windows(2).enumerate() {
+                let (a, b) = (pair[0], pair[1]);
+                let inside = rect.contains(bez.evaluate((a + b) / 2.));
+
+                if idx == 0 && piece_idx == 0 { starts_inside = inside; }
+                ends_inside = inside;
+
+                if inside {
+                    current.push(if cuts.is_empty() { bez.clone() } else { bez.restrict(a, b) });
+                } else if !current.is_empty() {
+                    runs.push(std::mem::replace(&mut current, Vec::new()));
+                }
+            }
+        }
+
+        if !current.is_empty() { runs.push(current); }
+
+        // a closed contour that's inside where it starts has its first run continuing on from its last
+        if runs.len() > 1 && starts_inside && ends_inside && self.is_closed() {
+            let first = runs.remove(0);
+            runs.last_mut().unwrap().extend(first);
+        }
+
+        return runs.into_iter().map(|curves| Piecewise { curves: curves }).collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(x: f64, y: f64) -> Vector
+    {
+        return Vector { x: x, y: y };
+    }
+
+    // A band across the middle of a circle leaves an arc on either side, the right one running through where the
+    // circle starts.
+    #[test]
+    fn circle_into_two_arcs()
+    {
+        let circle = Piecewise::circle(v(0., 0.), 100.);
+        let band = Rect { left: -200., right: 200., bottom: -50., top: 50. };
+        let arcs = circle.clip_to_rect(&band);
+        assert_eq!(arcs.len(), 2);
+
+        // the ends are exactly on the band's edges, and as close to the true circle's as the curves are
+        let x = f64::sqrt(100. * 100. - 50. * 50.);
+        let assert_at = |p: Vector, want: Vector| assert!((p.x - want.x).abs() < 0.03 && (p.y - want.y).abs() < 1e-9, "{:?} rather than {:?}", p, want);
+        assert_at(arcs[0].first().unwrap().evaluate(0.), v(-x, 50.));
+        assert_at(arcs[0].last().unwrap().evaluate(1.), v(-x, -50.));
+        assert_at(arcs[1].first().unwrap().evaluate(0.), v(x, -50.));
+        assert_at(arcs[1].last().unwrap().evaluate(1.), v(x, 50.));
+
+        for arc in &arcs {
+            assert!(!arc.is_closed());
+            for bez in arc.segs() {
+                for i in 0..=10 {
+                    let p = bez.evaluate(i as f64 / 10.);
+                    assert!(band.contains(p) && (p.distance(v(0., 0.)) - 100.).abs() < 0.03, "{:?}", p);
+                }
+            }
+        }
+    }
+
+    // Nothing gets split if nothing crosses an edge.
+    #[test]
+    fn inside_and_outside_whole()
+    {
+        let circle = Piecewise::circle(v(0., 0.), 100.);
+        let inside = circle.clip_to_rect(&Rect { left: -200., right: 200., bottom: -200., top: 200. });
+        assert_eq!(inside.len(), 1);
+        assert_eq!(inside[0].len(), circle.len());
+        assert!(circle.clip_to_rect(&Rect { left: 300., right: 400., bottom: -50., top: 50. }).is_empty());
+
+        let line = Bezier::from_control_points(v(-100., 0.), v(-100., 0.), v(100., 0.), v(100., 0.));
+        let ts = line.intersect_rect(&Rect { left: -50., right: 50., bottom: -10., top: 10. });
+        assert_eq!(ts.len(), 2);
+        assert!((line.evaluate(ts[0]).x + 50.).abs() < 1e-9 && (line.evaluate(ts[1]).x - 50.).abs() < 1e-9, "{:?}", ts);
+    }
+}
@@ -0,0 +1,44 @@
+use super::*;
+
+// Curvature combs, the teeth font editors hang off a curve to show how its curvature changes. Each tooth goes from
+// a point on the curve out along the normal by the curvature there times scale. Teeth stand on the outside of a
+// bend, the side away from its center, so where the curve goes from bending one way to the other they cross over.
+//
+// At a cusp the curvature runs off to infinity, so no tooth is ever taller than the contour is big. That keeps a
+// cusp obvious without the comb taking over the whole drawing.
+
+impl Piecewise<Bezier>
+{
+    // samples teeth along each curve, the first at its start and the last at its end, as (base, tip) pairs in order.
+    // Curves with no length to them are left out. See comb_to_svg_path_data and comb_to_skpath for drawing them.
+    pub fn curvature_comb(&self, samples: usize, scale: f64) -> Vec<(Vector, Vector)>
+    {
+        let samples = usize::max(samples, 2);
+        let bounds = self.bounds();
+        let max_height = f64::max(bounds.width(), bounds.height());
+
+        let mut output = Vec::new();
+        for bez in &self.curves {
+            let p = bez.to_control_points();
+            if p[0] == p[1] && p[1] == p[2] && p[2] == p[3] { continue; }
+
+            for k in 0..samples {
+                let t = k as f64 / (samples - 1) as f64;
+
+                // right on a cusp there's no curvature at all, a hair away there's plenty and it says which way
+                let nudged = if t < 0.5 { t + 1e-6 } else { t - 1e-6 };
+                let height = match bez.curvature(t).or_else(|| bez.curvature(nudged)) {
+                    Some(curvature) if (curvature * scale).is_finite() => f64::min(f64::max(curvature * scale, -max_height), max_height),
+                    Some(curvature) => curvature.signum() * max_height,
+                    None => 0.,
+                };
+
+                // the normal is on the left, which is the inside of a bend with positive curvature
+                let base = bez.evaluate(t);
+                output.push((base, base + -bez.normal_at(t) * height));
+            }
+        }
+
+        return output;
+    }
+}
@@ -0,0 +1,348 @@
+use super::*;
+
+// Copies of our geometry generic over the float they're stored in, mostly so we can hold lots of it around in f32,
+// like a live preview keeping every stroke it's drawn. What a preview needs to draw them, evaluating, cutting up,
+// bounds and flattening, is written once against Float and works in whatever they're stored in. Anything that
+// needs the precision, finding roots and measuring length, goes through the f64 types and comes back.
+
+// The floats our geometry can be stored in. num_traits has the arithmetic, we only add getting in and out of f64.
+pub trait Float: num_traits::Float + std::fmt::Debug + Default
+{
+    fn from_f64(v: f64) -> Self;
+    fn to_f64(self) -> f64;
+}
+
+impl Float for f32 {
+    fn from_f64(v: f64) -> Self
+    {
+        return v as f32;
+    }
+
+    fn to_f64(self) -> f64
+    {
+        return self as f64;
+    }
+}
+
+impl Float for f64 {
+    fn from_f64(v: f64) -> Self
+    {
+        return v;
+    }
+
+    fn to_f64(self) -> f64
+    {
+        return self;
+    }
+}
+
+// past this many halvings we take the chord whatever it looks like, same as Bezier::flatten
+const MAX_FLATTEN_DEPTH: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CompactVector<F: Float> {
+    pub x: F,
+    pub y: F,
+}
+
+// Stored as control points rather than coefficients, they survive the trip through f32 a lot better.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CompactBezier<F: Float> {
+    pub points: [CompactVector<F>; 4],
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompactContour<F: Float> {
+    pub curves: Vec<CompactBezier<F>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompactOutline<F: Float> {
+    pub contours: Vec<CompactContour<F>>,
+}
+
+pub type Vectorf32 = CompactVector<f32>;
+pub type Bezierf32 = CompactBezier<f32>;
+pub type Contourf32 = CompactContour<f32>;
+pub type Outlinef32 = CompactOutline<f32>;
+
+impl<F: Float> CompactVector<F> {
+    pub fn from_f64(v: Vector) -> Self
+    {
+        return CompactVector { x: F::from_f64(v.x), y: F::from_f64(v.y) };
+    }
+
+    pub fn to_f64(self) -> Vector
+    {
+        return Vector { x: self.x.to_f64(), y: self.y.to_f64() };
+    }
+
+    // Into another precision, through f64 so nothing is lost that the target could hold.
+    pub fn convert<G: Float>(self) -> CompactVector<G>
+    {
+        return CompactVector::from_f64(self.to_f64());
+    }
+
+    pub fn lerp(self, other: Self, t: F) -> Self
+    {
+        return CompactVector { x: self.x + (other.x - self.x) * t, y: self.y + (other.y - self.y) * t };
+    }
+
+    pub fn distance(self, other: Self) -> F
+    {
+        return (other.x - self.x).hypot(other.y - self.y);
+    }
+
+    // how far we are from the segment between a and b
+    fn segment_distance(self, a: Self, b: Self) -> F
+    {
+        let (dx, dy) = (b.x - a.x, b.y - a.y);
+        let length_squared = dx * dx + dy * dy;
+        if length_squared <= F::zero() { return self.distance(a); }
+
+        let along = ((self.x - a.x) * dx + (self.y - a.y) * dy) / length_squared;
+        return self.distance(a.lerp(b, along.max(F::zero()).min(F::one())));
+    }
+}
+
+impl<F: Float> CompactBezier<F> {
+    pub fn from_f64(bez: &Bezier) -> Self
+    {
+        let p = bez.to_control_points();
+        return CompactBezier { points: [CompactVector::from_f64(p[0]), CompactVector::from_f64(p[1]), CompactVector::from_f64(p[2]), CompactVector::from_f64(p[3])] };
+    }
+
+    pub fn to_f64(&self) -> Bezier
+    {
+        let p = self.points;
+        return Bezier::from_control_points(p[0].to_f64(), p[1].to_f64(), p[2].to_f64(), p[3].to_f64());
+    }
+
+    pub fn convert<G: Float>(&self) -> CompactBezier<G>
+    {
+        let p = self.points;
+        return CompactBezier { points: [p[0].convert(), p[1].convert(), p[2].convert(), p[3].convert()] };
+    }
+
+    // de Casteljau, which only ever takes weighted averages of the points and so stays as accurate as they are
+    pub fn evaluate(&self, t: F) -> CompactVector<F>
+    {
+        return self.subdivide(t).0.points[3];
+    }
+
+    // The first derivative with respect to t, pointing the way the curve travels.
+    pub fn derivative(&self, t: F) -> CompactVector<F>
+    {
+        let p = self.points;
+        let three = F::from_f64(3.);
+        let q0 = CompactVector { x: p[1].x - p[0].x, y: p[1].y - p[0].y };
+        let q1 = CompactVector { x: p[2].x - p[1].x, y: p[2].y - p[1].y };
+        let q2 = CompactVector { x: p[3].x - p[2].x, y: p[3].y - p[2].y };
+        let d = q0.lerp(q1, t).lerp(q1.lerp(q2, t), t);
+        return CompactVector { x: d.x * three, y: d.y * three };
+    }
+
+    pub fn subdivide(&self, t: F) -> (Self, Self)
+    {
+        let p = self.points;
+        let q0 = p[0].lerp(p[1], t);
+        let q1 = p[1].lerp(p[2], t);
+        let q2 = p[2].lerp(p[3], t);
+        let r0 = q0.lerp(q1, t);
+        let r1 = q1.lerp(q2, t);
+        let s0 = r0.lerp(r1, t);
+
+        return (CompactBezier { points: [p[0], q0, r0, s0] }, CompactBezier { points: [s0, r1, q2, p[3]] });
+    }
+
+    // The extrema come from the f64 curve's roots, the points at them are evaluated in our own precision so the
+    // box holds what we'd draw.
+    pub fn bounds(&self) -> Rect
+    {
+        let (start, end) = (self.points[0].to_f64(), self.points[3].to_f64());
+        let mut bounds = Rect { left: start.x, right: start.x, bottom: start.y, top: start.y }.encapsulate(end);
+        for t in self.to_f64().extrema() {
+            bounds = bounds.encapsulate(self.evaluate(F::from_f64(t)).to_f64());
+        }
+
+        return bounds;
+    }
+
+    // Whether both handles are within tolerance of the chord, see Bezier::is_flat.
+    pub fn is_flat(&self, tolerance: F) -> bool
+    {
+        let p = self.points;
+        return p[1].segment_distance(p[0], p[3]).max(p[2].segment_distance(p[0], p[3])) <= tolerance;
+    }
+
+    // Points along the curve such that the polyline through them is nowhere further than tolerance from it, the
+    // same halving Bezier::flatten does.
+    pub fn flatten(&self, tolerance: F) -> Vec<CompactVector<F>>
+    {
+        let mut output = vec![self.points[0]];
+        self.flatten_into(tolerance, 0, &mut output);
+        return output;
+    }
+
+    fn flatten_into(&self, tolerance: F, depth: usize, output: &mut Vec<CompactVector<F>>)
+    {
+        if self.is_flat(tolerance) || depth >= MAX_FLATTEN_DEPTH {
+            output.push(self.points[3]);
+            return;
+        }
+
+        let (first, second) = self.subdivide(F::from_f64(0.5));
+        first.flatten_into(tolerance, depth + 1, output);
+        second.flatten_into(tolerance, depth + 1, output);
+    }
+
+    pub fn arclen(&self, tolerance: f64) -> f64
+    {
+        return self.to_f64().arclen(tolerance);
+    }
+}
+
+impl<F: Float> CompactContour<F> {
+    pub fn from_f64(contour: &Piecewise<Bezier>) -> Self
+    {
+        return CompactContour { curves: contour.segs().map(CompactBezier::from_f64).collect() };
+    }
+
+    pub fn to_f64(&self) -> Piecewise<Bezier>
+    {
+        return Piecewise::new(self.curves.iter().map(|c| c.to_f64()).collect());
+    }
+
+    pub fn convert<G: Float>(&self) -> CompactContour<G>
+    {
+        return CompactContour { curves: self.curves.iter().map(|c| c.convert()).collect() };
+    }
+
+    // Global t like Piecewise, every curve gets an equal share of 0 to 1.
+    pub fn evaluate(&self, t: F) -> Option<CompactVector<F>>
+    {
+        if self.curves.is_empty() { return None; }
+
+        let (i, local) = segment_at(self.curves.len(), t.to_f64());
+        return Some(self.curves[i].evaluate(F::from_f64(local)));
+    }
+
+    pub fn bounds(&self) -> Option<Rect>
+    {
+        let mut boxes = self.curves.iter().map(|c| c.bounds());
+        let first = boxes.next()?;
+        return Some(boxes.fold(first, |a, b| a.encapsulate_rect(b)));
+    }
+
+    // Where one curve ends and the next starts there's only one point.
+    pub fn flatten(&self, tolerance: F) -> Vec<CompactVector<F>>
+    {
+        let mut output = Vec::new();
+        for (i, bez) in self.curves.iter().enumerate() {
+            let skip = if i > 0 { 1 } else { 0 };
+            output.extend(bez.flatten(tolerance).into_iter().skip(skip));
+        }
+
+        return output;
+    }
+
+    pub fn arclen(&self, tolerance: f64) -> f64
+    {
+        return self.to_f64().arclen(tolerance);
+    }
+}
+
+impl<F: Float> CompactOutline<F> {
+    pub fn from_f64(outline: &Piecewise<Piecewise<Bezier>>) -> Self
+    {
+        return CompactOutline { contours: outline.segs().map(CompactContour::from_f64).collect() };
+    }
+
+    pub fn to_f64(&self) -> Piecewise<Piecewise<Bezier>>
+    {
+        return Piecewise::new(self.contours.iter().map(|c| c.to_f64()).collect());
+    }
+
+    pub fn convert<G: Float>(&self) -> CompactOutline<G>
+    {
+        return CompactOutline { contours: self.contours.iter().map(|c| c.convert()).collect() };
+    }
+
+    pub fn bounds(&self) -> Option<Rect>
+    {
+        let mut boxes = self.contours.iter().filter_map(|c| c.bounds());
+        let first = boxes.next()?;
+        return Some(boxes.fold(first, |a, b| a.encapsulate_rect(b)));
+    }
+}
+
+impl Piecewise<Bezier>
+{
+    pub fn to_f32(&self) -> Contourf32
+    {
+        return CompactContour::from_f64(self);
+    }
+}
+
+impl Piecewise<Piecewise<Bezier>>
+{
+    pub fn to_f32(&self) -> Outlinef32
+    {
+        return CompactOutline::from_f64(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s_curve() -> Bezier
+    {
+        return Bezier::from_control_points(Vector { x: 0., y: 0. }, Vector { x: 100., y: 150. }, Vector { x: 200., y: -150. }, Vector { x: 300., y: 0. });
+    }
+
+    #[test]
+    fn f64_matches_bezier()
+    {
+        let (bez, compact) = (s_curve(), CompactBezier::<f64>::from_f64(&s_curve()));
+        for i in 0..=10 {
+            let t = i as f64 / 10.;
+            assert!(compact.evaluate(t).to_f64().distance(bez.evaluate(t)) < 1e-9, "t = {}", t);
+
+            // Evaluate's derivative points backwards along the curve
+            assert!(compact.derivative(t).to_f64().distance(-bez.derivative(t)) < 1e-9, "t = {}", t);
+        }
+
+        let (a, b) = (compact.bounds(), bez.bounds());
+        assert!((a.left - b.left).abs() < 1e-9 && (a.right - b.right).abs() < 1e-9);
+        assert!((a.bottom - b.bottom).abs() < 1e-9 && (a.top - b.top).abs() < 1e-9);
+        assert_eq!(compact.flatten(0.1).len(), bez.flatten(0.1).len());
+        assert_eq!(compact.convert::<f32>().convert::<f64>().convert::<f32>(), compact.convert::<f32>());
+    }
+
+    #[test]
+    fn f32_stays_close()
+    {
+        let circle = Piecewise::circle(Vector { x: 0., y: 0. }, 100.);
+        let compact = circle.to_f32();
+        assert_eq!(compact.curves.len(), circle.len());
+
+        // every point comes back within what f32 holds of numbers this size
+        for t in (0..=20).map(|i| i as f32 / 20.) {
+            let radius = compact.evaluate(t).unwrap().to_f64().magnitude();
+            assert!((radius - 100.).abs() < 0.03, "t = {}, radius {}", t, radius);
+        }
+
+        for p in compact.flatten(0.1) {
+            assert!((p.to_f64().magnitude() - 100.).abs() < 0.15, "{:?}", p);
+        }
+
+        let bounds = compact.bounds().unwrap();
+        assert!((bounds.right - 100.).abs() < 1e-4 && (bounds.left + 100.).abs() < 1e-4, "{:?}", (bounds.left, bounds.right));
+        assert!((compact.arclen(1e-6) - circle.arclen(1e-6)).abs() < 1e-3);
+
+        let outline = Piecewise::new(vec![circle.clone()]).to_f32();
+        assert_eq!(outline.to_f64().to_f32(), outline);
+        assert!(CompactContour::<f32>::from_f64(&Piecewise::new(Vec::new())).evaluate(0.5).is_none());
+    }
+}
@@ -0,0 +1,203 @@
+use super::*;
+
+// Telling whether two outlines are the same. approx_eq is strict about structure: the same contours in the same
+// order, with the same segments starting in the same place, only the coordinates can be off. shape_eq lets go of
+// where closed contours start, which way they go and what order the contours come in, which are all things an
+// operation can shuffle without changing the glyph. Neither goes as far as noticing a curve split in two is the same
+// curve, for that there's max_deviation_from. normalize_order is the part of that which is safe to do to masters
+// before interpolating: contour order and start points, never direction.
+
+impl Bezier {
+    // Every control point within epsilon of the other's.
+    pub fn approx_eq(&self, other: &Bezier, epsilon: f64) -> bool
+    {
+        let (a, b) = (self.to_control_points(), other.to_control_points());
+        return a.iter().zip(b.iter()).all(|(p, q)| p.distance(*q) <= epsilon);
+    }
+}
+
+// Whether a is lower than b, or as low within tolerance and further left.
+fn is_lower_left(a: Vector, b: Vector, tolerance: f64) -> bool
+{
+    if (a.y - b.y).abs() > tolerance { return a.y < b.y; }
+    return a.x < b.x - tolerance;
+}
+
+impl Piecewise<Bezier>
+{
+    // The same number of segments with each one's control points within epsilon of the other's.
+    pub fn approx_eq(&self, other: &Piecewise<Bezier>, epsilon: f64) -> bool
+    {
+        if self.curves.len() != other.curves.len() { return false; }
+        return self.curves.iter().zip(other.curves.iter()).all(|(a, b)| a.approx_eq(b, epsilon));
+    }
+
+    // The contour turned so it can be compared with approx_eq. A closed contour goes counter-clockwise, positive
+    // area, and starts on its lowest on-curve point, the leftmost of them if more than one is that low. Points within
+    // tolerance of each other in y count as equally low. An open contour can't be started anywhere else, it's only
+    // turned around if its end is lower and further left than its start.
+    pub fn normalized(&self, tolerance: f64) -> Self
+    {
+        if self.curves.is_empty() { return self.clone(); }
+
+        if !self.is_closed() {
+            let (start, end) = (self.curves[0].to_control_points()[0], self.curves[self.curves.len() - 1].to_control_points()[3]);
+            return if is_lower_left(end, start, tolerance) { self.reverse() } else { self.clone() };
+        }
+
+        let output = if self.signed_area() < 0. { self.reverse() } else { self.clone() };
+        return output.with_canonical_start(tolerance);
+    }
+
+    // A closed contour started on its lowest on-curve point, the leftmost of them if more than one is that low,
+    // going the same way it did. Open contours come back as they are.
+    pub fn with_canonical_start(&self, tolerance: f64) -> Self
+    {
+        let mut output = self.clone();
+        if self.curves.is_empty() || !self.is_closed() { return output; }
+
+        let mut lowest = 0;
+        for (i, bez) in output.curves.iter().enumerate() {
+            if is_lower_left(bez.to_control_points()[0], output.curves[lowest].to_control_points()[0], tolerance) { lowest = i; }
+        }
+        output.curves.rotate_left(lowest);
+
+        return output;
+    }
+
+    // Whether the two are the same contour once both are normalized, with control points within tolerance.
+    pub fn shape_eq(&self, other: &Piecewise<Bezier>, tolerance: f64) -> bool
+    {
+        return self.normalized(tolerance).approx_eq(&other.normalized(tolerance), tolerance);
+    }
+}
+
+impl Piecewise<Piecewise<Bezier>>
+{
+    // The same contours in the same order, each approx_eq to the other's.
+    pub fn approx_eq(&self, other: &Piecewise<Piecewise<Bezier>>, epsilon: f64) -> bool
+    {
+        if self.curves.len() != other.curves.len() { return false; }
+        return self.curves.iter().zip(other.curves.iter()).all(|(a, b)| a.approx_eq(b, epsilon));
+    }
+
+    // Every contour normalized, see Piecewise<Bezier>::normalized. The contours stay in the order they were.
+    pub fn normalized(&self, tolerance: f64) -> Self
+    {
+        return Piecewise { curves: self.curves.iter().map(|contour| contour.normalized(tolerance)).collect() };
+    }
+
+    // The outline in an order that doesn't depend on how it was drawn, for lining up masters. Contours are sorted by
+    // the top left of their bounds, highest first then leftmost, and then by area, bigger first, with coordinates
+    // counted in steps of tolerance so near ties sort the same every time. Contours that tie on all of it keep the
+    // order they had. Every closed contour is then started the way with_canonical_start does, without turning any
+    // of them around. The second part is where each contour went, new index by old, for moving anything kept
+    // alongside the contours into the same order.
+    pub fn normalize_order(&self, tolerance: f64) -> (Self, Vec<usize>)
+    {
+        let step = |v: f64| if tolerance > 0. { (v / tolerance).round() } else { v };
+        let keys: Vec<(f64, f64, f64)> = self.curves.iter().map(|contour| {
+            if contour.curves.is_empty() { return (f64::INFINITY, f64::INFINITY, 0.); }
+            let bounds = contour.bounds();
+            return (-step(bounds.top), step(bounds.left), -step(contour.signed_area().abs()));
+        }).collect();
+
+        let mut order: Vec<usize> = (0..self.curves.len()).collect();
+        order.sort_by(|&a, &b| keys[a].partial_cmp(&keys[b]).unwrap_or(std::cmp::Ordering::Equal));
+
+        let mut mapping = vec![0; order.len()];
+        for (new, &old) in order.iter().enumerate() {
+            mapping[old] = new;
+        }
+
+        let output = Piecewise { curves: order.iter().map(|&old| self.curves[old].with_canonical_start(tolerance)).collect() };
+        return (output, mapping);
+    }
+
+    // Whether every contour of one has a contour of the other that's shape_eq to it, in whatever order they come.
+    pub fn shape_eq(&self, other: &Piecewise<Piecewise<Bezier>>, tolerance: f64) -> bool
+    {
+        if self.curves.len() != other.curves.len() { return false; }
+
+        let theirs = other.normalized(tolerance);
+        let mut matched = vec![false; theirs.curves.len()];
+        for contour in self.normalized(tolerance).curves {
+            let found = (0..theirs.curves.len()).find(|&j| !matched[j] && contour.approx_eq(&theirs.curves[j], tolerance));
+            match found {
+                Some(j) => matched[j] = true,
+                None => return false,
+            }
+        }
+
+        return true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(x: f64, y: f64) -> Vector
+    {
+        return Vector { x: x, y: y };
+    }
+
+    // A square and a circle with a little nudge, started somewhere other than their lowest point.
+    fn outline() -> Piecewise<Piecewise<Bezier>>
+    {
+        let mut square = Piecewise::rect(Rect { left: 0., bottom: 0., right: 100., top: 100. }).curves.remove(0);
+        square.curves.rotate_left(2);
+        let mut circle = Piecewise::circle(v(300., 50.), 50.);
+        circle.curves.rotate_left(1);
+        return Piecewise::new(vec![square, circle]);
+    }
+
+    #[test]
+    fn approx_eq_is_strict_about_structure()
+    {
+        let a = outline();
+        let nudged = a.apply_transform(|p: &Vector| *p + v(1e-4, -1e-4));
+        assert!(a.approx_eq(&nudged, 1e-3));
+        assert!(!a.approx_eq(&nudged, 1e-5));
+
+        // another start, the other direction, the other order or an extra split all count as different
+        let mut restarted = a.clone();
+        restarted.curves[0].curves.rotate_left(1);
+        let reversed = Piecewise::new(vec![a.curves[0].reverse(), a.curves[1].clone()]);
+        let swapped = Piecewise::new(vec![a.curves[1].clone(), a.curves[0].clone()]);
+        let (first, second) = a.curves[0].curves[0].subdivide(0.5);
+        let mut split = a.clone();
+        split.curves[0].curves.splice(0..1, vec![first, second]);
+        for different in [&restarted, &reversed, &swapped, &split].iter() {
+            assert!(!a.approx_eq(different, 1e-3));
+        }
+
+        // shape_eq lets go of all of those but the split
+        for same in [&restarted, &reversed, &swapped, &nudged].iter() {
+            assert!(a.shape_eq(same, 1e-3));
+        }
+        assert!(!a.shape_eq(&split, 1e-3));
+        assert!(!a.shape_eq(&Piecewise::new(vec![a.curves[0].clone()]), 1e-3));
+    }
+
+    // Normalized closed contours go counter-clockwise from their lowest leftmost point, open ones are only turned
+    // around, never restarted.
+    #[test]
+    fn normalized_starts_and_directions()
+    {
+        for contour in outline().curves.iter().chain(outline().reverse().curves.iter()) {
+            let normal = contour.normalized(1e-9);
+            assert!(normal.signed_area() > 0.);
+            assert!(normal.shape_eq(contour, 1e-9));
+            let start = normal.curves[0].to_control_points()[0];
+            assert!(normal.curves.iter().all(|bez| !is_lower_left(bez.to_control_points()[0], start, 1e-9)));
+        }
+        let square = outline().curves[0].normalized(1e-9);
+        assert!(square.curves[0].to_control_points()[0].is_near(v(0., 0.), 1e-12));
+
+        let line = |a: Vector, b: Vector| Bezier::from_control_points(a, a, b, b);
+        let open = Piecewise::new(vec![line(v(100., 100.), v(50., 0.)), line(v(50., 0.), v(0., 0.))]);
+        assert!(open.normalized(1e-9).approx_eq(&open.reverse(), 0.));
+        assert!(open.reverse().normalized(1e-9).approx_eq(&open.reverse(), 0.));
+    }
+}
@@ -0,0 +1,121 @@
+use super::*;
+use super::edit::{move_start, move_end};
+
+// How smoothly a contour's curves meet. Broken - the curves don't even meet, there's a gap at the joint
+// G0 - they meet but turn a corner
+// G1 - they leave in the direction they arrived in
+// G2 - and they're bending the same amount there too
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Continuity {
+    Broken,
+    G0,
+    G1,
+    G2,
+}
+
+// What we found at one joint. index is the curve leaving it, 0 for a closed contour's start.
+#[derive(Debug, Clone, Copy)]
+pub struct JointContinuity {
+    pub index: usize,
+    pub continuity: Continuity,
+    // how far apart the end of one curve and the start of the next are
+    pub gap: f64,
+    // how far the direction turns at the joint in radians, always positive
+    pub angle: f64,
+    // the difference in signed curvature either side, None where a handle sitting on its point of a curve that
+    // isn't straight leaves one side with no curvature to speak of
+    pub curvature_difference: Option<f64>,
+}
+
+// How close is close enough for each level of continuity.
+#[derive(Debug, Clone, Copy)]
+pub struct ContinuityTolerances {
+    pub position: f64,
+    // radians
+    pub angle: f64,
+    pub curvature: f64,
+}
+
+impl Default for ContinuityTolerances {
+    fn default() -> Self
+    {
+        return ContinuityTolerances { position: 1e-6, angle: 1e-3, curvature: 1e-3 };
+    }
+}
+
+// A line has its handles on its points so its derivative vanishes at the ends, but it doesn't bend anywhere.
+fn end_curvature(bez: &Bezier, t: f64) -> Option<f64>
+{
+    let p = bez.to_control_points();
+    let straight = (p[1] + -p[0]).cross(p[3] + -p[0]).abs() <= 1e-9 && (p[2] + -p[0]).cross(p[3] + -p[0]).abs() <= 1e-9;
+    if straight { return Some(0.); }
+
+    return bez.curvature(t);
+}
+
+fn joint(index: usize, incoming: &Bezier, outgoing: &Bezier, tolerances: &ContinuityTolerances) -> JointContinuity
+{
+    let gap = incoming.evaluate(1.).distance(outgoing.evaluate(0.));
+    let angle = incoming.tangent_at(1.).angle_to(outgoing.tangent_at(0.)).abs();
+    let curvature_difference = match (end_curvature(incoming, 1.), end_curvature(outgoing, 0.)) {
+        (Some(a), Some(b)) => Some((a - b).abs()),
+        _ => None,
+    };
+
+    let continuity = if gap > tolerances.position { Continuity::Broken }
+        else if angle > tolerances.angle { Continuity::G0 }
+        else if curvature_difference.map_or(true, |d| d > tolerances.curvature) { Continuity::G1 }
+        else { Continuity::G2 };
+
+    return JointContinuity { index: index, continuity: continuity, gap: gap, angle: angle, curvature_difference: curvature_difference };
+}
+
+impl Piecewise<Bezier>
+{
+    // Every joint between the contour's curves in order, and its start if it's closed, with the default tolerances.
+    pub fn continuity_report(&self) -> Vec<JointContinuity>
+    {
+        return self.continuity_report_with(&ContinuityTolerances::default());
+    }
+
+    pub fn continuity_report_with(&self, tolerances: &ContinuityTolerances) -> Vec<JointContinuity>
+    {
+        if self.curves.is_empty() { return Vec::new(); }
+
+        let mut output = Vec::new();
+        if self.curves.len() > 1 && self.is_closed() {
+            output.push(joint(0, self.curves.last().unwrap(), &self.curves[0], tolerances));
+        }
+
+        for i in 1..self.curves.len() {
+            output.push(joint(i, &self.curves[i - 1], &self.curves[i], tolerances));
+        }
+
+        return output;
+    }
+
+    // Closes every gap at a joint that's no wider than tolerance by moving both ends to halfway between them, their
+    // handles move with them. A contour whose ends are that close together comes back closed.
+    pub fn fix_c0(&self, tolerance: f64) -> Self
+    {
+        let mut curves = self.curves.clone();
+        let n = curves.len();
+        if n == 0 { return Piecewise { curves: curves }; }
+
+        let weld = |curves: &mut Vec<Bezier>, a: usize, b: usize| {
+            let (end, start) = (curves[a].to_control_points()[3], curves[b].to_control_points()[0]);
+            if end == start || end.distance(start) > tolerance { return; }
+
+            let middle = end.lerp(start, 0.5);
+            curves[a] = move_end(&curves[a], middle);
+            curves[b] = move_start(&curves[b], middle);
+        };
+
+        for i in 1..n {
+            weld(&mut curves, i - 1, i);
+        }
+        weld(&mut curves, n - 1, 0);
+
+        return Piecewise { curves: curves };
+    }
+}
@@ -0,0 +1,71 @@
+use super::*;
+
+// Where a contour has corners, the joints its direction jumps at, and cutting it into the smooth runs between them.
+
+// turns smaller than this, in radians, are rounding and not a corner anyone drew
+pub const CORNER_ANGLE: f64 = 1e-6;
+// curves with every control point closer together than this have no direction of their own
+const DEGENERATE_LENGTH: f64 = 1e-9;
+
+fn goes_somewhere(bez: &Bezier) -> bool
+{
+    let p = bez.to_control_points();
+    return p[1..].iter().any(|q| q.distance(p[0]) > DEGENERATE_LENGTH);
+}
+
+impl Piecewise<Bezier>
+{
+    // The joints where the contour turns by more than angle_threshold radians, as the index of the curve leaving
+    // each one. A closed contour's start counts as a joint too and shows up as 0. Curves that are all one point
+    // are looked through, the turn is measured between the curves either side of them and the index is the one
+    // after them, so a point doubled up in a glif doesn't make a corner.
+    pub fn find_corners(&self, angle_threshold: f64) -> Vec<usize>
+    {
+        let directional: Vec<usize> = (0..self.curves.len()).filter(|i| goes_somewhere(&self.curves[*i])).collect();
+        if directional.is_empty() { return Vec::new(); }
+
+        let mut output = Vec::new();
+        if self.is_closed() && directional.len() > 1 {
+            let (last, first) = (&self.curves[*directional.last().unwrap()], &self.curves[directional[0]]);
+            if last.tangent_at(1.).angle_to(first.tangent_at(0.)).abs() > angle_threshold {
+                output.push(directional[0]);
+            }
+        }
+
+        for pair in directional.windows(2) {
+            let (incoming, outgoing) = (self.curves[pair[0]].tangent_at(1.), self.curves[pair[1]].tangent_at(0.));
+            if incoming.angle_to(outgoing).abs() > angle_threshold {
+                output.push(pair[1]);
+            }
+        }
+
+        return output;
+    }
+
+    // The smooth runs between the contour's corners, each one open and in order along the contour. A closed contour
+    // comes back starting at its first corner with the run through its start joined up, and one with no corners at
+    // all comes back whole and still closed.
+    pub fn split_at_corners(&self, angle_threshold: f64) -> Vec<Piecewise<Bezier>>
+    {
+        let corners = self.find_corners(angle_threshold);
+        if corners.is_empty() { return vec![self.clone()]; }
+
+        // a closed contour is walked starting from its first corner so the run over its start comes out in one piece
+        let n = self.curves.len();
+        let start = if self.is_closed() { corners[0] } else { 0 };
+
+        let mut output = Vec::new();
+        let mut run: Vec<Bezier> = Vec::new();
+        for k in 0..n {
+            let i = (start + k) % n;
+            if corners.contains(&i) && !run.is_empty() {
+                output.push(Piecewise { curves: run });
+                run = Vec::new();
+            }
+            run.push(self.curves[i].clone());
+        }
+        output.push(Piecewise { curves: run });
+
+        return output;
+    }
+}
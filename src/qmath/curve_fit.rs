@@ -0,0 +1,223 @@
+use super::*;
+
+// Fitting cubics to runs of points, after Philip Schneider's "An Algorithm for Automatically Fitting Digitized
+// Curves" in Graphics Gems. The handles come from a least squares fit with the ends and the directions at the ends
+// held fixed, then we nudge each point's t towards the closest point on the fit with Newton's method and fit again.
+
+// how many rounds of nudging t we'll do before giving up on a fit
+const MAX_ITERATIONS: usize = 4;
+// a fit whose error is within this many times max_error is worth nudging, anything worse just gets split
+const ITERATION_ERROR_FACTOR: f64 = 4.;
+// shorter than this and a tangent or a chord doesn't point anywhere
+const DEGENERATE_LENGTH: f64 = 1e-9;
+
+fn unit_or(v: Vector, fallback: Vector) -> Vector
+{
+    if v.magnitude() > DEGENERATE_LENGTH { return v.normalize(); }
+    return fallback;
+}
+
+// t for each point by how far along the polyline through them it is.
+fn chord_parameterize(points: &[Vector]) -> Vec<f64>
+{
+    let mut output = vec![0.];
+    for pair in points.windows(2) {
+        output.push(output.last().unwrap() + pair[0].distance(pair[1]));
+    }
+
+    let total = *output.last().unwrap();
+    if total <= 0. { return output.iter().enumerate().map(|(i, _)| i as f64 / (points.len() - 1) as f64).collect(); }
+
+    return output.iter().map(|s| s / total).collect();
+}
+
+// The least squares cubic through the ends of points leaving along start_tangent and arriving along end_tangent,
+// which are unit vectors pointing the way the curve travels, with each point matched to the curve at its t in
+// params. When the system is singular or wants a handle pointing backwards we fall back on handles a third of the
+// chord long.
+fn generate(points: &[Vector], params: &[f64], start_tangent: Vector, end_tangent: Vector) -> Bezier
+{
+    let first = points[0];
+    let last = *points.last().unwrap();
+
+    let mut c = [[0.; 2]; 2];
+    let mut x = [0.; 2];
+    for (point, t) in points.iter().zip(params.iter()) {
+        let mt = 1. - t;
+        let b0 = mt * mt * mt;
+        let b1 = 3. * t * mt * mt;
+        let b2 = 3. * t * t * mt;
+        let b3 = t * t * t;
+
+        let a1 = start_tangent * b1;
+        let a2 = end_tangent * -b2;
+
+        c[0][0] += a1.dot(a1);
+        c[0][1] += a1.dot(a2);
+        c[1][1] += a2.dot(a2);
+
+        let rest = *point + -(first * (b0 + b1) + last * (b2 + b3));
+        x[0] += a1.dot(rest);
+        x[1] += a2.dot(rest);
+    }
+    c[1][0] = c[0][1];
+
+    let det = c[0][0] * c[1][1] - c[1][0] * c[0][1];
+    let chord = first.distance(last);
+    let epsilon = 1e-6 * chord;
+
+    let (mut alpha_start, mut alpha_end) = (chord / 3., chord / 3.);
+    if det.abs() > 1e-12 {
+        let a = (x[0] * c[1][1] - x[1] * c[0][1]) / det;
+        let b = (c[0][0] * x[1] - c[1][0] * x[0]) / det;
+        if a > epsilon && b > epsilon {
+            alpha_start = a;
+            alpha_end = b;
+        }
+    }
+
+    return Bezier::from_control_points(first, first + start_tangent * alpha_start, last + end_tangent * -alpha_end, last);
+}
+
+// The furthest any point is from the curve at its t, and which point that is.
+fn max_error(bez: &Bezier, points: &[Vector], params: &[f64]) -> (f64, usize)
+{
+    let mut worst = (0., points.len() / 2);
+    for (i, (point, t)) in points.iter().zip(params.iter()).enumerate() {
+        let d = bez.evaluate(*t).distance(*point);
+        if d > worst.0 { worst = (d, i); }
+    }
+
+    return worst;
+}
+
+// One Newton step on each t towards the closest point on bez, which is where (Q(t) - P) . Q'(t) is zero.
+fn reparameterize(bez: &Bezier, points: &[Vector], params: &[f64]) -> Vec<f64>
+{
+    return points.iter().zip(params.iter()).map(|(point, t)| {
+        let t = *t;
+        let d1 = Vector {
+            x: 3. * bez.A * t * t + 2. * bez.B * t + bez.C,
+            y: 3. * bez.E * t * t + 2. * bez.F * t + bez.G
+        };
+        let d2 = bez.derivative2(t);
+        let diff = bez.evaluate(t) + -*point;
+
+        let denominator = d1.dot(d1) + diff.dot(d2);
+        if denominator.abs() <= 1e-12 { return t; }
+
+        return f64::min(f64::max(t - diff.dot(d1) / denominator, 0.), 1.);
+    }).collect();
+}
+
+// Fits points with their ts, nudging them up to MAX_ITERATIONS times until the error is within good_enough. A
+// first fit that's worse than hopeless isn't nudged at all. Gives back the best fit we found along with its error
+// and the point that error was at.
+fn fit_iterating(points: &[Vector], start_tangent: Vector, end_tangent: Vector, good_enough: f64, hopeless: f64) -> (Bezier, f64, usize)
+{
+    let mut params = chord_parameterize(points);
+    let mut bez = generate(points, &params, start_tangent, end_tangent);
+    let (mut error, mut split) = max_error(&bez, points, &params);
+
+    if error > hopeless { return (bez, error, split); }
+
+    for _ in 0..MAX_ITERATIONS {
+        if error <= good_enough { break; }
+
+        params = reparameterize(&bez, points, &params);
+        let candidate = generate(points, &params, start_tangent, end_tangent);
+        let (candidate_error, candidate_split) = max_error(&candidate, points, &params);
+        if candidate_error < error {
+            bez = candidate;
+            error = candidate_error;
+            split = candidate_split;
+        }
+    }
+
+    return (bez, error, split);
+}
+
+// Splits at the worst point until every piece is within max_allowed. Both pieces share the direction through the
+// point we split at so they join smoothly.
+fn fit_recursive(points: &[Vector], start_tangent: Vector, end_tangent: Vector, max_allowed: f64, output: &mut Vec<Bezier>)
+{
+    if points.len() == 2 {
+        output.push(generate(points, &[0., 1.], start_tangent, end_tangent));
+        return;
+    }
+
+    let (bez, error, split) = fit_iterating(points, start_tangent, end_tangent, max_allowed, max_allowed * ITERATION_ERROR_FACTOR);
+    if error <= max_allowed {
+        output.push(bez);
+        return;
+    }
+
+    // the ends can't be the split point, there would be nothing on one side
+    let split = split.max(1).min(points.len() - 2);
+    let chord = points[split + 1] + -points[split - 1];
+    let center = unit_or(chord, unit_or(points[split] + -points[split - 1], start_tangent));
+
+    fit_recursive(&points[..=split], start_tangent, center, max_allowed, output);
+    fit_recursive(&points[split..], center, end_tangent, max_allowed, output);
+}
+
+// Runs of the same point don't tell us anything and make for zero length chords, we only keep the first of each.
+fn dedup_points(points: &[Vector]) -> Vec<Vector>
+{
+    let mut output: Vec<Vector> = Vec::new();
+    for point in points {
+        match output.last() {
+            Some(last) if last.distance(*point) <= DEGENERATE_LENGTH => {}
+            _ => output.push(*point),
+        }
+    }
+
+    return output;
+}
+
+impl Bezier {
+    // The single cubic that best fits points in the least squares sense, starting on the first and ending on the
+    // last. The tangents point the way the curve travels at each end, the one at the end is the direction it
+    // arrives in, and their lengths don't matter. A tangent of zero length falls back on the chord.
+    pub fn fit(points: &[Vector], start_tangent: Vector, end_tangent: Vector) -> Bezier
+    {
+        let points = dedup_points(points);
+        if points.len() < 2 {
+            let p = points.first().cloned().unwrap_or(Vector { x: 0., y: 0. });
+            return Bezier::from_control_points(p, p, p, p);
+        }
+
+        let chord = unit_or(*points.last().unwrap() + -points[0], Vector { x: 1., y: 0. });
+        let start_tangent = unit_or(start_tangent, chord);
+        let end_tangent = unit_or(end_tangent, chord);
+
+        if points.len() == 2 {
+            return generate(&points, &[0., 1.], start_tangent, end_tangent);
+        }
+
+        return fit_iterating(&points, start_tangent, end_tangent, 0., f64::INFINITY).0;
+    }
+}
+
+impl Piecewise<Bezier>
+{
+    // Fits a smooth run of cubics through points that nowhere stray more than max_error from them, splitting at the
+    // worst point until each piece fits. Consecutive curves leave in the direction the last one arrived so they're
+    // G1 continuous. Fewer than two distinct points gives an empty Piecewise.
+    pub fn fit_curve(points: &[Vector], max_error: f64) -> Piecewise<Bezier>
+    {
+        let points = dedup_points(points);
+        if points.len() < 2 {
+            return Piecewise { curves: Vec::new() };
+        }
+
+        let n = points.len();
+        let start_tangent = (points[1] + -points[0]).normalize();
+        let end_tangent = (points[n - 1] + -points[n - 2]).normalize();
+
+        let mut output = Vec::new();
+        fit_recursive(&points, start_tangent, end_tangent, max_error, &mut output);
+
+        return Piecewise { curves: output };
+    }
+}
@@ -0,0 +1,66 @@
+use super::*;
+
+// Cutting a contour into dashes. The pattern works like SVG's stroke-dasharray: lengths along the contour that
+// alternate between on and off starting with on, and an odd number of them is repeated to make it even. The offset
+// is how far into the pattern the contour starts.
+
+impl Piecewise<Bezier>
+{
+    // The on pieces of the dash pattern as open contours, cut by arc length. An on length of zero gives a dot, a
+    // contour with a single curve that's all one point, for a stroker with round caps to turn into a circle. On a
+    // closed contour the pattern carries on round the start, so a dash running over the start comes back as one
+    // piece rather than two. A pattern with nothing in it or negative lengths leaves the contour whole.
+    pub fn dash(&self, pattern: &[f64], offset: f64) -> Vec<Piecewise<Bezier>>
+    {
+        let pattern: Vec<f64> = if pattern.len() % 2 == 1 { pattern.iter().chain(pattern.iter()).cloned().collect() } else { pattern.to_vec() };
+        let period: f64 = pattern.iter().sum();
+        if self.curves.is_empty() || pattern.iter().any(|l| *l < 0.) || !(period > 0.) {
+            return vec![self.clone()];
+        }
+
+        let measure = PathMeasure::new(self);
+        let length = measure.length();
+
+        // find where in the pattern the offset puts the start of the contour
+        let mut i = 0;
+        let mut phase = offset.rem_euclid(period);
+        while phase > pattern[i] {
+            phase -= pattern[i];
+            i = (i + 1) % pattern.len();
+        }
+
+        let mut output = Vec::new();
+        let mut starts_on = false;
+        let mut ends_on = false;
+        let mut s = 0.;
+        let mut remaining = pattern[i] - phase;
+        loop {
+            let end = f64::min(s + remaining, length);
+
+            if i % 2 == 0 {
+                if end > s {
+                    output.push(measure.segment(s, end));
+                    if s <= 0. { starts_on = true; }
+                    if end >= length { ends_on = true; }
+                } else {
+                    let p = measure.pos_tan_at(s).0;
+                    output.push(Piecewise { curves: vec![Bezier::from_control_points(p, p, p, p)] });
+                }
+            }
+
+            if s + remaining >= length { break; }
+
+            s += remaining;
+            i = (i + 1) % pattern.len();
+            remaining = pattern[i];
+        }
+
+        // the last dash runs on into the first round the start
+        if self.is_closed() && starts_on && ends_on && output.len() > 1 {
+            let first = output.remove(0);
+            output.last_mut().unwrap().curves.extend(first.curves);
+        }
+
+        return output;
+    }
+}
@@ -0,0 +1,125 @@
+use super::*;
+
+// How far one contour strays from another, the Hausdorff distance between them. Every point on a piece of curve is
+// at most as far from a point q as the furthest of the piece's control points, and it's at least as close to the
+// other contour as it is to q. So with q the nearest point on the other contour to either end of the piece, that
+// gives us a ceiling on how far anything on the piece can be. We halve pieces until the ceiling is within tolerance
+// of the furthest point we've actually measured, so the answer is never short by more than tolerance. It's only
+// as good as the nearest point search behind it, see nearest.rs.
+
+// evenly spaced points per curve we measure before refining, a good first guess lets us throw pieces away early
+const DEVIATION_SAMPLES: usize = 16;
+// past this many halvings a piece is a millionth of its curve and we take its ceiling as it is
+const MAX_DEVIATION_DEPTH: usize = 20;
+
+// the point on other nearest to point and how far away it is
+fn nearest_on(other: &Piecewise<Bezier>, point: Vector) -> (Vector, f64)
+{
+    let (_, q, distance) = other.project(point).unwrap();
+    return (q, distance);
+}
+
+fn ceiling(piece: &Bezier, q: Vector) -> f64
+{
+    return piece.to_control_points().iter().map(|p| p.distance(q)).fold(0., f64::max);
+}
+
+fn refine(piece: &Bezier, q0: Vector, q1: Vector, other: &Piecewise<Bezier>, tolerance: f64, depth: usize, worst: &mut f64)
+{
+    let bound = ceiling(piece, q0).min(ceiling(piece, q1));
+    if bound <= *worst + tolerance { return; }
+    if depth >= MAX_DEVIATION_DEPTH {
+        *worst = worst.max(bound);
+        return;
+    }
+
+    let (left, right) = piece.subdivide(0.5);
+    let (qm, dm) = nearest_on(other, right.to_control_points()[0]);
+    *worst = worst.max(dm);
+
+    refine(&left, q0, qm, other, tolerance, depth + 1, worst);
+    refine(&right, qm, q1, other, tolerance, depth + 1, worst);
+}
+
+impl Piecewise<Bezier>
+{
+    // The furthest any point on this contour is from other, to within tolerance. This is one sided, a short
+    // contour lying along a long one is close to it even though most of the long one is far away from it. Zero
+    // if both are empty and infinite if only one is.
+    pub fn directed_deviation_from(&self, other: &Piecewise<Bezier>, tolerance: f64) -> f64
+    {
+        if self.curves.is_empty() || other.curves.is_empty() {
+            return if self.curves.is_empty() && other.curves.is_empty() { 0. } else { f64::INFINITY };
+        }
+
+        let mut worst: f64 = 0.;
+        for bez in &self.curves {
+            let nearest: Vec<(Vector, f64)> = (0..=DEVIATION_SAMPLES)
+                .map(|i| nearest_on(other, bez.evaluate(i as f64 / DEVIATION_SAMPLES as f64)))
+                .collect();
+            worst = nearest.iter().map(|n| n.1).fold(worst, f64::max);
+
+            let cuts: Vec<f64> = (1..DEVIATION_SAMPLES).map(|i| i as f64 / DEVIATION_SAMPLES as f64).collect();
+            for (i, piece) in bez.split_at_multiple(&cuts).iter().enumerate() {
+                refine(piece, nearest[i].0, nearest[i + 1].0, other, tolerance, 0, &mut worst);
+            }
+        }
+
+        return worst;
+    }
+
+    // The Hausdorff distance between the two contours to within tolerance, how far the furthest point on either
+    // one is from the other. Two approximations of the same shape agree to within this everywhere.
+    pub fn max_deviation_from(&self, other: &Piecewise<Bezier>, tolerance: f64) -> f64
+    {
+        return self.directed_deviation_from(other, tolerance).max(other.directed_deviation_from(self, tolerance));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(x: f64, y: f64) -> Vector
+    {
+        return Vector { x: x, y: y };
+    }
+
+    fn line(from: Vector, to: Vector) -> Piecewise<Bezier>
+    {
+        return Piecewise::new(vec![Bezier::from_control_points(from, from, to, to)]);
+    }
+
+    // A short line lying along a long one is right on it, the other way round the far end is 50 away.
+    #[test]
+    fn one_sided_and_symmetric()
+    {
+        let (long, short) = (line(v(0., 0.), v(100., 0.)), line(v(0., 0.), v(50., 0.)));
+        assert!(short.directed_deviation_from(&long, 1e-3) < 1e-9);
+        assert!((long.directed_deviation_from(&short, 1e-3) - 50.).abs() <= 1e-3);
+        assert!((long.max_deviation_from(&short, 1e-3) - 50.).abs() <= 1e-3);
+
+        let empty = Piecewise::<Bezier>::new(vec![]);
+        assert_eq!((empty.max_deviation_from(&empty, 1e-3), empty.max_deviation_from(&long, 1e-3)), (0., f64::INFINITY));
+
+        // two circles ten apart, less however far apart the two cubic approximations of a circle get
+        let (inner, outer) = (Piecewise::circle(v(0., 0.), 100.), Piecewise::circle(v(0., 0.), 110.));
+        let deviation = inner.max_deviation_from(&outer, 1e-4);
+        assert!((deviation - 10.).abs() < 0.01, "{}", deviation);
+    }
+
+    // An S across a line, against how far a dense brute force scan gets. We can be out by up to tolerance either
+    // way, and a finer tolerance gets closer.
+    #[test]
+    fn matches_a_dense_scan()
+    {
+        let s = Piecewise::new(vec![Bezier::from_control_points(v(0., 0.), v(40., 90.), v(60., -70.), v(100., 10.))]);
+        let base = line(v(0., 0.), v(100., 0.));
+
+        let scan = (0..=100000).map(|k| base.nearest(s.evaluate(k as f64 / 100000.)).unwrap().2).fold(0., f64::max);
+        for tolerance in &[1., 1e-2, 1e-4] {
+            let deviation = s.directed_deviation_from(&base, *tolerance);
+            assert!((deviation - scan).abs() <= *tolerance, "{} against {} at {}", deviation, scan, tolerance);
+        }
+    }
+}
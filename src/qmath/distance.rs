@@ -0,0 +1,210 @@
+use super::*;
+
+// How close two curves come to each other. Curves that cross are zero apart wherever they cross. For the rest the
+// closest approach is either an end of one curve against the other, which projecting the ends finds, or somewhere
+// both curves are square to the line between them, which we get at by sampling both and then projecting back and
+// forth from the closest pair of samples until it stops getting closer.
+//
+// Piecewises and outlines find their closest pair of segments through SegmentIndex::nearest_pairs, so segments whose
+// bounds are already further apart than the closest pair so far are never looked at.
+
+// how many places along each curve we sample before refining
+const DISTANCE_SAMPLES: usize = 16;
+// the most times we project back and forth
+const REFINE_STEPS: usize = 16;
+// a projection that doesn't get closer than this has converged
+const REFINE_EPSILON: f64 = 1e-12;
+
+impl Bezier {
+    // Projects back and forth between the curves starting from ta on self until the two points stop getting closer.
+    fn refine_closest(&self, other: &Bezier, mut ta: f64) -> (f64, f64, f64)
+    {
+        let (mut tb, mut distance) = other.nearest(self.evaluate(ta));
+
+        for _ in 0..REFINE_STEPS {
+            let (next_ta, _) = self.nearest(other.evaluate(tb));
+            let (next_tb, next_distance) = other.nearest(self.evaluate(next_ta));
+            if !(next_distance < distance - REFINE_EPSILON) {
+                if next_distance < distance { ta = next_ta; tb = next_tb; distance = next_distance; }
+                break;
+            }
+            ta = next_ta;
+            tb = next_tb;
+            distance = next_distance;
+        }
+
+        return (distance, ta, tb);
+    }
+
+    // The least distance between the two curves and the t on each where it happens. Curves that cross give zero at
+    // one of their crossings.
+    pub fn min_distance_to(&self, other: &Bezier) -> (f64, f64, f64)
+    {
+        if self.bounds().overlaps(&other.bounds()) {
+            if let Some((ta, tb)) = self.intersect_bezier(other).first() {
+                return (0., *ta, *tb);
+            }
+        }
+
+        let ours: Vec<Vector> = (0..=DISTANCE_SAMPLES).map(|i| self.evaluate(i as f64 / DISTANCE_SAMPLES as f64)).collect();
+        let theirs: Vec<Vector> = (0..=DISTANCE_SAMPLES).map(|j| other.evaluate(j as f64 / DISTANCE_SAMPLES as f64)).collect();
+        let mut closest = (f64::INFINITY, 0);
+        for (i, p) in ours.iter().enumerate() {
+            for q in &theirs {
+                if p.distance(*q) < closest.0 { closest = (p.distance(*q), i); }
+            }
+        }
+
+        let mut best = self.refine_closest(other, closest.1 as f64 / DISTANCE_SAMPLES as f64);
+
+        // each end against the other curve
+        for t in [0., 1.].iter() {
+            let (tb, distance) = other.nearest(self.evaluate(*t));
+            if distance < best.0 { best = (distance, *t, tb); }
+
+            let (ta, distance) = self.nearest(other.evaluate(*t));
+            if distance < best.0 { best = (distance, ta, *t); }
+        }
+
+        return best;
+    }
+}
+
+impl Piecewise<Bezier>
+{
+    // The least distance between the two contours and the global t on each where it happens, zero at a crossing
+    // if they cross. An empty contour is infinitely far from everything, at t 0.
+    pub fn min_distance_to(&self, other: &Piecewise<Bezier>) -> (f64, f64, f64)
+    {
+        let ours = SegmentIndex::from_contour(self);
+        let theirs = SegmentIndex::from_contour(other);
+
+        let mut best = (f64::INFINITY, 0., 0.);
+        ours.nearest_pairs(&theirs, f64::INFINITY, |(_, i), (_, j), _| {
+            let (distance, ta, tb) = self.curves[i].min_distance_to(&other.curves[j]);
+            if distance < best.0 { best = (distance, self.global_t(i, ta), other.global_t(j, tb)); }
+            return distance;
+        });
+
+        return best;
+    }
+}
+
+// The closest two segments of a and b come that's nearer than within, skipping pairs on the same contour when a
+// and b are the same outline.
+fn closest_between(a: &Piecewise<Piecewise<Bezier>>, b: &Piecewise<Piecewise<Bezier>>, within: f64, same: bool) -> Option<(f64, CurveLocation, CurveLocation)>
+{
+    let ours = SegmentIndex::from_piecewise(a);
+    let theirs = if same { None } else { Some(SegmentIndex::from_piecewise(b)) };
+
+    let mut best: Option<(f64, CurveLocation, CurveLocation)> = None;
+    ours.nearest_pairs(theirs.as_ref().unwrap_or(&ours), within, |(ac, asg), (bc, bsg), bound| {
+        // each pair shows up both ways round against itself, once is enough
+        if same && ac >= bc { return bound; }
+
+        let (distance, ta, tb) = a.curves[ac].curves[asg].min_distance_to(&b.curves[bc].curves[bsg]);
+        if distance < best.map_or(within, |found| found.0) {
+            best = Some((distance, CurveLocation { contour: ac, segment: asg, t: ta }, CurveLocation { contour: bc, segment: bsg, t: tb }));
+        }
+        return distance;
+    });
+
+    return best;
+}
+
+impl Piecewise<Piecewise<Bezier>>
+{
+    // The least distance between the two outlines and where on each it happens, zero at a crossing if they cross.
+    // None if either has no curves.
+    pub fn min_distance_to(&self, other: &Piecewise<Piecewise<Bezier>>) -> Option<(f64, CurveLocation, CurveLocation)>
+    {
+        return closest_between(self, other, f64::INFINITY, false);
+    }
+
+    // How close the outline's contours come to each other, with where on each, the first location's contour always
+    // the lower. Zero if two of them cross. A contour coming close to itself isn't counted, its neighbouring curves
+    // always touch. None with fewer than two contours that have curves.
+    pub fn clearance(&self) -> Option<(f64, CurveLocation, CurveLocation)>
+    {
+        return closest_between(self, self, f64::INFINITY, true);
+    }
+
+    // Whether any two of the outline's contours come closer than distance. Cheaper than clearance, segments whose
+    // bounds are distance apart or more are never measured.
+    pub fn comes_within(&self, distance: f64) -> bool
+    {
+        return closest_between(self, self, distance, true).is_some();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(x: f64, y: f64) -> Vector
+    {
+        return Vector { x: x, y: y };
+    }
+
+    // Circles one inside the other are as far apart as their radii, less the little the inner one bulges out by,
+    // and the ts given are where they're that far apart.
+    #[test]
+    fn concentric_circles()
+    {
+        let inner = Piecewise::circle(v(0., 0.), 100.);
+        let outer = Piecewise::circle(v(0., 0.), 130.);
+
+        let (distance, ta, tb) = inner.min_distance_to(&outer);
+        assert!(distance <= 30. && distance > 30. - 100. * 2.8e-4, "{}", distance);
+        assert!((inner.evaluate(ta).distance(outer.evaluate(tb)) - distance).abs() < 1e-9);
+    }
+
+    // Crossing contours are zero apart at the crossing, and non-crossing ones match brute force sampling of every
+    // segment pair, so pruning never skips the closest pair.
+    #[test]
+    fn crossing_and_against_brute_force()
+    {
+        let line = |from: Vector, to: Vector| Bezier::from_control_points(from, from, to, to);
+        let zigzag = Piecewise::new(vec![line(v(0., 0.), v(100., 50.)), line(v(100., 50.), v(200., 0.)), line(v(200., 0.), v(300., 50.))]);
+        let across = Piecewise::new(vec![line(v(150., -20.), v(150., 100.))]);
+        let (distance, ta, tb) = zigzag.min_distance_to(&across);
+        assert!(distance < 1e-9);
+        assert!(zigzag.evaluate(ta).is_near(v(150., 25.), 1e-6) && across.evaluate(tb).is_near(v(150., 25.), 1e-6), "{:?} {:?} {} {}", zigzag.evaluate(ta), across.evaluate(tb), ta, tb);
+
+        let wave = Piecewise::new(vec![
+            Bezier::from_control_points(v(0., 120.), v(60., 60.), v(120., 180.), v(180., 90.)),
+            Bezier::from_control_points(v(180., 90.), v(220., 30.), v(260., 150.), v(320., 100.)),
+        ]);
+        let (distance, _, _) = zigzag.min_distance_to(&wave);
+
+        let mut brute = f64::INFINITY;
+        for i in 0..=2000 {
+            let p = zigzag.evaluate(i as f64 / 2000.);
+            for j in 0..=2000 {
+                brute = brute.min(p.distance(wave.evaluate(j as f64 / 2000.)));
+            }
+        }
+        assert!(distance <= brute + 1e-9 && distance > brute - 0.5, "{} against {} sampled", distance, brute);
+    }
+
+    // Two squares 40 apart in one outline, and a third off to the side further still.
+    #[test]
+    fn outline_clearance()
+    {
+        let square = |left: f64| Piecewise::rect(Rect { left: left, bottom: 0., right: left + 100., top: 100. })[0].clone();
+        let outline = Piecewise::new(vec![square(0.), square(140.), square(400.)]);
+
+        let (distance, a, b) = outline.clearance().unwrap();
+        assert!((distance - 40.).abs() < 1e-9, "{}", distance);
+        assert_eq!((a.contour, b.contour), (0, 1));
+        assert!(outline.comes_within(50.));
+        assert!(!outline.comes_within(40.));
+
+        let other = Piecewise::new(vec![square(-300.)]);
+        let (distance, a, b) = outline.min_distance_to(&other).unwrap();
+        assert!((distance - 200.).abs() < 1e-9, "{}", distance);
+        assert_eq!((a.contour, b.contour), (0, 0));
+
+        assert!(Piecewise::new(vec![square(0.)]).clearance().is_none());
+    }
+}
@@ -0,0 +1,498 @@
+use super::*;
+
+// How remove_segment closes the gap a segment leaves behind.
+// MoveNext - the next segment's start moves back to where the removed one started.
+// MovePrev - the previous segment's end moves forward to where the removed one ended.
+// InsertLine - a straight line takes the removed segment's place.
+// Gap - it doesn't, a closed contour opens up there and an open one comes apart in two.
+// Refit - the segment and the ones either side of it become a single cubic that keeps close to their old shape.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WeldMode {
+    MoveNext,
+    MovePrev,
+    InsertLine,
+    Gap,
+    Refit,
+}
+
+#[derive(Debug)]
+pub enum EditError {
+    OutOfRange(usize),
+    // the edit would leave the contour with no segments, or there was nothing to put in
+    Empty,
+    // the new curves don't meet their neighbours, this is how far apart the worst gap is
+    Discontinuous(f64),
+    // the edit only makes sense on a closed contour
+    OpenContour,
+}
+
+impl std::fmt::Display for EditError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        match self {
+            EditError::OutOfRange(i) => write!(f, "There's no segment {} in this contour.", i),
+            EditError::Empty => write!(f, "The edit would leave nothing behind."),
+            EditError::Discontinuous(gap) => write!(f, "The new curves miss their neighbours by {}.", gap),
+            EditError::OpenContour => write!(f, "The contour has to be closed for that."),
+        }
+    }
+}
+
+// Gaps smaller than this are closed by snapping, anything bigger is an error.
+const WELD_TOLERANCE: f64 = 1e-3;
+
+// How many points Refit samples from each segment it merges.
+const REFIT_SAMPLES: usize = 16;
+
+// Moving an end drags its handle along so the curve keeps its shape near the end.
+pub(crate) fn move_start(bez: &Bezier, to: Vector) -> Bezier
+{
+    let p = bez.to_control_points();
+    let delta = to + -p[0];
+    return Bezier::from_control_points(to, p[1] + delta, p[2], p[3]);
+}
+
+pub(crate) fn move_end(bez: &Bezier, to: Vector) -> Bezier
+{
+    let p = bez.to_control_points();
+    let delta = to + -p[3];
+    return Bezier::from_control_points(p[0], p[1], p[2] + delta, to);
+}
+
+// Checks a run of curves is continuous and, when given, starts at start and ends at end. Small gaps get snapped
+// shut, the run is only touched if everything is within tolerance.
+fn weld(curves: &mut Vec<Bezier>, start: Option<Vector>, end: Option<Vector>, tolerance: f64) -> Result<(), EditError>
+{
+    let mut worst: f64 = 0.;
+    for pair in curves.windows(2) {
+        worst = worst.max(pair[0].evaluate(1.).distance(pair[1].evaluate(0.)));
+    }
+    if let Some(s) = start { worst = worst.max(curves[0].evaluate(0.).distance(s)); }
+    if let Some(e) = end { worst = worst.max(curves[curves.len() - 1].evaluate(1.).distance(e)); }
+
+    if worst > tolerance { return Err(EditError::Discontinuous(worst)); }
+
+    for i in 1..curves.len() {
+        let joint = curves[i - 1].to_control_points()[3];
+        curves[i] = move_start(&curves[i], joint);
+    }
+    if let Some(s) = start { curves[0] = move_start(&curves[0], s); }
+    if let Some(e) = end {
+        let last = curves.len() - 1;
+        curves[last] = move_end(&curves[last], e);
+    }
+
+    return Ok(());
+}
+
+impl Piecewise<Bezier>
+{
+    // Swaps segment i for curves. Where they start or end within WELD_TOLERANCE of segment i they're snapped onto
+    // its ends. Further away they move the joint, the segment on that side has its end dragged, handle and all,
+    // onto theirs so the contour stays joined up. The open ends of an open contour just go where the curves do.
+    pub fn replace_segment(&mut self, i: usize, mut curves: Vec<Bezier>) -> Result<(), EditError>
+    {
+        let count = self.curves.len();
+        if i >= count { return Err(EditError::OutOfRange(i)); }
+        if curves.is_empty() { return Err(EditError::Empty); }
+
+        let old = self.curves[i].to_control_points();
+        let start = curves[0].to_control_points()[0];
+        let end = curves[curves.len() - 1].to_control_points()[3];
+        let pin = |from: Vector, to: Vector| if from.distance(to) <= WELD_TOLERANCE { Some(to) } else { None };
+        let (pin_start, pin_end) = (pin(start, old[0]), pin(end, old[3]));
+        weld(&mut curves, pin_start, pin_end, WELD_TOLERANCE)?;
+
+        let closed = self.is_closed();
+        let prev = if i > 0 || closed { Some((i + count - 1) % count) } else { None };
+        let next = if i < count - 1 || closed { Some((i + 1) % count) } else { None };
+        let (start, end) = (curves[0].to_control_points()[0], curves[curves.len() - 1].to_control_points()[3]);
+
+        // a closed contour of one segment is its own neighbour
+        if let Some(k) = prev.filter(|k| pin_start.is_none() && *k != i) { self.curves[k] = move_end(&self.curves[k], start); }
+        if let Some(k) = next.filter(|k| pin_end.is_none() && *k != i) { self.curves[k] = move_start(&self.curves[k], end); }
+
+        self.curves.splice(i..i + 1, curves);
+        return Ok(());
+    }
+
+    // Puts curves in before segment i, i == len appends. They start where the previous segment ends and end
+    // where segment i starts, so on a closed contour they have to come back to where they began. The open ends of
+    // an open contour are free, inserting there extends the contour.
+    pub fn insert_segments(&mut self, i: usize, mut curves: Vec<Bezier>) -> Result<(), EditError>
+    {
+        let count = self.curves.len();
+        if i > count { return Err(EditError::OutOfRange(i)); }
+        if curves.is_empty() { return Err(EditError::Empty); }
+
+        if count == 0 {
+            weld(&mut curves, None, None, WELD_TOLERANCE)?;
+            self.curves = curves;
+            return Ok(());
+        }
+
+        let closed = self.is_closed();
+        let prev_end = if i > 0 { Some(self.curves[i - 1].to_control_points()[3]) }
+            else if closed { Some(self.curves[count - 1].to_control_points()[3]) }
+            else { None };
+        let next_start = if i < count { Some(self.curves[i].to_control_points()[0]) }
+            else if closed { Some(self.curves[0].to_control_points()[0]) }
+            else { None };
+
+        weld(&mut curves, prev_end, next_start, WELD_TOLERANCE)?;
+
+        self.curves.splice(i..i, curves);
+        return Ok(());
+    }
+
+    // Puts curves on after the last segment, starting where it ends. Unlike insert_segments at len the contour is
+    // never taken to be closed, so this is how to build one up a run at a time whether or not its ends happen to
+    // meet along the way, finishing with close. Runs put together from approximations only meet to within their
+    // own tolerance, so that's what we snap to here.
+    pub fn append_segments(&mut self, mut curves: Vec<Bezier>, tolerance: f64) -> Result<(), EditError>
+    {
+        if curves.is_empty() { return Err(EditError::Empty); }
+
+        let end = self.curves.last().map(|bez| bez.to_control_points()[3]);
+        weld(&mut curves, end, None, tolerance)?;
+
+        self.curves.extend(curves);
+        return Ok(());
+    }
+
+    // Snaps the end of the contour onto its start, if they're within tolerance of each other.
+    pub fn close(&mut self, tolerance: f64) -> Result<(), EditError>
+    {
+        let last = match self.curves.len() {
+            0 => return Err(EditError::Empty),
+            count => count - 1,
+        };
+
+        let start = self.curves[0].to_control_points()[0];
+        let gap = self.curves[last].to_control_points()[3].distance(start);
+        if gap > tolerance { return Err(EditError::Discontinuous(gap)); }
+
+        self.curves[last] = move_end(&self.curves[last], start);
+        return Ok(());
+    }
+
+    // Takes segment i out. Removing the first or last segment of an open contour just shortens it, anywhere else
+    // the gap gets closed the way weld says. A closed contour opened up by Gap starts with the segment that came
+    // after i, Gap in the middle of an open contour keeps the part before i and hands back the part after it.
+    // Refit merges segment i with the neighbours it has, at the ends of an open contour too, into one cubic running
+    // from the first one's start to the last one's end and leaving the tangents there alone. When that wraps round
+    // the start of a closed contour the fitted curve becomes the first segment.
+    pub fn remove_segment(&mut self, i: usize, weld: WeldMode) -> Result<Option<Piecewise<Bezier>>, EditError>
+    {
+        let count = self.curves.len();
+        if i >= count { return Err(EditError::OutOfRange(i)); }
+        if count == 1 { return Err(EditError::Empty); }
+
+        let closed = self.is_closed();
+        match weld {
+            WeldMode::Gap => return Ok(self.open_at(i, closed)),
+            WeldMode::Refit => {
+                self.refit_around(i, closed);
+                return Ok(None);
+            }
+            _ => {}
+        }
+
+        let removed = self.curves.remove(i).to_control_points();
+        if !closed && (i == 0 || i == count - 1) { return Ok(None); }
+
+        // indices into what's left, on a closed contour these wrap around
+        let remaining = count - 1;
+        let next = i % remaining;
+        let prev = (i + remaining - 1) % remaining;
+
+        match weld {
+            WeldMode::MoveNext => self.curves[next] = move_start(&self.curves[next], removed[0]),
+            WeldMode::MovePrev => self.curves[prev] = move_end(&self.curves[prev], removed[3]),
+            WeldMode::InsertLine => {
+                let line = Bezier::from_control_points(removed[0], removed[0], removed[3], removed[3]);
+                self.curves.insert(i, line);
+            }
+            // these were seen to above
+            WeldMode::Gap | WeldMode::Refit => {}
+        }
+
+        return Ok(None);
+    }
+
+    // remove_segment's Gap.
+    fn open_at(&mut self, i: usize, closed: bool) -> Option<Piecewise<Bezier>>
+    {
+        if closed {
+            self.curves.rotate_left(i + 1);
+            self.curves.pop();
+            return None;
+        }
+
+        let rest = self.curves.split_off(i + 1);
+        self.curves.pop();
+        if self.curves.is_empty() {
+            self.curves = rest;
+            return None;
+        }
+        if rest.is_empty() { return None; }
+        return Some(Piecewise { curves: rest });
+    }
+
+    // remove_segment's Refit.
+    fn refit_around(&mut self, i: usize, closed: bool)
+    {
+        let count = self.curves.len();
+
+        // the first and last segments of the span being merged
+        let first = if i > 0 || closed { (i + count - 1) % count } else { i };
+        let last = if i < count - 1 || closed { (i + 1) % count } else { i };
+
+        // on a closed contour of two both neighbours are the same segment and the whole contour gets merged
+        let span = if first == last { 2 } else { (last + count - first) % count + 1 };
+        let wraps = first >= last;
+        if wraps { self.curves.rotate_left(first); }
+        let at = if wraps { 0 } else { first };
+
+        let mut points = Vec::new();
+        for bez in &self.curves[at..at + span] {
+            for s in 0..REFIT_SAMPLES {
+                points.push(bez.evaluate(s as f64 / REFIT_SAMPLES as f64));
+            }
+        }
+        points.push(self.curves[at + span - 1].evaluate(1.));
+
+        let start_tangent = self.curves[at].tangent_at(0.);
+        let end_tangent = self.curves[at + span - 1].tangent_at(1.);
+        let fitted = Bezier::fit(&points, start_tangent, end_tangent);
+
+        // fit keeps the first and last points, snapping makes sure they meet the neighbours exactly
+        let (start, end) = (points[0], *points.last().unwrap());
+        let fitted = move_end(&move_start(&fitted, start), end);
+
+        self.curves.splice(at..at + span, vec![fitted]);
+    }
+
+    // Rotates a closed contour so it starts with segment i, which is where to_contour's first point will be. The
+    // shape doesn't change. 0 and len are both the contour as it is.
+    pub fn set_start_segment(&mut self, i: usize) -> Result<(), EditError>
+    {
+        if self.curves.is_empty() { return Err(EditError::Empty); }
+        if i > self.curves.len() { return Err(EditError::OutOfRange(i)); }
+        if !self.is_closed() { return Err(EditError::OpenContour); }
+
+        let count = self.curves.len();
+        self.curves.rotate_left(i % count);
+        return Ok(());
+    }
+
+    // Starts a closed contour at whichever segment starts nearest to point, the first of them if there's a tie.
+    pub fn set_start_nearest(&mut self, point: Vector) -> Result<(), EditError>
+    {
+        let mut nearest: Option<(usize, f64)> = None;
+        for (i, bez) in self.curves.iter().enumerate() {
+            let distance = bez.to_control_points()[0].distance(point);
+            if nearest.map_or(true, |n| distance < n.1) { nearest = Some((i, distance)); }
+        }
+
+        return self.set_start_segment(nearest.ok_or(EditError::Empty)?.0);
+    }
+
+    // Splits the segment the global t lands in at that point, the shape doesn't change. Gives back the index of the
+    // segment starting at the new point, or of the one already starting there if t is on a joint.
+    pub fn insert_point_at(&mut self, t: f64) -> Result<usize, EditError>
+    {
+        let count = self.curves.len();
+        if count == 0 { return Err(EditError::Empty); }
+
+        let (index, local) = self.segment_at(t.max(0.).min(1.));
+
+        if local <= 1e-9 { return Ok(index); }
+        if local >= 1. - 1e-9 { return Ok((index + 1) % count); }
+
+        let (first, second) = self.curves[index].subdivide(local);
+        self.replace_segment(index, vec![first, second])?;
+        return Ok(index + 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: f64, y: f64) -> Vector
+    {
+        return Vector { x: x, y: y };
+    }
+
+    fn line(from: Vector, to: Vector) -> Bezier
+    {
+        return Bezier::from_control_points(from, from, to, to);
+    }
+
+    // the 100 unit square through these corners, closed, or open along its last side when it's left off
+    fn square(closed: bool) -> Piecewise<Bezier>
+    {
+        let corners = [point(0., 0.), point(100., 0.), point(100., 100.), point(0., 100.)];
+        let sides = if closed { 4 } else { 3 };
+        return Piecewise { curves: (0..sides).map(|i| line(corners[i], corners[(i + 1) % 4])).collect() };
+    }
+
+    fn ends(contour: &Piecewise<Bezier>) -> Vec<(Vector, Vector)>
+    {
+        return contour.curves.iter().map(|bez| { let p = bez.to_control_points(); (p[0], p[3]) }).collect();
+    }
+
+    // curves are kept as coefficients, so points only come back out of them to within rounding
+    fn joined_up(contour: &Piecewise<Bezier>) -> bool
+    {
+        return contour.curves.windows(2).all(|pair| pair[0].to_control_points()[3].is_near(pair[1].to_control_points()[0], 1e-9));
+    }
+
+    #[test]
+    fn remove_ends_of_an_open_contour()
+    {
+        let mut first = square(false);
+        first.remove_segment(0, WeldMode::MoveNext).unwrap();
+        assert_eq!(ends(&first), vec![(point(100., 0.), point(100., 100.)), (point(100., 100.), point(0., 100.))]);
+
+        let mut last = square(false);
+        last.remove_segment(2, WeldMode::MovePrev).unwrap();
+        assert_eq!(ends(&last), vec![(point(0., 0.), point(100., 0.)), (point(100., 0.), point(100., 100.))]);
+    }
+
+    // the first and last segments of a closed contour have neighbours round the back, and it stays closed
+    #[test]
+    fn remove_ends_of_a_closed_contour()
+    {
+        let mut first = square(true);
+        first.remove_segment(0, WeldMode::MoveNext).unwrap();
+        assert_eq!(first.curves.len(), 3);
+        assert_eq!(first.curves[0].to_control_points()[0], point(0., 0.));
+        assert!(joined_up(&first) && first.is_closed());
+
+        let mut last = square(true);
+        last.remove_segment(3, WeldMode::MovePrev).unwrap();
+        assert_eq!(last.curves.len(), 3);
+        assert_eq!(last.curves[2].to_control_points()[3], point(0., 0.));
+        assert!(joined_up(&last) && last.is_closed());
+
+        let mut bridged = square(true);
+        bridged.remove_segment(3, WeldMode::InsertLine).unwrap();
+        assert_eq!(ends(&bridged), ends(&square(true)));
+    }
+
+    #[test]
+    fn replace_snaps_small_gaps_and_moves_joints_for_big_ones()
+    {
+        let mut contour = square(true);
+        let nudged = line(point(0., 1e-4), point(50., 0.));
+        contour.replace_segment(0, vec![nudged, line(point(50., 0.), point(100., 0.))]).unwrap();
+        assert_eq!(contour.curves.len(), 5);
+        assert_eq!(contour.curves[0].to_control_points()[0], point(0., 0.));
+        assert!(joined_up(&contour) && contour.is_closed());
+
+        // the new curves have to be joined up among themselves
+        let broken = vec![line(point(0., 0.), point(50., 0.)), line(point(50., 1.), point(100., 0.))];
+        assert!(matches!(contour.replace_segment(0, broken), Err(EditError::Discontinuous(_))));
+
+        // the left side pulled out to x = -10 takes the top and bottom with it, round the back for the bottom
+        let mut moved = square(true);
+        moved.replace_segment(3, vec![line(point(-10., 100.), point(-10., 0.))]).unwrap();
+        assert_eq!(moved.curves[2].to_control_points()[3], point(-10., 100.));
+        assert_eq!(moved.curves[0].to_control_points()[0], point(-10., 0.));
+        assert!(joined_up(&moved) && moved.is_closed());
+
+        // an open contour's free end just goes where the new curve does
+        let mut open = square(false);
+        open.replace_segment(2, vec![line(point(100., 100.), point(50., 150.))]).unwrap();
+        assert_eq!(open.curves[2].to_control_points()[3], point(50., 150.));
+        assert!(joined_up(&open) && !open.is_closed());
+    }
+
+    #[test]
+    fn insert_snaps_small_gaps_only()
+    {
+        // inserting at len on a closed contour goes between the last segment and the first
+        let mut closed = square(true);
+        assert!(matches!(closed.insert_segments(4, vec![line(point(0., 0.), point(-50., 0.))]), Err(EditError::Discontinuous(_))));
+        closed.insert_segments(4, vec![line(point(0., 0.), point(-50., 0.)), line(point(-50., 0.), point(0., 0.))]).unwrap();
+        assert!(joined_up(&closed) && closed.is_closed());
+    }
+
+    // a contour built up a run at a time can come back round to where it started without being taken as closed
+    #[test]
+    fn append_then_close()
+    {
+        let mut contour: Piecewise<Bezier> = Piecewise::new(Vec::new());
+        contour.append_segments(vec![line(point(0., 0.), point(100., 0.))], 0.1).unwrap();
+        contour.append_segments(vec![line(point(100., 0.05), point(0., 0.))], 0.1).unwrap();
+        contour.append_segments(vec![line(point(0., 0.), point(0., 100.))], 0.1).unwrap();
+        assert!(joined_up(&contour));
+        assert!(matches!(contour.append_segments(vec![line(point(0., 101.), point(0., 0.))], 0.1), Err(EditError::Discontinuous(_))));
+        assert!(matches!(contour.close(0.1), Err(EditError::Discontinuous(_))));
+
+        contour.append_segments(vec![line(point(0., 100.), point(0.05, 0.))], 0.1).unwrap();
+        contour.close(0.1).unwrap();
+        assert!(contour.is_closed());
+    }
+
+    #[test]
+    fn insert_point_keeps_the_shape()
+    {
+        let mut contour = Piecewise { curves: vec![Bezier::from_control_points(point(0., 0.), point(0., 100.), point(100., 100.), point(100., 0.))] };
+        let original = contour.clone();
+
+        assert_eq!(contour.insert_point_at(0.25).unwrap(), 1);
+        assert_eq!(contour.curves.len(), 2);
+        assert!(joined_up(&contour));
+        for i in 0..=8 {
+            let t = i as f64 / 8.;
+            let (index, local) = if t < 0.25 { (0, t / 0.25) } else { (1, (t - 0.25) / 0.75) };
+            assert!(contour.curves[index].evaluate(local).distance(original.curves[0].evaluate(t)) < 1e-9);
+        }
+
+        // on a joint there's nothing to split
+        assert_eq!(contour.insert_point_at(0.5).unwrap(), 1);
+        assert_eq!(contour.curves.len(), 2);
+    }
+
+    #[test]
+    fn remove_leaving_a_gap()
+    {
+        let mut closed = square(true);
+        assert!(closed.remove_segment(1, WeldMode::Gap).unwrap().is_none());
+        assert_eq!(ends(&closed), vec![(point(100., 100.), point(0., 100.)), (point(0., 100.), point(0., 0.)), (point(0., 0.), point(100., 0.))]);
+
+        let mut open = square(false);
+        let rest = open.remove_segment(1, WeldMode::Gap).unwrap().unwrap();
+        assert_eq!(ends(&open), vec![(point(0., 0.), point(100., 0.))]);
+        assert_eq!(ends(&rest), vec![(point(100., 100.), point(0., 100.))]);
+
+        let mut end = square(false);
+        assert!(end.remove_segment(2, WeldMode::Gap).unwrap().is_none());
+        assert_eq!(end.curves.len(), 2);
+    }
+
+    // taking a quarter out of a circle and refitting gives a single curve across the three quarters from the one
+    // before to the one after, which can't be round but has to keep their outer ends and tangents
+    #[test]
+    fn remove_and_refit()
+    {
+        let mut circle = Piecewise::circle(point(0., 0.), 100.);
+        circle.remove_segment(0, WeldMode::Refit).unwrap();
+        assert_eq!(circle.curves.len(), 2);
+        assert!(joined_up(&circle) && circle.is_closed());
+
+        let fitted = circle.curves[0].to_control_points();
+        assert!(fitted[0].distance(point(0., -100.)) < 1e-9 && fitted[3].distance(point(-100., 0.)) < 1e-9);
+        assert!((fitted[1].x - fitted[0].x) > 0. && (fitted[1].y - fitted[0].y).abs() < 1e-9);
+        assert!((fitted[3].y - fitted[2].y) < 0. && (fitted[3].x - fitted[2].x).abs() < 1e-9);
+
+        let mut open = square(false);
+        open.remove_segment(0, WeldMode::Refit).unwrap();
+        assert_eq!(open.curves.len(), 2);
+        assert_eq!(open.curves[0].to_control_points()[0], point(0., 0.));
+        assert_eq!(open.curves[0].to_control_points()[3], point(100., 100.));
+    }
+}
@@ -0,0 +1,77 @@
+use super::*;
+
+// What a Piecewise does with a t outside 0 to 1. Plain evaluate hands it to the first or last curve, whose
+// polynomial carries on curving past its end, which is rarely what anyone wants but is cheap and never panics.
+// evaluate_with and derivative_with make the choice explicit.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtrapolationMode {
+    // t is held to 0 to 1, so anything past an end is that end
+    Clamp,
+    // carries on in a straight line along the tangent at the end, at the speed the curve left it
+    Extend,
+    // goes round again on a closed contour, an open contour has nowhere to go round to and clamps
+    Wrap,
+    // anything outside 0 to 1 is a bug in the caller
+    Panic,
+}
+
+// t brought back into 0 to 1 for the modes that do that, and how far past the end it was for Extend in the end
+// curve's own t, for a piecewise of n curves. Panics for Panic.
+fn resolve_t(t: f64, n: usize, mode: ExtrapolationMode, closed: bool) -> (f64, f64)
+{
+    if t >= 0. && t <= 1. { return (t, 0.); }
+
+    return match mode {
+        ExtrapolationMode::Clamp => (t.max(0.).min(1.), 0.),
+        ExtrapolationMode::Wrap if closed => (t.rem_euclid(1.), 0.),
+        ExtrapolationMode::Wrap => (t.max(0.).min(1.), 0.),
+        ExtrapolationMode::Extend if t < 0. => (0., t * n as f64),
+        ExtrapolationMode::Extend => (1., (t - 1.) * n as f64),
+        ExtrapolationMode::Panic => panic!("t = {} is outside of the piecewise!", t),
+    };
+}
+
+impl Piecewise<Bezier>
+{
+    // How fast the curve leaves the end at t, which is 0 or 1, as a derivative pointing backwards like the rest. A
+    // handle sitting on its end point has no speed there, so we go along the tangent at the end curve's chord length
+    // per unit of its t instead, which is how fast a line covers it.
+    fn end_derivative(&self, t: f64) -> Vector
+    {
+        let (i, local) = self.segment_at(t);
+        let bez = &self.curves[i];
+        let p = bez.to_control_points();
+
+        // the handle is the derivative at that end, as long as it isn't sitting on its point
+        let handle = if local < 0.5 { p[1] + -p[0] } else { p[3] + -p[2] };
+        let chord = p[0].distance(p[3]);
+        if handle.length() > 1e-9 * f64::max(chord, 1.) { return -handle * 3.; }
+
+        return -bez.tangent_at(local) * chord;
+    }
+
+    // evaluate with out of range ts handled by mode. The closed check for Wrap uses is_closed.
+    pub fn evaluate_with(&self, t: f64, mode: ExtrapolationMode) -> Vector
+    {
+        if self.curves.is_empty() { panic!("Can't evaluate an empty piecewise!") }
+
+        let (t, past) = resolve_t(t, self.curves.len(), mode, mode == ExtrapolationMode::Wrap && self.is_closed());
+        if past == 0. { return self.evaluate(t); }
+
+        // derivative points backwards, so going forward past the end takes it away
+        return self.evaluate(t) + -self.end_derivative(t) * past;
+    }
+
+    // derivative with out of range ts handled by mode, in the same curve's own t as derivative. On the straight line
+    // Extend carries on along it's the same everywhere.
+    pub fn derivative_with(&self, t: f64, mode: ExtrapolationMode) -> Vector
+    {
+        if self.curves.is_empty() { panic!("Can't find derivative for an empty piecewise!") }
+
+        let (t, past) = resolve_t(t, self.curves.len(), mode, mode == ExtrapolationMode::Wrap && self.is_closed());
+        if past == 0. { return self.derivative(t); }
+
+        return self.end_derivative(t);
+    }
+}
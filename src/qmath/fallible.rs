@@ -0,0 +1,102 @@
+use super::*;
+
+// Versions of the Piecewise methods that panic on empty input which hand back an error instead. Anything reading
+// glifs it didn't write itself, like an editor that can't have its UI thread taken down by a bad file, should go
+// through these. The panicking ones are still there for code that's already made sure its input is good.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeometryError {
+    // a Piecewise with no curves where we needed at least one
+    EmptyPiecewise,
+    // an outline with no contours at all
+    EmptyOutline,
+    // the contour at this index has too few points to make a curve, nothing at all or a lone point
+    DegenerateContour(usize),
+}
+
+impl std::fmt::Display for GeometryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        match self {
+            GeometryError::EmptyPiecewise => write!(f, "There are no curves to work with."),
+            GeometryError::EmptyOutline => write!(f, "The outline has no contours."),
+            GeometryError::DegenerateContour(i) => write!(f, "Contour {} has fewer than two points.", i),
+        }
+    }
+}
+
+impl<T: Evaluate> Piecewise<T> {
+    fn check_not_empty(&self) -> Result<(), GeometryError>
+    {
+        if self.curves.is_empty() { return Err(GeometryError::EmptyPiecewise); }
+        return Ok(());
+    }
+
+    pub fn try_evaluate(&self, t: f64) -> Result<Vector, GeometryError>
+    {
+        self.check_not_empty()?;
+        return Ok(self.evaluate(t));
+    }
+
+    pub fn try_derivative(&self, t: f64) -> Result<Vector, GeometryError>
+    {
+        self.check_not_empty()?;
+        return Ok(self.derivative(t));
+    }
+
+    pub fn try_bounds(&self) -> Result<Rect, GeometryError>
+    {
+        self.check_not_empty()?;
+        return Ok(self.bounds());
+    }
+
+    pub fn try_directional_bounds(&self, direction: Vector) -> Result<(f64, f64), GeometryError>
+    {
+        self.check_not_empty()?;
+        return Ok(self.directional_bounds(direction));
+    }
+}
+
+fn contour_from<U>(contour: &Contour<U>, index: usize) -> Result<Piecewise<Bezier>, GeometryError>
+{
+    if contour.len() < 2 { return Err(GeometryError::DegenerateContour(index)); }
+    return Ok(Piecewise::from_contour(contour));
+}
+
+impl Piecewise<Bezier>
+{
+    pub fn try_from_contour<U>(contour: &Contour<U>) -> Result<Self, GeometryError>
+    {
+        return contour_from(contour, 0);
+    }
+
+    pub fn try_to_contour(&self) -> Result<Contour<Option<PointData>>, GeometryError>
+    {
+        self.check_not_empty()?;
+        return Ok(self.to_contour());
+    }
+}
+
+impl Piecewise<Piecewise<Bezier>>
+{
+    pub fn try_from_outline<U>(outline: &Outline<U>) -> Result<Self, GeometryError>
+    {
+        if outline.is_empty() { return Err(GeometryError::EmptyOutline); }
+
+        let mut curves = Vec::new();
+        for (i, contour) in outline.iter().enumerate() {
+            curves.push(contour_from(contour, i)?);
+        }
+
+        return Ok(Piecewise { curves: curves });
+    }
+
+    pub fn try_to_outline(&self) -> Result<Outline<Option<PointData>>, GeometryError>
+    {
+        let mut output = Outline::new();
+        for contour in &self.curves {
+            output.push(contour.try_to_contour()?);
+        }
+
+        return Ok(output);
+    }
+}
@@ -0,0 +1,111 @@
+use super::*;
+
+// Rounding off a contour's corners with circular arcs. Each side of a corner is trimmed back by however far along it
+// the circle touches, radius * tan(turn / 2) on straight sides, and a cubic close to the arc between the two trims
+// goes in the gap. On curved sides the arc is sized to the trim points rather than the radius, so it still meets
+// both sides tangent.
+//
+// Corners closer together than their trims need share out the side between them, both trims shrinking by the same
+// factor until they fit, which is the biggest radius that does. A side used up completely is dropped and the arcs
+// either side of it meet.
+
+// how closely the sides are measured for trimming, in font units
+const LENGTH_TOLERANCE: f64 = 1e-6;
+// turns within this of a full about-face have no circle between their sides, they're left sharp
+const HAIRPIN_MARGIN: f64 = 1e-3;
+// sides trimmed shorter than this are gone
+const VANISHED_LENGTH: f64 = 1e-9;
+
+// A cubic close to the circular arc from start heading along start_tangent to end heading along end_tangent, or the
+// straight line between them if they don't turn.
+fn fillet(start: Vector, start_tangent: Vector, end: Vector, end_tangent: Vector) -> Bezier
+{
+    let turn = start_tangent.angle_to(end_tangent).abs();
+    let chord = start.distance(end);
+    if !(turn > 0.) || !(chord > 0.) { return Bezier::from_control_points(start, start, end, end); }
+
+    let radius = chord / (2. * (turn / 2.).sin());
+    let handle = 4. / 3. * (turn / 4.).tan() * radius;
+
+    return Bezier::from_control_points(start, start + start_tangent * handle, end + -end_tangent * handle, end);
+}
+
+impl Piecewise<Bezier>
+{
+    // The contour with every corner turning by more than angle_threshold radians rounded off to radius, or less
+    // where there isn't room. An open contour's ends aren't corners and stay where they are.
+    pub fn round_corners(&self, radius: f64, angle_threshold: f64) -> Self
+    {
+        if !(radius > 0.) || self.find_corners(angle_threshold).is_empty() { return self.clone(); }
+
+        let closed = self.is_closed();
+        let runs = self.split_at_corners(angle_threshold);
+        let lengths: Vec<f64> = runs.iter().map(|run| run.arclen(LENGTH_TOLERANCE)).collect();
+        let n = runs.len();
+
+        // joint k is where run k meets the one after it, an open contour's last run meets nothing
+        let joints = if closed { n } else { n - 1 };
+        let mut trims: Vec<f64> = (0..joints).map(|k| {
+            let turn = runs[k].tangent_at(1.).angle_to(runs[(k + 1) % n].tangent_at(0.)).abs();
+            if !(turn < std::f64::consts::PI - HAIRPIN_MARGIN) { return 0.; }
+            return radius * (turn / 2.).tan();
+        }).collect();
+
+        let start_joint = |r: usize| if closed { Some((r + n - 1) % n) } else if r > 0 { Some(r - 1) } else { None };
+        let end_joint = |r: usize| if r < joints { Some(r) } else { None };
+        let trim_at = |trims: &[f64], joint: Option<usize>| joint.map_or(0., |k| trims[k]);
+
+        // how much each run's trims have to shrink to fit on it, then each joint takes the worse of its two runs
+        let fits: Vec<f64> = (0..n).map(|r| {
+            let wanted = trim_at(&trims, start_joint(r)) + trim_at(&trims, end_joint(r));
+            return if wanted > lengths[r] { lengths[r] / wanted } else { 1. };
+        }).collect();
+        for k in 0..joints {
+            trims[k] = trims[k] * f64::min(fits[k], fits[(k + 1) % n]);
+        }
+
+        let trimmed: Vec<Piecewise<Bezier>> = (0..n)
+            .map(|r| runs[r].trim(trim_at(&trims, start_joint(r)), trim_at(&trims, end_joint(r))))
+            .collect();
+
+        let mut output = Vec::new();
+        for r in 0..n {
+            let vanished = lengths[r] - trim_at(&trims, start_joint(r)) - trim_at(&trims, end_joint(r)) <= VANISHED_LENGTH;
+            if !vanished {
+                output.extend(trimmed[r].curves.iter().cloned());
+            }
+
+            let k = match end_joint(r) {
+                Some(k) if trims[k] > 0. => k,
+                _ => continue,
+            };
+            let next = (r + 1) % n;
+
+            // the arc's ends come from the trimmed runs so it meets them exactly, a run that's gone is left as
+            // nothing but its start for the arcs either side to meet at
+            let start = if vanished {
+                trimmed[r].curves[0].to_control_points()[0]
+            } else {
+                trimmed[r].curves[trimmed[r].curves.len() - 1].to_control_points()[3]
+            };
+            let end = trimmed[next].curves[0].to_control_points()[0];
+            let incoming = PathMeasure::new(&runs[r]);
+            let outgoing = PathMeasure::new(&runs[next]);
+            let (_, start_tangent) = incoming.pos_tan_at(incoming.length() - trims[k]);
+            let (_, end_tangent) = outgoing.pos_tan_at(trim_at(&trims, start_joint(next)));
+
+            output.push(fillet(start, start_tangent, end, end_tangent));
+        }
+
+        return Piecewise { curves: output };
+    }
+}
+
+impl Piecewise<Piecewise<Bezier>>
+{
+    // round_corners for every contour.
+    pub fn round_corners(&self, radius: f64, angle_threshold: f64) -> Self
+    {
+        return Piecewise { curves: self.curves.iter().map(|contour| contour.round_corners(radius, angle_threshold)).collect() };
+    }
+}
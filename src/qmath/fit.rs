@@ -0,0 +1,214 @@
+use super::*;
+
+// How fit_endpoints gets a path's ends onto their new positions.
+// Rigid - rotate, translate and uniformly scale so the old chord lands on the new one. Shapes are preserved exactly.
+// Affine - like rigid but only scaled along the chord, the path keeps its height off the chord.
+// Bend - rigid, then the interior is pushed out along the new chord's normal by a smooth bump that's zero at the
+//        ends, by however much brings the path back to the length it had. Only the interior moves off the rigid fit.
+//        A path squeezed between closer ends bows out further, one stretched between further ends than its length
+//        allows just gets the rigid fit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FitMode {
+    Rigid,
+    Affine,
+    Bend,
+}
+
+// how close Bend gets to the length the path had
+const BEND_LENGTH_TOLERANCE: f64 = 1e-6;
+const BEND_ITERATIONS: usize = 100;
+
+#[derive(Debug, PartialEq)]
+pub enum FitError {
+    // there's no path to fit
+    Empty,
+    // only open paths have endpoints to move
+    ClosedContour,
+    // the path starts and ends at the same point so there's no chord to map
+    DegenerateChord,
+}
+
+impl std::fmt::Display for FitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        match self {
+            FitError::Empty => write!(f, "There's no path to fit."),
+            FitError::ClosedContour => write!(f, "Can't fit the endpoints of a closed contour."),
+            FitError::DegenerateChord => write!(f, "The path starts and ends at the same point."),
+        }
+    }
+}
+
+impl Piecewise<Bezier>
+{
+    pub fn fit_endpoints(&self, new_start: Vector, new_end: Vector, mode: FitMode) -> Result<Piecewise<Bezier>, FitError>
+    {
+        if self.curves.is_empty() {
+            return Err(FitError::Empty);
+        }
+        if self.is_closed() {
+            return Err(FitError::ClosedContour);
+        }
+
+        let old_start = self.curves.first().unwrap().to_control_points()[0];
+        let old_end = self.curves.last().unwrap().to_control_points()[3];
+
+        let old_chord = old_end + -old_start;
+        let new_chord = new_end + -new_start;
+        let old_len = old_chord.magnitude();
+        let new_len = new_chord.magnitude();
+
+        if old_len < 1e-9 || new_len < 1e-9 {
+            return Err(FitError::DegenerateChord);
+        }
+
+        // we describe every point by how far along and how far off the old chord it is, in chord lengths for
+        // along and in units for off
+        let old_dir = old_chord * (1. / old_len);
+        let new_dir = new_chord * (1. / new_len);
+        let old_normal = old_dir.perpendicular();
+        let new_normal = new_dir.perpendicular();
+
+        // how far along the old chord and where the rigid fit puts it
+        let place = |p: &Vector, off_scale: f64| {
+            let rel = *p + -old_start;
+            let along = rel.dot(old_dir) / old_len;
+            let off = rel.dot(old_normal);
+            return (along, new_start + new_dir * (along * new_len) + new_normal * (off * off_scale));
+        };
+
+        let curves: Vec<Bezier> = match mode {
+            FitMode::Rigid => self.fit_with(&|p| place(p, new_len / old_len).1),
+            FitMode::Affine => self.fit_with(&|p| place(p, 1.).1),
+            FitMode::Bend => {
+                // sin squared is flat at both ends, so the interior eases away from where the rigid fit put the ends
+                let bent = |height: f64| self.fit_with(&|p| {
+                    let (along, rigid) = place(p, new_len / old_len);
+                    let bump = if along > 0. && along < 1. { (std::f64::consts::PI * along).sin().powi(2) } else { 0. };
+                    return rigid + new_normal * (height * bump);
+                });
+                let length_of = |curves: &Vec<Bezier>| Piecewise { curves: curves.clone() }.arclen(BEND_LENGTH_TOLERANCE);
+
+                // bowing out the way the path already does, or left for a straight one
+                let old_length = self.arclen(BEND_LENGTH_TOLERANCE);
+                let bulge: f64 = self.curves.iter().flat_map(|bez| bez.to_control_points().to_vec()).map(|p| (p + -old_start).dot(old_normal)).sum();
+                let side = if bulge < 0. { -1. } else { 1. };
+
+                // the length only grows as the bump does, and a bump higher than the path is long plus however far
+                // the rigid fit already is from the chord takes it past the length it had
+                let rigid = bent(0.);
+                if length_of(&rigid) >= old_length {
+                    rigid
+                } else {
+                    let reach = self.curves.iter().flat_map(|bez| bez.to_control_points().to_vec())
+                        .map(|p| (p + -old_start).dot(old_normal).abs() * new_len / old_len)
+                        .fold(0., f64::max);
+                    let (mut low, mut high) = (0., old_length + reach);
+                    for _ in 0..BEND_ITERATIONS {
+                        let height = (low + high) / 2.;
+                        if length_of(&bent(side * height)) < old_length { low = height; } else { high = height; }
+                        if high - low < BEND_LENGTH_TOLERANCE { break; }
+                    }
+                    bent(side * (low + high) / 2.)
+                }
+            }
+        };
+
+        return Ok(Piecewise { curves: Self::land_ends(curves, new_start, new_end) });
+    }
+
+    fn fit_with(&self, transform: &dyn Fn(&Vector) -> Vector) -> Vec<Bezier>
+    {
+        return self.curves.iter().map(|bez| bez.apply_transform(transform)).collect();
+    }
+
+    // Put the ends exactly where they were asked to go rather than wherever the float math landed them.
+    fn land_ends(mut curves: Vec<Bezier>, new_start: Vector, new_end: Vector) -> Vec<Bezier>
+    {
+        let first = curves[0].to_control_points();
+        curves[0] = Bezier::from_control_points(new_start, first[1], first[2], first[3]);
+        let last_idx = curves.len() - 1;
+        let last = curves[last_idx].to_control_points();
+        curves[last_idx] = Bezier::from_control_points(last[0], last[1], last[2], new_end);
+
+        return curves;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(x: f64, y: f64) -> Vector
+    {
+        return Vector { x: x, y: y };
+    }
+
+    // an s with a corner in the middle of it
+    fn path() -> Piecewise<Bezier>
+    {
+        return Piecewise::new(vec![
+            Bezier::from_control_points(v(0., 0.), v(20., 40.), v(60., 40.), v(80., 10.)),
+            Bezier::from_control_points(v(80., 10.), v(100., -30.), v(140., -20.), v(150., 0.)),
+        ]);
+    }
+
+    fn tangents(path: &Piecewise<Bezier>) -> Vec<Vector>
+    {
+        return path.curves.iter().flat_map(|bez| (0..=8).map(move |i| bez.tangent_at(i as f64 / 8.))).collect();
+    }
+
+    #[test]
+    fn endpoints_land_exactly()
+    {
+        let (start, end) = (v(13.1, -7.3), v(-41.7, 203.9));
+        for mode in [FitMode::Rigid, FitMode::Affine, FitMode::Bend].iter() {
+            let fitted = path().fit_endpoints(start, end, *mode).unwrap();
+            assert_eq!(fitted.curves[0].to_control_points()[0], start, "{:?}", mode);
+            // the end comes back out of the coefficients, which is as exact as any curve's end gets
+            assert!(fitted.curves[1].to_control_points()[3].is_near(end, 1e-12), "{:?}", mode);
+            assert!(fitted.curves[0].to_control_points()[3].is_near(fitted.curves[1].to_control_points()[0], 1e-9), "{:?}", mode);
+        }
+    }
+
+    // A similarity turns every direction by the same angle, so the angles between tangents, the corner included,
+    // don't change.
+    #[test]
+    fn rigid_preserves_angles()
+    {
+        let before = tangents(&path());
+        let after = tangents(&path().fit_endpoints(v(10., 10.), v(10., 90.), FitMode::Rigid).unwrap());
+
+        let angle = |a: Vector, b: Vector| a.cross(b).atan2(a.dot(b));
+        for i in 1..before.len() {
+            let (was, is) = (angle(before[0], before[i]), angle(after[0], after[i]));
+            assert!((was - is).abs() < 1e-9, "tangent {} was {} from the first and is {}", i, was, is);
+        }
+    }
+
+    // Squeezed between closer ends the path bows out to keep its length, more in the middle than near the ends.
+    #[test]
+    fn bend_keeps_length()
+    {
+        let (start, end) = (v(0., 0.), v(0., 100.));
+        let rigid = path().fit_endpoints(start, end, FitMode::Rigid).unwrap();
+        let bent = path().fit_endpoints(start, end, FitMode::Bend).unwrap();
+
+        assert!((bent.arclen(1e-6) - path().arclen(1e-6)).abs() < 1e-3, "{} rather than {}", bent.arclen(1e-6), path().arclen(1e-6));
+        let moved = |t: f64| bent.evaluate(t).distance(rigid.evaluate(t));
+        assert!(moved(0.5) > 1. && moved(0.1) < moved(0.3) && moved(0.9) < moved(0.7), "{} {} {}", moved(0.1), moved(0.5), moved(0.9));
+
+        // stretched past its length there's nothing to give back, it's the rigid fit
+        let far = v(0., 1000.);
+        assert!(path().fit_endpoints(start, far, FitMode::Bend).unwrap().approx_eq(&path().fit_endpoints(start, far, FitMode::Rigid).unwrap(), 1e-9));
+    }
+
+    #[test]
+    fn errors()
+    {
+        let empty: Piecewise<Bezier> = Piecewise::new(Vec::new());
+        assert_eq!(empty.fit_endpoints(v(0., 0.), v(1., 0.), FitMode::Rigid).err(), Some(FitError::Empty));
+        assert_eq!(Piecewise::circle(v(0., 0.), 10.).fit_endpoints(v(0., 0.), v(1., 0.), FitMode::Rigid).err(), Some(FitError::ClosedContour));
+        assert_eq!(path().fit_endpoints(v(5., 5.), v(5., 5.), FitMode::Bend).err(), Some(FitError::DegenerateChord));
+    }
+}
@@ -0,0 +1,153 @@
+use super::*;
+
+// Turning curves into polylines that stay within a tolerance of them. We halve each curve until both handles are
+// within tolerance of the chord. The curve never leaves the hull of its control points, so then it can't be any
+// further than that from the chord either and the error bound is a guarantee rather than an estimate.
+
+// Tolerances are never finer than this much of the curve's size, which is as close as we can put points in floating
+// point anyway, and keeps a tolerance of zero from halving forever. Each halving takes the handles about four times
+// closer to the chord so that's at most fifteen deep, and past this many we take the chord whatever it looks like.
+const MIN_RELATIVE_TOLERANCE: f64 = 1e-9;
+const MAX_FLATTEN_DEPTH: usize = 24;
+
+// how far p is from the segment between a and b
+fn segment_distance(p: Vector, a: Vector, b: Vector) -> f64
+{
+    let ab = b + -a;
+    let length_squared = ab.x * ab.x + ab.y * ab.y;
+    if length_squared <= 0. { return p.distance(a); }
+
+    let along = ((p.x - a.x) * ab.x + (p.y - a.y) * ab.y) / length_squared;
+    return p.distance(a.lerp(b, f64::min(f64::max(along, 0.), 1.)));
+}
+
+// Adds the points after the start of bez, which covers t0 to t1 of the curve we started with.
+fn flatten_into(bez: &Bezier, t0: f64, t1: f64, tolerance: f64, depth: usize, output: &mut Vec<(f64, Vector)>)
+{
+    if bez.is_flat(tolerance) || depth >= MAX_FLATTEN_DEPTH {
+        output.push((t1, bez.to_control_points()[3]));
+        return;
+    }
+
+    let mid = (t0 + t1) / 2.;
+    let (first, second) = bez.subdivide(0.5);
+    flatten_into(&first, t0, mid, tolerance, depth + 1, output);
+    flatten_into(&second, mid, t1, tolerance, depth + 1, output);
+}
+
+impl Bezier {
+    // Whether both handles are within tolerance of the chord, which puts the whole curve within tolerance of it.
+    pub fn is_flat(&self, tolerance: f64) -> bool
+    {
+        let p = self.to_control_points();
+        return f64::max(segment_distance(p[1], p[0], p[3]), segment_distance(p[2], p[0], p[3])) <= tolerance;
+    }
+
+    // Points along the curve, each with its t, such that the polyline through them is nowhere further than
+    // tolerance from the curve. Straight curves give back just their two ends.
+    pub fn flatten_with_t(&self, tolerance: f64) -> Vec<(f64, Vector)>
+    {
+        let hull = Rect::AABB_from_points(self.to_control_points_vec());
+        let size = f64::max(hull.right - hull.left, hull.top - hull.bottom);
+        let tolerance = f64::max(tolerance, size * MIN_RELATIVE_TOLERANCE);
+
+        let mut output = vec![(0., self.to_control_points()[0])];
+        flatten_into(self, 0., 1., tolerance, 0, &mut output);
+        return output;
+    }
+
+    pub fn flatten(&self, tolerance: f64) -> Vec<Vector>
+    {
+        return self.flatten_with_t(tolerance).into_iter().map(|(_, p)| p).collect();
+    }
+}
+
+impl Piecewise<Bezier>
+{
+    // The same for the whole contour with global ts. Where one curve ends and the next starts there's only one
+    // point, a closed contour's polyline ends back on its first point.
+    pub fn flatten_with_t(&self, tolerance: f64) -> Vec<(f64, Vector)>
+    {
+        let mut output = Vec::new();
+        for (i, bez) in self.curves.iter().enumerate() {
+            let points = bez.flatten_with_t(tolerance);
+            let skip = if i > 0 { 1 } else { 0 };
+            output.extend(points.into_iter().skip(skip).map(|(t, p)| (self.global_t(i, t), p)));
+        }
+
+        return output;
+    }
+
+    pub fn flatten(&self, tolerance: f64) -> Vec<Vector>
+    {
+        return self.flatten_with_t(tolerance).into_iter().map(|(_, p)| p).collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::bezier_points_strategy;
+    use proptest::prelude::*;
+
+    fn v(x: f64, y: f64) -> Vector
+    {
+        return Vector { x: x, y: y };
+    }
+
+    // how far p is from the polyline
+    fn polyline_distance(p: Vector, polyline: &[Vector]) -> f64
+    {
+        return polyline.windows(2).fold(f64::INFINITY, |m, w| f64::min(m, segment_distance(p, w[0], w[1])));
+    }
+
+    proptest! {
+        #[test]
+        fn within_tolerance(points in bezier_points_strategy())
+        {
+            // the strategy's curves go from tiny to enormous, so the tolerance goes with them
+            let bez = Bezier::from_control_points(points[0], points[1], points[2], points[3]);
+            let size = points.iter().fold(0., |m, p| f64::max(m, f64::max(p.x.abs(), p.y.abs())));
+            let tolerance = f64::max(size * 1e-4, 0.01);
+            let flat = bez.flatten_with_t(tolerance);
+            let polyline: Vec<Vector> = flat.iter().map(|(_, p)| *p).collect();
+
+            // every point is on the curve at its t, in order
+            prop_assert!(flat.windows(2).all(|w| w[0].0 < w[1].0));
+            for (t, p) in &flat {
+                prop_assert!(bez.evaluate(*t).is_near(*p, size * 1e-12 + 1e-9));
+            }
+
+            // and the curve is never further than the tolerance from the polyline
+            for k in 0..=200 {
+                let p = bez.evaluate(k as f64 / 200.);
+                let d = polyline_distance(p, &polyline);
+                prop_assert!(d <= tolerance * (1. + 1e-9), "{:?} is {} away", p, d);
+            }
+        }
+    }
+
+    #[test]
+    fn lines_and_contours()
+    {
+        let line = Bezier::from_control_points(v(0., 0.), v(0., 0.), v(100., 50.), v(100., 50.));
+        assert_eq!(line.flatten(0.01), vec![v(0., 0.), v(100., 50.)]);
+        let thirds = Bezier::from_control_points(v(0., 0.), v(100. / 3., 50. / 3.), v(200. / 3., 100. / 3.), v(100., 50.));
+        assert_eq!(thirds.flatten(0.01).len(), 2);
+
+        let circle = Piecewise::circle(v(0., 0.), 100.);
+        let coarse = circle.flatten_with_t(1.);
+        let fine = circle.flatten_with_t(0.01);
+        assert!(fine.len() > 4 * coarse.len());
+
+        // shared joints appear once, and a closed contour comes back round to where it started
+        let per_curve: usize = circle.curves.iter().map(|bez| bez.flatten(0.01).len()).sum();
+        assert_eq!(fine.len(), per_curve - (circle.curves.len() - 1));
+        assert!(fine.windows(2).all(|w| w[0].0 < w[1].0 && !w[0].1.is_near(w[1].1, 1e-9)));
+        assert_eq!((fine[0].0, fine.last().unwrap().0), (0., 1.));
+        assert!(fine[0].1.is_near(fine.last().unwrap().1, 1e-9));
+        for (t, p) in &fine {
+            assert!(circle.evaluate(*t).is_near(*p, 1e-9));
+        }
+    }
+}
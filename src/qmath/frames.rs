@@ -0,0 +1,77 @@
+use super::*;
+
+// Evenly spaced spots along a contour with which way it's going at each, for putting things along it: components,
+// anchors, the copies pattern along path lays down. Everything comes out of PathMeasure so the tangents are sound
+// at the ends of lines where the derivative vanishes.
+
+// One spot along the contour. normal is the left of tangent in font space, both are unit length.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frame {
+    pub point: Vector,
+    pub tangent: Vector,
+    pub normal: Vector,
+    // the piecewise's global t
+    pub t: f64,
+    // how far along the contour
+    pub distance: f64,
+}
+
+fn frame_at(measure: &PathMeasure, distance: f64) -> Frame
+{
+    let (point, tangent) = measure.pos_tan_at(distance);
+    return Frame {
+        point: point,
+        tangent: tangent,
+        normal: Orientation::YUp.left_normal(tangent),
+        t: measure.t_at(distance),
+        distance: distance,
+    };
+}
+
+impl Piecewise<Bezier>
+{
+    // A frame every spacing along the contour from its start, plus one on its end, so the last gap is usually
+    // shorter than spacing. A spacing that isn't positive gives just the ends, a contour with no length just its
+    // start and an empty one nothing.
+    pub fn frames(&self, spacing: f64) -> Vec<Frame>
+    {
+        if self.curves.is_empty() { return Vec::new(); }
+
+        let measure = PathMeasure::new(self);
+        let length = measure.length();
+
+        let mut output = vec![frame_at(&measure, 0.)];
+        if !(length > 0.) { return output; }
+
+        if spacing > 0. && spacing.is_finite() {
+            let mut k = 1;
+            // anything closer to the end than a thousandth of spacing would just be the end again
+            while k as f64 * spacing < length - spacing * 1e-3 {
+                output.push(frame_at(&measure, k as f64 * spacing));
+                k = k + 1;
+            }
+        }
+
+        output.push(frame_at(&measure, length));
+        return output;
+    }
+
+    // Like frames, but spacing is stretched or squeezed to the nearest spacing that divides the contour into equal
+    // gaps, so there's no short gap at the end. On a closed contour the gaps go all the way round through the seam
+    // and the frame that would land back on the start is left off.
+    pub fn frames_wrapped(&self, spacing: f64) -> Vec<Frame>
+    {
+        if self.curves.is_empty() { return Vec::new(); }
+
+        let measure = PathMeasure::new(self);
+        let length = measure.length();
+        let closed = measure.is_closed();
+        if !(length > 0.) || !(spacing > 0.) || !spacing.is_finite() { return self.frames(spacing); }
+
+        let gaps = (length / spacing).round().max(1.) as usize;
+        let even = length / gaps as f64;
+        let count = if closed { gaps } else { gaps + 1 };
+
+        return (0..count).map(|k| frame_at(&measure, if k == gaps { length } else { k as f64 * even })).collect();
+    }
+}
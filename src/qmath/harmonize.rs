@@ -0,0 +1,113 @@
+use super::*;
+
+// Tidying up smooth joints, the way designers do it by hand after offset and fit leaves them slightly off.
+// smooth_tangents straightens out joints that are only a little kinked. harmonize then slides each smooth joint
+// along its handles to where the curvature either side of it matches. Corners are left alone by both.
+//
+// Harmonizing: with the handle lines either side of the joint meeting at d, the curvature on each side is set by how
+// the outer handle's length compares to the distance from the inner handle to d. Putting the joint at the
+// geometric mean of those two ratios along the line between the inner handles makes them agree.
+
+// The joints as (curve arriving, curve leaving), including the one at the start of a closed contour.
+fn joints(contour: &Piecewise<Bezier>) -> Vec<(usize, usize)>
+{
+    let n = contour.curves.len();
+    let mut output: Vec<(usize, usize)> = (1..n).map(|i| (i - 1, i)).collect();
+    if n > 1 && contour.is_closed() { output.push((n - 1, 0)); }
+
+    return output;
+}
+
+// where the lines through a0 and a1 and through b0 and b1 cross, None if they're parallel
+fn line_crossing(a0: Vector, a1: Vector, b0: Vector, b1: Vector) -> Option<Vector>
+{
+    let (da, db) = (a1 + -a0, b1 + -b0);
+    let denominator = da.cross(db);
+    if denominator.abs() <= VECTOR_EPSILON * da.length() * db.length() { return None; }
+
+    return Some(a0 + da * ((b0 + -a0).cross(db) / denominator));
+}
+
+fn has_handle_at_end(bez: &Bezier) -> bool
+{
+    let p = bez.to_control_points();
+    return p[2] != p[3];
+}
+
+fn has_handle_at_start(bez: &Bezier) -> bool
+{
+    let p = bez.to_control_points();
+    return p[1] != p[0];
+}
+
+impl Piecewise<Bezier>
+{
+    // Moves every smooth joint between two curves along its handles so the curvature is continuous across it.
+    // Smooth means turning no more than CORNER_ANGLE, run smooth_tangents first for joints that are only close. A
+    // joint with a line or a handle sitting on its point on either side stays where it is, and so does an
+    // inflection, where the two sides bend opposite ways and only straightening them out would match them up.
+    pub fn harmonize(&self) -> Self
+    {
+        let mut curves = self.curves.clone();
+
+        for (a, b) in joints(self) {
+            let (incoming, outgoing) = (&curves[a], &curves[b]);
+            if !has_handle_at_end(incoming) || !has_handle_at_start(outgoing) { continue; }
+            if incoming.tangent_at(1.).angle_to(outgoing.tangent_at(0.)).abs() > CORNER_ANGLE { continue; }
+
+            let (pa, pb) = (incoming.to_control_points(), outgoing.to_control_points());
+            let (a0, a1, joint, b0, b1) = (pa[1], pa[2], pa[3], pb[1], pb[2]);
+
+            // both outer handles have to be on the same side of the tangent
+            let tangent = b0 + -a1;
+            if tangent.cross(a0 + -joint) * tangent.cross(b1 + -joint) <= 0. { continue; }
+
+            let d = match line_crossing(a0, a1, b0, b1) {
+                Some(d) => d,
+                None => continue,
+            };
+            if a1.distance(d) == 0. || b0.distance(b1) == 0. { continue; }
+
+            let ratio = ((a0.distance(a1) / a1.distance(d)) * (d.distance(b0) / b0.distance(b1))).sqrt();
+            let moved = a1.lerp(b0, ratio / (ratio + 1.));
+
+            curves[a] = Bezier::from_control_points(pa[0], a0, a1, moved);
+            curves[b] = Bezier::from_control_points(moved, b0, b1, pb[3]);
+        }
+
+        return Piecewise { curves: curves };
+    }
+
+    // Lines the handles up either side of every joint that turns by no more than angle_threshold radians, keeping
+    // their lengths. Where one side is a line the handle on the other turns to follow it, two lines meeting at an
+    // angle are left as they are. Joints turning by more are corners and aren't touched.
+    pub fn smooth_tangents(&self, angle_threshold: f64) -> Self
+    {
+        let mut curves = self.curves.clone();
+
+        for (a, b) in joints(self) {
+            let (incoming, outgoing) = (&curves[a], &curves[b]);
+            let (u, v) = (incoming.tangent_at(1.), outgoing.tangent_at(0.));
+            let turn = u.angle_to(v).abs();
+            if turn == 0. || turn > angle_threshold { continue; }
+
+            let direction = match (has_handle_at_end(incoming), has_handle_at_start(outgoing)) {
+                (true, true) => (u + v).normalize_or_zero(),
+                (false, true) => u,
+                (true, false) => v,
+                (false, false) => continue,
+            };
+            if direction.length_squared() == 0. { continue; }
+
+            let (pa, pb) = (incoming.to_control_points(), outgoing.to_control_points());
+            let joint = pa[3];
+            let a1 = joint + -direction * joint.distance(pa[2]);
+            let b0 = joint + direction * joint.distance(pb[1]);
+
+            curves[a] = Bezier::from_control_points(pa[0], pa[1], a1, joint);
+            curves[b] = Bezier::from_control_points(joint, b0, pb[2], pb[3]);
+        }
+
+        return Piecewise { curves: curves };
+    }
+}
@@ -0,0 +1,57 @@
+use super::*;
+use std::cmp::Ordering;
+
+// Convex hulls of contours. The hull of the control points always contains the curves, which makes it a cheap test
+// for whether two contours could possibly touch before running the real intersector on them. The hull of the curves
+// themselves is tighter and is what spacing heuristics want.
+
+// Andrew's monotone chain. The hull comes back counter-clockwise in y-up space starting from the leftmost point,
+// the lowest of them if there's a tie, with no point on it twice and none lying along a side. Points that are all the same give back one point,
+// points all on one line give back the two ends of it.
+fn convex_hull(points: &[Vector]) -> Vec<Vector>
+{
+    let mut sorted: Vec<Vector> = points.to_vec();
+    sorted.sort_by(|a, b| a.x.partial_cmp(&b.x).unwrap_or(Ordering::Equal).then(a.y.partial_cmp(&b.y).unwrap_or(Ordering::Equal)));
+    sorted.dedup();
+    if sorted.len() < 3 { return sorted; }
+
+    // whether o -> a -> b turns counter-clockwise
+    let turns_left = |o: Vector, a: Vector, b: Vector| (a + -o).cross(b + -o) > 0.;
+
+    let mut lower: Vec<Vector> = Vec::new();
+    for p in &sorted {
+        while lower.len() >= 2 && !turns_left(lower[lower.len() - 2], lower[lower.len() - 1], *p) { lower.pop(); }
+        lower.push(*p);
+    }
+
+    let mut upper: Vec<Vector> = Vec::new();
+    for p in sorted.iter().rev() {
+        while upper.len() >= 2 && !turns_left(upper[upper.len() - 2], upper[upper.len() - 1], *p) { upper.pop(); }
+        upper.push(*p);
+    }
+
+    // each chain ends where the other starts
+    lower.pop();
+    upper.pop();
+    lower.extend(upper);
+
+    return lower;
+}
+
+impl Piecewise<Bezier>
+{
+    // The convex hull of every control point in the contour, see convex_hull for what it looks like. The contour
+    // never leaves it. Nothing for an empty contour.
+    pub fn control_hull(&self) -> Vec<Vector>
+    {
+        let points: Vec<Vector> = self.curves.iter().flat_map(|bez| bez.to_control_points().to_vec()).collect();
+        return convex_hull(&points);
+    }
+
+    // The convex hull of the contour flattened to within tolerance. Its corners are all on the contour, so it's
+    // inside the true hull and no more than tolerance short of it anywhere.
+    pub fn geometry_hull(&self, tolerance: f64) -> Vec<Vector>
+    {
+        return convex_hull(&self.flatten(tolerance));
+    }
+}
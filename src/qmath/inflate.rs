@@ -0,0 +1,132 @@
+use super::*;
+
+// Making a filled outline uniformly bolder or lighter. Every contour is offset away from the fill, which is to the
+// right of an outer going counter-clockwise and to the right of a hole going clockwise too, so once the winding's
+// fixed outers grow and holes shrink with the same offset. Corners pointing out of the fill get rounded over the
+// way a circle rolled round the outside would, corners pointing in get overlapping offsets that remove_overlap
+// cleans up.
+//
+// A counter smaller than the offset turns inside out: every point of its offset ends up nearer the counter's edge
+// than the offset distance, because no point inside it is that far from the edge. We drop those rather than let
+// them fill in a blob where the counter was. Counters that only partly close up still have some of their offset
+// clear of the edge and come through with the closed part looped the wrong way, which nonzero fills in, and that's
+// what we want.
+//
+// Shrinking is growing the space around the outline. Shrinking directly would leave the parts of a stem thinner
+// than the offset looped the wrong way where nonzero would fill them back in, so we put a rect round the whole
+// outline, flip everything inside it, grow that and flip it back.
+
+// how close the offset curves are fit by default, the same as a stroke's default tolerance
+const INFLATE_TOLERANCE: f64 = 0.01;
+
+// The contour offset distance to its right, which is away from the fill for a contour wound the right way.
+fn grow_contour(contour: &Piecewise<Bezier>, distance: f64, tolerance: f64) -> Piecewise<Bezier>
+{
+    let runs = contour.split_at_corners(CORNER_ANGLE);
+    if runs.len() == 1 && runs[0].is_closed() { return runs[0].offset(-distance, tolerance); }
+
+    let offsets: Vec<Vec<Bezier>> = runs.iter().map(|run| run.offset(-distance, tolerance).curves).collect();
+
+    let mut curves = Vec::new();
+    for k in 0..runs.len() {
+        if offsets[k].is_empty() { continue; }
+        curves.extend(offsets[k].iter().cloned());
+
+        let next = (k + 1) % runs.len();
+        if offsets[next].is_empty() { continue; }
+
+        let corner = runs[k].curves[runs[k].curves.len() - 1].to_control_points()[3];
+        let incoming = runs[k].curves[runs[k].curves.len() - 1].tangent_at(1.);
+        let outgoing = runs[next].curves[0].tangent_at(0.);
+        let end = curves[curves.len() - 1].to_control_points()[3];
+        let start = offsets[next][0].to_control_points()[0];
+        if end.distance(start) <= tolerance { continue; }
+
+        // turning left puts the gap between the offsets on the right, the outside of the corner
+        if incoming.cross(outgoing) > 0. {
+            let from = end + -corner;
+            let mut arc = Piecewise::arc(corner, distance, from.y.atan2(from.x), from.angle_to(start + -corner)).curves;
+
+            let first = arc[0].to_control_points();
+            arc[0] = Bezier::from_control_points(end, first[1], first[2], first[3]);
+            let last = arc.len() - 1;
+            let p = arc[last].to_control_points();
+            arc[last] = Bezier::from_control_points(p[0], p[1], p[2], start);
+            curves.extend(arc);
+        } else {
+            curves.push(Bezier::from_control_points(end, end, start, start));
+        }
+    }
+
+    // the offsets are snapped onto each other at every join, the contour's start is the one place left
+    if let (Some(first), Some(last)) = (curves.first(), curves.last()) {
+        let (start, p) = (first.to_control_points()[0], last.to_control_points());
+        let last = curves.len() - 1;
+        curves[last] = Bezier::from_control_points(p[0], p[1], p[2], start);
+    }
+
+    return Piecewise { curves: curves };
+}
+
+// Whether any of offset is as far from contour as it should be. Checking the middles of the curves is plenty, a
+// counter that's still there has a whole stretch of its offset clear of its edge.
+fn survives(contour: &Piecewise<Bezier>, offset: &Piecewise<Bezier>, distance: f64, tolerance: f64) -> bool
+{
+    return offset.curves.iter().any(|bez| {
+        contour.nearest(bez.evaluate(0.5)).map_or(false, |(_, _, d)| d >= distance - 2. * tolerance)
+    });
+}
+
+// Grows every contour of an outline that's wound the right way by distance and resolves the overlaps, leaving out
+// contours that turned inside out.
+fn grow(outline: &Piecewise<Piecewise<Bezier>>, distance: f64, tolerance: f64) -> Result<Piecewise<Piecewise<Bezier>>, SkiaError>
+{
+    let mut grown = Vec::new();
+    for contour in &outline.curves {
+        let direction = contour.winding_direction();
+        if direction == WindingDirection::Degenerate { continue; }
+
+        let offset = grow_contour(contour, distance, tolerance);
+        if offset.curves.is_empty() || !survives(contour, &offset, distance, tolerance) { continue; }
+        grown.push(offset);
+    }
+
+    return Piecewise { curves: grown }.remove_overlap();
+}
+
+impl Piecewise<Piecewise<Bezier>>
+{
+    // inflate_with_tolerance fitting the offsets to within a hundredth of a unit.
+    pub fn inflate(&self, delta: f64) -> Result<Self, SkiaError>
+    {
+        return self.inflate_with_tolerance(delta, INFLATE_TOLERANCE);
+    }
+
+    // Makes the fill delta bolder all the way round, or lighter for a negative delta, with the offsets fit to within
+    // tolerance. Counters and stems too small to survive disappear cleanly. The outline is read nonzero after fixing
+    // its winding, so holes drawn the wrong way round are still holes and contours overlapping each other are merged.
+    // Open contours don't fill anything and are left out. What comes back has no overlaps and is wound like
+    // remove_overlap's.
+    pub fn inflate_with_tolerance(&self, delta: f64, tolerance: f64) -> Result<Self, SkiaError>
+    {
+        let closed = Piecewise { curves: self.curves.iter().filter(|c| !c.curves.is_empty() && c.is_closed()).cloned().collect() };
+        let filled = closed.fix_winding().remove_overlap()?;
+        if delta == 0. || filled.curves.is_empty() { return Ok(filled); }
+
+        if delta > 0. { return grow(&filled, delta, tolerance); }
+
+        // flipped inside a rect with room to spare, the outline's contours are holes in it and its holes are outers
+        let bounds = filled.curves.iter().fold(Rect::empty(), |b, c| if b.is_empty() { c.bounds() } else { b.encapsulate_rect(c.bounds()) });
+        let frame = Piecewise::rect(bounds.outset(-delta * 2. + 1.));
+        let mut flipped = filled.reverse();
+        flipped.curves.extend(frame.curves);
+
+        let mut grown = grow(&flipped, -delta, tolerance)?;
+
+        // the frame grew too and is still the biggest thing there
+        let frame = (0..grown.curves.len()).max_by(|a, b| grown.curves[*a].area().partial_cmp(&grown.curves[*b].area()).unwrap_or(std::cmp::Ordering::Equal));
+        if let Some(frame) = frame { grown.curves.remove(frame); }
+
+        return Ok(grown.reverse());
+    }
+}
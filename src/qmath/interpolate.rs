@@ -0,0 +1,207 @@
+use super::*;
+
+// Says exactly where two outlines stopped being compatible so whoever made them knows what to fix.
+#[derive(Debug)]
+pub enum CompatError {
+    ContourCount { left: usize, right: usize },
+    SegmentCount { contour: usize, left: usize, right: usize },
+}
+
+impl std::fmt::Display for CompatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        match self {
+            CompatError::ContourCount { left, right } =>
+                write!(f, "Outlines aren't compatible: the first has {} contours and the second has {}.", left, right),
+            CompatError::SegmentCount { contour, left, right } =>
+                write!(f, "Outlines aren't compatible: contour {} has {} segments in the first and {} in the second.", contour, left, right),
+        }
+    }
+}
+
+impl Bezier {
+    // The coefficients are linear in the control points so we can lerp them directly. Weighting both ends rather
+    // than adding on a fraction of the difference means t = 0 and t = 1 give back self and other bit for bit.
+    pub fn lerp(&self, other: &Bezier, t: f64) -> Bezier
+    {
+        let mix = |a: f64, b: f64| a * (1. - t) + b * t;
+
+        return Bezier {
+            A: mix(self.A, other.A), B: mix(self.B, other.B), C: mix(self.C, other.C), D: mix(self.D, other.D),
+            E: mix(self.E, other.E), F: mix(self.F, other.F), G: mix(self.G, other.G), H: mix(self.H, other.H),
+        };
+    }
+}
+
+impl Piecewise<Bezier>
+{
+    // A contour is closed if it ends where it started. We don't keep track of this anywhere else yet.
+    fn ends_at_start(&self) -> bool
+    {
+        match (self.curves.first(), self.curves.last()) {
+            (Some(first), Some(last)) => first.to_control_points()[0].is_near(last.to_control_points()[3], 1e-6),
+            _ => false
+        }
+    }
+
+    // Rotates a closed contour's segments so that it starts as close as possible to where reference starts.
+    // Open contours can't be rotated without changing their shape so they're returned as is.
+    fn with_start_matching(&self, reference: &Piecewise<Bezier>) -> Piecewise<Bezier>
+    {
+        if !self.ends_at_start() || reference.curves.is_empty() {
+            return Piecewise { curves: self.curves.clone() };
+        }
+
+        let target = reference.curves[0].to_control_points()[0];
+
+        let mut best = 0;
+        let mut best_distance = f64::INFINITY;
+        for (i, bez) in self.curves.iter().enumerate() {
+            let distance = bez.to_control_points()[0].distance(target);
+            if distance < best_distance {
+                best = i;
+                best_distance = distance;
+            }
+        }
+
+        let mut curves = Vec::new();
+        curves.extend_from_slice(&self.curves[best..]);
+        curves.extend_from_slice(&self.curves[..best]);
+
+        return Piecewise { curves: curves };
+    }
+}
+
+impl Piecewise<Piecewise<Bezier>>
+{
+    // Two outlines can be interpolated when they have the same number of contours and each pair of contours
+    // has the same number of segments.
+    pub fn check_compatibility(&self, other: &Self) -> Result<(), CompatError>
+    {
+        if self.curves.len() != other.curves.len() {
+            return Err(CompatError::ContourCount { left: self.curves.len(), right: other.curves.len() });
+        }
+
+        for (i, (a, b)) in self.curves.iter().zip(other.curves.iter()).enumerate() {
+            if a.curves.len() != b.curves.len() {
+                return Err(CompatError::SegmentCount { contour: i, left: a.curves.len(), right: b.curves.len() });
+            }
+        }
+
+        return Ok(());
+    }
+
+    // Lerps every control point between the two outlines, t = 0 gives us self and t = 1 gives us other.
+    pub fn interpolate(&self, other: &Self, t: f64) -> Result<Self, CompatError>
+    {
+        self.check_compatibility(other)?;
+
+        let mut contours = Vec::new();
+        for (a, b) in self.curves.iter().zip(other.curves.iter()) {
+            let curves = a.curves.iter().zip(b.curves.iter()).map(|(ab, bb)| ab.lerp(bb, t)).collect();
+            contours.push(Piecewise { curves: curves });
+        }
+
+        return Ok(Piecewise { curves: contours });
+    }
+
+    // The same without the compatibility check, for previews while someone's still making the masters match.
+    // Contours and segments are paired up in order and anything without a partner is left out.
+    pub fn interpolate_unchecked(&self, other: &Self, t: f64) -> Self
+    {
+        let mut contours = Vec::new();
+        for (a, b) in self.curves.iter().zip(other.curves.iter()) {
+            let curves = a.curves.iter().zip(b.curves.iter()).map(|(ab, bb)| ab.lerp(bb, t)).collect();
+            contours.push(Piecewise { curves: curves });
+        }
+
+        return Piecewise { curves: contours };
+    }
+}
+
+// Interpolates two stroked outlines. Stroking doesn't care where a contour starts, so before checking
+// compatibility we line up the start points of b's closed contours with a's to give us the best chance.
+pub fn interpolate_strokes(a: &Piecewise<Piecewise<Bezier>>, b: &Piecewise<Piecewise<Bezier>>, t: f64) -> Result<Piecewise<Piecewise<Bezier>>, CompatError>
+{
+    a.check_compatibility(b)?;
+
+    let mut normalized = Vec::new();
+    for (ac, bc) in a.curves.iter().zip(b.curves.iter()) {
+        normalized.push(bc.with_start_matching(ac));
+    }
+
+    return a.interpolate(&Piecewise { curves: normalized }, t);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::read_glif_piecewise;
+    use std::path::Path;
+
+    fn masters() -> (Piecewise<Piecewise<Bezier>>, Piecewise<Piecewise<Bezier>>)
+    {
+        let light = read_glif_piecewise(&Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures").join("skeletons").join("closed_mixed.glif")).unwrap();
+        let bold = light.apply_transform(|v: &Vector| Vector { x: v.x * 1.3 + v.y * 0.01, y: v.y - 7. + (v.x * 0.1).sin() });
+        return (light, bold);
+    }
+
+    fn bits(outline: &Piecewise<Piecewise<Bezier>>) -> Vec<u64>
+    {
+        return outline.curves.iter().flat_map(|contour| contour.curves.iter())
+            .flat_map(|b| vec![b.A, b.B, b.C, b.D, b.E, b.F, b.G, b.H])
+            .map(f64::to_bits)
+            .collect();
+    }
+
+    #[test]
+    fn masters_come_back_bit_for_bit()
+    {
+        let (light, bold) = masters();
+        assert_eq!(bits(&light.interpolate(&bold, 0.).unwrap()), bits(&light));
+        assert_eq!(bits(&light.interpolate(&bold, 1.).unwrap()), bits(&bold));
+
+        let halfway = light.interpolate(&bold, 0.5).unwrap();
+        let p = halfway[0][2].evaluate(0.4);
+        assert!(p.is_near(light[0][2].evaluate(0.4).lerp(bold[0][2].evaluate(0.4), 0.5), 1e-9));
+    }
+
+    #[test]
+    fn incompatible_masters_say_where()
+    {
+        let (light, bold) = masters();
+
+        let one_contour = Piecewise::new(vec![bold[0].clone()]);
+        match light.interpolate(&one_contour, 0.5) {
+            Err(CompatError::ContourCount { left: 2, right: 1 }) => {}
+            other => panic!("{:?}", other.err()),
+        }
+
+        let short = Piecewise::new(vec![bold[0].clone(), Piecewise::new(bold[1].segs().skip(1).cloned().collect())]);
+        let n = light[1].len();
+        match light.interpolate(&short, 0.5) {
+            Err(CompatError::SegmentCount { contour: 1, left, right }) => assert_eq!((left, right), (n, n - 1)),
+            other => panic!("{:?}", other.err()),
+        }
+        assert_eq!(light.interpolate(&short, 0.5).err().unwrap().to_string(),
+            format!("Outlines aren't compatible: contour 1 has {} segments in the first and {} in the second.", n, n - 1));
+
+        // the unchecked version leaves out whatever has no partner
+        let preview = light.interpolate_unchecked(&short, 0.5);
+        assert_eq!((preview.len(), preview[0].len(), preview[1].len()), (2, light[0].len(), n - 1));
+    }
+
+    // Strokes can start anywhere round a closed contour, interpolate_strokes lines them up first.
+    #[test]
+    fn strokes_line_up_their_starts()
+    {
+        let (light, bold) = masters();
+        let mut rotated = bold[0].segs().cloned().collect::<Vec<_>>();
+        rotated.rotate_left(2);
+        let rotated = Piecewise::new(vec![Piecewise::new(rotated), bold[1].clone()]);
+
+        let want = light.interpolate(&bold, 0.3).unwrap();
+        let got = interpolate_strokes(&light, &rotated, 0.3).unwrap();
+        assert_eq!(bits(&got), bits(&want));
+    }
+}
@@ -1,61 +1,351 @@
- /* Not working quite yet, but I don't want to lose the code.
-    //https://www.particleincell.com/2013/cubic-line-intersection/
-    //Ported this article from js to rust for the following two functions. Left out the line segment checks in the second function
-    //because we don't have need for that in this case.
-    // I can't get this to work at the moment, I'm going to have to build a real-time visualizer to help me check the line
-    // vs the reported intersections.
-    #[allow(non_snake_case)]
-    fn cubic_roots(a: f64, b: f64, c: f64, d: f64) -> Vec<f64>
+use super::*;
+
+// how flat, in units, both curves have to be before we intersect their chords instead of splitting further
+const FLATNESS: f64 = 1e-6;
+// how many places along a possibly shared stretch we check really are on both curves
+const SHARED_SAMPLES: usize = 16;
+// backstops for degenerate input like two copies of the same curve, where every piece overlaps every other
+const MAX_DEPTH: usize = 48;
+const MAX_HITS: usize = 1024;
+
+fn flat(bez: &Bezier) -> bool
+{
+    return flat_within(bez, FLATNESS);
+}
+
+fn flat_within(bez: &Bezier, tolerance: f64) -> bool
+{
+    let cp = bez.to_control_points();
+    let chord = cp[3] + -cp[0];
+    let length = chord.magnitude();
+    if length < tolerance { return cp[1].distance(cp[0]) < tolerance && cp[2].distance(cp[0]) < tolerance; }
+
+    let off = |p: Vector| ((p.x - cp[0].x) * chord.y - (p.y - cp[0].y) * chord.x).abs() / length;
+    return off(cp[1]) < tolerance && off(cp[2]) < tolerance;
+}
+
+// The t on a flat piece at fraction f along its chord. Flat only means straight, the handles can be anywhere along
+// the chord, a line with its handles on its ends being the usual case, so the fraction isn't the t. The point on the
+// piece nearest that spot on the chord is.
+fn chord_t(bez: &Bezier, f: f64) -> f64
+{
+    if f <= 0. { return 0.; }
+    if f >= 1. { return 1.; }
+    return bez.nearest(bez.evaluate(0.).lerp(bez.evaluate(1.), f)).0;
+}
+
+// Where the chords cross, as the t on each piece. Parallel chords, overlapping or not, don't count.
+fn chord_hit(a: &Bezier, b: &Bezier) -> Option<(f64, f64)>
+{
+    let (a0, a1) = (a.evaluate(0.), a.evaluate(1.));
+    let (b0, b1) = (b.evaluate(0.), b.evaluate(1.));
+    let da = a1 + -a0;
+    let db = b1 + -b0;
+
+    let denominator = da.x * db.y - da.y * db.x;
+    if denominator.abs() < 1e-18 { return None; }
+
+    let diff = b0 + -a0;
+    let s = (diff.x * db.y - diff.y * db.x) / denominator;
+    let u = (diff.x * da.y - diff.y * da.x) / denominator;
+
+    let slack = 1e-9;
+    if s < -slack || s > 1. + slack || u < -slack || u > 1. + slack { return None; }
+    return Some((chord_t(a, s), chord_t(b, u)));
+}
+
+fn intersect_pieces(a: &Bezier, ar: (f64, f64), b: &Bezier, br: (f64, f64), depth: usize, out: &mut Vec<(f64, f64)>)
+{
+    if out.len() >= MAX_HITS || !a.bounds().overlaps(&b.bounds()) { return; }
+
+    if depth >= MAX_DEPTH || (flat(a) && flat(b)) {
+        if let Some((s, u)) = chord_hit(a, b) {
+            out.push((ar.0 + (ar.1 - ar.0) * s, br.0 + (br.1 - br.0) * u));
+        }
+        return;
+    }
+
+    let am = (ar.0 + ar.1) / 2.;
+    let bm = (br.0 + br.1) / 2.;
+    let (a_left, a_right) = a.subdivide(0.5);
+    let (b_left, b_right) = b.subdivide(0.5);
+
+    intersect_pieces(&a_left, (ar.0, am), &b_left, (br.0, bm), depth + 1, out);
+    intersect_pieces(&a_left, (ar.0, am), &b_right, (bm, br.1), depth + 1, out);
+    intersect_pieces(&a_right, (am, ar.1), &b_left, (br.0, bm), depth + 1, out);
+    intersect_pieces(&a_right, (am, ar.1), &b_right, (bm, br.1), depth + 1, out);
+}
+
+// Where two flat pieces that don't cross come within tolerance of each other. The pieces' t ranges come along so
+// neighbouring contacts can be joined up afterwards.
+#[derive(Clone, Copy)]
+struct Contact {
+    ar: (f64, f64),
+    br: (f64, f64),
+    // where they're closest, and how close that is
+    closest: (f64, f64, f64),
+}
+
+// The fraction along the chord from s0 to s1 closest to p, and how far away that is.
+fn closest_on_chord(p: Vector, s0: Vector, s1: Vector) -> (f64, f64)
+{
+    let d = s1 + -s0;
+    let length_squared = d.x * d.x + d.y * d.y;
+    if length_squared < 1e-24 { return (0., p.distance(s0)); }
+
+    let f = f64::clamp(((p.x - s0.x) * d.x + (p.y - s0.y) * d.y) / length_squared, 0., 1.);
+    return (f, p.distance(s0.lerp(s1, f)));
+}
+
+fn leaf_contact(a: &Bezier, ar: (f64, f64), b: &Bezier, br: (f64, f64), tolerance: f64) -> Option<Contact>
+{
+    let (a0, a1) = (a.evaluate(0.), a.evaluate(1.));
+    let (b0, b1) = (b.evaluate(0.), b.evaluate(1.));
+
+    // every chord end against the other chord, as (fraction on a, fraction on b, distance)
+    let candidates = [
+        { let (u, d) = closest_on_chord(a0, b0, b1); (0., u, d) },
+        { let (u, d) = closest_on_chord(a1, b0, b1); (1., u, d) },
+        { let (s, d) = closest_on_chord(b0, a0, a1); (s, 0., d) },
+        { let (s, d) = closest_on_chord(b1, a0, a1); (s, 1., d) },
+    ];
+
+    let best = candidates.iter().copied().fold(candidates[0], |best, c| if c.2 < best.2 { c } else { best });
+    if best.2 > tolerance { return None; }
+
+    let closest = (ar.0 + (ar.1 - ar.0) * chord_t(a, best.0), br.0 + (br.1 - br.0) * chord_t(b, best.1), best.2);
+    return Some(Contact { ar: ar, br: br, closest: closest });
+}
+
+// The stretch two curves share when they run along each other within tolerance, as its two ends in order along
+// self. Two cubics can only coincide up to where one of them ends, so the ends of a shared stretch are always
+// the end of one curve sitting on the other.
+fn shared_stretch(a: &Bezier, b: &Bezier, tolerance: f64) -> Option<((f64, f64), (f64, f64))>
+{
+    let mut ends: Vec<(f64, f64)> = Vec::new();
+    for t in [0., 1.].iter() {
+        let (tb, d) = b.nearest(a.evaluate(*t));
+        if d <= tolerance { ends.push((*t, tb)); }
+
+        let (ta, d) = a.nearest(b.evaluate(*t));
+        if d <= tolerance { ends.push((ta, *t)); }
+    }
+
+    if ends.len() < 2 { return None; }
+    let start = ends.iter().copied().fold(ends[0], |x, e| if e.0 < x.0 { e } else { x });
+    let end = ends.iter().copied().fold(ends[0], |x, e| if e.0 > x.0 { e } else { x });
+
+    // both ends in the same place is just the curves meeting, like neighbours at their joint
+    if a.evaluate(start.0).distance(a.evaluate(end.0)) <= tolerance { return None; }
+
+    for k in 1..SHARED_SAMPLES {
+        let t = start.0 + (end.0 - start.0) * k as f64 / SHARED_SAMPLES as f64;
+        if b.nearest(a.evaluate(t)).1 > tolerance { return None; }
+    }
+
+    return Some((start, end));
+}
+
+fn contacts_between(a: &Bezier, ar: (f64, f64), b: &Bezier, br: (f64, f64), tolerance: f64, depth: usize, crossings: &mut Vec<Contact>, contacts: &mut Vec<Contact>)
+{
+    if crossings.len() + contacts.len() >= MAX_HITS { return; }
+
+    let grow = |r: Rect| Rect { left: r.left - tolerance, right: r.right + tolerance, bottom: r.bottom - tolerance, top: r.top + tolerance };
+    if !grow(a.bounds()).overlaps(&b.bounds()) { return; }
+
+    if depth >= MAX_DEPTH || (flat_within(a, tolerance) && flat_within(b, tolerance)) {
+        match chord_hit(a, b) {
+            Some((s, u)) => crossings.push(Contact { ar: ar, br: br, closest: (ar.0 + (ar.1 - ar.0) * s, br.0 + (br.1 - br.0) * u, 0.) }),
+            None => contacts.extend(leaf_contact(a, ar, b, br, tolerance))
+        }
+        return;
+    }
+
+    let am = (ar.0 + ar.1) / 2.;
+    let bm = (br.0 + br.1) / 2.;
+    let (a_left, a_right) = a.subdivide(0.5);
+    let (b_left, b_right) = b.subdivide(0.5);
+
+    contacts_between(&a_left, (ar.0, am), &b_left, (br.0, bm), tolerance, depth + 1, crossings, contacts);
+    contacts_between(&a_left, (ar.0, am), &b_right, (bm, br.1), tolerance, depth + 1, crossings, contacts);
+    contacts_between(&a_right, (am, ar.1), &b_left, (br.0, bm), tolerance, depth + 1, crossings, contacts);
+    contacts_between(&a_right, (am, ar.1), &b_right, (bm, br.1), tolerance, depth + 1, crossings, contacts);
+}
+
+fn ranges_touch(x: (f64, f64), y: (f64, f64)) -> bool
+{
+    return x.0 <= y.1 + 1e-12 && y.0 <= x.1 + 1e-12;
+}
+
+impl Bezier {
+    // Raw (t on self, t on other) pairs where the two curves meet, by splitting both in half until the pieces that
+    // still overlap are flat and then crossing their chords. Hits come out unordered and a crossing that lands on
+    // a split can show up more than once, put them through an IntersectionSet before using them.
+    pub fn intersect_bezier(&self, other: &Bezier) -> Vec<(f64, f64)>
     {
-        let A = b/a;
-        let B = c/a;
-        let C = d/a;
-
-        let Q = (3.*B - A.powi(2))/9.;
-        let R = (9.*A*B - 27.*C - 2.*A.powi(3))/54.;
-        let D = Q.powi(3) + R.powi(2); // the polynomical discriminant
-
-        let mut t: [f64; 3] = [0., 0., 0.];
-
-        if D >= 0. 
-        { 
-            // complex or duplicate roots
-            let S = f64::signum(R + f64::sqrt(D)) * f64::powf(f64::abs(R + f64::sqrt(D)), 1./3.);
-            let T = f64::signum(R - f64::sqrt(D)) * f64::powf(f64::abs(R - f64::sqrt(D)), 1./3.);
-
-            t[0] =  -A/3. + (S + T);
-            t[1] = -A/3. - (S + T)/2.;
-            t[2] = -A/3. - (S + T)/2.;
-            let Im = f64::abs(f64::sqrt(3.)*(S - T)/2.);
-
-            if Im != 0.0
-            {
-            t[1] = -1.;
-            t[2] = -1.; 
-            }
+        let mut hits = Vec::new();
+        intersect_pieces(self, (0., 1.), other, (0., 1.), 0, &mut hits);
+        return hits;
+    }
+
+    // (t on self, t on other) for every place the curves meet, sorted by t on self. Both curves are split until
+    // they're flat to within tolerance and then each pair of pieces that still overlap either crosses or comes
+    // within tolerance of the other without crossing. Runs of neighbouring pieces that come within tolerance are
+    // one tangential touch and we give back where they came closest.
+    //
+    // Nearly coincident curves would match everywhere along the stretch they share, so we look for that first and
+    // give back just its two ends, only searching the rest of self for anything else. Hits within tolerance of
+    // either curve's ends are put exactly on t = 0 or 1, so neighbouring segments meeting at their shared point
+    // come out as (1, 0) rather than something nearby.
+    pub fn intersects(&self, other: &Bezier, tolerance: f64) -> Vec<(f64, f64)>
+    {
+        let shared = shared_stretch(self, other, tolerance);
+
+        let mut hits = Vec::new();
+        let mut ranges = vec![(0., 1.)];
+        if let Some((start, end)) = shared {
+            hits.push(start);
+            hits.push(end);
+            ranges = vec![(0., start.0), (end.0, 1.)];
         }
-        else
-        {
-            //distinct real roots
-            let th = f64::acos(R/f64::sqrt(f64::powi(Q, 3)));
-            t[0] = 2. * f64::sqrt(-Q) * f64::cos(th/3.) - A/3.;
-            t[1] = 2. * f64::sqrt(-Q) * f64::cos((th + 2.*std::f64::consts::PI)/3.) - A/3.;
-            t[2] = 2. * f64::sqrt(-Q) * f64::cos((th + 2.*std::f64::consts::PI)/3.) - A/3.;
+
+        let mut crossings = Vec::new();
+        let mut contacts = Vec::new();
+        for (lo, hi) in ranges {
+            if hi - lo <= 1e-9 { continue; }
+            contacts_between(&self.restrict(lo, hi), (lo, hi), other, (0., 1.), tolerance, 0, &mut crossings, &mut contacts);
         }
 
-        let mut result = Vec::new();
-        for &r in &t
-        {
-            if !(r<0.0 || r>1.0) && !r.is_nan()
-            {
-                result.push(r)
+        contacts.sort_by(|x, y| x.ar.0.partial_cmp(&y.ar.0).unwrap());
+        let mut runs: Vec<Vec<Contact>> = Vec::new();
+        for contact in contacts {
+            let joins = runs.last().map_or(false, |run: &Vec<Contact>| {
+                run.iter().any(|c| ranges_touch(c.ar, contact.ar) && ranges_touch(c.br, contact.br))
+            });
+            if joins { runs.last_mut().unwrap().push(contact); } else { runs.push(vec![contact]); }
+        }
+
+        // curves that cross where they run close, the way osculating ones do, come within tolerance either side of
+        // the crossing, that's all the one meeting
+        let mut meetings: Vec<(f64, f64)> = crossings.iter().map(|c| (c.closest.0, c.closest.1)).collect();
+        for run in runs {
+            let crossed = run.iter().any(|c| crossings.iter().any(|x| ranges_touch(c.ar, x.ar) && ranges_touch(c.br, x.br)));
+            if crossed { continue; }
+
+            let closest = run.iter().fold(run[0].closest, |best, c| if c.closest.2 < best.2 { c.closest } else { best });
+            meetings.push((closest.0, closest.1));
+        }
+
+        // the search either side of a shared stretch finds its ends again
+        for (ta, tb) in meetings {
+            let point = self.evaluate(ta);
+            let on_shared_end = shared.map_or(false, |(start, end)| {
+                point.distance(self.evaluate(start.0)) <= tolerance || point.distance(self.evaluate(end.0)) <= tolerance
+            });
+            if !on_shared_end { hits.push((ta, tb)); }
+        }
+
+        let snap = |t: f64, curve: &Bezier, point: Vector| {
+            if curve.evaluate(0.).distance(point) <= tolerance { 0. }
+            else if curve.evaluate(1.).distance(point) <= tolerance { 1. }
+            else { t }
+        };
+
+        let mut set = IntersectionSet::new(INTERSECTION_EPSILON);
+        for (ta, tb) in hits {
+            let point = self.evaluate(ta);
+            let ta = snap(ta, self, point);
+            let tb = snap(tb, other, other.evaluate(tb));
+            set.push(CurveLocation { contour: 0, segment: 0, t: ta }, Some(CurveLocation { contour: 0, segment: 0, t: tb }), point);
+        }
+
+        return set.intersections().iter().map(|h| (h.a.t, h.b.unwrap().t)).collect();
+    }
+}
+
+impl Piecewise<Bezier>
+{
+    // Bezier::intersects for every pair of curves that come within tolerance of each other, with the ts mapped onto
+    // each piecewise's global t. A hit on a joint shows up on both curves around it, those come out once.
+    pub fn intersections_with(&self, other: &Piecewise<Bezier>, tolerance: f64) -> Vec<(f64, f64)>
+    {
+        let n = self.curves.len() as f64;
+        let m = other.curves.len() as f64;
+
+        // outsetting one side is enough for anything within tolerance to overlap
+        let ours = SegmentIndex::from_rects(self.curves.iter().map(|bez| bez.bounds().outset(tolerance)));
+        let theirs = SegmentIndex::from_contour(other);
+
+        let mut hits: Vec<(f64, f64)> = Vec::new();
+        for ((_, i), (_, j)) in ours.query_pairs(&theirs) {
+            for (ta, tb) in self.curves[i].intersects(&other.curves[j], tolerance) {
+                hits.push(((i as f64 + ta) / n, (j as f64 + tb) / m));
             }
         }
 
-        if result.len() > 1 { result.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Greater)); }
+        hits.sort_by(|x, y| x.partial_cmp(y).unwrap());
+        hits.dedup_by(|x, y| (x.0 - y.0).abs() < 1e-9 && (x.1 - y.1).abs() < 1e-9);
+        return hits;
+    }
+}
+
+impl Piecewise<Piecewise<Bezier>>
+{
+    // Every place a curve of self meets a curve of other, with the a side on self.
+    pub fn intersections(&self, other: &Piecewise<Piecewise<Bezier>>) -> IntersectionSet
+    {
+        let mut set = IntersectionSet::new(INTERSECTION_EPSILON);
+
+        let ours = SegmentIndex::from_piecewise(self);
+        let theirs = SegmentIndex::from_piecewise(other);
+        for ((ac, asg), (bc, bsg)) in ours.query_pairs(&theirs) {
+            let a = &self.curves[ac].curves[asg];
+            let b = &other.curves[bc].curves[bsg];
+            for (ta, tb) in a.intersect_bezier(b) {
+                set.push(CurveLocation { contour: ac, segment: asg, t: ta }, Some(CurveLocation { contour: bc, segment: bsg, t: tb }), a.evaluate(ta));
+            }
+        }
+
+        set.finish();
+        return set;
+    }
+
+    // Where the outline's curves cross each other, each crossing once with a before b. Neighbouring curves always
+    // meet where they're joined, those meetings aren't included. A single curve looping over itself isn't found.
+    pub fn self_intersections(&self) -> IntersectionSet
+    {
+        let mut set = IntersectionSet::new(INTERSECTION_EPSILON);
+
+        let index = SegmentIndex::from_piecewise(self);
+        for ((ac, asg), (bc, bsg)) in index.query_pairs(&index) {
+            if (ac, asg) >= (bc, bsg) { continue; }
+
+            let a = &self.curves[ac].curves[asg];
+            let b = &self.curves[bc].curves[bsg];
+
+            // the joint between two neighbours, including the one closing the contour
+            let count = self.curves[ac].curves.len();
+            let joined_after = ac == bc && bsg == asg + 1;
+            let joined_before = ac == bc && asg == 0 && bsg == count - 1 && self.curves[ac].is_closed();
 
-        return result;
+            for (ta, tb) in a.intersect_bezier(b) {
+                if joined_after && ta > 1. - 1e-6 && tb < 1e-6 { continue; }
+                if joined_before && ta < 1e-6 && tb > 1. - 1e-6 { continue; }
+                set.push(CurveLocation { contour: ac, segment: asg, t: ta }, Some(CurveLocation { contour: bc, segment: bsg, t: tb }), a.evaluate(ta));
+            }
+        }
+
+        set.finish();
+        return set;
     }
+}
+
+ /* Not working quite yet, but I don't want to lose the code.
+    //https://www.particleincell.com/2013/cubic-line-intersection/
+    //Ported this article from js to rust for the following function. Left out the line segment checks
+    //because we don't have need for that in this case. Root finding lives in roots.rs now.
+    // I can't get this to work at the moment, I'm going to have to build a real-time visualizer to help me check the line
+    // vs the reported intersections.
 
     // Compute intersections for beziers vs a line defined by two points. Returns a vec of t values at intersections.
     #[allow(non_snake_case)]
@@ -74,7 +364,7 @@
         let c = A * self.B + B * self.F;
         let d = A * self.A + B * self.E + C;
 
-        let r = Bezier::cubic_roots(a, b, c, d);
+        let r = roots::solve_cubic(a, b, c, d, 0., 1.);
         
         return r;
     }
@@ -149,4 +439,70 @@
         return l1 + (v - l0) * (h1 - l1) / (h0 - l0);
     }
 
-*/
\ No newline at end of file
+*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(x: f64, y: f64) -> Vector
+    {
+        return Vector { x: x, y: y };
+    }
+
+    fn line(from: Vector, to: Vector) -> Bezier
+    {
+        return Bezier::from_control_points(from, from, to, to);
+    }
+
+    // The graph of the cubic f from x = -50 to 50 as a single curve. It goes through f at thirds of the way along,
+    // and x moving evenly with t makes that the graph exactly.
+    fn graph(f: impl Fn(f64) -> f64) -> Bezier
+    {
+        let y: Vec<f64> = (0..4).map(|i| f(-50. + 100. * i as f64 / 3.)).collect();
+        let b1 = (-5. * y[0] + 18. * y[1] - 9. * y[2] + 2. * y[3]) / 6.;
+        let b2 = (2. * y[0] - 9. * y[1] + 18. * y[2] - 5. * y[3]) / 6.;
+        return Bezier::from_control_points(v(-50., y[0]), v(-50. + 100. / 3., b1), v(50. - 100. / 3., b2), v(50., y[3]));
+    }
+
+    // y = x^2 / 200 and the same plus a millionth of x^3 agree at the origin in height, slope and curvature and stay
+    // within an eighth of a unit of each other the whole way. They cross once, and every piece either side of the
+    // origin comes close enough to look like another hit, all of which have to come out as the one.
+    #[test]
+    fn osculating_curves()
+    {
+        let a = graph(|x| x * x / 200.);
+        let b = graph(|x| x * x / 200. + 1e-6 * x * x * x);
+
+        let outline = |bez: &Bezier| Piecewise::new(vec![Piecewise::new(vec![bez.clone()])]);
+        let set = outline(&a).intersections(&outline(&b));
+        assert_eq!(set.len(), 1, "{:?}", set.intersections());
+        assert!(set.points()[0].is_near(v(0., 0.), 1e-3), "{:?}", set.points());
+
+        let hits = a.intersects(&b, 1e-3);
+        assert_eq!(hits.len(), 1, "{:?}", hits);
+        assert!(a.evaluate(hits[0].0).is_near(v(0., 0.), 1e-3) && b.evaluate(hits[0].1).is_near(v(0., 0.), 1e-3), "{:?}", hits);
+    }
+
+    // A stem standing on a bar with its foot exactly on it, and the same stem carried on through the bar as two
+    // lines joined where they cross it. Each is one hit, on the bar at the foot and at the stem's joint.
+    #[test]
+    fn t_junction()
+    {
+        let bar = Piecewise::new(vec![Piecewise::new(vec![line(v(0., 0.), v(200., 0.))])]);
+        let stem = Piecewise::new(vec![Piecewise::new(vec![line(v(70., 100.), v(70., 0.))])]);
+
+        let set = bar.intersections(&stem);
+        assert_eq!(set.len(), 1, "{:?}", set.intersections());
+        let hit = set.intersections()[0];
+        assert!(hit.point.is_near(v(70., 0.), 1e-6), "{:?}", hit);
+        assert!(bar[0][0].evaluate(hit.a.t).is_near(v(70., 0.), 1e-6), "{:?}", hit);
+        assert!((hit.b.unwrap().t - 1.).abs() < 1e-6, "{:?}", hit);
+
+        let through = Piecewise::new(vec![Piecewise::new(vec![line(v(70., 100.), v(70., 0.)), line(v(70., 0.), v(70., -100.))])]);
+        let set = bar.intersections(&through);
+        assert_eq!(set.len(), 1, "{:?}", set.intersections());
+        assert!(set.points()[0].is_near(v(70., 0.), 1e-6), "{:?}", set.points());
+        let b = set.intersections()[0].b.unwrap();
+        assert!((b.segment as f64 + b.t - 1.).abs() < 1e-6, "{:?}", b);
+    }
+}
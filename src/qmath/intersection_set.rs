@@ -0,0 +1,156 @@
+use super::*;
+use std::cell::OnceCell;
+
+// Where on an outline something is. The segment is the curve's index in its contour and t is on that curve.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CurveLocation {
+    pub contour: usize,
+    pub segment: usize,
+    pub t: f64,
+}
+
+impl CurveLocation {
+    // segment and t together, so the end of one curve and the start of the next compare as the same place
+    fn position(&self) -> f64
+    {
+        return self.segment as f64 + self.t;
+    }
+
+    fn near(&self, other: &CurveLocation, epsilon: f64) -> bool
+    {
+        return self.contour == other.contour && (self.position() - other.position()).abs() < epsilon;
+    }
+}
+
+// A hit, on the curve we were intersecting and, for curve against curve, on the one it hit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Intersection {
+    pub a: CurveLocation,
+    pub b: Option<CurveLocation>,
+    pub point: Vector,
+}
+
+// Everything that finds intersections hands them back in one of these. Splitting code is very sensitive to being
+// handed the same hit twice, that's how zero length segments get made, so once the raw hits are in we merge any
+// closer than epsilon in parameter space and put what's left in a fixed order: by contour, segment, then t on the
+// a side. The end of one segment and the start of the next count as the same place. The merged hits are worked out
+// the first time something looks at them and kept until another hit gets pushed, so the views only need &self.
+pub struct IntersectionSet {
+    epsilon: f64,
+    hits: Vec<Intersection>,
+    merged: OnceCell<Vec<Intersection>>,
+}
+
+// the default merge distance in parameter space
+pub const INTERSECTION_EPSILON: f64 = 1e-7;
+
+impl IntersectionSet {
+    pub fn new(epsilon: f64) -> Self
+    {
+        return IntersectionSet { epsilon: epsilon, hits: Vec::new(), merged: OnceCell::new() };
+    }
+
+    pub fn push(&mut self, a: CurveLocation, b: Option<CurveLocation>, point: Vector)
+    {
+        self.hits.push(Intersection { a: a, b: b, point: point });
+        self.merged = OnceCell::new();
+    }
+
+    pub fn extend(&mut self, other: IntersectionSet)
+    {
+        for hit in other.hits {
+            self.push(hit.a, hit.b, hit.point);
+        }
+    }
+
+    // Sorts and merges the raw hits. Every view calls this so there's normally no need to yourself.
+    pub fn finish(&self)
+    {
+        self.intersections();
+    }
+
+    pub fn len(&self) -> usize
+    {
+        return self.intersections().len();
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        return self.len() == 0;
+    }
+
+    pub fn intersections(&self) -> &[Intersection]
+    {
+        return self.merged.get_or_init(|| merge(&self.hits, self.epsilon));
+    }
+
+    // The point space view.
+    pub fn points(&self) -> Vec<Vector>
+    {
+        return self.intersections().iter().map(|h| h.point).collect();
+    }
+
+    // The parameter space view for splitting one curve, its sorted t values from either side of every hit.
+    pub fn ts_for(&self, contour: usize, segment: usize) -> Vec<f64>
+    {
+        let mut ts: Vec<f64> = Vec::new();
+        for hit in self.intersections() {
+            for loc in std::iter::once(hit.a).chain(hit.b) {
+                if loc.contour == contour && loc.segment == segment { ts.push(loc.t); }
+            }
+        }
+
+        ts.sort_by(|x, y| x.partial_cmp(y).unwrap());
+        ts.dedup_by(|x, y| (*x - *y).abs() < self.epsilon);
+        return ts;
+    }
+}
+
+fn merge(hits: &[Intersection], epsilon: f64) -> Vec<Intersection>
+{
+    let mut sorted = hits.to_vec();
+    let key = |h: &Intersection| (h.a.contour, h.a.position());
+    sorted.sort_by(|x, y| key(x).partial_cmp(&key(y)).unwrap());
+
+    // runs of hits that are all within epsilon of their neighbour on both sides get averaged into one
+    let mut merged: Vec<Intersection> = Vec::new();
+    let mut run: Vec<Intersection> = Vec::new();
+    for hit in sorted {
+        let continues = run.last().map_or(false, |last: &Intersection| {
+            last.a.near(&hit.a, epsilon) && match (last.b, hit.b) {
+                (Some(lb), Some(hb)) => lb.near(&hb, epsilon),
+                (None, None) => true,
+                _ => false
+            }
+        });
+
+        if !continues && !run.is_empty() {
+            merged.push(average(&run));
+            run.clear();
+        }
+        run.push(hit);
+    }
+    if !run.is_empty() { merged.push(average(&run)); }
+
+    return merged;
+}
+
+// A run gets the middle of its parameters and points. Locations on a run can straddle a joint, (2, 0.99999999)
+// next to (3, 0.0), so we average the combined position and split it back up.
+fn average(run: &[Intersection]) -> Intersection
+{
+    let average_location = |locs: Vec<CurveLocation>| {
+        let position = locs.iter().map(|l| l.position()).sum::<f64>() / locs.len() as f64;
+        // stay on the first hit's segment when the average sits right on its end, its t is what callers expect
+        let first = locs[0];
+        let segment = if position - first.segment as f64 <= 1. { first.segment } else { position.floor() as usize };
+        CurveLocation { contour: first.contour, segment: segment, t: f64::clamp(position - segment as f64, 0., 1.) }
+    };
+
+    let n = run.len() as f64;
+    let point = run.iter().fold(Vector { x: 0., y: 0. }, |sum, h| sum + h.point) * (1. / n);
+    let a = average_location(run.iter().map(|h| h.a).collect());
+    let b = if run[0].b.is_some() { Some(average_location(run.iter().filter_map(|h| h.b).collect())) } else { None };
+
+    return Intersection { a: a, b: b, point: point };
+}
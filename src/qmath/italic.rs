@@ -0,0 +1,44 @@
+use super::*;
+
+// Slanting a glyph into an oblique. A skew keeps horizontals horizontal, so the tops and bottoms of curves stay
+// where they were, but a curve's leftmost and rightmost points move off its old vertical extrema and end up partway
+// along a segment. After skewing we put points at the new extrema and turn any handles the skew left nearly flat or
+// upright back onto the axis, the way a font wants them.
+
+// extrema closer than this to a point that's already there don't get one of their own, in font units
+const ITALIC_EXTREMA_TOLERANCE: f64 = 1.;
+// handles within this many degrees of an axis after the skew are turned onto it
+const ITALIC_SNAP_ANGLE: f64 = 1.;
+
+// The skew leaning vertical lines angle degrees to the right, with the line y = cross_height staying where it is.
+fn italic_transform(angle: f64, cross_height: f64) -> Affine
+{
+    let lean = angle.to_radians().tan();
+    return Affine::translate(-cross_height * lean, 0.) * Affine::skew(angle.to_radians(), 0.);
+}
+
+impl Piecewise<Bezier>
+{
+    // The contour slanted angle degrees to the right about the line y = cross_height, with points at its new
+    // extrema. Angles of 90 degrees or more either way have no slant to give and leave the contour as it is.
+    pub fn italicize(&self, angle: f64, cross_height: f64) -> Self
+    {
+        if !(angle.abs() < 90.) { return self.clone(); }
+
+        let skewed = self.transform(&italic_transform(angle, cross_height));
+        return skewed.insert_extrema_points(ITALIC_EXTREMA_TOLERANCE).snapped_tangents(ITALIC_SNAP_ANGLE).0;
+    }
+}
+
+impl Piecewise<Piecewise<Bezier>>
+{
+    // italicize for every contour. Along with the outline comes how far the slant moved the baseline sideways, for
+    // adjusting the sidebearings by.
+    pub fn italicize(&self, angle: f64, cross_height: f64) -> (Self, f64)
+    {
+        if !(angle.abs() < 90.) { return (self.clone(), 0.); }
+
+        let output = Piecewise { curves: self.curves.iter().map(|contour| contour.italicize(angle, cross_height)).collect() };
+        return (output, italic_transform(angle, cross_height).apply(Vector { x: 0., y: 0. }).x);
+    }
+}
@@ -0,0 +1,130 @@
+use super::*;
+use ::kurbo::{BezPath, CubicBez, PathEl, Point};
+
+// Conversions to and from kurbo's types, so the rest of the Rust font tooling can work on our curves and hand them
+// back. Going out, lines are written as lines and everything else as cubics. Coming in, quadratics are raised to
+// cubics, which is exact, and a close draws a line back to the start the same way it does coming from skia.
+
+impl From<Vector> for Point {
+    fn from(v: Vector) -> Point
+    {
+        return Point::new(v.x, v.y);
+    }
+}
+
+impl From<Point> for Vector {
+    fn from(p: Point) -> Vector
+    {
+        return Vector { x: p.x, y: p.y };
+    }
+}
+
+impl From<Bezier> for CubicBez {
+    fn from(bez: Bezier) -> CubicBez
+    {
+        let cp = bez.to_control_points();
+        return CubicBez::new(Point::from(cp[0]), Point::from(cp[1]), Point::from(cp[2]), Point::from(cp[3]));
+    }
+}
+
+impl From<CubicBez> for Bezier {
+    fn from(bez: CubicBez) -> Bezier
+    {
+        return Bezier::from_control_points(bez.p0.into(), bez.p1.into(), bez.p2.into(), bez.p3.into());
+    }
+}
+
+impl Piecewise<Piecewise<Bezier>>
+{
+    pub fn to_bezpath(&self) -> BezPath
+    {
+        let mut path = BezPath::new();
+        for contour in &self.curves {
+            contour.append_to_bezpath(&mut path);
+        }
+
+        return path;
+    }
+
+    pub fn from_bezpath(path: &BezPath) -> Self
+    {
+        let mut contours: Vec<Piecewise<Bezier>> = Vec::new();
+
+        let mut cur_contour: Vec<Bezier> = Vec::new();
+        let mut contour_start = Vector { x: 0., y: 0. };
+        let mut last_point = Vector { x: 0., y: 0. };
+        for el in path.elements() {
+            match *el {
+                // a move with nothing pending just replaces the last one, consecutive moves don't make empty contours
+                PathEl::MoveTo(p) => {
+                    if !cur_contour.is_empty() {
+                        contours.push(Piecewise { curves: cur_contour });
+                    }
+
+                    cur_contour = Vec::new();
+                    contour_start = p.into();
+                    last_point = contour_start;
+                }
+
+                PathEl::LineTo(p) => {
+                    let np: Vector = p.into();
+                    cur_contour.push(Bezier::from_control_points(last_point, last_point, np, np));
+                    last_point = np;
+                }
+
+                PathEl::QuadTo(p1, p2) => {
+                    let np: Vector = p2.into();
+                    cur_contour.push(Bezier::from_quadratic_points(last_point, p1.into(), np));
+                    last_point = np;
+                }
+
+                PathEl::CurveTo(p1, p2, p3) => {
+                    let np: Vector = p3.into();
+                    cur_contour.push(Bezier::from_control_points(last_point, p1.into(), p2.into(), np));
+                    last_point = np;
+                }
+
+                PathEl::ClosePath => {
+                    if !cur_contour.is_empty() {
+                        if last_point != contour_start {
+                            cur_contour.push(Bezier::from_control_points(last_point, last_point, contour_start, contour_start));
+                        }
+                        contours.push(Piecewise { curves: cur_contour });
+                    }
+
+                    cur_contour = Vec::new();
+                    last_point = contour_start;
+                }
+            }
+        }
+
+        if !cur_contour.is_empty() {
+            contours.push(Piecewise { curves: cur_contour });
+        }
+
+        return Piecewise { curves: contours };
+    }
+}
+
+impl Piecewise<Bezier>
+{
+    pub fn append_to_bezpath(&self, path: &mut BezPath)
+    {
+        let first = match self.curves.first() {
+            Some(bez) => bez.to_control_points()[0],
+            None => return,
+        };
+        path.move_to(Point::from(first));
+
+        for bez in &self.curves {
+            let cp = bez.to_control_points();
+            if cp[1] == cp[0] && cp[2] == cp[3] {
+                path.line_to(Point::from(cp[3]));
+            } else {
+                path.curve_to(Point::from(cp[1]), Point::from(cp[2]), Point::from(cp[3]));
+            }
+        }
+
+        if self.is_closed() { path.close_path(); }
+    }
+}
@@ -0,0 +1,182 @@
+use super::*;
+
+// how far the polyline we measure along can be from the curve's length, as a fraction of each piece's length
+const LENGTH_TOLERANCE: f64 = 1e-6;
+// how many times we'll halve a piece of the curve before taking its length as it is
+const MAX_DEPTH: usize = 24;
+// curves that aren't piecewises don't tell us where their joints are, so we start out with this many pieces
+const INITIAL_PIECES: usize = 16;
+// bisection steps when finding t inside one of the table's pieces
+const INVERSE_STEPS: usize = 48;
+// how far either side of a cusp curvature_ds looks for a curvature, as fractions of the whole length
+const CUSP_STEPS: [f64; 3] = [1e-6, 1e-4, 1e-2];
+
+// Evaluates a curve by how far along it you are instead of by t. Evaluate's t walks in parameter space, so a
+// Piecewise made of a long curve and a tiny one spends half its t on each and sampling it evenly in t bunches
+// the samples up on the tiny one. This measures the curve once into a table of (t, length) breakpoints that
+// follows the curve to within LENGTH_TOLERANCE and then maps normalized arc length, 0 to 1, back to t.
+//
+// Zero length stretches, like a degenerate segment, take up no arc length so they never get landed in. Arc length
+// right at one maps to the start of it, which is the same point.
+pub struct LengthParameterized<'a, T: Evaluate> {
+    curve: &'a T,
+    table: Vec<(f64, f64)>,
+}
+
+// The chord from a to b.
+fn chord<T: Evaluate>(curve: &T, a: f64, b: f64) -> f64
+{
+    return Vector::distance(curve.evaluate(a), curve.evaluate(b));
+}
+
+impl<'a, T: Evaluate> LengthParameterized<'a, T> {
+    pub fn new(curve: &'a T) -> Self
+    {
+        let breaks: Vec<f64> = (0..=INITIAL_PIECES).map(|i| i as f64 / INITIAL_PIECES as f64).collect();
+        return Self::from_breaks(curve, &breaks);
+    }
+
+    // Measures starting from the pieces between breaks, which should go from 0 to 1. Putting the curve's joints in
+    // breaks keeps us from ever measuring across one.
+    fn from_breaks(curve: &'a T, breaks: &[f64]) -> Self
+    {
+        let mut table = vec![(0., 0.)];
+        for pair in breaks.windows(2) {
+            Self::measure(curve, pair[0], pair[1], chord(curve, pair[0], pair[1]), 0, &mut table);
+        }
+
+        return LengthParameterized { curve: curve, table: table };
+    }
+
+    // Adds breakpoints from a to b, splitting in half until the two halves' chords add up to about the whole's.
+    fn measure(curve: &T, a: f64, b: f64, whole: f64, depth: usize, table: &mut Vec<(f64, f64)>)
+    {
+        let m = (a + b) / 2.;
+        let left = chord(curve, a, m);
+        let right = chord(curve, m, b);
+
+        if depth >= MAX_DEPTH || left + right - whole <= LENGTH_TOLERANCE * f64::max(left + right, 1e-9) {
+            let s = table.last().unwrap().1;
+            table.push((m, s + left));
+            table.push((b, s + left + right));
+            return;
+        }
+
+        Self::measure(curve, a, m, left, depth + 1, table);
+        Self::measure(curve, m, b, right, depth + 1, table);
+    }
+
+    pub fn total_length(&self) -> f64
+    {
+        return self.table.last().unwrap().1;
+    }
+
+    // The curve's t at normalized arc length u, u is clamped to 0 to 1.
+    pub fn t_at_length(&self, u: f64) -> f64
+    {
+        let total = self.total_length();
+        if total <= 0. { return 0.; }
+
+        let target = f64::min(f64::max(u, 0.), 1.) * total;
+
+        // the first breakpoint at or past target, a zero length stretch has all its breakpoints at the same length
+        // so this finds its start
+        let idx = self.table.partition_point(|(_, s)| *s < target);
+        if idx == 0 { return self.table[0].0; }
+        if idx >= self.table.len() { return self.table.last().unwrap().0; }
+
+        let (t0, s0) = self.table[idx - 1];
+        let (t1, s1) = self.table[idx];
+        if s1 - target <= 0. { return t1; }
+
+        // The table's pieces are short enough that the chord from the start of one is its arc length, so we find
+        // where the chord reaches the length we're missing.
+        let start = self.curve.evaluate(t0);
+        let wanted = target - s0;
+        let (mut lo, mut hi) = (t0, t1);
+        for _ in 0..INVERSE_STEPS {
+            let mid = (lo + hi) / 2.;
+            if Vector::distance(self.curve.evaluate(mid), start) < wanted { lo = mid; } else { hi = mid; }
+        }
+
+        return (lo + hi) / 2.;
+    }
+
+    pub fn evaluate_at_length(&self, u: f64) -> Vector
+    {
+        return self.curve.evaluate(self.t_at_length(u));
+    }
+
+    // The curve's t at distance s along it rather than normalized arc length, s is clamped to the curve. The _ds
+    // functions below all take distances like this.
+    pub fn t_at_distance(&self, s: f64) -> f64
+    {
+        let total = self.total_length();
+        if total <= 0. { return 0.; }
+
+        return self.t_at_length(s / total);
+    }
+
+    pub fn evaluate_ds(&self, s: f64) -> Vector
+    {
+        return self.curve.evaluate(self.t_at_distance(s));
+    }
+
+    // The derivative with respect to distance, which is the unit tangent: by the chain rule it's the derivative in t
+    // times dt/ds, and dt/ds is one over the speed. Where the speed vanishes, at a cusp or a handle sitting on its
+    // point, there's nothing to divide by and we fall back on tangent_at's direction of travel there instead.
+    pub fn tangent_ds(&self, s: f64) -> Vector
+    {
+        return self.curve.tangent_at(self.t_at_distance(s));
+    }
+
+    // Curvature per unit of distance, one over the radius of the circle that fits the curve at s. Curvature is a
+    // property of the shape and not of how fast t moves through it, so this is curvature at the t that s lands on.
+    // At a cusp the speed vanishes and curvature isn't defined, so we take it from just after s, or just before at the
+    // end of the curve, stepping out further until there's something to take. None if there's nothing nearby either.
+    pub fn curvature_ds(&self, s: f64) -> Option<f64>
+    {
+        if let Some(k) = self.curve.curvature(self.t_at_distance(s)) { return Some(k); }
+
+        let total = self.total_length();
+        for step in CUSP_STEPS.iter() {
+            for nearby in [s + step * total, s - step * total].iter() {
+                if *nearby < 0. || *nearby > total { continue; }
+                if let Some(k) = self.curve.curvature(self.t_at_distance(*nearby)) { return Some(k); }
+            }
+        }
+
+        return None;
+    }
+}
+
+impl<'a, T: Evaluate> Parameterization for LengthParameterized<'a, T>
+{
+    fn parameterize(&self, u: f64) -> f64
+    {
+        return self.t_at_length(u);
+    }
+}
+
+impl Bezier {
+    pub fn length_parameterized(&self) -> LengthParameterized<'_, Self>
+    {
+        return LengthParameterized::new(self);
+    }
+}
+
+impl<T: Evaluate> Piecewise<T>
+{
+    // Arc length parameterization that knows where our joints are. Each curve is measured on its own, so the
+    // table never straddles a corner and a zero length curve adds nothing.
+    pub fn length_parameterized(&self) -> LengthParameterized<'_, Self>
+    {
+        let n = self.curves.len();
+        if n == 0 { return LengthParameterized { curve: self, table: vec![(0., 0.)] }; }
+
+        // a few pieces per curve so an s shaped curve can't hide its length by ending up where a chord says
+        let per_curve = 4;
+        let breaks: Vec<f64> = (0..=n * per_curve).map(|i| i as f64 / (n * per_curve) as f64).collect();
+        return LengthParameterized::from_breaks(self, &breaks);
+    }
+}
@@ -0,0 +1,128 @@
+use super::*;
+
+// Where curves cross a straight line, for guides and scanlines. Along the line's normal the curve's distance from it
+// is a cubic in t, so the crossings are just that cubic's roots. roots.rs drops down a degree when the leading
+// coefficients vanish, which is what lines and quadratics stored as cubics need, and merges a double root into one
+// so a curve that only touches the line shows up once.
+
+// crossings closer together than this in global t are the same one, a joint is found from the curves either side
+const SAME_CROSSING: f64 = 1e-9;
+// how far past the ends of a segment a crossing can be and still count, it's as close as rounding lets us get
+const SEGMENT_SLOP: f64 = 1e-9;
+
+impl Bezier {
+    // The ts where the curve meets the infinite line through p going along d, sorted. Nothing if d is zero.
+    pub fn line_intersections(&self, p: Vector, d: Vector) -> Vec<f64>
+    {
+        let n = d.perpendicular();
+        if n.length_squared() == 0. { return Vec::new(); }
+
+        let a = n.x * self.A + n.y * self.E;
+        let b = n.x * self.B + n.y * self.F;
+        let c = n.x * self.C + n.y * self.G;
+        let e = n.x * (self.D - p.x) + n.y * (self.H - p.y);
+
+        return roots::solve_cubic(a, b, c, e, 0., 1.);
+    }
+}
+
+impl Piecewise<Bezier>
+{
+    // Every place the contour meets the infinite line through p going along d, as global t and the point there,
+    // sorted by t. A crossing on a joint is only reported once, and so is one on a closed contour's start.
+    pub fn line_intersections(&self, p: Vector, d: Vector) -> Vec<(f64, Vector)>
+    {
+        let mut output: Vec<(f64, Vector)> = Vec::new();
+
+        for (i, bez) in self.curves.iter().enumerate() {
+            for t in bez.line_intersections(p, d) {
+                let global = self.global_t(i, t);
+                if output.last().map_or(false, |last| global - last.0 <= SAME_CROSSING) { continue; }
+                output.push((global, bez.evaluate(t)));
+            }
+        }
+
+        if output.len() > 1 && self.is_closed() && output[0].0 <= SAME_CROSSING && output.last().unwrap().0 >= 1. - SAME_CROSSING {
+            output.pop();
+        }
+
+        return output;
+    }
+
+    // line_intersections restricted to the segment from a to b.
+    pub fn segment_intersections(&self, a: Vector, b: Vector) -> Vec<(f64, Vector)>
+    {
+        let d = b + -a;
+        let length_squared = d.length_squared();
+        if length_squared == 0. { return Vec::new(); }
+
+        return self.line_intersections(a, d).into_iter()
+            .filter(|(_, point)| {
+                let s = (*point + -a).dot(d) / length_squared;
+                s >= -SEGMENT_SLOP && s <= 1. + SEGMENT_SLOP
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(x: f64, y: f64) -> Vector
+    {
+        return Vector { x: x, y: y };
+    }
+
+    fn points(found: &[(f64, Vector)]) -> Vec<Vector>
+    {
+        return found.iter().map(|(_, p)| *p).collect();
+    }
+
+    // A square is made of lines, which only get through the solver by dropping degrees. Going through a corner
+    // is on a joint, and the first of those is the closed contour's start too.
+    #[test]
+    fn square_crossings_and_corners()
+    {
+        let square = Piecewise::rect(Rect { left: -50., bottom: -50., right: 50., top: 50. }).curves.remove(0);
+
+        let across = square.line_intersections(v(0., 10.), v(1., 0.));
+        assert_eq!(across.len(), 2, "{:?}", across);
+        assert!(across.iter().all(|(t, p)| square.evaluate(*t).is_near(*p, 1e-9)));
+        let mut xs: Vec<f64> = points(&across).iter().map(|p| p.x).collect();
+        xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert!((xs[0] + 50.).abs() < 1e-9 && (xs[1] - 50.).abs() < 1e-9);
+
+        // the diagonal goes through two corners and each is reported once
+        let diagonal = square.line_intersections(v(0., 0.), v(1., 1.));
+        assert_eq!(diagonal.len(), 2, "{:?}", diagonal);
+        let corners = points(&diagonal);
+        assert!(corners.iter().any(|p| p.is_near(v(-50., -50.), 1e-9)) && corners.iter().any(|p| p.is_near(v(50., 50.), 1e-9)));
+
+        assert!(square.line_intersections(v(0., 100.), v(1., 0.)).is_empty());
+        assert!(square.line_intersections(v(0., 0.), v(0., 0.)).is_empty());
+    }
+
+    // A circle's top has a horizontal tangent right on a joint, a line along it touches once. The segment variant
+    // only keeps what's between its ends.
+    #[test]
+    fn touches_and_segments()
+    {
+        let circle = Piecewise::circle(v(0., 0.), 100.);
+        let touch = circle.line_intersections(v(-500., 100.), v(1., 0.));
+        assert_eq!(touch.len(), 1, "{:?}", touch);
+        assert!(touch[0].1.is_near(v(0., 100.), 1e-6));
+
+        // and one sitting in the middle of a curve is a double root
+        let arch = Piecewise::new(vec![Bezier::from_control_points(v(0., 0.), v(0., 40.), v(100., 40.), v(100., 0.))]);
+        let top = arch.line_intersections(v(0., 30.), v(1., 0.));
+        assert_eq!(top.len(), 1, "{:?}", top);
+        assert!(top[0].1.is_near(v(50., 30.), 1e-6));
+
+        let right_half = circle.segment_intersections(v(0., 0.), v(200., 0.));
+        assert_eq!(right_half.len(), 1);
+        assert!(right_half[0].1.is_near(v(100., 0.), 1e-9));
+        assert_eq!(circle.segment_intersections(v(0., 0.), v(50., 0.)).len(), 0);
+        assert_eq!(circle.segment_intersections(v(-200., 0.), v(200., 0.)).len(), 2);
+    }
+}
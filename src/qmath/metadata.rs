@@ -0,0 +1,44 @@
+use super::*;
+
+// to_contour and to_outline only know about geometry, so point names and whatever data the points carried are lost
+// going through a Piecewise. These take the contour or outline the Piecewise was read from and put its names and data
+// back on the points written out. That only makes sense when every point still lines up with the one it came from,
+// so a contour whose point count changed along the way comes back without any.
+
+fn with_metadata<U: Clone>(point: glifparser::Point<Option<PointData>>, original: Option<&glifparser::Point<U>>) -> glifparser::Point<U>
+{
+    return glifparser::Point {
+        x: point.x,
+        y: point.y,
+        a: point.a,
+        b: point.b,
+        ptype: point.ptype,
+        name: original.and_then(|o| o.name.clone()),
+        data: original.and_then(|o| o.data.clone()),
+    };
+}
+
+impl Piecewise<Bezier>
+{
+    pub fn to_contour_preserving<U: Clone>(&self, original: &Contour<U>) -> Contour<U>
+    {
+        let output = self.to_contour();
+        let matches = output.len() == original.len();
+
+        return output.into_iter().enumerate()
+            .map(|(i, point)| with_metadata(point, if matches { original.get(i) } else { None }))
+            .collect();
+    }
+}
+
+impl Piecewise<Piecewise<Bezier>>
+{
+    // Contours are matched up in order, any past the end of original come back without metadata.
+    pub fn to_outline_preserving<U: Clone>(&self, original: &Outline<U>) -> Outline<U>
+    {
+        let empty = Vec::new();
+        return self.curves.iter().enumerate()
+            .map(|(i, contour)| contour.to_contour_preserving(original.get(i).unwrap_or(&empty)))
+            .collect();
+    }
+}
@@ -0,0 +1,45 @@
+use super::*;
+
+// Mirroring for building symmetric glyphs, like drawing half an A and flipping it over for the other half. A mirror
+// turns every contour inside out, so these reverse them afterwards and outers stay counter-clockwise and holes
+// clockwise. Going through apply_transform with a mirror yourself doesn't, and gets you a glyph that fills wrong.
+
+impl Piecewise<Bezier>
+{
+    // Flips x across the vertical line x = axis.
+    pub fn mirror_x(&self, axis: f64) -> Self
+    {
+        return self.mirror_about_line(Vector { x: axis, y: 0. }, Vector { x: 0., y: 1. });
+    }
+
+    // Flips y across the horizontal line y = axis.
+    pub fn mirror_y(&self, axis: f64) -> Self
+    {
+        return self.mirror_about_line(Vector { x: 0., y: axis }, Vector { x: 1., y: 0. });
+    }
+
+    // Reflects across the line through p going along d, the contour comes back as it was if d is zero.
+    pub fn mirror_about_line(&self, p: Vector, d: Vector) -> Self
+    {
+        if d.try_normalize().is_none() { return Piecewise { curves: self.curves.clone() }; }
+        return self.transform(&Affine::mirror_about_line(p, d)).reverse();
+    }
+}
+
+impl Piecewise<Piecewise<Bezier>>
+{
+    pub fn mirror_x(&self, axis: f64) -> Self
+    {
+        return Piecewise { curves: self.curves.iter().map(|contour| contour.mirror_x(axis)).collect() };
+    }
+
+    pub fn mirror_y(&self, axis: f64) -> Self
+    {
+        return Piecewise { curves: self.curves.iter().map(|contour| contour.mirror_y(axis)).collect() };
+    }
+
+    pub fn mirror_about_line(&self, p: Vector, d: Vector) -> Self
+    {
+        return Piecewise { curves: self.curves.iter().map(|contour| contour.mirror_about_line(p, d)).collect() };
+    }
+}
@@ -0,0 +1,147 @@
+use super::*;
+
+// Cutting curves up so x and y only ever go one way along each piece. Sweep lines, scanline filling and bounds from
+// the ends all get a lot simpler when they can count on that.
+//
+// Cutting at an extremum in floating point leaves the handles next to the cut a hair off the tangent they should
+// be on, enough for the piece to turn back for a moment right at its end. We put them back on it, which only moves
+// them by that same hair. An extremum too close to an end to cut at gets the end's handle lined up the same way
+// instead of making a sliver.
+
+// extrema closer than this to an end, or to each other, aren't worth a cut of their own
+const MONOTONE_EPSILON: f64 = 1e-6;
+
+// Where a curve turns around, with whether it's x, y or both doing it there.
+struct Turn {
+    t: f64,
+    x: bool,
+    y: bool,
+}
+
+fn turns(bez: &Bezier) -> Vec<Turn>
+{
+    let mut output: Vec<Turn> = roots::solve_quadratic(3. * bez.A, 2. * bez.B, bez.C, 0., 1.).into_iter()
+        .map(|t| Turn { t: t, x: true, y: false })
+        .chain(roots::solve_quadratic(3. * bez.E, 2. * bez.F, bez.G, 0., 1.).into_iter().map(|t| Turn { t: t, x: false, y: true }))
+        .filter(|turn| turn.t.is_finite() && turn.t >= 0. && turn.t <= 1.)
+        .collect();
+    output.sort_by(|a, b| a.t.partial_cmp(&b.t).unwrap());
+
+    let mut merged: Vec<Turn> = Vec::new();
+    for turn in output {
+        match merged.last_mut() {
+            Some(last) if turn.t - last.t < MONOTONE_EPSILON => {
+                last.x |= turn.x;
+                last.y |= turn.y;
+            }
+            _ => merged.push(turn),
+        }
+    }
+
+    return merged;
+}
+
+// Lines the handle up with the point it hangs off in whichever of x and y turn around there.
+fn line_up(handle: &mut Vector, point: Vector, x: bool, y: bool)
+{
+    if x { handle.x = point.x; }
+    if y { handle.y = point.y; }
+}
+
+impl Bezier {
+    // Splits the curve at its extrema so x and y are each monotone on every piece. The pieces share their ends
+    // exactly and evaluate to the curve everywhere but the handles either side of a cut, which get nudged onto the
+    // tangent there. A curve that's already monotone comes back as itself.
+    pub fn split_at_extrema(&self) -> Vec<Bezier>
+    {
+        let turns = turns(self);
+        if turns.is_empty() { return vec![self.clone()]; }
+
+        let (start, inner): (Vec<&Turn>, Vec<&Turn>) = turns.iter().partition(|turn| turn.t < MONOTONE_EPSILON);
+        let (inner, end): (Vec<&Turn>, Vec<&Turn>) = inner.into_iter().partition(|turn| turn.t <= 1. - MONOTONE_EPSILON);
+
+        let cuts: Vec<f64> = inner.iter().map(|turn| turn.t).collect();
+        let mut pieces: Vec<[Vector; 4]> = self.split_at_multiple(&cuts).iter().map(|bez| bez.to_control_points()).collect();
+
+        let last = pieces.len() - 1;
+        for turn in start {
+            let point = pieces[0][0];
+            line_up(&mut pieces[0][1], point, turn.x, turn.y);
+        }
+        for turn in end {
+            let point = pieces[last][3];
+            line_up(&mut pieces[last][2], point, turn.x, turn.y);
+        }
+        for (k, turn) in inner.iter().enumerate() {
+            let joint = pieces[k][3];
+            line_up(&mut pieces[k][2], joint, turn.x, turn.y);
+            line_up(&mut pieces[k + 1][1], joint, turn.x, turn.y);
+        }
+
+        return pieces.iter().map(|p| Bezier::from_control_points(p[0], p[1], p[2], p[3])).collect();
+    }
+
+    // The ts split_at_extrema cuts at, one fewer than the pieces it gives back.
+    pub(super) fn extrema_cuts(&self) -> Vec<f64>
+    {
+        return turns(self).iter().map(|turn| turn.t).filter(|t| *t >= MONOTONE_EPSILON && *t <= 1. - MONOTONE_EPSILON).collect();
+    }
+
+    // Like split_at_extrema but for adding points a person will see, so an extremum within tolerance of either end,
+    // or of the last one we cut at, is left alone rather than making a sliver. Nothing is done to the ends' handles,
+    // they belong to the joints that were already there.
+    fn split_at_extrema_apart(&self, tolerance: f64) -> Vec<Bezier>
+    {
+        let p = self.to_control_points();
+        let mut inner: Vec<(Turn, Vector)> = Vec::new();
+        for turn in turns(self) {
+            if turn.t < MONOTONE_EPSILON || turn.t > 1. - MONOTONE_EPSILON { continue; }
+
+            let point = self.evaluate(turn.t);
+            if point.distance(p[0]) <= tolerance || point.distance(p[3]) <= tolerance { continue; }
+            if inner.last().map_or(false, |(_, last)| point.distance(*last) <= tolerance) { continue; }
+            inner.push((turn, point));
+        }
+        if inner.is_empty() { return vec![self.clone()]; }
+
+        let cuts: Vec<f64> = inner.iter().map(|(turn, _)| turn.t).collect();
+        let mut pieces: Vec<[Vector; 4]> = self.split_at_multiple(&cuts).iter().map(|bez| bez.to_control_points()).collect();
+        for (k, (turn, _)) in inner.iter().enumerate() {
+            let joint = pieces[k][3];
+            line_up(&mut pieces[k][2], joint, turn.x, turn.y);
+            line_up(&mut pieces[k + 1][1], joint, turn.x, turn.y);
+        }
+
+        return pieces.iter().map(|p| Bezier::from_control_points(p[0], p[1], p[2], p[3])).collect();
+    }
+}
+
+impl Piecewise<Bezier>
+{
+    // Every curve split at its extrema, see Bezier::split_at_extrema.
+    pub fn to_monotone(&self) -> Self
+    {
+        return Piecewise { curves: self.curves.iter().flat_map(|bez| bez.split_at_extrema()).collect() };
+    }
+
+    // Puts an on-curve point at every x and y extremum that hasn't got one, the way fonts like them. Extrema within
+    // tolerance of a point that's already there are skipped. Where a point goes its handles are lined up flat with
+    // it so the extremum lands on it exactly.
+    pub fn insert_extrema_points(&self, tolerance: f64) -> Self
+    {
+        return Piecewise { curves: self.curves.iter().flat_map(|bez| bez.split_at_extrema_apart(tolerance)).collect() };
+    }
+}
+
+impl Piecewise<Piecewise<Bezier>>
+{
+    pub fn to_monotone(&self) -> Self
+    {
+        return Piecewise { curves: self.curves.iter().map(|contour| contour.to_monotone()).collect() };
+    }
+
+    pub fn insert_extrema_points(&self, tolerance: f64) -> Self
+    {
+        return Piecewise { curves: self.curves.iter().map(|contour| contour.insert_extrema_points(tolerance)).collect() };
+    }
+}
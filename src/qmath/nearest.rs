@@ -0,0 +1,199 @@
+use super::*;
+
+// how many evenly spaced samples we check before refining
+const NEAREST_SAMPLES: usize = 32;
+// Newton steps per candidate, each one that doesn't get us closer is halved until it does
+const NEWTON_STEPS: usize = 8;
+const MAX_HALVINGS: usize = 16;
+// golden section steps narrowing in on a dip before Newton, each keeps 0.618 of the bracket
+const BRACKET_STEPS: usize = 40;
+
+impl Bezier {
+    // Newton's method on the derivative of the squared distance starting from t, which is the dot product of the
+    // curve's derivative with the vector from point to the curve. Steps are clamped to the curve and shortened
+    // whenever they'd take us farther away, so this can only ever improve on where it started.
+    fn refine_nearest(&self, point: Vector, mut t: f64) -> (f64, f64)
+    {
+        let mut distance = self.evaluate(t).distance(point);
+
+        for _ in 0..NEWTON_STEPS {
+            let p = self.evaluate(t);
+            let d1 = Vector { x: 3. * self.A * t * t + 2. * self.B * t + self.C, y: 3. * self.E * t * t + 2. * self.F * t + self.G };
+            let d2 = Vector { x: 6. * self.A * t + 2. * self.B, y: 6. * self.E * t + 2. * self.F };
+            let diff = p + -point;
+
+            let numerator = diff.x * d1.x + diff.y * d1.y;
+            let denominator = d1.x * d1.x + d1.y * d1.y + diff.x * d2.x + diff.y * d2.y;
+            if denominator.abs() < 1e-12 { break; }
+
+            let mut step = numerator / denominator;
+            let mut improved = false;
+            for _ in 0..MAX_HALVINGS {
+                let next = f64::clamp(t - step, 0., 1.);
+                let next_distance = self.evaluate(next).distance(point);
+                if next_distance < distance {
+                    t = next;
+                    distance = next_distance;
+                    improved = true;
+                    break;
+                }
+                step = step / 2.;
+            }
+
+            if !improved { break; }
+        }
+
+        return (t, distance);
+    }
+
+    // Golden section search for the closest point between the samples either side of sample i. Newton alone
+    // gets stuck where the curve stands still, like the ends of our lines with their handles on their points,
+    // since the dot product it's chasing is zero there whichever way the closest point is.
+    fn narrow_dip(&self, point: Vector, i: usize) -> f64
+    {
+        let ratio = (5f64.sqrt() - 1.) / 2.;
+        let distance = |t: f64| self.evaluate(t).distance(point);
+
+        let mut low = i.saturating_sub(1) as f64 / NEAREST_SAMPLES as f64;
+        let mut high = usize::min(i + 1, NEAREST_SAMPLES) as f64 / NEAREST_SAMPLES as f64;
+        for _ in 0..BRACKET_STEPS {
+            let a = high - (high - low) * ratio;
+            let b = low + (high - low) * ratio;
+            if distance(a) < distance(b) { high = b; } else { low = a; }
+        }
+
+        // the bracket might hold more than one dip, so never come back with worse than the sample we started at
+        let sample = i as f64 / NEAREST_SAMPLES as f64;
+        let narrowed = (low + high) / 2.;
+        return if distance(narrowed) < distance(sample) { narrowed } else { sample };
+    }
+
+    // The closest point on the curve to point, as its t, the point itself, and how far away it is. We sample the
+    // curve and refine every sample that's closer than both its neighbours, since the closest sample isn't always
+    // in the same dip as the closest point, and then the ends are checked on their own. Anything past an end of
+    // the curve projects onto that end.
+    pub fn project(&self, point: Vector) -> (f64, Vector, f64)
+    {
+        let distances: Vec<f64> = (0..=NEAREST_SAMPLES)
+            .map(|i| self.evaluate(i as f64 / NEAREST_SAMPLES as f64).distance(point))
+            .collect();
+
+        let mut best = (0., distances[0]);
+        if distances[NEAREST_SAMPLES] < best.1 { best = (1., distances[NEAREST_SAMPLES]); }
+
+        for i in 0..=NEAREST_SAMPLES {
+            let before = if i > 0 { distances[i - 1] } else { f64::INFINITY };
+            let after = if i < NEAREST_SAMPLES { distances[i + 1] } else { f64::INFINITY };
+            if distances[i] > before || distances[i] > after { continue; }
+
+            let candidate = self.refine_nearest(point, self.narrow_dip(point, i));
+            if candidate.1 < best.1 { best = candidate; }
+        }
+
+        return (best.0, self.evaluate(best.0), best.1);
+    }
+
+    // The t of the point on the curve closest to point, and how far away it is.
+    pub fn nearest(&self, point: Vector) -> (f64, f64)
+    {
+        let (t, _, distance) = self.project(point);
+        return (t, distance);
+    }
+}
+
+impl Piecewise<Bezier>
+{
+    // The index of the closest curve, the t on that curve and the distance. None for an empty piecewise.
+    pub fn nearest(&self, point: Vector) -> Option<(usize, f64, f64)>
+    {
+        let mut best: Option<(usize, f64, f64)> = None;
+
+        for (i, bez) in self.curves.iter().enumerate() {
+            let (t, distance) = bez.nearest(point);
+            if best.map_or(true, |b| distance < b.2) {
+                best = Some((i, t, distance));
+            }
+        }
+
+        return best;
+    }
+
+    // Like Bezier's project but with the contour's global t, the one evaluate takes. None for an empty piecewise.
+    pub fn project(&self, point: Vector) -> Option<(f64, Vector, f64)>
+    {
+        let (segment, t, distance) = self.nearest(point)?;
+        return Some((self.global_t(segment, t), self.curves[segment].evaluate(t), distance));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(x: f64, y: f64) -> Vector
+    {
+        return Vector { x: x, y: y };
+    }
+
+    fn arch() -> Bezier
+    {
+        return Bezier::from_control_points(v(0., 0.), v(0., 150.), v(200., 150.), v(200., 0.));
+    }
+
+    // Points all around the arch, inside it, past its ends and under the middle of it where two dips are about as
+    // close as each other, agree with a brute force scan.
+    #[test]
+    fn matches_brute_force()
+    {
+        let bez = arch();
+        let scan: Vec<(f64, Vector)> = (0..=20000).map(|i| (i as f64 / 20000., bez.evaluate(i as f64 / 20000.))).collect();
+
+        for x in (-100..=300).step_by(25) {
+            for y in (-100..=250).step_by(25) {
+                let point = v(x as f64, y as f64);
+                let (t, closest, distance) = bez.project(point);
+                let (brute_t, brute) = scan.iter()
+                    .fold((0., f64::INFINITY), |best, (t, p)| if p.distance(point) < best.1 { (*t, p.distance(point)) } else { best });
+
+                assert!(closest.is_near(bez.evaluate(t), 1e-12));
+                assert!((closest.distance(point) - distance).abs() < 1e-9);
+                assert!(distance <= brute + 1e-9, "{:?} projects {} away, the scan found {}", point, distance, brute);
+                // the scan is fine enough that it's never far behind either, and the t agrees unless two dips tie
+                assert!(brute - distance < 1e-4, "{:?}", point);
+                assert!((t - brute_t).abs() < 1e-3 || (t - (1. - brute_t)).abs() < 1e-3, "{:?}", point);
+            }
+        }
+    }
+
+    #[test]
+    fn past_the_ends_and_global_t()
+    {
+        let bez = arch();
+        assert_eq!(bez.project(v(-30., -40.)), (0., v(0., 0.), 50.));
+        assert_eq!(bez.project(v(230., -40.)), (1., v(200., 0.), 50.));
+
+        let path = Piecewise::new(vec![
+            Bezier::from_control_points(v(-100., 0.), v(-100., 0.), v(0., 0.), v(0., 0.)),
+            bez.clone(),
+        ]);
+        let (t, closest, distance) = path.project(v(100., 150.)).unwrap();
+        assert!((t - 0.75).abs() < 1e-9);
+        assert!(closest.is_near(v(100., 112.5), 1e-9) && (distance - 37.5).abs() < 1e-9);
+        assert!(path.evaluate(t).is_near(closest, 1e-9));
+
+        assert!(Piecewise::<Bezier>::new(vec![]).project(v(0., 0.)).is_none());
+    }
+
+    // A line with its handles on its points stands still at its ends, points just along from an end used to project
+    // onto the end itself.
+    #[test]
+    fn lines_that_stand_still_at_their_ends()
+    {
+        let line = Bezier::from_control_points(v(0., 0.), v(0., 0.), v(100., 0.), v(100., 0.));
+        for k in 0..=1000 {
+            let x = k as f64 / 10.;
+            let (_, closest, distance) = line.project(v(x, 5.));
+            assert!(closest.is_near(v(x, 0.), 1e-6) && (distance - 5.).abs() < 1e-9, "{} projects to {:?}", x, closest);
+        }
+    }
+}
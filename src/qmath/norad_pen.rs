@@ -0,0 +1,99 @@
+use super::*;
+
+// Adapters between our pens and norad's contours, so outlines can be moved between a norad::Glyph and the
+// math library without round tripping through glifparser.
+fn segment_type(typ: &norad::PointType) -> Option<SegmentType>
+{
+    match typ {
+        norad::PointType::Move => Some(SegmentType::Move),
+        norad::PointType::Line => Some(SegmentType::Line),
+        norad::PointType::Curve => Some(SegmentType::Curve),
+        norad::PointType::QCurve => Some(SegmentType::QCurve),
+        norad::PointType::OffCurve => None,
+    }
+}
+
+fn point_type(segment_type: Option<SegmentType>) -> norad::PointType
+{
+    match segment_type {
+        Some(SegmentType::Move) => norad::PointType::Move,
+        Some(SegmentType::Line) => norad::PointType::Line,
+        Some(SegmentType::Curve) => norad::PointType::Curve,
+        Some(SegmentType::QCurve) => norad::PointType::QCurve,
+        None => norad::PointType::OffCurve,
+    }
+}
+
+pub fn draw_norad_contour(contour: &norad::Contour, pen: &mut impl PointPen)
+{
+    pen.begin_path();
+
+    for point in &contour.points {
+        pen.add_point(Vector { x: point.x as f64, y: point.y as f64 }, segment_type(&point.typ));
+    }
+
+    pen.end_path();
+}
+
+// A PointPen that collects norad contours.
+pub struct NoradPointPen {
+    contours: Vec<norad::Contour>,
+    points: Vec<norad::ContourPoint>,
+}
+
+impl NoradPointPen {
+    pub fn new() -> Self
+    {
+        return NoradPointPen {
+            contours: Vec::new(),
+            points: Vec::new(),
+        };
+    }
+
+    pub fn into_contours(self) -> Vec<norad::Contour>
+    {
+        return self.contours;
+    }
+}
+
+impl PointPen for NoradPointPen {
+    fn begin_path(&mut self)
+    {
+        self.points.clear();
+    }
+
+    fn add_point(&mut self, point: Vector, segment_type: Option<SegmentType>)
+    {
+        self.points.push(norad::ContourPoint::new(point.x as f32, point.y as f32, point_type(segment_type), false, None, None, None));
+    }
+
+    fn end_path(&mut self)
+    {
+        let points = std::mem::replace(&mut self.points, Vec::new());
+        self.contours.push(norad::Contour::new(points, None, None));
+    }
+}
+
+impl Piecewise<Piecewise<Bezier>>
+{
+    pub fn from_norad_contours(contours: &[norad::Contour]) -> Self
+    {
+        let mut builder = PiecewiseBuilder::new();
+
+        {
+            let mut pen = PointToContourPen::new(&mut builder);
+            for contour in contours {
+                draw_norad_contour(contour, &mut pen);
+            }
+        }
+
+        return builder.build();
+    }
+
+    pub fn to_norad_contours(&self) -> Vec<norad::Contour>
+    {
+        let mut pen = NoradPointPen::new();
+        self.draw_points(&mut pen);
+        return pen.into_contours();
+    }
+}
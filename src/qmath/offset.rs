@@ -0,0 +1,189 @@
+use super::*;
+
+// past this many halvings the piece is tiny and we take whatever fit we've got
+const MAX_SPLIT_DEPTH: usize = 10;
+// how many points inside each piece we check the fit against
+const ERROR_SAMPLES: usize = 16;
+// shorter than this and a handle or a derivative doesn't tell us which way the curve is going
+const DEGENERATE_LENGTH: f64 = 1e-9;
+
+fn velocity(bez: &Bezier, t: f64) -> Vector
+{
+    return Vector {
+        x: 3. * bez.A * t * t + 2. * bez.B * t + bez.C,
+        y: 3. * bez.E * t * t + 2. * bez.F * t + bez.G
+    };
+}
+
+// The direction of travel at the start and end of the curve. When a handle sits on its point the curve leaves in
+// the direction of the next control point that doesn't, and a curve with every point in the same place has none.
+fn end_tangents(bez: &Bezier) -> Option<(Vector, Vector)>
+{
+    let p = bez.to_control_points();
+
+    let start = [p[1], p[2], p[3]].iter()
+        .map(|q| *q + -p[0])
+        .find(|d| d.magnitude() > DEGENERATE_LENGTH)?;
+    let end = [p[2], p[1], p[0]].iter()
+        .map(|q| p[3] + -*q)
+        .find(|d| d.magnitude() > DEGENERATE_LENGTH)?;
+
+    return Some((start.normalize(), end.normalize()));
+}
+
+// The unit normal to the left of the direction of travel at t. At a cusp the derivative vanishes so we look at
+// which way the curve is heading a little to either side of it instead.
+fn normal_at(bez: &Bezier, t: f64) -> Vector
+{
+    let mut d = velocity(bez, t);
+    if d.magnitude() <= DEGENERATE_LENGTH {
+        let step = 1e-4;
+        d = bez.evaluate(f64::min(t + step, 1.)) + -bez.evaluate(f64::max(t - step, 0.));
+    }
+    if d.magnitude() <= DEGENERATE_LENGTH {
+        return Vector { x: 0., y: 0. };
+    }
+
+    return Orientation::YUp.left_normal(d);
+}
+
+// A cubic with the offset's ends that leaves and arrives in the same directions as the original. The handles get
+// stretched on the outside of a bend and shrunk on the inside by how much the radius of curvature changes, which
+// is exact for circular arcs. Where distance is past the radius the handle would flip around so it stops at zero.
+fn fit_piece(bez: &Bezier, distance: f64, tangents: (Vector, Vector)) -> Bezier
+{
+    let p = bez.to_control_points();
+    let (start_tangent, end_tangent) = tangents;
+
+    let start_normal = Orientation::YUp.left_normal(start_tangent);
+    let end_normal = Orientation::YUp.left_normal(end_tangent);
+
+    let start_factor = f64::max(1. - distance * bez.curvature(0.).unwrap_or(0.), 0.);
+    let end_factor = f64::max(1. - distance * bez.curvature(1.).unwrap_or(0.), 0.);
+
+    let start = p[0] + start_normal * distance;
+    let end = p[3] + end_normal * distance;
+    let start_handle = start + start_tangent * (p[1].distance(p[0]) * start_factor);
+    let end_handle = end + end_tangent * -(p[3].distance(p[2]) * end_factor);
+
+    return Bezier::from_control_points(start, start_handle, end_handle, end);
+}
+
+// The furthest any sampled point of the true offset is from the fitted curve.
+fn fit_error(bez: &Bezier, fitted: &Bezier, distance: f64) -> f64
+{
+    let mut error: f64 = 0.;
+    for i in 1..ERROR_SAMPLES {
+        let t = i as f64 / ERROR_SAMPLES as f64;
+        let target = bez.evaluate(t) + normal_at(bez, t) * distance;
+        let (_, _, d) = fitted.project(target);
+        if d.is_finite() { error = error.max(d); }
+    }
+
+    return error;
+}
+
+fn append_offset(bez: &Bezier, distance: f64, tolerance: f64, depth: usize, output: &mut Vec<Bezier>)
+{
+    // a piece that's all one point has no sides to offset to
+    let tangents = match end_tangents(bez) {
+        Some(tangents) => tangents,
+        None => return,
+    };
+
+    let fitted = fit_piece(bez, distance, tangents);
+    if depth >= MAX_SPLIT_DEPTH || fit_error(bez, &fitted, distance) <= tolerance {
+        output.push(fitted);
+        return;
+    }
+
+    let (first, second) = bez.subdivide(0.5);
+    append_offset(&first, distance, tolerance, depth + 1, output);
+    append_offset(&second, distance, tolerance, depth + 1, output);
+}
+
+// Joins the end of what we have to the start of next. Ends within tolerance of each other are snapped together,
+// anything further apart is a corner in the original and gets bridged with a straight line.
+fn connect(output: &mut Vec<Bezier>, next: Vec<Bezier>, tolerance: f64)
+{
+    connect_at(output, next, tolerance, true);
+}
+
+// connect, but where we know the original is smooth the ends are snapped together however far apart they are.
+fn connect_at(output: &mut Vec<Bezier>, next: Vec<Bezier>, tolerance: f64, corner: bool)
+{
+    let mut next = next.into_iter();
+    let first = match next.next() {
+        Some(first) => first,
+        None => return,
+    };
+
+    match output.last() {
+        Some(last) => {
+            let end = last.to_control_points()[3];
+            let p = first.to_control_points();
+            if !corner || end.distance(p[0]) <= tolerance {
+                output.push(Bezier::from_control_points(end, p[1], p[2], p[3]));
+            } else {
+                output.push(Bezier::from_control_points(end, end, p[0], p[0]));
+                output.push(first);
+            }
+        }
+        None => output.push(first),
+    }
+
+    for bez in next {
+        connect(output, vec![bez], tolerance);
+    }
+}
+
+impl Bezier {
+    // Approximates the curve running distance to its left, negative distances run to its right. The curve is cut at
+    // its extrema and inflections first so each piece bends one way by no more than a quarter turn, then each piece
+    // is fit with a cubic and halved until the fit is within tolerance of the true offset.
+    pub fn offset(&self, distance: f64, tolerance: f64) -> Vec<Bezier>
+    {
+        let mut cuts = self.extrema();
+        cuts.extend(self.inflections());
+
+        let mut output = Vec::new();
+        for piece in self.split_at_multiple(&cuts) {
+            let mut fitted = Vec::new();
+            append_offset(&piece, distance, tolerance, 0, &mut fitted);
+            connect(&mut output, fitted, tolerance);
+        }
+
+        return output;
+    }
+}
+
+impl Piecewise<Bezier>
+{
+    // The curve running distance to the left of this one, or to the right for a negative distance, within
+    // tolerance. The result is one connected contour: where the original has a corner, see find_corners, the two
+    // sides are joined by a straight line, and a closed contour comes back closed. Where distance is more than the
+    // radius of curvature on the inside of a bend the true offset has a cusp, we don't trim the loop that makes but
+    // we don't blow up either.
+    pub fn offset(&self, distance: f64, tolerance: f64) -> Piecewise<Bezier>
+    {
+        let corners = self.find_corners(CORNER_ANGLE);
+
+        let mut output = Vec::new();
+        for (i, bez) in self.curves.iter().enumerate() {
+            connect_at(&mut output, bez.offset(distance, tolerance), tolerance, corners.contains(&i));
+        }
+
+        if !output.is_empty() && self.is_closed() {
+            let start = output[0].to_control_points()[0];
+            let last = output.len() - 1;
+            let p = output[last].to_control_points();
+            if !corners.contains(&0) || p[3].distance(start) <= tolerance {
+                output[last] = Bezier::from_control_points(p[0], p[1], p[2], start);
+            } else {
+                output.push(Bezier::from_control_points(p[3], p[3], start, start));
+            }
+        }
+
+        return Piecewise { curves: output };
+    }
+}
@@ -0,0 +1,115 @@
+use super::*;
+
+// How far an offset strays from being exactly expected units away from its skeleton.
+#[derive(Debug, Clone, Copy)]
+pub struct OffsetErrorReport {
+    pub max_error: f64,
+    pub mean_error: f64,
+    // where on the offset max_error was measured
+    pub worst: Vector,
+    // samples that were actually measured, ones that belong to caps are skipped
+    pub samples: usize,
+}
+
+impl OffsetErrorReport {
+    // Folds another report into this one as if all the samples had been taken together.
+    pub fn merge(&mut self, other: &OffsetErrorReport)
+    {
+        let total = self.samples + other.samples;
+        if total == 0 { return; }
+
+        self.mean_error = (self.mean_error * self.samples as f64 + other.mean_error * other.samples as f64) / total as f64;
+        if other.max_error > self.max_error || self.samples == 0 {
+            self.max_error = other.max_error;
+            self.worst = other.worst;
+        }
+        self.samples = total;
+    }
+}
+
+// Samples the offset evenly and measures the true distance from each sample back to the skeleton with a nearest
+// point projection. Samples whose nearest point is an open end of the skeleton are round the end of the stroke
+// rather than beside it so they don't get counted.
+pub fn verify_offset(skeleton: &Piecewise<Bezier>, offset: &Piecewise<Bezier>, expected: f64, samples: usize) -> OffsetErrorReport
+{
+    let mut report = OffsetErrorReport { max_error: 0., mean_error: 0., worst: Vector { x: 0., y: 0. }, samples: 0 };
+    if skeleton.curves.is_empty() || offset.curves.is_empty() { return report; }
+
+    let open = !skeleton.is_closed();
+    let last = skeleton.curves.len() - 1;
+    let mut total = 0.;
+
+    for n in 0..samples {
+        let point = offset.evaluate(n as f64 / samples as f64);
+
+        let (index, t, distance) = match skeleton.nearest(point) {
+            Some(nearest) => nearest,
+            None => continue
+        };
+
+        if open && ((index == 0 && t <= 1e-6) || (index == last && t >= 1. - 1e-6)) { continue; }
+
+        let error = (distance - expected).abs();
+        total = total + error;
+        if error > report.max_error || report.samples == 0 {
+            report.max_error = error;
+            report.worst = point;
+        }
+        report.samples = report.samples + 1;
+    }
+
+    if report.samples > 0 { report.mean_error = total / report.samples as f64; }
+    return report;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stroke::{constant_width_stroke, StrokeCap, StrokeSettings};
+
+    fn v(x: f64, y: f64) -> Vector
+    {
+        return Vector { x: x, y: y };
+    }
+
+    // Both sides of a stroked S and circle are within the stroker's tolerance of half the width, and a circle 2 too
+    // far out is off by 2 everywhere.
+    #[test]
+    fn strokes_are_half_their_width_away()
+    {
+        let settings = StrokeSettings { width: 40., cap: StrokeCap::Round, ..StrokeSettings::default() };
+        let s = Piecewise::new(vec![Bezier::from_control_points(v(0., 0.), v(300., 0.), v(0., 300.), v(300., 300.))]);
+        let circle = Piecewise::circle(v(0., 0.), 100.);
+
+        for skeleton in [s.clone(), circle.clone()].iter() {
+            let stroke = constant_width_stroke(&Piecewise::new(vec![skeleton.clone()]), &settings);
+            for side in stroke.segs() {
+                let report = verify_offset(skeleton, side, 20., 500);
+                assert!(report.samples > 0);
+                assert!(report.max_error <= 2. * settings.tolerance, "{:?}", report);
+            }
+        }
+
+        let too_far = verify_offset(&circle, &Piecewise::circle(v(0., 0.), 122.), 20., 200);
+        assert_eq!(too_far.samples, 200);
+        assert!((too_far.max_error - 2.).abs() < 0.05 && (too_far.mean_error - 2.).abs() < 0.05, "{:?}", too_far);
+        assert!((too_far.worst.distance(v(0., 0.)) - 122.).abs() < 0.05);
+    }
+
+    // Round caps are nearest the ends of an open skeleton, those samples don't count.
+    #[test]
+    fn caps_are_skipped_and_reports_merge()
+    {
+        let line = Piecewise::new(vec![Bezier::from_control_points(v(0., 0.), v(0., 0.), v(200., 0.), v(200., 0.))]);
+        let settings = StrokeSettings { width: 40., cap: StrokeCap::Round, ..StrokeSettings::default() };
+        let stroke = constant_width_stroke(&Piecewise::new(vec![line.clone()]), &settings);
+
+        let report = verify_offset(&line, &stroke[0], 20., 1000);
+        assert!(report.samples > 0 && report.samples < 1000, "{:?}", report);
+        assert!(report.max_error <= 2. * settings.tolerance, "{:?}", report);
+
+        let mut merged = OffsetErrorReport { max_error: 1., mean_error: 1., worst: v(1., 1.), samples: 10 };
+        merged.merge(&OffsetErrorReport { max_error: 4., mean_error: 2.5, worst: v(4., 4.), samples: 30 });
+        assert_eq!((merged.max_error, merged.mean_error, merged.worst, merged.samples), (4., 2.125, v(4., 4.), 40));
+    }
+}
@@ -0,0 +1,53 @@
+use super::*;
+
+// Which way y points. Glyphs are y-up, SVG and skia are y-down, and anything that talks about left and right or
+// clockwise and counter-clockwise needs to know which it's looking at or it'll come out mirrored.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Orientation {
+    YUp,
+    YDown,
+}
+
+impl Default for Orientation {
+    fn default() -> Self { Orientation::YUp }
+}
+
+impl Orientation {
+    // The unit vector pointing to the left of someone walking along derivative, as seen on screen.
+    pub fn left_normal(&self, derivative: Vector) -> Vector
+    {
+        match self {
+            Orientation::YUp => Vector { x: -derivative.y, y: derivative.x }.normalize(),
+            Orientation::YDown => Vector { x: derivative.y, y: -derivative.x }.normalize(),
+        }
+    }
+
+    pub fn right_normal(&self, derivative: Vector) -> Vector
+    {
+        return -self.left_normal(derivative);
+    }
+
+    // signed_area is positive for counter-clockwise contours in y-up space, y-down sees the same numbers mirrored
+    pub fn is_counter_clockwise(&self, contour: &Piecewise<Bezier>) -> bool
+    {
+        match self {
+            Orientation::YUp => contour.signed_area() > 0.,
+            Orientation::YDown => contour.signed_area() < 0.,
+        }
+    }
+}
+
+impl Piecewise<Piecewise<Bezier>>
+{
+    // Moves an outline between conventions by mirroring y about the horizontal line y = axis. Mirrored coordinates
+    // read in the opposite convention look exactly like the original so contours keep their on screen direction,
+    // which is what the winding rules care about, and don't need reversing.
+    pub fn convert_orientation(&self, from: Orientation, to: Orientation, axis: f64) -> Self
+    {
+        if from == to {
+            return self.apply_transform(|v: &Vector| *v);
+        }
+
+        return self.apply_transform(|v: &Vector| Vector { x: v.x, y: 2. * axis - v.y });
+    }
+}
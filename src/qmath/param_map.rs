@@ -0,0 +1,150 @@
+use super::*;
+
+// Piecewise maps global t evenly across its curves, so cutting curves up changes what every global t points at.
+// A ParamMap remembers where each of the original curves was cut so parameters can be carried across the change
+// in either direction.
+pub struct ParamMap {
+    // for every original curve the local t values it was cut at, including 0 and 1 at the ends
+    cuts: Vec<Vec<f64>>,
+    // the index of the first new curve each original curve turned into
+    offsets: Vec<usize>,
+    new_len: usize,
+}
+
+// Splits a global t into a curve index and the t local to that curve, the same way Piecewise::evaluate does.
+fn split_global(t: f64, len: usize) -> (usize, f64)
+{
+    let modified_time = len as f64 * t;
+    let index = modified_time.floor().max(0.).min((len - 1) as f64) as usize;
+    return (index, modified_time - index as f64);
+}
+
+impl ParamMap {
+    // Each entry of cuts holds the local t values between 0 and 1 that the matching original curve was cut at, in
+    // increasing order. An empty entry means that curve was left alone. A cut right at an end makes a zero length
+    // curve just like subdivide does, the map skips over those.
+    pub fn from_cuts(cuts: Vec<Vec<f64>>) -> Self
+    {
+        let mut offsets = Vec::new();
+        let mut new_len = 0;
+        let mut full_cuts = Vec::new();
+
+        for curve_cuts in cuts {
+            offsets.push(new_len);
+            new_len = new_len + curve_cuts.len() + 1;
+
+            let mut full = vec![0.];
+            full.extend(curve_cuts);
+            full.push(1.);
+            full_cuts.push(full);
+        }
+
+        return ParamMap { cuts: full_cuts, offsets: offsets, new_len: new_len };
+    }
+
+    pub fn identity(len: usize) -> Self
+    {
+        return Self::from_cuts(vec![Vec::new(); len]);
+    }
+
+    // Maps a global t on the original piecewise to the global t of the same point on the cut up one.
+    pub fn to_new(&self, t: f64) -> f64
+    {
+        if self.cuts.is_empty() { return t; }
+
+        let (index, local) = split_global(t, self.cuts.len());
+        let cuts = &self.cuts[index];
+
+        // find the piece that holds local, we skip over zero width pieces from cuts that landed on each other
+        let mut piece = cuts.len() - 2;
+        for i in 0..cuts.len() - 1 {
+            if local < cuts[i + 1] && cuts[i + 1] > cuts[i] {
+                piece = i;
+                break;
+            }
+        }
+
+        let width = cuts[piece + 1] - cuts[piece];
+        let new_local = if width > 0. { (local - cuts[piece]) / width } else { 0. };
+
+        return (self.offsets[index] + piece) as f64 / self.new_len as f64 + new_local / self.new_len as f64;
+    }
+
+    // Maps a global t on the cut up piecewise back to the original.
+    pub fn to_old(&self, t: f64) -> f64
+    {
+        if self.cuts.is_empty() { return t; }
+
+        let (new_index, new_local) = split_global(t, self.new_len);
+
+        // the last original curve whose first new curve is at or before new_index is the one it came from
+        let index = match self.offsets.binary_search(&new_index) {
+            Ok(i) => i,
+            Err(i) => i - 1,
+        };
+        let piece = new_index - self.offsets[index];
+        let cuts = &self.cuts[index];
+
+        let local = cuts[piece] + new_local * (cuts[piece + 1] - cuts[piece]);
+
+        return (index as f64 + local) / self.cuts.len() as f64;
+    }
+}
+
+impl Piecewise<Bezier>
+{
+    // subdivide, but also hands back the map from our parameters to the new piecewise's
+    pub fn subdivide_tracked(&self, t: f64) -> (Piecewise<Bezier>, ParamMap)
+    {
+        let map = ParamMap::from_cuts(vec![vec![f64::clamp(t, 0., 1.)]; self.curves.len()]);
+        return (self.subdivide(t), map);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::vector_strategy;
+    use proptest::prelude::*;
+
+    fn curves(points: &[Vector]) -> Piecewise<Bezier>
+    {
+        return Piecewise::new(points.windows(4).step_by(3).map(|p| Bezier::from_control_points(p[0], p[1], p[2], p[3])).collect());
+    }
+
+    fn assert_maps(old: &Piecewise<Bezier>, new: &Piecewise<Bezier>, map: &ParamMap, t: f64)
+    {
+        let mapped = map.to_new(t);
+        assert!(old.evaluate(t).is_near(new.evaluate(mapped), 1e-9), "{} went to {}", t, mapped);
+        assert!((map.to_old(mapped) - t).abs() < 1e-12, "{} came back as {}", t, map.to_old(mapped));
+    }
+
+    proptest! {
+        #[test]
+        fn mapped_parameters_land_on_the_same_point(points in prop::collection::vec(vector_strategy(), 10), split in 0.0f64..1.0, t in 0.0f64..1.0)
+        {
+            let old = curves(&points);
+            let (new, map) = old.subdivide_tracked(split);
+            assert_maps(&old, &new, &map, t);
+        }
+    }
+
+    // Cuts at the ends leave zero length curves behind, and the ends of the whole piecewise have to stay put.
+    #[test]
+    fn cuts_at_the_ends()
+    {
+        let old = curves(&[Vector { x: 0., y: 0. }, Vector { x: 0., y: 100. }, Vector { x: 100., y: 100. }, Vector { x: 100., y: 0. },
+            Vector { x: 200., y: 50. }, Vector { x: 300., y: 50. }, Vector { x: 300., y: 200. }]);
+
+        for split in [0., 1.].iter() {
+            let (new, map) = old.subdivide_tracked(*split);
+            assert_eq!(new.len(), 4);
+            for i in 0..=20 {
+                assert_maps(&old, &new, &map, i as f64 / 20.);
+            }
+        }
+
+        let map = ParamMap::identity(2);
+        assert_eq!((map.to_new(0.3), map.to_old(0.7)), (0.3, 0.7));
+    }
+}
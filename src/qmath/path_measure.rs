@@ -0,0 +1,307 @@
+use super::*;
+
+// Position, tangent and normal by distance along a contour, the way skia's PathMeasure hands them out. Anything
+// walking a path by arc length should go through this so the awkward spots, like the ends of our lines where the
+// derivative vanishes, only have to be handled here.
+pub struct PathMeasure<'a> {
+    path: &'a Piecewise<Bezier>,
+    lengths: Lengths,
+}
+
+enum Lengths {
+    // the whole path sampled into a table, quick to look things up in over and over
+    Table(ArcLengthParameterization),
+    // every curve's length to within tolerance, and distances found on them by bisection, for when a cut has to
+    // land where the length says
+    Curves(Vec<f64>, f64),
+}
+
+// how far either side of t we look for the direction of the path, in order of preference
+const FALLBACK_STEPS: [f64; 3] = [1e-6, 1e-4, 1e-2];
+// halvings when looking for the t at a distance along a curve, which gets t to well under a billionth
+const BISECTIONS: usize = 40;
+
+// The curve and its t at distance s along the path, s between 0 and the path's length.
+fn locate(path: &Piecewise<Bezier>, lengths: &[f64], tolerance: f64, s: f64) -> (usize, f64)
+{
+    let mut before = 0.;
+    for (i, bez) in path.segs().enumerate() {
+        if s > before + lengths[i] && i + 1 < path.len() {
+            before = before + lengths[i];
+            continue;
+        }
+
+        let target = s - before;
+        if target <= 0. { return (i, 0.); }
+        if target >= lengths[i] { return (i, 1.); }
+
+        let (mut low, mut high) = (0., 1.);
+        for _ in 0..BISECTIONS {
+            let mid = (low + high) / 2.;
+            if bez.arclen_between(0., mid, tolerance) < target { low = mid; } else { high = mid; }
+        }
+        return (i, (low + high) / 2.);
+    }
+
+    return (path.len() - 1, 1.);
+}
+
+impl Bezier {
+    // The piece of the curve between a and b.
+    pub fn restrict(&self, a: f64, b: f64) -> Bezier
+    {
+        let tail = if a > 0. { self.subdivide(a).1 } else { self.clone() };
+        if b >= 1. { return tail; }
+
+        return tail.subdivide((b - a) / (1. - a)).0;
+    }
+}
+
+impl<'a> PathMeasure<'a> {
+    pub fn new(path: &'a Piecewise<Bezier>) -> Self
+    {
+        return PathMeasure { path: path, lengths: Lengths::Table(ArcLengthParameterization::from(path)) };
+    }
+
+    // With a coarser or finer arc length table than the default.
+    pub fn with_cuts(path: &'a Piecewise<Bezier>, arclen_cuts: usize) -> Self
+    {
+        return PathMeasure { path: path, lengths: Lengths::Table(ArcLengthParameterization::from_cuts(path, arclen_cuts)) };
+    }
+
+    // Measuring every curve to within tolerance instead of with a table. Slower to ask, but lengths and where
+    // distances land are as good as tolerance, which is what trimming wants.
+    pub fn with_tolerance(path: &'a Piecewise<Bezier>, tolerance: f64) -> Self
+    {
+        let lengths = path.segs().map(|bez| bez.arclen(tolerance)).collect();
+        return PathMeasure { path: path, lengths: Lengths::Curves(lengths, tolerance) };
+    }
+
+    pub fn length(&self) -> f64
+    {
+        return match &self.lengths {
+            Lengths::Table(table) => table.get_total_arclen(),
+            Lengths::Curves(lengths, _) => lengths.iter().sum(),
+        };
+    }
+
+    pub fn is_closed(&self) -> bool
+    {
+        return !self.path.is_empty() && self.path.is_closed();
+    }
+
+    // the arc length table, for code that still works in terms of parameterizations. None when measuring to a
+    // tolerance.
+    pub fn parameterization(&self) -> Option<&ArcLengthParameterization>
+    {
+        return match &self.lengths {
+            Lengths::Table(table) => Some(table),
+            Lengths::Curves(..) => None,
+        };
+    }
+
+    // The piecewise's t at distance s along it, s is clamped to the path.
+    pub fn t_at(&self, s: f64) -> f64
+    {
+        let length = self.length();
+        if length <= 0. { return 0.; }
+
+        let s = f64::min(f64::max(s, 0.), length);
+        return match &self.lengths {
+            Lengths::Table(table) => table.parameterize(s / length),
+            Lengths::Curves(lengths, tolerance) => {
+                let (i, t) = locate(self.path, lengths, *tolerance, s);
+                self.path.global_t(i, t)
+            }
+        };
+    }
+
+    // The unit tangent at t. We don't use derivative here, Bezier's is a finite difference that points backwards
+    // along the curve and vanishes where a handle sits on its anchor. Instead we take a chord around t, widening it
+    // until the path actually goes somewhere, and if it never does we just point along x.
+    fn tangent(&self, t: f64) -> Vector
+    {
+        for step in FALLBACK_STEPS.iter() {
+            let before = f64::max(t - step, 0.);
+            let after = f64::min(t + step, 1.);
+            let chord = self.path.evaluate(after) + -self.path.evaluate(before);
+            if chord.magnitude() > 1e-9 { return chord.normalize(); }
+        }
+
+        return Vector { x: 1., y: 0. };
+    }
+
+    pub fn pos_tan_at(&self, s: f64) -> (Vector, Vector)
+    {
+        let t = self.t_at(s);
+        return (self.path.evaluate(t), self.tangent(t));
+    }
+
+    // the left side of the tangent, in font space
+    pub fn normal_at(&self, s: f64) -> Vector
+    {
+        let (_, tangent) = self.pos_tan_at(s);
+        return Orientation::YUp.left_normal(tangent);
+    }
+
+    // Maps the x axis onto the tangent at s and the origin onto the point at s.
+    pub fn matrix_at(&self, s: f64) -> Affine
+    {
+        let (p, tan) = self.pos_tan_at(s);
+        return Affine { matrix: [tan.x, tan.y, -tan.y, tan.x, p.x, p.y] };
+    }
+
+    // The part of the path between start_s and stop_s. Empty when there's nothing between them.
+    pub fn segment(&self, start_s: f64, stop_s: f64) -> Piecewise<Bezier>
+    {
+        let mut curves = Vec::new();
+        if stop_s <= start_s || self.path.is_empty() { return Piecewise::new(curves); }
+
+        // a cut right on a joint lands at the start of the curve after it, which we don't want a sliver of
+        let (first, t0) = self.path.segment_at(self.t_at(start_s));
+        let (last, t1) = self.path.segment_at(self.t_at(stop_s));
+
+        for i in first..=last {
+            let a = if i == first { t0 } else { 0. };
+            let b = if i == last { t1 } else { 1. };
+            if b > a { curves.push(self.path[i].restrict(a, b)); }
+        }
+
+        return Piecewise::new(curves);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(x: f64, y: f64) -> Vector
+    {
+        return Vector { x: x, y: y };
+    }
+
+    fn line(a: Vector, b: Vector) -> Bezier
+    {
+        return Bezier::from_control_points(a, a, b, b);
+    }
+
+    // 100 square, counter-clockwise from the origin
+    fn square() -> Piecewise<Bezier>
+    {
+        return Piecewise::new(vec![
+            line(v(0., 0.), v(100., 0.)),
+            line(v(100., 0.), v(100., 100.)),
+            line(v(100., 100.), v(0., 100.)),
+            line(v(0., 100.), v(0., 0.)),
+        ]);
+    }
+
+    fn wave() -> Piecewise<Bezier>
+    {
+        return Piecewise::new(vec![
+            Bezier::from_control_points(v(0., 0.), v(50., 120.), v(100., -80.), v(150., 0.)),
+            Bezier::from_control_points(v(150., 0.), v(200., 80.), v(260., 40.), v(300., -20.)),
+        ]);
+    }
+
+    fn ends(contour: &Piecewise<Bezier>) -> (Vector, Vector)
+    {
+        return (contour.first().unwrap().to_control_points()[0], contour.last().unwrap().to_control_points()[3]);
+    }
+
+    #[test]
+    fn square_by_distance()
+    {
+        let path = square();
+        for measure in [PathMeasure::new(&path), PathMeasure::with_tolerance(&path, 1e-9)].iter() {
+            assert!((measure.length() - 400.).abs() < 1e-6);
+            assert!(measure.is_closed());
+
+            let (p, tan) = measure.pos_tan_at(150.);
+            assert!(p.distance(v(100., 50.)) < 1e-3, "{:?}", p);
+            assert!(tan.distance(v(0., 1.)) < 1e-6, "{:?}", tan);
+            assert!(measure.matrix_at(150.).apply(v(0., 10.)).distance(v(90., 50.)) < 1e-3);
+
+            // the ends of a line have their handles on them, the tangent still has to come out
+            assert!(measure.pos_tan_at(0.).1.distance(v(1., 0.)) < 1e-6);
+            assert!(measure.pos_tan_at(400.).1.distance(v(0., -1.)) < 1e-6);
+        }
+
+        let path = wave();
+        let (table, exact) = (PathMeasure::new(&path), PathMeasure::with_tolerance(&path, 1e-9));
+        assert!((table.length() - exact.length()).abs() < 1e-3);
+        assert!(table.parameterization().is_some() && exact.parameterization().is_none());
+    }
+
+    #[test]
+    fn segments_cut_on_joints_without_slivers()
+    {
+        let path = square();
+        let measure = PathMeasure::with_tolerance(&path, 1e-9);
+
+        let middle = measure.segment(100., 300.);
+        assert_eq!(middle.len(), 2);
+        let (start, end) = ends(&middle);
+        assert!(start.distance(v(100., 0.)) < 1e-6 && end.distance(v(0., 100.)) < 1e-6);
+
+        let across = measure.segment(50., 150.);
+        assert_eq!(across.len(), 2);
+        assert!((PathMeasure::with_tolerance(&across, 1e-9).length() - 100.).abs() < 1e-6);
+
+        assert!(measure.segment(200., 200.).is_empty());
+    }
+
+    #[test]
+    fn trims_and_dashes_measure_the_same_way()
+    {
+        let path = wave();
+        let length = PathMeasure::with_tolerance(&path, 1e-9).length();
+
+        let trimmed = path.trim(30., 50.);
+        assert!((PathMeasure::with_tolerance(&trimmed, 1e-9).length() - (length - 80.)).abs() < 1e-5);
+        let measure = PathMeasure::with_tolerance(&path, 1e-9);
+        let (start, end) = ends(&trimmed);
+        assert!(start.distance(measure.pos_tan_at(30.).0) < 1e-6);
+        assert!(end.distance(measure.pos_tan_at(length - 50.).0) < 1e-6);
+
+        // dashes 40 on 20 off, the first is the first 40 of the path
+        let dashes = path.dash(&[40., 20.], 0.);
+        assert_eq!(dashes.len(), (length / 60.).ceil() as usize);
+        assert!(ends(&dashes[0]).1.distance(PathMeasure::new(&path).pos_tan_at(40.).0) < 1e-6);
+        assert!((PathMeasure::with_tolerance(&dashes[1], 1e-9).length() - 40.).abs() < 0.01);
+    }
+
+    // skia's PathMeasure on the same paths. Its tolerance is 0.5 / res_scale units, with a res_scale of 100 what we
+    // get back should be within a few thousandths of our own.
+    #[cfg(feature = "skia")]
+    #[test]
+    fn matches_skias_path_measure()
+    {
+        use skulpin::skia_safe;
+
+        let circle = Piecewise::circle(v(50., 50.), 80.);
+        for (name, path) in [("square", square()), ("wave", wave()), ("circle", circle)].iter() {
+            let ours = PathMeasure::with_tolerance(path, 1e-9);
+            let mut theirs = skia_safe::PathMeasure::new(&path.append_to_skpath(skia_safe::Path::new()), false, Some(100.));
+            let length = ours.length();
+            assert!((theirs.length() as f64 - length).abs() < 0.01, "{}: {} vs {}", name, theirs.length(), length);
+
+            for i in 0..=20 {
+                let s = length * i as f64 / 20.;
+                let (p, tan) = ours.pos_tan_at(s);
+                let (skia_p, skia_tan) = theirs.pos_tan(s as f32).unwrap();
+                assert!(p.distance(Vector::from_skia_point(&skia_p)) < 0.01, "{} at {}", name, s);
+                assert!(tan.dot(Vector::from_skia_point(&skia_tan)) > 0.9999, "{} at {}", name, s);
+            }
+
+            let (a, b) = (length * 0.2, length * 0.7);
+            let segment = ours.segment(a, b);
+            let skia_segment = theirs.segment(a as f32, b as f32, true).unwrap();
+            let skia_segment = Piecewise::from_skpath(&skia_segment).unwrap();
+            let (start, end) = ends(&segment);
+            let (skia_start, skia_end) = ends(&skia_segment[0]);
+            assert!(start.distance(skia_start) < 0.01 && end.distance(skia_end) < 0.01, "{}", name);
+            assert!((PathMeasure::with_tolerance(&segment, 1e-9).length() - (b - a)).abs() < 1e-5, "{}", name);
+        }
+    }
+}
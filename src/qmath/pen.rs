@@ -0,0 +1,391 @@
+use super::*;
+
+// A pen is anything that can be fed an outline one segment at a time. This is the same protocol fontTools and
+// most of the Rust font crates speak, so implementing it is the easiest way to get geometry in or out of the math
+// library without going through glifparser.
+pub trait ContourPen {
+    fn move_to(&mut self, to: Vector);
+    fn line_to(&mut self, to: Vector);
+    fn curve_to(&mut self, h1: Vector, h2: Vector, to: Vector);
+
+    // Finishes a closed contour. If the pen isn't back at the start a line is implied.
+    fn close(&mut self);
+    // Finishes an open contour.
+    fn end(&mut self);
+}
+
+// The kinds of on-curve point a PointPen can be given. Off-curve points have no segment type.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SegmentType {
+    Move,
+    Line,
+    Curve,
+    QCurve,
+}
+
+// The point pen protocol from UFO land. Points come in one at a time and a contour is closed unless its first point
+// is a move.
+pub trait PointPen {
+    fn begin_path(&mut self);
+    fn add_point(&mut self, point: Vector, segment_type: Option<SegmentType>);
+    fn end_path(&mut self);
+}
+
+fn is_line(control_points: &[Vector; 4]) -> bool
+{
+    return control_points[0] == control_points[1] && control_points[2] == control_points[3];
+}
+
+impl Piecewise<Piecewise<Bezier>>
+{
+    pub fn draw(&self, pen: &mut impl ContourPen)
+    {
+        for contour in &self.curves {
+            contour.draw(pen);
+        }
+    }
+
+    pub fn draw_points(&self, pen: &mut impl PointPen)
+    {
+        for contour in &self.curves {
+            contour.draw_points(pen);
+        }
+    }
+}
+
+impl Piecewise<Bezier>
+{
+    // We don't keep track of whether a contour is open so we call it closed if it ends where it started. Going
+    // through the coefficients loses a little precision so the ends only need to be very close. An empty contour
+    // has no ends to meet and isn't.
+    pub fn is_closed(&self) -> bool
+    {
+        if self.curves.is_empty() { return false; }
+
+        let first = self.curves.first().unwrap().to_control_points()[0];
+        let last = self.curves.last().unwrap().to_control_points()[3];
+        return first.is_near(last, 1e-9);
+    }
+
+    pub fn draw(&self, pen: &mut impl ContourPen)
+    {
+        if self.curves.is_empty() { return; }
+
+        pen.move_to(self.curves[0].to_control_points()[0]);
+
+        for bez in &self.curves {
+            let controlp = bez.to_control_points();
+
+            if is_line(&controlp) {
+                pen.line_to(controlp[3]);
+            } else {
+                pen.curve_to(controlp[1], controlp[2], controlp[3]);
+            }
+        }
+
+        if self.is_closed() {
+            pen.close();
+        } else {
+            pen.end();
+        }
+    }
+
+    pub fn draw_points(&self, pen: &mut impl PointPen)
+    {
+        if self.curves.is_empty() { return; }
+
+        let closed = self.is_closed();
+
+        pen.begin_path();
+
+        // a closed contour's last point doubles as its start so we only need the move for open ones
+        if !closed {
+            pen.add_point(self.curves[0].to_control_points()[0], Some(SegmentType::Move));
+        }
+
+        for bez in &self.curves {
+            let controlp = bez.to_control_points();
+
+            if is_line(&controlp) {
+                pen.add_point(controlp[3], Some(SegmentType::Line));
+            } else {
+                pen.add_point(controlp[1], None);
+                pen.add_point(controlp[2], None);
+                pen.add_point(controlp[3], Some(SegmentType::Curve));
+            }
+        }
+
+        pen.end_path();
+    }
+}
+
+// Collects whatever gets drawn into it as a Piecewise.
+pub struct PiecewiseBuilder {
+    contours: Vec<Piecewise<Bezier>>,
+    current: Vec<Bezier>,
+    start: Vector,
+    last: Vector,
+}
+
+impl PiecewiseBuilder {
+    pub fn new() -> Self
+    {
+        return PiecewiseBuilder {
+            contours: Vec::new(),
+            current: Vec::new(),
+            start: Vector { x: 0., y: 0. },
+            last: Vector { x: 0., y: 0. },
+        };
+    }
+
+    fn finish_contour(&mut self)
+    {
+        if !self.current.is_empty() {
+            let curves = std::mem::replace(&mut self.current, Vec::new());
+            self.contours.push(Piecewise { curves: curves });
+        }
+    }
+
+    pub fn build(mut self) -> Piecewise<Piecewise<Bezier>>
+    {
+        self.finish_contour();
+
+        return Piecewise {
+            curves: self.contours,
+        };
+    }
+}
+
+impl ContourPen for PiecewiseBuilder {
+    fn move_to(&mut self, to: Vector)
+    {
+        self.finish_contour();
+        self.start = to;
+        self.last = to;
+    }
+
+    fn line_to(&mut self, to: Vector)
+    {
+        self.current.push(Bezier::from_control_points(self.last, self.last, to, to));
+        self.last = to;
+    }
+
+    fn curve_to(&mut self, h1: Vector, h2: Vector, to: Vector)
+    {
+        self.current.push(Bezier::from_control_points(self.last, h1, h2, to));
+        self.last = to;
+    }
+
+    fn close(&mut self)
+    {
+        if self.last != self.start {
+            self.line_to(self.start);
+        }
+
+        self.finish_contour();
+    }
+
+    fn end(&mut self)
+    {
+        self.finish_contour();
+    }
+}
+
+// Turns point pen calls into segment pen calls so anything that draws points can feed a ContourPen.
+pub struct PointToContourPen<'a, P: ContourPen> {
+    pen: &'a mut P,
+    points: Vec<(Vector, Option<SegmentType>)>,
+    current: Vector,
+}
+
+impl<'a, P: ContourPen> PointToContourPen<'a, P> {
+    pub fn new(pen: &'a mut P) -> Self
+    {
+        return PointToContourPen {
+            pen: pen,
+            points: Vec::new(),
+            current: Vector { x: 0., y: 0. },
+        };
+    }
+
+    fn quad_to(&mut self, q: Vector, to: Vector)
+    {
+        let cubic = Bezier::from_quadratic_points(self.current, q, to).to_control_points();
+        self.pen.curve_to(cubic[1], cubic[2], to);
+        self.current = to;
+    }
+
+    fn draw_segments(&mut self, points: &[(Vector, Option<SegmentType>)])
+    {
+        let mut off_curve: Vec<Vector> = Vec::new();
+
+        for (point, segment_type) in points {
+            let point = *point;
+
+            match segment_type {
+                None => { off_curve.push(point); continue; }
+
+                Some(SegmentType::QCurve) => {
+                    // consecutive quadratic off-curve points have an implied on-curve point halfway between them
+                    for i in 0..off_curve.len() {
+                        let to = if i + 1 < off_curve.len() { off_curve[i].lerp(off_curve[i+1], 0.5) } else { point };
+                        self.quad_to(off_curve[i], to);
+                    }
+
+                    if off_curve.is_empty() {
+                        self.pen.line_to(point);
+                    }
+                }
+
+                // a curve with a single handle is really a quadratic, and anything with more than two handles
+                // isn't something we can represent so we keep the outermost two
+                _ => {
+                    match off_curve.len() {
+                        0 => self.pen.line_to(point),
+                        1 => self.quad_to(off_curve[0], point),
+                        n => self.pen.curve_to(off_curve[0], off_curve[n-1], point),
+                    }
+                }
+            }
+
+            self.current = point;
+            off_curve.clear();
+        }
+    }
+}
+
+impl<'a, P: ContourPen> PointPen for PointToContourPen<'a, P> {
+    fn begin_path(&mut self)
+    {
+        self.points.clear();
+    }
+
+    fn add_point(&mut self, point: Vector, segment_type: Option<SegmentType>)
+    {
+        self.points.push((point, segment_type));
+    }
+
+    fn end_path(&mut self)
+    {
+        let mut points = std::mem::replace(&mut self.points, Vec::new());
+        if points.is_empty() { return; }
+
+        if points[0].1 == Some(SegmentType::Move) {
+            self.current = points[0].0;
+            self.pen.move_to(self.current);
+            self.draw_segments(&points[1..]);
+            self.pen.end();
+            return;
+        }
+
+        // Closed contours can start anywhere, even on an off-curve point. We rotate them so they end on an
+        // on-curve point and start from there. A contour made only of off-curve points is a TrueType-ism, every
+        // on-curve point is implied, so we start from the one between the last and first and go all the way
+        // round in quadratics.
+        match points.iter().rposition(|p| p.1.is_some()) {
+            Some(i) => points.rotate_left(i + 1),
+            None => {
+                let start = points[points.len() - 1].0.lerp(points[0].0, 0.5);
+                points.push((start, Some(SegmentType::QCurve)));
+            }
+        }
+
+        self.current = points.last().unwrap().0;
+        self.pen.move_to(self.current);
+        self.draw_segments(&points);
+        self.pen.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::read_glif_piecewise;
+    use std::path::Path;
+
+    fn v(x: f64, y: f64) -> Vector
+    {
+        return Vector { x: x, y: y };
+    }
+
+    fn assert_same(a: &Piecewise<Piecewise<Bezier>>, b: &Piecewise<Piecewise<Bezier>>)
+    {
+        assert_eq!(a.len(), b.len());
+        for (ac, bc) in a.segs().zip(b.segs()) {
+            assert_eq!(ac.len(), bc.len());
+            for (ab, bb) in ac.segs().zip(bc.segs()) {
+                for (p, q) in ab.to_control_points().iter().zip(bb.to_control_points().iter()) {
+                    assert!(p.is_near(*q, 1e-9), "{:?} rather than {:?}", p, q);
+                }
+            }
+        }
+    }
+
+    fn closed_mixed() -> Piecewise<Piecewise<Bezier>>
+    {
+        return read_glif_piecewise(&Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures").join("skeletons").join("closed_mixed.glif")).unwrap();
+    }
+
+    // closed_mixed.glif drawn by hand, the closing lines left for close to fill in.
+    #[test]
+    fn builder_matches_from_contour()
+    {
+        let mut builder = PiecewiseBuilder::new();
+        builder.move_to(v(100., 0.));
+        builder.line_to(v(300., 0.));
+        builder.curve_to(v(440., 0.), v(520., 120.), v(520., 350.));
+        builder.curve_to(v(520., 580.), v(440., 700.), v(300., 700.));
+        builder.line_to(v(100., 700.));
+        builder.close();
+        builder.move_to(v(200., 100.));
+        builder.line_to(v(200., 600.));
+        builder.line_to(v(290., 600.));
+        builder.curve_to(v(380., 600.), v(420., 500.), v(420., 350.));
+        builder.curve_to(v(420., 200.), v(380., 100.), v(290., 100.));
+        builder.close();
+
+        assert_same(&builder.build(), &closed_mixed());
+
+        // an open contour stays open, nothing gets added on the end
+        let mut builder = PiecewiseBuilder::new();
+        builder.move_to(v(0., 0.));
+        builder.line_to(v(100., 0.));
+        builder.end();
+        let open = builder.build();
+        assert_eq!(open[0].len(), 1);
+        assert!(!open[0].is_closed());
+    }
+
+    // Drawing into a builder, through either kind of pen, gives back what was drawn.
+    #[test]
+    fn pens_round_trip()
+    {
+        let outline = closed_mixed();
+
+        let mut builder = PiecewiseBuilder::new();
+        outline.draw(&mut builder);
+        assert_same(&builder.build(), &outline);
+
+        let mut builder = PiecewiseBuilder::new();
+        {
+            let mut pen = PointToContourPen::new(&mut builder);
+            outline.draw_points(&mut pen);
+        }
+        assert_same(&builder.build(), &outline);
+
+        // two quadratic off-curve points in a row have an on-curve point halfway between them
+        let mut builder = PiecewiseBuilder::new();
+        {
+            let mut pen = PointToContourPen::new(&mut builder);
+            pen.begin_path();
+            pen.add_point(v(0., 0.), Some(SegmentType::Move));
+            pen.add_point(v(0., 100.), None);
+            pen.add_point(v(100., 100.), None);
+            pen.add_point(v(100., 0.), Some(SegmentType::QCurve));
+            pen.end_path();
+        }
+        let quads = builder.build();
+        assert_eq!(quads[0].len(), 2);
+        assert!(quads[0][0].evaluate(1.).is_near(v(50., 100.), 1e-9));
+    }
+}
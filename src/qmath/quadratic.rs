@@ -0,0 +1,425 @@
+use super::*;
+
+// A quadratic bezier given by its control points. Glifs sourced from TrueType are full of these, so they can go in
+// a Piecewise on their own and be worked with without going up to cubics and back.
+#[derive(Clone, Copy, Debug)]
+pub struct QuadBezier {
+    pub start: Vector,
+    pub control: Vector,
+    pub end: Vector,
+}
+
+impl QuadBezier {
+    // Builds the segment from point to next_point. Like glifs the type of the segment is on the point it ends at, a
+    // qcurve's control point is whichever of the two handles between them is set. Lines get their control point
+    // halfway along so they're exact quadratics, and a cubic only makes it through if it's secretly a quadratic.
+    pub fn from<T>(point: &glifparser::Point<T>, next_point: &glifparser::Point<T>) -> Option<Self>
+    {
+        let p = Vector::from_point(point);
+        let np = Vector::from_point(next_point);
+
+        match next_point.ptype {
+            PointType::QCurve => {
+                let control = match (point.a, next_point.b) {
+                    (Handle::At(..), _) => Vector::from_handle(point, WhichHandle::A),
+                    (_, Handle::At(..)) => Vector::from_handle(next_point, WhichHandle::B),
+                    _ => p.lerp(np, 0.5),
+                };
+                return Some(QuadBezier { start: p, control: control, end: np });
+            }
+            PointType::Line => return Some(QuadBezier { start: p, control: p.lerp(np, 0.5), end: np }),
+            _ => return QuadBezier::from_cubic_exact(&Bezier::from(point, next_point), 1e-6),
+        }
+    }
+
+    // The quadratic a cubic was elevated from, None if the cubic isn't one to within eps font units.
+    pub fn from_cubic_exact(bez: &Bezier, eps: f64) -> Option<Self>
+    {
+        if !bez.is_quadratic(eps) { return None; }
+        return Some(bez.reduce_to_quadratic().0);
+    }
+
+    pub fn to_cubic(&self) -> Bezier
+    {
+        return Bezier::from_quadratic_points(self.start, self.control, self.end);
+    }
+
+    // returns two curves one before t and one after
+    pub fn subdivide(&self, t: f64) -> (QuadBezier, QuadBezier)
+    {
+        let q0 = self.start.lerp(self.control, t);
+        let q1 = self.control.lerp(self.end, t);
+        let s0 = q0.lerp(q1, t);
+
+        return (QuadBezier { start: self.start, control: q0, end: s0 }, QuadBezier { start: s0, control: q1, end: self.end });
+    }
+
+    // Splits the curve at every t in ts the same way Bezier::split_at_multiple does.
+    pub fn split_at_multiple(&self, ts: &[f64]) -> Vec<QuadBezier>
+    {
+        let mut cuts: Vec<f64> = ts.iter().copied().filter(|t| t.is_finite() && *t > 1e-9 && *t < 1. - 1e-9).collect();
+        cuts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        cuts.dedup_by(|b, a| *b - *a < 1e-9);
+
+        let mut output = Vec::with_capacity(cuts.len() + 1);
+        let mut rest = *self;
+        let mut rest_start = 0.;
+
+        for t in cuts {
+            let (first, second) = rest.subdivide((t - rest_start) / (1. - rest_start));
+            output.push(first);
+            rest = second;
+            rest_start = t;
+        }
+
+        output.push(rest);
+        return output;
+    }
+
+    fn velocity(&self, t: f64) -> Vector
+    {
+        return (self.control + -self.start) * (2. * (1. - t)) + (self.end + -self.control) * (2. * t);
+    }
+}
+
+impl Evaluate for QuadBezier {
+    fn evaluate(&self, t: f64) -> Vector
+    {
+        let mt = 1. - t;
+        return self.start * (mt * mt) + self.control * (2. * t * mt) + self.end * (t * t);
+    }
+
+    // points back along the curve, the same as Bezier's does
+    fn derivative(&self, t: f64) -> Vector
+    {
+        return -self.velocity(t);
+    }
+
+    fn derivative2(&self, _t: f64) -> Vector
+    {
+        return (self.start + self.control * -2. + self.end) * 2.;
+    }
+
+    fn bounds(&self) -> Rect
+    {
+        let mut points = vec![self.start, self.end];
+
+        // the derivative is linear so each axis turns around at most once
+        let a = self.start + self.control * -2. + self.end;
+        let b = self.control + -self.start;
+        for t in roots::solve_linear(a.x, b.x, 0., 1.).iter().chain(roots::solve_linear(a.y, b.y, 0., 1.).iter()) {
+            points.push(self.evaluate(*t));
+        }
+
+        return Rect::AABB_from_points(points);
+    }
+
+    fn directional_bounds(&self, direction: Vector) -> (f64, f64)
+    {
+        let project = |v: Vector| v.x * direction.x + v.y * direction.y;
+
+        let mut min = f64::min(project(self.start), project(self.end));
+        let mut max = f64::max(project(self.start), project(self.end));
+
+        let a = project(self.start + self.control * -2. + self.end);
+        let b = project(self.control + -self.start);
+        for t in roots::solve_linear(a, b, 0., 1.) {
+            min = min.min(project(self.evaluate(t)));
+            max = max.max(project(self.evaluate(t)));
+        }
+
+        return (min, max);
+    }
+
+    fn apply_transform<F>(&self, transform: F) -> Self where F: Fn(&Vector) -> Vector
+    {
+        return QuadBezier { start: transform(&self.start), control: transform(&self.control), end: transform(&self.end) };
+    }
+}
+
+// how many samples we take along the curve when measuring how far a quadratic strays from it
+const DEVIATION_SAMPLES: usize = 64;
+
+// past this many halvings the curve is tiny and we take whatever fit we've got
+const MAX_SPLIT_DEPTH: usize = 10;
+
+impl Bezier {
+    // A cubic is a degree elevated quadratic exactly when its cubic coefficients are zero, which is the same as saying
+    // both handles point at a single quadratic control point. eps is in font units.
+    pub fn is_quadratic(&self, eps: f64) -> bool
+    {
+        return self.A.abs() <= eps && self.E.abs() <= eps;
+    }
+
+    // The quadratic with the same endpoints that's closest to us in the least squares sense, along with the furthest
+    // it strays from us at the same t. Minimizing the integral of |C(t) - Q(t)|^2 over the control point works out to
+    // q = (3(p1 + p2) - (p0 + p3)) / 4, which is exactly the original control point for an elevated quadratic.
+    pub fn reduce_to_quadratic(&self) -> (QuadBezier, f64)
+    {
+        let p = self.to_control_points();
+        let control = (p[1] + p[2]) * 0.75 + (p[0] + p[3]) * -0.25;
+        let quad = QuadBezier { start: p[0], control: control, end: p[3] };
+
+        let mut deviation: f64 = 0.;
+        for i in 0..=DEVIATION_SAMPLES {
+            let t = i as f64 / DEVIATION_SAMPLES as f64;
+            deviation = deviation.max(self.evaluate(t).distance(quad.evaluate(t)));
+        }
+
+        return (quad, deviation);
+    }
+
+    // Approximates the curve with quadratics that stray no further than tolerance from it. Curves that already are
+    // quadratic, or close enough, come back as a single quadratic, anything else gets halved until the pieces fit.
+    pub fn to_quadratics(&self, tolerance: f64) -> Vec<QuadBezier>
+    {
+        let mut output = Vec::new();
+        self.append_quadratics(tolerance, 0, &mut output);
+        return output;
+    }
+
+    fn append_quadratics(&self, tolerance: f64, depth: usize, output: &mut Vec<QuadBezier>)
+    {
+        let (quad, deviation) = self.reduce_to_quadratic();
+
+        if deviation <= tolerance || depth >= MAX_SPLIT_DEPTH {
+            output.push(quad);
+            return;
+        }
+
+        let (first, second) = self.subdivide(0.5);
+        first.append_quadratics(tolerance, depth + 1, output);
+        second.append_quadratics(tolerance, depth + 1, output);
+    }
+}
+
+impl Piecewise<Bezier>
+{
+    pub fn to_quadratics(&self, tolerance: f64) -> Vec<QuadBezier>
+    {
+        return self.curves.iter().flat_map(|bez| bez.to_quadratics(tolerance)).collect();
+    }
+}
+
+// TrueType runs of off-curve points. A glif from TrueType can have any number of off-curve points in a row before a
+// qcurve, with an on-curve point implied halfway between each neighbouring pair, and a closed contour can be nothing
+// but off-curve points with every on-curve point implied. Those come through a Contour as points of their own with
+// an OffCurve type rather than as handles, so a contour that has any gets laid back out as the points it was in
+// the glif, handles and all, and drawn through PointToContourPen which knows how to fill in the implied points.
+
+// an on-curve point this close to halfway between its neighbouring control points goes without saying
+const IMPLIED_EPSILON: f64 = 1e-6;
+
+pub(super) fn has_off_curve_points<U>(contour: &Contour<U>) -> bool
+{
+    return contour.iter().any(|point| point.ptype == PointType::OffCurve);
+}
+
+// The contour as a point pen would be given it. Handles become off-curve points on whichever side of their point
+// they're on, except the ones next to a line, which Bezier::from would ignore too, and the ones hanging off the
+// ends of an open contour.
+fn glif_points<U>(contour: &Contour<U>) -> Vec<(Vector, Option<SegmentType>)>
+{
+    let open = contour[0].ptype == PointType::Move;
+    let mut points = Vec::new();
+
+    for (i, point) in contour.iter().enumerate() {
+        let position = Vector::from_point(point);
+        let segment_type = match point.ptype {
+            PointType::OffCurve => { points.push((position, None)); continue; }
+            PointType::Move if i == 0 => SegmentType::Move,
+            PointType::Line => SegmentType::Line,
+            PointType::QCurve => SegmentType::QCurve,
+            _ => SegmentType::Curve,
+        };
+
+        let first = open && i == 0;
+        let last = open && i + 1 == contour.len();
+        let next_is_line = contour[(i + 1) % contour.len()].ptype == PointType::Line;
+
+        // a qcurve's control can be on both sides of it, once is enough
+        if let (Handle::At(..), false, false) = (point.b, first, segment_type == SegmentType::Line) {
+            let handle = Vector::from_handle(point, WhichHandle::B);
+            if points.last() != Some(&(handle, None)) {
+                points.push((handle, None));
+            }
+        }
+        points.push((position, Some(segment_type)));
+        if let (Handle::At(..), false, false) = (point.a, last, next_is_line) {
+            points.push((Vector::from_handle(point, WhichHandle::A), None));
+        }
+    }
+
+    return points;
+}
+
+impl Piecewise<Bezier>
+{
+    // from_contour for a contour with off-curve points of its own, see above.
+    pub(super) fn from_implied_contour<U>(contour: &Contour<U>) -> Self
+    {
+        let mut builder = PiecewiseBuilder::new();
+
+        {
+            let mut pen = PointToContourPen::new(&mut builder);
+            pen.begin_path();
+            for (point, segment_type) in glif_points(contour) {
+                pen.add_point(point, segment_type);
+            }
+            pen.end_path();
+        }
+
+        return builder.build().curves.pop().unwrap_or(Piecewise { curves: Vec::new() });
+    }
+}
+
+impl Piecewise<QuadBezier>
+{
+    // Reads a contour as quadratics, for glifs that came from TrueType. Line segments come along as straight
+    // quadratics. None if any segment is a true cubic, reading those as quadratics would change the shape.
+    pub fn from_quadratic_contour<U>(contour: &Contour<U>) -> Option<Self>
+    {
+        // runs of off-curve points come out of from_contour as elevated quadratics, which we take straight back down
+        if has_off_curve_points(contour) {
+            let mut curves = Vec::new();
+            for bez in &Piecewise::from_contour(contour).curves {
+                let cp = bez.to_control_points();
+                if is_straight(&cp, LINE_EPSILON) {
+                    curves.push(QuadBezier { start: cp[0], control: cp[0].lerp(cp[3], 0.5), end: cp[3] });
+                } else {
+                    curves.push(QuadBezier::from_cubic_exact(bez, 1e-6)?);
+                }
+            }
+            return Some(Piecewise { curves: curves });
+        }
+
+        let mut curves = Vec::new();
+        for pair in contour.windows(2) {
+            curves.push(QuadBezier::from(&pair[0], &pair[1])?);
+        }
+
+        let first = contour.first()?;
+        if first.ptype != PointType::Move {
+            curves.push(QuadBezier::from(contour.last().unwrap(), first)?);
+        }
+
+        return Some(Piecewise { curves: curves });
+    }
+
+    pub fn to_cubic(&self) -> Piecewise<Bezier>
+    {
+        return Piecewise { curves: self.curves.iter().map(|quad| quad.to_cubic()).collect() };
+    }
+
+    // The other way from from_quadratic_contour, written the way TrueType would: every control point is an
+    // off-curve point of its own and an on-curve point halfway between the control points either side of it is
+    // left out to be implied. Straight quadratics with their control halfway along are written as lines. A closed
+    // contour whose on-curve points are all implied comes out as nothing but off-curve points.
+    pub fn to_quadratic_contour(&self) -> Contour<Option<PointData>>
+    {
+        let mut output: Contour<Option<PointData>> = Vec::new();
+        let (first, last) = match (self.curves.first(), self.curves.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return output,
+        };
+        let closed = first.start.is_near(last.end, 1e-9);
+        let n = self.curves.len();
+
+        let is_line = |quad: &QuadBezier| quad.control.is_near(quad.start.lerp(quad.end, 0.5), IMPLIED_EPSILON);
+        let point = |at: Vector, ptype: PointType| {
+            let mut point = at.to_point(Handle::Colocated, Handle::Colocated);
+            point.ptype = ptype;
+            return point;
+        };
+
+        if !closed {
+            output.push(point(first.start, PointType::Move));
+        }
+
+        for (i, quad) in self.curves.iter().enumerate() {
+            if is_line(quad) {
+                output.push(point(quad.end, PointType::Line));
+                continue;
+            }
+
+            output.push(point(quad.control, PointType::OffCurve));
+
+            // the point between this quadratic and the next is implied if it's halfway between their controls
+            let next = if i + 1 < n { Some(&self.curves[i + 1]) } else if closed { Some(first) } else { None };
+            let implied = match next {
+                Some(next) => !is_line(next) && quad.end.is_near(quad.control.lerp(next.control, 0.5), IMPLIED_EPSILON),
+                None => false,
+            };
+            if !implied {
+                output.push(point(quad.end, PointType::QCurve));
+            }
+        }
+
+        // a closed contour starts from the point its last segment ends at, the same as to_contour's do
+        if closed {
+            if let Some(i) = output.iter().rposition(|p| p.ptype != PointType::OffCurve) {
+                output.rotate_left(i);
+            }
+        }
+
+        return output;
+    }
+}
+
+impl Piecewise<Bezier>
+{
+    // Approximates the contour with quadratics to within tolerance and writes them the way to_quadratic_contour
+    // does, implied on-curve points and all.
+    pub fn to_quadratic_contour(&self, tolerance: f64) -> Contour<Option<PointData>>
+    {
+        return Piecewise { curves: self.to_quadratics(tolerance) }.to_quadratic_contour();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(x: f64, y: f64) -> Vector
+    {
+        return Vector { x: x, y: y };
+    }
+
+    #[test]
+    fn elevated_quadratic_reduces_exactly()
+    {
+        let quad = QuadBezier { start: v(0., 0.), control: v(150., 300.), end: v(400., 20.) };
+        let cubic = quad.to_cubic();
+        assert!(cubic.is_quadratic(1e-9));
+
+        let (reduced, deviation) = cubic.reduce_to_quadratic();
+        assert!(deviation < 1e-9, "{}", deviation);
+        assert!(reduced.control.is_near(quad.control, 1e-9));
+        assert_eq!(cubic.to_quadratics(0.1).len(), 1);
+    }
+
+    #[test]
+    fn true_cubic_gets_split()
+    {
+        let s_curve = Bezier::from_control_points(v(0., 0.), v(300., 0.), v(0., 200.), v(300., 200.));
+        assert!(!s_curve.is_quadratic(1.));
+
+        let (_, deviation) = s_curve.reduce_to_quadratic();
+        assert!(deviation > 1., "{}", deviation);
+
+        let quads = s_curve.to_quadratics(0.5);
+        assert!(quads.len() > 1);
+        assert!(quads[0].start.is_near(s_curve.evaluate(0.), 1e-9) && quads[quads.len() - 1].end.is_near(s_curve.evaluate(1.), 1e-9));
+        for pair in quads.windows(2) {
+            assert!(pair[0].end.is_near(pair[1].start, 1e-9));
+        }
+
+        // nowhere do the quadratics stray further than tolerance from the cubic
+        for quad in &quads {
+            for i in 0..=20 {
+                let (_, d) = s_curve.nearest(quad.evaluate(i as f64 / 20.));
+                assert!(d <= 0.5, "{}", d);
+            }
+        }
+    }
+}
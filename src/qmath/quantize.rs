@@ -0,0 +1,144 @@
+use super::*;
+
+// Anything worth telling the user about after quantizing an outline. Rounding can collapse tiny segments
+// down to nothing so we clean those up and record what we removed here.
+pub struct QuantizeReport {
+    pub warnings: Vec<String>
+}
+
+fn quantize_value(v: f64, decimals: u32) -> f64
+{
+    let factor = f64::powi(10., decimals as i32);
+    let rounded = (v * factor).round() / factor;
+
+    // rounding small negative values gives us -0 which we never want to write out
+    if rounded == 0. { 0. } else { rounded }
+}
+
+fn round_value(v: f64, grid: f64) -> f64
+{
+    if !(grid > 0.) { return v; }
+    let rounded = (v / grid).round() * grid;
+
+    if rounded == 0. { 0. } else { rounded }
+}
+
+impl Vector {
+    pub fn quantize(self, decimals: u32) -> Self
+    {
+        Vector { x: quantize_value(self.x, decimals), y: quantize_value(self.y, decimals) }
+    }
+
+    // The nearest point on a grid this far apart, a grid of 1 rounds to whole units. Anything that isn't a
+    // positive grid leaves the point where it is.
+    pub fn round_to_grid(self, grid: f64) -> Self
+    {
+        Vector { x: round_value(self.x, grid), y: round_value(self.y, grid) }
+    }
+}
+
+impl Bezier {
+    pub fn quantize(&self, decimals: u32) -> Self
+    {
+        let cp = self.to_control_points();
+
+        return Bezier::from_control_points(
+            cp[0].quantize(decimals),
+            cp[1].quantize(decimals),
+            cp[2].quantize(decimals),
+            cp[3].quantize(decimals)
+        );
+    }
+
+    // Snaps the control points to the grid. A handle sitting on its point stays on it, which also keeps lines
+    // exactly straight. With on_curve_only the ends are snapped and any handles of their own are left where they
+    // are, the way a lot of UFO tools round.
+    pub fn round_to_grid(&self, grid: f64, on_curve_only: bool) -> Self
+    {
+        let cp = self.to_control_points();
+        let (start, end) = (cp[0].round_to_grid(grid), cp[3].round_to_grid(grid));
+
+        let handle = |h: Vector, point: Vector, rounded: Vector| {
+            if h == point { rounded } else if on_curve_only { h } else { h.round_to_grid(grid) }
+        };
+
+        return Bezier::from_control_points(start, handle(cp[1], cp[0], start), handle(cp[2], cp[3], end), end);
+    }
+
+    // A curve where all four control points landed on the same spot after rounding.
+    fn is_collapsed(&self) -> bool
+    {
+        let cp = self.to_control_points();
+        return cp[0] == cp[1] && cp[1] == cp[2] && cp[2] == cp[3];
+    }
+}
+
+impl Piecewise<Bezier>
+{
+    // Bezier's round_to_grid on every curve. Curves that collapse to a point are kept, see the outline's
+    // round_to_grid for a version that cleans them up.
+    pub fn round_to_grid(&self, grid: f64, on_curve_only: bool) -> Self
+    {
+        return Piecewise { curves: self.curves.iter().map(|bez| bez.round_to_grid(grid, on_curve_only)).collect() };
+    }
+
+    // The contour written out with its points snapped to the grid.
+    pub fn to_contour_rounded(&self, grid: f64, on_curve_only: bool) -> Contour<Option<PointData>>
+    {
+        return self.round_to_grid(grid, on_curve_only).to_contour();
+    }
+}
+
+impl Piecewise<Piecewise<Bezier>>
+{
+    // Rounds every on-curve point and handle to the given number of decimal places. Shared endpoints are
+    // rounded identically so contours stay connected. This should be the last thing done to an outline before
+    // it gets written, anything like overlap removal has to happen before this or it can undo our rounding.
+    pub fn quantize(&self, decimals: u32) -> (Self, QuantizeReport)
+    {
+        return self.round_each(|bez| bez.quantize(decimals));
+    }
+
+    // Like quantize but snapping to a grid, see Bezier's round_to_grid.
+    pub fn round_to_grid(&self, grid: f64, on_curve_only: bool) -> (Self, QuantizeReport)
+    {
+        return self.round_each(|bez| bez.round_to_grid(grid, on_curve_only));
+    }
+
+    // The outline written out with its points snapped to the grid, along with anything rounding removed.
+    pub fn to_outline_rounded(&self, grid: f64, on_curve_only: bool) -> (Outline<Option<PointData>>, QuantizeReport)
+    {
+        let (rounded, report) = self.round_to_grid(grid, on_curve_only);
+        return (rounded.to_outline(), report);
+    }
+
+    fn round_each(&self, round: impl Fn(&Bezier) -> Bezier) -> (Self, QuantizeReport)
+    {
+        let mut report = QuantizeReport { warnings: Vec::new() };
+        let mut contours = Vec::new();
+
+        for (ci, contour) in self.curves.iter().enumerate() {
+            let mut curves = Vec::new();
+
+            for (si, bez) in contour.curves.iter().enumerate() {
+                let quantized = round(bez);
+
+                if quantized.is_collapsed() {
+                    report.warnings.push(format!("Segment {} of contour {} collapsed to a point while rounding and was removed.", si, ci));
+                    continue;
+                }
+
+                curves.push(quantized);
+            }
+
+            if curves.is_empty() {
+                report.warnings.push(format!("Contour {} collapsed entirely while rounding and was removed.", ci));
+                continue;
+            }
+
+            contours.push(Piecewise { curves: curves });
+        }
+
+        return (Piecewise { curves: contours }, report);
+    }
+}
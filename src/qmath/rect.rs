@@ -0,0 +1,103 @@
+use super::*;
+
+// Everything else you'd want to ask a Rect. Piecewise::bounds and AABB_from_points start from a rect with its
+// left at infinity and its right at minus infinity and grow it from there, so a rect with nothing in it is one
+// that's inside out, and everything here treats it that way.
+
+impl Rect {
+    // The rect with nothing in it, encapsulating anything gives back a rect around just that.
+    pub fn empty() -> Rect
+    {
+        return Rect { left: f64::INFINITY, bottom: f64::INFINITY, right: -f64::INFINITY, top: -f64::INFINITY };
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        return !(self.left <= self.right && self.bottom <= self.top);
+    }
+
+    // zero for an empty rect
+    pub fn width(&self) -> f64
+    {
+        if self.is_empty() { return 0.; }
+        return self.right - self.left;
+    }
+
+    pub fn height(&self) -> f64
+    {
+        if self.is_empty() { return 0.; }
+        return self.top - self.bottom;
+    }
+
+    pub fn center(&self) -> Vector
+    {
+        return Vector { x: (self.left + self.right) / 2., y: (self.bottom + self.top) / 2. };
+    }
+
+    // Unlike contains this has no slack, edges count as inside.
+    pub fn contains_point(&self, p: Vector) -> bool
+    {
+        return p.x >= self.left && p.x <= self.right && p.y >= self.bottom && p.y <= self.top;
+    }
+
+    // An empty rect is inside everything, and nothing but an empty rect is inside an empty one.
+    pub fn contains_rect(&self, other: &Rect) -> bool
+    {
+        if other.is_empty() { return true; }
+
+        return other.left >= self.left && other.right <= self.right && other.bottom >= self.bottom && other.top <= self.top;
+    }
+
+    // Rects that only touch along an edge or at a corner overlap.
+    pub fn overlaps(&self, other: &Rect) -> bool
+    {
+        return self.left <= other.right && other.left <= self.right &&
+            self.bottom <= other.top && other.bottom <= self.top;
+    }
+
+    // How far apart the closest points of the two are, zero if they overlap. An empty rect is infinitely far from
+    // everything.
+    pub fn distance_to_rect(&self, other: &Rect) -> f64
+    {
+        if self.is_empty() || other.is_empty() { return f64::INFINITY; }
+
+        let dx = f64::max(0., f64::max(self.left - other.right, other.left - self.right));
+        let dy = f64::max(0., f64::max(self.bottom - other.top, other.bottom - self.top));
+        return f64::sqrt(dx * dx + dy * dy);
+    }
+
+    // The part the two have in common, None if they don't overlap. Touching rects give back a rect with no width
+    // or no height.
+    pub fn intersection(&self, other: &Rect) -> Option<Rect>
+    {
+        if !self.overlaps(other) { return None; }
+
+        return Some(Rect {
+            left: f64::max(self.left, other.left),
+            right: f64::min(self.right, other.right),
+            bottom: f64::max(self.bottom, other.bottom),
+            top: f64::min(self.top, other.top),
+        });
+    }
+
+    // Moves every side in by distance, or out for a negative one. Insetting past the middle leaves an empty rect.
+    pub fn inset(&self, distance: f64) -> Rect
+    {
+        return Rect {
+            left: self.left + distance,
+            right: self.right - distance,
+            bottom: self.bottom + distance,
+            top: self.top - distance,
+        };
+    }
+
+    pub fn outset(&self, distance: f64) -> Rect
+    {
+        return self.inset(-distance);
+    }
+
+    pub fn translated(&self, by: Vector) -> Rect
+    {
+        return Rect { left: self.left + by.x, right: self.right + by.x, bottom: self.bottom + by.y, top: self.top + by.y };
+    }
+}
@@ -0,0 +1,169 @@
+use super::*;
+use super::seams::fit_handles;
+
+// how far off of constant speed we're happy with, as a fraction of it
+const SPEED_TOLERANCE: f64 = 0.1;
+// samples per output segment, for fitting and for checking the fit
+const REPARAM_SAMPLES: usize = 16;
+// how many times we halve the target segment length before settling for what we've got
+const MAX_REFINEMENTS: usize = 10;
+
+// The cubic's speed at t, from the coefficients since Bezier's derivative is a backwards finite difference.
+fn speed(bez: &Bezier, t: f64) -> f64
+{
+    let cp = bez.to_control_points();
+    let mt = 1. - t;
+    let d = (cp[1] + -cp[0]) * (3. * mt * mt) + (cp[2] + -cp[1]) * (6. * mt * t) + (cp[3] + -cp[2]) * (3. * t * t);
+    return d.magnitude();
+}
+
+// One cubic tracing the original curve between arc lengths a and b with its parameter following arc length as
+// closely as we can get it, and how far the fit strays from the curve.
+fn fit_piece(measure: &PathMeasure, a: f64, b: f64) -> (Bezier, f64)
+{
+    let mut points = Vec::with_capacity(REPARAM_SAMPLES + 1);
+    let mut u = Vec::with_capacity(REPARAM_SAMPLES + 1);
+    for k in 0..=REPARAM_SAMPLES {
+        let f = k as f64 / REPARAM_SAMPLES as f64;
+        points.push(measure.pos_tan_at(a + (b - a) * f).0);
+        u.push(f);
+    }
+
+    let (start, t0) = measure.pos_tan_at(a);
+    let (end, t1) = measure.pos_tan_at(b);
+
+    // Fitting against samples spaced evenly by arc length, each given the parameter it should have, is what
+    // evens out the speed. The handle lengths are the only freedom so the ends and their tangents stay put.
+    let fitted = fit_handles(&points, &u, start, end, t0, -t1);
+    let error = points.iter().fold(0., |worst: f64, p| f64::max(worst, fitted.nearest(*p).1));
+
+    return (fitted, error);
+}
+
+impl Piecewise<Bezier>
+{
+    // A copy of the contour whose global t advances in proportion to arc length. Curves are split into pieces of
+    // about the same length and each piece is refit so its speed is close to constant, the traced shape stays
+    // within max_error of the original. We also hand back the residual speed variation, the largest relative
+    // difference between the output's speed anywhere and its average, so 0.05 means within 5% everywhere.
+    //
+    // The original curve boundaries are kept since corners can't be fit across, so pieces only come out equal
+    // in length to within the rounding of each curve's length to a whole number of pieces. We keep halving the
+    // target length until both that and the speed within each piece are within 10%, or we give up trying.
+    pub fn reparameterize_arclen(&self, max_error: f64) -> (Piecewise<Bezier>, f64)
+    {
+        let measures: Vec<(Piecewise<Bezier>, f64)> = self.curves.iter().map(|bez| {
+            let single = Piecewise { curves: vec![bez.clone()] };
+            let length = PathMeasure::new(&single).length();
+            (single, length)
+        }).collect();
+
+        let total: f64 = measures.iter().map(|m| m.1).sum();
+        if self.curves.is_empty() || total <= 1e-9 { return (self.clone(), 0.); }
+
+        let mut target = total / self.curves.len() as f64;
+        let mut best: Option<(Piecewise<Bezier>, f64)> = None;
+
+        for _ in 0..=MAX_REFINEMENTS {
+            let mut curves = Vec::new();
+            let mut lengths = Vec::new();
+            let mut worst_error: f64 = 0.;
+
+            for (single, length) in &measures {
+                // zero length curves would only be stalled time, there's no arc length to spread over them
+                if *length <= 1e-9 { continue; }
+
+                let measure = PathMeasure::new(single);
+                let pieces = f64::max((length / target).round(), 1.) as usize;
+                for i in 0..pieces {
+                    let a = length * i as f64 / pieces as f64;
+                    let b = length * (i + 1) as f64 / pieces as f64;
+                    let (fitted, error) = fit_piece(&measure, a, b);
+                    worst_error = f64::max(worst_error, error);
+                    curves.push(fitted);
+                    lengths.push(b - a);
+                }
+            }
+
+            // in global t every piece gets the same share so the average speed is the average piece length
+            let mean = lengths.iter().sum::<f64>() / lengths.len() as f64;
+            let mut variation: f64 = 0.;
+            for bez in &curves {
+                for k in 0..=REPARAM_SAMPLES {
+                    let s = speed(bez, k as f64 / REPARAM_SAMPLES as f64);
+                    variation = f64::max(variation, (s / mean - 1.).abs());
+                }
+            }
+
+            let output = Piecewise { curves: curves };
+            if worst_error <= max_error && variation <= SPEED_TOLERANCE {
+                return (output, variation);
+            }
+
+            // past the error bound we'd rather have more pieces than fewer, otherwise keep whichever was most even
+            if worst_error <= max_error && best.as_ref().map_or(true, |b| variation < b.1) {
+                best = Some((output, variation));
+            }
+
+            target = target / 2.;
+        }
+
+        // nothing stayed within max_error, splitting that finely would have been needed to trace the shape at all
+        return best.unwrap_or_else(|| (self.clone(), f64::INFINITY));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(x: f64, y: f64) -> Vector
+    {
+        return Vector { x: x, y: y };
+    }
+
+    // A cubic with both handles bunched up near its start, so its own parameter crawls there and races along the
+    // rest, followed by a line.
+    fn skewed() -> Piecewise<Bezier>
+    {
+        return Piecewise::new(vec![
+            Bezier::from_control_points(v(0., 0.), v(10., 40.), v(20., 60.), v(400., 100.)),
+            Bezier::from_control_points(v(400., 100.), v(400., 100.), v(600., 0.), v(600., 0.)),
+        ]);
+    }
+
+    #[test]
+    fn even_speed_same_shape()
+    {
+        let original = skewed();
+        let (reparam, variation) = original.reparameterize_arclen(0.1);
+        assert!(variation < SPEED_TOLERANCE, "{}", variation);
+
+        // the same ends, and it traces the same shape both ways round
+        assert!(reparam.curves[0].evaluate(0.).is_near(v(0., 0.), 1e-9));
+        assert!(reparam.curves.last().unwrap().evaluate(1.).is_near(v(600., 0.), 1e-9));
+        for (from, to) in [(&original, &reparam), (&reparam, &original)].iter() {
+            for bez in &from.curves {
+                for k in 0..=50 {
+                    let p = bez.evaluate(k as f64 / 50.);
+                    let (_, _, d) = to.nearest(p).unwrap();
+                    assert!(d <= 0.1 + 1e-6, "{:?} is {} away", p, d);
+                }
+            }
+        }
+
+        // global t covers every segment in the same share, so the speed along the whole contour against its
+        // average is each segment's speed against the average segment length
+        let mean = PathMeasure::new(&reparam).length() / reparam.curves.len() as f64;
+        for bez in &reparam.curves {
+            for k in 0..=40 {
+                let s = speed(bez, k as f64 / 40.);
+                assert!((s / mean - 1.).abs() < SPEED_TOLERANCE, "speed {} against {}", s, mean);
+            }
+        }
+
+        // before, the cubic's speed was nowhere near even
+        let before = PathMeasure::new(&Piecewise::new(vec![original.curves[0].clone()])).length();
+        assert!(speed(&original.curves[0], 0.05) < 0.5 * before);
+    }
+}
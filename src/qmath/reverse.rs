@@ -0,0 +1,99 @@
+use super::*;
+
+// Flipping direction, mostly for fixing up winding. A reversed Piecewise evaluated at t is the original at 1 - t.
+
+impl Bezier {
+    pub fn reverse(&self) -> Bezier
+    {
+        let p = self.to_control_points();
+        return Bezier::from_control_points(p[3], p[2], p[1], p[0]);
+    }
+}
+
+impl Piecewise<Bezier>
+{
+    pub fn reverse(&self) -> Piecewise<Bezier>
+    {
+        return Piecewise { curves: self.curves.iter().rev().map(|bez| bez.reverse()).collect() };
+    }
+}
+
+impl Piecewise<Piecewise<Bezier>>
+{
+    // Every contour reversed in place, their order stays the same.
+    pub fn reverse(&self) -> Piecewise<Piecewise<Bezier>>
+    {
+        return Piecewise { curves: self.curves.iter().map(|contour| contour.reverse()).collect() };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::read_glif_piecewise;
+    use std::path::Path;
+
+    fn skeleton(name: &str) -> Piecewise<Piecewise<Bezier>>
+    {
+        return read_glif_piecewise(&Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures").join("skeletons").join(name)).unwrap();
+    }
+
+    fn v(x: f64, y: f64) -> Vector
+    {
+        return Vector { x: x, y: y };
+    }
+
+    #[test]
+    fn reversed_at_t_is_original_at_one_minus_t()
+    {
+        for name in ["closed_mixed.glif", "s_curve.glif", "circle.glif"].iter() {
+            let outline = skeleton(name);
+            let reversed = outline.reverse();
+            assert_eq!(reversed.len(), outline.len());
+
+            for (contour, backwards) in outline.segs().zip(reversed.segs()) {
+                for i in 0..=100 {
+                    let t = i as f64 / 100.;
+                    assert!(backwards.evaluate(t).is_near(contour.evaluate(1. - t), 1e-9), "{} at {}", name, t);
+                }
+                for (bez, backwards) in contour.segs().zip(backwards.segs().rev()) {
+                    assert!(backwards.evaluate(0.3).is_near(bez.evaluate(0.7), 1e-9));
+                }
+            }
+
+            // twice is where we started, exactly
+            for (contour, twice) in outline.segs().zip(reversed.reverse().segs()) {
+                for (bez, twice) in contour.segs().zip(twice.segs()) {
+                    assert_eq!(bez.to_control_points(), twice.to_control_points());
+                }
+            }
+        }
+    }
+
+    // Written out as a glif contour, every point's handles trade places, what led into it now leads out of it.
+    #[test]
+    fn handles_swap_round_trip()
+    {
+        let circle = skeleton("circle.glif");
+        let reversed = circle[0].reverse();
+        let contour = reversed.to_contour();
+
+        let right = contour.iter().find(|p| Vector::from_point(p).is_near(v(600., 300.), 1e-9)).unwrap();
+        assert!(Vector::from_handle(right, WhichHandle::A).is_near(v(600., 134.), 1e-9));
+        assert!(Vector::from_handle(right, WhichHandle::B).is_near(v(600., 466.), 1e-9));
+
+        // lines and curves both make it back in and out of a glif contour unchanged
+        for name in ["circle.glif", "closed_mixed.glif"].iter() {
+            for contour in skeleton(name).segs() {
+                let reversed = contour.reverse();
+                let read_back = Piecewise::from_contour(&reversed.to_contour());
+                assert_eq!(read_back.len(), reversed.len(), "{}", name);
+                for (a, b) in reversed.segs().zip(read_back.segs()) {
+                    for (p, q) in a.to_control_points().iter().zip(b.to_control_points().iter()) {
+                        assert!(p.is_near(*q, 1e-9), "{}: {:?} came back as {:?}", name, p, q);
+                    }
+                }
+            }
+        }
+    }
+}
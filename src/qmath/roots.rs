@@ -0,0 +1,400 @@
+// Real root finding for the polynomials that come up working with cubic beziers: extrema, inflections, axis
+// intersections and the like. Everything here takes its coefficients highest degree first and returns the real
+// roots that fall in [min, max], sorted, with near duplicates merged.
+//
+// Coefficients that are tiny compared to the rest get treated as zero and we drop down a degree. A cubic with a
+// leading coefficient of 1e-15 is for all intents a quadratic, and its "extra" root is somewhere out near 1e15
+// where nobody is looking for it.
+const EPSILON: f64 = 1e-12;
+
+// roots that land just outside the interval through rounding still count, we clamp them in
+const INTERVAL_SLOP: f64 = 1e-9;
+
+// cubics whose leading coefficient is less than this next to the biggest one get solved flipped around, see
+// solve_cubic
+const FLIP_BELOW: f64 = 1e-3;
+
+fn is_negligible(v: f64, scale: f64) -> bool
+{
+    return v.abs() <= EPSILON * scale;
+}
+
+// Sorts, clamps and dedups the candidate roots.
+fn finish(mut roots: Vec<f64>, min: f64, max: f64) -> Vec<f64>
+{
+    let slop = INTERVAL_SLOP * f64::max(1., f64::max(min.abs(), max.abs()));
+
+    roots.retain(|r| r.is_finite() && *r >= min - slop && *r <= max + slop);
+    for r in roots.iter_mut() {
+        *r = r.max(min).min(max);
+    }
+
+    roots.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    roots.dedup_by(|a, b| (*a - *b).abs() <= INTERVAL_SLOP * f64::max(1., b.abs()));
+
+    return roots;
+}
+
+// a * x + b = 0
+pub fn solve_linear(a: f64, b: f64, min: f64, max: f64) -> Vec<f64>
+{
+    if is_negligible(a, b.abs()) || a == 0. {
+        return Vec::new();
+    }
+
+    return finish(vec![-b / a], min, max);
+}
+
+// a * x^2 + b * x + c = 0
+pub fn solve_quadratic(a: f64, b: f64, c: f64, min: f64, max: f64) -> Vec<f64>
+{
+    let scale = f64::max(b.abs(), c.abs());
+    if is_negligible(a, scale) || a == 0. {
+        return solve_linear(b, c, min, max);
+    }
+
+    let mut discriminant = b * b - 4. * a * c;
+
+    // a discriminant that's only negative because of rounding is a double root
+    if discriminant < 0. && -discriminant <= EPSILON * (b * b + (4. * a * c).abs()) {
+        discriminant = 0.;
+    }
+
+    if discriminant < 0. {
+        return Vec::new();
+    }
+
+    // The textbook formula loses everything to cancellation when b^2 dwarfs 4ac, one of the two roots comes out as
+    // the difference of two nearly equal numbers. Computing q first and getting the other root from c/q avoids it.
+    // https://people.csail.mit.edu/bkph/articles/Quadratics.pdf
+    let q = -0.5 * (b + f64::copysign(discriminant.sqrt(), b));
+
+    let mut roots = vec![q / a];
+    if q != 0. {
+        roots.push(c / q);
+    } else {
+        roots.push(0.);
+    }
+
+    return finish(roots, min, max);
+}
+
+// a * x^3 + b * x^2 + c * x + d = 0
+#[allow(non_snake_case)]
+pub fn solve_cubic(a: f64, b: f64, c: f64, d: f64, min: f64, max: f64) -> Vec<f64>
+{
+    // We scale everything so the biggest coefficient is 1, this keeps the intermediate values below from
+    // overflowing or underflowing when the inputs have a large dynamic range.
+    let scale = a.abs().max(b.abs()).max(c.abs()).max(d.abs());
+    if scale == 0. {
+        return Vec::new();
+    }
+
+    let (a, b, c, d) = (a / scale, b / scale, c / scale, d / scale);
+
+    if is_negligible(a, 1.) {
+        return solve_quadratic(b, c, d, min, max);
+    }
+
+    // a couple newton steps on the original polynomial to clean up whatever the closed forms lost
+    let polish = |root: &mut f64| {
+        for _ in 0..2 {
+            let x = *root;
+            let f = ((a * x + b) * x + c) * x + d;
+            let df = (3. * a * x + 2. * b) * x + c;
+            if df == 0. { break; }
+
+            let next = x - f / df;
+            if !next.is_finite() || (((a * next + b) * next + c) * next + d).abs() > f.abs() { break; }
+            *root = next;
+        }
+    };
+
+    // With a small leading coefficient one root is far out and the shift below is huge, the others come out as the
+    // difference of huge numbers and lose most of their digits. Flipping the polynomial around, x = 1/y, swaps the
+    // far root for one near zero and leaves a cubic that's well behaved, so we solve that and flip the roots back.
+    if a.abs() < FLIP_BELOW && d.abs() > a.abs() {
+        let mut roots: Vec<f64> = solve_cubic(d, c, b, a, -f64::MAX, f64::MAX).into_iter()
+            .filter(|y| *y != 0.)
+            .map(|y| 1. / y)
+            .collect();
+        roots.iter_mut().for_each(polish);
+        return finish(roots, min, max);
+    }
+
+    // normalize to x^3 + A x^2 + B x + C and substitute x = y - A/3 to get the depressed cubic y^3 + p y + q
+    let A = b / a;
+    let B = c / a;
+    let C = d / a;
+    let shift = A / 3.;
+
+    let p = B - A * A / 3.;
+    let q = 2. * A * A * A / 27. - A * B / 3. + C;
+
+    let half_q = q / 2.;
+    let third_p = p / 3.;
+    let discriminant = half_q * half_q + third_p * third_p * third_p;
+    let tolerance = EPSILON * f64::max(1., f64::max(half_q * half_q, (third_p * third_p * third_p).abs()));
+
+    let mut roots = Vec::new();
+
+    if discriminant.abs() <= tolerance {
+        // repeated roots, if p is zero too it's a triple root
+        if is_negligible(p, f64::max(1., A * A)) {
+            roots.push(-shift);
+        } else {
+            roots.push(3. * q / p - shift);
+            roots.push(-3. * q / (2. * p) - shift);
+        }
+    } else if discriminant > 0. {
+        // one real root, Cardano
+        let sqrt = discriminant.sqrt();
+        let u = f64::cbrt(-half_q + sqrt);
+        let v = f64::cbrt(-half_q - sqrt);
+        roots.push(u + v - shift);
+    } else {
+        // three real roots, the trigonometric form is stable here where Cardano would need complex cube roots
+        let r = (-third_p).sqrt();
+        let phi = f64::acos(f64::clamp(-half_q / (r * r * r), -1., 1.));
+
+        for k in 0..3 {
+            roots.push(2. * r * f64::cos((phi + 2. * std::f64::consts::PI * k as f64) / 3.) - shift);
+        }
+    }
+
+    roots.iter_mut().for_each(polish);
+
+    return finish(roots, min, max);
+}
+
+// The roots of a cubic given as [a, b, c, d] in the curve's own t, the way Bezier::x_polynomial hands them out.
+// Roots up to epsilon outside [0, 1] are kept and clamped onto it, anything a curve's endpoint would be found at
+// shouldn't get lost to rounding just because it came out as -1e-10.
+pub fn solve_in_unit_interval(coefficients: [f64; 4], epsilon: f64) -> Vec<f64>
+{
+    let [a, b, c, d] = coefficients;
+    let roots = solve_cubic(a, b, c, d, -epsilon, 1. + epsilon);
+
+    // finish only lets rounding errors past the ends, these have been let in already so we clamp them ourselves
+    return finish(roots.into_iter().map(|r| f64::clamp(r, 0., 1.)).collect(), 0., 1.);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::qmath::{Bezier, Evaluate};
+    use crate::testing::bezier_points_strategy;
+    use proptest::prelude::*;
+
+    // roots step apart from -steps * step to steps * step, with a quarter that hits zero, both ends of the unit
+    // interval and plenty between
+    fn grid(steps: i32, step: f64) -> Vec<f64>
+    {
+        return (-steps..=steps).map(|i| i as f64 * step).collect();
+    }
+
+    // The distinct values of expected in order, merged the same way finish does.
+    fn distinct(mut expected: Vec<f64>) -> Vec<f64>
+    {
+        expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        expected.dedup();
+        return expected;
+    }
+
+    fn assert_roots(found: &[f64], expected: &[f64], tolerance: f64, what: &str)
+    {
+        assert_eq!(found.len(), expected.len(), "{}: found {:?}, expected {:?}", what, found, expected);
+        for (f, e) in found.iter().zip(expected) {
+            assert!((f - e).abs() <= tolerance, "{}: found {:?}, expected {:?}", what, found, expected);
+        }
+    }
+
+    #[test]
+    fn quadratic_roots()
+    {
+        let roots = grid(8, 0.25);
+        for scale in [1., 1e-3, 1e3, -2.].iter() {
+            for (i, &r) in roots.iter().enumerate() {
+                for &s in &roots[i..] {
+                    // scale * (x - r)(x - s)
+                    let found = solve_quadratic(*scale, -scale * (r + s), scale * r * s, -10., 10.);
+                    assert_roots(&found, &distinct(vec![r, s]), 1e-9, &format!("{} (x - {})(x - {})", scale, r, s));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn quadratic_without_real_roots()
+    {
+        assert!(solve_quadratic(1., 0., 1., -10., 10.).is_empty());
+        assert!(solve_quadratic(1., -2., 1.0001, -10., 10.).is_empty());
+    }
+
+    // a leading coefficient that's nothing next to the rest is a line, and nothing at all has no roots
+    #[test]
+    fn quadratic_drops_to_linear()
+    {
+        assert_roots(&solve_quadratic(0., 2., -1., -10., 10.), &[0.5], 1e-12, "2x - 1");
+        assert_roots(&solve_quadratic(1e-20, 2., -1., -10., 10.), &[0.5], 1e-12, "1e-20 x^2 + 2x - 1");
+        assert!(solve_quadratic(0., 0., 1., -10., 10.).is_empty());
+    }
+
+    #[test]
+    fn cubic_roots()
+    {
+        let roots = grid(6, 0.25);
+        for scale in [1., 1e-3, 1e3, -2.].iter() {
+            for (i, &r) in roots.iter().enumerate() {
+                for (j, &s) in roots.iter().enumerate().skip(i) {
+                    for &u in &roots[j..] {
+                        // scale * (x - r)(x - s)(x - u)
+                        let (b, c, d) = (-(r + s + u), r * s + r * u + s * u, -r * s * u);
+                        let found = solve_cubic(*scale, scale * b, scale * c, scale * d, -10., 10.);
+
+                        // a double root sits where the cubic only touches zero, rounding moves it by about the
+                        // square root of what it moves a single root by
+                        let expected = distinct(vec![r, s, u]);
+                        let tolerance = if expected.len() < 3 { 1e-6 } else { 1e-9 };
+                        assert_roots(&found, &expected, tolerance, &format!("{} (x - {})(x - {})(x - {})", scale, r, s, u));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cubic_with_one_real_root()
+    {
+        // (x - 0.5)(x^2 + 1)
+        assert_roots(&solve_cubic(1., -0.5, 1., -0.5, -10., 10.), &[0.5], 1e-12, "(x - 0.5)(x^2 + 1)");
+        // x^3 + 1
+        assert_roots(&solve_cubic(1., 0., 0., 1., -10., 10.), &[-1.], 1e-12, "x^3 + 1");
+    }
+
+    // Two roots closer together than the discriminant can tell apart. Whether they come back as two roots or get
+    // merged into one, whatever comes back has to be within the gap of both of them, and roots that are further
+    // apart than that have to come back as two.
+    #[test]
+    fn near_double_roots()
+    {
+        for &r in [-0.75, 0., 0.3, 1.].iter() {
+            for &gap in [1e-2, 1e-4, 1e-6, 1e-8, 1e-10].iter() {
+                let (s, u) = (r + gap, 2.);
+                let (b, c, d) = (-(r + s + u), r * s + r * u + s * u, -r * s * u);
+                let found = solve_cubic(1., b, c, d, -10., 10.);
+                let what = format!("(x - {})(x - {})(x - {})", r, s, u);
+
+                assert!(found.len() == 2 || found.len() == 3, "{}: found {:?}", what, found);
+                assert!((found[found.len() - 1] - u).abs() <= 1e-9, "{}: found {:?}", what, found);
+                for f in &found[..found.len() - 1] {
+                    assert!(*f >= r - 1e-6 && *f <= s + 1e-6, "{}: found {:?}", what, found);
+                }
+                if gap >= 1e-4 {
+                    assert_roots(&found, &[r, s, u], 1e-6, &what);
+                }
+
+                // and the same pair as a quadratic
+                let found = solve_quadratic(1., -(r + s), r * s, -10., 10.);
+                assert!(found.len() == 1 || found.len() == 2, "{}: found {:?}", what, found);
+                for f in &found {
+                    assert!(*f >= r - 1e-6 && *f <= s + 1e-6, "(x - {})(x - {}): found {:?}", r, s, found);
+                }
+                if gap >= 1e-4 {
+                    assert_roots(&found, &[r, s], 1e-9, &what);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn triple_root()
+    {
+        for &r in [-1., 0., 0.5, 1.].iter() {
+            let found = solve_cubic(1., -3. * r, 3. * r * r, -r * r * r, -10., 10.);
+            assert_roots(&found, &[r], 1e-6, &format!("(x - {})^3", r));
+        }
+    }
+
+    // only the roots in the interval come back, ones just outside through rounding are clamped onto it
+    #[test]
+    fn roots_outside_the_interval()
+    {
+        // (x + 1)(x - 0.5)(x - 2)
+        assert_roots(&solve_cubic(1., -1.5, -1.5, 1., 0., 1.), &[0.5], 1e-12, "(x + 1)(x - 0.5)(x - 2)");
+        assert_roots(&solve_cubic(1., -1.5, -1.5, 1., -1., 2.), &[-1., 0.5, 2.], 1e-12, "(x + 1)(x - 0.5)(x - 2)");
+        assert_roots(&solve_quadratic(1., -1. + 1e-12, -1e-12, 0., 1.), &[0., 1.], 0., "(x + 1e-12)(x - 1)");
+        assert_roots(&solve_in_unit_interval([1., -1.5, 0.5, 0.], 1e-9), &[0., 0.5, 1.], 1e-12, "x (x - 0.5)(x - 1)");
+    }
+
+    // A cubic that's nearly a quadratic, with its third root a hundred million out. Solved directly the two near
+    // roots are the difference of numbers around a hundred million and used to come back merged into one.
+    #[test]
+    fn far_third_root()
+    {
+        for &far in [1e4, 1e8, -1e8].iter() {
+            // (x - far)(x - 0.25)(x - 0.75) / far
+            let (r, s) = (0.25, 0.75);
+            let found = solve_cubic(1. / far, -(1. + (r + s) / far), (r + s) + r * s / far, -r * s, 0., 1.);
+            assert_roots(&found, &[r, s], 1e-9, &format!("(x - {})(x - {})(x - {})", far, r, s));
+        }
+    }
+
+    // Where x(t) of a curve crosses the middle of its x range, found by bisecting every sign change over a fine grid.
+    // Touching without crossing isn't a sign change, so this only checks the roots bisection can see.
+    fn bisected(poly: [f64; 4], level: f64) -> Vec<f64>
+    {
+        let f = |t: f64| ((poly[0] * t + poly[1]) * t + poly[2]) * t + poly[3] - level;
+        let mut output = Vec::new();
+        for k in 0..1000 {
+            let (mut low, mut high) = (k as f64 / 1000., (k + 1) as f64 / 1000.);
+            if f(low) == 0. { output.push(low); continue; }
+            if f(low).signum() == f(high).signum() { continue; }
+            for _ in 0..60 {
+                let mid = (low + high) / 2.;
+                if f(mid).signum() == f(low).signum() { low = mid; } else { high = mid; }
+            }
+            output.push((low + high) / 2.);
+        }
+        return output;
+    }
+
+    proptest! {
+        #[test]
+        fn polynomials_and_bisection_agree(points in bezier_points_strategy())
+        {
+            let bez = Bezier::from_control_points(points[0], points[1], points[2], points[3]);
+            let (x, y) = (bez.x_polynomial(), bez.y_polynomial());
+            let scale = points.iter().fold(1., |m, p| f64::max(m, f64::max(p.x.abs(), p.y.abs())));
+            for k in 0..=10 {
+                let t = k as f64 / 10.;
+                let p = bez.evaluate(t);
+                prop_assert!((((x[0] * t + x[1]) * t + x[2]) * t + x[3] - p.x).abs() <= 1e-9 * scale);
+                prop_assert!((((y[0] * t + y[1]) * t + y[2]) * t + y[3] - p.y).abs() <= 1e-9 * scale);
+            }
+
+            // a curve standing still in x is zero everywhere there, there's nothing to find
+            let bounds = bez.bounds();
+            if bounds.right - bounds.left <= 1e-9 * scale { return Ok(()); }
+            let level = (bounds.left + bounds.right) / 2.;
+            let found = solve_in_unit_interval([x[0], x[1], x[2], x[3] - level], 1e-9);
+            for root in bisected(x, level) {
+                prop_assert!(found.iter().any(|f| (f - root).abs() < 1e-6), "bisection found {} but we found {:?}", root, found);
+            }
+            for f in &found {
+                prop_assert!((bez.evaluate(*f).x - level).abs() <= 1e-6 * (bounds.right - bounds.left).max(1.), "{} is off", f);
+            }
+        }
+    }
+
+    // Roots a rounding error outside the unit interval are kept on its ends, further out they're dropped.
+    #[test]
+    fn unit_interval_epsilon()
+    {
+        // (t + 1e-7)(t - 0.5)(t - 1 - 1e-7)
+        let (r, s, u) = (-1e-7, 0.5, 1. + 1e-7);
+        let poly = [1., -(r + s + u), r * s + r * u + s * u, -r * s * u];
+        assert_roots(&solve_in_unit_interval(poly, 1e-6), &[0., 0.5, 1.], 1e-12, "just outside");
+        assert_roots(&solve_in_unit_interval(poly, 1e-9), &[0.5], 1e-12, "outside a tighter epsilon");
+    }
+}
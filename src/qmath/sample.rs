@@ -0,0 +1,42 @@
+use super::*;
+
+// Lots of points along a curve at once. Evenly in t is just a loop over evaluate, evenly along the curve needs the
+// arc length table, which is the expensive part, so it's built once for all the points rather than once per point.
+
+// how many pieces the curve's cut into for its arc length table, the same as ArcLengthParameterization::from
+const SAMPLE_ARCLEN_CUTS: usize = 10000;
+
+// None gives nothing, one gives the start, and two or more always include both ends exactly.
+pub(super) fn sample(curve: &impl Evaluate, n: usize) -> Vec<Vector>
+{
+    match n {
+        0 => Vec::new(),
+        1 => vec![curve.evaluate(0.)],
+        _ => (0..n).map(|i| curve.evaluate(if i == n - 1 { 1. } else { i as f64 / (n - 1) as f64 })).collect(),
+    }
+}
+
+// Starts at t = 0 and steps spacing along the curve each time. The end is always included, so the last gap is
+// usually shorter than spacing. A spacing that isn't positive gives just the ends, and a curve with no length just
+// its start.
+pub(super) fn sample_by_arclength(curve: &impl Evaluate, spacing: f64) -> Vec<(f64, Vector)>
+{
+    let table = ArcLengthParameterization::from_cuts(curve, SAMPLE_ARCLEN_CUTS);
+    let total = table.get_total_arclen();
+
+    let mut output = vec![(0., curve.evaluate(0.))];
+    if !(total > 0.) { return output; }
+
+    if spacing > 0. && spacing.is_finite() {
+        let mut k = 1;
+        // anything closer to the end than a thousandth of spacing would just be the end again
+        while k as f64 * spacing < total - spacing * 1e-3 {
+            let t = table.parameterize(k as f64 * spacing / total);
+            output.push((t, curve.evaluate(t)));
+            k = k + 1;
+        }
+    }
+
+    output.push((1., curve.evaluate(1.)));
+    return output;
+}
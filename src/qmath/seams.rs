@@ -0,0 +1,250 @@
+use super::*;
+
+// how close a joint in the outline has to be to a tagged seam to count as one, simplify goes through skia's f32s
+// so they won't line up exactly
+const SEAM_TOLERANCE: f64 = 1e-2;
+// samples taken from each of the two curves when fitting their replacement
+const SEAM_SAMPLES: usize = 16;
+
+// The direction a curve leaves its start and arrives at its end in. We look at the control points rather than the
+// derivative, lines are stored with their handles on their ends so we fall back to the next point that isn't.
+fn start_direction(bez: &Bezier) -> Vector
+{
+    let cp = bez.to_control_points();
+    let dir = if !cp[1].is_near(cp[0], 1e-9) { cp[1] + -cp[0] }
+        else if !cp[2].is_near(cp[0], 1e-9) { cp[2] + -cp[0] }
+        else { cp[3] + -cp[0] };
+    return dir.normalize();
+}
+
+fn end_direction(bez: &Bezier) -> Vector
+{
+    let cp = bez.to_control_points();
+    let dir = if !cp[3].is_near(cp[2], 1e-9) { cp[3] + -cp[2] }
+        else if !cp[3].is_near(cp[1], 1e-9) { cp[3] + -cp[1] }
+        else { cp[3] + -cp[0] };
+    return dir.normalize();
+}
+
+fn is_line(bez: &Bezier) -> bool
+{
+    let cp = bez.to_control_points();
+    return cp[1].is_near(cp[0], 1e-9) && cp[2].is_near(cp[3], 1e-9);
+}
+
+// Points along both curves, the joint only once.
+fn seam_samples(a: &Bezier, b: &Bezier) -> Vec<Vector>
+{
+    let mut points = Vec::new();
+    for i in 0..SEAM_SAMPLES {
+        points.push(a.evaluate(i as f64 / SEAM_SAMPLES as f64));
+    }
+    for i in 0..=SEAM_SAMPLES {
+        points.push(b.evaluate(i as f64 / SEAM_SAMPLES as f64));
+    }
+    return points;
+}
+
+// Least squares handle lengths for a cubic from start to end leaving and arriving along the given directions, the
+// same approach as Schneider's curve fitting. u holds a parameter for each point.
+pub(super) fn fit_handles(points: &[Vector], u: &[f64], start: Vector, end: Vector, t0: Vector, t1: Vector) -> Bezier
+{
+    let mut c00 = 0.; let mut c01 = 0.; let mut c11 = 0.;
+    let mut x0 = 0.; let mut x1 = 0.;
+
+    for (p, &t) in points.iter().zip(u.iter()) {
+        let mt = 1. - t;
+        let b0 = mt * mt * mt;
+        let b1 = 3. * t * mt * mt;
+        let b2 = 3. * t * t * mt;
+        let b3 = t * t * t;
+
+        let a1 = t0 * b1;
+        let a2 = t1 * b2;
+        let rest = *p + -(start * (b0 + b1) + end * (b2 + b3));
+
+        c00 += a1.dot(a1);
+        c01 += a1.dot(a2);
+        c11 += a2.dot(a2);
+        x0 += a1.dot(rest);
+        x1 += a2.dot(rest);
+    }
+
+    let det = c00 * c11 - c01 * c01;
+    let chord = start.distance(end);
+    let (mut alpha, mut beta) = if det.abs() > 1e-12 {
+        ((x0 * c11 - x1 * c01) / det, (c00 * x1 - c01 * x0) / det)
+    } else {
+        (chord / 3., chord / 3.)
+    };
+
+    // handles pointing backwards make loops, the chord heuristic is a safe fallback
+    if !(alpha > 1e-6 * chord) || !(beta > 1e-6 * chord) {
+        alpha = chord / 3.;
+        beta = chord / 3.;
+    }
+
+    return Bezier::from_control_points(start, start + t0 * alpha, end + t1 * beta, end);
+}
+
+// A single cubic that traces a followed by b within max_error and keeps their outer end tangents, if there is one.
+fn merge_pair(a: &Bezier, b: &Bezier, angle_tol: f64, max_error: f64) -> Option<Bezier>
+{
+    let incoming = end_direction(a);
+    let outgoing = start_direction(b);
+    let cross = incoming.x * outgoing.y - incoming.y * outgoing.x;
+    let angle = cross.atan2(incoming.dot(outgoing)).abs().to_degrees();
+    if !(angle <= angle_tol) { return None; }
+
+    let start = a.to_control_points()[0];
+    let end = b.to_control_points()[3];
+    if start.is_near(end, 1e-9) { return None; }
+
+    // two lines in a row stay a line
+    if is_line(a) && is_line(b) {
+        let merged = Bezier::from_control_points(start, start, end, end);
+        let joint = a.to_control_points()[3];
+        return if merged.nearest(joint).1 <= max_error { Some(merged) } else { None };
+    }
+
+    let t0 = start_direction(a);
+    let t1 = -end_direction(b);
+    let points = seam_samples(a, b);
+
+    // start out parameterized by chord length and then refine against the fit a couple of times
+    let mut u = Vec::with_capacity(points.len());
+    let mut total = 0.;
+    u.push(0.);
+    for i in 1..points.len() {
+        total += points[i].distance(points[i - 1]);
+        u.push(total);
+    }
+    if total < 1e-9 { return None; }
+    for t in u.iter_mut() { *t /= total; }
+
+    let mut fitted = fit_handles(&points, &u, start, end, t0, t1);
+    for _ in 0..3 {
+        for (t, p) in u.iter_mut().zip(points.iter()) {
+            *t = fitted.nearest(*p).0;
+        }
+        fitted = fit_handles(&points, &u, start, end, t0, t1);
+    }
+
+    let error = points.iter().fold(0., |worst: f64, p| f64::max(worst, fitted.nearest(*p).1));
+    if error <= max_error { return Some(fitted); }
+
+    return None;
+}
+
+impl Piecewise<Bezier>
+{
+    // Replaces each pair of curves meeting at one of the seam points with a single cubic when they meet smoothly,
+    // to within angle_tol degrees, and the cubic stays within max_error of them. Returns how many were merged.
+    pub fn merge_smooth_seams(&self, seams: &[Vector], angle_tol: f64, max_error: f64) -> (Piecewise<Bezier>, usize)
+    {
+        if self.curves.len() < 2 || seams.is_empty() { return (self.clone(), 0); }
+
+        let closed = self.is_closed();
+        let mut curves = self.curves.clone();
+        let mut merged = 0;
+
+        let mut i = 0;
+        while curves.len() > 1 {
+            let joints = if closed { curves.len() } else { curves.len() - 1 };
+            if i >= joints { break; }
+
+            let j = (i + 1) % curves.len();
+            let joint = curves[i].to_control_points()[3];
+            let is_seam = seams.iter().any(|s| s.is_near(joint, SEAM_TOLERANCE));
+            let replacement = if is_seam { merge_pair(&curves[i], &curves[j], angle_tol, max_error) } else { None };
+
+            if let Some(bez) = replacement {
+                curves[i] = bez;
+                curves.remove(j);
+                merged += 1;
+
+                // the joint at the start of a closed contour is the last one we look at
+                if j == 0 { break; }
+                // the merged curve ends at the next joint now so we look at it again
+                continue;
+            }
+
+            i += 1;
+        }
+
+        return (Piecewise { curves: curves }, merged);
+    }
+}
+
+impl Piecewise<Piecewise<Bezier>>
+{
+    // merge_smooth_seams on every contour, with the total merged. Seams are tagged by where they are rather than by
+    // index so the tags survive simplify renumbering everything.
+    pub fn merge_smooth_seams(&self, seams: &[Vector], angle_tol: f64, max_error: f64) -> (Piecewise<Piecewise<Bezier>>, usize)
+    {
+        let mut total = 0;
+        let curves = self.curves.iter().map(|contour| {
+            let (contour, merged) = contour.merge_smooth_seams(seams, angle_tol, max_error);
+            total += merged;
+            contour
+        }).collect();
+
+        return (Piecewise { curves: curves }, total);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    fn v(x: f64, y: f64) -> Vector
+    {
+        return Vector { x: x, y: y };
+    }
+
+    fn line(from: Vector, to: Vector) -> Bezier
+    {
+        return Bezier::from_control_points(from, from, to, to);
+    }
+
+    // A line from (0, 0) to (200, 0) stroked 40 wide with round caps the way simplify hands it back, with the edges
+    // cut a hair short of the caps' corners and the gaps filled with tiny lines.
+    fn round_capped_stroke() -> Piecewise<Bezier>
+    {
+        let mut curves = Vec::new();
+        curves.push(line(v(0., -20.), v(0.005, -20.)));
+        curves.push(line(v(0.005, -20.), v(199.995, -20.)));
+        curves.push(line(v(199.995, -20.), v(200., -20.)));
+        curves.extend(Piecewise::arc(v(200., 0.), 20., -PI / 2., PI).segs().cloned());
+        curves.push(line(v(200., 20.), v(199.995, 20.)));
+        curves.push(line(v(199.995, 20.), v(0.005, 20.)));
+        curves.push(line(v(0.005, 20.), v(0., 20.)));
+        curves.extend(Piecewise::arc(v(0., 0.), 20., PI / 2., PI).segs().cloned());
+        return Piecewise::new(curves);
+    }
+
+    #[test]
+    fn round_cap_seams_merge()
+    {
+        let stroke = round_capped_stroke();
+        assert_eq!(stroke.len(), 10);
+        let seams = [v(0., -20.), v(200., -20.), v(200., 20.), v(0., 20.)];
+
+        let (merged, count) = stroke.merge_smooth_seams(&seams, 1., 0.01);
+        assert!(merged.len() <= 8, "{} curves left", merged.len());
+        assert_eq!(merged.len() + count, 10);
+
+        // the outline hasn't moved
+        assert!((merged.area() - stroke.area()).abs() < 0.1, "{} rather than {}", merged.area(), stroke.area());
+        for bez in stroke.segs() {
+            for i in 0..=10 {
+                let (_, _, d) = merged.nearest(bez.evaluate(i as f64 / 10.)).unwrap();
+                assert!(d <= 0.01, "{}", d);
+            }
+        }
+
+        // joints that aren't tagged as seams are left alone, even smooth ones
+        assert_eq!(stroke.merge_smooth_seams(&[v(100., 0.)], 1., 0.01).1, 0);
+    }
+}
@@ -0,0 +1,320 @@
+use super::*;
+
+// A flat bounding volume hierarchy over the segments of an outline. Anything that needs to find segments near
+// other segments (intersections, hit-testing) should go through this rather than comparing every pair.
+// Segments are identified by (contour index, segment index) into the Piecewise the index was built from.
+const LEAF_SIZE: usize = 4;
+
+struct Entry {
+    bounds: Rect,
+    contour: usize,
+    segment: usize,
+}
+
+struct Node {
+    bounds: Rect,
+    // leaves cover entries[start..end], interior nodes have two children
+    start: usize,
+    end: usize,
+    children: Option<(usize, usize)>,
+}
+
+pub struct SegmentIndex {
+    nodes: Vec<Node>,
+    entries: Vec<Entry>,
+}
+
+fn union(entries: &[Entry]) -> Rect
+{
+    let mut bounds = entries[0].bounds;
+    for entry in &entries[1..] {
+        bounds = bounds.encapsulate_rect(entry.bounds);
+    }
+
+    return bounds;
+}
+
+impl SegmentIndex {
+    pub fn from_piecewise(outline: &Piecewise<Piecewise<Bezier>>) -> Self
+    {
+        let mut entries = Vec::new();
+        for (contour_idx, contour) in outline.curves.iter().enumerate() {
+            for (segment_idx, bez) in contour.curves.iter().enumerate() {
+                entries.push(Entry { bounds: bez.bounds(), contour: contour_idx, segment: segment_idx });
+            }
+        }
+
+        return Self::from_entries(entries);
+    }
+
+    // The segments of a single contour, all of them in contour 0.
+    pub fn from_contour(contour: &Piecewise<Bezier>) -> Self
+    {
+        return Self::from_rects(contour.curves.iter().map(|bez| bez.bounds()));
+    }
+
+    // An index over whatever the caller has bounds for, the nth rect coming back as (0, n). Handy when the things
+    // being indexed aren't a Piecewise's segments, or their bounds need outsetting by a tolerance first.
+    pub fn from_rects(rects: impl IntoIterator<Item = Rect>) -> Self
+    {
+        let entries = rects.into_iter().enumerate().map(|(i, r)| Entry { bounds: r, contour: 0, segment: i }).collect();
+        return Self::from_entries(entries);
+    }
+
+    fn from_entries(entries: Vec<Entry>) -> Self
+    {
+        let mut index = SegmentIndex { nodes: Vec::new(), entries: entries };
+        if !index.entries.is_empty() {
+            index.build(0, index.entries.len());
+        }
+
+        return index;
+    }
+
+    // Builds the node covering entries[start..end] and returns its index. We split at the median along
+    // whichever axis the node is longest in.
+    fn build(&mut self, start: usize, end: usize) -> usize
+    {
+        let bounds = union(&self.entries[start..end]);
+        let node_idx = self.nodes.len();
+        self.nodes.push(Node { bounds: bounds, start: start, end: end, children: None });
+
+        if end - start <= LEAF_SIZE {
+            return node_idx;
+        }
+
+        let split_x = bounds.right - bounds.left > bounds.top - bounds.bottom;
+        self.entries[start..end].sort_by(|a, b| {
+            let (ac, bc) = (a.bounds.center(), b.bounds.center());
+            let (ak, bk) = if split_x { (ac.x, bc.x) } else { (ac.y, bc.y) };
+            ak.partial_cmp(&bk).unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        let mid = start + (end - start) / 2;
+        let left = self.build(start, mid);
+        let right = self.build(mid, end);
+        self.nodes[node_idx].children = Some((left, right));
+
+        return node_idx;
+    }
+
+    pub fn len(&self) -> usize
+    {
+        return self.entries.len();
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        return self.entries.is_empty();
+    }
+
+    // Every segment whose bounds overlap rect. Results are in no particular order.
+    pub fn query_rect(&self, rect: Rect) -> impl Iterator<Item = (usize, usize)> + '_
+    {
+        return QueryRect {
+            index: self,
+            rect: rect,
+            stack: if self.nodes.is_empty() { Vec::new() } else { vec![0] },
+            leaf: 0..0,
+        };
+    }
+
+    // Broad phase for intersecting two outlines: every pair of segments, one from each index, whose bounds
+    // overlap. Passing the same index twice gives pairs for self-intersection, in which case each pair shows up in
+    // both orders along with every segment paired with itself so the caller should filter those.
+    pub fn query_pairs(&self, other: &SegmentIndex) -> Vec<((usize, usize), (usize, usize))>
+    {
+        let mut pairs = Vec::new();
+        if self.nodes.is_empty() || other.nodes.is_empty() { return pairs; }
+
+        let mut stack = vec![(0, 0)];
+        while let Some((a, b)) = stack.pop() {
+            let node_a = &self.nodes[a];
+            let node_b = &other.nodes[b];
+
+            if !node_a.bounds.overlaps(&node_b.bounds) { continue; }
+
+            match (node_a.children, node_b.children) {
+                (None, None) => {
+                    for ea in &self.entries[node_a.start..node_a.end] {
+                        for eb in &other.entries[node_b.start..node_b.end] {
+                            if ea.bounds.overlaps(&eb.bounds) {
+                                pairs.push(((ea.contour, ea.segment), (eb.contour, eb.segment)));
+                            }
+                        }
+                    }
+                }
+
+                // descend into whichever side still has children, the bigger one if both do
+                (Some((al, ar)), None) => { stack.push((al, b)); stack.push((ar, b)); }
+                (None, Some((bl, br))) => { stack.push((a, bl)); stack.push((a, br)); }
+                (Some((al, ar)), Some((bl, br))) => {
+                    if node_a.end - node_a.start >= node_b.end - node_b.start {
+                        stack.push((al, b)); stack.push((ar, b));
+                    } else {
+                        stack.push((a, bl)); stack.push((a, br));
+                    }
+                }
+            }
+        }
+
+        return pairs;
+    }
+
+    // Branch and bound for the closest pair of segments, one from each index. measure is handed each pair whose
+    // bounds are closer together than the best distance so far, starting from within, along with that distance,
+    // and gives back how close the pair really comes. Anything at least as far as the best so far is never
+    // measured, so it can give back whatever it likes for those. Returns the best distance found, within itself if
+    // nothing beat it. Like query_pairs, passing the same index twice pairs every segment with itself and with
+    // everything else both ways round.
+    pub fn nearest_pairs<F>(&self, other: &SegmentIndex, within: f64, mut measure: F) -> f64
+        where F: FnMut((usize, usize), (usize, usize), f64) -> f64
+    {
+        let mut best = within;
+        if self.nodes.is_empty() || other.nodes.is_empty() { return best; }
+
+        let mut stack = vec![(0, 0)];
+        while let Some((a, b)) = stack.pop() {
+            let node_a = &self.nodes[a];
+            let node_b = &other.nodes[b];
+
+            if !(node_a.bounds.distance_to_rect(&node_b.bounds) < best) { continue; }
+
+            // the nearer child goes on the stack last so it's looked at first and tightens best sooner
+            let push_nearer = |stack: &mut Vec<(usize, usize)>, x: (usize, usize), y: (usize, usize)| {
+                let dx = self.nodes[x.0].bounds.distance_to_rect(&other.nodes[x.1].bounds);
+                let dy = self.nodes[y.0].bounds.distance_to_rect(&other.nodes[y.1].bounds);
+                if dx <= dy { stack.push(y); stack.push(x); } else { stack.push(x); stack.push(y); }
+            };
+
+            match (node_a.children, node_b.children) {
+                (None, None) => {
+                    for ea in &self.entries[node_a.start..node_a.end] {
+                        for eb in &other.entries[node_b.start..node_b.end] {
+                            if ea.bounds.distance_to_rect(&eb.bounds) < best {
+                                best = f64::min(best, measure((ea.contour, ea.segment), (eb.contour, eb.segment), best));
+                            }
+                        }
+                    }
+                }
+
+                (Some((al, ar)), None) => push_nearer(&mut stack, (al, b), (ar, b)),
+                (None, Some((bl, br))) => push_nearer(&mut stack, (a, bl), (a, br)),
+                (Some((al, ar)), Some((bl, br))) => {
+                    if node_a.end - node_a.start >= node_b.end - node_b.start {
+                        push_nearer(&mut stack, (al, b), (ar, b));
+                    } else {
+                        push_nearer(&mut stack, (a, bl), (a, br));
+                    }
+                }
+            }
+        }
+
+        return best;
+    }
+}
+
+struct QueryRect<'a> {
+    index: &'a SegmentIndex,
+    rect: Rect,
+    stack: Vec<usize>,
+    leaf: std::ops::Range<usize>,
+}
+
+impl<'a> Iterator for QueryRect<'a> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize)>
+    {
+        loop {
+            while let Some(i) = self.leaf.next() {
+                let entry = &self.index.entries[i];
+                if entry.bounds.overlaps(&self.rect) {
+                    return Some((entry.contour, entry.segment));
+                }
+            }
+
+            let node = &self.index.nodes[self.stack.pop()?];
+            if !node.bounds.overlaps(&self.rect) { continue; }
+
+            match node.children {
+                Some((left, right)) => { self.stack.push(left); self.stack.push(right); }
+                None => { self.leaf = node.start..node.end; }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    // Two rings of short lines one inside the other, 2000 segments between them.
+    fn rings() -> Piecewise<Piecewise<Bezier>>
+    {
+        let ring = |r: f64| {
+            let points: Vec<Vector> = (0..=1000).map(|i| {
+                let (sin, cos) = (i as f64 / 1000. * 2. * std::f64::consts::PI).sin_cos();
+                Vector { x: r * cos, y: r * sin }
+            }).collect();
+            Piecewise::new(points.windows(2).map(|p| Bezier::from_control_points(p[0], p[0], p[1], p[1])).collect())
+        };
+
+        return Piecewise::new(vec![ring(1000.), ring(990.)]);
+    }
+
+    fn segments(outline: &Piecewise<Piecewise<Bezier>>) -> Vec<((usize, usize), Rect)>
+    {
+        return outline.segs().enumerate()
+            .flat_map(|(c, contour)| contour.segs().enumerate().map(move |(s, bez)| ((c, s), bez.bounds())))
+            .collect();
+    }
+
+    // Every overlapping pair and no others, while looking at a small fraction of all of them.
+    #[test]
+    fn pairs_match_brute_force()
+    {
+        let outline = rings();
+        let index = SegmentIndex::from_piecewise(&outline);
+        assert_eq!(index.len(), 2000);
+
+        let all = segments(&outline);
+        let mut want = HashSet::new();
+        for (a, ra) in &all {
+            for (b, rb) in &all {
+                if ra.overlaps(rb) { want.insert((*a, *b)); }
+            }
+        }
+
+        let pairs = index.query_pairs(&index);
+        let got: HashSet<_> = pairs.iter().cloned().collect();
+        assert_eq!(got.len(), pairs.len());
+        assert_eq!(got, want);
+        assert!(pairs.len() * 10 <= all.len() * all.len(), "{} pairs", pairs.len());
+
+        let rect = Rect { left: 900., bottom: -50., right: 1100., top: 50. };
+        let got: HashSet<_> = index.query_rect(rect).collect();
+        let want: HashSet<_> = all.iter().filter(|(_, r)| r.overlaps(&rect)).map(|(id, _)| *id).collect();
+        assert_eq!(got, want);
+        assert!(!got.is_empty());
+    }
+
+    // The closest pair between the rings is 10 apart, and getting there measures only a sliver of the pairs.
+    #[test]
+    fn nearest_pairs_prunes()
+    {
+        let outline = rings();
+        let (outer, inner) = (SegmentIndex::from_contour(&outline[0]), SegmentIndex::from_contour(&outline[1]));
+
+        let mut measured = 0;
+        let best = outer.nearest_pairs(&inner, f64::INFINITY, |(_, a), (_, b), _| {
+            measured += 1;
+            let (a, b) = (outline[0][a].to_control_points(), outline[1][b].to_control_points());
+            a[0].distance(b[0]).min(a[3].distance(b[3]))
+        });
+
+        assert!((best - 10.).abs() < 1e-9, "{}", best);
+        assert!(measured * 10 <= outer.len() * inner.len(), "{} measured", measured);
+    }
+}
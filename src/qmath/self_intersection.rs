@@ -0,0 +1,212 @@
+use super::*;
+
+// Finding where a single contour crosses itself and cutting away the loops that makes. Offsets around tight corners
+// fold back over themselves and leave a small loop running the wrong way, which fills wrong under nonzero.
+
+// how many times we'll split a contour into loops before taking what we've got
+const MAX_LOOP_DEPTH: usize = 32;
+// crossings closer together than this along the contour are the same crossing
+const SAME_CROSSING: f64 = 1e-7;
+
+// A stretch of one of the contour's curves that only goes one way in x and in y, so it can't cross itself.
+struct Piece {
+    bez: Bezier,
+    segment: usize,
+    t0: f64,
+    t1: f64,
+}
+
+fn monotone_pieces(contour: &Piecewise<Bezier>) -> Vec<Piece>
+{
+    let mut output = Vec::new();
+    for (segment, bez) in contour.curves.iter().enumerate() {
+        let mut cuts = vec![0.];
+        cuts.extend(bez.extrema());
+        cuts.push(1.);
+
+        for (piece, pair) in bez.split_at_multiple(&cuts[1..cuts.len() - 1]).into_iter().zip(cuts.windows(2)) {
+            output.push(Piece { bez: piece, segment: segment, t0: pair[0], t1: pair[1] });
+        }
+    }
+
+    return output;
+}
+
+// Puts the end of the loop exactly on its start, the two ends of a crossing only agree to within tolerance.
+fn close_loop(mut curves: Vec<Bezier>) -> Option<Piecewise<Bezier>>
+{
+    let start = curves.first()?.to_control_points()[0];
+    let last = curves.len() - 1;
+    let p = curves[last].to_control_points();
+    curves[last] = Bezier::from_control_points(p[0], p[1], p[2], start);
+
+    return Some(Piecewise { curves: curves });
+}
+
+fn split_loops(contour: Piecewise<Bezier>, tolerance: f64, depth: usize, output: &mut Vec<Piecewise<Bezier>>)
+{
+    let crossing = if depth < MAX_LOOP_DEPTH { contour.self_intersections(tolerance).into_iter().next() } else { None };
+
+    match crossing {
+        None => output.push(contour),
+        Some((ta, tb)) => {
+            if let Some(inner) = close_loop(contour.cut(ta, tb).curves) { split_loops(inner, tolerance, depth + 1, output); }
+            if let Some(outer) = close_loop(contour.cut(tb, ta).curves) { split_loops(outer, tolerance, depth + 1, output); }
+        }
+    }
+}
+
+impl Piecewise<Bezier>
+{
+    // Where the contour crosses or touches itself, as pairs of global ts with the first smaller. Neighbouring curves
+    // meeting at their joint don't count, and neither does a closed contour's last curve meeting its first. Curves
+    // are cut into pieces between their extrema first so a single curve looping over itself is found too.
+    pub fn self_intersections(&self, tolerance: f64) -> Vec<(f64, f64)>
+    {
+        let n = self.curves.len();
+        if n == 0 { return Vec::new(); }
+
+        let pieces = monotone_pieces(self);
+        let closed = self.is_closed();
+        let global = |piece: &Piece, u: f64| self.global_t(piece.segment, piece.t0 + u * (piece.t1 - piece.t0));
+
+        let index = SegmentIndex::from_rects(pieces.iter().map(|piece| piece.bez.bounds()));
+        let mut pairs: Vec<(usize, usize)> = index.query_pairs(&index).into_iter()
+            .map(|((_, i), (_, j))| (i, j))
+            .filter(|(i, j)| i < j)
+            .collect();
+        // the index hands pairs back in no particular order, and which crossing comes first matters to split_loops
+        pairs.sort();
+
+        let mut output: Vec<(f64, f64)> = Vec::new();
+        for (i, j) in pairs {
+            let (a, b) = (&pieces[i], &pieces[j]);
+
+            let neighbours = j == i + 1;
+            let wrapped = closed && i == 0 && j == pieces.len() - 1;
+
+            for (ua, ub) in a.bez.intersects(&b.bez, tolerance) {
+                if neighbours && ua == 1. && ub == 0. { continue; }
+                if wrapped && ua == 0. && ub == 1. { continue; }
+
+                let (ta, tb) = (global(a, ua), global(b, ub));
+                if (tb - ta).abs() <= SAME_CROSSING { continue; }
+                if wrapped && ta <= SAME_CROSSING && tb >= 1. - SAME_CROSSING { continue; }
+
+                // a crossing right on the cut between two pieces is found from both of them
+                let seen = output.iter().any(|(sa, sb)| (sa - ta).abs() <= SAME_CROSSING && (sb - tb).abs() <= SAME_CROSSING);
+                if !seen { output.push((ta, tb)); }
+            }
+        }
+
+        output.sort_by(|x, y| x.partial_cmp(y).unwrap());
+        return output;
+    }
+
+    // Cuts a closed contour into simple loops at its crossings and keeps the ones that go around the same way the
+    // biggest of them does, dropping the inverted loops a fold leaves behind. Kept loops inside another kept loop
+    // are dropped too so only the outermost boundary is left. An open contour comes back as it is.
+    pub fn remove_self_intersections(&self, tolerance: f64) -> Piecewise<Piecewise<Bezier>>
+    {
+        if self.curves.is_empty() || !self.is_closed() {
+            return Piecewise { curves: vec![self.clone()] };
+        }
+
+        let mut loops = Vec::new();
+        split_loops(self.clone(), tolerance, 0, &mut loops);
+
+        // a figure eight with even lobes has no direction as a whole, so we go by the biggest loop
+        let direction = loops.iter()
+            .max_by(|a, b| a.signed_area().abs().partial_cmp(&b.signed_area().abs()).unwrap())
+            .map(|l| l.winding_direction())
+            .unwrap();
+        let kept: Vec<Piecewise<Bezier>> = loops.into_iter().filter(|l| l.winding_direction() == direction).collect();
+
+        let mut output = Vec::new();
+        for (i, contour) in kept.iter().enumerate() {
+            let sample = contour.curves[0].evaluate(0.5);
+            let inside_another = kept.iter().enumerate().any(|(j, other)| i != j && other.winding_number(sample) != 0);
+            if !inside_another { output.push(contour.clone()); }
+        }
+
+        return Piecewise { curves: output };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(x: f64, y: f64) -> Vector
+    {
+        return Vector { x: x, y: y };
+    }
+
+    fn polygon(points: &[Vector]) -> Piecewise<Bezier>
+    {
+        let n = points.len();
+        return Piecewise::new((0..n).map(|i| {
+            let (a, b) = (points[i], points[(i + 1) % n]);
+            Bezier::from_control_points(a, a, b, b)
+        }).collect());
+    }
+
+    // A bow tie crosses once in the middle, between its first and third sides. Only one of its lobes goes the way
+    // the biggest loop does, so that's what's left.
+    #[test]
+    fn figure_eight()
+    {
+        let bow_tie = polygon(&[v(0., 0.), v(100., 100.), v(100., 0.), v(0., 100.)]);
+        let crossings = bow_tie.self_intersections(1e-6);
+        assert_eq!(crossings.len(), 1, "{:?}", crossings);
+        let (ta, tb) = crossings[0];
+        assert!((ta - 0.125).abs() < 1e-6 && (tb - 0.625).abs() < 1e-6, "{:?}", crossings);
+        assert!(bow_tie.evaluate(ta).is_near(v(50., 50.), 1e-6) && bow_tie.evaluate(tb).is_near(v(50., 50.), 1e-6));
+
+        let kept = bow_tie.remove_self_intersections(1e-6);
+        assert_eq!(kept.curves.len(), 1);
+        assert!((kept.curves[0].signed_area().abs() - 2500.).abs() < 1e-6);
+        assert!(kept.curves[0].self_intersections(1e-6).is_empty());
+
+        // a square doesn't cross itself, and an open contour is left alone
+        assert!(polygon(&[v(0., 0.), v(100., 0.), v(100., 100.), v(0., 100.)]).self_intersections(1e-6).is_empty());
+        let open = Piecewise::new(bow_tie.curves[..3].to_vec());
+        assert!(open.remove_self_intersections(1e-6).curves[0].approx_eq(&open, 0.));
+    }
+
+    // Offsetting a square inwards runs each side past the next at the corners, leaving a little backwards loop at
+    // each. Cutting those away leaves the smaller square.
+    #[test]
+    fn offset_folds_at_corners()
+    {
+        let square = polygon(&[v(-100., -100.), v(100., -100.), v(100., 100.), v(-100., 100.)]);
+        let inset = square.offset(10., 1e-3);
+        assert_eq!(inset.self_intersections(1e-6).len(), 4);
+
+        let kept = inset.remove_self_intersections(1e-6);
+        assert_eq!(kept.curves.len(), 1);
+        let bounds = kept.curves[0].bounds();
+        assert!((bounds.left + 90.).abs() < 1e-6 && (bounds.right - 90.).abs() < 1e-6);
+        assert!((bounds.bottom + 90.).abs() < 1e-6 && (bounds.top - 90.).abs() < 1e-6);
+        assert!((kept.curves[0].signed_area() - 180. * 180.).abs() < 1e-3);
+    }
+
+    // An ellipse is tighter at its ends than an inset of 50 can follow, the true offset has a cusp there and ours
+    // folds over itself in a swallowtail. What's left once that's cut off stays clear of the ellipse.
+    #[test]
+    fn offset_past_a_cusp()
+    {
+        let ellipse = Piecewise::circle(v(0., 0.), 100.).scale(3., 1.);
+        let inset = ellipse.offset(50., 1e-3);
+        assert!(inset.self_intersections(1e-6).len() >= 2);
+        let closest = |contour: &Piecewise<Bezier>| contour.curves.iter()
+            .flat_map(|bez| (0..=20).map(move |k| bez.evaluate(k as f64 / 20.)))
+            .fold(f64::INFINITY, |m, p| f64::min(m, ellipse.nearest(p).unwrap().2));
+        assert!(closest(&inset) < 50. - 1., "{}", closest(&inset));
+
+        let kept = inset.remove_self_intersections(1e-6);
+        assert_eq!(kept.curves.len(), 1);
+        assert!(kept.curves[0].self_intersections(1e-6).is_empty());
+        assert!(closest(&kept.curves[0]) > 50. - 0.1, "{}", closest(&kept.curves[0]));
+    }
+}
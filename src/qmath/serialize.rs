@@ -0,0 +1,58 @@
+use super::*;
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+
+// Vector, Rect and Piecewise derive theirs, a Piecewise is written as the plain list of its curves. Bezier keeps its
+// coefficients to itself, so it's written as its four control points instead and rebuilt from them on the way in.
+// A line comes back as a line since those are control points too.
+
+impl Serialize for Bezier {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    {
+        return self.to_control_points().serialize(serializer);
+    }
+}
+
+impl<'de> Deserialize<'de> for Bezier {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>
+    {
+        let p = <[Vector; 4]>::deserialize(deserializer)?;
+        return Ok(Bezier::from_control_points(p[0], p[1], p[2], p[3]));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::read_glif_piecewise;
+    use std::path::Path;
+
+    fn round_trip<T: Serialize + for<'de> Deserialize<'de>>(value: &T) -> T
+    {
+        return serde_json::from_str(&serde_json::to_string(value).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn geometry_round_trips_through_json()
+    {
+        let v = Vector { x: 0.1, y: -1e-7 };
+        assert_eq!(round_trip(&v), v);
+        let rect = Rect { left: 1., bottom: 2., right: 3.5, top: 4.25 };
+        let back = round_trip(&rect);
+        assert_eq!((back.left, back.bottom, back.right, back.top), (rect.left, rect.bottom, rect.right, rect.top));
+
+        // control points on the wire, none of the coefficients
+        let bez = Bezier::from_control_points(Vector { x: 0., y: 0. }, Vector { x: 1., y: 2. }, Vector { x: 3., y: 2. }, Vector { x: 4., y: 0. });
+        assert_eq!(serde_json::to_string(&bez).unwrap(), r#"[{"x":0.0,"y":0.0},{"x":1.0,"y":2.0},{"x":3.0,"y":2.0},{"x":4.0,"y":0.0}]"#);
+
+        let outline = read_glif_piecewise(&Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures").join("skeletons").join("closed_mixed.glif")).unwrap();
+        let back = round_trip(&outline);
+        assert_eq!(back.len(), outline.len());
+        for (contour, back) in outline.segs().zip(back.segs()) {
+            assert_eq!(back.len(), contour.len());
+            for i in 0..=100 {
+                let t = i as f64 / 100.;
+                assert_eq!(back.evaluate(t), contour.evaluate(t));
+            }
+        }
+    }
+}
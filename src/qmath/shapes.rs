@@ -0,0 +1,150 @@
+use super::*;
+use std::f64::consts::PI;
+
+// Rectangles, square cornered and rounded. Both come back as an outline of one closed counter-clockwise contour,
+// which is the right way round for an outer, going round from the bottom left corner along the bottom first.
+
+fn line(from: Vector, to: Vector) -> Bezier
+{
+    return Bezier::from_control_points(from, from, to, to);
+}
+
+impl Piecewise<Piecewise<Bezier>>
+{
+    // The rect as four lines. An empty rect gives an empty outline.
+    pub fn rect(rect: Rect) -> Self
+    {
+        return Self::rounded_rect_corners(rect, [0.; 4]);
+    }
+
+    // The rect with every corner rounded off by a quarter circle of radius.
+    pub fn rounded_rect(rect: Rect, radius: f64) -> Self
+    {
+        return Self::rounded_rect_corners(rect, [radius; 4]);
+    }
+
+    // The rect with each corner rounded by its own radius, going counter-clockwise from the bottom left: bottom left,
+    // bottom right, top right, top left. Negative radii count as zero. Where two corners along a side would need more
+    // than the side's length between them every radius is scaled down by the same amount until they fit, so the
+    // corners keep their proportions and never overlap. A corner with no radius is square.
+    pub fn rounded_rect_corners(rect: Rect, radii: [f64; 4]) -> Self
+    {
+        if rect.is_empty() { return Piecewise { curves: Vec::new() }; }
+
+        let (width, height) = (rect.width(), rect.height());
+        let mut r: Vec<f64> = radii.iter().map(|r| r.max(0.)).collect();
+
+        // each side against the two corners on its ends
+        let sides = [(width, r[0] + r[1]), (height, r[1] + r[2]), (width, r[2] + r[3]), (height, r[3] + r[0])];
+        let scale = sides.iter().fold(1_f64, |s, (length, needed)| if *needed > *length { s.min(length / needed) } else { s });
+        for radius in r.iter_mut() { *radius = *radius * scale; }
+
+        // the corners with which way the side leaving them goes
+        let corners = [
+            (Vector { x: rect.left, y: rect.bottom }, Vector { x: 1., y: 0. }),
+            (Vector { x: rect.right, y: rect.bottom }, Vector { x: 0., y: 1. }),
+            (Vector { x: rect.right, y: rect.top }, Vector { x: -1., y: 0. }),
+            (Vector { x: rect.left, y: rect.top }, Vector { x: 0., y: -1. }),
+        ];
+
+        let mut curves = Vec::new();
+        for i in 0..4 {
+            let (corner, along) = corners[i];
+            let (next_corner, next_along) = corners[(i + 1) % 4];
+
+            // the side from where this corner's rounding ends to where the next one's starts
+            let from = corner + along * r[i];
+            let to = next_corner + -along * r[(i + 1) % 4];
+            // corners that between them take up the whole side leave a hair of rounding error, not a side
+            let has_side = from.distance(to) > 1e-9 * (width + height);
+            if has_side { curves.push(line(from, to)); }
+
+            let radius = r[(i + 1) % 4];
+            if radius > 0. {
+                let center = next_corner + -along * radius + next_along * radius;
+                let start = (-next_along).y.atan2((-next_along).x);
+                let mut arc = Piecewise::arc(center, radius, start, PI / 2.).curves;
+
+                // put the arc's ends right on the sides rather than wherever sin and cos land them
+                let p = arc[0].to_control_points();
+                arc[0] = Bezier::from_control_points(if has_side { to } else { from }, p[1], p[2], next_corner + next_along * radius);
+                curves.extend(arc);
+            }
+        }
+
+        // start on the bottom left corner's rounding, or on the corner itself
+        if r[0] > 0. { curves.rotate_right(1); }
+
+        return Piecewise { curves: vec![Piecewise { curves: curves }] };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECT: Rect = Rect { left: 100., bottom: 50., right: 300., top: 110. };
+
+    // What a corner of radius r takes off, a square of r less the usual one cubic quarter circle, whose area out of
+    // a unit disc's quarter is 1/2 + 3k/5 - 3k^2/20 with its handles k long.
+    fn corner_area(r: f64) -> f64
+    {
+        let k = 4. / 3. * (2f64.sqrt() - 1.);
+        return r * r * (1. - (0.5 + 3. * k / 5. - 3. * k * k / 20.));
+    }
+
+    // closed, counter-clockwise, starting on the bottom left and the same bounds as the rect
+    fn assert_outer(outline: &Piecewise<Piecewise<Bezier>>, start: Vector)
+    {
+        assert_eq!(outline.len(), 1);
+        assert!(outline[0].is_closed());
+        assert_eq!(outline[0].winding_direction(), WindingDirection::CounterClockwise);
+        assert!(outline[0][0].to_control_points()[0].is_near(start, 1e-9), "{:?}", outline[0][0].to_control_points()[0]);
+
+        let b = outline.bounds();
+        assert!((b.left - RECT.left).abs() < 1e-9 && (b.right - RECT.right).abs() < 1e-9, "{:?}", b);
+        assert!((b.bottom - RECT.bottom).abs() < 1e-9 && (b.top - RECT.top).abs() < 1e-9, "{:?}", b);
+    }
+
+    #[test]
+    fn square_cornered()
+    {
+        let rect = Piecewise::rect(RECT);
+        assert_outer(&rect, Vector { x: 100., y: 50. });
+        assert_eq!(rect[0].len(), 4);
+        assert!((rect.area() - 200. * 60.).abs() < 1e-9);
+
+        assert!(Piecewise::rect(Rect { left: 300., bottom: 50., right: 100., top: 110. }).is_empty());
+    }
+
+    #[test]
+    fn rounded()
+    {
+        let rounded = Piecewise::rounded_rect(RECT, 20.);
+        assert_outer(&rounded, Vector { x: 100., y: 70. });
+        assert_eq!(rounded[0].len(), 8);
+        assert!((rounded.area() - (200. * 60. - 4. * corner_area(20.))).abs() < 1e-6, "{}", rounded.area());
+
+        // only the bottom left rounded, the rest square and the start on its rounding
+        let one = Piecewise::rounded_rect_corners(RECT, [20., 0., -5., 0.]);
+        assert_outer(&one, Vector { x: 100., y: 70. });
+        assert_eq!(one[0].len(), 5);
+        assert!((one.area() - (200. * 60. - corner_area(20.))).abs() < 1e-6, "{}", one.area());
+    }
+
+    // 80 is more than the short sides have room for. Every radius scales down until the corners on the short sides
+    // just meet, leaving 30, and those sides go altogether. Asked for 80 and 40 along the bottom, the left side only
+    // has room for 60 so both scale by 3/4, keeping their proportions.
+    #[test]
+    fn radii_clamp_to_fit()
+    {
+        let clamped = Piecewise::rounded_rect(RECT, 80.);
+        assert_outer(&clamped, Vector { x: 100., y: 80. });
+        assert_eq!(clamped[0].len(), 6);
+        assert!((clamped.area() - (200. * 60. - 4. * corner_area(30.))).abs() < 1e-6, "{}", clamped.area());
+
+        let uneven = Piecewise::rounded_rect_corners(RECT, [80., 40., 0., 0.]);
+        assert_outer(&uneven, Vector { x: 100., y: 110. });
+        assert!((uneven.area() - (200. * 60. - corner_area(60.) - corner_area(30.))).abs() < 1e-6, "{}", uneven.area());
+    }
+}
@@ -0,0 +1,482 @@
+use super::*;
+use skulpin::skia_safe::{path, stroke_rec, corner_path_effect, Path, PathEffect, PathFillType, PathOp, StrokeRec};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SkiaError {
+    // the path effect declined to produce anything for our path
+    EffectFailed,
+    // the effect asked for a stroke and skia couldn't apply it
+    StrokeFailed,
+    // skia's simplify choked on the outline, the effect's output or our own
+    SimplifyFailed,
+    // skia couldn't work out a boolean operation between two outlines
+    OpFailed,
+    // skia handed back a path with something in it we couldn't turn into curves, see SkPathConvError
+    ConversionFailed,
+}
+
+impl fmt::Display for SkiaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self {
+            SkiaError::EffectFailed => write!(f, "The path effect didn't produce a path."),
+            SkiaError::StrokeFailed => write!(f, "Couldn't apply the path effect's stroke to its output."),
+            SkiaError::SimplifyFailed => write!(f, "Couldn't simplify the outline."),
+            SkiaError::OpFailed => write!(f, "Couldn't combine the two outlines."),
+            SkiaError::ConversionFailed => write!(f, "Couldn't convert skia's path back into curves."),
+        }
+    }
+}
+
+// A verb from_skpath couldn't turn into curves, with the points skia gave it and where it is among the path's verbs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkPathConvError {
+    pub verb: path::Verb,
+    pub points: Vec<Vector>,
+    pub index: usize,
+}
+
+impl fmt::Display for SkPathConvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        write!(f, "Couldn't convert verb {} of the skia path, a {:?} with the points {:?}.", self.index, self.verb, self.points)
+    }
+}
+
+impl From<SkPathConvError> for SkiaError {
+    fn from(_: SkPathConvError) -> Self
+    {
+        return SkiaError::ConversionFailed;
+    }
+}
+
+// Whether we can make curves out of verb. It needs as many points as it uses, all of them finite, and a conic
+// needs a weight that's finite and above 0. A segment's first point is where the last one ended, which we keep
+// track of ourselves, so that one isn't looked at.
+fn verb_supported(verb: path::Verb, points: &[Vector], weight: Option<f64>) -> bool
+{
+    let (first, needed) = match verb {
+        path::Verb::Move => (0, 1),
+        path::Verb::Line => (1, 2),
+        path::Verb::Quad | path::Verb::Conic => (1, 3),
+        path::Verb::Cubic => (1, 4),
+        path::Verb::Close | path::Verb::Done => (0, 0),
+    };
+    if points.len() < needed || points[first..needed].iter().any(|p| !p.x.is_finite() || !p.y.is_finite()) { return false; }
+
+    if verb == path::Verb::Conic {
+        return weight.map_or(false, |w| w.is_finite() && w > 0.);
+    }
+
+    return true;
+}
+
+impl Vector {
+    pub fn to_skia_point(self) -> (f32, f32)
+    {
+        return (self.x as f32, self.y as f32);
+    }
+
+    pub fn from_skia_point(p: &skulpin::skia_safe::Point) -> Self
+    {
+        return Vector {x: p.x as f64, y: p.y as f64 }
+    }
+}
+
+impl FillRule {
+    pub fn to_skia(&self) -> PathFillType
+    {
+        match self {
+            FillRule::NonZero => PathFillType::Winding,
+            FillRule::EvenOdd => PathFillType::EvenOdd,
+        }
+    }
+}
+
+// how far a conic's cubic stand-in is allowed to stray before we halve the conic and try again
+const CONIC_TOLERANCE: f64 = 1e-3;
+// each halving takes us closer to a parabola, past this many the pieces are tiny anyway
+const MAX_CONIC_DEPTH: usize = 8;
+// how many points along the conic we check the cubic against
+const CONIC_SAMPLES: usize = 8;
+
+// A point on the rational quadratic with weight w at t.
+fn conic_at(p0: Vector, p1: Vector, p2: Vector, w: f64, t: f64) -> Vector
+{
+    let mt = 1. - t;
+    let (b0, b1, b2) = (mt * mt, 2. * w * t * mt, t * t);
+    return (p0 * b0 + p1 * b1 + p2 * b2) * (1. / (b0 + b1 + b2));
+}
+
+// We don't have rational curves so conics come in as cubics with their handles pulled in by the weight, which is
+// exact for w = 1 and close for circular arcs. Anything that doesn't come within CONIC_TOLERANCE gets cut in half,
+// the halves of a conic are conics with a weight nearer 1.
+fn append_conic(p0: Vector, p1: Vector, p2: Vector, w: f64, depth: usize, output: &mut Vec<Bezier>)
+{
+    let k = 4. * w / (3. * (1. + w));
+    let bez = Bezier::from_control_points(p0, p0.lerp(p1, k), p2.lerp(p1, k), p2);
+
+    let error = (1..CONIC_SAMPLES)
+        .map(|i| bez.project(conic_at(p0, p1, p2, w, i as f64 / CONIC_SAMPLES as f64)).2)
+        .filter(|d| d.is_finite())
+        .fold(0., f64::max);
+
+    if depth >= MAX_CONIC_DEPTH || error <= CONIC_TOLERANCE {
+        output.push(bez);
+        return;
+    }
+
+    let scale = 1. / (1. + w);
+    let first_control = (p0 + p1 * w) * scale;
+    let second_control = (p1 * w + p2) * scale;
+    let mid = (first_control + second_control) * 0.5;
+    let half_w = ((1. + w) / 2.).sqrt();
+
+    append_conic(p0, first_control, mid, half_w, depth + 1, output);
+    append_conic(mid, second_control, p2, half_w, depth + 1, output);
+}
+
+impl Piecewise<Piecewise<Bezier>>
+{
+    pub fn to_skpath(&self) -> Path
+    {
+        return self.to_skpath_with_fill_rule(FillRule::NonZero);
+    }
+
+    pub fn to_skpath_with_fill_rule(&self, fill_rule: FillRule) -> Path
+    {
+        let mut path = Path::new();
+        path.set_fill_type(fill_rule.to_skia());
+        return self.append_to_skpath(path);
+    }
+
+    pub fn from_skpath(ipath: &Path) -> Result<Self, SkPathConvError>
+    {
+        return Self::from_skpath_with(ipath, |_, _, _| None).map(|(outline, _)| outline);
+    }
+
+    // from_skpath, handing any verb we can't convert to on_unknown with the points skia gave it (for everything but
+    // a move the first is where it starts) and a conic's weight, if it has one. Whatever curves it gives back are
+    // added to the contour in the verb's place, Some of an empty Vec skips it and None fails the conversion with that
+    // verb. Along with the outline come whether each contour was closed in the skia path, since one that wasn't can
+    // still end where it started and that's all we go by, see to_outline_with_closed.
+    pub fn from_skpath_with<F>(ipath: &Path, mut on_unknown: F) -> Result<(Self, Vec<bool>), SkPathConvError>
+        where F: FnMut(path::Verb, &[Vector], Option<f64>) -> Option<Vec<Bezier>>
+    {
+        let mut contours: Vec<Piecewise<Bezier>> = Vec::new();
+        let mut closed: Vec<bool> = Vec::new();
+        let mut iter = path::Iter::new(ipath, false);
+
+        let mut cur_contour: Vec<Bezier> = Vec::new();
+        // each segment starts where the last one left off, and a close takes us back to where the contour started
+        let mut contour_start: Vector = Vector{x: 0., y: 0.};
+        let mut last_point: Vector = Vector{x: 0., y: 0.};
+        let mut index = 0;
+        // no verb has more than four points, one buffer does for all of them
+        let mut points: Vec<Vector> = Vec::with_capacity(4);
+        while let Some((v, vp)) = iter.next() {
+            points.clear();
+            points.extend(vp.iter().map(Vector::from_skia_point));
+            let weight = if v == path::Verb::Conic { iter.conic_weight().map(|w| w as f64) } else { None };
+
+            if !verb_supported(v, &points, weight) {
+                let substitute = on_unknown(v, &points, weight).ok_or_else(|| SkPathConvError { verb: v, points: std::mem::replace(&mut points, Vec::new()), index: index })?;
+                if let Some(last) = substitute.last() {
+                    last_point = last.to_control_points()[3];
+                }
+                cur_contour.extend(substitute);
+                index = index + 1;
+                continue;
+            }
+
+            match v {
+                // a move with nothing pending just replaces the last one, consecutive moves don't make empty contours
+                path::Verb::Move => {
+                    if !cur_contour.is_empty() {
+                        contours.push(Piecewise { curves: cur_contour });
+                        closed.push(false);
+                    }
+
+                    cur_contour = Vec::new();
+                    contour_start = points[0];
+                    last_point = contour_start;
+                }
+
+                path::Verb::Line => {
+                    let lp = last_point;
+                    let np = points[1];
+                    cur_contour.push(Bezier::from_control_points(lp, lp, np, np));
+                    last_point = np;
+                }
+
+                path::Verb::Quad => {
+                    cur_contour.push(Bezier::from_quadratic_points(last_point, points[1], points[2]));
+                    last_point = points[2];
+                }
+
+                // path effects and skia's own stroker like to hand these back for arcs
+                path::Verb::Conic => {
+                    append_conic(last_point, points[1], points[2], weight.unwrap(), 0, &mut cur_contour);
+                    last_point = points[2];
+                }
+
+                path::Verb::Cubic => {
+                    cur_contour.push(Bezier::from_control_points(last_point, points[1], points[2], points[3]));
+                    last_point = points[3];
+                }
+
+                // Skia's ops like to emit a close or a move with nothing before it, there's no contour there so we
+                // just drop it rather than putting out an empty one. A close draws a line back to the start if we
+                // aren't there already, that's what makes the contour come out closed on our side. Anything drawn
+                // after a close without a move starts a new contour from the same start, the way skia draws it.
+                path::Verb::Close => {
+                    if !cur_contour.is_empty() {
+                        if last_point != contour_start {
+                            cur_contour.push(Bezier::from_control_points(last_point, last_point, contour_start, contour_start));
+                        }
+                        contours.push(Piecewise { curves: std::mem::replace(&mut cur_contour, Vec::new()) });
+                        closed.push(true);
+                    }
+
+                    last_point = contour_start;
+                }
+
+                path::Verb::Done => break,
+            }
+
+            index = index + 1;
+        }
+
+        if !cur_contour.is_empty() {
+            contours.push(Piecewise{ curves: cur_contour });
+            closed.push(false);
+        }
+
+        return Ok((Piecewise { curves: contours }, closed));
+    }
+
+    // Runs one of skia's path effects over our outline. Without a stroke_rec the outline's treated as a fill. If the
+    // effect hands back a stroke we apply it, and the result is simplified the same way pattern output is.
+    pub fn apply_skia_path_effect(&self, effect: &PathEffect, stroke_rec: Option<&StrokeRec>) -> Result<Self, SkiaError>
+    {
+        let skpath = self.to_skpath();
+        let fill = StrokeRec::new(stroke_rec::InitStyle::Fill);
+        let rec = stroke_rec.unwrap_or(&fill);
+
+        let (mut filtered, out_rec) = effect.filter_path(&skpath, rec, *skpath.bounds()).ok_or(SkiaError::EffectFailed)?;
+
+        if !out_rec.is_fill_style() {
+            let mut stroked = Path::new();
+            if !out_rec.apply_to_path(&mut stroked, &filtered) { return Err(SkiaError::StrokeFailed); }
+            filtered = stroked;
+        }
+
+        let cleaned = filtered.simplify().and_then(|p| p.as_winding()).ok_or(SkiaError::SimplifyFailed)?;
+        return Ok(Piecewise::from_skpath(&cleaned)?);
+    }
+
+    // Skia's corner rounding. We don't have a fillet of our own yet so this is the one to reach for, and later
+    // it'll be handy for checking a native one against.
+    pub fn round_corners_skia(&self, radius: f64) -> Result<Self, SkiaError>
+    {
+        let effect = corner_path_effect::new(radius as f32).ok_or(SkiaError::EffectFailed)?;
+        return self.apply_skia_path_effect(&effect, None);
+    }
+
+    // Resolves every overlap in the outline, its contours crossing each other or themselves, into contours that
+    // don't overlap at all. Holes stay holes, outer contours come back counter-clockwise and holes clockwise the
+    // way skia hands them back, and any quadratics or conics it made are turned into cubics.
+    pub fn remove_overlap(&self) -> Result<Self, SkiaError>
+    {
+        return self.remove_overlap_with_fill_rule(FillRule::NonZero);
+    }
+
+    // Same as remove_overlap with the outline read under fill_rule. What comes back is always nonzero.
+    pub fn remove_overlap_with_fill_rule(&self, fill_rule: FillRule) -> Result<Self, SkiaError>
+    {
+        let cleaned = self.to_skpath_with_fill_rule(fill_rule).simplify().and_then(|p| p.as_winding()).ok_or(SkiaError::SimplifyFailed)?;
+        return Ok(Piecewise::from_skpath(&cleaned)?);
+    }
+
+    // Boolean operations through skia's path ops. Both outlines are read nonzero and whatever skia hands back comes
+    // out as a nonzero Piecewise again, with any quadratics or conics it made turned into cubics.
+    fn skia_op(&self, other: &Self, op: PathOp) -> Result<Self, SkiaError>
+    {
+        let result = self.to_skpath().op(&other.to_skpath(), op).ok_or(SkiaError::OpFailed)?;
+        let result = result.as_winding().ok_or(SkiaError::OpFailed)?;
+        return Ok(Piecewise::from_skpath(&result)?);
+    }
+
+    pub fn union(&self, other: &Self) -> Result<Self, SkiaError>
+    {
+        return self.skia_op(other, PathOp::Union);
+    }
+
+    pub fn intersect(&self, other: &Self) -> Result<Self, SkiaError>
+    {
+        return self.skia_op(other, PathOp::Intersect);
+    }
+
+    // what's left of us after other is cut away
+    pub fn difference(&self, other: &Self) -> Result<Self, SkiaError>
+    {
+        return self.skia_op(other, PathOp::Difference);
+    }
+
+    pub fn xor(&self, other: &Self) -> Result<Self, SkiaError>
+    {
+        return self.skia_op(other, PathOp::XOR);
+    }
+
+    pub fn append_to_skpath(&self, mut skpath: Path) -> Path {
+        for contour in &self.curves {
+            skpath = contour.append_to_skpath(skpath);
+        }
+
+        return skpath;
+    }
+}
+
+// A curvature comb as a skia path to stroke, the same lines comb_to_svg_path_data writes.
+pub fn comb_to_skpath(comb: &[(Vector, Vector)]) -> Path
+{
+    let mut path = Path::new();
+    for (base, tip) in comb {
+        path.move_to(base.to_skia_point());
+        path.line_to(tip.to_skia_point());
+    }
+
+    for (i, (_, tip)) in comb.iter().enumerate() {
+        if i == 0 { path.move_to(tip.to_skia_point()); } else { path.line_to(tip.to_skia_point()); }
+    }
+
+    return path;
+}
+
+impl Piecewise<Bezier>
+{
+    pub fn append_to_skpath(&self, mut skpath: Path) -> Path
+    {
+        let mut first = true;
+        for bez in &self.curves {
+            let controlp = bez.to_control_points();
+
+            if first {
+                skpath.move_to(controlp[0].to_skia_point());
+                first = false;
+            }
+            
+            // we've got ourselves a line, both handles sit on their points
+            if controlp[1] == controlp[0] && controlp[2] == controlp[3] {
+                skpath.line_to(controlp[3].to_skia_point());
+            } else {
+                skpath.cubic_to(controlp[1].to_skia_point(), controlp[2].to_skia_point(), controlp[3].to_skia_point());
+            }
+        }
+
+        // without this skia strokes a closed contour as an open one, with caps where it should have a join
+        if !self.curves.is_empty() && self.is_closed() {
+            skpath.close();
+        }
+
+        return skpath;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The on-curve points of every contour, in order of where they are so where each contour starts doesn't matter.
+    fn on_curve_points(outline: &Piecewise<Piecewise<Bezier>>) -> Vec<(f64, f64)>
+    {
+        let mut points: Vec<(f64, f64)> = outline.curves.iter()
+            .flat_map(|contour| contour.curves.iter())
+            .map(|bez| { let p = bez.to_control_points()[0]; (p.x, p.y) })
+            .collect();
+        points.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        return points;
+    }
+
+    // On a square skia's corner effect cuts each side back by the radius the same as round_corners does, but spans
+    // the gap with a parabola with its control point on the corner rather than a circular arc. The cuts land in the
+    // same places, and the parabola takes r^2 / 6 off each corner against the arc's near enough r^2 (1 - pi / 4),
+    // which is about 0.3% of this square.
+    #[test]
+    fn corner_effect_matches_round_corners()
+    {
+        let square = Piecewise::rect(Rect { left: 100., bottom: 100., right: 500., top: 500. });
+        let native = square.round_corners(50., CORNER_ANGLE);
+        let skia = square.round_corners_skia(50.).unwrap();
+
+        assert_eq!(skia.curves.len(), 1);
+        let (native_points, skia_points) = (on_curve_points(&native), on_curve_points(&skia));
+        assert_eq!(native_points.len(), skia_points.len(), "{:?}", skia_points);
+        for (n, s) in native_points.iter().zip(&skia_points) {
+            assert!((n.0 - s.0).abs() <= 1e-3 && (n.1 - s.1).abs() <= 1e-3, "{:?} rather than {:?}", s, n);
+        }
+
+        // round_corners' arc is the usual one cubic quarter, the area of its quarter of a unit disc is
+        // 1/2 + 3k/5 - 3k^2/20 with the handles k long
+        let k = 4. / 3. * (2f64.sqrt() - 1.);
+        let corner_difference = 50. * 50. * (1. - (0.5 + 3. * k / 5. - 3. * k * k / 20.) - 1. / 6.);
+        assert!((skia.area() - native.area() - 4. * corner_difference).abs() <= 1., "{} rather than {}", skia.area(), native.area());
+    }
+
+    // Paths with nothing to draw in them, which skia's ops are happy to hand back, come out as no contours at all.
+    // Moves with nothing after them and closes with nothing before them don't make empty ones either.
+    #[test]
+    fn empty_and_move_only_paths()
+    {
+        assert_eq!(Piecewise::from_skpath(&Path::new()).unwrap().curves.len(), 0);
+
+        let mut path = Path::new();
+        path.move_to((10., 10.));
+        assert_eq!(Piecewise::from_skpath(&path).unwrap().curves.len(), 0);
+
+        path.move_to((20., 20.)).line_to((30., 20.)).line_to((30., 30.)).close().move_to((40., 40.)).close().move_to((50., 50.));
+        let outline = Piecewise::from_skpath(&path).unwrap();
+        assert_eq!(outline.curves.len(), 1);
+        // the close draws the last side back to where the contour started
+        assert_eq!(outline.curves[0].curves.len(), 3);
+        assert!(outline.curves[0].is_closed());
+    }
+
+    // Two squares side by side sharing an edge. Unioned, skia merges them into one rect. They have nothing but the
+    // edge in common so their intersection is empty, however skia chooses to say so.
+    #[test]
+    fn op_on_touching_squares()
+    {
+        let square = |left: f64| Piecewise::rect(Rect { left: left, bottom: 0., right: left + 100., top: 100. }).to_skpath();
+
+        let union = Piecewise::from_skpath(&square(0.).op(&square(100.), PathOp::Union).unwrap()).unwrap();
+        assert_eq!(union.curves.len(), 1);
+        assert!((union.area().abs() - 200. * 100.).abs() <= 1e-3, "{}", union.area());
+
+        let intersection = Piecewise::from_skpath(&square(0.).op(&square(100.), PathOp::Intersect).unwrap()).unwrap();
+        assert_eq!(intersection.curves.len(), 0);
+    }
+
+    fn verbs(path: &Path) -> Vec<path::Verb>
+    {
+        return path::Iter::new(path, false).map(|(verb, _)| verb).take_while(|verb| *verb != path::Verb::Done).collect();
+    }
+
+    // One verb for every curve, lines as lines, and a close for closed contours only.
+    #[test]
+    fn one_verb_per_curve()
+    {
+        use path::Verb::*;
+
+        let square = Piecewise::rect(Rect { left: 0., bottom: 0., right: 100., top: 100. });
+        assert_eq!(verbs(&square.to_skpath()), vec![Move, Line, Line, Line, Line, Close]);
+
+        let circle = Piecewise::new(vec![Piecewise::circle(Vector { x: 0., y: 0. }, 50.)]);
+        assert_eq!(verbs(&circle.to_skpath()), vec![Move, Cubic, Cubic, Cubic, Cubic, Close]);
+
+        let (a, b) = (Vector { x: 0., y: 0. }, Vector { x: 100., y: 0. });
+        let open = Piecewise::new(vec![Piecewise::new(vec![Bezier::from_control_points(a, a, b, b), Bezier::from_control_points(b, b + Vector { x: 50., y: 0. }, a, a + Vector { x: 0., y: 10. })])]);
+        assert_eq!(verbs(&open.to_skpath()), vec![Move, Line, Cubic]);
+    }
+}
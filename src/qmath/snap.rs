@@ -0,0 +1,270 @@
+use super::*;
+use std::f64::consts::PI;
+
+// how finely we flatten contours when checking that a snap didn't make them cross themselves
+const FLATTEN_STEPS: usize = 16;
+
+// only proper crossings count, segments that merely touch don't
+fn segments_cross(a0: Vector, a1: Vector, b0: Vector, b1: Vector) -> bool
+{
+    let da = a1 + -a0;
+    let db = b1 + -b0;
+
+    let d1 = da.cross(b0 + -a0);
+    let d2 = da.cross(b1 + -a0);
+    let d3 = db.cross(a0 + -b0);
+    let d4 = db.cross(a1 + -b0);
+
+    return d1 * d2 < 0. && d3 * d4 < 0.;
+}
+
+impl Piecewise<Bezier>
+{
+    // How many times a flattened copy of the contour crosses itself. It's only good for comparing the same contour
+    // before and after a small change, curves that cross very shallowly can be missed.
+    fn self_crossings(&self) -> usize
+    {
+        let mut points = Vec::new();
+        for bez in &self.curves {
+            for n in 0..FLATTEN_STEPS {
+                points.push(bez.evaluate(n as f64 / FLATTEN_STEPS as f64));
+            }
+        }
+
+        let count = points.len();
+        let mut crossings = 0;
+        for i in 0..count {
+            for j in i + 2..count {
+                // the last edge and the first one share a point
+                if i == 0 && j == count - 1 { continue; }
+
+                if segments_cross(points[i], points[(i + 1) % count], points[j], points[(j + 1) % count]) {
+                    crossings = crossings + 1;
+                }
+            }
+        }
+
+        return crossings;
+    }
+
+    // The zone every control point of bez sits within tolerance of, if it's flat enough to count as horizontal.
+    fn horizontal_zone(bez: &Bezier, zones: &[(f64, f64)]) -> Option<f64>
+    {
+        let p = bez.to_control_points();
+        if (p[3].x - p[0].x).abs() <= (p[3].y - p[0].y).abs() { return None; }
+
+        let mut best: Option<f64> = None;
+        for (position, tolerance) in zones {
+            if p.iter().all(|v| (v.y - position).abs() <= *tolerance) {
+                let closer = match best {
+                    Some(b) => (p[0].y - position).abs() < (p[0].y - b).abs(),
+                    None => true
+                };
+                if closer { best = Some(*position); }
+            }
+        }
+
+        return best;
+    }
+
+    // Moves the ends of every near horizontal segment onto the zone it's in. Each anchor we move takes its handles
+    // with it so the neighbouring curves keep their shape. Returns the snapped contour and how many anchors moved.
+    pub fn snap_to_zones(&self, zones: &[(f64, f64)]) -> (Self, usize)
+    {
+        let count = self.curves.len();
+        if count == 0 { return (self.clone(), 0); }
+        let closed = self.is_closed();
+
+        // anchor i is the start of segment i, anchor count is the end of the last segment
+        let mut deltas: Vec<Option<f64>> = vec![None; count + 1];
+
+        for (i, bez) in self.curves.iter().enumerate() {
+            if let Some(zone) = Self::horizontal_zone(bez, zones) {
+                let p = bez.to_control_points();
+                deltas[i] = Some(zone - p[0].y);
+                deltas[i + 1] = Some(zone - p[3].y);
+            }
+        }
+
+        // on a closed contour the first and last anchors are the same point
+        if closed {
+            let shared = deltas[0].or(deltas[count]);
+            deltas[0] = shared;
+            deltas[count] = shared;
+        }
+
+        let moved = deltas.iter().take(if closed { count } else { count + 1 }).filter(|d| d.is_some()).count();
+
+        let shift = |v: Vector, delta: Option<f64>| Vector { x: v.x, y: v.y + delta.unwrap_or(0.) };
+        let curves = self.curves.iter().enumerate().map(|(i, bez)| {
+            let p = bez.to_control_points();
+            Bezier::from_control_points(shift(p[0], deltas[i]), shift(p[1], deltas[i]), shift(p[2], deltas[i + 1]), shift(p[3], deltas[i + 1]))
+        }).collect();
+
+        return (Piecewise { curves: curves }, moved);
+    }
+}
+
+impl Piecewise<Piecewise<Bezier>>
+{
+    // Snaps every contour's horizontals onto zones, which are (position, tolerance) pairs. A contour that would
+    // cross itself more after snapping than before is left as it was. Returns how many anchors moved.
+    pub fn snap_to_zones(&self, zones: &[(f64, f64)]) -> (Self, usize)
+    {
+        let mut moved = 0;
+        let mut contours = Vec::new();
+
+        for contour in &self.curves {
+            let (snapped, count) = contour.snap_to_zones(zones);
+
+            if count > 0 && snapped.self_crossings() <= contour.self_crossings() {
+                moved = moved + count;
+                contours.push(snapped);
+            } else {
+                contours.push(contour.clone());
+            }
+        }
+
+        return (Piecewise { curves: contours }, moved);
+    }
+}
+
+// What snap_tangents did.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TangentSnapReport {
+    // handles turned onto an axis
+    pub handles: usize,
+    // the furthest the contour moved, see max_deviation_from
+    pub max_deviation: f64,
+}
+
+// how closely the deviation in TangentSnapReport is measured
+const TANGENT_SNAP_DEVIATION_TOLERANCE: f64 = 1e-3;
+
+// handle is turned onto the nearest axis if it's within tolerance radians of it and not on it already
+fn axis_snap(handle: Vector, tolerance: f64) -> Option<Vector>
+{
+    let angle = handle.y.atan2(handle.x);
+    let axis = (angle / (PI / 2.)).round() * (PI / 2.);
+    let off = (angle - axis).abs();
+    if off > tolerance || off == 0. { return None; }
+
+    // sin and cos of a multiple of a quarter turn are a hair off 0 and 1, round them so the axis is exact
+    let (s, c) = axis.sin_cos();
+    return Some(Vector { x: c.round(), y: s.round() } * handle.length());
+}
+
+impl Piecewise<Bezier>
+{
+    // Turns handles that are within angle_tolerance degrees of horizontal or vertical onto it exactly, about the
+    // point they hang off, keeping their length. Where the two handles of a point are lined up with each other to
+    // within the tolerance they're turned together, so a smooth point stays smooth and comes out exactly on the
+    // axis. Handles sitting on their point have no direction and are left alone, and so are diagonals, the tolerance
+    // is held under 45 degrees. Moving a handle of length l by an angle a moves the curve by at most 4/9 l a, the
+    // report has how far it actually moved.
+    pub fn snap_tangents(&self, angle_tolerance: f64) -> (Self, TangentSnapReport)
+    {
+        let (output, handles) = self.snapped_tangents(angle_tolerance);
+        let max_deviation = if handles > 0 { output.max_deviation_from(self, TANGENT_SNAP_DEVIATION_TOLERANCE) } else { 0. };
+
+        return (output, TangentSnapReport { handles: handles, max_deviation: max_deviation });
+    }
+
+    // snap_tangents without measuring the deviation, which costs far more than the snapping does. For callers that
+    // throw the report away, just the contour and how many handles turned.
+    pub(super) fn snapped_tangents(&self, angle_tolerance: f64) -> (Self, usize)
+    {
+        let count = self.curves.len();
+        if count == 0 { return (self.clone(), 0); }
+
+        let tolerance = angle_tolerance.min(44.9).max(0.).to_radians();
+        let closed = self.is_closed();
+        let mut points: Vec<[Vector; 4]> = self.curves.iter().map(|bez| bez.to_control_points()).collect();
+        let mut handles = 0;
+
+        // joint i is the start of curve i, the end of the last curve is only a joint of its own when open
+        let joints = if closed { count } else { count + 1 };
+        for i in 0..joints {
+            let incoming = if i > 0 { Some(i - 1) } else if closed { Some(count - 1) } else { None };
+            let outgoing = if i < count { Some(i) } else { None };
+
+            let anchor = match outgoing { Some(o) => points[o][0], None => points[count - 1][3] };
+            let h_in = incoming.map(|k| points[k][2] + -anchor).filter(|h| h.length() > 0.);
+            let h_out = outgoing.map(|k| points[k][1] + -anchor).filter(|h| h.length() > 0.);
+
+            let mut snapped_in = h_in.and_then(|h| axis_snap(h, tolerance));
+            let mut snapped_out = h_out.and_then(|h| axis_snap(h, tolerance));
+
+            // a smooth point turns as one, along the direction halfway between its two handles
+            if let (Some(hi), Some(ho)) = (h_in, h_out) {
+                let (ui, uo) = (hi * (1. / hi.length()), ho * (1. / ho.length()));
+                if uo.angle_to(-ui).abs() <= tolerance {
+                    let through = uo + -ui;
+                    match axis_snap(through, tolerance) {
+                        Some(axis) => {
+                            let axis = axis * (1. / axis.length());
+                            snapped_in = Some(-axis * hi.length());
+                            snapped_out = Some(axis * ho.length());
+                        }
+                        None => {
+                            snapped_in = None;
+                            snapped_out = None;
+                        }
+                    }
+                }
+            }
+
+            if let (Some(k), Some(h)) = (incoming, snapped_in) {
+                points[k][2] = anchor + h;
+                handles = handles + 1;
+            }
+            if let (Some(k), Some(h)) = (outgoing, snapped_out) {
+                points[k][1] = anchor + h;
+                handles = handles + 1;
+            }
+        }
+
+        let output = Piecewise { curves: points.iter().map(|p| Bezier::from_control_points(p[0], p[1], p[2], p[3])).collect() };
+        return (output, handles);
+    }
+}
+
+impl Piecewise<Piecewise<Bezier>>
+{
+    // snap_tangents on every contour, with the handles added up and the worst deviation of any of them.
+    pub fn snap_tangents(&self, angle_tolerance: f64) -> (Self, TangentSnapReport)
+    {
+        let mut report = TangentSnapReport::default();
+        let mut contours = Vec::new();
+        for contour in &self.curves {
+            let (snapped, contour_report) = contour.snap_tangents(angle_tolerance);
+            report.handles = report.handles + contour_report.handles;
+            report.max_deviation = report.max_deviation.max(contour_report.max_deviation);
+            contours.push(snapped);
+        }
+
+        return (Piecewise { curves: contours }, report);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stroke::{constant_width_stroke, StrokeSettings};
+
+    // A bar stroked along y = 10.3 comes out with its bottom 0.3 above the baseline and lands right on it.
+    #[test]
+    fn bar_snaps_onto_the_baseline()
+    {
+        let skeleton = Piecewise::new(vec![Piecewise::new(vec![Bezier::from_control_points(
+            Vector { x: 0., y: 10.3 }, Vector { x: 0., y: 10.3 }, Vector { x: 100., y: 10.3 }, Vector { x: 100., y: 10.3 })])]);
+        let bar = constant_width_stroke(&skeleton, &StrokeSettings { width: 20., ..StrokeSettings::default() });
+        let (snapped, moved) = bar.snap_to_zones(&[(0., 1.), (500., 1.)]);
+
+        // the points on the curve, which are what go in the glif
+        let ys: Vec<f64> = snapped.curves[0].curves.iter().map(|bez| bez.to_control_points()[0].y).collect();
+        assert_eq!(moved, 2);
+        assert_eq!(ys.iter().filter(|y| **y == 0.).count(), 2, "{:?}", ys);
+        assert!(ys.iter().all(|y| *y == 0. || (*y - 20.3).abs() < 1e-9), "{:?}", ys);
+    }
+}
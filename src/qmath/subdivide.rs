@@ -0,0 +1,64 @@
+use super::*;
+
+// Splitting curves only where they need it. subdivide halves everything, so refining by calling it over and over
+// keeps halving the parts that were already fine. These halve a curve only while the predicate says it needs it.
+
+// past this many halvings a piece is a 65536th of its curve, a predicate that still isn't happy never will be
+const MAX_SUBDIVIDE_DEPTH: usize = 16;
+
+fn subdivide_into(bez: Bezier, predicate: &impl Fn(&Bezier) -> bool, depth: usize, output: &mut Vec<Bezier>)
+{
+    if depth >= MAX_SUBDIVIDE_DEPTH || !predicate(&bez) {
+        output.push(bez);
+        return;
+    }
+
+    let (first, second) = bez.subdivide(0.5);
+    subdivide_into(first, predicate, depth + 1, output);
+    subdivide_into(second, predicate, depth + 1, output);
+}
+
+impl Piecewise<Bezier>
+{
+    // Halves each curve, and then the halves, for as long as predicate says a piece needs splitting. The contour
+    // keeps its shape and its ends, only the number of curves goes up.
+    pub fn subdivide_adaptive(&self, predicate: impl Fn(&Bezier) -> bool) -> Self
+    {
+        let mut output = Vec::new();
+        for bez in &self.curves {
+            subdivide_into(bez.clone(), &predicate, 0, &mut output);
+        }
+
+        return Piecewise { curves: output };
+    }
+
+    // Split until every piece is within tolerance of its chord, see Bezier::is_flat.
+    pub fn subdivide_until_flat(&self, tolerance: f64) -> Self
+    {
+        return self.subdivide_adaptive(|bez| !bez.is_flat(tolerance));
+    }
+
+    // Split until no piece is longer than max_length.
+    pub fn subdivide_to_length(&self, max_length: f64) -> Self
+    {
+        return self.subdivide_adaptive(|bez| bez.arclen(max_length * 1e-3) > max_length);
+    }
+}
+
+impl Piecewise<Piecewise<Bezier>>
+{
+    pub fn subdivide_adaptive(&self, predicate: impl Fn(&Bezier) -> bool) -> Self
+    {
+        return Piecewise { curves: self.curves.iter().map(|contour| contour.subdivide_adaptive(&predicate)).collect() };
+    }
+
+    pub fn subdivide_until_flat(&self, tolerance: f64) -> Self
+    {
+        return Piecewise { curves: self.curves.iter().map(|contour| contour.subdivide_until_flat(tolerance)).collect() };
+    }
+
+    pub fn subdivide_to_length(&self, max_length: f64) -> Self
+    {
+        return Piecewise { curves: self.curves.iter().map(|contour| contour.subdivide_to_length(max_length)).collect() };
+    }
+}
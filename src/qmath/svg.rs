@@ -0,0 +1,428 @@
+use super::*;
+use std::f64::consts::PI;
+
+// Things that can go wrong reading SVG path data. The usize is the character offset into the string.
+#[derive(Debug)]
+pub enum SvgPathError {
+    UnexpectedCharacter(usize, char),
+    MissingCoordinates(usize, char),
+    UnsupportedCommand(usize, char),
+}
+
+impl std::fmt::Display for SvgPathError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        match self {
+            SvgPathError::UnexpectedCharacter(pos, c) => write!(f, "Unexpected character '{}' at {} in path data.", c, pos),
+            SvgPathError::MissingCoordinates(pos, c) => write!(f, "Command '{}' at {} is missing coordinates.", c, pos),
+            SvgPathError::UnsupportedCommand(pos, c) => write!(f, "Command '{}' at {} isn't supported.", c, pos),
+        }
+    }
+}
+
+// Splits path data into command letters and numbers. SVG is very lax about separators, "M1-2.5.5" is
+// three numbers after the M, so we can't just split on whitespace.
+struct PathLexer {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl PathLexer {
+    fn skip_separators(&mut self)
+    {
+        while self.pos < self.chars.len() && (self.chars[self.pos].is_whitespace() || self.chars[self.pos] == ',') {
+            self.pos = self.pos + 1;
+        }
+    }
+
+    fn at_end(&mut self) -> bool
+    {
+        self.skip_separators();
+        return self.pos >= self.chars.len();
+    }
+
+    fn next_is_number(&mut self) -> bool
+    {
+        self.skip_separators();
+        if self.pos >= self.chars.len() { return false; }
+
+        let c = self.chars[self.pos];
+        return c.is_ascii_digit() || c == '-' || c == '+' || c == '.';
+    }
+
+    fn command(&mut self) -> Option<char>
+    {
+        self.skip_separators();
+        if self.pos >= self.chars.len() { return None; }
+
+        let c = self.chars[self.pos];
+        if c.is_ascii_alphabetic() && c != 'e' && c != 'E' {
+            self.pos = self.pos + 1;
+            return Some(c);
+        }
+
+        return None;
+    }
+
+    fn number(&mut self) -> Option<f64>
+    {
+        self.skip_separators();
+        let start = self.pos;
+        let chars = &self.chars;
+        let mut end = self.pos;
+
+        if end < chars.len() && (chars[end] == '-' || chars[end] == '+') { end = end + 1; }
+
+        let mut digits = false;
+        while end < chars.len() && chars[end].is_ascii_digit() { end = end + 1; digits = true; }
+
+        if end < chars.len() && chars[end] == '.' {
+            end = end + 1;
+            while end < chars.len() && chars[end].is_ascii_digit() { end = end + 1; digits = true; }
+        }
+
+        if !digits { return None; }
+
+        // only treat an e as an exponent if there's actually an exponent after it
+        if end < chars.len() && (chars[end] == 'e' || chars[end] == 'E') {
+            let mut exp_end = end + 1;
+            if exp_end < chars.len() && (chars[exp_end] == '-' || chars[exp_end] == '+') { exp_end = exp_end + 1; }
+
+            let exp_digits_start = exp_end;
+            while exp_end < chars.len() && chars[exp_end].is_ascii_digit() { exp_end = exp_end + 1; }
+
+            if exp_end > exp_digits_start { end = exp_end; }
+        }
+
+        let string: String = chars[start..end].iter().collect();
+        match string.parse::<f64>() {
+            Ok(n) => {
+                self.pos = end;
+                Some(n)
+            }
+            Err(_) => None
+        }
+    }
+
+    fn numbers(&mut self, count: usize, command: char, command_pos: usize) -> Result<Vec<f64>, SvgPathError>
+    {
+        let mut output = Vec::new();
+        for _ in 0..count {
+            match self.number() {
+                Some(n) => output.push(n),
+                None => return Err(SvgPathError::MissingCoordinates(command_pos, command))
+            }
+        }
+
+        return Ok(output);
+    }
+
+    // The large arc and sweep flags are a single 0 or 1 and don't need anything separating them from what comes
+    // next, "a1 1 0 011 1" is a valid arc.
+    fn flag(&mut self, command: char, command_pos: usize) -> Result<bool, SvgPathError>
+    {
+        self.skip_separators();
+        if self.pos < self.chars.len() && (self.chars[self.pos] == '0' || self.chars[self.pos] == '1') {
+            self.pos = self.pos + 1;
+            return Ok(self.chars[self.pos - 1] == '1');
+        }
+
+        return Err(SvgPathError::MissingCoordinates(command_pos, command));
+    }
+}
+
+fn line(from: Vector, to: Vector) -> Bezier
+{
+    return Bezier::from_control_points(from, from, to, to);
+}
+
+// An elliptical arc from from to to as cubics, at most a quarter turn each. The parameters are the ones in the
+// path data, so first this finds the center the way the SVG spec's implementation notes do. Radii too small to
+// reach are scaled up until they just do, a zero radius is a straight line and an arc to where we already are
+// isn't anything at all.
+fn elliptical_arc(from: Vector, radii: Vector, rotation: f64, large_arc: bool, sweep: bool, to: Vector) -> Vec<Bezier>
+{
+    if from == to { return Vec::new(); }
+
+    let (mut rx, mut ry) = (radii.x.abs(), radii.y.abs());
+    if rx == 0. || ry == 0. { return vec![line(from, to)]; }
+
+    let phi = rotation.to_radians();
+    let (sin_phi, cos_phi) = phi.sin_cos();
+
+    // the midpoint between the ends in the ellipse's own frame
+    let half = (from + -to) * 0.5;
+    let p = Vector { x: cos_phi * half.x + sin_phi * half.y, y: -sin_phi * half.x + cos_phi * half.y };
+
+    let lambda = (p.x * p.x) / (rx * rx) + (p.y * p.y) / (ry * ry);
+    if lambda > 1. {
+        rx = rx * lambda.sqrt();
+        ry = ry * lambda.sqrt();
+    }
+
+    let numerator = rx * rx * ry * ry - rx * rx * p.y * p.y - ry * ry * p.x * p.x;
+    let denominator = rx * rx * p.y * p.y + ry * ry * p.x * p.x;
+    let mut scale = (numerator / denominator).max(0.).sqrt();
+    if large_arc == sweep { scale = -scale; }
+
+    let c = Vector { x: scale * rx * p.y / ry, y: -scale * ry * p.x / rx };
+    let mid = (from + to) * 0.5;
+    let center = Vector { x: cos_phi * c.x - sin_phi * c.y + mid.x, y: sin_phi * c.x + cos_phi * c.y + mid.y };
+
+    // angles on the unit circle the ellipse is stretched from
+    let u = Vector { x: (p.x - c.x) / rx, y: (p.y - c.y) / ry };
+    let v = Vector { x: (-p.x - c.x) / rx, y: (-p.y - c.y) / ry };
+    let start = u.y.atan2(u.x);
+    let mut delta = u.angle_to(v);
+    if !sweep && delta > 0. { delta = delta - 2. * PI; }
+    if sweep && delta < 0. { delta = delta + 2. * PI; }
+
+    let to_ellipse = |q: Vector| {
+        let (x, y) = (q.x * rx, q.y * ry);
+        return Vector { x: cos_phi * x - sin_phi * y + center.x, y: sin_phi * x + cos_phi * y + center.y };
+    };
+
+    let pieces = (delta.abs() / (PI / 2.)).ceil().max(1.) as usize;
+    let step = delta / pieces as f64;
+    let k = 4. / 3. * (step / 4.).tan();
+
+    let mut output = Vec::new();
+    for i in 0..pieces {
+        let (a0, a1) = (start + step * i as f64, start + step * (i + 1) as f64);
+        let (s0, c0) = a0.sin_cos();
+        let (s1, c1) = a1.sin_cos();
+
+        let p0 = if i == 0 { from } else { to_ellipse(Vector { x: c0, y: s0 }) };
+        let p3 = if i == pieces - 1 { to } else { to_ellipse(Vector { x: c1, y: s1 }) };
+        let p1 = to_ellipse(Vector { x: c0 - k * s0, y: s0 + k * c0 });
+        let p2 = to_ellipse(Vector { x: c1 + k * s1, y: s1 - k * c1 });
+        output.push(Bezier::from_control_points(p0, p1, p2, p3));
+    }
+
+    return output;
+}
+
+// Formats a coordinate with at most precision decimals and no trailing zeros. None gives the shortest
+// representation that round trips.
+fn format_number(v: f64, precision: Option<usize>) -> String
+{
+    let mut string = match precision {
+        Some(p) => format!("{:.*}", p, v),
+        None => v.to_string()
+    };
+
+    if string.contains('.') {
+        string = string.trim_end_matches('0').trim_end_matches('.').to_string();
+    }
+
+    if string == "-0" { string = String::from("0"); }
+
+    return string;
+}
+
+impl Piecewise<Piecewise<Bezier>>
+{
+    // Builds an outline from the d attribute of an SVG path element. Every subpath becomes a contour, lines,
+    // quadratics and arcs get converted to cubics. Subpaths closed with Z get a line back to their start if they
+    // need one.
+    pub fn from_svg_path_data(d: &str) -> Result<Self, SvgPathError>
+    {
+        let mut lexer = PathLexer { chars: d.chars().collect(), pos: 0 };
+        let mut contours: Vec<Piecewise<Bezier>> = Vec::new();
+        let mut cur_contour: Vec<Bezier> = Vec::new();
+
+        let origin = Vector { x: 0., y: 0. };
+        let mut current = origin;
+        let mut subpath_start = origin;
+
+        // the control points that S and T reflect, these are only valid right after a C/S or Q/T respectively
+        let mut last_cubic_control: Option<Vector> = None;
+        let mut last_quad_control: Option<Vector> = None;
+
+        let mut last_command: Option<char> = None;
+
+        while !lexer.at_end() {
+            let command_pos = lexer.pos;
+            let command = match lexer.command() {
+                Some(c) => c,
+                None => {
+                    // numbers without a command repeat the last command, and a moveto's extra pairs are linetos
+                    match last_command {
+                        Some('M') if lexer.next_is_number() => 'L',
+                        Some('m') if lexer.next_is_number() => 'l',
+                        Some(c) if c != 'Z' && c != 'z' && lexer.next_is_number() => c,
+                        _ => return Err(SvgPathError::UnexpectedCharacter(lexer.pos, lexer.chars[lexer.pos]))
+                    }
+                }
+            };
+
+            let relative = command.is_ascii_lowercase();
+            let offset = if relative { current } else { origin };
+            let mut cubic_control = None;
+            let mut quad_control = None;
+
+            match command.to_ascii_uppercase() {
+                'M' => {
+                    let n = lexer.numbers(2, command, command_pos)?;
+
+                    if !cur_contour.is_empty() {
+                        contours.push(Piecewise { curves: cur_contour });
+                    }
+                    cur_contour = Vec::new();
+
+                    current = Vector { x: n[0], y: n[1] } + offset;
+                    subpath_start = current;
+                }
+
+                'L' => {
+                    let n = lexer.numbers(2, command, command_pos)?;
+                    let p = Vector { x: n[0], y: n[1] } + offset;
+                    cur_contour.push(line(current, p));
+                    current = p;
+                }
+
+                'H' => {
+                    let n = lexer.numbers(1, command, command_pos)?;
+                    let p = Vector { x: n[0] + offset.x, y: current.y };
+                    cur_contour.push(line(current, p));
+                    current = p;
+                }
+
+                'V' => {
+                    let n = lexer.numbers(1, command, command_pos)?;
+                    let p = Vector { x: current.x, y: n[0] + offset.y };
+                    cur_contour.push(line(current, p));
+                    current = p;
+                }
+
+                'C' => {
+                    let n = lexer.numbers(6, command, command_pos)?;
+                    let h1 = Vector { x: n[0], y: n[1] } + offset;
+                    let h2 = Vector { x: n[2], y: n[3] } + offset;
+                    let p = Vector { x: n[4], y: n[5] } + offset;
+                    cur_contour.push(Bezier::from_control_points(current, h1, h2, p));
+                    cubic_control = Some(h2);
+                    current = p;
+                }
+
+                'S' => {
+                    let n = lexer.numbers(4, command, command_pos)?;
+                    let h1 = match last_cubic_control {
+                        Some(c) => current * 2. + -c,
+                        None => current
+                    };
+                    let h2 = Vector { x: n[0], y: n[1] } + offset;
+                    let p = Vector { x: n[2], y: n[3] } + offset;
+                    cur_contour.push(Bezier::from_control_points(current, h1, h2, p));
+                    cubic_control = Some(h2);
+                    current = p;
+                }
+
+                'Q' => {
+                    let n = lexer.numbers(4, command, command_pos)?;
+                    let q = Vector { x: n[0], y: n[1] } + offset;
+                    let p = Vector { x: n[2], y: n[3] } + offset;
+                    cur_contour.push(Bezier::from_quadratic_points(current, q, p));
+                    quad_control = Some(q);
+                    current = p;
+                }
+
+                'T' => {
+                    let n = lexer.numbers(2, command, command_pos)?;
+                    let q = match last_quad_control {
+                        Some(c) => current * 2. + -c,
+                        None => current
+                    };
+                    let p = Vector { x: n[0], y: n[1] } + offset;
+                    cur_contour.push(Bezier::from_quadratic_points(current, q, p));
+                    quad_control = Some(q);
+                    current = p;
+                }
+
+                'A' => {
+                    let n = lexer.numbers(3, command, command_pos)?;
+                    let large_arc = lexer.flag(command, command_pos)?;
+                    let sweep = lexer.flag(command, command_pos)?;
+                    let e = lexer.numbers(2, command, command_pos)?;
+                    let p = Vector { x: e[0], y: e[1] } + offset;
+                    cur_contour.extend(elliptical_arc(current, Vector { x: n[0], y: n[1] }, n[2], large_arc, sweep, p));
+                    current = p;
+                }
+
+                'Z' => {
+                    if current != subpath_start {
+                        cur_contour.push(line(current, subpath_start));
+                    }
+
+                    if !cur_contour.is_empty() {
+                        contours.push(Piecewise { curves: cur_contour });
+                    }
+                    cur_contour = Vec::new();
+
+                    current = subpath_start;
+                }
+
+                _ => return Err(SvgPathError::UnsupportedCommand(command_pos, command))
+            }
+
+            last_cubic_control = cubic_control;
+            last_quad_control = quad_control;
+            last_command = Some(command);
+        }
+
+        if !cur_contour.is_empty() {
+            contours.push(Piecewise { curves: cur_contour });
+        }
+
+        return Ok(Piecewise { curves: contours });
+    }
+
+    // Writes the outline out as SVG path data. Segments with handles sitting on their endpoints are written as lines.
+    pub fn to_svg_path_data(&self, precision: Option<usize>) -> String
+    {
+        let mut commands: Vec<String> = Vec::new();
+        let point = |v: Vector| format!("{} {}", format_number(v.x, precision), format_number(v.y, precision));
+
+        for contour in &self.curves {
+            if contour.curves.is_empty() { continue; }
+
+            let start = contour.curves[0].to_control_points()[0];
+            commands.push(format!("M{}", point(start)));
+
+            for bez in &contour.curves {
+                let cp = bez.to_control_points();
+
+                if cp[0].is_near(cp[1], 1e-9) && cp[2].is_near(cp[3], 1e-9) {
+                    commands.push(format!("L{}", point(cp[3])));
+                } else {
+                    commands.push(format!("C{} {} {}", point(cp[1]), point(cp[2]), point(cp[3])));
+                }
+            }
+
+            // open contours, like skeletons, stay open
+            let end = contour.curves[contour.curves.len() - 1].to_control_points()[3];
+            if start.is_near(end, 1e-9) {
+                commands.push(String::from("Z"));
+            }
+        }
+
+        return commands.join(" ");
+    }
+}
+
+// SVG path data for a curvature comb, a line for each tooth and then one through all their tips in order.
+pub fn comb_to_svg_path_data(comb: &[(Vector, Vector)], precision: Option<usize>) -> String
+{
+    let point = |v: Vector| format!("{} {}", format_number(v.x, precision), format_number(v.y, precision));
+    let mut commands: Vec<String> = Vec::new();
+
+    for (base, tip) in comb {
+        commands.push(format!("M{} L{}", point(*base), point(*tip)));
+    }
+
+    for (i, (_, tip)) in comb.iter().enumerate() {
+        commands.push(format!("{}{}", if i == 0 { "M" } else { "L" }, point(*tip)));
+    }
+
+    return commands.join(" ");
+}
@@ -0,0 +1,166 @@
+use super::*;
+
+// What kind of segment each curve was in the glif it came from. from_contour turns everything into cubics, and going
+// back to_contour has to guess from the geometry which were lines, so a curve whose handles happen to sit on its
+// chord comes back as a line. A TaggedContour keeps the glif's answer alongside each curve so anything that doesn't
+// reshape its segments writes them back the way they came in. Segments that were made up along the way are None and
+// get the geometric guess.
+//
+// A glif can't say much more than this about a segment. Quadratics are degree elevated on the way in, and one tagged
+// QCurve goes back out as a qcurve with its one control point for as long as it's still a quadratic.
+
+impl SegmentType {
+    // The type of the segment ending at a point with this ptype, the same as a PointPen would be given for it. Move
+    // doesn't end a segment.
+    pub fn from_point_type(ptype: PointType) -> Option<Self>
+    {
+        return match ptype {
+            PointType::Line => Some(SegmentType::Line),
+            PointType::Curve => Some(SegmentType::Curve),
+            PointType::QCurve => Some(SegmentType::QCurve),
+            _ => None,
+        };
+    }
+}
+
+// A segment cut in two or turned around is still the same kind of segment.
+impl SegmentAttribute for SegmentType {}
+
+pub type TaggedContour = AttributedPiecewise<Bezier, Option<SegmentType>>;
+
+impl AttributedPiecewise<Bezier, Option<SegmentType>> {
+    // Piecewise::from_contour with each curve tagged by the type on the point it ends at, the same one Bezier::from
+    // went by.
+    pub fn from_contour<U>(contour: &Contour<U>) -> Self
+    {
+        if contour.is_empty() { return Self::from_pairs(Vec::new(), Vec::new()); }
+
+        // curves made up around implied on-curve points don't line up with the glif's points, they're left untagged
+        let piecewise = Piecewise::from_contour(contour);
+        if quadratic::has_off_curve_points(contour) {
+            let tags = vec![None; piecewise.len()];
+            return Self::from_pairs(piecewise.curves, tags);
+        }
+
+        let mut tags: Vec<Option<SegmentType>> = contour.iter().skip(1).map(|p| SegmentType::from_point_type(p.ptype)).collect();
+        if piecewise.len() > tags.len() {
+            tags.push(SegmentType::from_point_type(contour[0].ptype));
+        }
+
+        return Self::from_pairs(piecewise.curves, tags);
+    }
+
+    // Piecewise::to_contour, except a tagged curve is written as the type it's tagged with. A Line tag only holds
+    // while the curve is still a line and a QCurve one while it's still a quadratic, one that's since been bent out
+    // of it goes back to the geometric guess or a curve.
+    pub fn to_contour(&self) -> Contour<Option<PointData>>
+    {
+        let attributes = self.attributes();
+        let piecewise = self.piecewise();
+        let closed = piecewise.is_closed();
+        let mut contour = piecewise.to_contour_deciding(closed, &|i, control_points| {
+            return match attributes[i] {
+                Some(SegmentType::Curve) | Some(SegmentType::QCurve) => false,
+                _ => is_straight(control_points, LINE_EPSILON),
+            };
+        });
+
+        // curve i starts at point i and ends at the next one, which for the last curve of a closed contour is the first
+        for (i, bez) in piecewise.curves.iter().enumerate() {
+            if attributes[i] != Some(SegmentType::QCurve) { continue; }
+            let quad = match QuadBezier::from_cubic_exact(bez, 1e-6) {
+                Some(quad) => quad,
+                None => continue,
+            };
+
+            let end = if closed && i + 1 == piecewise.len() { 0 } else { i + 1 };
+            contour[i].a = quad.control.to_handle();
+            contour[end].b = Handle::Colocated;
+            contour[end].ptype = PointType::QCurve;
+        }
+
+        return contour;
+    }
+}
+
+// from_outline with every contour tagged.
+pub fn tagged_from_outline<U>(outline: &Outline<U>) -> Vec<TaggedContour>
+{
+    return outline.iter().map(|contour| TaggedContour::from_contour(contour)).collect();
+}
+
+pub fn tagged_to_outline(contours: &[TaggedContour]) -> Outline<Option<PointData>>
+{
+    return contours.iter().map(|contour| contour.to_contour()).collect();
+}
+
+// fix_winding_with_fill_rule for tagged contours, each keeping its tags. The tags don't come into deciding anything.
+pub fn fix_winding_tagged(contours: &[TaggedContour], fill_rule: FillRule) -> Vec<TaggedContour>
+{
+    let outline = Piecewise { curves: contours.iter().map(|c| c.piecewise().clone()).collect() };
+    return contours.iter().zip(outline.winding_fixes(fill_rule))
+        .map(|(contour, reverse)| if reverse { contour.reverse() } else { contour.clone() })
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A line, two qcurves with a control point each and a curve back to the start.
+    const GLIF: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<glyph name="tagged" format="2">
+  <advance width="300"/>
+  <outline>
+    <contour>
+      <point x="0" y="0" type="curve"/>
+      <point x="100" y="0" type="line"/>
+      <point x="150" y="50"/>
+      <point x="100" y="100" type="qcurve"/>
+      <point x="50" y="150"/>
+      <point x="0" y="100" type="qcurve"/>
+      <point x="-20" y="70"/>
+      <point x="-20" y="30"/>
+    </contour>
+  </outline>
+</glyph>
+"#;
+
+    // to a thousandth of a unit, going through the coefficients and back isn't exact
+    fn points(outline: &Outline<Option<PointData>>) -> Vec<(i64, i64, PointType)>
+    {
+        return outline.iter().flatten().map(|p| ((p.x * 1000.).round() as i64, (p.y * 1000.).round() as i64, p.ptype)).collect();
+    }
+
+    // Reversed and back again and written out, the qcurves are still qcurves with their one control point each.
+    #[test]
+    fn qcurves_round_trip()
+    {
+        let glif: glifparser::Glif<Option<PointData>> = glifparser::read_ufo_glif(GLIF);
+        let outline = glif.outline.clone().unwrap();
+        let reversed: Vec<TaggedContour> = tagged_from_outline(&outline).iter().map(|c| c.reverse().reverse()).collect();
+
+        let written = crate::glifwriter::write_ufo_glif(glifparser::Glif { outline: Some(tagged_to_outline(&reversed)), ..glif });
+        assert_eq!(written.matches("<point").count(), GLIF.matches("<point").count(), "{}", written);
+        assert_eq!(written.matches("qcurve").count(), 2, "{}", written);
+
+        let read: glifparser::Glif<Option<PointData>> = glifparser::read_ufo_glif(&written);
+        assert_eq!(points(read.outline.as_ref().unwrap()), points(&outline), "{}", written);
+        assert!(Piecewise::from_outline(read.outline.as_ref().unwrap()).approx_eq(&Piecewise::from_outline(&outline), 1e-6));
+    }
+
+    // A qcurve bent into a true cubic is written as a curve.
+    #[test]
+    fn reshaped_qcurves_are_curves()
+    {
+        let glif: glifparser::Glif<Option<PointData>> = glifparser::read_ufo_glif(GLIF);
+        let tagged = TaggedContour::from_contour(&glif.outline.unwrap()[0]);
+        let mut curves = tagged.piecewise().curves.clone();
+        let cp = curves[1].to_control_points();
+        curves[1] = Bezier::from_control_points(cp[0], cp[1] + Vector { x: 10., y: 0. }, cp[2], cp[3]);
+
+        let bent = TaggedContour::from_pairs(curves, tagged.attributes().to_vec()).to_contour();
+        let types: Vec<PointType> = bent.iter().map(|p| p.ptype).collect();
+        assert_eq!(types, vec![PointType::Curve, PointType::Line, PointType::Curve, PointType::QCurve]);
+    }
+}
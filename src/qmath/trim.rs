@@ -0,0 +1,95 @@
+use super::*;
+use super::edit::move_start;
+
+// Shortening and lengthening open paths at their ends by distance, so a cap can be pulled back inside a stem or a
+// stroke pushed on past one to overlap what it meets. Distances are measured with a PathMeasure that measures each
+// curve rather than using a table, the cuts land where the length says to within TRIM_TOLERANCE.
+
+// how close the cuts come to the distances asked for, in font units
+const TRIM_TOLERANCE: f64 = 1e-6;
+
+// What extend_with carries an end on with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtendMode {
+    // a straight line along the tangent at the end
+    Line,
+    // an arc with the curvature at the end, so the path keeps turning the way it was. Where the end is straight
+    // this is a line too.
+    Arc,
+}
+
+// The curves carried on past the end of the last one by distance.
+fn extend_end(mut curves: Vec<Bezier>, distance: f64, mode: ExtendMode) -> Vec<Bezier>
+{
+    if !(distance > 0.) || curves.is_empty() { return curves; }
+
+    let last = &curves[curves.len() - 1];
+    let end = last.to_control_points()[3];
+    let tangent = last.tangent_at(1.);
+
+    let curvature = match mode {
+        ExtendMode::Arc => last.curvature(1.).filter(|k| k.abs() > 1e-12),
+        ExtendMode::Line => None,
+    };
+
+    match curvature {
+        Some(k) => {
+            // the center is off to the left of the tangent for a curve turning counter-clockwise
+            let normal = Vector { x: -tangent.y, y: tangent.x };
+            let center = end + normal * (1. / k);
+            let from = end + -center;
+            let arc = Piecewise::arc(center, 1. / k.abs(), from.y.atan2(from.x), distance * k);
+
+            let mut pieces = arc.curves;
+            pieces[0] = move_start(&pieces[0], end);
+            curves.extend(pieces);
+        }
+        None => {
+            let to = end + tangent * distance;
+            curves.push(Bezier::from_control_points(end, end, to, to));
+        }
+    }
+
+    return curves;
+}
+
+impl Piecewise<Bezier>
+{
+    // The path with start_by taken off its start and end_by off its end, both by length along it. Negative
+    // distances count as nothing. When they add up to the whole length or more what's left is the single point
+    // where the two would meet, splitting the path between them in proportion, as one zero length curve like cut
+    // gives back. An empty path stays empty.
+    pub fn trim(&self, start_by: f64, end_by: f64) -> Self
+    {
+        if self.curves.is_empty() { return self.clone(); }
+
+        let (start_by, end_by) = (f64::max(start_by, 0.), f64::max(end_by, 0.));
+        let measure = PathMeasure::with_tolerance(self, TRIM_TOLERANCE);
+        let total = measure.length();
+
+        if start_by + end_by >= total {
+            let s = if start_by + end_by > 0. { total * start_by / (start_by + end_by) } else { 0. };
+            let p = measure.pos_tan_at(s).0;
+            return Piecewise { curves: vec![Bezier::from_control_points(p, p, p, p)] };
+        }
+
+        return measure.segment(start_by, total - end_by);
+    }
+
+    // extend_with a straight line on each end.
+    pub fn extend(&self, start_by: f64, end_by: f64) -> Self
+    {
+        return self.extend_with(start_by, end_by, ExtendMode::Line);
+    }
+
+    // The path carried on past its start by start_by and past its end by end_by, each extension new curves of its
+    // own joined smoothly on. A distance of zero or less leaves that end alone.
+    pub fn extend_with(&self, start_by: f64, end_by: f64, mode: ExtendMode) -> Self
+    {
+        let curves = extend_end(self.curves.clone(), end_by, mode);
+
+        // the start is the end of the path going the other way
+        let backwards = Piecewise { curves: curves }.reverse();
+        return Piecewise { curves: extend_end(backwards.curves, start_by, mode) }.reverse();
+    }
+}
@@ -0,0 +1,176 @@
+use super::*;
+
+// Checking an outline over before anything works on it. Bad input otherwise turns up as a panic somewhere deep in
+// evaluate or to_contour with nothing to say which contour did it, here everything wrong gets listed with where it
+// is so it can be shown to whoever drew it. Later checks skip contours that earlier ones found broken, there's no
+// sense asking which way a contour full of NaNs winds.
+
+// What validate looks for beyond the problems that are always wrong.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ValidateOptions {
+    // every contour should be closed, the case for outlines but not for the paths we stroke
+    pub expect_closed: bool,
+    // look for contours crossing themselves, this runs the intersector over every contour so it's slow
+    pub self_intersections: bool,
+    // coordinates have to be within (min, max), the default is what TrueType's 16 bit coordinates can hold
+    pub coordinate_range: Option<(f64, f64)>,
+    // points this close together are the same point
+    pub epsilon: f64,
+}
+
+impl Default for ValidateOptions {
+    fn default() -> Self
+    {
+        return ValidateOptions {
+            expect_closed: true,
+            self_intersections: false,
+            coordinate_range: Some((-32768., 32767.)),
+            epsilon: 1e-9,
+        };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiagnosticKind {
+    // a contour with no segments at all
+    EmptyContour,
+    // a NaN or infinity in one of the segment's control points
+    NonFinite,
+    // every control point of the segment is in the same place
+    ZeroLength,
+    // the segment starts and ends on the same point but its handles go somewhere, two on-curve points in a row
+    // that are the same point
+    DuplicatePoint,
+    // the segment doesn't start where the one before it ended
+    Disconnected { gap: f64 },
+    OpenContour,
+    // the contour crosses itself at point
+    SelfIntersection { point: Vector },
+    // the contour goes the same way as the one around it instead of the opposite, outers should go counter-clockwise
+    // and holes clockwise
+    InconsistentWinding { direction: WindingDirection },
+    // a control point outside the coordinate range
+    OutOfRange { point: Vector },
+}
+
+// One problem with the outline. segment is None for problems with the whole contour.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub contour: usize,
+    pub segment: Option<usize>,
+    pub kind: DiagnosticKind,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        match self.segment {
+            Some(segment) => write!(f, "Contour {} segment {}: ", self.contour, segment)?,
+            None => write!(f, "Contour {}: ", self.contour)?,
+        }
+
+        match &self.kind {
+            DiagnosticKind::EmptyContour => write!(f, "the contour has no segments."),
+            DiagnosticKind::NonFinite => write!(f, "a coordinate is NaN or infinite."),
+            DiagnosticKind::ZeroLength => write!(f, "the segment has no length."),
+            DiagnosticKind::DuplicatePoint => write!(f, "the segment starts and ends on the same point."),
+            DiagnosticKind::Disconnected { gap } => write!(f, "the segment starts {} units from where the last one ended.", gap),
+            DiagnosticKind::OpenContour => write!(f, "the contour isn't closed."),
+            DiagnosticKind::SelfIntersection { point } => write!(f, "the contour crosses itself at ({}, {}).", point.x, point.y),
+            DiagnosticKind::InconsistentWinding { direction } => write!(f, "the contour winds {:?}, the wrong way for where it is.", direction),
+            DiagnosticKind::OutOfRange { point } => write!(f, "({}, {}) is out of range.", point.x, point.y),
+        }
+    }
+}
+
+fn is_finite(bez: &Bezier) -> bool
+{
+    return bez.coefficients().iter().all(|c| c.is_finite());
+}
+
+// Everything wrong with one contour on its own. Hands back whether it came through clean and closed, which is what
+// the winding check needs.
+fn validate_contour(index: usize, contour: &Piecewise<Bezier>, options: &ValidateOptions, output: &mut Vec<Diagnostic>) -> bool
+{
+    let found = output.len();
+    let mut push = |segment: Option<usize>, kind: DiagnosticKind| output.push(Diagnostic { contour: index, segment: segment, kind: kind });
+
+    if contour.curves.is_empty() {
+        push(None, DiagnosticKind::EmptyContour);
+        return false;
+    }
+
+    for (i, bez) in contour.curves.iter().enumerate() {
+        if !is_finite(bez) {
+            push(Some(i), DiagnosticKind::NonFinite);
+            continue;
+        }
+
+        let p = bez.to_control_points();
+        if bez.is_degenerate(options.epsilon) {
+            push(Some(i), DiagnosticKind::ZeroLength);
+        } else if p[0].distance(p[3]) <= options.epsilon {
+            push(Some(i), DiagnosticKind::DuplicatePoint);
+        }
+
+        if i > 0 {
+            let gap = contour.curves[i - 1].to_control_points()[3].distance(p[0]);
+            if gap > options.epsilon { push(Some(i), DiagnosticKind::Disconnected { gap: gap }); }
+        }
+
+        if let Some((min, max)) = options.coordinate_range {
+            if let Some(point) = p.iter().find(|q| q.x < min || q.x > max || q.y < min || q.y > max) {
+                push(Some(i), DiagnosticKind::OutOfRange { point: *point });
+            }
+        }
+    }
+
+    // a NaN never equals anything so every contour with one in would look open
+    let finite = contour.curves.iter().all(is_finite);
+    let closed = finite && contour.is_closed();
+    if options.expect_closed && finite && !closed { push(None, DiagnosticKind::OpenContour); }
+
+    // a zero length segment or a gap meets its neighbours in all the wrong places, we only look at clean contours
+    if output.len() > found { return false; }
+
+    if options.self_intersections {
+        let n = contour.curves.len() as f64;
+        for (t, _) in contour.self_intersections(options.epsilon.max(1e-6)) {
+            let segment = ((t * n).floor() as usize).min(contour.curves.len() - 1);
+            output.push(Diagnostic { contour: index, segment: Some(segment), kind: DiagnosticKind::SelfIntersection { point: contour.evaluate(t) } });
+        }
+    }
+
+    return closed && output.len() == found;
+}
+
+// validate_with using the default options.
+pub fn validate(outline: &Piecewise<Piecewise<Bezier>>) -> Vec<Diagnostic>
+{
+    return validate_with(outline, &ValidateOptions::default());
+}
+
+// Everything wrong with outline, contour by contour in order. Self intersections are only looked for on contours with
+// nothing else wrong with them, and winding only between closed contours with nothing wrong at all. Like fix_winding
+// that assumes they don't cross each other.
+pub fn validate_with(outline: &Piecewise<Piecewise<Bezier>>, options: &ValidateOptions) -> Vec<Diagnostic>
+{
+    let mut output = Vec::new();
+
+    let mut sound = Vec::new();
+    for (i, contour) in outline.curves.iter().enumerate() {
+        if validate_contour(i, contour, options, &mut output) { sound.push(i); }
+    }
+
+    let checked = Piecewise { curves: sound.iter().map(|&i| outline.curves[i].clone()).collect() };
+    let fixed = checked.fix_winding();
+    for (k, &i) in sound.iter().enumerate() {
+        let direction = checked.curves[k].winding_direction();
+        if direction != WindingDirection::Degenerate && direction != fixed.curves[k].winding_direction() {
+            output.push(Diagnostic { contour: i, segment: None, kind: DiagnosticKind::InconsistentWinding { direction: direction } });
+        }
+    }
+
+    output.sort_by_key(|d| (d.contour, d.segment.map_or(0, |s| s + 1)));
+    return output;
+}
@@ -0,0 +1,160 @@
+use super::*;
+
+// The rest of what you'd want to do with a Vector. Most of the modules that needed a dot or a cross product used to
+// carry their own copy, these are the one place for them now.
+
+// what approx_eq counts as the same, the same nudge the rest of qmath uses to ignore rounding
+pub const VECTOR_EPSILON: f64 = 1e-9;
+
+impl Vector {
+    pub fn dot(self, v1: Vector) -> f64
+    {
+        return self.x * v1.x + self.y * v1.y;
+    }
+
+    // The z of the 3D cross product, positive when v1 is counter-clockwise of self in y-up space.
+    pub fn cross(self, v1: Vector) -> f64
+    {
+        return self.x * v1.y - self.y * v1.x;
+    }
+
+    pub fn length(self) -> f64
+    {
+        return self.magnitude();
+    }
+
+    pub fn length_squared(self) -> f64
+    {
+        return self.dot(self);
+    }
+
+    // normalize divides by zero for a zero length vector, this hands back None for one instead.
+    pub fn try_normalize(self) -> Option<Vector>
+    {
+        let length = self.length();
+        if length <= VECTOR_EPSILON || !length.is_finite() { return None; }
+        return Some(self * (1. / length));
+    }
+
+    pub fn normalize_or_zero(self) -> Vector
+    {
+        return self.try_normalize().unwrap_or(Vector { x: 0., y: 0. });
+    }
+
+    // The angle that turns self onto v1, in -pi..=pi and counter-clockwise positive in y-up space. Zero if either
+    // of them is zero.
+    pub fn angle_to(self, v1: Vector) -> f64
+    {
+        return f64::atan2(self.cross(v1), self.dot(v1));
+    }
+
+    // Turned counter-clockwise by angle radians in y-up space, clockwise on screen in y-down space.
+    pub fn rotate(self, angle: f64) -> Vector
+    {
+        let (sin, cos) = angle.sin_cos();
+        return Vector { x: self.x * cos - self.y * sin, y: self.x * sin + self.y * cos };
+    }
+
+    pub fn rotate_around(self, pivot: Vector, angle: f64) -> Vector
+    {
+        return pivot + (self + -pivot).rotate(angle);
+    }
+
+    // Turned a quarter counter-clockwise in y-up space, the same length as self.
+    pub fn perpendicular(self) -> Vector
+    {
+        return Vector { x: -self.y, y: self.x };
+    }
+
+    pub fn approx_eq(self, v1: Vector) -> bool
+    {
+        return self.is_near(v1, VECTOR_EPSILON);
+    }
+
+    // Close enough relative to how big the two are, for comparing points far from the origin where an absolute
+    // epsilon is smaller than a rounding error.
+    pub fn approx_eq_relative(self, v1: Vector, tolerance: f64) -> bool
+    {
+        let scale = f64::max(1., f64::max(self.length(), v1.length()));
+        return self.distance(v1) <= tolerance * scale;
+    }
+}
+
+impl std::ops::Div<f64> for Vector {
+    type Output = Vector;
+
+    fn div(self, s: f64) -> Vector { return Vector { x: self.x / s, y: self.y / s }; }
+}
+
+impl std::ops::Mul<Vector> for f64 {
+    type Output = Vector;
+
+    fn mul(self, v: Vector) -> Vector { return v.multiply_scalar(self); }
+}
+
+impl std::ops::AddAssign<Vector> for Vector {
+    fn add_assign(&mut self, v1: Vector) { *self = self.add(v1); }
+}
+
+impl std::ops::SubAssign<Vector> for Vector {
+    fn sub_assign(&mut self, v1: Vector) { *self = Vector::sub(*self, v1); }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    fn v(x: f64, y: f64) -> Vector
+    {
+        return Vector { x: x, y: y };
+    }
+
+    #[test]
+    fn products_and_angles()
+    {
+        let (a, b) = (v(3., 4.), v(-4., 3.));
+        assert_eq!((a.dot(b), a.cross(b), b.cross(a)), (0., 25., -25.));
+        assert_eq!((a.length(), a.length_squared()), (5., 25.));
+        assert_eq!(a.perpendicular(), b);
+
+        assert_eq!(a.angle_to(b), FRAC_PI_2);
+        assert_eq!(b.angle_to(a), -FRAC_PI_2);
+        assert_eq!(v(1., 0.).angle_to(v(-1., 0.)), PI);
+        assert_eq!(v(0., 0.).angle_to(a), 0.);
+    }
+
+    #[test]
+    fn rotation_and_normalizing()
+    {
+        assert!(v(1., 0.).rotate(FRAC_PI_2).approx_eq(v(0., 1.)));
+        assert!(v(3., 4.).rotate(PI).approx_eq(v(-3., -4.)));
+        assert!(v(2., 1.).rotate_around(v(1., 1.), FRAC_PI_2).approx_eq(v(1., 2.)));
+        assert!(v(1., 1.).rotate_around(v(1., 1.), 1.).approx_eq(v(1., 1.)));
+        let turned = v(3., 4.).rotate(0.3);
+        assert!((turned.length() - 5.).abs() < 1e-12 && (v(3., 4.).angle_to(turned) - 0.3).abs() < 1e-12);
+
+        assert!(v(3., 4.).try_normalize().unwrap().approx_eq(v(0.6, 0.8)));
+        assert_eq!(v(0., 0.).try_normalize(), None);
+        assert_eq!(v(f64::INFINITY, 0.).try_normalize(), None);
+        assert_eq!(v(0., 1e-12).normalize_or_zero(), v(0., 0.));
+    }
+
+    #[test]
+    fn operators_and_equality()
+    {
+        let mut a = v(1., 2.);
+        a += v(2., 2.);
+        assert_eq!(a, v(3., 4.));
+        a -= v(1., 1.);
+        assert_eq!((a, a / 2., 2. * a, a * 2., -a), (v(2., 3.), v(1., 1.5), v(4., 6.), v(4., 6.), v(-2., -3.)));
+        assert_eq!(a.lerp(v(4., 5.), 0.5), v(3., 4.));
+
+        assert!(v(1., 1.).approx_eq(v(1. + 1e-10, 1.)));
+        assert!(!v(1., 1.).approx_eq(v(1. + 1e-8, 1.)));
+        // a millionth off a million out is a rounding error relatively, not absolutely
+        assert!(v(1e6, 0.).approx_eq_relative(v(1e6 + 1e-6, 0.), 1e-9));
+        assert!(!v(1e6, 0.).approx_eq(v(1e6 + 1e-6, 0.)));
+        assert!(!v(1., 0.).approx_eq_relative(v(1. + 1e-6, 0.), 1e-9));
+    }
+}
@@ -0,0 +1,103 @@
+use super::*;
+use super::edit::{move_start, move_end};
+
+// Putting open runs of curves back together into contours. Dashing, cutting and clipping leave piles of pieces whose
+// ends nearly meet, weld chains them up end to end, turning pieces around where they run the wrong way, and closes
+// any chain that comes back round to where it started.
+//
+// Where more than two ends meet at one place there's more than one way to chain them. We don't try to be clever,
+// the nearest end wins, closing the chain wins a tie and after that the earliest piece going the way it already
+// goes. The junction is reported so the caller can decide whether that's good enough.
+
+// What weld did.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WeldReport {
+    // pieces joined onto another
+    pub joined: usize,
+    // chains that came back round and were closed
+    pub closed: usize,
+    // places more than two ends met, once each
+    pub ambiguous: Vec<Vector>,
+}
+
+fn start_of(piece: &Piecewise<Bezier>) -> Vector
+{
+    return piece.curves[0].to_control_points()[0];
+}
+
+fn end_of(piece: &Piecewise<Bezier>) -> Vector
+{
+    return piece.curves[piece.curves.len() - 1].to_control_points()[3];
+}
+
+// Grows chain off its end until nothing more meets it, returning whether it closed.
+fn extend(chain: &mut Vec<Bezier>, pieces: &[Piecewise<Bezier>], used: &mut [bool], tolerance: f64, report: &mut WeldReport) -> bool
+{
+    loop {
+        let first = chain[0].to_control_points()[0];
+        let end = chain[chain.len() - 1].to_control_points()[3];
+
+        // (how far, which piece or None to close, whether it's reversed)
+        let mut candidates: Vec<(f64, Option<usize>, bool)> = Vec::new();
+        if end.distance(first) <= tolerance { candidates.push((end.distance(first), None, false)); }
+        for (k, piece) in pieces.iter().enumerate() {
+            if used[k] { continue; }
+            if end.distance(start_of(piece)) <= tolerance { candidates.push((end.distance(start_of(piece)), Some(k), false)); }
+            if end.distance(end_of(piece)) <= tolerance { candidates.push((end.distance(end_of(piece)), Some(k), true)); }
+        }
+
+        if candidates.len() > 1 && !report.ambiguous.iter().any(|p| p.distance(end) <= tolerance) {
+            report.ambiguous.push(end);
+        }
+
+        // None sorts before Some so closing wins a tie, then the earlier piece, then the one that isn't reversed
+        candidates.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+
+        match candidates.first() {
+            None => return false,
+            Some((_, None, _)) => {
+                let last = chain.len() - 1;
+                chain[last] = move_end(&chain[last], first);
+                report.closed = report.closed + 1;
+                return true;
+            }
+            Some((_, Some(k), reversed)) => {
+                let (k, reversed) = (*k, *reversed);
+                used[k] = true;
+                let mut next = if reversed { pieces[k].reverse().curves } else { pieces[k].curves.clone() };
+                next[0] = move_start(&next[0], end);
+                chain.extend(next);
+                report.joined = report.joined + 1;
+            }
+        }
+    }
+}
+
+// Chains the pieces into as few contours as it can, joining ends within tolerance of each other. Each joint is
+// snapped onto the end of the chain so far, and a chain whose ends meet is closed by snapping its end onto its
+// start. Chains that don't come back round stay open. Every chain goes the way the earliest piece in it went and
+// chains come out in the order of their earliest pieces. Empty pieces are dropped.
+pub fn weld(pieces: Vec<Piecewise<Bezier>>, tolerance: f64) -> (Vec<Piecewise<Bezier>>, WeldReport)
+{
+    let pieces: Vec<Piecewise<Bezier>> = pieces.into_iter().filter(|p| !p.curves.is_empty()).collect();
+    let mut used = vec![false; pieces.len()];
+    let mut report = WeldReport::default();
+
+    let mut output = Vec::new();
+    for i in 0..pieces.len() {
+        if used[i] { continue; }
+        used[i] = true;
+
+        let mut chain = pieces[i].curves.clone();
+        if !extend(&mut chain, &pieces, &mut used, tolerance, &mut report) {
+            // the first piece might have been from the middle, so grow it off its start too
+            let mut backwards = Piecewise { curves: chain }.reverse().curves;
+            extend(&mut backwards, &pieces, &mut used, tolerance, &mut report);
+            chain = Piecewise { curves: backwards }.reverse().curves;
+        }
+
+        output.push(Piecewise { curves: chain });
+    }
+
+    return (output, report);
+}
@@ -0,0 +1,103 @@
+use super::*;
+use std::cmp::Ordering;
+
+// How wide a stroke actually came out along its skeleton. Where offset_error measures from the outline back to the
+// skeleton, this goes the other way: stand on the skeleton, look out along the normal either side and see how far
+// it is to the outline. That catches a variable width stroke drifting off the widths it was given and remove
+// overlap eating into the stroke, neither of which the distance from the outline back to the skeleton shows.
+
+// how far in from an open end, as a fraction of the skeleton's length, the stations on the ends are measured from.
+// Right on the end a butt cap runs through the station along the normal and there's nothing either side to measure
+const END_NUDGE: f64 = 1e-6;
+
+// A crossing further from the station than this many times its distance from the nearest point on the skeleton is
+// beside some other part of the stroke, round a cap or across a gap, not beside the station. Twice is the outline
+// sloping away at 60 degrees, which no width we'd be asked to check should do.
+const MAX_SLANT: f64 = 2.;
+
+// One station along the skeleton. The widths are measured from the skeleton out to the outline, left is the left
+// of the skeleton's direction in font space. A side is None where looking out that way leaves the stroke round a
+// cap or somewhere else that isn't beside the station, and total is None unless both sides were measured.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WidthSample {
+    pub point: Vector,
+    // how far along the skeleton the station is
+    pub distance: f64,
+    pub left: Option<f64>,
+    pub right: Option<f64>,
+    pub total: Option<f64>,
+}
+
+// How far it is from station along direction to where the outline's fill ends, looking no further than reach.
+// Outlines that haven't had their overlap removed have pieces of contour running through the fill, like an inner
+// join pinned to the skeleton, so that's the first crossing with nothing filled past it rather than just the first
+// crossing. Zero if the station isn't filled at all.
+fn measure_side(skeleton: &Piecewise<Bezier>, outline: &Piecewise<Piecewise<Bezier>>, station: Vector, direction: Vector, reach: f64) -> Option<f64>
+{
+    if !outline.contains(station, FillRule::NonZero) { return Some(0.); }
+
+    let far = station + direction * reach;
+    let mut crossings: Vec<f64> = outline.curves.iter()
+        .flat_map(|contour| contour.segment_intersections(station, far))
+        .map(|(_, point)| point.distance(station))
+        .collect();
+    crossings.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+    let edge = (0..crossings.len()).find(|&i| match crossings.get(i + 1) {
+        Some(next) => !outline.contains(station + direction * ((crossings[i] + next) / 2.), FillRule::NonZero),
+        None => true,
+    });
+    let distance = crossings[edge?];
+
+    let (_, _, from_skeleton) = skeleton.nearest(station + direction * distance)?;
+    if distance > from_skeleton * MAX_SLANT { return None; }
+
+    return Some(distance);
+}
+
+// Measures the stroke's width at n stations spaced evenly by arc length along skeleton. An open skeleton gets a
+// station on both ends, a closed one only on its start. Nothing for an empty skeleton or n of zero, and every side
+// None if the outline is empty.
+pub fn measure_widths(skeleton: &Piecewise<Bezier>, outline: &Piecewise<Piecewise<Bezier>>, n: usize) -> Vec<WidthSample>
+{
+    if skeleton.curves.is_empty() || n == 0 { return Vec::new(); }
+
+    let bounds = outline.curves.iter().filter(|c| !c.curves.is_empty())
+        .fold(None, |b: Option<Rect>, c| Some(b.map_or(c.bounds(), |b| b.encapsulate_rect(c.bounds()))));
+
+    let measure = PathMeasure::new(skeleton);
+    let length = measure.length();
+    let closed = skeleton.is_closed();
+    let spacing = match (closed, n) {
+        (true, _) => length / n as f64,
+        (false, 1) => 0.,
+        (false, _) => length / (n - 1) as f64,
+    };
+
+    let mut output = Vec::new();
+    for i in 0..n {
+        let s = spacing * i as f64;
+        let at = if closed { s } else { s.max(length * END_NUDGE).min(length * (1. - END_NUDGE)) };
+        let (station, _) = measure.pos_tan_at(at);
+        let normal = measure.normal_at(at);
+
+        let (left, right) = match bounds {
+            Some(bounds) => {
+                // far enough that the segment leaves the outline's bounds whichever way it points
+                let reach = station.distance(bounds.center()) + bounds.width() + bounds.height();
+                (measure_side(skeleton, outline, station, normal, reach), measure_side(skeleton, outline, station, -normal, reach))
+            }
+            None => (None, None),
+        };
+
+        output.push(WidthSample {
+            point: station,
+            distance: s,
+            left: left,
+            right: right,
+            total: left.and_then(|l| right.map(|r| l + r)),
+        });
+    }
+
+    return output;
+}
@@ -0,0 +1,179 @@
+use super::*;
+
+// Which points an outline covers. Glifs and TrueType are drawn nonzero, but SVG input can ask for even-odd and
+// anything that tests containment or removes overlap needs to agree with whichever one the output is going to be
+// rendered with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "lowercase"))]
+pub enum FillRule {
+    NonZero,
+    EvenOdd,
+}
+
+impl Default for FillRule {
+    fn default() -> Self
+    {
+        return FillRule::NonZero;
+    }
+}
+
+impl FillRule {
+    pub fn is_filled(&self, winding: i32) -> bool
+    {
+        match self {
+            FillRule::NonZero => winding != 0,
+            FillRule::EvenOdd => winding % 2 != 0,
+        }
+    }
+}
+
+impl Bezier {
+    // How many times the curve crosses a ray cast from point towards +x, counting upward crossings as +1 and
+    // downward ones as -1.
+    //
+    // We split the curve where it turns around vertically so every piece only goes up or down, then count a piece
+    // if point.y is in [lowest, highest) of it. Half open like that a joint between two pieces, whether it's between
+    // two curves or at a turning point inside of one, gets counted exactly once when the path carries on through it
+    // and either zero or two times (cancelling) when the ray only grazes it. Lines are stored with their handles
+    // on their ends so their derivative vanishes there, this doesn't rely on the derivative at all.
+    //
+    // end_y is where the next curve starts. Going through the coefficients our end can come out a hair off of that,
+    // and a joint only gets counted once if both sides agree on where it is.
+    fn winding_contribution(&self, point: Vector, end_y: f64) -> i32
+    {
+        let mut bounds = vec![0.];
+        bounds.extend(roots::solve_quadratic(3. * self.E, 2. * self.F, self.G, 0., 1.));
+        bounds.push(1.);
+
+        let mut winding = 0;
+        for pair in bounds.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            let y0 = self.evaluate(a).y;
+            let y1 = if b >= 1. { end_y } else { self.evaluate(b).y };
+            if y0 == y1 { continue; }
+
+            let (low, high) = if y0 < y1 { (y0, y1) } else { (y1, y0) };
+            if !(point.y >= low && point.y < high) { continue; }
+
+            // the piece is monotone in y so bisecting finds where it crosses
+            let (mut lo, mut hi) = (a, b);
+            for _ in 0..60 {
+                let mid = (lo + hi) / 2.;
+                let below = self.evaluate(mid).y < point.y;
+                if below == (y0 < y1) { lo = mid; } else { hi = mid; }
+            }
+
+            if self.evaluate((lo + hi) / 2.).x <= point.x { continue; }
+            winding = winding + if y1 > y0 { 1 } else { -1 };
+        }
+
+        return winding;
+    }
+}
+
+impl Piecewise<Bezier>
+{
+    // Positive for counter-clockwise contours in our y-up space, the same orientation signed_area calls positive.
+    // Contours that don't end where they start are treated as closed by a straight line.
+    pub fn winding_number(&self, point: Vector) -> i32
+    {
+        if self.curves.is_empty() { return 0; }
+
+        let closed = self.is_closed();
+        let start = self.curves[0].to_control_points()[0];
+
+        let mut winding = 0;
+        for (i, bez) in self.curves.iter().enumerate() {
+            let end = match self.curves.get(i + 1) {
+                Some(next) => next.to_control_points()[0],
+                None => if closed { start } else { bez.to_control_points()[3] }
+            };
+            winding = winding + bez.winding_contribution(point, end.y);
+        }
+
+        if !closed {
+            let end = self.curves.last().unwrap().to_control_points()[3];
+            winding = winding + Bezier::from_control_points(end, end, start, start).winding_contribution(point, start.y);
+        }
+
+        return winding;
+    }
+}
+
+impl Piecewise<Piecewise<Bezier>>
+{
+    pub fn winding_number(&self, point: Vector) -> i32
+    {
+        return self.curves.iter().map(|c| c.winding_number(point)).sum();
+    }
+
+    pub fn contains(&self, point: Vector, fill_rule: FillRule) -> bool
+    {
+        return fill_rule.is_filled(self.winding_number(point));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    fn v(x: f64, y: f64) -> Vector
+    {
+        return Vector { x: x, y: y };
+    }
+
+    // Counter-clockwise round a circle to the left of the origin and then clockwise round one to the right, both
+    // starting and ending on the origin. Rays along y = 0 go straight through the joints at the sides of both
+    // circles and the one in the middle where they touch.
+    fn figure_eight() -> Piecewise<Bezier>
+    {
+        let mut curves = Piecewise::arc(v(-50., 0.), 50., 0., 2. * PI).curves;
+        curves.extend(Piecewise::arc(v(50., 0.), 50., PI, -2. * PI).curves);
+        return Piecewise::new(curves);
+    }
+
+    #[test]
+    fn figure_eight_lobes_wind_opposite_ways()
+    {
+        let eight = figure_eight();
+        assert_eq!(eight.winding_number(v(-50., 0.)), 1);
+        assert_eq!(eight.winding_number(v(50., 0.)), -1);
+        assert_eq!(eight.winding_number(v(-150., 0.)), 0);
+        assert_eq!(eight.winding_number(v(150., 0.)), 0);
+        assert_eq!(eight.winding_number(v(0., 30.)), 0);
+        assert_eq!(eight.winding_number(v(-50., 49.)), 1);
+        assert_eq!(eight.winding_number(v(50., -49.)), -1);
+
+        // both lobes are filled either way, neither is inside the other
+        let outline = Piecewise::new(vec![eight]);
+        for fill_rule in [FillRule::NonZero, FillRule::EvenOdd].iter() {
+            assert!(outline.contains(v(-50., 0.), *fill_rule) && outline.contains(v(50., 0.), *fill_rule));
+            assert!(!outline.contains(v(0., 30.), *fill_rule));
+        }
+    }
+
+    // Circles inside circles, every one starting on y = 0 where the ray from the center crosses them all.
+    #[test]
+    fn nested_contours_add_up()
+    {
+        let ccw = |r: f64| Piecewise::circle(v(0., 0.), r);
+        let cw = |r: f64| Piecewise::circle(v(0., 0.), r).reverse();
+        let at = |outline: &Piecewise<Piecewise<Bezier>>| [0., 10., 40., 80., 120.].iter().map(|x| outline.winding_number(v(*x, 0.))).collect::<Vec<i32>>();
+
+        // outer, hole, island
+        let alternating = Piecewise::new(vec![ccw(100.), cw(60.), ccw(20.)]);
+        assert_eq!(at(&alternating), vec![1, 1, 0, 1, 0]);
+
+        // all the same way they stack up, so only nonzero fills the middle
+        let stacked = Piecewise::new(vec![ccw(100.), ccw(60.), ccw(20.)]);
+        assert_eq!(at(&stacked), vec![3, 3, 2, 1, 0]);
+        assert!(stacked.contains(v(40., 0.), FillRule::NonZero));
+        assert!(!stacked.contains(v(40., 0.), FillRule::EvenOdd));
+        assert!(stacked.contains(v(0., 0.), FillRule::EvenOdd));
+
+        // an open contour is closed by the straight line back to its start, a half circle is the half disc
+        let half = Piecewise::arc(v(0., 0.), 50., 0., PI);
+        assert_eq!([half.winding_number(v(0., 25.)), half.winding_number(v(0., -25.))], [1, 0]);
+    }
+}
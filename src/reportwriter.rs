@@ -0,0 +1,224 @@
+use qstroke::stroke_report::StrokeReport;
+use std::fs;
+use std::path::Path;
+
+const CSV_HEADER: &str = "name,input_contours,output_contours,input_points,output_points,bbox_left,bbox_bottom,bbox_right,bbox_top,filled_area,measure_angle,measured_extent,max_offset_error,mean_offset_error,max_join_ratio,join_histogram,moved_anchors,warnings,time_ms,cached,error";
+
+fn csv_field(field: &str) -> String
+{
+    if field.contains(',') || field.contains('"') || field.contains('\n') {
+        return format!("\"{}\"", field.replace('"', "\"\""));
+    }
+
+    return field.to_string();
+}
+
+fn json_string(string: &str) -> String
+{
+    let mut output = String::from("\"");
+    for c in string.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => output.push_str(&format!("\\u{:04x}", c as u32)),
+            c => output.push(c)
+        }
+    }
+    output.push('"');
+
+    return output;
+}
+
+// the bucket counts separated by spaces so they stay in one column
+fn histogram_field(histogram: &[usize]) -> String
+{
+    return histogram.iter().map(|c| c.to_string()).collect::<Vec<String>>().join(" ");
+}
+
+// the bounds come back as left, bottom, right, top or as empty strings when there was no output to measure
+fn bounds_fields(report: &StrokeReport) -> [String; 4]
+{
+    match report.output_bounds {
+        Some(b) => [b.left.to_string(), b.bottom.to_string(), b.right.to_string(), b.top.to_string()],
+        None => [String::new(), String::new(), String::new(), String::new()]
+    }
+}
+
+fn report_to_csv(reports: &[StrokeReport]) -> String
+{
+    let mut output = String::from(CSV_HEADER);
+    output.push('\n');
+
+    for report in reports {
+        let bounds = bounds_fields(report);
+        let row = vec![
+            csv_field(&report.name),
+            report.input_contours.to_string(),
+            report.output_contours.to_string(),
+            report.input_points.to_string(),
+            report.output_points.to_string(),
+            bounds[0].clone(), bounds[1].clone(), bounds[2].clone(), bounds[3].clone(),
+            report.filled_area.to_string(),
+            report.measure_angle.map(|a| a.to_string()).unwrap_or_default(),
+            report.measured_extent.map(|e| e.to_string()).unwrap_or_default(),
+            report.offset_error.map(|e| e.max_error.to_string()).unwrap_or_default(),
+            report.offset_error.map(|e| e.mean_error.to_string()).unwrap_or_default(),
+            report.max_join_ratio.map(|r| r.to_string()).unwrap_or_default(),
+            histogram_field(&report.join_histogram),
+            csv_field(&report.moved_anchors.join(" ")),
+            report.warnings.len().to_string(),
+            report.duration.as_millis().to_string(),
+            report.cached.to_string(),
+            csv_field(report.error.as_deref().unwrap_or("")),
+        ];
+
+        output.push_str(&row.join(","));
+        output.push('\n');
+    }
+
+    return output;
+}
+
+fn report_to_json(reports: &[StrokeReport]) -> String
+{
+    let mut rows = Vec::new();
+
+    for report in reports {
+        let bounds = match report.output_bounds {
+            Some(b) => format!("{{\"left\": {}, \"bottom\": {}, \"right\": {}, \"top\": {}}}", b.left, b.bottom, b.right, b.top),
+            None => String::from("null")
+        };
+
+        let error = match &report.error {
+            Some(e) => json_string(e),
+            None => String::from("null")
+        };
+
+        rows.push(format!(
+            "  {{\"name\": {}, \"input_contours\": {}, \"output_contours\": {}, \"input_points\": {}, \"output_points\": {}, \"bounds\": {}, \"filled_area\": {}, \"measure_angle\": {}, \"measured_extent\": {}, \"max_offset_error\": {}, \"mean_offset_error\": {}, \"max_join_ratio\": {}, \"join_histogram\": [{}], \"moved_anchors\": [{}], \"warnings\": {}, \"time_ms\": {}, \"cached\": {}, \"error\": {}}}",
+            json_string(&report.name),
+            report.input_contours,
+            report.output_contours,
+            report.input_points,
+            report.output_points,
+            bounds,
+            report.filled_area,
+            report.measure_angle.map(|a| a.to_string()).unwrap_or(String::from("null")),
+            report.measured_extent.map(|e| e.to_string()).unwrap_or(String::from("null")),
+            report.offset_error.map(|e| e.max_error.to_string()).unwrap_or(String::from("null")),
+            report.offset_error.map(|e| e.mean_error.to_string()).unwrap_or(String::from("null")),
+            report.max_join_ratio.map(|r| r.to_string()).unwrap_or(String::from("null")),
+            report.join_histogram.iter().map(|c| c.to_string()).collect::<Vec<String>>().join(", "),
+            report.moved_anchors.iter().map(|a| json_string(a)).collect::<Vec<String>>().join(", "),
+            report.warnings.len(),
+            report.duration.as_millis(),
+            report.cached,
+            error
+        ));
+    }
+
+    return format!("[\n{}\n]\n", rows.join(",\n"));
+}
+
+// Writes one row per report, picking CSV or JSON based on the file's extension.
+pub fn write_report(filename: &str, reports: &[StrokeReport]) -> Result<(), String>
+{
+    let extension = Path::new(filename).extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase());
+
+    let contents = match extension.as_deref() {
+        Some("csv") => report_to_csv(reports),
+        Some("json") => report_to_json(reports),
+        _ => return Err(format!("Can't tell what kind of report to write from {}. Use a .csv or .json extension.", filename))
+    };
+
+    return fs::write(filename, contents).map_err(|e| format!("Unable to write report: {}", e));
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    // Reads CSV the way a spreadsheet would, quoted fields can hold commas, doubled quotes and newlines.
+    pub fn parse_csv(text: &str) -> Vec<Vec<String>>
+    {
+        let mut rows = Vec::new();
+        let mut row = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut chars = text.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match (c, quoted) {
+                ('"', true) if chars.peek() == Some(&'"') => { field.push('"'); chars.next(); }
+                ('"', _) => quoted = !quoted,
+                (',', false) => row.push(std::mem::take(&mut field)),
+                ('\n', false) => {
+                    row.push(std::mem::take(&mut field));
+                    rows.push(std::mem::take(&mut row));
+                }
+                (c, _) => field.push(c)
+            }
+        }
+
+        return rows;
+    }
+
+    fn awkward_report() -> StrokeReport
+    {
+        let mut report = StrokeReport::new("quote\"comma,\nnewline");
+        report.moved_anchors = vec![String::from("top,1"), String::from("back\\slash")];
+        report.join_histogram = vec![1, 0, 2];
+        report.warnings = vec![String::from("one"), String::from("two")];
+        report.duration = Duration::from_millis(12);
+        report.error = Some(String::from("tab\there, bell\u{7} \"quoted\""));
+        return report;
+    }
+
+    #[test]
+    fn csv_fields_are_escaped()
+    {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn json_strings_are_escaped()
+    {
+        assert_eq!(json_string("plain"), "\"plain\"");
+        assert_eq!(json_string("\"\\\n\r\t"), "\"\\\"\\\\\\n\\r\\t\"");
+        assert_eq!(json_string("\u{1}\u{1f}"), "\"\\u0001\\u001f\"");
+        assert_eq!(json_string("é ✓"), "\"é ✓\"");
+    }
+
+    // Whatever's in the names and errors, each report reads back as one row with every column.
+    #[test]
+    fn awkward_reports_read_back()
+    {
+        let reports = [awkward_report(), StrokeReport::new("b")];
+
+        let rows = parse_csv(&report_to_csv(&reports));
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].join(","), CSV_HEADER);
+        assert!(rows.iter().all(|row| row.len() == rows[0].len()));
+        assert_eq!(rows[1][0], reports[0].name);
+        assert_eq!(rows[1][15], "1 0 2");
+        assert_eq!(rows[1][16], "top,1 back\\slash");
+        assert_eq!(rows[1][17], "2");
+        assert_eq!(rows[1][18], "12");
+        assert_eq!(rows[1][20], reports[0].error.clone().unwrap());
+        assert_eq!(rows[2][5], "");
+
+        let json: serde_json::Value = serde_json::from_str(&report_to_json(&reports)).unwrap();
+        assert_eq!(json[0]["name"], reports[0].name.as_str());
+        assert_eq!(json[0]["moved_anchors"], serde_json::json!(["top,1", "back\\slash"]));
+        assert_eq!(json[0]["error"], reports[0].error.clone().unwrap().as_str());
+        assert_eq!(json[1]["bounds"], serde_json::Value::Null);
+        assert_eq!(json[1]["error"], serde_json::Value::Null);
+    }
+}
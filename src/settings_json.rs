@@ -0,0 +1,224 @@
+// Reads pattern settings out of a JSON object for the C API and the wasm wrapper. The keys mirror the CLI's flags:
+// mode, count, placement, ends, scale_x, scale_y, subdivide, spacing, normal_offset, tangent_offset, stretch, stretch_factor,
+// flip_alternate, rotation, simplify, center_pattern, fill_rule, auto_width, start_cap, end_cap, max_spike_length, expand, merge_seams, geometry_profile and round, plus the pattern itself which each caller interprets in its own way.
+use crate::pattern_along_path::*;
+use crate::geometry_profile::GeometryProfile;
+use crate::qmath::{FillRule, Vector};
+use crate::auto_width::TargetMeasurement;
+use serde_json::Value;
+
+pub struct JsonSettings {
+    pub settings: PatternSettings,
+    pub pattern: String,
+    pub round: Option<u32>,
+}
+
+fn number_setting(settings: &Value, name: &str, default: f64) -> Result<f64, String>
+{
+    match settings.get(name) {
+        None => Ok(default),
+        Some(v) => v.as_f64().ok_or(format!("Setting {} must be a number.", name))
+    }
+}
+
+fn bool_setting(settings: &Value, name: &str, default: bool) -> Result<bool, String>
+{
+    match settings.get(name) {
+        None => Ok(default),
+        Some(v) => v.as_bool().ok_or(format!("Setting {} must be a boolean.", name))
+    }
+}
+
+// Custom caps need a shape, which JSON can't give us, so only the built in caps are available here.
+fn cap_setting(settings: &Value, name: &str) -> Result<CapStyle, String>
+{
+    match settings.get(name).and_then(|c| c.as_str()) {
+        None | Some("butt") => Ok(CapStyle::Butt),
+        Some("round") => Ok(CapStyle::Round),
+        Some("square") => Ok(CapStyle::Square),
+        Some("pointed") => Ok(CapStyle::Pointed),
+        Some(c) => Err(format!("Invalid {} {}.", name, c))
+    }
+}
+
+fn cap_name(cap: &CapStyle) -> &'static str
+{
+    match cap {
+        CapStyle::Round => "round",
+        CapStyle::Square => "square",
+        CapStyle::Pointed => "pointed",
+        _ => "butt"
+    }
+}
+
+pub fn parse_settings(json: &str) -> Result<JsonSettings, String>
+{
+    let settings: Value = serde_json::from_str(json).map_err(|e| format!("Invalid settings JSON: {}", e))?;
+
+    let pattern = match settings.get("pattern").and_then(|p| p.as_str()) {
+        Some(p) => p.to_string(),
+        None => return Err(String::from("Settings need a pattern in \"pattern\"."))
+    };
+
+    let mut output = PatternSettings::default();
+
+    output.copies = match settings.get("mode").and_then(|m| m.as_str()) {
+        None | Some("single") => PatternCopies::Single,
+        Some("repeated") => PatternCopies::Repeated,
+        Some("fixed") => PatternCopies::Fixed(number_setting(&settings, "count", 1.)? as usize),
+        Some(m) => return Err(format!("Invalid mode {}.", m))
+    };
+
+    output.placement = match settings.get("placement").and_then(|p| p.as_str()) {
+        None | Some("warp") => PatternPlacement::Warp,
+        Some("rigid") => PatternPlacement::Rigid,
+        Some(p) => return Err(format!("Invalid placement {}.", p))
+    };
+
+    output.rotation = match settings.get("rotation").and_then(|r| r.as_str()) {
+        None | Some("follow") => PatternRotation::Follow,
+        Some("upright") => PatternRotation::Upright,
+        Some(r) => return Err(format!("Invalid rotation {}.", r))
+    };
+
+    output.ends = match settings.get("ends").and_then(|e| e.as_str()) {
+        None | Some("drop") => PatternEnds::Drop,
+        Some("overflow") => PatternEnds::Overflow,
+        Some("clip") => PatternEnds::Clip,
+        Some(e) => return Err(format!("Invalid ends {}.", e))
+    };
+
+    let subdivide = number_setting(&settings, "subdivide", 0.)?;
+    if subdivide > 0. { output.subdivide = PatternSubdivide::Simple(subdivide as usize); }
+
+    output.pattern_scale = Vector {
+        x: number_setting(&settings, "scale_x", 1.)?,
+        y: number_setting(&settings, "scale_y", 1.)?,
+    };
+    output.spacing = number_setting(&settings, "spacing", 0.)?;
+    output.normal_offset = number_setting(&settings, "normal_offset", 0.)?;
+    output.tangent_offset = number_setting(&settings, "tangent_offset", 0.)?;
+    output.stretch = bool_setting(&settings, "stretch", false)?;
+    output.stretch_factor = number_setting(&settings, "stretch_factor", 1.)?;
+    output.flip_alternate = bool_setting(&settings, "flip_alternate", false)?;
+    output.simplify = bool_setting(&settings, "simplify", false)?;
+    output.center_pattern = bool_setting(&settings, "center_pattern", true)?;
+
+    output.fill_rule = match settings.get("fill_rule").and_then(|f| f.as_str()) {
+        None | Some("nonzero") => FillRule::NonZero,
+        Some("evenodd") => FillRule::EvenOdd,
+        Some(f) => return Err(format!("Invalid fill rule {}.", f))
+    };
+
+    output.auto_width = match settings.get("auto_width") {
+        None | Some(Value::Null) => None,
+        Some(v) => match v.as_f64() {
+            Some(n) => Some(TargetMeasurement { target: n, probes: Vec::new(), tolerance: 0.01 }),
+            None => return Err(String::from("Setting auto_width must be a number or null."))
+        }
+    };
+
+    output.max_spike_length = match settings.get("max_spike_length") {
+        None | Some(Value::Null) => None,
+        Some(v) => Some(v.as_f64().ok_or(String::from("Setting max_spike_length must be a number or null."))?)
+    };
+
+    output.merge_seams = match settings.get("merge_seams") {
+        None | Some(Value::Null) => None,
+        Some(v) => Some(v.as_f64().ok_or(String::from("Setting merge_seams must be a number or null."))?)
+    };
+
+    output.geometry_profile = match settings.get("geometry_profile").and_then(|p| p.as_str()) {
+        None => GeometryProfile::Latest,
+        Some(p) => GeometryProfile::from_name(p).ok_or(format!("Invalid geometry_profile {}.", p))?
+    };
+
+    output.expand = match settings.get("expand").and_then(|e| e.as_str()) {
+        None | Some("both") => ExpandMode::Both,
+        Some("outward") => ExpandMode::OutwardOnly,
+        Some("inward") => ExpandMode::InwardOnly,
+        Some(e) => return Err(format!("Invalid expand {}.", e))
+    };
+
+    output.start_cap = cap_setting(&settings, "start_cap")?;
+    output.end_cap = cap_setting(&settings, "end_cap")?;
+
+    // null turns rounding off just like --round off does
+    let round = match settings.get("round") {
+        None => Some(2),
+        Some(Value::Null) => None,
+        Some(v) => match v.as_u64() {
+            Some(n) => Some(n as u32),
+            None => return Err(String::from("Setting round must be a positive integer or null."))
+        }
+    };
+
+    return Ok(JsonSettings { settings: output, pattern: pattern, round: round });
+}
+
+// The inverse of parse_settings, anything that came out of it will parse back to the same settings.
+pub fn settings_to_json(settings: &JsonSettings) -> String
+{
+    let s = &settings.settings;
+
+    let (mode, count) = match s.copies {
+        PatternCopies::Repeated => ("repeated", None),
+        PatternCopies::Fixed(n) => ("fixed", Some(n)),
+        PatternCopies::Single => ("single", None)
+    };
+
+    let fill_rule = match s.fill_rule {
+        FillRule::NonZero => "nonzero",
+        FillRule::EvenOdd => "evenodd"
+    };
+
+    let subdivide = match s.subdivide {
+        PatternSubdivide::Simple(n) => n,
+        PatternSubdivide::Off => 0
+    };
+
+    let value = serde_json::json!({
+        "pattern": settings.pattern,
+        "mode": mode,
+        "count": count,
+        "placement": match s.placement {
+            PatternPlacement::Warp => "warp",
+            PatternPlacement::Rigid => "rigid"
+        },
+        "rotation": match s.rotation {
+            PatternRotation::Follow => "follow",
+            PatternRotation::Upright => "upright"
+        },
+        "ends": match s.ends {
+            PatternEnds::Drop => "drop",
+            PatternEnds::Overflow => "overflow",
+            PatternEnds::Clip => "clip"
+        },
+        "scale_x": s.pattern_scale.x,
+        "scale_y": s.pattern_scale.y,
+        "subdivide": subdivide,
+        "spacing": s.spacing,
+        "normal_offset": s.normal_offset,
+        "tangent_offset": s.tangent_offset,
+        "stretch": s.stretch,
+        "stretch_factor": s.stretch_factor,
+        "flip_alternate": s.flip_alternate,
+        "simplify": s.simplify,
+        "center_pattern": s.center_pattern,
+        "fill_rule": fill_rule,
+        "auto_width": s.auto_width.as_ref().map(|a| a.target),
+        "start_cap": cap_name(&s.start_cap),
+        "end_cap": cap_name(&s.end_cap),
+        "max_spike_length": s.max_spike_length,
+        "expand": match s.expand {
+            ExpandMode::Both => "both",
+            ExpandMode::OutwardOnly => "outward",
+            ExpandMode::InwardOnly => "inward"
+        },
+        "merge_seams": s.merge_seams,
+        "geometry_profile": s.geometry_profile.name(),
+        "round": settings.round,
+    });
+
+    return value.to_string();
+}
@@ -0,0 +1,116 @@
+// Going back from a stroke to its skeleton. A closed contour that's really a stroked open path is two sides running
+// the same way with a cap at each end, so we find the ends, split the contour there and walk along one side pairing
+// each point with the nearest point on the other. The middles of those pairs fit a curve for the skeleton and how
+// far apart they are is the width.
+//
+// This is a best guess and meant to be one. Around the caps the nearest point on the other side is off along the
+// cap rather than across the stroke, we leave those pairs out so the skeleton stops about where the stroke's path
+// did. Anything that doesn't look like a stroke, too stubby or with a width all over the place, gives None rather
+// than a skeleton nobody asked for.
+use crate::qmath::*;
+
+#[derive(Clone, Debug)]
+pub struct SkeletonSettings {
+    // how many points we pair up along the side
+    pub samples: usize,
+    // how far the skeleton can stray from the middles of the pairs
+    pub tolerance: f64,
+    // the least the skeleton's length can be as a multiple of the average width
+    pub min_aspect: f64,
+    // the most the widths can vary, as their standard deviation over their average
+    pub max_width_variation: f64,
+}
+
+impl Default for SkeletonSettings {
+    fn default() -> Self
+    {
+        return SkeletonSettings {
+            samples: 64,
+            tolerance: 1.,
+            min_aspect: 2.,
+            max_width_variation: 0.5,
+        };
+    }
+}
+
+// a pair only counts as going across the stroke when the line between its points is within 60 degrees of square
+// to the side, as the cosine
+const ACROSS_COSINE: f64 = 0.5;
+
+// The two points of the contour furthest apart, as global ts. For a stroke those are at its ends.
+fn find_ends(contour: &Piecewise<Bezier>, samples: usize) -> (f64, f64)
+{
+    let measure = PathMeasure::new(contour);
+    let length = measure.length();
+    let points: Vec<(f64, Vector)> = (0..samples).map(|k| {
+        let distance = length * k as f64 / samples as f64;
+        return (measure.t_at(distance), measure.pos_tan_at(distance).0);
+    }).collect();
+
+    let mut best = (0., 0., 0.);
+    for i in 0..points.len() {
+        for j in i + 1..points.len() {
+            let d = points[i].1.distance(points[j].1);
+            if d > best.2 { best = (points[i].0, points[j].0, d); }
+        }
+    }
+
+    return (best.0, best.1);
+}
+
+// Guesses the open path that was stroked to make contour, with the full width of the stroke at each of its on-curve
+// points in order, the way point_widths_from_json takes them. None if the contour isn't closed or doesn't look like
+// a stroke.
+pub fn extract_skeleton(contour: &Piecewise<Bezier>, settings: &SkeletonSettings) -> Option<(Piecewise<Bezier>, Vec<f64>)>
+{
+    if contour.is_empty() || !contour.is_closed() || settings.samples < 2 { return None; }
+
+    // both sides from one end to the other
+    let (start, end) = find_ends(contour, settings.samples * 2);
+    if start == end { return None; }
+    let side = contour.cut(start, end);
+    let other = contour.cut(end, start).reverse();
+
+    let measure = PathMeasure::new(&side);
+    let length = measure.length();
+    if !(length > 0.) { return None; }
+
+    let mut middles = Vec::new();
+    let mut widths = Vec::new();
+    for k in 1..settings.samples {
+        let (point, tangent) = measure.pos_tan_at(length * k as f64 / settings.samples as f64);
+        let (i, t, width) = match other.nearest(point) {
+            Some(n) => n,
+            None => return None,
+        };
+        if !(width > 0.) { continue; }
+
+        let across = other[i].evaluate(t);
+        if ((across + -point) * (1. / width)).dot(tangent).abs() > ACROSS_COSINE { continue; }
+
+        middles.push(Vector::lerp(point, across, 0.5));
+        widths.push(width);
+    }
+
+    // most of the side should have had something across from it
+    if middles.len() < 2 || middles.len() * 2 < settings.samples { return None; }
+
+    let count = widths.len() as f64;
+    let mean = widths.iter().sum::<f64>() / count;
+    let variance = widths.iter().map(|w| (w - mean) * (w - mean)).sum::<f64>() / count;
+    if variance.sqrt() > mean * settings.max_width_variation { return None; }
+
+    let skeleton = Piecewise::fit_curve(&middles, settings.tolerance);
+    if skeleton.is_empty() { return None; }
+    if PathMeasure::new(&skeleton).length() < mean * settings.min_aspect { return None; }
+
+    // every on-curve point gets the width of the pair whose middle it's nearest
+    let mut on_curve: Vec<Vector> = skeleton.segs().map(|bez| bez.to_control_points()[0]).collect();
+    on_curve.push(skeleton[skeleton.len() - 1].to_control_points()[3]);
+    let point_widths = on_curve.iter().map(|p| {
+        let nearest = (0..middles.len()).min_by(|a, b| p.distance(middles[*a]).partial_cmp(&p.distance(middles[*b])).unwrap()).unwrap();
+        return widths[nearest];
+    }).collect();
+
+    return Some((skeleton, point_widths));
+}
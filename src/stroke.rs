@@ -0,0 +1,726 @@
+// Plain constant width stroking, the path with a pen of fixed width dragged along it. Each side is the path offset
+// by half the width, see qmath/offset.rs, with joins built where the path has corners and caps on the ends of open
+// contours. Everything comes back as cubics so it goes straight through to_outline.
+use crate::qmath::*;
+use crate::{caps, joins};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+// butt - the stroke ends square where the path does
+// round - a half circle as wide as the stroke
+// square - a butt cap pushed out by half the width
+// custom - an open contour drawn for a stroke one unit wide, see caps::generate
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "lowercase"))]
+pub enum StrokeCap {
+    Butt,
+    Round,
+    Square,
+    Custom(Piecewise<Bezier>),
+}
+
+// Piecewise has no Debug of its own, so a custom cap just says how many curves it has
+impl std::fmt::Debug for StrokeCap {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        match self {
+            StrokeCap::Butt => write!(f, "Butt"),
+            StrokeCap::Round => write!(f, "Round"),
+            StrokeCap::Square => write!(f, "Square"),
+            StrokeCap::Custom(contour) => write!(f, "Custom({} curves)", contour.len()),
+        }
+    }
+}
+
+// miter - the two sides are carried on until they meet, falling back on a bevel past the miter limit
+// round - an arc around the corner
+// bevel - a straight line across the corner
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "lowercase"))]
+pub enum StrokeJoin {
+    Miter,
+    Round,
+    Bevel,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct StrokeSettings {
+    pub width: f64,
+    pub cap: StrokeCap,
+    pub join: StrokeJoin,
+    // how far a miter can reach from the corner as a multiple of half the width, same as SVG's stroke-miterlimit
+    pub miter_limit: f64,
+    // how far the sides can stray from the true offset
+    pub tolerance: f64,
+}
+
+impl Default for StrokeSettings {
+    fn default() -> Self
+    {
+        return StrokeSettings {
+            width: 10.,
+            cap: StrokeCap::Butt,
+            join: StrokeJoin::Miter,
+            miter_limit: 4.,
+            tolerance: 0.01,
+        };
+    }
+}
+
+// curves with every control point closer together than this have no direction and get dropped before stroking
+const DEGENERATE_LENGTH: f64 = 1e-9;
+
+fn line(from: Vector, to: Vector) -> Bezier
+{
+    return Bezier::from_control_points(from, from, to, to);
+}
+
+// What goes between the end of one side's offset at a corner and the start of the next. side is 1 for the left
+// side and -1 for the right. On the inside of the turn we go through the corner itself, the little loop that makes
+// is inside the stroke and fills the same either way under nonzero.
+fn join(corner: Vector, end: Vector, start: Vector, incoming: Vector, outgoing: Vector, side: f64, settings: &StrokeSettings) -> Vec<Bezier>
+{
+    let turn = incoming.cross(outgoing);
+    if turn * side >= 0. {
+        return vec![line(end, corner), line(corner, start)];
+    }
+
+    // how far this side is from the path at the corner, a variable width stroke can have come down to nothing
+    let half = end.distance(corner);
+    if half <= DEGENERATE_LENGTH {
+        return vec![line(end, start)];
+    }
+
+    // the generated join only lands on end and start to within rounding, append snaps it on
+    let generated = joins::generate(settings.join, corner, incoming, outgoing, 2. * half, settings.miter_limit);
+    if generated.is_empty() {
+        return vec![line(end, start)];
+    }
+
+    return generated.into_segs();
+}
+
+// Adds curves on to the end of side one at a time. The offsets and the joins and caps between them meet to within
+// tolerance and get snapped together, anything further apart is bridged with a straight line. That goes for gaps
+// inside what's being added too, a custom cap can be drawn in pieces that don't quite meet.
+fn append(side: &mut Piecewise<Bezier>, curves: Vec<Bezier>, tolerance: f64)
+{
+    for bez in curves {
+        let start = bez.to_control_points()[0];
+        match side.last().map(|last| last.to_control_points()[3]) {
+            Some(end) if end.distance(start) <= tolerance => side.push(move_start(&bez, end)),
+            Some(end) => {
+                side.push(line(end, start));
+                side.push(bez);
+            }
+            None => side.push(bez),
+        }
+    }
+}
+
+// Joins the end of side back up with its start the same way append joins things on.
+fn close(side: &mut Piecewise<Bezier>, tolerance: f64)
+{
+    let last = match side.len() {
+        0 => return,
+        count => count - 1,
+    };
+
+    let start = side[0].to_control_points()[0];
+    let end = side[last].to_control_points()[3];
+    if end.distance(start) > tolerance {
+        side.push(line(end, start));
+    } else {
+        side[last] = move_end(&side[last], start);
+    }
+}
+
+// One side of the stroke from the offsets of each of the path's curves, side is 1 for the left and -1 for the
+// right. corners are the path's, from find_corners, and get joins. Everywhere else the offsets just get snapped
+// together. A closed path can have a join at its start too and comes back closed.
+fn offset_side(curves: &[Bezier], corners: &[usize], offsets: Vec<Vec<Bezier>>, closed: bool, side: f64, settings: &StrokeSettings) -> Vec<Bezier>
+{
+    let mut output: Piecewise<Bezier> = Piecewise::new(Vec::new());
+    for (i, offset) in offsets.into_iter().enumerate() {
+        if offset.is_empty() { continue; }
+
+        if let Some(last) = output.last() {
+            let end = last.to_control_points()[3];
+            let start = offset[0].to_control_points()[0];
+            let (incoming, outgoing) = (curves[i - 1].tangent_at(1.), curves[i].tangent_at(0.));
+
+            if end.distance(start) > settings.tolerance && corners.contains(&i) {
+                append(&mut output, join(curves[i].to_control_points()[0], end, start, incoming, outgoing, side, settings), settings.tolerance);
+            }
+        }
+
+        append(&mut output, offset, settings.tolerance);
+    }
+
+    if closed && !output.is_empty() {
+        let end = output.last().unwrap().to_control_points()[3];
+        let start = output[0].to_control_points()[0];
+        let (incoming, outgoing) = (curves.last().unwrap().tangent_at(1.), curves[0].tangent_at(0.));
+
+        if end.distance(start) > settings.tolerance && corners.contains(&0) {
+            append(&mut output, join(curves[0].to_control_points()[0], end, start, incoming, outgoing, side, settings), settings.tolerance);
+        }
+
+        close(&mut output, settings.tolerance);
+    }
+
+    return output.into_segs();
+}
+
+// The cap at the end of a path, from the right side's end round to the left side's. tangent is the direction the
+// path arrives in. Caps sit centered between the two ends so they fit a stroke that's wider on one side, and where
+// a stroke has come down to nothing there's no cap at all, it's already pointed.
+fn cap(tangent: Vector, from: Vector, to: Vector, settings: &StrokeSettings) -> Vec<Bezier>
+{
+    let half = from.distance(to) / 2.;
+    if half <= DEGENERATE_LENGTH { return Vec::new(); }
+    let center = from.lerp(to, 0.5);
+
+    let generated = caps::generate(&settings.cap, center, tangent, 2. * half);
+    if generated.is_empty() {
+        return vec![line(from, to)];
+    }
+
+    return generated.into_segs();
+}
+
+// The curves of contour that go somewhere, along with where they were in it.
+fn directional_curves(contour: &Piecewise<Bezier>) -> Vec<(usize, Bezier)>
+{
+    return contour.segs().cloned().enumerate()
+        .filter(|(_, bez)| {
+            let p = bez.to_control_points();
+            p[1..].iter().any(|q| q.distance(p[0]) > DEGENERATE_LENGTH)
+        })
+        .collect();
+}
+
+// points per curve of the inner side we check against the path when looking for a hole that's filled in
+const COLLAPSE_SAMPLES: usize = 16;
+
+// Whether the inner side of a closed stroke offset half away from the path has gone past the middle, leaving no
+// hole at all. Every point on the inner side is at most half from the path. Where there's a hole some of them are
+// exactly half away, but once half is more than the path's inradius the offset has crossed over itself everywhere
+// and all of it is closer than that. Offsets are only within tolerance of where they should be, so we allow for
+// that and for the same again in the nearest point search.
+fn collapsed(inner: &Piecewise<Bezier>, curves: &[Bezier], half: f64, tolerance: f64) -> bool
+{
+    let path = Piecewise::new(curves.to_vec());
+
+    for bez in inner.segs() {
+        for i in 0..COLLAPSE_SAMPLES {
+            let (_, _, distance) = path.project(bez.evaluate(i as f64 / COLLAPSE_SAMPLES as f64)).unwrap();
+            if distance >= half - 2. * tolerance { return false; }
+        }
+    }
+
+    return true;
+}
+
+// Puts the two sides together. Closed paths give an outer edge and a hole, open paths one contour going out along
+// the right side and back along the left with caps on the ends. distances are how far the left and right sides are
+// from the path when that's the same all the way round, which lets us tell when a stroke has filled in the hole in
+// a closed path. A variable width stroke doesn't get that check.
+fn assemble(curves: &[Bezier], closed: bool, left: Vec<Bezier>, right: Vec<Bezier>, distances: Option<(f64, f64)>, settings: &StrokeSettings) -> Vec<Piecewise<Bezier>>
+{
+    if left.is_empty() || right.is_empty() { return Vec::new(); }
+
+    if closed {
+        // one side is the outer edge and the other the hole, which is which depends on the way the path goes
+        let left = (Piecewise::new(left), distances.map(|d| d.0));
+        let right = (Piecewise::new(right), distances.map(|d| d.1));
+        let ((outer, _), (inner, half)) = if left.0.signed_area().abs() >= right.0.signed_area().abs() { (left, right) } else { (right, left) };
+
+        let mut output = vec![match outer.winding_direction() {
+            WindingDirection::Clockwise => outer.reverse(),
+            _ => outer,
+        }];
+
+        // a stroke wider than the hole it goes around leaves nothing inside, and the inner side has crossed over
+        // itself into a bowtie or a smaller copy of the path going the same way as the outer edge
+        if half.map_or(false, |half| collapsed(&inner, curves, half, settings.tolerance)) {
+            return output;
+        }
+
+        match inner.winding_direction() {
+            WindingDirection::CounterClockwise => output.push(inner.reverse()),
+            WindingDirection::Clockwise => output.push(inner),
+            WindingDirection::Degenerate => {}
+        }
+
+        return output;
+    }
+
+    // going out along the right side and back along the left makes the outline run counter-clockwise
+    let first = &curves[0];
+    let last = curves.last().unwrap();
+    let left = Piecewise::new(left).reverse().into_segs();
+
+    let mut output: Piecewise<Bezier> = Piecewise::new(Vec::new());
+    append(&mut output, right, settings.tolerance);
+    let (from, to) = (output.last().unwrap().to_control_points()[3], left[0].to_control_points()[0]);
+    append(&mut output, cap(last.tangent_at(1.), from, to, settings), settings.tolerance);
+    append(&mut output, left, settings.tolerance);
+    let (from, to) = (output.last().unwrap().to_control_points()[3], output[0].to_control_points()[0]);
+    append(&mut output, cap(-first.tangent_at(0.), from, to, settings), settings.tolerance);
+    close(&mut output, settings.tolerance);
+
+    return vec![output];
+}
+
+// The stroke of one contour with its left side left from it and its right side right from it. An open contour gets
+// caps on its ends even if they meet.
+fn stroke_contour(contour: &Piecewise<Bezier>, closed: bool, left: f64, right: f64, settings: &StrokeSettings) -> Vec<Piecewise<Bezier>>
+{
+    let curves: Vec<Bezier> = directional_curves(contour).into_iter().map(|(_, bez)| bez).collect();
+    if curves.is_empty() { return Vec::new(); }
+
+    let closed = closed && contour.is_closed();
+    let corners = Piecewise::new(curves.clone()).find_corners(CORNER_ANGLE);
+    let left_side = offset_side(&curves, &corners, curves.iter().map(|bez| bez.offset(left, settings.tolerance)).collect(), closed, 1., settings);
+    let right_side = offset_side(&curves, &corners, curves.iter().map(|bez| bez.offset(-right, settings.tolerance)).collect(), closed, -1., settings);
+
+    return assemble(&curves, closed, left_side, right_side, Some((left, right)), settings);
+}
+
+// Strokes every contour of path with a pen settings.width wide. Outer edges run counter-clockwise and the holes
+// closed contours leave run clockwise, each hole straight after its outer edge, so the result fills right under
+// nonzero. A closed contour stroked wider than the hole it leaves comes back as just its outer edge. Contours with
+// no length to them are dropped.
+pub fn constant_width_stroke(path: &Piecewise<Piecewise<Bezier>>, settings: &StrokeSettings) -> Piecewise<Piecewise<Bezier>>
+{
+    let half = settings.width / 2.;
+    return two_sided_stroke(path, half, half, settings);
+}
+
+// constant_width_stroke with each side its own distance from the path, left as seen walking along it, so a stroke
+// can be thicker on one side or only on one side. A side with a distance of zero runs along the path itself. Equal
+// distances are the same stroke constant_width_stroke gives for twice that width, and settings.width is ignored.
+// Caps are centered between the ends of the two sides.
+pub fn two_sided_stroke(path: &Piecewise<Piecewise<Bezier>>, left: f64, right: f64, settings: &StrokeSettings) -> Piecewise<Piecewise<Bezier>>
+{
+    return two_sided_stroke_with_closed(path, &[], left, right, settings);
+}
+
+// constant_width_stroke with whether each contour is closed given rather than going by whether its ends meet, the
+// way from_outline_with_closed reads it off a glif. A skeleton drawn open that happens to end where it started
+// still gets its caps. Contours past the end of closed go by their ends.
+pub fn constant_width_stroke_with_closed(path: &Piecewise<Piecewise<Bezier>>, closed: &[bool], settings: &StrokeSettings) -> Piecewise<Piecewise<Bezier>>
+{
+    let half = settings.width / 2.;
+    return two_sided_stroke_with_closed(path, closed, half, half, settings);
+}
+
+pub fn two_sided_stroke_with_closed(path: &Piecewise<Piecewise<Bezier>>, closed: &[bool], left: f64, right: f64, settings: &StrokeSettings) -> Piecewise<Piecewise<Bezier>>
+{
+    let closed_at = |i: usize| closed.get(i).copied().unwrap_or(true);
+
+    // each contour strokes on its own, collecting in order keeps the output the same either way
+    #[cfg(feature = "rayon")]
+    let stroked: Vec<Vec<Piecewise<Bezier>>> = path.as_slice().par_iter().enumerate().map(|(i, contour)| stroke_contour(contour, closed_at(i), left, right, settings)).collect();
+
+    #[cfg(not(feature = "rayon"))]
+    let stroked: Vec<Vec<Piecewise<Bezier>>> = path.segs().enumerate().map(|(i, contour)| stroke_contour(contour, closed_at(i), left, right, settings)).collect();
+
+    return Piecewise::new(stroked.into_iter().flatten().collect());
+}
+
+// The bounds of what constant_width_stroke would give back for path, without stroking it. The sides are inside the
+// path's bounds padded by half the width, and on top of that go the caps and the joins, which we build on their own
+// and box. Joins are put on every corner whether or not the stroke would need one there, so this can come out a
+// little big but never too small. Empty if nothing in path has any length.
+pub fn stroke_bounds(path: &Piecewise<Piecewise<Bezier>>, settings: &StrokeSettings) -> Rect
+{
+    let half = settings.width / 2.;
+    let mut output = Rect::empty();
+    let mut include = |rect: Rect| if !rect.is_empty() { output = output.encapsulate_rect(rect); };
+
+    for contour in path.segs() {
+        let curves: Vec<Bezier> = directional_curves(contour).into_iter().map(|(_, bez)| bez).collect();
+        if curves.is_empty() { continue; }
+
+        let closed = contour.is_closed();
+        let contour = Piecewise::new(curves);
+        include(contour.bounds_padded(half));
+
+        let count = contour.len();
+        for i in contour.find_corners(CORNER_ANGLE) {
+            let (incoming, outgoing) = (contour[(i + count - 1) % count].tangent_at(1.), contour[i].tangent_at(0.));
+            let corner = contour[i].to_control_points()[0];
+            let join = joins::generate(settings.join, corner, incoming, outgoing, settings.width, settings.miter_limit);
+            if !join.is_empty() { include(join.bounds()); }
+        }
+
+        if closed { continue; }
+
+        let (first, last) = (&contour[0], &contour[count - 1]);
+        let ends = [(last.to_control_points()[3], last.tangent_at(1.)), (first.to_control_points()[0], -first.tangent_at(0.))];
+        for (point, tangent) in ends.iter() {
+            let cap = caps::generate(&settings.cap, *point, *tangent, settings.width);
+            if !cap.is_empty() { include(cap.bounds()); }
+        }
+    }
+
+    return output;
+}
+
+// how many points along each curve we put either side of it before fitting the sides of a variable width stroke
+const WIDTH_SAMPLES: usize = 32;
+
+// One curve's side of a variable width stroke, found by putting points out along the normal by the width at each
+// and fitting cubics back through them. index and count place the curve in its contour for the widths' global t.
+fn variable_offset(bez: &Bezier, index: usize, count: usize, side: f64, width_at: &dyn Fn(f64) -> (f64, f64), tolerance: f64) -> Vec<Bezier>
+{
+    let points: Vec<Vector> = (0..=WIDTH_SAMPLES).map(|k| {
+        let u = k as f64 / WIDTH_SAMPLES as f64;
+        let (left, right) = width_at((index as f64 + u) / count as f64);
+        let distance = if side > 0. { left } else { -right };
+        return bez.evaluate(u) + bez.normal_at(u) * distance;
+    }).collect();
+
+    return Piecewise::fit_curve(&points, tolerance).into_segs();
+}
+
+fn variable_stroke_contour(contour: &Piecewise<Bezier>, closed: bool, widths: &impl Fn(f64) -> (f64, f64), settings: &StrokeSettings) -> Vec<Piecewise<Bezier>>
+{
+    let indexed = directional_curves(contour);
+    if indexed.is_empty() { return Vec::new(); }
+
+    let count = contour.len();
+    let closed = closed && contour.is_closed();
+
+    // A closed contour's start and end are the same point but the widths there needn't agree. Whatever difference
+    // there is gets spread over the last curve so the sides meet up at the start.
+    let width_at = |t: f64| {
+        let (left, right) = widths(t);
+        let ramp = t * count as f64 - (count - 1) as f64;
+        if !closed || ramp <= 0. { return (left, right); }
+
+        let (start_left, start_right) = widths(0.);
+        let (end_left, end_right) = widths(1.);
+        return (left + (start_left - end_left) * ramp, right + (start_right - end_right) * ramp);
+    };
+
+    let curves: Vec<Bezier> = indexed.iter().map(|(_, bez)| bez.clone()).collect();
+    let corners = Piecewise::new(curves.clone()).find_corners(CORNER_ANGLE);
+    let sides: Vec<Vec<Bezier>> = [1., -1.].iter().map(|side| {
+        let offsets = indexed.iter().map(|(i, bez)| variable_offset(bez, *i, count, *side, &width_at, settings.tolerance)).collect();
+        return offset_side(&curves, &corners, offsets, closed, *side, settings);
+    }).collect();
+
+    let mut sides = sides.into_iter();
+    return assemble(&curves, closed, sides.next().unwrap(), sides.next().unwrap(), None, settings);
+}
+
+// Strokes every contour of path with widths that change along it. widths gives how far out the left and right
+// sides are at each global t of a contour, with left as seen walking along the path. A width of zero at an end
+// brings the stroke to a point there and no cap is put on it. Everything else about the stroke comes from
+// settings, its width is ignored.
+pub fn variable_width_stroke(path: &Piecewise<Piecewise<Bezier>>, widths: &impl Fn(f64) -> (f64, f64), settings: &StrokeSettings) -> Piecewise<Piecewise<Bezier>>
+{
+    return variable_width_stroke_with_closed(path, &[], widths, settings);
+}
+
+// variable_width_stroke with whether each contour is closed given rather than going by whether its ends meet, like
+// constant_width_stroke_with_closed.
+pub fn variable_width_stroke_with_closed(path: &Piecewise<Piecewise<Bezier>>, closed: &[bool], widths: &impl Fn(f64) -> (f64, f64), settings: &StrokeSettings) -> Piecewise<Piecewise<Bezier>>
+{
+    let mut output = Vec::new();
+    for (i, contour) in path.segs().enumerate() {
+        output.extend(variable_stroke_contour(contour, closed.get(i).copied().unwrap_or(true), widths, settings));
+    }
+
+    return Piecewise::new(output);
+}
+
+// variable_width_stroke with the widths carried on each contour's segments rather than given as a function, so they
+// follow along through subdividing, cutting and reversing the contour. Empty contours are skipped.
+pub fn segment_width_stroke(path: &[AttributedPiecewise<Bezier, SegmentWidths>], settings: &StrokeSettings) -> Piecewise<Piecewise<Bezier>>
+{
+    return segment_width_stroke_with_closed(path, &[], settings);
+}
+
+// segment_width_stroke with whether each contour is closed given, see constant_width_stroke_with_closed.
+pub fn segment_width_stroke_with_closed(path: &[AttributedPiecewise<Bezier, SegmentWidths>], closed: &[bool], settings: &StrokeSettings) -> Piecewise<Piecewise<Bezier>>
+{
+    let mut output = Vec::new();
+    for (i, contour) in path.iter().enumerate() {
+        if contour.is_empty() { continue; }
+
+        let single = Piecewise::new(vec![contour.piecewise().clone()]);
+        let widths_at = |t: f64| {
+            let (i, local) = contour.piecewise().segment_at(t);
+            return contour.attributes()[i].at(local);
+        };
+        let closed = closed.get(i).copied().unwrap_or(true);
+        output.extend(variable_width_stroke_with_closed(&single, &[closed], &widths_at, settings).into_segs());
+    }
+
+    return Piecewise::new(output);
+}
+
+// The widths along each of count curves from widths given at the contour's on-curve points. A closed contour's last
+// curve heads back to its first point's width.
+fn widths_between_points(widths: &[(f64, f64)], count: usize, closed: bool) -> Vec<SegmentWidths>
+{
+    return (0..count).map(|i| SegmentWidths { start: widths[i], end: widths[if closed { (i + 1) % widths.len() } else { i + 1 }] }).collect();
+}
+
+// the glif lib key point_width_stroke's widths can be kept under, as a string holding the same JSON the sidecar
+// files do, see point_widths_from_json
+pub const POINT_WIDTHS_LIB_KEY: &str = "com.qstroke.pointWidths";
+
+// Reads widths for point_width_stroke. The JSON is an array with an array for each contour, and in those a width
+// for each point, either a number for the whole width split evenly either side or a [left, right] pair.
+pub fn point_widths_from_json(json: &str) -> Result<Vec<Vec<(f64, f64)>>, String>
+{
+    let value: serde_json::Value = serde_json::from_str(json).map_err(|e| format!("Invalid widths JSON: {}", e))?;
+    let contours = value.as_array().ok_or(String::from("Widths must be an array with an array for each contour."))?;
+
+    let mut output = Vec::new();
+    for (i, contour) in contours.iter().enumerate() {
+        let points = contour.as_array().ok_or(format!("The widths for contour {} must be an array.", i))?;
+
+        let mut widths = Vec::new();
+        for point in points {
+            let pair = point.as_array().filter(|pair| pair.len() == 2).map(|pair| (pair[0].as_f64(), pair[1].as_f64()));
+            let width = match (point.as_f64(), pair) {
+                (Some(w), _) => (w / 2., w / 2.),
+                (None, Some((Some(left), Some(right)))) => (left, right),
+                _ => return Err(format!("Widths for contour {} must be numbers or [left, right] pairs.", i))
+            };
+            widths.push(width);
+        }
+        output.push(widths);
+    }
+
+    return Ok(output);
+}
+
+// variable_width_stroke with the widths given as (left, right) at each contour's on-curve points, in the order
+// they're in the glif, rather than as a function. A closed contour has a point for each curve and an open one has
+// one more. A contour with the wrong number of widths is an error naming it.
+pub fn point_width_stroke(path: &Piecewise<Piecewise<Bezier>>, widths: &[Vec<(f64, f64)>], settings: &StrokeSettings) -> Result<Piecewise<Piecewise<Bezier>>, String>
+{
+    return point_width_stroke_with_closed(path, &[], widths, settings);
+}
+
+// point_width_stroke with whether each contour is closed given, see constant_width_stroke_with_closed. A contour
+// drawn open whose ends meet has a width for its last point too.
+pub fn point_width_stroke_with_closed(path: &Piecewise<Piecewise<Bezier>>, closed: &[bool], widths: &[Vec<(f64, f64)>], settings: &StrokeSettings) -> Result<Piecewise<Piecewise<Bezier>>, String>
+{
+    if widths.len() != path.len() {
+        return Err(format!("There are widths for {} contours but the path has {}.", widths.len(), path.len()));
+    }
+
+    let mut attributed = Vec::new();
+    let mut attributed_closed = Vec::new();
+    for (i, contour) in path.segs().enumerate() {
+        if contour.is_empty() { continue; }
+
+        let count = contour.len();
+        let closed = closed.get(i).copied().unwrap_or(true) && contour.is_closed();
+        let points = if closed { count } else { count + 1 };
+        if widths[i].len() != points {
+            return Err(format!("Contour {} has {} points but {} widths.", i, points, widths[i].len()));
+        }
+
+        attributed.push(AttributedPiecewise::new(contour.clone(), widths_between_points(&widths[i], count, closed)).unwrap());
+        attributed_closed.push(closed);
+    }
+
+    return Ok(segment_width_stroke_with_closed(&attributed, &attributed_closed, settings));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "rayon")]
+    use crate::pattern_along_path::pattern_along_outline;
+    #[cfg(feature = "rayon")]
+    use crate::testing::{golden_settings, read_glif_piecewise};
+    #[cfg(feature = "rayon")]
+    use rayon::ThreadPoolBuilder;
+    #[cfg(feature = "rayon")]
+    use std::fs;
+    #[cfg(feature = "rayon")]
+    use std::path::Path;
+
+    fn horizontal_line(length: f64) -> Piecewise<Piecewise<Bezier>>
+    {
+        return Piecewise::new(vec![Piecewise::new(vec![line(Vector { x: 0., y: 0. }, Vector { x: length, y: 0. })])]);
+    }
+
+    // Every curve of every contour starts where the one before it ends, round to the first again.
+    fn assert_joined_up(stroke: &Piecewise<Piecewise<Bezier>>)
+    {
+        for contour in stroke.segs() {
+            let count = contour.len();
+            for i in 0..count {
+                let end = contour[i].to_control_points()[3];
+                let start = contour[(i + 1) % count].to_control_points()[0];
+                assert!(end.is_near(start, 1e-9), "curve {} ends at {:?} but the next starts at {:?}", i, end, start);
+            }
+        }
+    }
+
+    // A custom cap drawn in pieces further apart than the tolerance gets them bridged rather than falling over.
+    #[test]
+    fn custom_cap_with_a_gap()
+    {
+        let v = |x, y| Vector { x: x, y: y };
+        let cap = Piecewise::new(vec![line(v(0., -0.5), v(0.3, -0.5)), line(v(0.3, -0.2), v(0.3, 0.5)), line(v(0.3, 0.5), v(0., 0.5))]);
+        let settings = StrokeSettings { width: 40., cap: StrokeCap::Custom(cap), ..StrokeSettings::default() };
+
+        let stroke = constant_width_stroke(&horizontal_line(100.), &settings);
+        assert_eq!(stroke.len(), 1);
+        assert_joined_up(&stroke);
+
+        let bounds = stroke.bounds();
+        assert!((bounds.right - 112.).abs() < 1e-9 && (bounds.left + 12.).abs() < 1e-9, "{:?}", bounds);
+        assert!((bounds.top - 20.).abs() < 1e-9 && (bounds.bottom + 20.).abs() < 1e-9, "{:?}", bounds);
+    }
+
+    // The bowl of an e, a half disc sat on its bar. The biggest circle that fits in a half disc is half its radius
+    // across, so a stroke up to twice that wide leaves some of the counter open and anything wider fills it in,
+    // leaving only the outer edge.
+    #[test]
+    fn e_counter_fills_in()
+    {
+        let v = |x, y| Vector { x: x, y: y };
+        let mut bowl = Piecewise::arc(v(250., 250.), 150., 0., std::f64::consts::PI);
+        bowl.push(line(v(100., 250.), v(400., 250.)));
+        let e = Piecewise::new(vec![bowl]);
+
+        let stroke = |width| constant_width_stroke(&e, &StrokeSettings { width: width, ..StrokeSettings::default() });
+        for width in &[100., 140.] {
+            let open = stroke(*width);
+            assert_eq!(open.len(), 2, "{}", width);
+            assert_eq!(open[1].winding_direction(), WindingDirection::Clockwise, "{}", width);
+            assert_joined_up(&open);
+        }
+
+        let filled = stroke(160.);
+        assert_eq!(filled.len(), 1);
+        assert_eq!(filled[0].winding_direction(), WindingDirection::CounterClockwise);
+        assert_joined_up(&filled);
+        assert!(filled.bounds().top > 400. + 80. - 1e-6, "{:?}", filled.bounds());
+    }
+
+    // A circle strokes into its outer edge counter-clockwise and then the hole clockwise whichever way it goes round,
+    // until the stroke is wider than the circle and the hole fills in.
+    #[test]
+    fn circle_ring_signed_areas()
+    {
+        let circle = Piecewise::circle(Vector { x: 0., y: 0. }, 100.);
+        let disc = |r: f64| std::f64::consts::PI * r * r;
+        let assert_area = |got: f64, want: f64| assert!((got - want).abs() <= 1e-3 * want.abs(), "{} rather than {}", got, want);
+
+        for path in [circle.clone(), circle.reverse()].iter() {
+            let path = Piecewise::new(vec![path.clone()]);
+            let ring = constant_width_stroke(&path, &StrokeSettings { width: 20., ..StrokeSettings::default() });
+            assert_eq!(ring.len(), 2);
+            assert_area(ring[0].signed_area(), disc(110.));
+            assert_area(ring[1].signed_area(), -disc(90.));
+
+            let filled = constant_width_stroke(&path, &StrokeSettings { width: 210., ..StrokeSettings::default() });
+            assert_eq!(filled.len(), 1);
+            assert_area(filled[0].signed_area(), disc(205.));
+        }
+    }
+
+    // An open S and a closed circle, the two ways a two sided stroke gets put together.
+    fn s_and_circle() -> Vec<Piecewise<Piecewise<Bezier>>>
+    {
+        let v = |x, y| Vector { x: x, y: y };
+        let s = Bezier::from_control_points(v(0., 0.), v(150., 0.), v(50., 200.), v(200., 200.));
+        return vec![Piecewise::new(vec![Piecewise::new(vec![s])]), Piecewise::new(vec![Piecewise::circle(v(100., 100.), 80.)])];
+    }
+
+    // The same distance either side is the symmetric stroke down to the last digit.
+    #[test]
+    fn two_sided_with_equal_sides()
+    {
+        for path in s_and_circle() {
+            let settings = StrokeSettings { width: 50., ..StrokeSettings::default() };
+            assert_eq!(two_sided_stroke(&path, 25., 25., &settings).to_svg_path_data(None), constant_width_stroke(&path, &settings).to_svg_path_data(None));
+        }
+    }
+
+    // With nothing on the left, the left side runs along the path, which is all of the path lying on the stroke's
+    // edge within the tolerance. The circle goes counter-clockwise so its left is its inside, and the hole in its
+    // stroke is the circle itself.
+    #[test]
+    fn two_sided_with_no_left_side()
+    {
+        let settings = StrokeSettings::default();
+        for path in s_and_circle() {
+            let stroke = two_sided_stroke(&path, 0., 30., &settings);
+            assert_eq!(stroke.len(), if path[0].is_closed() { 2 } else { 1 });
+
+            let edge = stroke.last().unwrap();
+            for bez in path[0].segs() {
+                for i in 0..=20 {
+                    let point = bez.evaluate(i as f64 / 20.);
+                    let (_, _, distance) = edge.nearest(point).unwrap();
+                    assert!(distance <= 2. * settings.tolerance, "{:?} is {} from the stroke's edge", point, distance);
+                }
+            }
+
+        }
+
+        // all of the ring is outside the circle
+        let circle = &s_and_circle()[1];
+        let area = two_sided_stroke(circle, 0., 30., &settings).area();
+        let want = std::f64::consts::PI * (110. * 110. - 80. * 80.);
+        assert!((area - want).abs() <= 1e-3 * want, "{} rather than {}", area, want);
+    }
+
+    // Every fixture skeleton stroked with each join and cap and laid along by the pattern with every golden setting,
+    // written out to full precision so two runs only match if every coordinate does.
+    #[cfg(feature = "rayon")]
+    fn stroke_fixtures() -> Vec<String>
+    {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        let pattern = read_glif_piecewise(&fixtures.join("pattern.glif")).unwrap();
+
+        let mut skeletons: Vec<_> = fs::read_dir(fixtures.join("skeletons")).unwrap().map(|e| e.unwrap().path()).collect();
+        skeletons.sort();
+
+        let mut output = Vec::new();
+        for skeleton_path in skeletons {
+            let skeleton = read_glif_piecewise(&skeleton_path).unwrap();
+
+            for (cap, join) in [(StrokeCap::Butt, StrokeJoin::Miter), (StrokeCap::Round, StrokeJoin::Round), (StrokeCap::Square, StrokeJoin::Bevel)].iter() {
+                let settings = StrokeSettings { width: 40., cap: cap.clone(), join: *join, ..StrokeSettings::default() };
+                output.push(constant_width_stroke(&skeleton, &settings).to_svg_path_data(None));
+            }
+            for (_, settings) in golden_settings() {
+                output.push(pattern_along_outline(&skeleton, &pattern, &settings).to_svg_path_data(None));
+            }
+        }
+
+        return output;
+    }
+
+    // A pool with one thread runs the contours one after the other in order, the same as without rayon, and has to
+    // come out byte for byte the same as one with several threads racing each other.
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_matches_serial()
+    {
+        let serial = ThreadPoolBuilder::new().num_threads(1).build().unwrap().install(stroke_fixtures);
+        let parallel = ThreadPoolBuilder::new().num_threads(4).build().unwrap().install(stroke_fixtures);
+
+        assert_eq!(serial.len(), parallel.len());
+        for (i, (s, p)) in serial.iter().zip(&parallel).enumerate() {
+            assert!(s == p, "stroke {} came out different in parallel:\n{}\n{}", i, s, p);
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use crate::qmath::*;
+use crate::joins::{self, JoinRecord};
+use glifparser::{Handle, Outline};
+use std::time::Duration;
+
+// Statistics about a single glyph going through the stroker. We only collect them here, how they get written
+// out is up to whatever is driving us.
+pub struct StrokeReport {
+    pub name: String,
+    pub input_contours: usize,
+    pub input_points: usize,
+    pub output_contours: usize,
+    pub output_points: usize,
+    pub output_bounds: Option<Rect>,
+    pub filled_area: f64,
+    // set measure_angle (degrees from the x axis) before recording the output to get the output's extent along it
+    pub measure_angle: Option<f64>,
+    pub measured_extent: Option<f64>,
+    // set verify_offset to how far the output should sit from the path to have record_offset_error check it
+    pub verify_offset: Option<f64>,
+    pub offset_error: Option<OffsetErrorReport>,
+    // the highest join ratio and how many joins fall into each of joins::JOIN_BUCKETS
+    pub max_join_ratio: Option<f64>,
+    pub join_histogram: Vec<usize>,
+    // names of the anchors that --project-anchors moved
+    pub moved_anchors: Vec<String>,
+    pub warnings: Vec<String>,
+    pub duration: Duration,
+    // the output was copied out of the cache instead of being stroked, the output statistics are left empty
+    pub cached: bool,
+    pub error: Option<String>,
+}
+
+impl StrokeReport {
+    pub fn new(name: &str) -> Self
+    {
+        return StrokeReport {
+            name: name.to_string(),
+            input_contours: 0,
+            input_points: 0,
+            output_contours: 0,
+            output_points: 0,
+            output_bounds: None,
+            filled_area: 0.,
+            measure_angle: None,
+            measured_extent: None,
+            verify_offset: None,
+            offset_error: None,
+            max_join_ratio: None,
+            join_histogram: Vec::new(),
+            moved_anchors: Vec::new(),
+            warnings: Vec::new(),
+            duration: Duration::default(),
+            cached: false,
+            error: None,
+        };
+    }
+
+    pub fn record_input<T>(&mut self, outline: &Outline<T>)
+    {
+        self.input_contours = outline.len();
+        self.input_points = count_points(outline);
+    }
+
+    // For input that didn't come from a glif. We count points like they'd be written to a glif, every
+    // curve adds an on-curve point and two handles.
+    pub fn record_input_piecewise(&mut self, outline: &Piecewise<Piecewise<Bezier>>)
+    {
+        self.input_contours = outline.len();
+        self.input_points = outline.segs().map(|c| c.len() * 3).sum();
+    }
+
+    pub fn record_output<T>(&mut self, outline: &Outline<T>, fill_rule: FillRule)
+    {
+        self.output_contours = outline.len();
+        self.output_points = count_points(outline);
+
+        // empty contours and lone move points have no geometry to measure so we leave them out
+        let mut piecewise = Piecewise::new(Vec::new());
+        for contour in outline {
+            if contour.is_empty() { continue; }
+
+            let pw = Piecewise::from_contour(contour);
+            if !pw.is_empty() { piecewise.push(pw); }
+        }
+
+        if piecewise.is_empty() { return; }
+
+        self.output_bounds = Some(piecewise.bounds());
+        self.filled_area = piecewise.filled_area(fill_rule);
+
+        if let Some(angle) = self.measure_angle {
+            let radians = angle.to_radians();
+            let (min, max) = piecewise.directional_bounds(Vector { x: radians.cos(), y: radians.sin() });
+            self.measured_extent = Some(max - min);
+        }
+    }
+
+    pub fn record_joins(&mut self, joins: &[JoinRecord])
+    {
+        self.max_join_ratio = joins.iter().filter_map(|j| j.ratio).fold(None, |max: Option<f64>, r| Some(max.map_or(r, |m| m.max(r))));
+        self.join_histogram = joins::join_histogram(joins);
+    }
+
+    // Checks every output contour against whichever path contour it's closest to keeping up the expected
+    // distance, which is the one it came from.
+    pub fn record_offset_error<T>(&mut self, path: &Piecewise<Piecewise<Bezier>>, outline: &Outline<T>)
+    {
+        let expected = match self.verify_offset {
+            Some(e) => e,
+            None => return
+        };
+
+        let mut total: Option<OffsetErrorReport> = None;
+        for contour in outline {
+            if contour.is_empty() { continue; }
+
+            let offset = Piecewise::from_contour(contour);
+            if offset.is_empty() { continue; }
+
+            let best = path.segs()
+                .map(|skeleton| verify_offset(skeleton, &offset, expected, OFFSET_SAMPLES))
+                .filter(|r| r.samples > 0)
+                .min_by(|a, b| a.mean_error.partial_cmp(&b.mean_error).unwrap());
+
+            match (&mut total, best) {
+                (Some(t), Some(b)) => t.merge(&b),
+                (None, Some(b)) => total = Some(b),
+                _ => {}
+            }
+        }
+
+        self.offset_error = total;
+    }
+}
+
+// per output contour, enough to catch a bulge on a curve without making big glyphs slow
+const OFFSET_SAMPLES: usize = 200;
+
+// Counts points the way they'd appear in a .glif file, so handles count as their own off-curve points.
+pub fn count_points<T>(outline: &Outline<T>) -> usize
+{
+    let mut count = 0;
+    for contour in outline {
+        for point in contour {
+            count = count + 1;
+            if let Handle::At(_, _) = point.a { count = count + 1; }
+            if let Handle::At(_, _) = point.b { count = count + 1; }
+        }
+    }
+
+    return count;
+}
@@ -0,0 +1,277 @@
+use crate::qmath::*;
+use xmltree::{Element, XMLNode};
+use xmlwriter::*;
+
+// An affine transform in SVG's order: [a b c d e f] maps (x, y) to (ax + cy + e, bx + dy + f).
+type Matrix = [f64; 6];
+
+const IDENTITY: Matrix = [1., 0., 0., 1., 0., 0.];
+
+// Elements whose children never get drawn directly, any paths in here are only referenced from elsewhere.
+const NON_RENDERING: [&str; 7] = ["defs", "clipPath", "mask", "marker", "pattern", "symbol", "metadata"];
+
+// Everything we pulled out of an SVG document. The outline is still in SVG's coordinate space.
+pub struct SvgDocument {
+    pub outline: Piecewise<Piecewise<Bezier>>,
+    pub view_box: Option<[f64; 4]>,
+    // always YDown, it's here so code handed a document doesn't have to remember that
+    pub orientation: Orientation,
+}
+
+// Describes how SVG user space maps onto font space. SVG is y-down with its origin at the top left of the
+// viewBox while glyphs are y-up so we flip about the bottom of the viewBox and scale everything by scale.
+pub struct SvgSpace {
+    pub view_box: Option<[f64; 4]>,
+    pub scale: f64,
+}
+
+// m1 * m2, the result applies m2 first and then m1
+fn multiply(m1: Matrix, m2: Matrix) -> Matrix
+{
+    return [
+        m1[0] * m2[0] + m1[2] * m2[1],
+        m1[1] * m2[0] + m1[3] * m2[1],
+        m1[0] * m2[2] + m1[2] * m2[3],
+        m1[1] * m2[2] + m1[3] * m2[3],
+        m1[0] * m2[4] + m1[2] * m2[5] + m1[4],
+        m1[1] * m2[4] + m1[3] * m2[5] + m1[5],
+    ];
+}
+
+fn parse_numbers(string: &str) -> Result<Vec<f64>, String>
+{
+    let mut output = Vec::new();
+    for part in string.split(|c: char| c.is_whitespace() || c == ',').filter(|p| !p.is_empty()) {
+        match part.parse::<f64>() {
+            Ok(n) => output.push(n),
+            Err(_) => return Err(format!("Invalid number '{}' in SVG.", part))
+        }
+    }
+
+    return Ok(output);
+}
+
+// Parses a transform attribute like "translate(10 20) rotate(45)" into a single matrix.
+fn parse_transform(string: &str) -> Result<Matrix, String>
+{
+    let mut matrix = IDENTITY;
+    let mut rest = string.trim();
+
+    while !rest.is_empty() {
+        let open = rest.find('(').ok_or(format!("Invalid transform '{}'.", string))?;
+        let close = rest.find(')').ok_or(format!("Invalid transform '{}'.", string))?;
+        if close < open { return Err(format!("Invalid transform '{}'.", string)); }
+
+        let name = rest[..open].trim().trim_start_matches(',').trim();
+        let args = parse_numbers(&rest[open + 1..close])?;
+        let arg = |i: usize| args.get(i).cloned();
+
+        let transform: Matrix = match (name, args.len()) {
+            ("matrix", 6) => [args[0], args[1], args[2], args[3], args[4], args[5]],
+            ("translate", 1) | ("translate", 2) => [1., 0., 0., 1., args[0], arg(1).unwrap_or(0.)],
+            ("scale", 1) | ("scale", 2) => [args[0], 0., 0., arg(1).unwrap_or(args[0]), 0., 0.],
+            ("rotate", 1) | ("rotate", 3) => {
+                let (sin, cos) = args[0].to_radians().sin_cos();
+                let rotation = [cos, sin, -sin, cos, 0., 0.];
+
+                match (arg(1), arg(2)) {
+                    (Some(cx), Some(cy)) => multiply(multiply([1., 0., 0., 1., cx, cy], rotation), [1., 0., 0., 1., -cx, -cy]),
+                    _ => rotation
+                }
+            }
+            ("skewX", 1) => [1., 0., args[0].to_radians().tan(), 1., 0., 0.],
+            ("skewY", 1) => [1., args[0].to_radians().tan(), 0., 1., 0., 0.],
+            _ => return Err(format!("Invalid transform '{}'.", string))
+        };
+
+        matrix = multiply(matrix, transform);
+        rest = rest[close + 1..].trim();
+    }
+
+    return Ok(matrix);
+}
+
+fn parse_view_box(root: &Element) -> Option<[f64; 4]>
+{
+    if let Some(view_box) = root.attributes.get("viewBox") {
+        if let Ok(n) = parse_numbers(view_box) {
+            if n.len() == 4 { return Some([n[0], n[1], n[2], n[3]]); }
+        }
+    }
+
+    // without a viewBox the width and height give us the same information as long as they're in user units
+    let dimension = |name: &str| root.attributes.get(name).and_then(|v| v.trim_end_matches("px").parse::<f64>().ok());
+    match (dimension("width"), dimension("height")) {
+        (Some(w), Some(h)) => Some([0., 0., w, h]),
+        _ => None
+    }
+}
+
+fn collect_paths(element: &Element, parent: Matrix, output: &mut Vec<Piecewise<Bezier>>) -> Result<(), String>
+{
+    if NON_RENDERING.contains(&element.name.as_str()) { return Ok(()); }
+
+    let matrix = match element.attributes.get("transform") {
+        Some(t) => multiply(parent, parse_transform(t)?),
+        None => parent
+    };
+
+    if element.name == "path" {
+        if let Some(d) = element.attributes.get("d") {
+            let outline = Piecewise::from_svg_path_data(d).map_err(|e| e.to_string())?;
+            let transformed = outline.apply_transform(|v: &Vector| Vector {
+                x: matrix[0] * v.x + matrix[2] * v.y + matrix[4],
+                y: matrix[1] * v.x + matrix[3] * v.y + matrix[5],
+            });
+
+            output.extend(transformed.into_segs());
+        }
+    }
+
+    for child in &element.children {
+        if let XMLNode::Element(e) = child {
+            collect_paths(e, matrix, output)?;
+        }
+    }
+
+    return Ok(());
+}
+
+// Reads every path element in the document with their transforms applied. Other shapes are ignored.
+pub fn read_svg(contents: &str) -> Result<SvgDocument, String>
+{
+    let root = Element::parse(contents.as_bytes()).map_err(|e| format!("Failed to parse SVG! {}", e))?;
+
+    let mut contours = Vec::new();
+    collect_paths(&root, IDENTITY, &mut contours)?;
+
+    return Ok(SvgDocument {
+        outline: Piecewise::new(contours),
+        view_box: parse_view_box(&root),
+        orientation: Orientation::YDown,
+    });
+}
+
+// Writes the outline as a single filled path. If we don't have a viewBox we make one that fits the outline.
+pub fn write_svg(outline: &Piecewise<Piecewise<Bezier>>, view_box: Option<[f64; 4]>, precision: Option<usize>) -> String
+{
+    let view_box = match view_box {
+        Some(vb) => vb,
+        None if !outline.is_empty() => {
+            let b = outline.bounds();
+            [b.left, b.bottom, b.right - b.left, b.top - b.bottom]
+        }
+        None => [0., 0., 0., 0.]
+    };
+
+    let mut writer = XmlWriter::new(Options::default());
+    writer.start_element("svg");
+    writer.write_attribute("xmlns", "http://www.w3.org/2000/svg");
+    writer.write_attribute("viewBox", &format!("{} {} {} {}", view_box[0], view_box[1], view_box[2], view_box[3]));
+
+    writer.start_element("path");
+    writer.write_attribute("d", &outline.to_svg_path_data(precision));
+    writer.end_element();
+
+    writer.end_element();
+    return writer.end_document();
+}
+
+impl SvgSpace {
+    // The bottom of the viewBox lands on the baseline.
+    fn flip_line(&self) -> (f64, f64)
+    {
+        match self.view_box {
+            Some(vb) => (vb[0], vb[1] + vb[3]),
+            None => (0., 0.)
+        }
+    }
+
+    pub fn to_font_space(&self, outline: &Piecewise<Piecewise<Bezier>>) -> Piecewise<Piecewise<Bezier>>
+    {
+        let (ox, oy) = self.flip_line();
+        let scale = self.scale;
+        let flipped = outline.convert_orientation(Orientation::YDown, Orientation::YUp, oy);
+
+        return flipped.apply_transform(|v: &Vector| Vector { x: (v.x - ox) * scale, y: (v.y - oy) * scale });
+    }
+
+    pub fn to_svg_space(&self, outline: &Piecewise<Piecewise<Bezier>>) -> Piecewise<Piecewise<Bezier>>
+    {
+        let (ox, oy) = self.flip_line();
+        let scale = self.scale;
+        let scaled = outline.apply_transform(|v: &Vector| Vector { x: v.x / scale + ox, y: v.y / scale + oy });
+
+        return scaled.convert_orientation(Orientation::YUp, Orientation::YDown, oy);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Two paths, one transformed directly and one inside a transformed group.
+    const TWO_PATHS: &str = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 200 100">
+  <path transform="translate(50 30)" d="M0 0L100 0"/>
+  <g transform="translate(150 0)">
+    <path transform="scale(0.5)" d="M0 0L0 100"/>
+  </g>
+</svg>"#;
+
+    fn ends(contour: &Piecewise<Bezier>) -> (Vector, Vector)
+    {
+        return (contour.first().unwrap().to_control_points()[0], contour.last().unwrap().to_control_points()[3]);
+    }
+
+    #[test]
+    fn reads_paths_with_their_transforms()
+    {
+        let document = read_svg(TWO_PATHS).unwrap();
+        assert_eq!(document.view_box, Some([0., 0., 200., 100.]));
+        assert_eq!(document.outline.len(), 2);
+
+        let (start, end) = ends(&document.outline[0]);
+        assert!(start.is_near(Vector { x: 50., y: 30. }, 1e-9) && end.is_near(Vector { x: 150., y: 30. }, 1e-9));
+        let (start, end) = ends(&document.outline[1]);
+        assert!(start.is_near(Vector { x: 150., y: 0. }, 1e-9) && end.is_near(Vector { x: 150., y: 50. }, 1e-9));
+    }
+
+    // Into font space, where the bottom of the viewBox is the baseline and y goes up, then back out to a file and
+    // in again lands on the same paths.
+    #[test]
+    fn font_space_round_trip()
+    {
+        let document = read_svg(TWO_PATHS).unwrap();
+        let space = SvgSpace { view_box: document.view_box, scale: 2. };
+
+        let font = space.to_font_space(&document.outline);
+        let (start, end) = ends(&font[0]);
+        assert!(start.is_near(Vector { x: 100., y: 140. }, 1e-9) && end.is_near(Vector { x: 300., y: 140. }, 1e-9));
+        let (start, end) = ends(&font[1]);
+        assert!(start.is_near(Vector { x: 300., y: 200. }, 1e-9) && end.is_near(Vector { x: 300., y: 100. }, 1e-9));
+
+        let written = write_svg(&space.to_svg_space(&font), space.view_box, None);
+        let read_back = read_svg(&written).unwrap();
+        assert_eq!(read_back.view_box, document.view_box);
+        assert!(read_back.outline.approx_eq(&document.outline, 1e-9), "{}", written);
+    }
+
+    // fixtures/skeletons/square.glif drawn into an SVG the same size as its advance. SVG is y-down so its y
+    // coordinates are 600 take away the glif's, and it goes round the same way on screen. Brought into font space
+    // it has to stroke exactly like the glif does, a stroke with different widths either side included, which comes
+    // out mirrored if either side of the conversion has its idea of left and right the wrong way round.
+    #[test]
+    fn strokes_like_the_same_glif()
+    {
+        let glif = crate::testing::read_glif_piecewise(&std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/skeletons/square.glif")).unwrap();
+        let document = read_svg(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 600 600">
+  <path d="M100 500L500 500L500 100L100 100Z"/>
+</svg>"#).unwrap();
+        let svg = SvgSpace { view_box: document.view_box, scale: 1. }.to_font_space(&document.outline);
+
+        let settings = crate::stroke::StrokeSettings::default();
+        let (glif_stroke, svg_stroke) = (crate::stroke::two_sided_stroke(&glif, 10., 30., &settings), crate::stroke::two_sided_stroke(&svg, 10., 30., &settings));
+        assert_eq!(glif_stroke.len(), 2);
+        assert!(svg_stroke.approx_eq(&glif_stroke, 1e-9), "{}\n{}", svg_stroke.to_svg_path_data(None), glif_stroke.to_svg_path_data(None));
+    }
+}
@@ -0,0 +1,1278 @@
+// Generators and shape comparisons for property testing the conversions between glifs, Piecewises and skia paths.
+// They're public so crates building on this one can fuzz their own conversions with the same inputs. Built with the
+// testing feature, and for our own tests, which are at the bottom.
+use crate::qmath::*;
+use crate::pattern_along_path::*;
+use crate::pipeline::{Pipeline, Stage, StageArtifact};
+use crate::geometry_profile::GeometryProfile;
+use glifparser::{Contour, Glif, Handle, Outline, PointType};
+use proptest::prelude::*;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+#[cfg(feature = "skia")]
+use std::time::{Duration, Instant};
+#[cfg(feature = "skia")]
+use skulpin::skia_safe::{paint, stroke_rec, StrokeRec};
+
+// A contour described by its segments rather than its points, this is easy to generate and can be turned into
+// any of the representations we want to compare.
+#[derive(Clone, Debug)]
+pub struct TestContour {
+    pub start: Vector,
+    pub segments: Vec<TestSegment>,
+    // a closed contour's last segment always ends back at start
+    pub closed: bool,
+}
+
+#[derive(Clone, Debug)]
+pub enum TestSegment {
+    Line(Vector),
+    Curve(Vector, Vector, Vector),
+    QCurve(Vector, Vector),
+}
+
+impl TestSegment {
+    pub fn end(&self) -> Vector
+    {
+        match self {
+            TestSegment::Line(to) => *to,
+            TestSegment::Curve(_, _, to) => *to,
+            TestSegment::QCurve(_, to) => *to,
+        }
+    }
+}
+
+// What the generator picks before we know where each segment starts. A handle of None sits on its point and a
+// Duplicate is a zero length line back to the point we're already on.
+#[derive(Clone, Debug)]
+enum RawSegment {
+    Line(Vector),
+    Curve(Option<Vector>, Option<Vector>, Vector),
+    QCurve(Vector, Vector),
+    Duplicate,
+}
+
+fn resolve(start: Vector, raw: Vec<RawSegment>, closed: bool) -> TestContour
+{
+    let mut ends: Vec<Vector> = Vec::new();
+    let mut last = start;
+    for segment in &raw {
+        last = match segment {
+            RawSegment::Line(to) => *to,
+            RawSegment::Curve(_, _, to) => *to,
+            RawSegment::QCurve(_, to) => *to,
+            RawSegment::Duplicate => last,
+        };
+        ends.push(last);
+    }
+
+    if closed {
+        *ends.last_mut().unwrap() = start;
+    }
+
+    let mut segments = Vec::new();
+    let mut from = start;
+    for (segment, to) in raw.iter().zip(ends) {
+        segments.push(match segment {
+            RawSegment::Curve(h1, h2, _) => TestSegment::Curve(h1.unwrap_or(from), h2.unwrap_or(to), to),
+            RawSegment::QCurve(control, _) => TestSegment::QCurve(*control, to),
+            _ => TestSegment::Line(to),
+        });
+        from = to;
+    }
+
+    return TestContour { start: start, segments: segments, closed: closed };
+}
+
+// Coordinates are kept to values an f32 can hold exactly enough, glifs store f32s.
+pub fn vector_strategy() -> impl Strategy<Value = Vector>
+{
+    return (-1000.0f64..1000.0, -1000.0f64..1000.0).prop_map(|(x, y)| Vector { x: x, y: y });
+}
+
+fn raw_segment_strategy() -> impl Strategy<Value = RawSegment>
+{
+    return prop_oneof![
+        4 => vector_strategy().prop_map(RawSegment::Line),
+        4 => (prop::option::weighted(0.8, vector_strategy()), prop::option::weighted(0.8, vector_strategy()), vector_strategy())
+            .prop_map(|(h1, h2, to)| RawSegment::Curve(h1, h2, to)),
+        2 => (vector_strategy(), vector_strategy()).prop_map(|(control, to)| RawSegment::QCurve(control, to)),
+        1 => Just(RawSegment::Duplicate),
+    ];
+}
+
+// Mixed line, quadratic and cubic contours, open and closed, with the occasional colocated handle or duplicate point.
+pub fn contour_strategy() -> impl Strategy<Value = TestContour>
+{
+    return (vector_strategy(), prop::collection::vec(raw_segment_strategy(), 1..12), any::<bool>())
+        .prop_map(|(start, raw, closed)| resolve(start, raw, closed));
+}
+
+pub fn outline_strategy() -> impl Strategy<Value = Vec<TestContour>>
+{
+    return prop::collection::vec(contour_strategy(), 1..5);
+}
+
+fn handle(h: Vector, point: Vector) -> Handle
+{
+    return if h == point { Handle::Colocated } else { h.to_handle() };
+}
+
+impl TestContour {
+    pub fn to_piecewise(&self) -> Piecewise<Bezier>
+    {
+        let mut curves = Vec::new();
+        let mut from = self.start;
+
+        for segment in &self.segments {
+            curves.push(match segment {
+                TestSegment::Line(to) => Bezier::from_control_points(from, from, *to, *to),
+                TestSegment::Curve(h1, h2, to) => Bezier::from_control_points(from, *h1, *h2, *to),
+                TestSegment::QCurve(control, to) => Bezier::from_quadratic_points(from, *control, *to),
+            });
+            from = segment.end();
+        }
+
+        return Piecewise::new(curves);
+    }
+
+    // A closed contour doesn't repeat its start point in a glif, the last segment is implied by the first point's
+    // incoming handle. An open one starts with a move. A quadratic's control point goes in the a handle of the
+    // point it leaves from.
+    pub fn to_contour(&self) -> Contour<Option<PointData>>
+    {
+        let n = self.segments.len();
+        let point_count = if self.closed { n } else { n + 1 };
+        let mut contour: Contour<Option<PointData>> = Vec::new();
+
+        for i in 0..point_count {
+            let position = if i == 0 { self.start } else { self.segments[i - 1].end() };
+
+            let incoming = if i > 0 { Some(&self.segments[i - 1]) } else if self.closed { self.segments.last() } else { None };
+            let outgoing = self.segments.get(i);
+
+            let b = match incoming {
+                Some(TestSegment::Curve(_, h2, _)) => handle(*h2, position),
+                _ => Handle::Colocated,
+            };
+
+            let a = match outgoing {
+                Some(TestSegment::Curve(h1, _, _)) => handle(*h1, position),
+                Some(TestSegment::QCurve(control, _)) => handle(*control, position),
+                _ => Handle::Colocated,
+            };
+
+            let mut point = position.to_point(a, b);
+            point.ptype = match incoming {
+                None => PointType::Move,
+                Some(TestSegment::Line(_)) => PointType::Line,
+                Some(TestSegment::Curve(..)) => PointType::Curve,
+                Some(TestSegment::QCurve(..)) => PointType::QCurve,
+            };
+
+            contour.push(point);
+        }
+
+        return contour;
+    }
+}
+
+pub fn to_outline(contours: &[TestContour]) -> Outline<Option<PointData>>
+{
+    return contours.iter().map(|c| c.to_contour()).collect();
+}
+
+pub fn to_piecewise(contours: &[TestContour]) -> Piecewise<Piecewise<Bezier>>
+{
+    return Piecewise::new(contours.iter().map(|c| c.to_piecewise()).collect());
+}
+
+fn sample(contour: &Piecewise<Bezier>, samples_per_segment: usize) -> Vec<Vector>
+{
+    let mut points = Vec::new();
+    for bez in contour.segs() {
+        for i in 0..=samples_per_segment {
+            points.push(bez.evaluate(i as f64 / samples_per_segment as f64));
+        }
+    }
+
+    return points;
+}
+
+fn directed_distance(from: &[Vector], to: &[Vector]) -> f64
+{
+    let mut worst: f64 = 0.;
+    for p in from {
+        let nearest = to.iter().map(|q| p.distance(*q)).fold(f64::INFINITY, f64::min);
+        worst = worst.max(nearest);
+    }
+
+    return worst;
+}
+
+// The Hausdorff distance between points sampled along both contours. This doesn't care how the contours are cut
+// into segments or where they start, only about the shape they trace. It's brute force so keep the inputs small.
+pub fn shape_distance(a: &Piecewise<Bezier>, b: &Piecewise<Bezier>, samples_per_segment: usize) -> f64
+{
+    let sa = sample(a, samples_per_segment);
+    let sb = sample(b, samples_per_segment);
+
+    if sa.is_empty() || sb.is_empty() {
+        return if sa.len() == sb.len() { 0. } else { f64::INFINITY };
+    }
+
+    return directed_distance(&sa, &sb).max(directed_distance(&sb, &sa));
+}
+
+// The Hausdorff distance between points sampled along every contour of both outlines at once. Outlines that trace
+// the same edges come out close however their contours are split up or ordered.
+pub fn outline_distance(a: &Piecewise<Piecewise<Bezier>>, b: &Piecewise<Piecewise<Bezier>>, samples_per_segment: usize) -> f64
+{
+    let sa: Vec<Vector> = a.segs().flat_map(|contour| sample(contour, samples_per_segment)).collect();
+    let sb: Vec<Vector> = b.segs().flat_map(|contour| sample(contour, samples_per_segment)).collect();
+
+    if sa.is_empty() || sb.is_empty() {
+        return if sa.len() == sb.len() { 0. } else { f64::INFINITY };
+    }
+
+    return directed_distance(&sa, &sb).max(directed_distance(&sb, &sa));
+}
+
+// Describes how two outlines differ if they're further apart than epsilon. Contours are compared in order so
+// this also catches any that were added, dropped or shuffled.
+pub fn compare_shapes(a: &Piecewise<Piecewise<Bezier>>, b: &Piecewise<Piecewise<Bezier>>, epsilon: f64) -> Result<(), String>
+{
+    if a.len() != b.len() {
+        return Err(format!("contour counts differ, {} vs {}", a.len(), b.len()));
+    }
+
+    for (i, (ca, cb)) in a.segs().zip(b.segs()).enumerate() {
+        let distance = shape_distance(ca, cb, 8);
+        if distance > epsilon {
+            return Err(format!("contour {} differs by {} which is more than {}", i, distance, epsilon));
+        }
+    }
+
+    return Ok(());
+}
+
+pub fn assert_same_shape(a: &Piecewise<Piecewise<Bezier>>, b: &Piecewise<Piecewise<Bezier>>, epsilon: f64)
+{
+    if let Err(e) = compare_shapes(a, b, epsilon) {
+        panic!("{}", e);
+    }
+}
+
+// Golden fixtures. A fixtures directory holds pattern.glif, the skeletons to stroke in skeletons/ and the outputs
+// we expect in expected/ as path data, one file per skeleton and setting. Every skeleton gets stroked with every
+// entry of golden_settings and compared against its expectation by shape rather than byte for byte. Failures
+// write an svg to diffs/ with the expected output in blue and what we got in red.
+//
+// With UPDATE_GOLDENS set in the environment the expectations get rewritten from the current output instead.
+pub const UPDATE_GOLDENS: &str = "UPDATE_GOLDENS";
+
+pub fn golden_settings() -> Vec<(&'static str, PatternSettings)>
+{
+    let mut repeated = PatternSettings::default();
+    repeated.copies = PatternCopies::Repeated;
+    repeated.pattern_scale = Vector { x: 0.5, y: 1. };
+
+    let mut stretched = PatternSettings::default();
+    stretched.copies = PatternCopies::Repeated;
+    stretched.stretch = true;
+    stretched.spacing = 10.;
+
+    let mut offset = PatternSettings::default();
+    offset.subdivide = PatternSubdivide::Simple(2);
+    offset.normal_offset = 20.;
+
+    return vec![
+        ("single", PatternSettings::default()),
+        ("repeated", repeated),
+        ("stretched", stretched),
+        ("offset", offset),
+    ];
+}
+
+// Empty contours and lone points are kept out of the Piecewise, a glif made only of components has no outline.
+pub fn read_glif_piecewise(path: &Path) -> Result<Piecewise<Piecewise<Bezier>>, String>
+{
+    return Ok(read_glif_piecewise_with_closed(path)?.0);
+}
+
+// read_glif_piecewise along with whether each contour it kept was drawn closed, see from_outline_with_closed.
+pub fn read_glif_piecewise_with_closed(path: &Path) -> Result<(Piecewise<Piecewise<Bezier>>, Vec<bool>), String>
+{
+    let contents = fs::read_to_string(path).map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+    let glif: Glif<Option<PointData>> = glifparser::read_ufo_glif(&contents);
+
+    let mut output = Piecewise::new(Vec::new());
+    let mut closed = Vec::new();
+    for contour in glif.outline.unwrap_or_default() {
+        let piecewise = Piecewise::from_contour(&contour);
+        if piecewise.is_empty() { continue; }
+        output.push(piecewise);
+        closed.push(contour.first().map_or(false, |p| p.ptype != PointType::Move));
+    }
+
+    return Ok((output, closed));
+}
+
+fn diff_svg(expected: Option<&Piecewise<Piecewise<Bezier>>>, actual: &Piecewise<Piecewise<Bezier>>) -> String
+{
+    let mut bounds: Option<Rect> = None;
+    for outline in expected.into_iter().chain(Some(actual)) {
+        for contour in outline.segs().filter(|c| !c.is_empty()) {
+            let b = contour.bounds();
+            bounds = Some(match bounds { Some(r) => r.encapsulate_rect(b), None => b });
+        }
+    }
+
+    let b = bounds.unwrap_or(Rect { left: 0., right: 1., bottom: 0., top: 1. });
+    let margin = 10.;
+
+    // font space is y-up so we flip the whole drawing
+    let mut svg = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">\n<g transform=\"scale(1 -1)\" fill=\"none\">\n",
+        b.left - margin, -b.top - margin, b.right - b.left + 2. * margin, b.top - b.bottom + 2. * margin);
+
+    if let Some(expected) = expected {
+        svg.push_str(&format!("<path stroke=\"blue\" d=\"{}\"/>\n", expected.to_svg_path_data(Some(3))));
+    }
+    svg.push_str(&format!("<path stroke=\"red\" d=\"{}\"/>\n", actual.to_svg_path_data(Some(3))));
+    svg.push_str("</g>\n</svg>\n");
+
+    return svg;
+}
+
+// Runs every fixture and returns a description of each one that failed, an empty Vec means everything passed.
+pub fn check_goldens(fixtures: &Path, epsilon: f64) -> Vec<String>
+{
+    let update = std::env::var_os(UPDATE_GOLDENS).is_some();
+    let mut failures = Vec::new();
+
+    let pattern = match read_glif_piecewise(&fixtures.join("pattern.glif")) {
+        Ok(p) => p,
+        Err(e) => return vec![e],
+    };
+
+    let mut skeletons: Vec<_> = match fs::read_dir(fixtures.join("skeletons")) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path())
+            .filter(|p| p.extension().map_or(false, |ext| ext == "glif"))
+            .collect(),
+        Err(e) => return vec![format!("Couldn't read the skeletons: {}", e)],
+    };
+    skeletons.sort();
+
+    let _ = fs::create_dir_all(fixtures.join("expected"));
+    let _ = fs::create_dir_all(fixtures.join("diffs"));
+
+    for skeleton_path in skeletons {
+        let skeleton = match read_glif_piecewise(&skeleton_path) {
+            Ok(s) => s,
+            Err(e) => { failures.push(e); continue; }
+        };
+        let name = skeleton_path.file_stem().unwrap().to_string_lossy().to_string();
+
+        for (setting_name, settings) in golden_settings() {
+            let case = format!("{}.{}", name, setting_name);
+            let expected_path = fixtures.join("expected").join(format!("{}.txt", case));
+            let diff_path = fixtures.join("diffs").join(format!("{}.svg", case));
+
+            let result = panic::catch_unwind(AssertUnwindSafe(|| pattern_along_outline(&skeleton, &pattern, &settings)));
+            let actual = match result {
+                Ok(a) => a,
+                Err(_) => { failures.push(format!("{} panicked", case)); continue; }
+            };
+
+            if update {
+                if let Err(e) = fs::write(&expected_path, actual.to_svg_path_data(Some(4))) {
+                    failures.push(format!("Couldn't write {}: {}", expected_path.display(), e));
+                }
+                let _ = fs::write(&diff_path, diff_svg(None, &actual));
+                continue;
+            }
+
+            let expected = match fs::read_to_string(&expected_path) {
+                Ok(d) => match Piecewise::from_svg_path_data(&d) {
+                    Ok(e) => e,
+                    Err(e) => { failures.push(format!("{} has a broken expectation: {}", case, e)); continue; }
+                },
+                Err(_) => { failures.push(format!("{} has no expectation, run with {} set to create it", case, UPDATE_GOLDENS)); continue; }
+            };
+
+            if let Err(e) = compare_shapes(&expected, &actual, epsilon) {
+                failures.push(format!("{}: {}", case, e));
+                let _ = fs::write(&diff_path, diff_svg(Some(&expected), &actual));
+            }
+        }
+    }
+
+    return failures;
+}
+
+// The skeleton and golden setting every geometry profile gets a golden output for. Pattern along path is what
+// the profiles' constants shape, an open curve repeated along shows off both the arc length and the normals.
+pub const PROFILE_GOLDEN_CASE: (&str, &str) = ("s_curve", "repeated");
+
+// Locks what a geometry profile strokes PROFILE_GOLDEN_CASE as, so a change meant for Latest that leaks into an
+// older profile shows up. The expectation is expected_profiles/<skeleton>.<profile>.txt, compared structurally like
+// the op goldens since a profile has to reproduce its output rather than just its shape. Returns a description of
+// what failed, UPDATE_GOLDENS rewrites the expectation instead.
+pub fn check_profile_golden(fixtures: &Path, profile: GeometryProfile, epsilon: f64) -> Vec<String>
+{
+    let update = std::env::var_os(UPDATE_GOLDENS).is_some();
+    let (skeleton_name, setting_name) = PROFILE_GOLDEN_CASE;
+    let case = format!("{}.{}", skeleton_name, profile.name());
+
+    let pattern = match read_glif_piecewise(&fixtures.join("pattern.glif")) {
+        Ok(p) => p,
+        Err(e) => return vec![e],
+    };
+    let skeleton = match read_glif_piecewise(&fixtures.join("skeletons").join(format!("{}.glif", skeleton_name))) {
+        Ok(s) => s,
+        Err(e) => return vec![e],
+    };
+
+    let mut settings = golden_settings().into_iter().find(|(name, _)| *name == setting_name).unwrap().1;
+    settings.geometry_profile = profile;
+    let actual = pattern_along_outline(&skeleton, &pattern, &settings);
+
+    let _ = fs::create_dir_all(fixtures.join("expected_profiles"));
+    let expected_path = fixtures.join("expected_profiles").join(format!("{}.txt", case));
+    if update {
+        return match fs::write(&expected_path, actual.to_svg_path_data(Some(4))) {
+            Ok(_) => vec![],
+            Err(e) => vec![format!("Couldn't write {}: {}", expected_path.display(), e)],
+        };
+    }
+
+    let expected = match fs::read_to_string(&expected_path) {
+        Ok(d) => match Piecewise::from_svg_path_data(&d) {
+            Ok(e) => e,
+            Err(e) => return vec![format!("{} has a broken expectation: {}", case, e)],
+        },
+        Err(_) => return vec![format!("{} has no expectation, run with {} set to create it", case, UPDATE_GOLDENS)],
+    };
+
+    if let Some(difference) = first_difference(&expected, &actual, epsilon) {
+        let _ = fs::create_dir_all(fixtures.join("diffs"));
+        let _ = fs::write(fixtures.join("diffs").join(format!("{}.svg", case)), diff_svg(Some(&expected), &actual));
+        return vec![format!("{}: {}", case, difference)];
+    }
+
+    return vec![];
+}
+
+// Runs every fixture skeleton with every golden setting through the pipeline twice, once straight through and once
+// stopping after each stage in turn, saving the artifact as JSON and resuming from what was read back. The split
+// runs have to come out exactly the same as the straight one. Returns a description of each that didn't.
+pub fn check_split_pipeline(fixtures: &Path) -> Vec<String>
+{
+    let mut failures = Vec::new();
+
+    let pattern = match read_glif_piecewise(&fixtures.join("pattern.glif")) {
+        Ok(p) => p,
+        Err(e) => return vec![e],
+    };
+
+    let mut skeletons: Vec<_> = match fs::read_dir(fixtures.join("skeletons")) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path())
+            .filter(|p| p.extension().map_or(false, |ext| ext == "glif"))
+            .collect(),
+        Err(e) => return vec![format!("Couldn't read the skeletons: {}", e)],
+    };
+    skeletons.sort();
+
+    for skeleton_path in skeletons {
+        let skeleton = match read_glif_piecewise(&skeleton_path) {
+            Ok(s) => s,
+            Err(e) => { failures.push(e); continue; }
+        };
+        let name = skeleton_path.file_stem().unwrap().to_string_lossy().to_string();
+
+        for (setting_name, settings) in golden_settings() {
+            let pipeline = Pipeline::new(&settings);
+            let whole = match panic::catch_unwind(AssertUnwindSafe(|| pipeline.run(&skeleton, &pattern))) {
+                Ok(a) => a,
+                Err(_) => { failures.push(format!("{}.{} panicked", name, setting_name)); continue; }
+            };
+
+            for stage in Stage::ALL.iter().filter(|s| **s != Stage::Postprocess) {
+                let case = format!("{}.{} split after {}", name, setting_name, stage.name());
+
+                let first_half = Pipeline { settings: &settings, stop_after: *stage };
+                let json = first_half.run(&skeleton, &pattern).to_json();
+                let resumed = match StageArtifact::from_json(&json) {
+                    Ok(artifact) => pipeline.resume(artifact),
+                    Err(e) => { failures.push(format!("{}: {}", case, e)); continue; }
+                };
+
+                // the shortest representation that round trips, so this only matches if every coordinate does
+                if resumed.outline.to_svg_path_data(None) != whole.outline.to_svg_path_data(None) || resumed.provenance != whole.provenance {
+                    failures.push(format!("{}: came out different from running straight through", case));
+                }
+            }
+        }
+    }
+
+    return failures;
+}
+
+// Where two outlines first differ by more than epsilon, naming the contour, the segment and the control point, or
+// None if every control point of one is within epsilon of the other's. This is the structural comparison approx_eq
+// makes, spelled out for a failure message.
+pub fn first_difference(expected: &Piecewise<Piecewise<Bezier>>, actual: &Piecewise<Piecewise<Bezier>>, epsilon: f64) -> Option<String>
+{
+    if expected.len() != actual.len() {
+        return Some(format!("expected {} contours but got {}", expected.len(), actual.len()));
+    }
+
+    for (i, (ce, ca)) in expected.segs().zip(actual.segs()).enumerate() {
+        if ce.len() != ca.len() {
+            return Some(format!("contour {} should have {} segments but has {}", i, ce.len(), ca.len()));
+        }
+
+        for (j, (be, ba)) in ce.segs().zip(ca.segs()).enumerate() {
+            let (pe, pa) = (be.to_control_points(), ba.to_control_points());
+            for k in 0..4 {
+                let off = pe[k].distance(pa[k]);
+                if off > epsilon {
+                    return Some(format!("contour {} segment {} control point {} is at ({}, {}) instead of ({}, {}), {} off",
+                        i, j, k, pa[k].x, pa[k].y, pe[k].x, pe[k].y, off));
+                }
+            }
+        }
+    }
+
+    return None;
+}
+
+// The geometric operations with golden outputs, each run on a skeleton read from a glif. The stroke and offset
+// distances are big enough to be obvious in a diff and small enough to keep the fixtures' counters open. Ops that
+// finish by removing overlap, like inflate, aren't here: what skia hands back changes between its versions in where
+// contours start and how its f32s round, which a structural comparison can't look past.
+// Each op gets whether every contour was drawn closed along with the skeleton, the strokes go by that rather than
+// by whether the ends meet the way CWS and VWS do, so open_loop comes out with caps.
+pub fn golden_ops() -> Vec<(&'static str, fn(&Piecewise<Piecewise<Bezier>>, &[bool]) -> Piecewise<Piecewise<Bezier>>)>
+{
+    let ops: Vec<(&'static str, fn(&Piecewise<Piecewise<Bezier>>, &[bool]) -> Piecewise<Piecewise<Bezier>>)> = vec![
+        ("round_trip", |outline, _| Piecewise::from_outline(&outline.to_outline())),
+        ("subdivide", |outline, _| outline.subdivide(0.5)),
+        ("reverse", |outline, _| outline.reverse()),
+        ("stroke", |outline, closed| crate::stroke::constant_width_stroke_with_closed(outline, closed, &crate::stroke::StrokeSettings::default())),
+        // open skeletons are what people draw to be stroked, s_curve.round_cap_stroke is the one to look at first
+        ("round_cap_stroke", |outline, closed| crate::stroke::constant_width_stroke_with_closed(outline, closed, &crate::stroke::StrokeSettings { cap: crate::stroke::StrokeCap::Round, ..Default::default() })),
+        ("two_sided_stroke", |outline, closed| crate::stroke::two_sided_stroke_with_closed(outline, closed, 10., 4., &crate::stroke::StrokeSettings::default())),
+        // 5 either side at the start of each contour up to 15 at its end
+        ("variable_width_stroke", |outline, closed| crate::stroke::variable_width_stroke_with_closed(outline, closed, &|t| (5. + 10. * t, 5. + 10. * t), &crate::stroke::StrokeSettings::default())),
+        ("offset", |outline, _| Piecewise::new(outline.segs().map(|contour| contour.offset(10., 0.01)).collect())),
+        // square.round_corners should be its sides cut back 50 from each corner with a quarter circle across each
+        ("round_corners", |outline, _| outline.round_corners(50., CORNER_ANGLE)),
+        // circle.italicize should lean 12 degrees about its middle with a point on each side where it's widest
+        ("italicize", |outline, _| outline.italicize(12., 300.).0),
+    ];
+
+    return ops;
+}
+
+// Golden outputs for the geometric operations. Every skeleton in the fixtures is run through every entry of
+// golden_ops and the result compared with expected_ops/<skeleton>.<op>.glif, structurally: the same contours with
+// the same segments and every control point within epsilon. That's stricter than check_goldens' comparison by shape
+// on purpose, these are the ops whose handles we care about. Failures name the first point that's off and write
+// an svg to diffs/ same as check_goldens. UPDATE_GOLDENS rewrites the expectations here too.
+pub fn check_op_goldens(fixtures: &Path, epsilon: f64) -> Vec<String>
+{
+    let update = std::env::var_os(UPDATE_GOLDENS).is_some();
+    let mut failures = Vec::new();
+
+    let mut skeletons: Vec<_> = match fs::read_dir(fixtures.join("skeletons")) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path())
+            .filter(|p| p.extension().map_or(false, |ext| ext == "glif"))
+            .collect(),
+        Err(e) => return vec![format!("Couldn't read the skeletons: {}", e)],
+    };
+    skeletons.sort();
+
+    let _ = fs::create_dir_all(fixtures.join("expected_ops"));
+    let _ = fs::create_dir_all(fixtures.join("diffs"));
+
+    for skeleton_path in skeletons {
+        let (skeleton, closed) = match read_glif_piecewise_with_closed(&skeleton_path) {
+            Ok(s) => s,
+            Err(e) => { failures.push(e); continue; }
+        };
+        let name = skeleton_path.file_stem().unwrap().to_string_lossy().to_string();
+
+        for (op_name, op) in golden_ops() {
+            let case = format!("{}.{}", name, op_name);
+            let expected_path = fixtures.join("expected_ops").join(format!("{}.glif", case));
+            let diff_path = fixtures.join("diffs").join(format!("{}.svg", case));
+
+            let actual = match panic::catch_unwind(AssertUnwindSafe(|| op(&skeleton, &closed))) {
+                Ok(a) => a,
+                Err(_) => { failures.push(format!("{} panicked", case)); continue; }
+            };
+
+            if update {
+                // everything but the outline comes from the skeleton
+                let source: Glif<Option<PointData>> = glifparser::read_ufo_glif(&fs::read_to_string(&skeleton_path).unwrap_or_default());
+                let glif = Glif {
+                    outline: Some(actual.to_outline()),
+                    order: source.order,
+                    anchors: None,
+                    width: source.width,
+                    unicode: source.unicode,
+                    name: source.name,
+                    format: 2,
+                };
+                if let Err(e) = fs::write(&expected_path, crate::glifwriter::write_ufo_glif(glif)) {
+                    failures.push(format!("Couldn't write {}: {}", expected_path.display(), e));
+                }
+                let _ = fs::write(&diff_path, diff_svg(None, &actual));
+                continue;
+            }
+
+            if !expected_path.exists() {
+                failures.push(format!("{} has no expectation, run with {} set to create it", case, UPDATE_GOLDENS));
+                continue;
+            }
+            let expected = match read_glif_piecewise(&expected_path) {
+                Ok(e) => e,
+                Err(e) => { failures.push(format!("{} has a broken expectation: {}", case, e)); continue; }
+            };
+
+            // the expectation went through a glif, where straight curves lose their handles, so ours does too
+            if let Some(difference) = first_difference(&expected, &Piecewise::from_outline(&actual.to_outline()), epsilon) {
+                failures.push(format!("{}: {}", case, difference));
+                let _ = fs::write(&diff_path, diff_svg(Some(&expected), &actual));
+            }
+        }
+    }
+
+    return failures;
+}
+
+// TrueType glifs. Every glif in fixtures/truetype is read point by point and each contour put through from_contour,
+// then checked against its own points: the on-curve points, the implied ones halfway between neighbouring off-curve
+// points and the middle of every quadratic worked out by hand all have to be within epsilon of what came in. Then
+// it goes out through to_quadratic_contour and back in again, which should give the same shape from the same number
+// of points, all of them off-curve again for a contour that started that way. Last it's written out as a glif of its
+// own by glifwriter and read back, which has to keep all of that too.
+fn truetype_contour(points: &[(Vector, Option<SegmentType>)]) -> Contour<Option<PointData>>
+{
+    return points.iter().map(|(position, segment_type)| {
+        let mut point = position.to_point(Handle::Colocated, Handle::Colocated);
+        point.ptype = match segment_type {
+            None => PointType::OffCurve,
+            Some(SegmentType::Move) => PointType::Move,
+            Some(SegmentType::Line) => PointType::Line,
+            Some(SegmentType::Curve) => PointType::Curve,
+            Some(SegmentType::QCurve) => PointType::QCurve,
+        };
+        return point;
+    }).collect();
+}
+
+// The points a contour's quadratics have to pass through, worked out straight from its glif points.
+fn implied_samples(points: &[(Vector, Option<SegmentType>)]) -> Vec<Vector>
+{
+    let n = points.len();
+    let closed = points.first().map_or(false, |p| p.1 != Some(SegmentType::Move));
+    let mut samples = Vec::new();
+
+    for i in 0..n {
+        let (position, segment_type) = points[i];
+        if segment_type.is_some() { samples.push(position); continue; }
+        if !closed && (i == 0 || i + 1 == n) { continue; }
+
+        let (before, before_type) = points[(i + n - 1) % n];
+        let (after, after_type) = points[(i + 1) % n];
+        let start = if before_type.is_some() { before } else { before.lerp(position, 0.5) };
+        let end = if after_type.is_some() { after } else { position.lerp(after, 0.5) };
+
+        samples.push(start);
+        samples.push(start * 0.25 + position * 0.5 + end * 0.25);
+    }
+
+    return samples;
+}
+
+pub fn check_truetype_fixtures(fixtures: &Path, epsilon: f64) -> Vec<String>
+{
+    let mut failures = Vec::new();
+
+    let mut glifs: Vec<_> = match fs::read_dir(fixtures.join("truetype")) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path())
+            .filter(|p| p.extension().map_or(false, |ext| ext == "glif"))
+            .collect(),
+        Err(e) => return vec![format!("Couldn't read the TrueType fixtures: {}", e)],
+    };
+    glifs.sort();
+
+    for path in glifs {
+        let name = path.file_stem().unwrap().to_string_lossy().to_string();
+        let contours = match fs::read_to_string(&path).map_err(|e| e.to_string()).and_then(|xml| crate::components::read_contour_points(&xml)) {
+            Ok(c) => c,
+            Err(e) => { failures.push(format!("Couldn't read {}: {}", path.display(), e)); continue; }
+        };
+
+        for (i, points) in contours.iter().enumerate() {
+            let contour = truetype_contour(points);
+            let imported = Piecewise::from_contour(&contour);
+
+            for sample in implied_samples(points) {
+                let distance = imported.nearest(sample).map_or(f64::INFINITY, |nearest| nearest.2);
+                if distance > epsilon {
+                    failures.push(format!("{} contour {} misses ({}, {}) by {}", name, i, sample.x, sample.y, distance));
+                    break;
+                }
+            }
+
+            let written = match Piecewise::<QuadBezier>::from_quadratic_contour(&contour) {
+                Some(quadratics) => quadratics.to_quadratic_contour(),
+                None => { failures.push(format!("{} contour {} didn't read as quadratics", name, i)); continue; }
+            };
+            let all_off_curve = |c: &Contour<Option<PointData>>| c.iter().all(|p| p.ptype == PointType::OffCurve);
+            if written.len() != contour.len() || all_off_curve(&written) != all_off_curve(&contour) {
+                failures.push(format!("{} contour {} was written back as {} points from {}", name, i, written.len(), contour.len()));
+            }
+
+            let round_trip = Piecewise::new(vec![Piecewise::from_contour(&written)]);
+            let imported = Piecewise::new(vec![imported]);
+            if let Err(e) = compare_shapes(&imported, &round_trip, epsilon) {
+                failures.push(format!("{} contour {} changed on the way back out: {}", name, i, e));
+            }
+
+            let glif = Glif {
+                outline: Some(vec![written.clone()]),
+                order: glifparser::OutlineType::Quadratic,
+                anchors: None,
+                width: 0,
+                unicode: glifparser::Codepoint::Undefined,
+                name: name.clone(),
+                format: 2,
+            };
+            let xml = crate::glifwriter::write_ufo_glif(glif);
+            let read: Glif<Option<PointData>> = glifparser::read_ufo_glif(&xml);
+            let read = read.outline.unwrap_or_default();
+            if xml.matches("<point").count() != written.len() || read.iter().any(|c| all_off_curve(c) != all_off_curve(&contour)) {
+                failures.push(format!("{} contour {} was saved as {} points from {}", name, i, xml.matches("<point").count(), written.len()));
+            }
+            if let Err(e) = compare_shapes(&imported, &Piecewise::from_outline(&read), epsilon) {
+                failures.push(format!("{} contour {} changed going through a glif: {}", name, i, e));
+            }
+        }
+    }
+
+    return failures;
+}
+
+// Properties of a single curve. The coefficient form is what everything evaluates with and the control points are
+// what everyone sees, these check the two stay the same curve over coordinates a lot wider than vector_strategy's,
+// where cancellation in the coefficients is most likely to show. Each check describes the first thing it found
+// wrong, so they drop straight into a prop_assert. The ones taking an Evaluate work for any curve type we add.
+
+// relative to the biggest coordinate of the curve
+const ROUND_TRIP_TOLERANCE: f64 = 1e-9;
+// the finite difference we check derivative against is a lot rougher than the rest
+const DERIVATIVE_TOLERANCE: f64 = 1e-4;
+const DERIVATIVE_STEP: f64 = 1e-6;
+pub const PROPERTY_SAMPLES: usize = 1000;
+
+// Ordinary coordinates most of the time, and now and then huge or tiny ones. Both signs either way.
+pub fn coordinate_strategy() -> impl Strategy<Value = f64>
+{
+    return prop_oneof![
+        4 => -1000.0f64..1000.0,
+        1 => -1e9f64..1e9,
+        1 => -1e-6f64..1e-6,
+    ];
+}
+
+pub fn wide_vector_strategy() -> impl Strategy<Value = Vector>
+{
+    return (coordinate_strategy(), coordinate_strategy()).prop_map(|(x, y)| Vector { x: x, y: y });
+}
+
+// A cubic's control points. A handle of None sits on its point and an end of None is back on the start, so every
+// so often we get lines, retracted handles and curves that are all one point.
+pub fn bezier_points_strategy() -> impl Strategy<Value = [Vector; 4]>
+{
+    return (wide_vector_strategy(), prop::option::weighted(0.8, wide_vector_strategy()), prop::option::weighted(0.8, wide_vector_strategy()), prop::option::weighted(0.9, wide_vector_strategy()))
+        .prop_map(|(p0, h1, h2, p3)| {
+            let p3 = p3.unwrap_or(p0);
+            return [p0, h1.unwrap_or(p0), h2.unwrap_or(p3), p3];
+        });
+}
+
+fn scale_of(points: &[Vector]) -> f64
+{
+    return points.iter().fold(0., |m, p| f64::max(m, f64::max(p.x.abs(), p.y.abs())));
+}
+
+fn sample_t(k: usize, samples: usize) -> f64
+{
+    return k as f64 / (samples - 1) as f64;
+}
+
+fn de_casteljau(points: &[Vector; 4], t: f64) -> Vector
+{
+    let q0 = Vector::lerp(points[0], points[1], t);
+    let q1 = Vector::lerp(points[1], points[2], t);
+    let q2 = Vector::lerp(points[2], points[3], t);
+    return Vector::lerp(Vector::lerp(q0, q1, t), Vector::lerp(q1, q2, t), t);
+}
+
+// from_control_points then to_control_points gives back the same points.
+pub fn check_control_point_round_trip(points: &[Vector; 4]) -> Result<(), String>
+{
+    let tolerance = ROUND_TRIP_TOLERANCE * scale_of(points);
+    let back = Bezier::from_control_points(points[0], points[1], points[2], points[3]).to_control_points();
+
+    for i in 0..4 {
+        if back[i].distance(points[i]) > tolerance {
+            return Err(format!("control point {} of {:?} came back as {:?}", i, points, back[i]));
+        }
+    }
+
+    return Ok(());
+}
+
+// The polynomial in evaluate and de Casteljau on the control points trace the same curve.
+pub fn check_evaluation_agreement(points: &[Vector; 4]) -> Result<(), String>
+{
+    let tolerance = ROUND_TRIP_TOLERANCE * scale_of(points);
+    let bez = Bezier::from_control_points(points[0], points[1], points[2], points[3]);
+
+    for k in 0..PROPERTY_SAMPLES {
+        let t = sample_t(k, PROPERTY_SAMPLES);
+        let (polynomial, casteljau) = (bez.evaluate(t), de_casteljau(points, t));
+        if polynomial.distance(casteljau) > tolerance {
+            return Err(format!("{:?} evaluates to {:?} at t = {} but de Casteljau says {:?}", points, polynomial, t, casteljau));
+        }
+    }
+
+    return Ok(());
+}
+
+// Both halves of subdivide meet where the curve is at t, and start and end where it does.
+pub fn check_subdivision_continuity(points: &[Vector; 4]) -> Result<(), String>
+{
+    let tolerance = ROUND_TRIP_TOLERANCE * scale_of(points);
+    let bez = Bezier::from_control_points(points[0], points[1], points[2], points[3]);
+
+    for k in 0..PROPERTY_SAMPLES {
+        let t = sample_t(k, PROPERTY_SAMPLES);
+        let (first, second) = bez.subdivide(t);
+        let expected = [(first.evaluate(0.), bez.evaluate(0.), "start of the first half"),
+            (first.evaluate(1.), bez.evaluate(t), "end of the first half"),
+            (second.evaluate(0.), bez.evaluate(t), "start of the second half"),
+            (second.evaluate(1.), bez.evaluate(1.), "end of the second half")];
+
+        for (got, want, what) in expected.iter() {
+            if got.distance(*want) > tolerance {
+                return Err(format!("the {} of {:?} split at t = {} is {:?} rather than {:?}", what, points, t, got, want));
+            }
+        }
+    }
+
+    return Ok(());
+}
+
+// derivative agrees with a finite difference of evaluate, one sided at the ends. derivative points backwards so
+// that's what we compare against. scale is the size of the curve's coordinates, the tolerance is relative to it.
+pub fn check_derivative<T: Evaluate>(curve: &T, scale: f64) -> Result<(), String>
+{
+    for k in 0..PROPERTY_SAMPLES {
+        let t = sample_t(k, PROPERTY_SAMPLES);
+        let (before, after) = (f64::max(t - DERIVATIVE_STEP, 0.), f64::min(t + DERIVATIVE_STEP, 1.));
+        let difference = (curve.evaluate(before) + -curve.evaluate(after)) * (1. / (after - before));
+        let derivative = curve.derivative(t);
+
+        if !derivative.x.is_finite() || !derivative.y.is_finite() {
+            return Err(format!("the derivative at t = {} is {:?}", t, derivative));
+        }
+        if derivative.distance(difference) > DERIVATIVE_TOLERANCE * (difference.length() + scale) {
+            return Err(format!("the derivative at t = {} is {:?} but evaluate changes at {:?}", t, derivative, difference));
+        }
+    }
+
+    return Ok(());
+}
+
+// Every point along the curve is inside bounds, give or take scale's rounding.
+pub fn check_bounds_contain<T: Evaluate>(curve: &T, scale: f64) -> Result<(), String>
+{
+    let bounds = curve.bounds();
+    let tolerance = ROUND_TRIP_TOLERANCE * scale;
+
+    for k in 0..PROPERTY_SAMPLES {
+        let t = sample_t(k, PROPERTY_SAMPLES);
+        let p = curve.evaluate(t);
+        if p.x < bounds.left - tolerance || p.x > bounds.right + tolerance || p.y < bounds.bottom - tolerance || p.y > bounds.top + tolerance {
+            return Err(format!("the point {:?} at t = {} is outside the bounds {:?}", p, t, bounds));
+        }
+    }
+
+    return Ok(());
+}
+
+// Everything above for the curve through points, for a proptest body, see bezier_properties below.
+pub fn check_bezier(points: &[Vector; 4]) -> Result<(), String>
+{
+    let bez = Bezier::from_control_points(points[0], points[1], points[2], points[3]);
+    let scale = scale_of(points);
+
+    check_control_point_round_trip(points)?;
+    check_evaluation_agreement(points)?;
+    check_subdivision_continuity(points)?;
+    check_derivative(&bez, scale)?;
+    check_bounds_contain(&bez, scale)?;
+
+    return Ok(());
+}
+
+// Our stroker against skia's. Every fixture skeleton is stroked by both with round caps and joins, the one place
+// the two should agree, and both results have their overlaps removed so it's the filled shapes being compared and
+// not how each happens to lay out its contours. Miters are left out on purpose, skia measures its limit differently.
+//
+// Skeletons where the two legitimately disagree are listed here with why, and skipped. Anything that isn't on the
+// list has to come within the threshold.
+// The width the comparison test and bench stroke at, and how far apart the two strokes can be. Our offsets are
+// fitted to within the default tolerance of 0.01, skia flattens its own a good deal more loosely than that.
+#[cfg(feature = "skia")]
+pub const SKIA_STROKE_WIDTH: f64 = 20.;
+#[cfg(feature = "skia")]
+pub const SKIA_STROKE_THRESHOLD: f64 = 0.5;
+
+#[cfg(feature = "skia")]
+pub const SKIA_STROKE_EXCLUSIONS: &[(&str, &str)] = &[
+    ("cusp", "skia puts a whole circle around a cusp, our offset only goes round the outside of it"),
+];
+
+// How one skeleton came out, with how long each stroker took on it.
+#[cfg(feature = "skia")]
+#[derive(Clone, Debug)]
+pub struct SkiaStrokeComparison {
+    pub skeleton: String,
+    pub deviation: f64,
+    pub ours: Duration,
+    pub skia: Duration,
+}
+
+// The settings both strokers get, round caps and joins width wide.
+#[cfg(feature = "skia")]
+pub fn skia_comparison_settings(width: f64) -> crate::stroke::StrokeSettings
+{
+    return crate::stroke::StrokeSettings {
+        width: width,
+        cap: crate::stroke::StrokeCap::Round,
+        join: crate::stroke::StrokeJoin::Round,
+        ..Default::default()
+    };
+}
+
+// skia's stroke of outline with its overlaps removed, and how long the stroking took.
+#[cfg(feature = "skia")]
+pub fn skia_stroke(outline: &Piecewise<Piecewise<Bezier>>, width: f64) -> Result<(Piecewise<Piecewise<Bezier>>, Duration), String>
+{
+    let mut rec = StrokeRec::new(stroke_rec::InitStyle::Hairline);
+    rec.set_stroke_style(width as f32, false);
+    rec.set_stroke_params(paint::Cap::Round, paint::Join::Round, 4.);
+
+    let source = outline.to_skpath();
+    let mut stroked = skulpin::skia_safe::Path::new();
+    let start = Instant::now();
+    if !rec.apply_to_path(&mut stroked, &source) { return Err(SkiaError::StrokeFailed.to_string()); }
+    let elapsed = start.elapsed();
+
+    let cleaned = stroked.simplify().and_then(|p| p.as_winding()).ok_or(SkiaError::SimplifyFailed.to_string())?;
+    return Ok((Piecewise::from_skpath(&cleaned).map_err(|e| e.to_string())?, elapsed));
+}
+
+// Every fixture skeleton not in SKIA_STROKE_EXCLUSIONS by name, in file order.
+#[cfg(feature = "skia")]
+pub fn skia_comparison_skeletons(fixtures: &Path) -> Result<Vec<(String, Piecewise<Piecewise<Bezier>>)>, String>
+{
+    let mut skeletons: Vec<_> = match fs::read_dir(fixtures.join("skeletons")) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path())
+            .filter(|p| p.extension().map_or(false, |ext| ext == "glif"))
+            .collect(),
+        Err(e) => return Err(format!("Couldn't read the skeletons: {}", e)),
+    };
+    skeletons.sort();
+
+    let mut output = Vec::new();
+    for skeleton_path in skeletons {
+        let name = skeleton_path.file_stem().unwrap().to_string_lossy().to_string();
+        if SKIA_STROKE_EXCLUSIONS.iter().any(|(excluded, _)| *excluded == name) { continue; }
+
+        output.push((name, read_glif_piecewise(&skeleton_path)?));
+    }
+
+    return Ok(output);
+}
+
+// Strokes every skia_comparison_skeletons width wide with both strokers.
+#[cfg(feature = "skia")]
+pub fn compare_with_skia_stroker(fixtures: &Path, width: f64) -> Result<Vec<SkiaStrokeComparison>, String>
+{
+    let settings = skia_comparison_settings(width);
+
+    let mut output = Vec::new();
+    for (name, skeleton) in skia_comparison_skeletons(fixtures)? {
+        let start = Instant::now();
+        let ours = crate::stroke::constant_width_stroke(&skeleton, &settings);
+        let ours_elapsed = start.elapsed();
+        let ours = ours.remove_overlap().map_err(|e| format!("{}: {}", name, e))?;
+
+        let (theirs, skia_elapsed) = skia_stroke(&skeleton, width).map_err(|e| format!("{}: {}", name, e))?;
+
+        output.push(SkiaStrokeComparison {
+            deviation: outline_distance(&ours, &theirs, 8),
+            skeleton: name,
+            ours: ours_elapsed,
+            skia: skia_elapsed,
+        });
+    }
+
+    return Ok(output);
+}
+
+// compare_with_skia_stroker, describing every skeleton that came out further than threshold from skia's stroke.
+// An empty Vec means everything passed.
+#[cfg(feature = "skia")]
+pub fn check_against_skia_stroker(fixtures: &Path, width: f64, threshold: f64) -> Vec<String>
+{
+    return match compare_with_skia_stroker(fixtures, width) {
+        Ok(comparisons) => comparisons.iter()
+            .filter(|c| !(c.deviation <= threshold))
+            .map(|c| format!("{} is {} from skia's stroke, more than {}", c.skeleton, c.deviation, threshold))
+            .collect(),
+        Err(e) => vec![e],
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // glifs and skia paths both hold f32s, coordinates up to vector_strategy's thousand come back this close
+    const CONVERSION_EPSILON: f64 = 1e-3;
+
+    proptest! {
+        #[test]
+        fn bezier_properties(points in bezier_points_strategy())
+        {
+            let result = check_bezier(&points);
+            prop_assert!(result.is_ok(), "{}", result.unwrap_err());
+        }
+
+        // from_contour then to_contour and back again, the shape has to make it through both ways
+        #[test]
+        fn glif_round_trip(contours in outline_strategy())
+        {
+            let expected = to_piecewise(&contours);
+
+            let read = Piecewise::from_outline(&to_outline(&contours));
+            let result = compare_shapes(&expected, &read, CONVERSION_EPSILON);
+            prop_assert!(result.is_ok(), "reading the glif: {}", result.unwrap_err());
+
+            let reread = Piecewise::from_outline(&read.to_outline());
+            let result = compare_shapes(&expected, &reread, CONVERSION_EPSILON);
+            prop_assert!(result.is_ok(), "writing it back out: {}", result.unwrap_err());
+        }
+
+        #[cfg(feature = "skia")]
+        #[test]
+        fn skpath_round_trip(contours in outline_strategy())
+        {
+            let expected = to_piecewise(&contours);
+            let back = Piecewise::from_skpath(&expected.to_skpath());
+            prop_assert!(back.is_ok(), "{}", back.err().unwrap());
+
+            let result = compare_shapes(&expected, &back.unwrap(), CONVERSION_EPSILON);
+            prop_assert!(result.is_ok(), "{}", result.unwrap_err());
+        }
+
+        // rounding can collapse segments and leave contours with nothing in them, none of that can panic
+        #[test]
+        fn quantize_then_clean(contours in outline_strategy(), decimals in 0u32..4)
+        {
+            let (quantized, _) = to_piecewise(&contours).quantize(decimals);
+            let (cleaned, _) = quantized.clean(f64::powi(10., -(decimals as i32)));
+            prop_assert!(cleaned.segs().all(|contour| !contour.is_empty()));
+        }
+    }
+
+    // a stroke can be this far from its expectation, which is written out to four decimals
+    const GOLDEN_EPSILON: f64 = 0.01;
+
+    fn fixtures() -> std::path::PathBuf
+    {
+        return Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+    }
+
+    #[test]
+    fn goldens()
+    {
+        let failures = check_goldens(&fixtures(), GOLDEN_EPSILON);
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+
+    // Our stroke comes out within SKIA_STROKE_THRESHOLD of skia's on every skeleton that isn't excluded.
+    #[cfg(feature = "skia")]
+    #[test]
+    fn matches_skia_stroker()
+    {
+        let failures = check_against_skia_stroker(&fixtures(), SKIA_STROKE_WIDTH, SKIA_STROKE_THRESHOLD);
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+
+    // Profile goldens are written to four decimals like the stroke goldens, but compared point by point.
+    const PROFILE_GOLDEN_EPSILON: f64 = 1e-3;
+
+    #[test]
+    fn v1_profile_golden()
+    {
+        let failures = check_profile_golden(&fixtures(), GeometryProfile::V1, PROFILE_GOLDEN_EPSILON);
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+
+    #[test]
+    fn latest_profile_golden()
+    {
+        let failures = check_profile_golden(&fixtures(), GeometryProfile::Latest, PROFILE_GOLDEN_EPSILON);
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+
+    // the pipeline run in two halves, with the artifact between them going through JSON, matches one straight run
+    #[test]
+    fn split_pipeline()
+    {
+        let failures = check_split_pipeline(&fixtures());
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+
+    // Op goldens are written out as glifs, which hold f32s.
+    const OP_GOLDEN_EPSILON: f64 = 1e-3;
+
+    // Every op in golden_ops on every skeleton matches its expectation.
+    #[test]
+    fn op_goldens()
+    {
+        let failures = check_op_goldens(&fixtures(), OP_GOLDEN_EPSILON);
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+
+    // fixtures/truetype's o is all off-curve points, every on-curve point of it implied.
+    #[test]
+    fn truetype_fixtures()
+    {
+        let failures = check_truetype_fixtures(&fixtures(), 1e-6);
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+
+    fn skeleton(name: &str) -> Piecewise<Piecewise<Bezier>>
+    {
+        return skeleton_with_closed(name).0;
+    }
+
+    fn skeleton_with_closed(name: &str) -> (Piecewise<Piecewise<Bezier>>, Vec<bool>)
+    {
+        return read_glif_piecewise_with_closed(&fixtures().join("skeletons").join(format!("{}.glif", name))).unwrap();
+    }
+
+    // The expectation for running op on the named skeleton, after checking the op still gives it.
+    fn op_golden(name: &str, op_name: &str) -> Piecewise<Piecewise<Bezier>>
+    {
+        let case = format!("{}.{}", name, op_name);
+        let expected = read_glif_piecewise(&fixtures().join("expected_ops").join(format!("{}.glif", case))).unwrap();
+        let (_, op) = golden_ops().into_iter().find(|(n, _)| *n == op_name).unwrap();
+
+        let (skeleton, closed) = skeleton_with_closed(name);
+        let actual = Piecewise::from_outline(&op(&skeleton, &closed).to_outline());
+        if let Some(difference) = first_difference(&expected, &actual, OP_GOLDEN_EPSILON) {
+            panic!("{}: {}", case, difference);
+        }
+        return expected;
+    }
+
+    fn assert_bounds(got: Rect, want: Rect, tolerance: f64, what: &str)
+    {
+        let sides = [(got.left, want.left), (got.bottom, want.bottom), (got.right, want.right), (got.top, want.top)];
+        assert!(sides.iter().all(|(g, w)| (g - w).abs() <= tolerance), "{}: bounds {:?} rather than {:?}", what, got, want);
+    }
+
+    fn assert_area(got: f64, want: f64, relative: f64, what: &str)
+    {
+        assert!((got - want).abs() <= relative * want, "{}: area {} rather than {}", what, got, want);
+    }
+
+    // The op goldens worth looking at first, each held to what it has to come out as worked out without the op.
+
+    // A round capped stroke is the skeleton swept by a disc, so its bounds are the skeleton's pushed out by half the
+    // width on every side, and as long as it doesn't overlap itself its area is the skeleton's length times the
+    // width plus the disc the two caps make between them.
+    #[test]
+    fn round_cap_stroke_golden()
+    {
+        let expected = op_golden("s_curve", "round_cap_stroke");
+        let s_curve = skeleton("s_curve");
+        let half = crate::stroke::StrokeSettings::default().width / 2.;
+
+        assert_eq!(expected.len(), 1);
+        assert_bounds(expected.bounds(), s_curve.bounds().outset(half), 0.05, "s_curve.round_cap_stroke");
+        let length = s_curve[0].arclen(1e-6);
+        assert_area(expected.area(), length * 2. * half + std::f64::consts::PI * half * half, 1e-3, "s_curve.round_cap_stroke");
+    }
+
+    // The 400 unit square with each corner cut back 50 along both sides and a quarter circle across it. The bounds
+    // don't move and each corner loses the difference between a 50 unit square and the quarter circle. That's the
+    // usual one curve quarter with its handles 4/3 (sqrt 2 - 1) of the radius long, which bulges out a little past
+    // the true circle, the area of its quarter of a unit disc being 1/2 + 3k/5 - 3k^2/20 rather than pi/4.
+    #[test]
+    fn round_corners_golden()
+    {
+        let expected = op_golden("square", "round_corners");
+        let square = skeleton("square");
+
+        assert_eq!(expected.len(), 1);
+        assert_eq!(expected[0].len(), 8);
+        assert_bounds(expected.bounds(), square.bounds(), OP_GOLDEN_EPSILON, "square.round_corners");
+        let k = 4. / 3. * (2f64.sqrt() - 1.);
+        let corner = 50. * 50. * (1. - (0.5 + 3. * k / 5. - 3. * k * k / 20.));
+        assert_area(expected.area(), 400. * 400. - 4. * corner, 1e-6, "square.round_corners");
+
+        // every other segment is a corner, the rest are what's left of the sides
+        let mut corners = 0;
+        for bez in expected[0].segs() {
+            let [p0, p1, p2, p3] = bez.to_control_points();
+            if (p0.distance(p3) - 300.).abs() <= OP_GOLDEN_EPSILON { continue; }
+            corners += 1;
+            assert!((p0.distance(p3) - 50. * 2f64.sqrt()).abs() <= OP_GOLDEN_EPSILON, "square.round_corners: {:?}", (p0, p1, p2, p3));
+            assert!((p0.distance(p1) - 50. * k).abs() <= OP_GOLDEN_EPSILON, "square.round_corners: {:?}", (p0, p1, p2, p3));
+            assert!((p3.distance(p2) - 50. * k).abs() <= OP_GOLDEN_EPSILON, "square.round_corners: {:?}", (p0, p1, p2, p3));
+        }
+        assert_eq!(corners, 4);
+    }
+
+    // A 12 degree slant about y = 300, which goes through the middle of the circle. A skew keeps the area and the
+    // height, and moves each row of the circle sideways by how far it is from the middle, so the circle's center
+    // stays put and it gets wider by 1 / cos 12 degrees. The circle in the fixture is the usual four curve one,
+    // which is a little off round, hence the looser tolerance on its sides.
+    #[test]
+    fn italicize_golden()
+    {
+        let expected = op_golden("circle", "italicize");
+        let circle = skeleton("circle");
+        let b = circle.bounds();
+        let (center, radius) = ((b.left + b.right) / 2., (b.right - b.left) / 2.);
+        let half_width = radius / 12f64.to_radians().cos();
+
+        assert_eq!(expected.len(), 1);
+        assert_bounds(expected.bounds(), Rect { left: center - half_width, right: center + half_width, bottom: b.bottom, top: b.top }, 0.2, "circle.italicize");
+        assert_area(expected.area(), circle.area(), 1e-6, "circle.italicize");
+    }
+}
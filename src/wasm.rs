@@ -0,0 +1,87 @@
+// wasm-bindgen wrappers for using the stroker from a browser. Build with --no-default-features --features wasm for
+// wasm32-unknown-unknown. Skia doesn't build there and we have no overlap removal of our own, so without it the
+// output isn't simplified: copies of the pattern overlap each other the way the CLI's do with simplify off. Asking
+// for simplify throws rather than quietly handing back overlapping contours, remove the overlap on the JS side
+// (paper.js's unite, say) if you need it.
+use crate::pattern_along_path::pattern_along_outline;
+use crate::qmath::*;
+use crate::settings_json::{self, JsonSettings};
+use wasm_bindgen::prelude::*;
+
+// Path data is y-down like the rest of SVG. We flip into y-up before stroking so the pattern lands on the same
+// side of the path it would if the same artwork went through the CLI.
+fn flip(outline: &Piecewise<Piecewise<Bezier>>) -> Piecewise<Piecewise<Bezier>>
+{
+    let curves = outline.segs().map(|contour| {
+        Piecewise::new(contour.segs().map(|bez| {
+            let cp = bez.to_control_points();
+            let f = |v: Vector| Vector { x: v.x, y: -v.y };
+            Bezier::from_control_points(f(cp[0]), f(cp[1]), f(cp[2]), f(cp[3]))
+        }).collect())
+    }).collect();
+
+    return Piecewise::new(curves);
+}
+
+fn stroke(d: &str, settings: &JsonSettings) -> Result<String, JsValue>
+{
+    let path = Piecewise::from_svg_path_data(d).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let pattern = Piecewise::from_svg_path_data(&settings.pattern).map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+    if pattern.is_empty() {
+        return Err(JsValue::from_str("The pattern is empty."));
+    }
+
+    if cfg!(not(feature = "skia")) && settings.settings.simplify {
+        return Err(JsValue::from_str("Simplify needs the skia feature, which doesn't build for wasm."));
+    }
+
+    let mut output = flip(&pattern_along_outline(&flip(&path), &flip(&pattern), &settings.settings));
+
+    if let Some(decimals) = settings.round {
+        output = output.quantize(decimals).0;
+    }
+
+    return Ok(output.to_svg_path_data(settings.round.map(|d| d as usize)));
+}
+
+// Converts a settings object into our settings. It's the same object the settings JSON describes, the pattern
+// is given as SVG path data.
+pub fn settings_from_js(value: &JsValue) -> Result<JsonSettings, JsValue>
+{
+    let json: String = js_sys::JSON::stringify(value)?.into();
+    return settings_json::parse_settings(&json).map_err(|e| JsValue::from_str(&e));
+}
+
+pub fn settings_to_js(settings: &JsonSettings) -> Result<JsValue, JsValue>
+{
+    return js_sys::JSON::parse(&settings_json::settings_to_json(settings));
+}
+
+// Strokes the path data d and returns the result as path data. Throws if either can't be parsed.
+#[wasm_bindgen]
+pub fn stroke_svg_d(d: &str, settings_json: &str) -> Result<String, JsValue>
+{
+    let settings = settings_json::parse_settings(settings_json).map_err(|e| JsValue::from_str(&e))?;
+    return stroke(d, &settings);
+}
+
+// Same as stroke_svg_d but takes the settings as an object.
+#[wasm_bindgen]
+pub fn stroke_svg_d_with(d: &str, settings: &JsValue) -> Result<String, JsValue>
+{
+    return stroke(d, &settings_from_js(settings)?);
+}
+
+// The default settings as an object, with pattern holding the given path data.
+#[wasm_bindgen]
+pub fn default_settings(pattern: &str) -> Result<JsValue, JsValue>
+{
+    let settings = JsonSettings {
+        settings: Default::default(),
+        pattern: pattern.to_string(),
+        round: Some(2),
+    };
+
+    return settings_to_js(&settings);
+}
@@ -0,0 +1,98 @@
+// Loads the cdylib the way a C caller would and strokes through the C API in include/mfekstroke.h. The library
+// cargo builds for these tests is the same one that gets shipped, so this is the closest we get to calling it from
+// C without a C compiler.
+#![cfg(feature = "ffi")]
+
+use libloading::Library;
+use qstroke::ffi::*;
+use std::ffi::CStr;
+use std::fs;
+use std::os::raw::c_char;
+use std::path::Path;
+use std::ptr;
+
+type StrokeGlif = unsafe extern "C" fn(*const c_char, usize, *const c_char, *mut c_char, *mut usize) -> i32;
+type Version = unsafe extern "C" fn() -> *const c_char;
+type LastError = unsafe extern "C" fn(*mut c_char, *mut usize) -> i32;
+
+// The cdylib sits in the target directory, the tests themselves one down in deps.
+fn library() -> Library
+{
+    let exe = std::env::current_exe().unwrap();
+    let name = libloading::library_filename("qstroke");
+    let found = exe.ancestors().skip(1).take(2).map(|dir| dir.join(&name)).find(|path| path.is_file());
+    let path = found.expect("The cdylib should be built next to the tests.");
+
+    return unsafe { Library::new(path).unwrap() };
+}
+
+fn fixture(name: &str) -> String
+{
+    return fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures").join(name)).unwrap();
+}
+
+fn settings() -> std::ffi::CString
+{
+    let json = serde_json::json!({ "pattern": fixture("pattern.glif"), "mode": "repeated", "round": 2 });
+    return std::ffi::CString::new(json.to_string()).unwrap();
+}
+
+// Asks once with no buffer to find out how big it has to be, then again with one that big.
+fn stroke(function: StrokeGlif, input: &str, settings: &CStr) -> Result<String, i32>
+{
+    let mut len = 0;
+    let status = unsafe { function(input.as_ptr() as *const c_char, input.len(), settings.as_ptr(), ptr::null_mut(), &mut len) };
+    if status != MFEKSTROKE_BUFFER_TOO_SMALL { return Err(status); }
+
+    let mut buffer = vec![0u8; len];
+    let status = unsafe { function(input.as_ptr() as *const c_char, input.len(), settings.as_ptr(), buffer.as_mut_ptr() as *mut c_char, &mut len) };
+    if status != MFEKSTROKE_OK { return Err(status); }
+
+    assert_eq!(len, buffer.len());
+    assert_eq!(buffer.pop(), Some(0));
+    return Ok(String::from_utf8(buffer).unwrap());
+}
+
+fn last_error(function: LastError) -> String
+{
+    let mut len = 0;
+    assert_eq!(unsafe { function(ptr::null_mut(), &mut len) }, MFEKSTROKE_BUFFER_TOO_SMALL);
+
+    let mut buffer = vec![0u8; len];
+    assert_eq!(unsafe { function(buffer.as_mut_ptr() as *mut c_char, &mut len) }, MFEKSTROKE_OK);
+    return CStr::from_bytes_with_nul(&buffer).unwrap().to_str().unwrap().to_string();
+}
+
+// The loaded library strokes a fixture to the very same glif as calling into the crate directly.
+#[test]
+fn strokes_through_the_cdylib()
+{
+    let library = library();
+    let loaded = unsafe { *library.get::<StrokeGlif>(b"mfekstroke_stroke_glif_utf8\0").unwrap() };
+
+    let input = fixture("skeletons/s_curve.glif");
+    let output = stroke(loaded, &input, &settings()).unwrap();
+    assert!(output.contains("<contour>"), "{}", output);
+    assert_eq!(output, stroke(mfekstroke_stroke_glif_utf8, &input, &settings()).unwrap());
+}
+
+#[test]
+fn version_and_errors_through_the_cdylib()
+{
+    let library = library();
+    let loaded = unsafe { *library.get::<StrokeGlif>(b"mfekstroke_stroke_glif_utf8\0").unwrap() };
+    let version = unsafe { *library.get::<Version>(b"mfekstroke_version\0").unwrap() };
+    let error = unsafe { *library.get::<LastError>(b"mfekstroke_last_error_message\0").unwrap() };
+
+    assert_eq!(unsafe { CStr::from_ptr(version()) }.to_str().unwrap(), env!("CARGO_PKG_VERSION"));
+
+    let input = fixture("skeletons/s_curve.glif");
+    let no_pattern = std::ffi::CString::new("{}").unwrap();
+    assert_eq!(stroke(loaded, &input, &no_pattern), Err(MFEKSTROKE_INVALID_SETTINGS));
+    assert_eq!(last_error(error), "Settings need a pattern in \"pattern\".");
+
+    let mut len = 0;
+    let status = unsafe { loaded(ptr::null(), 0, no_pattern.as_ptr(), ptr::null_mut(), &mut len) };
+    assert_eq!(status, MFEKSTROKE_INVALID_ARGUMENT);
+    assert_eq!(last_error(error), "Input pointer is NULL.");
+}
@@ -0,0 +1,39 @@
+// Strokes through the wasm-bindgen wrappers in src/wasm.rs. Runs headless under node with
+// wasm-pack test --node --no-default-features --features wasm
+#![cfg(all(feature = "wasm", target_arch = "wasm32"))]
+
+use qstroke::wasm::*;
+use wasm_bindgen_test::*;
+
+// A 10 by 20 rectangle, centered on the path the way patterns get drawn.
+const PATTERN: &str = "M0 -10L10 -10L10 10L0 10Z";
+
+fn settings(simplify: bool) -> String
+{
+    return format!(r#"{{ "pattern": "{}", "mode": "repeated", "simplify": {}, "round": 2 }}"#, PATTERN, simplify);
+}
+
+#[wasm_bindgen_test]
+fn strokes_a_line()
+{
+    let d = stroke_svg_d("M0 0L100 0", &settings(false)).unwrap();
+
+    // Repeated mode lays ten copies end to end along the line, one contour each.
+    assert_eq!(d.matches('M').count(), 10, "{}", d);
+    assert!(d.contains("100 "), "{}", d);
+}
+
+#[wasm_bindgen_test]
+fn settings_as_an_object()
+{
+    let settings = default_settings(PATTERN).unwrap();
+    assert_eq!(stroke_svg_d_with("M0 0L100 0", &settings).unwrap(), stroke_svg_d("M0 0L100 0", r#"{ "pattern": "M0 -10L10 -10L10 10L0 10Z", "round": 2 }"#).unwrap());
+}
+
+#[wasm_bindgen_test]
+fn simplify_and_bad_input_throw()
+{
+    assert!(stroke_svg_d("M0 0L100 0", &settings(true)).is_err());
+    assert!(stroke_svg_d("M0 0L", &settings(false)).is_err());
+    assert!(stroke_svg_d("M0 0L100 0", r#"{ "pattern": "" }"#).is_err());
+}